@@ -0,0 +1,236 @@
+//! Per-conversation figures computed from loaded messages
+//!
+//! Used by `quaid show`, `quaid list --verbose` and the HTTP API, which all
+//! already have the messages in memory.
+
+use crate::providers::{Conversation, Message, Role};
+use crate::storage::extract_text_content;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Who said how much in a conversation, and over how long
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConversationSummary {
+    pub user_messages: usize,
+    pub assistant_messages: usize,
+    pub system_messages: usize,
+    pub tool_messages: usize,
+    pub user_words: usize,
+    pub assistant_words: usize,
+    /// Earliest message timestamp, or the conversation's creation time when
+    /// no message has one
+    pub first_message_at: Option<DateTime<Utc>>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    /// Seconds from the first to the last message
+    pub duration_seconds: Option<i64>,
+}
+
+/// Count messages and words per role and the time the conversation spans
+///
+/// Messages without a timestamp still count. When none has one, the span
+/// falls back to the conversation's own created/updated times; an empty
+/// conversation has no span.
+pub fn conversation_summary(
+    conversation: &Conversation,
+    messages: &[Message],
+) -> ConversationSummary {
+    let mut summary = ConversationSummary::default();
+    for message in messages {
+        let words = extract_text_content(&message.content)
+            .split_whitespace()
+            .count();
+        match message.role {
+            Role::User => {
+                summary.user_messages += 1;
+                summary.user_words += words;
+            }
+            Role::Assistant => {
+                summary.assistant_messages += 1;
+                summary.assistant_words += words;
+            }
+            Role::System => summary.system_messages += 1,
+            Role::Tool => summary.tool_messages += 1,
+        }
+    }
+
+    if messages.is_empty() {
+        return summary;
+    }
+    let stamps = messages.iter().filter_map(|m| m.created_at);
+    let (first, last) = match (stamps.clone().min(), stamps.max()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => (conversation.created_at, conversation.updated_at),
+    };
+    summary.first_message_at = Some(first);
+    summary.last_message_at = Some(last);
+    summary.duration_seconds = Some((last - first).num_seconds().max(0));
+    summary
+}
+
+impl ConversationSummary {
+    /// Total messages of every role
+    pub fn messages(&self) -> usize {
+        self.user_messages + self.assistant_messages + self.system_messages + self.tool_messages
+    }
+
+    /// One line for the end of `quaid show`
+    pub fn line(&self) -> String {
+        if self.messages() == 0 {
+            return "No messages".to_string();
+        }
+        let mut counts = vec![
+            plural(self.user_messages, "user message"),
+            format!("{} assistant", self.assistant_messages),
+        ];
+        if self.tool_messages > 0 {
+            counts.push(format!("{} tool", self.tool_messages));
+        }
+        if self.system_messages > 0 {
+            counts.push(format!("{} system", self.system_messages));
+        }
+        let mut line = format!(
+            "{}; {} from the user, {} from the assistant",
+            counts.join(", "),
+            plural(self.user_words, "word"),
+            self.assistant_words
+        );
+        if let (Some(first), Some(last), Some(seconds)) = (
+            self.first_message_at,
+            self.last_message_at,
+            self.duration_seconds,
+        ) {
+            line.push_str(&format!(
+                "; {} to {} ({})",
+                first.format("%Y-%m-%d %H:%M"),
+                last.format("%Y-%m-%d %H:%M"),
+                format_duration(seconds)
+            ));
+        }
+        line
+    }
+
+    /// Short form for `quaid list --verbose`, e.g. `4u 5a 1t 2h13m`
+    pub fn compact(&self) -> String {
+        let mut compact = format!("{}u {}a", self.user_messages, self.assistant_messages);
+        if self.tool_messages > 0 {
+            compact.push_str(&format!(" {}t", self.tool_messages));
+        }
+        if let Some(seconds) = self.duration_seconds {
+            compact.push(' ');
+            compact.push_str(&format_duration(seconds));
+        }
+        compact
+    }
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+/// `45s`, `12m`, `2h13m` or `3d4h`
+fn format_duration(seconds: i64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h{}m", h, m),
+        (d, h, _) => format!("{}d{}h", d, h),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::MessageContent;
+    use chrono::TimeZone;
+
+    fn conversation() -> Conversation {
+        Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "claude".to_string(),
+            title: "Ingress".to_string(),
+            created_at: Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 3, 1, 10, 30, 0).unwrap(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    fn message(id: &str, role: Role, text: &str, minute: Option<u32>) -> Message {
+        Message {
+            id: id.to_string(),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: minute.map(|m| Utc.with_ymd_and_hms(2024, 3, 1, 9, m, 0).unwrap()),
+            model: None,
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn test_counts_roles_words_and_span() {
+        let messages = vec![
+            message("m1", Role::User, "How do I configure an ingress?", Some(2)),
+            message(
+                "m2",
+                Role::Assistant,
+                "Create an Ingress resource.",
+                Some(3),
+            ),
+            message("m3", Role::Tool, "kubectl apply -f ingress.yaml", None),
+            message("m4", Role::User, "Thanks", Some(47)),
+        ];
+        let summary = conversation_summary(&conversation(), &messages);
+
+        assert_eq!(summary.user_messages, 2);
+        assert_eq!(summary.assistant_messages, 1);
+        assert_eq!(summary.tool_messages, 1);
+        assert_eq!(summary.user_words, 7);
+        assert_eq!(summary.assistant_words, 4);
+        assert_eq!(summary.duration_seconds, Some(45 * 60));
+        assert_eq!(summary.compact(), "2u 1a 1t 45m");
+        assert_eq!(
+            summary.line(),
+            "2 user messages, 1 assistant, 1 tool; 7 words from the user, 4 from the \
+             assistant; 2024-03-01 09:02 to 2024-03-01 09:47 (45m)"
+        );
+    }
+
+    #[test]
+    fn test_notes_only_conversation_uses_conversation_times() {
+        // Granola and Fathom store AI notes as a single undated assistant message
+        let messages = vec![message(
+            "notes",
+            Role::Assistant,
+            "Action items: ship it",
+            None,
+        )];
+        let summary = conversation_summary(&conversation(), &messages);
+
+        assert_eq!(summary.user_messages, 0);
+        assert_eq!(summary.assistant_messages, 1);
+        assert_eq!(summary.assistant_words, 4);
+        assert_eq!(summary.first_message_at, Some(conversation().created_at));
+        assert_eq!(summary.duration_seconds, Some(90 * 60));
+        assert_eq!(summary.compact(), "0u 1a 1h30m");
+    }
+
+    #[test]
+    fn test_empty_conversation() {
+        let summary = conversation_summary(&conversation(), &[]);
+
+        assert_eq!(summary, ConversationSummary::default());
+        assert_eq!(summary.line(), "No messages");
+        assert_eq!(summary.compact(), "0u 0a");
+    }
+}
@@ -0,0 +1,392 @@
+//! Credentials in a passphrase-encrypted file
+//!
+//! For servers without a desktop keyring. Every credential is kept in one
+//! JSON document, encrypted with ChaCha20-Poly1305 under a key derived from
+//! the passphrase with PBKDF2-HMAC-SHA256. The file holds
+//!
+//! ```text
+//! magic (8) | PBKDF2 iterations (u32, big endian) | salt (16) | nonce (12) | ciphertext and tag
+//! ```
+//!
+//! and everything before the nonce is authenticated along with the
+//! ciphertext. Each save uses a fresh nonce and replaces the file through a
+//! temporary sibling that only its owner can read. Saves hold a lock on a
+//! `.lock` sibling and merge into whatever is on disk at that moment, so two
+//! quaid processes saving different credentials keep both.
+
+use super::{CredentialError, CredentialStore};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Start of every credentials file
+const MAGIC: &[u8; 8] = b"QUAIDCR1";
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN;
+/// PBKDF2 iterations for new files; existing ones keep theirs
+const ITERATIONS: u32 = 600_000;
+
+/// Passwords by service, then user
+type Entries = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Credential store kept in an encrypted file
+pub struct FileStore {
+    path: PathBuf,
+    /// Magic, iterations and salt, written back unchanged on every save
+    header: Vec<u8>,
+    key: LessSafeKey,
+    rng: SystemRandom,
+    entries: Mutex<Entries>,
+}
+
+impl FileStore {
+    /// Open the credentials file at `path`, unlocking it with `passphrase`
+    ///
+    /// A missing file is created, encrypted with `passphrase`, by the first
+    /// credential saved.
+    pub fn open(path: impl Into<PathBuf>, passphrase: &str) -> Result<Self, CredentialError> {
+        Self::open_with_iterations(path.into(), passphrase, ITERATIONS)
+    }
+
+    fn open_with_iterations(
+        path: PathBuf,
+        passphrase: &str,
+        iterations: u32,
+    ) -> Result<Self, CredentialError> {
+        if passphrase.is_empty() {
+            return Err(CredentialError::File("the passphrase is empty".to_string()));
+        }
+        let rng = SystemRandom::new();
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let mut salt = [0; SALT_LEN];
+                rng.fill(&mut salt).map_err(|_| random_error())?;
+                let key = derive_key(passphrase, iterations, &salt)?;
+                return Ok(Self {
+                    header: [&MAGIC[..], &iterations.to_be_bytes(), &salt].concat(),
+                    path,
+                    key,
+                    rng,
+                    entries: Mutex::default(),
+                });
+            }
+            Err(e) => return Err(io_error(&path, e)),
+        };
+
+        let (header, _) = split_header(&path, &contents)?;
+        let (iterations, salt) = header[MAGIC.len()..].split_at(4);
+        let iterations = u32::from_be_bytes(iterations.try_into().expect("4 bytes"));
+        let key = derive_key(passphrase, iterations, salt)?;
+        let entries = decrypt(&path, &key, &contents)?;
+
+        Ok(Self {
+            header: header.to_vec(),
+            path,
+            key,
+            rng,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Apply `change` to the credentials on disk and save the result
+    ///
+    /// The file is re-read under an exclusive lock, so a credential another
+    /// process saved since this one opened the file isn't overwritten.
+    fn update(
+        &self,
+        change: impl FnOnce(&mut Entries) -> Result<(), CredentialError>,
+    ) -> Result<(), CredentialError> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut lock_path = self.path.clone().into_os_string();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+        // Released when the file closes
+        let _lock = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .and_then(|file| file.lock().map(|()| file))
+            .map_err(|e| io_error(&lock_path, e))?;
+
+        let mut updated = self.reload()?.unwrap_or_else(|| entries.clone());
+        change(&mut updated)?;
+        self.save(&updated)?;
+        *entries = updated;
+        Ok(())
+    }
+
+    /// The entries currently on disk, or `None` if the file doesn't exist yet
+    fn reload(&self) -> Result<Option<Entries>, CredentialError> {
+        let contents = match fs::read(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error(&self.path, e)),
+        };
+        let (header, _) = split_header(&self.path, &contents)?;
+        if header != self.header.as_slice() {
+            return Err(CredentialError::File(format!(
+                "{} was replaced by another process; run the command again",
+                self.path.display()
+            )));
+        }
+        decrypt(&self.path, &self.key, &contents).map(Some)
+    }
+
+    /// Encrypt `entries` and replace the file with them
+    fn save(&self, entries: &Entries) -> Result<(), CredentialError> {
+        let mut nonce = [0; NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(|_| random_error())?;
+        let mut in_out =
+            serde_json::to_vec(entries).map_err(|e| CredentialError::File(e.to_string()))?;
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&self.header[..]),
+                &mut in_out,
+            )
+            .map_err(|_| CredentialError::File("couldn't encrypt the credentials".to_string()))?;
+
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        // Left over from an interrupted save, maybe with other permissions
+        let _ = fs::remove_file(&temp_path);
+
+        let write = || -> io::Result<()> {
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let mut file = options.open(&temp_path)?;
+            file.write_all(&self.header)?;
+            file.write_all(&nonce)?;
+            file.write_all(&in_out)?;
+            file.sync_all()?;
+            fs::rename(&temp_path, &self.path)
+        };
+        write().map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            io_error(&self.path, e)
+        })
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn get(&self, service: &str, user: &str) -> Result<String, CredentialError> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(service)
+            .and_then(|users| users.get(user))
+            .cloned()
+            .ok_or(CredentialError::NotFound)
+    }
+
+    fn set(&self, service: &str, user: &str, password: &str) -> Result<(), CredentialError> {
+        self.update(|entries| {
+            entries
+                .entry(service.to_string())
+                .or_default()
+                .insert(user.to_string(), password.to_string());
+            Ok(())
+        })
+    }
+
+    fn delete(&self, service: &str, user: &str) -> Result<(), CredentialError> {
+        self.update(|entries| {
+            let users = entries.get_mut(service).ok_or(CredentialError::NotFound)?;
+            users.remove(user).ok_or(CredentialError::NotFound)?;
+            if users.is_empty() {
+                entries.remove(service);
+            }
+            Ok(())
+        })
+    }
+}
+
+fn derive_key(
+    passphrase: &str,
+    iterations: u32,
+    salt: &[u8],
+) -> Result<LessSafeKey, CredentialError> {
+    let iterations = NonZeroU32::new(iterations)
+        .ok_or_else(|| CredentialError::File("the file has no PBKDF2 iterations".to_string()))?;
+    let mut key = [0; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key =
+        UnboundKey::new(&CHACHA20_POLY1305, &key).expect("ChaCha20-Poly1305 keys are 32 bytes");
+    Ok(LessSafeKey::new(key))
+}
+
+/// The authenticated header of a credentials file, and everything after it
+fn split_header<'a>(
+    path: &Path,
+    contents: &'a [u8],
+) -> Result<(&'a [u8], &'a [u8]), CredentialError> {
+    if contents.len() < HEADER_LEN + NONCE_LEN || !contents.starts_with(MAGIC) {
+        return Err(CredentialError::File(format!(
+            "{} isn't a quaid credentials file",
+            path.display()
+        )));
+    }
+    Ok(contents.split_at(HEADER_LEN))
+}
+
+/// Decrypt the entries in a credentials file's `contents` with `key`
+fn decrypt(path: &Path, key: &LessSafeKey, contents: &[u8]) -> Result<Entries, CredentialError> {
+    let (header, rest) = split_header(path, contents)?;
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).expect("NONCE_LEN bytes");
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(header), &mut in_out)
+        .map_err(|_| {
+            CredentialError::File(format!(
+                "couldn't unlock {}: wrong passphrase, or the file is damaged",
+                path.display()
+            ))
+        })?;
+    serde_json::from_slice(plaintext)
+        .map_err(|e| CredentialError::File(format!("{}: {}", path.display(), e)))
+}
+
+fn io_error(path: &Path, e: io::Error) -> CredentialError {
+    CredentialError::File(format!("{}: {}", path.display(), e))
+}
+
+fn random_error() -> CredentialError {
+    CredentialError::File("no randomness available".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Open with few iterations, which unit tests can afford
+    fn open(path: &Path, passphrase: &str) -> Result<FileStore, CredentialError> {
+        FileStore::open_with_iterations(path.to_path_buf(), passphrase, 1_000)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let store = open(&path, "hunter2").unwrap();
+        assert!(!path.exists());
+
+        store
+            .set("quaid", "claude-cookies", "sessionKey=abc")
+            .unwrap();
+        store.set("quaid", "claude-org-id", "org-1").unwrap();
+        store.set("quaid", "claude-org-id", "org-2").unwrap();
+        assert_eq!(store.get("quaid", "claude-org-id").unwrap(), "org-2");
+        assert!(!fs::read(&path)
+            .unwrap()
+            .windows(b"sessionKey".len())
+            .any(|w| w == b"sessionKey"));
+
+        // FileStore::open reads the iterations back from the file
+        let reopened = FileStore::open(&path, "hunter2").unwrap();
+        assert_eq!(
+            reopened.get("quaid", "claude-cookies").unwrap(),
+            "sessionKey=abc"
+        );
+        reopened.delete("quaid", "claude-cookies").unwrap();
+        assert!(matches!(
+            reopened.delete("quaid", "claude-cookies"),
+            Err(CredentialError::NotFound)
+        ));
+
+        let reopened = open(&path, "hunter2").unwrap();
+        assert!(matches!(
+            reopened.get("quaid", "claude-cookies"),
+            Err(CredentialError::NotFound)
+        ));
+        assert_eq!(reopened.get("quaid", "claude-org-id").unwrap(), "org-2");
+        assert!(!dir.path().join("credentials.enc.tmp").exists());
+    }
+
+    #[test]
+    fn test_concurrent_stores_keep_each_others_credentials() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        open(&path, "hunter2")
+            .unwrap()
+            .set("quaid", "claude-org-id", "org-1")
+            .unwrap();
+
+        // Both open before either saves, like two quaid processes
+        let first = open(&path, "hunter2").unwrap();
+        let second = open(&path, "hunter2").unwrap();
+        first.set("quaid", "fathom-api-key", "key").unwrap();
+        second
+            .set("quaid", "claude-cookies", "sessionKey=abc")
+            .unwrap();
+        second.delete("quaid", "claude-org-id").unwrap();
+
+        let reopened = open(&path, "hunter2").unwrap();
+        assert_eq!(reopened.get("quaid", "fathom-api-key").unwrap(), "key");
+        assert_eq!(
+            reopened.get("quaid", "claude-cookies").unwrap(),
+            "sessionKey=abc"
+        );
+        assert!(matches!(
+            reopened.get("quaid", "claude-org-id"),
+            Err(CredentialError::NotFound)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_is_only_readable_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        open(&path, "hunter2")
+            .unwrap()
+            .set("quaid", "fathom-api-key", "key")
+            .unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_or_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        open(&path, "hunter2")
+            .unwrap()
+            .set("quaid", "fathom-api-key", "key")
+            .unwrap();
+        assert!(matches!(
+            open(&path, "hunter3"),
+            Err(CredentialError::File(_))
+        ));
+        assert!(matches!(open(&path, ""), Err(CredentialError::File(_))));
+
+        let other = dir.path().join("notes.txt");
+        fs::write(&other, "not credentials, but long enough to hold a header").unwrap();
+        assert!(matches!(
+            open(&other, "hunter2"),
+            Err(CredentialError::File(_))
+        ));
+    }
+}
@@ -0,0 +1,382 @@
+//! Credential storage abstraction
+//!
+//! Provides a trait for credential storage with implementations for:
+//! - KeyringStore: Uses the system keychain (macOS Keychain, Windows Credential Manager, etc.)
+//! - FileStore: A passphrase-encrypted file, for servers without a keyring
+//! - MockStore: In-memory storage for testing
+//!
+//! Each provider account keeps its own copy of a credential under a user
+//! namespaced by its email, e.g. `chatgpt-token-me@example.com`, so several
+//! accounts of one provider can be signed in at once. The users each
+//! provider saves are listed in [`keys`], so signing out removes what signing
+//! in saved.
+
+mod file;
+
+pub use file::FileStore;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Keyring service every credential is saved under
+pub const SERVICE: &str = "quaid";
+
+/// Keyring users each provider keeps its credentials under
+pub mod keys {
+    pub mod chatgpt {
+        pub const TOKEN: &str = "chatgpt-token";
+        pub const COOKIES: &str = "chatgpt-cookies";
+        /// Workspace the sign-in opened in, from before workspaces were separate accounts
+        pub const WORKSPACE: &str = "chatgpt-workspace-id";
+        /// Ids of the accounts chosen to sync at sign-in, as a JSON list
+        pub const SELECTED: &str = "chatgpt-selected-accounts";
+        pub const ALL: &[&str] = &[TOKEN, COOKIES, WORKSPACE, SELECTED];
+    }
+
+    pub mod claude {
+        pub const COOKIES: &str = "claude-cookies";
+        pub const ORG: &str = "claude-org-id";
+        pub const ALL: &[&str] = &[COOKIES, ORG];
+    }
+
+    pub mod copilot {
+        pub const COOKIES: &str = "copilot-cookies";
+        pub const ALL: &[&str] = &[COOKIES];
+    }
+
+    pub mod deepseek {
+        pub const TOKEN: &str = "deepseek-token";
+        pub const COOKIES: &str = "deepseek-cookies";
+        pub const ALL: &[&str] = &[TOKEN, COOKIES];
+    }
+
+    pub mod fathom {
+        pub const API_KEY: &str = "fathom-api-key";
+        pub const ALL: &[&str] = &[API_KEY];
+    }
+
+    pub mod gemini {
+        pub const PSID: &str = "gemini-1psid";
+        pub const PSIDTS: &str = "gemini-1psidts";
+        pub const ALL: &[&str] = &[PSID, PSIDTS];
+    }
+
+    pub mod grok {
+        pub const AUTH_TOKEN: &str = "grok-auth-token";
+        pub const CT0: &str = "grok-ct0";
+        pub const ALL: &[&str] = &[AUTH_TOKEN, CT0];
+    }
+
+    pub mod lechat {
+        pub const COOKIES: &str = "lechat-cookies";
+        pub const ALL: &[&str] = &[COOKIES];
+    }
+
+    pub mod otter {
+        pub const COOKIES: &str = "otter-cookies";
+        pub const ALL: &[&str] = &[COOKIES];
+    }
+
+    pub mod poe {
+        pub const PB: &str = "poe-p-b";
+        pub const FORMKEY: &str = "poe-formkey";
+        pub const ALL: &[&str] = &[PB, FORMKEY];
+    }
+
+    /// Every keyring user `provider` saves; none for Granola, which reads the
+    /// desktop app's sign-in, and AI Studio, whose prompts are files
+    pub fn for_provider(provider: &str) -> &'static [&'static str] {
+        match provider {
+            "chatgpt" => chatgpt::ALL,
+            "claude" => claude::ALL,
+            "copilot" => copilot::ALL,
+            "deepseek" => deepseek::ALL,
+            "fathom" => fathom::ALL,
+            "gemini" => gemini::ALL,
+            "grok" => grok::ALL,
+            "lechat" => lechat::ALL,
+            "otter" => otter::ALL,
+            "poe" => poe::ALL,
+            _ => &[],
+        }
+    }
+}
+
+/// Errors that can occur during credential operations
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    #[error("Credential not found")]
+    NotFound,
+    #[error("Keyring error: {0}")]
+    Keyring(String),
+    #[error("Credential file error: {0}")]
+    File(String),
+}
+
+/// Trait for credential storage backends
+pub trait CredentialStore: Send + Sync {
+    /// Get a credential by service and user
+    fn get(&self, service: &str, user: &str) -> Result<String, CredentialError>;
+
+    /// Set a credential
+    fn set(&self, service: &str, user: &str, password: &str) -> Result<(), CredentialError>;
+
+    /// Delete a credential
+    fn delete(&self, service: &str, user: &str) -> Result<(), CredentialError>;
+
+    /// Get an account's credential
+    ///
+    /// Falls back to the un-namespaced credential saved before accounts were
+    /// kept apart, so existing sign-ins keep working.
+    fn get_for_account(
+        &self,
+        service: &str,
+        user: &str,
+        email: Option<&str>,
+    ) -> Result<String, CredentialError> {
+        if let Some(email) = email {
+            match self.get(service, &account_user(user, email)) {
+                Err(CredentialError::NotFound) => {}
+                found => return found,
+            }
+        }
+        self.get(service, user)
+    }
+
+    /// Set an account's credential
+    fn set_for_account(
+        &self,
+        service: &str,
+        user: &str,
+        email: &str,
+        password: &str,
+    ) -> Result<(), CredentialError> {
+        self.set(service, &account_user(user, email), password)
+    }
+
+    /// Delete an account's credential, and the un-namespaced one it would
+    /// fall back to, returning whether there was either
+    fn delete_for_account(
+        &self,
+        service: &str,
+        user: &str,
+        email: &str,
+    ) -> Result<bool, CredentialError> {
+        let mut deleted = false;
+        for user in [account_user(user, email), user.to_string()] {
+            match self.delete(service, &user) {
+                Ok(()) => deleted = true,
+                Err(CredentialError::NotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+/// Stores hold secrets, so only the kind is shown
+impl std::fmt::Debug for dyn CredentialStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CredentialStore")
+    }
+}
+
+/// Keyring user holding `user`'s credential for the account signed in as `email`
+pub fn account_user(user: &str, email: &str) -> String {
+    format!("{}-{}", user, email)
+}
+
+/// Real keyring-based credential store
+pub struct KeyringStore;
+
+impl KeyringStore {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for KeyringStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn get(&self, service: &str, user: &str) -> Result<String, CredentialError> {
+        let entry = keyring::Entry::new(service, user)
+            .map_err(|e| CredentialError::Keyring(e.to_string()))?;
+        entry.get_password().map_err(|e| match e {
+            keyring::Error::NoEntry => CredentialError::NotFound,
+            _ => CredentialError::Keyring(e.to_string()),
+        })
+    }
+
+    fn set(&self, service: &str, user: &str, password: &str) -> Result<(), CredentialError> {
+        let entry = keyring::Entry::new(service, user)
+            .map_err(|e| CredentialError::Keyring(e.to_string()))?;
+        entry
+            .set_password(password)
+            .map_err(|e| CredentialError::Keyring(e.to_string()))
+    }
+
+    fn delete(&self, service: &str, user: &str) -> Result<(), CredentialError> {
+        let entry = keyring::Entry::new(service, user)
+            .map_err(|e| CredentialError::Keyring(e.to_string()))?;
+        entry.delete_credential().map_err(|e| match e {
+            keyring::Error::NoEntry => CredentialError::NotFound,
+            _ => CredentialError::Keyring(e.to_string()),
+        })
+    }
+}
+
+/// In-memory credential store for testing
+#[derive(Clone, Default)]
+pub struct MockStore {
+    store: Arc<Mutex<HashMap<(String, String), String>>>,
+}
+
+impl MockStore {
+    pub fn new() -> Self {
+        Self {
+            store: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Create a mock store with pre-populated credentials
+    pub fn with_credentials(credentials: Vec<(&str, &str, &str)>) -> Self {
+        let store = Self::new();
+        for (service, user, password) in credentials {
+            store.set(service, user, password).unwrap();
+        }
+        store
+    }
+}
+
+impl CredentialStore for MockStore {
+    fn get(&self, service: &str, user: &str) -> Result<String, CredentialError> {
+        let store = self.store.lock().unwrap();
+        store
+            .get(&(service.to_string(), user.to_string()))
+            .cloned()
+            .ok_or(CredentialError::NotFound)
+    }
+
+    fn set(&self, service: &str, user: &str, password: &str) -> Result<(), CredentialError> {
+        let mut store = self.store.lock().unwrap();
+        store.insert(
+            (service.to_string(), user.to_string()),
+            password.to_string(),
+        );
+        Ok(())
+    }
+
+    fn delete(&self, service: &str, user: &str) -> Result<(), CredentialError> {
+        let mut store = self.store.lock().unwrap();
+        store
+            .remove(&(service.to_string(), user.to_string()))
+            .map(|_| ())
+            .ok_or(CredentialError::NotFound)
+    }
+}
+
+/// Get the default credential store (keyring for production)
+pub fn default_store() -> Arc<dyn CredentialStore> {
+    Arc::new(KeyringStore::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_store_get_set() {
+        let store = MockStore::new();
+        store.set("service", "user", "password123").unwrap();
+        assert_eq!(store.get("service", "user").unwrap(), "password123");
+    }
+
+    #[test]
+    fn test_mock_store_not_found() {
+        let store = MockStore::new();
+        assert!(matches!(
+            store.get("service", "user"),
+            Err(CredentialError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_mock_store_delete() {
+        let store = MockStore::new();
+        store.set("service", "user", "password123").unwrap();
+        store.delete("service", "user").unwrap();
+        assert!(matches!(
+            store.get("service", "user"),
+            Err(CredentialError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_mock_store_with_credentials() {
+        let store = MockStore::with_credentials(vec![
+            ("svc1", "user1", "pass1"),
+            ("svc2", "user2", "pass2"),
+        ]);
+        assert_eq!(store.get("svc1", "user1").unwrap(), "pass1");
+        assert_eq!(store.get("svc2", "user2").unwrap(), "pass2");
+    }
+
+    #[test]
+    fn test_account_credentials_are_kept_apart() {
+        let store = MockStore::with_credentials(vec![("quaid", "chatgpt-token", "legacy")]);
+        store
+            .set_for_account("quaid", "chatgpt-token", "work@example.com", "work")
+            .unwrap();
+        assert_eq!(
+            store
+                .get("quaid", "chatgpt-token-work@example.com")
+                .unwrap(),
+            "work"
+        );
+
+        let get = |email| {
+            store
+                .get_for_account("quaid", "chatgpt-token", email)
+                .unwrap()
+        };
+        assert_eq!(get(Some("work@example.com")), "work");
+        // Signed in before accounts were namespaced
+        assert_eq!(get(Some("me@example.com")), "legacy");
+        assert_eq!(get(None), "legacy");
+    }
+
+    #[test]
+    fn test_delete_for_account() {
+        let store = MockStore::with_credentials(vec![
+            (SERVICE, "claude-cookies", "legacy"),
+            (SERVICE, "claude-cookies-work@example.com", "work"),
+            (SERVICE, "claude-cookies-me@example.com", "me"),
+        ]);
+        let delete = |email| {
+            store
+                .delete_for_account(SERVICE, keys::claude::COOKIES, email)
+                .unwrap()
+        };
+        assert!(delete("work@example.com"));
+        assert!(!delete("work@example.com"));
+        assert!(store.get(SERVICE, "claude-cookies").is_err());
+        assert_eq!(
+            store.get(SERVICE, "claude-cookies-me@example.com").unwrap(),
+            "me"
+        );
+    }
+
+    #[test]
+    fn test_keys_for_provider() {
+        assert_eq!(
+            keys::for_provider("claude"),
+            ["claude-cookies", "claude-org-id"]
+        );
+        assert!(keys::for_provider("chatgpt").contains(&keys::chatgpt::SELECTED));
+        assert!(keys::for_provider("granola").is_empty());
+    }
+}
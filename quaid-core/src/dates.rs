@@ -0,0 +1,364 @@
+//! Date expressions for `--since`, `--until` and other date flags
+//!
+//! Every expression names a span of time: `2024-06-01` is that whole day,
+//! `2024-06` the whole month, `yesterday` and `last monday` a day, and a
+//! timestamp or an offset like `7d` a single instant. A lower bound uses the
+//! start of the span and an upper bound its end, so `--until 2024-06-01`
+//! takes in everything on June 1st. Calendar values are read in the given
+//! time zone.
+
+use chrono::{
+    DateTime, Datelike, Days, LocalResult, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta,
+    TimeZone, Utc, Weekday,
+};
+use thiserror::Error;
+
+/// Wall-clock formats accepted without an offset
+const LOCAL_DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "Invalid date: {0:?}. Use a date (2024-06-01), a month (2024-06), a time \
+     (2024-06-01T14:30 or RFC3339), an offset (7d, 3w, 2mo, 1y), today, yesterday \
+     or last <weekday>"
+)]
+pub struct DateError(pub String);
+
+/// The first and last instant a date expression covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateSpan {
+    pub start: DateTime<Utc>,
+    /// Inclusive: the last nanosecond of the span
+    pub end: DateTime<Utc>,
+}
+
+impl DateSpan {
+    fn instant(at: DateTime<Utc>) -> Self {
+        Self { start: at, end: at }
+    }
+}
+
+/// Start of the span `value` names, see [`parse_date_span`]
+pub fn parse_date_expr<Tz: TimeZone>(
+    value: &str,
+    now: DateTime<Utc>,
+    tz: &Tz,
+) -> Result<DateTime<Utc>, DateError> {
+    parse_date_span(value, now, tz).map(|span| span.start)
+}
+
+/// The span of time `value` names, with relative forms counted back from `now`
+///
+/// Accepts RFC3339 timestamps, `YYYY-MM-DD[THH:MM[:SS]]`, `YYYY-MM`, offsets
+/// in hours, days, weeks, months or years (`12h`, `7d`, `3w`, `2mo`, `1y`),
+/// `now`, `today`, `yesterday` and `last <weekday>`. Months and years are
+/// calendar ones, so `1mo` before March 31st is February's last day.
+pub fn parse_date_span<Tz: TimeZone>(
+    value: &str,
+    now: DateTime<Utc>,
+    tz: &Tz,
+) -> Result<DateSpan, DateError> {
+    let invalid = || DateError(value.to_string());
+    let trimmed = value.trim();
+
+    if let Ok(at) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(DateSpan::instant(at.with_timezone(&Utc)));
+    }
+    for format in LOCAL_DATETIME_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(trimmed, format) {
+            return local(tz, naive).map(DateSpan::instant).ok_or_else(invalid);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d") {
+        return days(tz, date, date).ok_or_else(invalid);
+    }
+    if let Some(first) = parse_month(trimmed) {
+        let last = first
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .ok_or_else(invalid)?;
+        return days(tz, first, last).ok_or_else(invalid);
+    }
+
+    let lower = trimmed.to_ascii_lowercase();
+    let local_now = now.with_timezone(tz).naive_local();
+    let today = local_now.date();
+    let span = match lower.as_str() {
+        "now" => Some(DateSpan::instant(now)),
+        "today" => days(tz, today, today),
+        "yesterday" => today.pred_opt().and_then(|day| days(tz, day, day)),
+        _ => match lower.strip_prefix("last ") {
+            Some(name) => {
+                let weekday: Weekday = name.trim().parse().map_err(|_| invalid())?;
+                let day = today - Days::new(days_since(today.weekday(), weekday));
+                days(tz, day, day)
+            }
+            None => offset(tz, now, local_now, &lower),
+        },
+    };
+    span.ok_or_else(invalid)
+}
+
+/// `2024-06` as June 1st
+fn parse_month(value: &str) -> Option<NaiveDate> {
+    let (year, month) = value.split_once('-')?;
+    if year.len() != 4 || month.len() != 2 {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)
+}
+
+/// Days back from `today` to the previous `weekday`; a week on that weekday itself
+fn days_since(today: Weekday, weekday: Weekday) -> u64 {
+    let back = (today.num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    if back == 0 {
+        7
+    } else {
+        back as u64
+    }
+}
+
+/// `7d` and the like, as the instant that long before `now`
+///
+/// Days and longer are counted on the wall clock, so `1d` across a DST change
+/// is the same time of day rather than 24 hours.
+fn offset<Tz: TimeZone>(
+    tz: &Tz,
+    now: DateTime<Utc>,
+    local_now: NaiveDateTime,
+    value: &str,
+) -> Option<DateSpan> {
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = value.split_at(split);
+    let n: u32 = number.parse().ok()?;
+    let then = match unit {
+        "h" => return Some(DateSpan::instant(now - TimeDelta::hours(n.into()))),
+        "d" => local_now.checked_sub_days(Days::new(n.into()))?,
+        "w" => local_now.checked_sub_days(Days::new(u64::from(n) * 7))?,
+        "mo" => local_now.checked_sub_months(Months::new(n))?,
+        "y" => local_now.checked_sub_months(Months::new(n.checked_mul(12)?))?,
+        _ => return None,
+    };
+    local(tz, then).map(DateSpan::instant)
+}
+
+/// From the start of `first` to the end of `last`, in `tz`
+fn days<Tz: TimeZone>(tz: &Tz, first: NaiveDate, last: NaiveDate) -> Option<DateSpan> {
+    let start = local(tz, first.and_time(NaiveTime::MIN))?;
+    let next = local(tz, last.succ_opt()?.and_time(NaiveTime::MIN))?;
+    Some(DateSpan {
+        start,
+        end: next - TimeDelta::nanoseconds(1),
+    })
+}
+
+/// `naive` read in `tz`
+///
+/// A time repeated when clocks go back is the earlier of the two; one
+/// skipped when they go forward moves to the first time that exists.
+fn local<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    let resolve = |naive: NaiveDateTime| match tz.from_local_datetime(&naive) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => Some(at.with_timezone(&Utc)),
+        LocalResult::None => None,
+    };
+    // Gaps are at most a couple of hours long
+    (0..=24 * 4).find_map(|quarter| resolve(naive + TimeDelta::minutes(15 * quarter)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::{New_York, Sao_Paulo};
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    /// A Wednesday afternoon
+    fn now() -> DateTime<Utc> {
+        utc("2024-06-12T15:30:00Z")
+    }
+
+    fn span(value: &str) -> DateSpan {
+        parse_date_span(value, now(), &Utc).unwrap()
+    }
+
+    #[test]
+    fn test_absolute_dates_and_times() {
+        assert_eq!(
+            span("2024-06-01"),
+            DateSpan {
+                start: utc("2024-06-01T00:00:00Z"),
+                end: utc("2024-06-01T23:59:59.999999999Z"),
+            }
+        );
+        assert_eq!(
+            span("2024-06-01T14:30:00+02:00"),
+            DateSpan::instant(utc("2024-06-01T12:30:00Z"))
+        );
+        assert_eq!(
+            span("2024-06-01T14:30"),
+            DateSpan::instant(utc("2024-06-01T14:30:00Z"))
+        );
+        assert_eq!(
+            span(" 2024-06-01 14:30:15 "),
+            DateSpan::instant(utc("2024-06-01T14:30:15Z"))
+        );
+        assert_eq!(
+            span("2024-06"),
+            DateSpan {
+                start: utc("2024-06-01T00:00:00Z"),
+                end: utc("2024-06-30T23:59:59.999999999Z"),
+            }
+        );
+        assert_eq!(
+            parse_date_expr("2024-06-01", now(), &New_York).unwrap(),
+            utc("2024-06-01T04:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_until_takes_in_the_whole_day() {
+        let until = span("2024-06-01").end;
+        assert!(utc("2024-06-01T23:59:59.5Z") <= until);
+        assert!(utc("2024-06-02T00:00:00Z") > until);
+        // A timestamp is exact
+        assert_eq!(
+            span("2024-06-01T12:00:00Z").end,
+            utc("2024-06-01T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_relative_offsets() {
+        assert_eq!(span("12h").start, utc("2024-06-12T03:30:00Z"));
+        assert_eq!(span("7d").start, utc("2024-06-05T15:30:00Z"));
+        assert_eq!(span("3w").start, utc("2024-05-22T15:30:00Z"));
+        assert_eq!(span("2mo").start, utc("2024-04-12T15:30:00Z"));
+        assert_eq!(span("1y").start, utc("2023-06-12T15:30:00Z"));
+        assert_eq!(span("now"), DateSpan::instant(now()));
+        assert_eq!(span("1mo").start, span("1mo").end);
+    }
+
+    #[test]
+    fn test_named_days() {
+        assert_eq!(span("today").start, utc("2024-06-12T00:00:00Z"));
+        assert_eq!(
+            span("Yesterday"),
+            DateSpan {
+                start: utc("2024-06-11T00:00:00Z"),
+                end: utc("2024-06-11T23:59:59.999999999Z"),
+            }
+        );
+        assert_eq!(span("last monday").start, utc("2024-06-10T00:00:00Z"));
+        assert_eq!(span("last tue").start, utc("2024-06-11T00:00:00Z"));
+        // Never today: a week back on the same weekday
+        assert_eq!(span("last wednesday").start, utc("2024-06-05T00:00:00Z"));
+        assert_eq!(span("last thursday").start, utc("2024-06-06T00:00:00Z"));
+
+        // The local day, not the UTC one: late Tuesday in New York is Wednesday in UTC
+        let late = utc("2024-06-12T02:00:00Z");
+        assert_eq!(
+            parse_date_expr("last monday", late, &New_York).unwrap(),
+            utc("2024-06-10T04:00:00Z")
+        );
+        assert_eq!(
+            parse_date_expr("today", late, &New_York).unwrap(),
+            utc("2024-06-11T04:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_leap_days() {
+        assert_eq!(
+            span("2024-02"),
+            DateSpan {
+                start: utc("2024-02-01T00:00:00Z"),
+                end: utc("2024-02-29T23:59:59.999999999Z"),
+            }
+        );
+        assert_eq!(span("2024-02-29").start, utc("2024-02-29T00:00:00Z"));
+        assert!(parse_date_span("2023-02-29", now(), &Utc).is_err());
+
+        let leap_day = utc("2024-02-29T10:00:00Z");
+        assert_eq!(
+            parse_date_expr("1y", leap_day, &Utc).unwrap(),
+            utc("2023-02-28T10:00:00Z")
+        );
+        assert_eq!(
+            parse_date_expr("1mo", utc("2024-03-31T10:00:00Z"), &Utc).unwrap(),
+            utc("2024-02-29T10:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_dst_transitions() {
+        // Clocks went back in New York: the day is 25 hours long
+        let fall_back = parse_date_span("2024-11-03", now(), &New_York).unwrap();
+        assert_eq!(fall_back.start, utc("2024-11-03T04:00:00Z"));
+        assert_eq!(fall_back.end, utc("2024-11-04T04:59:59.999999999Z"));
+        // 01:30 happened twice; the first one counts
+        assert_eq!(
+            parse_date_expr("2024-11-03T01:30", now(), &New_York).unwrap(),
+            utc("2024-11-03T05:30:00Z")
+        );
+
+        // 02:30 never happened when clocks went forward
+        assert_eq!(
+            parse_date_expr("2024-03-10T02:30", now(), &New_York).unwrap(),
+            utc("2024-03-10T07:00:00Z")
+        );
+        // A day back across the change is the same wall-clock time, 23 hours earlier
+        let after = utc("2024-03-11T05:00:00Z");
+        assert_eq!(
+            parse_date_expr("1d", after, &New_York).unwrap(),
+            utc("2024-03-10T06:00:00Z")
+        );
+
+        // Sao Paulo skipped midnight itself in 2018, so the day started at 01:00
+        assert_eq!(
+            parse_date_expr("2018-11-04", now(), &Sao_Paulo).unwrap(),
+            utc("2018-11-04T03:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_errors_list_accepted_formats() {
+        for value in [
+            "June 1st",
+            "",
+            "7",
+            "7x",
+            "-3d",
+            "last someday",
+            "2024-13",
+            "2024-6",
+        ] {
+            let err = parse_date_span(value, now(), &Utc).unwrap_err();
+            assert_eq!(err, DateError(value.to_string()));
+        }
+        let message = DateError("June 1st".to_string()).to_string();
+        assert!(
+            message.starts_with("Invalid date: \"June 1st\"."),
+            "{}",
+            message
+        );
+        for example in [
+            "2024-06-01",
+            "2024-06",
+            "RFC3339",
+            "7d",
+            "yesterday",
+            "last <weekday>",
+        ] {
+            assert!(message.contains(example), "{}", message);
+        }
+    }
+}
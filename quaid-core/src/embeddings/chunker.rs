@@ -1,8 +1,30 @@
 //! Message chunking for embedding generation
 //!
-//! Splits long messages into smaller chunks suitable for embedding models.
-
-use crate::providers::{Message, MessageContent};
+//! Splits long messages into smaller chunks suitable for embedding models,
+//! either by character count or, with the model's tokenizer, by token count.
+
+use crate::providers::{document_language, Message, MessageContent};
+use std::sync::Arc;
+use tokenizers::Tokenizer;
+
+/// Rough characters per token, for when the tokenizer isn't available
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// How chunk sizes are measured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChunkStrategy {
+    /// `max_chunk_chars` and `overlap_chars`, a proxy for tokens
+    #[default]
+    Chars,
+    /// Tokens of the embedding model's tokenizer, so chunks are cut on token
+    /// boundaries whatever the language. Without a tokenizer (remote and
+    /// Ollama embedders) this falls back to `CHARS_PER_TOKEN` characters per
+    /// token.
+    Tokens {
+        max_tokens: usize,
+        overlap_tokens: usize,
+    },
+}
 
 /// Configuration for the message chunker
 #[derive(Debug, Clone)]
@@ -11,6 +33,16 @@ pub struct ChunkerConfig {
     pub max_chunk_chars: usize,
     /// Number of characters to overlap between chunks
     pub overlap_chars: usize,
+    /// Use `cjk_max_chunk_chars` for Chinese, Japanese and Korean text
+    pub language_aware: bool,
+    /// Maximum chunk size for CJK text, where one character is roughly one
+    /// token but three bytes
+    pub cjk_max_chunk_chars: usize,
+    /// Characters by default; the `*_chars` settings don't apply to tokens
+    pub strategy: ChunkStrategy,
+    /// Embed thinking blocks along with the rest of a message; left out by
+    /// default, so reasoning doesn't outrank answers in semantic search
+    pub embed_thinking: bool,
 }
 
 impl Default for ChunkerConfig {
@@ -20,10 +52,45 @@ impl Default for ChunkerConfig {
             max_chunk_chars: 1024,
             // ~32 tokens * 4 chars/token = 128 chars
             overlap_chars: 128,
+            language_aware: true,
+            // ~256 tokens * 1 char/token * 3 bytes/char = 768
+            cjk_max_chunk_chars: 768,
+            strategy: ChunkStrategy::Chars,
+            embed_thinking: false,
         }
     }
 }
 
+/// Sentence terminators used in Chinese and Japanese
+const CJK_TERMINATORS: [char; 4] = ['。', '！', '？', '．'];
+
+/// Ideographic and full-width commas, a weaker break than a sentence end
+const CJK_COMMAS: [char; 2] = ['、', '，'];
+
+/// Closing brackets that belong to the sentence they end (「…。」)
+const CJK_CLOSERS: [char; 4] = ['」', '』', '）', '】'];
+
+/// Whether `c` is a Han, kana or Hangul character
+fn is_cjk_char(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+    )
+}
+
+/// Whether most of the letters in `text` are CJK
+fn is_mostly_cjk(text: &str) -> bool {
+    let (cjk, letters) = text
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .fold((0, 0), |(cjk, all), c| {
+            (cjk + is_cjk_char(c) as usize, all + 1)
+        });
+    letters > 0 && cjk * 2 > letters
+}
+
 /// A chunk of text from a message
 #[derive(Debug, Clone)]
 pub struct Chunk {
@@ -40,11 +107,21 @@ pub struct Chunk {
 /// Chunker for splitting messages into smaller pieces
 pub struct MessageChunker {
     config: ChunkerConfig,
+    tokenizer: Option<Arc<Tokenizer>>,
 }
 
 impl MessageChunker {
     pub fn new(config: ChunkerConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            tokenizer: None,
+        }
+    }
+
+    /// Count tokens with `tokenizer` under `ChunkStrategy::Tokens`
+    pub fn with_tokenizer(mut self, tokenizer: Arc<Tokenizer>) -> Self {
+        self.tokenizer = Some(tokenizer);
+        self
     }
 
     /// Find the nearest valid UTF-8 character boundary at or before the given byte index
@@ -73,8 +150,12 @@ impl MessageChunker {
         i
     }
 
-    /// Extract text content from a message
+    /// Extract text content from a message, leaving out thinking
     pub fn extract_text(content: &MessageContent) -> String {
+        Self::extract(content, false)
+    }
+
+    fn extract(content: &MessageContent, thinking: bool) -> String {
         match content {
             MessageContent::Text { text } => text.clone(),
             MessageContent::Code { code, language } => {
@@ -82,9 +163,15 @@ impl MessageChunker {
             }
             MessageContent::Image { alt, .. } => alt.clone().unwrap_or_default(),
             MessageContent::Audio { transcript, .. } => transcript.clone().unwrap_or_default(),
+            MessageContent::Document { title, mime, text } => {
+                format!("{}\n\n```{}\n{}\n```", title, document_language(mime), text)
+            }
+            MessageContent::Thinking { text } if thinking => text.clone(),
+            MessageContent::Thinking { .. } => String::new(),
             MessageContent::Mixed { parts } => parts
                 .iter()
-                .map(Self::extract_text)
+                .filter(|part| thinking || !matches!(part, MessageContent::Thinking { .. }))
+                .map(|part| Self::extract(part, thinking))
                 .collect::<Vec<_>>()
                 .join("\n\n"),
         }
@@ -92,13 +179,58 @@ impl MessageChunker {
 
     /// Chunk a single text string
     pub fn chunk_text(&self, text: &str) -> Vec<String> {
+        self.chunk_text_for_language(text, None)
+    }
+
+    /// Chunk text whose language is known, e.g. from language detection.
+    ///
+    /// `language` is an ISO 639-1 code; without one, CJK text is recognised
+    /// from its characters. CJK text gets the smaller `cjk_max_chunk_chars`
+    /// limit when `language_aware` is set. Token counts need no such hint.
+    pub fn chunk_text_for_language(&self, text: &str, language: Option<&str>) -> Vec<String> {
         let text = text.trim();
 
         if text.is_empty() {
             return vec![];
         }
 
-        if text.len() <= self.config.max_chunk_chars {
+        match (self.config.strategy, &self.tokenizer) {
+            (
+                ChunkStrategy::Tokens {
+                    max_tokens,
+                    overlap_tokens,
+                },
+                Some(tokenizer),
+            ) => self
+                .chunk_tokens(tokenizer, text, max_tokens, overlap_tokens)
+                .unwrap_or_else(|| self.chunk_chars(text, language)),
+            _ => self.chunk_chars(text, language),
+        }
+    }
+
+    /// Character limits: the configured ones, or estimated from token limits
+    fn char_limits(&self) -> (usize, usize) {
+        match self.config.strategy {
+            ChunkStrategy::Chars => (self.config.max_chunk_chars, self.config.overlap_chars),
+            ChunkStrategy::Tokens {
+                max_tokens,
+                overlap_tokens,
+            } => (
+                max_tokens * CHARS_PER_TOKEN,
+                overlap_tokens * CHARS_PER_TOKEN,
+            ),
+        }
+    }
+
+    fn chunk_chars(&self, text: &str, language: Option<&str>) -> Vec<String> {
+        let (max_chunk_chars, overlap_chars) = self.char_limits();
+        let max_chunk_chars = if self.config.language_aware && Self::is_cjk(text, language) {
+            self.config.cjk_max_chunk_chars.min(max_chunk_chars)
+        } else {
+            max_chunk_chars
+        };
+
+        if text.len() <= max_chunk_chars {
             return vec![text.to_string()];
         }
 
@@ -107,10 +239,7 @@ impl MessageChunker {
 
         while start < text.len() {
             // Ensure end is at a valid char boundary
-            let end = Self::floor_char_boundary(
-                text,
-                (start + self.config.max_chunk_chars).min(text.len()),
-            );
+            let end = Self::floor_char_boundary(text, (start + max_chunk_chars).min(text.len()));
 
             // Try to find a good break point (sentence boundary or paragraph)
             let chunk_end = if end < text.len() {
@@ -130,13 +259,10 @@ impl MessageChunker {
             }
 
             // Ensure new start is at a valid char boundary
-            start = Self::ceil_char_boundary(
-                text,
-                chunk_end.saturating_sub(self.config.overlap_chars),
-            );
+            start = Self::ceil_char_boundary(text, chunk_end.saturating_sub(overlap_chars));
 
             // Ensure we make progress
-            if start <= chunks.len().saturating_sub(1) * self.config.max_chunk_chars {
+            if start <= chunks.len().saturating_sub(1) * max_chunk_chars {
                 start = chunk_end;
             }
         }
@@ -144,14 +270,79 @@ impl MessageChunker {
         chunks
     }
 
+    /// Windows of at most `max_tokens` tokens, `overlap_tokens` apart, cut at
+    /// the same kind of break as `chunk_chars` when one falls in the overlap.
+    /// `None` if the tokenizer fails on the text.
+    fn chunk_tokens(
+        &self,
+        tokenizer: &Tokenizer,
+        text: &str,
+        max_tokens: usize,
+        overlap_tokens: usize,
+    ) -> Option<Vec<String>> {
+        let encoding = tokenizer.encode(text, false).ok()?;
+        // Byte offsets of each token in `text`
+        let offsets = encoding.get_offsets();
+        let max_tokens = max_tokens.max(1);
+        let overlap_tokens = overlap_tokens.min(max_tokens - 1);
+
+        if offsets.len() <= max_tokens {
+            return Some(vec![text.to_string()]);
+        }
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        loop {
+            let mut end = (start + max_tokens).min(offsets.len());
+            if end < offsets.len() {
+                let search_start = offsets[(end - overlap_tokens).max(start + 1)].0;
+                let max_end = offsets[end - 1].1;
+                let break_at = Self::find_break_in(text, search_start, max_end);
+                // Snap to the last token that ends before the break
+                let snapped = start + offsets[start..end].partition_point(|o| o.1 <= break_at);
+                if snapped > start {
+                    end = snapped;
+                }
+            }
+
+            let from = Self::floor_char_boundary(text, offsets[start].0);
+            let to = Self::ceil_char_boundary(text, offsets[end - 1].1);
+            let chunk = text[from..to].trim();
+            if !chunk.is_empty() {
+                chunks.push(chunk.to_string());
+            }
+
+            if end >= offsets.len() {
+                break;
+            }
+            let next = end - overlap_tokens;
+            start = if next > start { next } else { end };
+        }
+
+        Some(chunks)
+    }
+
+    fn is_cjk(text: &str, language: Option<&str>) -> bool {
+        match language {
+            Some(lang) => {
+                let primary = lang.split(['-', '_']).next().unwrap_or(lang);
+                matches!(primary.to_ascii_lowercase().as_str(), "zh" | "ja" | "ko")
+            }
+            None => is_mostly_cjk(text),
+        }
+    }
+
     /// Find a good break point (prefer sentence/paragraph boundaries)
     fn find_break_point(&self, text: &str, _start: usize, max_end: usize) -> usize {
+        let (_, overlap_chars) = self.char_limits();
+        Self::find_break_in(text, max_end.saturating_sub(overlap_chars), max_end)
+    }
+
+    /// The best break between `search_start` and `max_end`, or `max_end`
+    fn find_break_in(text: &str, search_start: usize, max_end: usize) -> usize {
         // Ensure boundaries are valid UTF-8 char boundaries
         let max_end = Self::floor_char_boundary(text, max_end);
-        let search_start = Self::ceil_char_boundary(
-            text,
-            max_end.saturating_sub(self.config.overlap_chars),
-        );
+        let search_start = Self::ceil_char_boundary(text, search_start).min(max_end);
 
         // Safety: search_start and max_end are now guaranteed to be valid char boundaries
         let search_text = &text[search_start..max_end];
@@ -161,8 +352,19 @@ impl MessageChunker {
             return search_start + pos + 2;
         }
 
-        // Look for sentence end (. ! ?)
+        // Look for sentence end (. ! ? or a CJK terminator)
         for (i, c) in search_text.char_indices().rev() {
+            if CJK_TERMINATORS.contains(&c) {
+                // CJK sentences aren't followed by a space; keep a closing
+                // bracket with the sentence it ends
+                let mut next_idx = search_start + i + c.len_utf8();
+                if let Some(closer) = text[next_idx..].chars().next() {
+                    if CJK_CLOSERS.contains(&closer) {
+                        next_idx += closer.len_utf8();
+                    }
+                }
+                return next_idx;
+            }
             if c == '.' || c == '!' || c == '?' {
                 // Check if followed by space or end
                 let next_idx = search_start + i + c.len_utf8();
@@ -183,6 +385,15 @@ impl MessageChunker {
             return search_start + pos + 1;
         }
 
+        // Look for an ideographic or full-width comma
+        if let Some((i, c)) = search_text
+            .char_indices()
+            .rev()
+            .find(|(_, c)| CJK_COMMAS.contains(c))
+        {
+            return search_start + i + c.len_utf8();
+        }
+
         // Look for word break (space)
         if let Some(pos) = search_text.rfind(' ') {
             return search_start + pos + 1;
@@ -194,7 +405,7 @@ impl MessageChunker {
 
     /// Chunk a message into multiple chunks
     pub fn chunk_message(&self, message: &Message) -> Vec<Chunk> {
-        let text = Self::extract_text(&message.content);
+        let text = Self::extract(&message.content, self.config.embed_thinking);
         let text_chunks = self.chunk_text(&text);
         let total_chunks = text_chunks.len();
 
@@ -235,6 +446,7 @@ mod tests {
             },
             created_at: None,
             model: None,
+            speaker: None,
         }
     }
 
@@ -257,6 +469,7 @@ mod tests {
         let config = ChunkerConfig {
             max_chunk_chars: 100,
             overlap_chars: 20,
+            ..Default::default()
         };
         let chunker = MessageChunker::new(config);
 
@@ -282,6 +495,7 @@ mod tests {
         let config = ChunkerConfig {
             max_chunk_chars: 50,
             overlap_chars: 10,
+            ..Default::default()
         };
         let chunker = MessageChunker::new(config);
 
@@ -320,6 +534,7 @@ mod tests {
             },
             created_at: None,
             model: None,
+            speaker: None,
         };
 
         let chunks = chunker.chunk_message(&message);
@@ -334,6 +549,7 @@ mod tests {
         let config = ChunkerConfig {
             max_chunk_chars: 50,
             overlap_chars: 20,
+            ..Default::default()
         };
         let chunker = MessageChunker::new(config);
 
@@ -350,11 +566,14 @@ mod tests {
             let first_words: std::collections::HashSet<_> = first_end.split_whitespace().collect();
             let second_words: std::collections::HashSet<_> =
                 second_start.split_whitespace().collect();
-            let overlap: Vec<_> = first_words.intersection(&second_words).collect();
+            let _overlap: Vec<_> = first_words.intersection(&second_words).collect();
 
             // Some overlap is expected but not guaranteed due to sentence boundary seeking
             // This test is more about ensuring the chunker doesn't crash with overlap config
-            assert!(chunks.len() >= 2, "Should have multiple chunks for overlap test");
+            assert!(
+                chunks.len() >= 2,
+                "Should have multiple chunks for overlap test"
+            );
         }
     }
 
@@ -363,6 +582,7 @@ mod tests {
         let config = ChunkerConfig {
             max_chunk_chars: 50,
             overlap_chars: 10,
+            ..Default::default()
         };
         let chunker = MessageChunker::new(config);
 
@@ -411,6 +631,33 @@ mod tests {
         assert!(extracted.contains("Part 2"));
     }
 
+    #[test]
+    fn test_thinking_is_embedded_only_when_configured() {
+        let mut message = create_test_message("msg-1", "");
+        message.content = MessageContent::Mixed {
+            parts: vec![
+                MessageContent::Thinking {
+                    text: "Weighing the options".to_string(),
+                },
+                MessageContent::Text {
+                    text: "Use SQLite".to_string(),
+                },
+            ],
+        };
+
+        let chunks = MessageChunker::new(ChunkerConfig::default()).chunk_message(&message);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].text, "Use SQLite");
+
+        let chunks = MessageChunker::new(ChunkerConfig {
+            embed_thinking: true,
+            ..Default::default()
+        })
+        .chunk_message(&message);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].text, "Weighing the options\n\nUse SQLite");
+    }
+
     #[test]
     fn test_chunk_empty_message() {
         let chunker = MessageChunker::new(ChunkerConfig::default());
@@ -452,6 +699,7 @@ mod tests {
         let config = ChunkerConfig {
             max_chunk_chars: 100,
             overlap_chars: 20,
+            ..Default::default()
         };
         let chunker = MessageChunker::new(config);
 
@@ -460,7 +708,10 @@ mod tests {
         let chunks = chunker.chunk_text(&text);
         assert!(!chunks.is_empty(), "Should produce chunks");
         for chunk in &chunks {
-            assert!(chunk.is_ascii() || chunk.len() > 0, "Chunk should be valid");
+            assert!(
+                chunk.is_ascii() || !chunk.is_empty(),
+                "Chunk should be valid"
+            );
         }
 
         // Text with emojis (4 bytes each)
@@ -491,4 +742,195 @@ mod tests {
         assert_eq!(MessageChunker::ceil_char_boundary(s, 2), 3); // Inside ─, go forward to 'a'
         assert_eq!(MessageChunker::ceil_char_boundary(s, 3), 3); // At 'a'
     }
+
+    const JAPANESE: &str =
+        "今日は東京で会議がありました。新しいプロジェクトについて話し合いました。\
+        来週までに資料を準備する必要があります。「本当ですか？」と彼は聞きました。";
+    const CHINESE: &str = "我们今天讨论了新的产品设计。团队成员提出了很多建议！\
+        下周我们需要完成原型，然后进行用户测试。你觉得这个计划可行吗？";
+
+    fn cjk_chunker(max_chunk_chars: usize) -> MessageChunker {
+        MessageChunker::new(ChunkerConfig {
+            max_chunk_chars,
+            overlap_chars: 90,
+            ..Default::default()
+        })
+    }
+
+    fn ends_at_cjk_sentence(chunk: &str) -> bool {
+        let trimmed = chunk.trim_end_matches(CJK_CLOSERS);
+        trimmed.ends_with(CJK_TERMINATORS)
+    }
+
+    #[test]
+    fn test_chunk_japanese_breaks_at_terminators() {
+        let text = JAPANESE.repeat(4);
+        let chunks = cjk_chunker(150).chunk_text(&text);
+
+        assert!(chunks.len() > 1, "Expected multiple chunks");
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(
+                ends_at_cjk_sentence(chunk),
+                "Mid-sentence break: '{}'",
+                chunk
+            );
+            assert!(
+                !chunk.starts_with(CJK_CLOSERS),
+                "Orphaned bracket: '{}'",
+                chunk
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunk_chinese_breaks_at_terminators() {
+        let text = CHINESE.repeat(4);
+        let chunks = cjk_chunker(150).chunk_text(&text);
+
+        assert!(chunks.len() > 1, "Expected multiple chunks");
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(
+                ends_at_cjk_sentence(chunk),
+                "Mid-sentence break: '{}'",
+                chunk
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunk_cjk_falls_back_to_ideographic_comma() {
+        // One long sentence: the only break candidates are commas
+        let text = "東京、大阪、名古屋、福岡、札幌、仙台、広島、".repeat(6);
+        let chunks = cjk_chunker(120).chunk_text(&text);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(chunk.ends_with('、'), "Expected comma break: '{}'", chunk);
+        }
+    }
+
+    #[test]
+    fn test_chunk_cjk_uses_smaller_limit() {
+        let chunker = MessageChunker::new(ChunkerConfig::default());
+        let text = CHINESE.repeat(12);
+
+        let chunks = chunker.chunk_text(&text);
+        let cjk_limit = ChunkerConfig::default().cjk_max_chunk_chars;
+        assert!(chunks.iter().all(|c| c.len() <= cjk_limit));
+
+        // The language hint overrides detection
+        let english = chunker.chunk_text_for_language(&text, Some("en"));
+        assert!(english.len() < chunks.len());
+        let hinted = chunker.chunk_text_for_language(&text, Some("zh-CN"));
+        assert_eq!(hinted, chunks);
+
+        // Without language awareness, the regular limit applies
+        let plain = MessageChunker::new(ChunkerConfig {
+            language_aware: false,
+            ..Default::default()
+        });
+        assert_eq!(plain.chunk_text(&text), english);
+    }
+
+    /// Words and punctuation runs as tokens, like a pre-tokenizer on its own
+    fn word_tokenizer() -> Arc<Tokenizer> {
+        let json = r#"{
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": null,
+            "pre_tokenizer": {"type": "Whitespace"},
+            "post_processor": null,
+            "decoder": null,
+            "model": {"type": "WordLevel", "vocab": {"[UNK]": 0}, "unk_token": "[UNK]"}
+        }"#;
+        Arc::new(json.parse().unwrap())
+    }
+
+    fn token_chunker(max_tokens: usize, overlap_tokens: usize) -> MessageChunker {
+        MessageChunker::new(ChunkerConfig {
+            strategy: ChunkStrategy::Tokens {
+                max_tokens,
+                overlap_tokens,
+            },
+            ..Default::default()
+        })
+        .with_tokenizer(word_tokenizer())
+    }
+
+    fn count_tokens(text: &str) -> usize {
+        word_tokenizer().encode(text, false).unwrap().len()
+    }
+
+    #[test]
+    fn test_token_chunks_respect_max_tokens() {
+        let chunker = token_chunker(12, 3);
+        let text = "The deploy failed again. Check the ingress logs first. \
+            Then restart the pod and watch it come back up."
+            .repeat(5);
+
+        let chunks = chunker.chunk_text(&text);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(count_tokens(chunk) <= 12, "Too many tokens: '{}'", chunk);
+        }
+        // Sentence ends in the overlap window are preferred
+        assert!(chunks[0].ends_with('.'), "{}", chunks[0]);
+
+        // Consecutive chunks share their overlap
+        let last_word = chunks[0].trim_end_matches('.').rsplit(' ').next().unwrap();
+        assert!(chunks[1].contains(last_word));
+    }
+
+    #[test]
+    fn test_token_chunks_never_split_a_token() {
+        // Multibyte words and no sentence breaks to prefer
+        let text = "añadir_configuración_día ".repeat(40);
+        let chunks = token_chunker(7, 2).chunk_text(&text);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk
+                .split(' ')
+                .all(|word| word == "añadir_configuración_día"));
+        }
+    }
+
+    #[test]
+    fn test_token_strategy_without_tokenizer_estimates_chars() {
+        let text = "word ".repeat(200);
+        let tokens = ChunkerConfig {
+            strategy: ChunkStrategy::Tokens {
+                max_tokens: 25,
+                overlap_tokens: 5,
+            },
+            ..Default::default()
+        };
+        let chars = ChunkerConfig {
+            max_chunk_chars: 25 * CHARS_PER_TOKEN,
+            overlap_chars: 5 * CHARS_PER_TOKEN,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            MessageChunker::new(tokens).chunk_text(&text),
+            MessageChunker::new(chars).chunk_text(&text)
+        );
+        // A short text is one chunk either way
+        assert_eq!(
+            token_chunker(25, 5).chunk_text("Hello there"),
+            vec!["Hello there"]
+        );
+    }
+
+    #[test]
+    fn test_is_mostly_cjk() {
+        assert!(is_mostly_cjk(JAPANESE));
+        assert!(is_mostly_cjk(CHINESE));
+        assert!(is_mostly_cjk("안녕하세요, 반갑습니다."));
+        assert!(!is_mostly_cjk("Hello world, this is English."));
+        assert!(!is_mostly_cjk("你好 Hello there, how are you doing today?"));
+        assert!(!is_mostly_cjk("12345 !!!"));
+    }
 }
@@ -0,0 +1,286 @@
+//! Model file downloads with checksum verification
+//!
+//! Every downloaded file gets a `<file>.manifest.json` sidecar recording the URL
+//! it came from and its SHA-256. A cached file is only reused while it still
+//! matches both, so a corrupted file or a changed pin triggers a fresh download.
+
+use super::{EmbeddingError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How many times a download is attempted before giving up
+const DOWNLOAD_ATTEMPTS: usize = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    url: String,
+    sha256: String,
+}
+
+/// Make sure `path` holds the file served at `url`.
+///
+/// A cached copy is kept if it matches its manifest (and `expected_sha256`, when
+/// given); otherwise the file is downloaded again. A download whose digest does
+/// not match `expected_sha256` is discarded and retried.
+pub fn ensure_file(path: &Path, url: &str, expected_sha256: Option<&str>) -> Result<()> {
+    if is_cached(path, url, expected_sha256)? {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut last_error = None;
+    for _ in 0..DOWNLOAD_ATTEMPTS {
+        match download_verified(path, url, expected_sha256) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.expect("at least one download attempt"))
+}
+
+/// Delete a cached file and its manifest so the next run downloads it again
+pub fn remove_cached(path: &Path) -> Result<()> {
+    for file in [path.to_path_buf(), sidecar(path, ".manifest.json")] {
+        match std::fs::remove_file(&file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Hex-encoded SHA-256 of a file
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+fn is_cached(path: &Path, url: &str, expected_sha256: Option<&str>) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    let manifest: Option<Manifest> = std::fs::read(sidecar(path, ".manifest.json"))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+
+    let actual = match (&manifest, expected_sha256) {
+        // Placed there by hand and nothing to check it against: trust it
+        (None, None) => return Ok(true),
+        (Some(m), _) if m.url != url => return Ok(false),
+        _ => sha256_file(path)?,
+    };
+
+    let matches_manifest = manifest.as_ref().is_none_or(|m| m.sha256 == actual);
+    let matches_expected = expected_sha256.is_none_or(|e| e.eq_ignore_ascii_case(&actual));
+    if matches_manifest && matches_expected {
+        if manifest.is_none() {
+            write_manifest(path, url, &actual)?;
+        }
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+fn download_verified(path: &Path, url: &str, expected_sha256: Option<&str>) -> Result<()> {
+    let part = sidecar(path, ".part");
+
+    // The blocking client must not run inside an async runtime, and callers
+    // may be on one (e.g. the CLI's search command)
+    let target = part.clone();
+    let source = url.to_string();
+    std::thread::spawn(move || download_to(&source, &target))
+        .join()
+        .map_err(|_| EmbeddingError::Download(format!("download thread panicked: {}", url)))??;
+
+    let actual = sha256_file(&part)?;
+    if let Some(expected) = expected_sha256 {
+        if !expected.eq_ignore_ascii_case(&actual) {
+            std::fs::remove_file(&part)?;
+            return Err(EmbeddingError::Download(format!(
+                "checksum mismatch for {}: expected {}, got {}",
+                url, expected, actual
+            )));
+        }
+    }
+
+    std::fs::rename(&part, path)?;
+    write_manifest(path, url, &actual)
+}
+
+fn download_to(url: &str, target: &Path) -> Result<()> {
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_secs(30 * 60))
+        .build()
+        .map_err(|e| EmbeddingError::Download(e.to_string()))?;
+
+    let mut response = client
+        .get(url)
+        .send()
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| EmbeddingError::Download(format!("{}: {}", url, e)))?;
+
+    let mut file = std::fs::File::create(target)?;
+    response
+        .copy_to(&mut file)
+        .map_err(|e| EmbeddingError::Download(format!("{}: {}", url, e)))?;
+    Ok(())
+}
+
+fn write_manifest(path: &Path, url: &str, sha256: &str) -> Result<()> {
+    let manifest = Manifest {
+        url: url.to_string(),
+        sha256: sha256.to_string(),
+    };
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| EmbeddingError::Download(e.to_string()))?;
+    std::fs::write(sidecar(path, ".manifest.json"), json)?;
+    Ok(())
+}
+
+/// `path` with `suffix` appended to its file name
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path as url_path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const BODY: &[u8] = b"model bytes";
+    // sha256("model bytes")
+    const BODY_SHA256: &str = "9cb7487000bc86ac36ce83c4acfabe8878552be99572a6770f65ab1d048a5c48";
+
+    async fn serve(body: &'static [u8], expected_calls: u64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(url_path("/model.onnx"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+            .expect(expected_calls)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn ensure(path: PathBuf, url: String, sha: Option<String>) -> Result<()> {
+        tokio::task::spawn_blocking(move || ensure_file(&path, &url, sha.as_deref()))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_is_cached_after_first_fetch() {
+        let server = serve(BODY, 1).await;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("models/model.onnx");
+        let url = format!("{}/model.onnx", server.uri());
+
+        let sha = Some(BODY_SHA256.to_string());
+        ensure(path.clone(), url.clone(), sha.clone())
+            .await
+            .unwrap();
+        ensure(path.clone(), url, sha).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+        assert!(sidecar(&path, ".manifest.json").exists());
+        assert!(!sidecar(&path, ".part").exists());
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch_is_rejected() {
+        let server = serve(BODY, DOWNLOAD_ATTEMPTS as u64).await;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("model.onnx");
+        let url = format!("{}/model.onnx", server.uri());
+
+        let err = ensure(path.clone(), url, Some("00".repeat(32)))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("checksum mismatch"));
+        assert!(!path.exists());
+        assert!(!sidecar(&path, ".part").exists());
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_cache_is_downloaded_again() {
+        let server = serve(BODY, 2).await;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("model.onnx");
+        let url = format!("{}/model.onnx", server.uri());
+
+        ensure(path.clone(), url.clone(), None).await.unwrap();
+        std::fs::write(&path, b"truncat").unwrap();
+        ensure(path.clone(), url, None).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    }
+
+    #[tokio::test]
+    async fn test_changed_url_is_downloaded_again() {
+        let server = serve(BODY, 1).await;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("model.onnx");
+        std::fs::write(&path, b"old revision").unwrap();
+        write_manifest(&path, "https://example.com/old/model.onnx", "stale").unwrap();
+
+        let url = format!("{}/model.onnx", server.uri());
+        ensure(path.clone(), url, None).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    }
+
+    #[test]
+    fn test_remove_cached() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("model.onnx");
+        std::fs::write(&path, BODY).unwrap();
+        write_manifest(&path, "https://example.com/model.onnx", BODY_SHA256).unwrap();
+
+        remove_cached(&path).unwrap();
+        assert!(!path.exists());
+        assert!(!sidecar(&path, ".manifest.json").exists());
+
+        // Removing again is not an error
+        remove_cached(&path).unwrap();
+    }
+}
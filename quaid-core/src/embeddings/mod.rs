@@ -1,13 +1,25 @@
 //! Embeddings module for semantic search
 //!
-//! Provides text chunking and ONNX-based embedding generation.
+//! Provides text chunking and embedding generation, either locally with ONNX,
+//! through a remote embeddings API, or through a local Ollama server.
 
 pub mod chunker;
+pub mod download;
 pub mod model;
+pub mod ollama;
+pub mod remote;
+pub mod rerank;
 
-pub use chunker::{Chunk, ChunkerConfig, MessageChunker};
-pub use model::{Embedder, EmbeddingModel, EmbeddingModelConfig, MockEmbeddingModel};
+pub use chunker::{Chunk, ChunkStrategy, ChunkerConfig, MessageChunker};
+pub use model::{Embedder, EmbeddingModel, EmbeddingModelConfig, MockEmbeddingModel, ModelSource};
+pub use ollama::{OllamaConfig, OllamaEmbedder};
+pub use remote::{OpenAiEmbedder, RemoteEmbedder, RemoteEmbedderConfig};
+pub use rerank::{CrossEncoder, CrossEncoderConfig, Reranker};
 
+use crate::credentials::CredentialStore;
+use crate::storage::embeddings::EmbeddingsMetadata;
+use std::path::Path;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,6 +38,237 @@ pub enum EmbeddingError {
 
     #[error("ONNX runtime error: {0}")]
     Ort(#[from] ort::Error),
+
+    #[error("Remote embedding error: {0}")]
+    Remote(String),
 }
 
 pub type Result<T> = std::result::Result<T, EmbeddingError>;
+
+/// Which embedder generates vectors for the index
+#[derive(Debug, Clone, Default)]
+pub enum EmbeddingBackend {
+    /// ONNX multilingual-e5-small, run in-process
+    #[default]
+    Local,
+    /// OpenAI-compatible embeddings API
+    Remote(RemoteEmbedderConfig),
+    /// Local Ollama server
+    Ollama(OllamaConfig),
+}
+
+impl EmbeddingBackend {
+    /// Backend from its name (`local`, `openai` or `ollama`) and an optional model
+    pub fn parse(name: &str, model: Option<&str>) -> Result<Self> {
+        match (name, model) {
+            ("local", None) => Ok(Self::Local),
+            ("local", Some(_)) => Err(EmbeddingError::Model(
+                "the local backend only supports multilingual-e5-small".to_string(),
+            )),
+            ("openai" | "remote", None) => Ok(Self::Remote(RemoteEmbedderConfig::openai())),
+            ("openai" | "remote", Some(model)) => RemoteEmbedderConfig::openai_model(model)
+                .map(Self::Remote)
+                .ok_or_else(|| {
+                    EmbeddingError::Model(format!("Unknown OpenAI embedding model: {}", model))
+                }),
+            ("ollama", model) => Ok(Self::Ollama(
+                model.map(OllamaConfig::new).unwrap_or_default(),
+            )),
+            (other, _) => Err(EmbeddingError::Model(format!(
+                "Unknown embedder: {}. Use local, openai or ollama",
+                other
+            ))),
+        }
+    }
+
+    /// Backend that produced an existing index
+    pub fn from_metadata(metadata: &EmbeddingsMetadata) -> Result<Self> {
+        match metadata.backend.as_str() {
+            "local" => Ok(Self::Local),
+            "remote" => Ok(Self::Remote(RemoteEmbedderConfig {
+                model: metadata.model.clone(),
+                dimension: metadata.dimension,
+                ..RemoteEmbedderConfig::openai()
+            })),
+            "ollama" => Ok(Self::Ollama(OllamaConfig {
+                dimension: Some(metadata.dimension),
+                ..OllamaConfig::new(metadata.model.clone())
+            })),
+            other => Err(EmbeddingError::Model(format!(
+                "Index was built with unknown embedder {}",
+                other
+            ))),
+        }
+    }
+
+    /// Send requests to `endpoint` instead of the default one
+    ///
+    /// The URL isn't part of the index metadata, so it applies to a backend
+    /// picked from an existing index too. The local backend has none and
+    /// ignores it.
+    pub fn with_endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            match &mut self {
+                Self::Local => {}
+                Self::Remote(config) => config.endpoint = endpoint.to_string(),
+                Self::Ollama(config) => config.endpoint = endpoint.to_string(),
+            }
+        }
+        self
+    }
+
+    /// Backend name as recorded in the index metadata
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Remote(_) => "remote",
+            Self::Ollama(_) => "ollama",
+        }
+    }
+
+    /// Model name as recorded in the index metadata
+    pub fn model(&self) -> String {
+        match self {
+            Self::Local => EmbeddingsMetadata::local_default().model,
+            Self::Remote(config) => config.model.clone(),
+            Self::Ollama(config) => config.model.clone(),
+        }
+    }
+
+    /// Metadata recorded alongside the embeddings written by `embedder`
+    pub fn metadata(&self, embedder: &dyn Embedder) -> EmbeddingsMetadata {
+        EmbeddingsMetadata {
+            backend: self.name().to_string(),
+            model: self.model(),
+            dimension: embedder.embedding_dim(),
+        }
+    }
+
+    /// Instantiate the embedder
+    ///
+    /// A remote embedder reads its API key from `credentials`.
+    pub fn load(
+        &self,
+        data_dir: &Path,
+        credentials: &Arc<dyn CredentialStore>,
+    ) -> Result<Arc<dyn Embedder>> {
+        self.load_with(
+            EmbeddingModelConfig::multilingual_e5_small(data_dir.join("models")),
+            credentials,
+        )
+    }
+
+    /// Instantiate the embedder with a single-threaded, arena-less local model
+    ///
+    /// Remote and Ollama embedders don't hold a model in this process, so
+    /// they load as usual.
+    pub fn load_low_memory(
+        &self,
+        data_dir: &Path,
+        credentials: &Arc<dyn CredentialStore>,
+    ) -> Result<Arc<dyn Embedder>> {
+        self.load_with(
+            EmbeddingModelConfig::multilingual_e5_small(data_dir.join("models")).with_low_memory(),
+            credentials,
+        )
+    }
+
+    /// A chunker for this backend's input, with the model's tokenizer when
+    /// `config` counts tokens and the model runs locally
+    ///
+    /// Call after [`load`](Self::load), which downloads the tokenizer.
+    pub fn chunker(&self, config: ChunkerConfig, data_dir: &Path) -> Result<MessageChunker> {
+        let counts_tokens = matches!(config.strategy, ChunkStrategy::Tokens { .. });
+        let chunker = MessageChunker::new(config);
+        Ok(match self {
+            Self::Local if counts_tokens => {
+                let local = EmbeddingModelConfig::multilingual_e5_small(data_dir.join("models"));
+                chunker.with_tokenizer(Arc::new(local.load_tokenizer()?))
+            }
+            _ => chunker,
+        })
+    }
+
+    fn load_with(
+        &self,
+        local: EmbeddingModelConfig,
+        credentials: &Arc<dyn CredentialStore>,
+    ) -> Result<Arc<dyn Embedder>> {
+        Ok(match self {
+            Self::Local => Arc::new(EmbeddingModel::load_or_download_with(local)?),
+            Self::Remote(config) => {
+                Arc::new(RemoteEmbedder::new(config.clone(), credentials.clone())?)
+            }
+            Self::Ollama(config) => Arc::new(OllamaEmbedder::new(config.clone())?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backend() {
+        assert!(matches!(
+            EmbeddingBackend::parse("local", None),
+            Ok(EmbeddingBackend::Local)
+        ));
+        assert!(EmbeddingBackend::parse("local", Some("bge-m3")).is_err());
+        assert!(EmbeddingBackend::parse("word2vec", None).is_err());
+
+        let openai = EmbeddingBackend::parse("openai", Some("text-embedding-3-large")).unwrap();
+        assert_eq!(openai.name(), "remote");
+        assert_eq!(openai.model(), "text-embedding-3-large");
+
+        let ollama = EmbeddingBackend::parse("ollama", None).unwrap();
+        assert_eq!(ollama.model(), ollama::DEFAULT_OLLAMA_MODEL);
+    }
+
+    #[test]
+    fn test_with_endpoint() {
+        let gateway = EmbeddingBackend::parse("openai", None)
+            .unwrap()
+            .with_endpoint(Some("http://gateway:4000/v1"));
+        match gateway {
+            EmbeddingBackend::Remote(config) => {
+                assert_eq!(config.endpoint, "http://gateway:4000/v1")
+            }
+            other => panic!("unexpected backend {:?}", other),
+        }
+
+        let ollama = EmbeddingBackend::parse("ollama", None).unwrap();
+        match ollama.clone().with_endpoint(None) {
+            EmbeddingBackend::Ollama(config) => {
+                assert_eq!(config.endpoint, ollama::DEFAULT_OLLAMA_ENDPOINT)
+            }
+            other => panic!("unexpected backend {:?}", other),
+        }
+        match ollama.with_endpoint(Some("http://gpu-box:11434")) {
+            EmbeddingBackend::Ollama(config) => assert_eq!(config.endpoint, "http://gpu-box:11434"),
+            other => panic!("unexpected backend {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_backend_round_trips_through_metadata() {
+        let embedder = MockEmbeddingModel::new(768);
+        let backend = EmbeddingBackend::parse("ollama", Some("nomic-embed-text")).unwrap();
+        let metadata = backend.metadata(&embedder);
+        assert_eq!(metadata.dimension, 768);
+
+        match EmbeddingBackend::from_metadata(&metadata).unwrap() {
+            EmbeddingBackend::Ollama(config) => {
+                assert_eq!(config.model, "nomic-embed-text");
+                assert_eq!(config.dimension, Some(768));
+            }
+            other => panic!("unexpected backend {:?}", other),
+        }
+
+        let local = EmbeddingsMetadata::local_default();
+        assert!(matches!(
+            EmbeddingBackend::from_metadata(&local),
+            Ok(EmbeddingBackend::Local)
+        ));
+    }
+}
@@ -2,8 +2,41 @@
 //!
 //! Provides embedding generation using ONNX Runtime with multilingual models.
 
-use super::Result;
+use super::{download, EmbeddingError, Result};
+use ndarray::{Array2, ArrayView2, ArrayView3, Ix3};
+use ort::execution_providers::CPUExecutionProvider;
+use ort::session::Session;
+use ort::value::Tensor;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+/// Hugging Face repository of the default model
+pub const E5_SMALL_REPO: &str = "intfloat/multilingual-e5-small";
+
+/// Where the model and tokenizer files are downloaded from
+#[derive(Debug, Clone)]
+pub struct ModelSource {
+    pub model_url: String,
+    pub tokenizer_url: String,
+    /// Expected SHA-256 of the model file, checked after every download
+    pub model_sha256: Option<String>,
+    /// Expected SHA-256 of the tokenizer file
+    pub tokenizer_sha256: Option<String>,
+}
+
+impl ModelSource {
+    /// Files from a Hugging Face repo at a branch, tag or commit
+    pub fn huggingface(repo: &str, revision: &str) -> Self {
+        let base = format!("https://huggingface.co/{}/resolve/{}", repo, revision);
+        Self {
+            model_url: format!("{}/onnx/model.onnx", base),
+            tokenizer_url: format!("{}/tokenizer.json", base),
+            model_sha256: None,
+            tokenizer_sha256: None,
+        }
+    }
+}
 
 /// Configuration for the embedding model
 #[derive(Debug, Clone)]
@@ -16,6 +49,14 @@ pub struct EmbeddingModelConfig {
     pub max_length: usize,
     /// Embedding dimension (384 for e5-small)
     pub embedding_dim: usize,
+    /// Maximum texts run through the model at once
+    pub batch_size: usize,
+    /// Threads ONNX Runtime uses within one operator, `None` for its default
+    pub intra_op_threads: Option<usize>,
+    /// Keep freed tensor memory in ONNX Runtime's arena for reuse
+    pub memory_arena: bool,
+    /// Download location and expected checksums
+    pub source: ModelSource,
 }
 
 impl EmbeddingModelConfig {
@@ -27,8 +68,57 @@ impl EmbeddingModelConfig {
             tokenizer_path: models_dir.join("multilingual-e5-small-tokenizer.json"),
             max_length: 512,
             embedding_dim: 384,
+            batch_size: 32,
+            intra_op_threads: None,
+            memory_arena: true,
+            source: ModelSource::huggingface(E5_SMALL_REPO, "main"),
         }
     }
+
+    /// Pin the model to a Hugging Face revision (commit hash or tag)
+    pub fn with_revision(mut self, revision: &str) -> Self {
+        let pinned = ModelSource::huggingface(E5_SMALL_REPO, revision);
+        self.source.model_url = pinned.model_url;
+        self.source.tokenizer_url = pinned.tokenizer_url;
+        self
+    }
+
+    /// Download from explicit URLs instead of Hugging Face
+    pub fn with_source(mut self, source: ModelSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Run at most `batch_size` texts through the model per inference call
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Single-threaded session without a memory arena, on small batches
+    pub fn with_low_memory(mut self) -> Self {
+        self.batch_size = self.batch_size.min(8);
+        self.intra_op_threads = Some(1);
+        self.memory_arena = false;
+        self
+    }
+
+    /// Require the downloaded files to have these SHA-256 digests
+    pub fn with_checksums(mut self, model_sha256: &str, tokenizer_sha256: Option<&str>) -> Self {
+        self.source.model_sha256 = Some(model_sha256.to_string());
+        self.source.tokenizer_sha256 = tokenizer_sha256.map(str::to_string);
+        self
+    }
+
+    /// Load the downloaded tokenizer for counting tokens, without the
+    /// truncation and padding it applies for inference
+    pub fn load_tokenizer(&self) -> Result<Tokenizer> {
+        let tokenizer_error = |e: tokenizers::Error| EmbeddingError::Tokenizer(e.to_string());
+        let mut tokenizer = Tokenizer::from_file(&self.tokenizer_path).map_err(tokenizer_error)?;
+        tokenizer.with_truncation(None).map_err(tokenizer_error)?;
+        tokenizer.with_padding(None);
+        Ok(tokenizer)
+    }
 }
 
 /// Trait for embedding models (allows mocking)
@@ -40,38 +130,186 @@ pub trait Embedder: Send + Sync {
     fn embed(&self, text: &str) -> Result<Vec<f32>>;
 
     /// Generate embeddings for a batch of texts
-    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+    ///
+    /// Defaults to one `embed` call per text; models that can run a whole batch
+    /// in one pass should override it.
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
 }
 
 /// ONNX-based embedding model
 ///
 /// Uses multilingual-e5-small for English/Spanish text embedding.
 pub struct EmbeddingModel {
-    #[allow(dead_code)]
     config: EmbeddingModelConfig,
-    // Session and tokenizer will be added when model files are available
+    /// `None` without model files, when mock embeddings stand in
+    session: Option<OnnxSession>,
     mock: MockEmbeddingModel,
 }
 
+/// A loaded ONNX model and the tokenizer that feeds it
+struct OnnxSession {
+    /// Running the session needs exclusive access
+    session: Mutex<Session>,
+    /// Truncates to the model's maximum length and pads to the longest text
+    tokenizer: Tokenizer,
+    /// Whether the model takes `token_type_ids`, as BERT exports do
+    token_type_ids: bool,
+}
+
+impl OnnxSession {
+    fn load(config: &EmbeddingModelConfig) -> Result<Self> {
+        let cpu = CPUExecutionProvider::default().with_arena_allocator(config.memory_arena);
+        let mut builder = Session::builder()?.with_execution_providers([cpu.build()])?;
+        if let Some(threads) = config.intra_op_threads {
+            builder = builder.with_intra_threads(threads)?;
+        }
+        let session = builder.commit_from_file(&config.model_path)?;
+        let token_type_ids = session
+            .inputs
+            .iter()
+            .any(|input| input.name == "token_type_ids");
+
+        let tokenizer_error = |e: tokenizers::Error| EmbeddingError::Tokenizer(e.to_string());
+        let mut tokenizer =
+            Tokenizer::from_file(&config.tokenizer_path).map_err(tokenizer_error)?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_length,
+                ..Default::default()
+            }))
+            .map_err(tokenizer_error)?;
+        // Keep the tokenizer's own pad token, padding only to the longest text
+        let padding = tokenizer.get_padding().cloned().unwrap_or_default();
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            ..padding
+        }));
+
+        Ok(Self {
+            session: Mutex::new(session),
+            tokenizer,
+            token_type_ids,
+        })
+    }
+
+    /// Embed `texts` in one inference call, padded to the longest of them
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| EmbeddingError::Tokenizer(e.to_string()))?;
+        let shape = (
+            encodings.len(),
+            encodings.first().map_or(0, |encoding| encoding.len()),
+        );
+        let ids = Array2::from_shape_fn(shape, |(row, col)| encodings[row].get_ids()[col] as i64);
+        let mask = Array2::from_shape_fn(shape, |(row, col)| {
+            encodings[row].get_attention_mask()[col] as i64
+        });
+
+        let mut inputs = ort::inputs![
+            "input_ids" => Tensor::from_array(ids)?,
+            "attention_mask" => Tensor::from_array(mask.clone())?,
+        ];
+        if self.token_type_ids {
+            let types = Array2::<i64>::zeros(shape);
+            inputs.push(("token_type_ids".into(), Tensor::from_array(types)?.into()));
+        }
+
+        let mut session = self.session.lock().unwrap_or_else(PoisonError::into_inner);
+        let outputs = session.run(inputs)?;
+        let hidden = outputs[0].try_extract_array::<f32>()?;
+        let hidden = hidden
+            .into_dimensionality::<Ix3>()
+            .map_err(|e| EmbeddingError::Model(format!("Unexpected model output shape: {}", e)))?;
+        Ok(pool_tokens(hidden, mask.view()))
+    }
+}
+
+/// Mean of each text's token vectors, L2 normalized
+///
+/// Padding is left out through the attention mask, so a text gets the same
+/// embedding whether it runs alone or padded in a batch.
+fn pool_tokens(hidden: ArrayView3<f32>, mask: ArrayView2<i64>) -> Vec<Vec<f32>> {
+    hidden
+        .outer_iter()
+        .zip(mask.outer_iter())
+        .map(|(tokens, mask)| {
+            // Normalizing the sum gives the same vector as normalizing the mean
+            let mut pooled = vec![0.0f32; tokens.ncols()];
+            for (token, _) in tokens.outer_iter().zip(mask).filter(|(_, &m)| m != 0) {
+                for (sum, value) in pooled.iter_mut().zip(token) {
+                    *sum += value;
+                }
+            }
+
+            let norm: f32 = pooled.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                pooled.iter_mut().for_each(|x| *x /= norm);
+            }
+            pooled
+        })
+        .collect()
+}
+
 impl EmbeddingModel {
     /// Load an embedding model from files
     ///
-    /// If model files don't exist, falls back to mock embeddings.
+    /// If model files don't exist, or are the empty stand-ins tests use,
+    /// falls back to mock embeddings.
     pub fn load(config: EmbeddingModelConfig) -> Result<Self> {
-        // TODO: Implement real ONNX loading when model files are available
-        // For now, use mock embeddings for development
         let mock = MockEmbeddingModel::new(config.embedding_dim);
-
-        Ok(Self { config, mock })
+        let has_model = std::fs::metadata(&config.model_path).is_ok_and(|m| m.len() > 0)
+            && config.tokenizer_path.exists();
+        let session = if has_model {
+            Some(OnnxSession::load(&config)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            config,
+            session,
+            mock,
+        })
     }
 
     /// Load with auto-download if model doesn't exist
     pub fn load_or_download(models_dir: impl AsRef<Path>) -> Result<Self> {
-        let config = EmbeddingModelConfig::multilingual_e5_small(&models_dir);
+        Self::load_or_download_with(EmbeddingModelConfig::multilingual_e5_small(&models_dir))
+    }
 
-        // TODO: Implement model download from HuggingFace
-        // For now, just use mock
-        Self::load(config)
+    /// Download (or verify the cached copy of) the configured model, then load it
+    ///
+    /// A model file that ONNX Runtime rejects is deleted so the next run fetches
+    /// a fresh copy instead of failing on the same file forever.
+    pub fn load_or_download_with(config: EmbeddingModelConfig) -> Result<Self> {
+        Self::download(&config)?;
+
+        match Self::load(config.clone()) {
+            Err(EmbeddingError::Ort(e)) => {
+                download::remove_cached(&config.model_path)?;
+                Err(EmbeddingError::Ort(e))
+            }
+            result => result,
+        }
+    }
+
+    /// Fetch the model and tokenizer files unless a verified copy is cached
+    pub fn download(config: &EmbeddingModelConfig) -> Result<()> {
+        let source = &config.source;
+        download::ensure_file(
+            &config.model_path,
+            &source.model_url,
+            source.model_sha256.as_deref(),
+        )?;
+        download::ensure_file(
+            &config.tokenizer_path,
+            &source.tokenizer_url,
+            source.tokenizer_sha256.as_deref(),
+        )
     }
 
     /// Compute mean pooled embedding from multiple embeddings (for conversation-level)
@@ -110,13 +348,32 @@ impl Embedder for EmbeddingModel {
     }
 
     fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        // TODO: Use real ONNX inference when implemented
-        Ok(self.mock.embed(text))
+        match &self.session {
+            Some(_) => Ok(self.embed_batch(&[text])?.remove(0)),
+            None => Ok(self.mock.embed(text)),
+        }
     }
 
     fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
-        // TODO: Use real ONNX batch inference when implemented
-        Ok(self.mock.embed_batch(texts))
+        // Each slice is one inference call, padded to its longest input
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.batch_size.max(1)) {
+            match &self.session {
+                Some(session) => embeddings.extend(session.embed_batch(batch)?),
+                None => embeddings.extend(self.mock.embed_batch(batch)),
+            }
+        }
+        if let Some(embedding) = embeddings
+            .first()
+            .filter(|e| e.len() != self.config.embedding_dim)
+        {
+            return Err(EmbeddingError::Model(format!(
+                "Model returned {} dimensions, expected {}",
+                embedding.len(),
+                self.config.embedding_dim
+            )));
+        }
+        Ok(embeddings)
     }
 }
 
@@ -133,9 +390,9 @@ impl MockEmbeddingModel {
 
     /// Generate a deterministic embedding based on text hash
     pub fn embed(&self, text: &str) -> Vec<f32> {
-        let hash = text.bytes().fold(0u64, |acc, b| {
-            acc.wrapping_mul(31).wrapping_add(b as u64)
-        });
+        let hash = text
+            .bytes()
+            .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
 
         // Generate deterministic values based on hash
         let mut embedding: Vec<f32> = (0..self.dim)
@@ -203,7 +460,10 @@ mod tests {
         let embedding = model.embed("Test text");
 
         let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
-        assert!((norm - 1.0).abs() < 0.01, "Embedding should be L2 normalized");
+        assert!(
+            (norm - 1.0).abs() < 0.01,
+            "Embedding should be L2 normalized"
+        );
     }
 
     #[test]
@@ -235,6 +495,72 @@ mod tests {
         assert_eq!(embedding.len(), 384);
     }
 
+    #[test]
+    fn test_default_embed_batch_embeds_each_text() {
+        struct Single(MockEmbeddingModel);
+
+        impl Embedder for Single {
+            fn embedding_dim(&self) -> usize {
+                self.0.dim
+            }
+
+            fn embed(&self, text: &str) -> Result<Vec<f32>> {
+                Ok(self.0.embed(text))
+            }
+        }
+
+        let model = Single(MockEmbeddingModel::new(8));
+        let embeddings = model.embed_batch(&["Hello", "World", "!"]).unwrap();
+
+        assert_eq!(embeddings.len(), 3);
+        assert_eq!(embeddings[1], model.0.embed("World"));
+    }
+
+    #[test]
+    fn test_embedding_model_batches_preserve_order() {
+        let config = EmbeddingModelConfig::multilingual_e5_small("/nonexistent").with_batch_size(2);
+        let model = EmbeddingModel::load(config).unwrap();
+        let texts = ["a", "b", "c", "d", "e"];
+
+        let embeddings = model.embed_batch(&texts).unwrap();
+
+        assert_eq!(embeddings.len(), 5);
+        assert_eq!(embeddings[4], model.embed("e").unwrap());
+    }
+
+    #[test]
+    fn test_batch_matches_single_texts() {
+        let config = EmbeddingModelConfig::multilingual_e5_small("/nonexistent").with_batch_size(3);
+        let model = EmbeddingModel::load(config).unwrap();
+        let texts = ["short", "a somewhat longer text", "", "mañana", "the end"];
+
+        let batched = model.embed_batch(&texts).unwrap();
+        let single: Vec<_> = texts.iter().map(|t| model.embed(t).unwrap()).collect();
+        assert_eq!(batched, single);
+    }
+
+    #[test]
+    fn test_pool_tokens_ignores_padding() {
+        // Two texts of 3 and 1 tokens, the second padded to 3
+        let hidden = ndarray::Array3::from_shape_vec(
+            (2, 3, 2),
+            vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 3.0, 4.0, 9.0, 9.0, -9.0, 9.0],
+        )
+        .unwrap();
+        let mask = ndarray::array![[1, 1, 1], [1, 0, 0]];
+        let batched = pool_tokens(hidden.view(), mask.view());
+
+        // The second text on its own, unpadded
+        let single = ndarray::Array3::from_shape_vec((1, 1, 2), vec![3.0, 4.0]).unwrap();
+        let alone = pool_tokens(single.view(), ndarray::array![[1]].view());
+
+        assert_eq!(batched[1], alone[0]);
+        assert_eq!(alone[0], vec![0.6, 0.8]);
+        let norm: f32 = batched[0].iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+        assert!((batched[0][0] - batched[0][1]).abs() < 1e-6);
+    }
+
     #[test]
     fn test_mean_pool() {
         let embedding1 = vec![1.0, 0.0, 0.0];
@@ -266,6 +592,32 @@ mod tests {
             .contains("multilingual-e5-small.onnx"));
     }
 
+    #[test]
+    fn test_embedding_model_config_revision_pin() {
+        let config = EmbeddingModelConfig::multilingual_e5_small("/tmp/models")
+            .with_revision("abc123")
+            .with_checksums("deadbeef", None);
+
+        assert_eq!(
+            config.source.model_url,
+            "https://huggingface.co/intfloat/multilingual-e5-small/resolve/abc123/onnx/model.onnx"
+        );
+        assert!(config.source.tokenizer_url.contains("/resolve/abc123/"));
+        assert_eq!(config.source.model_sha256.as_deref(), Some("deadbeef"));
+    }
+
+    #[test]
+    fn test_embedding_model_config_low_memory() {
+        let config = EmbeddingModelConfig::multilingual_e5_small("/tmp/models");
+        assert_eq!(config.intra_op_threads, None);
+        assert!(config.memory_arena);
+
+        let config = config.with_low_memory();
+        assert_eq!(config.intra_op_threads, Some(1));
+        assert!(!config.memory_arena);
+        assert_eq!(config.batch_size, 8);
+    }
+
     #[test]
     fn test_embedding_model_load_mock() {
         let config = EmbeddingModelConfig::multilingual_e5_small("/nonexistent");
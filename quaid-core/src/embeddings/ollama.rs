@@ -0,0 +1,260 @@
+//! Ollama embedding backend
+//!
+//! Calls a local Ollama server's `/api/embed` endpoint. Nothing leaves the
+//! machine and no API key is needed.
+
+use super::{Embedder, EmbeddingError, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Address Ollama listens on by default
+pub const DEFAULT_OLLAMA_ENDPOINT: &str = "http://localhost:11434";
+
+/// Model used when none is given
+pub const DEFAULT_OLLAMA_MODEL: &str = "nomic-embed-text";
+
+/// Configuration for an Ollama embeddings endpoint
+#[derive(Debug, Clone)]
+pub struct OllamaConfig {
+    /// Base URL of the server; `/api/embed` is appended
+    pub endpoint: String,
+    /// Model name, as shown by `ollama list`
+    pub model: String,
+    /// Expected vector dimension; probed from the server when `None`
+    pub dimension: Option<usize>,
+    /// Maximum number of inputs per request
+    pub batch_size: usize,
+}
+
+impl OllamaConfig {
+    /// Config for `model` on the default local endpoint
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            endpoint: DEFAULT_OLLAMA_ENDPOINT.to_string(),
+            model: model.into(),
+            dimension: None,
+            batch_size: 32,
+        }
+    }
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_OLLAMA_MODEL)
+    }
+}
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Embedder backed by a local Ollama server
+///
+/// Uses a blocking client since embedders run on the pipeline's worker threads.
+pub struct OllamaEmbedder {
+    config: OllamaConfig,
+    dimension: usize,
+    client: reqwest::blocking::Client,
+}
+
+impl OllamaEmbedder {
+    /// Connect to the server, probing the model's dimension if it isn't configured
+    pub fn new(config: OllamaConfig) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(120))
+            .build()
+            .map_err(|e| EmbeddingError::Remote(e.to_string()))?;
+
+        let mut embedder = Self {
+            dimension: config.dimension.unwrap_or(0),
+            config,
+            client,
+        };
+
+        if embedder.config.dimension.is_none() {
+            let probe = embedder.request(&["dimension probe"])?;
+            embedder.dimension = probe.first().map(Vec::len).unwrap_or(0);
+            if embedder.dimension == 0 {
+                return Err(EmbeddingError::Remote(format!(
+                    "Ollama model {} returned an empty embedding",
+                    embedder.config.model
+                )));
+            }
+        }
+        Ok(embedder)
+    }
+
+    pub fn config(&self) -> &OllamaConfig {
+        &self.config
+    }
+
+    fn request(&self, inputs: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/api/embed", self.config.endpoint.trim_end_matches('/'));
+        let response = self
+            .client
+            .post(&url)
+            .json(&EmbedRequest {
+                model: &self.config.model,
+                input: inputs,
+            })
+            .send()
+            .map_err(|e| {
+                EmbeddingError::Remote(format!(
+                    "Could not reach Ollama at {}: {}",
+                    self.config.endpoint, e
+                ))
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().unwrap_or_default();
+            return Err(EmbeddingError::Remote(format!(
+                "Ollama returned {}: {}",
+                status, text
+            )));
+        }
+
+        let parsed: EmbedResponse = response
+            .json()
+            .map_err(|e| EmbeddingError::Remote(format!("Invalid response: {}", e)))?;
+
+        if parsed.embeddings.len() != inputs.len() {
+            return Err(EmbeddingError::Remote(format!(
+                "Requested {} embeddings, got {}",
+                inputs.len(),
+                parsed.embeddings.len()
+            )));
+        }
+        Ok(parsed.embeddings)
+    }
+}
+
+impl Embedder for OllamaEmbedder {
+    fn embedding_dim(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_batch(&[text])?
+            .pop()
+            .ok_or_else(|| EmbeddingError::Remote("Empty response".to_string()))
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.batch_size.max(1)) {
+            for embedding in self.request(batch)? {
+                if embedding.len() != self.dimension {
+                    return Err(EmbeddingError::Remote(format!(
+                        "Model {} returned dimension {}, expected {}",
+                        self.config.model,
+                        embedding.len(),
+                        self.dimension
+                    )));
+                }
+                embeddings.push(embedding);
+            }
+        }
+        Ok(embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    /// Responds with one `dim`-sized vector per input
+    struct EchoEmbeddings {
+        dim: usize,
+    }
+
+    impl Respond for EchoEmbeddings {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let count = body["input"].as_array().unwrap().len();
+            let embeddings = vec![vec![0.25f32; self.dim]; count];
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "embeddings": embeddings }))
+        }
+    }
+
+    fn test_config(endpoint: String) -> OllamaConfig {
+        OllamaConfig {
+            endpoint,
+            batch_size: 2,
+            ..OllamaConfig::new("all-minilm")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ollama_embedder_probes_dimension() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .and(body_partial_json(
+                serde_json::json!({ "model": "all-minilm" }),
+            ))
+            .respond_with(EchoEmbeddings { dim: 12 })
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let config = test_config(server.uri());
+        let (dim, embeddings) = tokio::task::spawn_blocking(move || {
+            let embedder = OllamaEmbedder::new(config).unwrap();
+            (
+                embedder.embedding_dim(),
+                embedder.embed_batch(&["a", "b", "c"]).unwrap(),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(dim, 12);
+        assert_eq!(embeddings.len(), 3);
+        assert!(embeddings.iter().all(|e| e.len() == 12));
+    }
+
+    #[tokio::test]
+    async fn test_ollama_embedder_rejects_unexpected_dimension() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(EchoEmbeddings { dim: 4 })
+            .mount(&server)
+            .await;
+
+        let mut config = test_config(server.uri());
+        config.dimension = Some(768);
+        let result = tokio::task::spawn_blocking(move || OllamaEmbedder::new(config)?.embed("hi"))
+            .await
+            .unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ollama_embedder_reports_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("model not found"))
+            .mount(&server)
+            .await;
+
+        let config = test_config(server.uri());
+        let result = tokio::task::spawn_blocking(move || OllamaEmbedder::new(config).map(|_| ()))
+            .await
+            .unwrap();
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("model not found"), "{}", err);
+    }
+}
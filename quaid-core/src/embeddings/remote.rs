@@ -0,0 +1,454 @@
+//! Remote embedding backend
+//!
+//! Calls an OpenAI-compatible `/embeddings` endpoint (OpenAI, Voyage, a local
+//! gateway, ...) instead of running the ONNX model locally.
+
+use super::{Embedder, EmbeddingError, Result};
+use crate::credentials::CredentialStore;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Keyring service and user under which the embeddings API key is stored
+pub const API_KEY_SERVICE: &str = "quaid";
+pub const API_KEY_USER: &str = "embeddings-api-key";
+
+/// Environment variable read for the API key before the credential store
+pub const API_KEY_ENV: &str = "QUAID_EMBEDDINGS_API_KEY";
+
+/// Configuration for a remote embeddings endpoint
+#[derive(Debug, Clone)]
+pub struct RemoteEmbedderConfig {
+    /// Base URL of the API; `/embeddings` is appended
+    pub endpoint: String,
+    /// Model name sent with each request
+    pub model: String,
+    /// Dimension of the vectors the model returns
+    pub dimension: usize,
+    /// Maximum number of inputs per request
+    pub batch_size: usize,
+    /// Inputs longer than this (in characters) are truncated before sending
+    pub max_input_chars: usize,
+    /// How many times a rate-limited request is retried
+    pub max_retries: u32,
+    /// Delay before the first retry when the server sends no `Retry-After`
+    pub retry_base_delay: Duration,
+    /// Stop embedding after this many inputs in one run
+    pub max_embeddings: Option<usize>,
+}
+
+impl RemoteEmbedderConfig {
+    /// Config for OpenAI's text-embedding-3-small
+    pub fn openai() -> Self {
+        Self {
+            endpoint: "https://api.openai.com/v1".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            dimension: 1536,
+            batch_size: 64,
+            max_input_chars: 8000,
+            max_retries: 5,
+            retry_base_delay: Duration::from_secs(1),
+            max_embeddings: Some(100_000),
+        }
+    }
+
+    /// Config for another OpenAI embedding model
+    ///
+    /// Returns `None` for models whose dimension isn't known.
+    pub fn openai_model(model: &str) -> Option<Self> {
+        let dimension = match model {
+            "text-embedding-3-small" | "text-embedding-ada-002" => 1536,
+            "text-embedding-3-large" => 3072,
+            _ => return None,
+        };
+        Some(Self {
+            model: model.to_string(),
+            dimension,
+            ..Self::openai()
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    input: Vec<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embedder backed by an OpenAI-compatible HTTP API
+///
+/// Uses a blocking client since embedders run on the pipeline's worker threads.
+pub struct RemoteEmbedder {
+    config: RemoteEmbedderConfig,
+    api_key: String,
+    client: reqwest::blocking::Client,
+    embedded: AtomicUsize,
+    cap_warned: AtomicBool,
+}
+
+impl RemoteEmbedder {
+    /// Create an embedder, reading the API key from `QUAID_EMBEDDINGS_API_KEY`,
+    /// else the credential store
+    pub fn new(
+        config: RemoteEmbedderConfig,
+        credential_store: Arc<dyn CredentialStore>,
+    ) -> Result<Self> {
+        let api_key = api_key(std::env::var(API_KEY_ENV).ok(), credential_store.as_ref())?;
+        Self::with_api_key(config, api_key)
+    }
+
+    /// Create an embedder with an explicit API key
+    pub fn with_api_key(config: RemoteEmbedderConfig, api_key: impl Into<String>) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| EmbeddingError::Remote(e.to_string()))?;
+
+        Ok(Self {
+            config,
+            api_key: api_key.into(),
+            client,
+            embedded: AtomicUsize::new(0),
+            cap_warned: AtomicBool::new(false),
+        })
+    }
+
+    pub fn config(&self) -> &RemoteEmbedderConfig {
+        &self.config
+    }
+
+    /// Number of inputs embedded so far in this run
+    pub fn embedded_count(&self) -> usize {
+        self.embedded.load(Ordering::Relaxed)
+    }
+
+    /// Reserve `n` embeddings against the per-run cap
+    fn reserve(&self, n: usize) -> Result<()> {
+        let Some(cap) = self.config.max_embeddings else {
+            self.embedded.fetch_add(n, Ordering::Relaxed);
+            return Ok(());
+        };
+
+        let reserved = self
+            .embedded
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                (used + n <= cap).then_some(used + n)
+            });
+
+        if reserved.is_err() {
+            if !self.cap_warned.swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "Warning: reached the limit of {} remote embeddings for this run; \
+                     remaining chunks will not be embedded",
+                    cap
+                );
+            }
+            return Err(EmbeddingError::Remote(format!(
+                "Embedding cap of {} per run reached",
+                cap
+            )));
+        }
+        Ok(())
+    }
+
+    /// Send one request, retrying on 429
+    fn request(&self, inputs: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.config.endpoint.trim_end_matches('/'));
+        let truncated: Vec<&str> = inputs
+            .iter()
+            .map(|t| truncate_chars(t, self.config.max_input_chars))
+            .collect();
+        let body = EmbeddingsRequest {
+            model: &self.config.model,
+            input: truncated,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let response = self
+                .client
+                .post(&url)
+                .bearer_auth(&self.api_key)
+                .json(&body)
+                .send()
+                .map_err(|e| EmbeddingError::Remote(e.to_string()))?;
+
+            let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < self.config.max_retries
+            {
+                let delay = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .map(Duration::from_secs)
+                    .unwrap_or(self.config.retry_base_delay * 2u32.pow(attempt));
+                std::thread::sleep(delay);
+                attempt += 1;
+                continue;
+            }
+
+            if !status.is_success() {
+                let text = response.text().unwrap_or_default();
+                return Err(EmbeddingError::Remote(format!(
+                    "Embeddings API returned {}: {}",
+                    status, text
+                )));
+            }
+
+            let mut parsed: EmbeddingsResponse = response
+                .json()
+                .map_err(|e| EmbeddingError::Remote(format!("Invalid response: {}", e)))?;
+
+            if parsed.data.len() != inputs.len() {
+                return Err(EmbeddingError::Remote(format!(
+                    "Requested {} embeddings, got {}",
+                    inputs.len(),
+                    parsed.data.len()
+                )));
+            }
+            parsed.data.sort_by_key(|d| d.index);
+
+            for data in &parsed.data {
+                if data.embedding.len() != self.config.dimension {
+                    return Err(EmbeddingError::Remote(format!(
+                        "Model {} returned dimension {}, configured {}",
+                        self.config.model,
+                        data.embedding.len(),
+                        self.config.dimension
+                    )));
+                }
+            }
+
+            return Ok(parsed.data.into_iter().map(|d| d.embedding).collect());
+        }
+    }
+}
+
+/// The key from the environment when set, else the one `quaid embeddings auth` stored
+fn api_key(from_env: Option<String>, credential_store: &dyn CredentialStore) -> Result<String> {
+    if let Some(key) = from_env.filter(|key| !key.trim().is_empty()) {
+        return Ok(key.trim().to_string());
+    }
+    credential_store
+        .get(API_KEY_SERVICE, API_KEY_USER)
+        .map_err(|e| {
+            EmbeddingError::Remote(format!(
+                "No embeddings API key configured ({}). Run `quaid embeddings auth` or set {}",
+                e, API_KEY_ENV
+            ))
+        })
+}
+
+/// The remote embedder pointed at api.openai.com
+pub type OpenAiEmbedder = RemoteEmbedder;
+
+impl Embedder for RemoteEmbedder {
+    fn embedding_dim(&self) -> usize {
+        self.config.dimension
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_batch(&[text])?
+            .pop()
+            .ok_or_else(|| EmbeddingError::Remote("Empty response".to_string()))
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.batch_size.max(1)) {
+            self.reserve(batch.len())?;
+            embeddings.extend(self.request(batch)?);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Truncate to at most `max` characters on a char boundary
+fn truncate_chars(text: &str, max: usize) -> &str {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => &text[..idx],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    // The blocking client must be created and dropped off the async runtime,
+    // hence the `spawn_blocking` in every test.
+
+    /// Responds with one `dim`-sized vector per input
+    struct EchoEmbeddings {
+        dim: usize,
+    }
+
+    impl Respond for EchoEmbeddings {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let count = body["input"].as_array().unwrap().len();
+            let data: Vec<_> = (0..count)
+                .map(|i| serde_json::json!({ "index": i, "embedding": vec![0.5f32; self.dim] }))
+                .collect();
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "data": data }))
+        }
+    }
+
+    fn test_config(endpoint: String) -> RemoteEmbedderConfig {
+        RemoteEmbedderConfig {
+            endpoint,
+            model: "test-embedding".to_string(),
+            dimension: 8,
+            batch_size: 2,
+            max_input_chars: 100,
+            max_retries: 2,
+            retry_base_delay: Duration::from_millis(10),
+            max_embeddings: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_embedder_batches_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(EchoEmbeddings { dim: 8 })
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let config = test_config(server.uri());
+        let embeddings = tokio::task::spawn_blocking(move || {
+            let embedder = RemoteEmbedder::with_api_key(config, "sk-test").unwrap();
+            embedder.embed_batch(&["a", "b", "c", "d", "e"]).unwrap()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(embeddings.len(), 5);
+        assert!(embeddings.iter().all(|e| e.len() == 8));
+    }
+
+    #[tokio::test]
+    async fn test_remote_embedder_retries_on_429() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(EchoEmbeddings { dim: 8 })
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = test_config(server.uri());
+        let embedding = tokio::task::spawn_blocking(move || {
+            RemoteEmbedder::with_api_key(config, "sk-test")?.embed("hello")
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(embedding.len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_remote_embedder_rejects_unexpected_dimension() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(EchoEmbeddings { dim: 4 })
+            .mount(&server)
+            .await;
+
+        let config = test_config(server.uri());
+        let result = tokio::task::spawn_blocking(move || {
+            RemoteEmbedder::with_api_key(config, "sk-test")?.embed("hello")
+        })
+        .await
+        .unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remote_embedder_enforces_run_cap() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(EchoEmbeddings { dim: 8 })
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = test_config(server.uri());
+        config.max_embeddings = Some(3);
+        let (first, second, count) = tokio::task::spawn_blocking(move || {
+            let embedder = RemoteEmbedder::with_api_key(config, "sk-test").unwrap();
+            let first = embedder.embed_batch(&["a", "b"]);
+            let second = embedder.embed_batch(&["c", "d"]);
+            (first, second, embedder.embedded_count())
+        })
+        .await
+        .unwrap();
+
+        assert!(first.is_ok());
+        assert!(second.is_err());
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("hello", 10), "hello");
+        assert_eq!(truncate_chars("hello", 3), "hel");
+        assert_eq!(truncate_chars("héllo", 2), "hé");
+    }
+
+    #[test]
+    fn test_openai_model_dimensions() {
+        let large = RemoteEmbedderConfig::openai_model("text-embedding-3-large").unwrap();
+        assert_eq!(large.dimension, 3072);
+        assert_eq!(large.endpoint, RemoteEmbedderConfig::openai().endpoint);
+        assert!(RemoteEmbedderConfig::openai_model("mystery-embedder").is_none());
+    }
+
+    #[test]
+    fn test_new_requires_api_key() {
+        let store = Arc::new(crate::credentials::MockStore::new());
+        assert!(RemoteEmbedder::new(RemoteEmbedderConfig::openai(), store).is_err());
+    }
+
+    #[test]
+    fn test_api_key_prefers_the_environment() {
+        let store = crate::credentials::MockStore::new();
+        let err = api_key(None, &store).unwrap_err();
+        assert!(err.to_string().contains("quaid embeddings auth"));
+
+        store
+            .set(API_KEY_SERVICE, API_KEY_USER, "sk-stored")
+            .unwrap();
+        assert_eq!(api_key(None, &store).unwrap(), "sk-stored");
+        assert_eq!(api_key(Some(" ".to_string()), &store).unwrap(), "sk-stored");
+        assert_eq!(
+            api_key(Some("sk-env\n".to_string()), &store).unwrap(),
+            "sk-env"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Cross-encoder reranking for semantic search
+//!
+//! Vector search compares a query and a chunk that were embedded separately,
+//! so loosely related chunks often rank high. A cross-encoder reads the query
+//! and the chunk together and scores how well one answers the other. It is too
+//! slow to run over the whole index, so it only reorders the top candidates.
+
+use super::model::ModelSource;
+use super::{download, EmbeddingError, Result};
+use crate::storage::SemanticSearchResult;
+use ndarray::{Array2, Ix2};
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+/// Hugging Face repository of the default reranker
+pub const MS_MARCO_MINILM_REPO: &str = "cross-encoder/ms-marco-MiniLM-L-6-v2";
+
+/// Candidates fetched from the vector index for every result kept after reranking
+pub const RERANK_CANDIDATE_FACTOR: usize = 4;
+
+/// Query and chunk pairs scored per inference call
+const RERANK_BATCH_SIZE: usize = 16;
+
+/// Configuration for the cross-encoder model
+#[derive(Debug, Clone)]
+pub struct CrossEncoderConfig {
+    /// Path to the ONNX model file
+    pub model_path: PathBuf,
+    /// Path to the tokenizer JSON file
+    pub tokenizer_path: PathBuf,
+    /// Maximum length of the query and chunk together, in tokens
+    pub max_length: usize,
+    /// Download location and expected checksums
+    pub source: ModelSource,
+}
+
+impl CrossEncoderConfig {
+    /// Create config for ms-marco-MiniLM-L-6-v2
+    pub fn ms_marco_minilm(models_dir: impl AsRef<Path>) -> Self {
+        let models_dir = models_dir.as_ref();
+        Self {
+            model_path: models_dir.join("ms-marco-minilm-l6.onnx"),
+            tokenizer_path: models_dir.join("ms-marco-minilm-l6-tokenizer.json"),
+            max_length: 512,
+            source: ModelSource::huggingface(MS_MARCO_MINILM_REPO, "main"),
+        }
+    }
+
+    /// Download from explicit URLs instead of Hugging Face
+    pub fn with_source(mut self, source: ModelSource) -> Self {
+        self.source = source;
+        self
+    }
+}
+
+/// Trait for rerankers (allows mocking)
+pub trait Reranker: Send + Sync {
+    /// Relevance of each text to `query`, higher is better, in input order
+    fn score(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>>;
+}
+
+/// ONNX cross-encoder
+pub struct CrossEncoder {
+    /// Running the session needs exclusive access
+    session: Mutex<Session>,
+    /// Encodes the query and chunk as one pair, truncated to the model's length
+    tokenizer: Tokenizer,
+    /// Whether the model takes `token_type_ids`, as BERT exports do
+    token_type_ids: bool,
+}
+
+impl CrossEncoder {
+    /// Load a cross-encoder from files
+    pub fn load(config: CrossEncoderConfig) -> Result<Self> {
+        let session = Session::builder()?.commit_from_file(&config.model_path)?;
+        let token_type_ids = session
+            .inputs
+            .iter()
+            .any(|input| input.name == "token_type_ids");
+
+        let tokenizer_error = |e: tokenizers::Error| EmbeddingError::Tokenizer(e.to_string());
+        let mut tokenizer =
+            Tokenizer::from_file(&config.tokenizer_path).map_err(tokenizer_error)?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_length,
+                ..Default::default()
+            }))
+            .map_err(tokenizer_error)?;
+        let padding = tokenizer.get_padding().cloned().unwrap_or_default();
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            ..padding
+        }));
+
+        Ok(Self {
+            session: Mutex::new(session),
+            tokenizer,
+            token_type_ids,
+        })
+    }
+
+    /// Load with auto-download if the model doesn't exist
+    pub fn load_or_download(models_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::load_or_download_with(CrossEncoderConfig::ms_marco_minilm(&models_dir))
+    }
+
+    /// Download (or verify the cached copy of) the configured model, then load it
+    pub fn load_or_download_with(config: CrossEncoderConfig) -> Result<Self> {
+        let source = &config.source;
+        download::ensure_file(
+            &config.model_path,
+            &source.model_url,
+            source.model_sha256.as_deref(),
+        )?;
+        download::ensure_file(
+            &config.tokenizer_path,
+            &source.tokenizer_url,
+            source.tokenizer_sha256.as_deref(),
+        )?;
+
+        match Self::load(config.clone()) {
+            Err(EmbeddingError::Ort(e)) => {
+                download::remove_cached(&config.model_path)?;
+                Err(EmbeddingError::Ort(e))
+            }
+            result => result,
+        }
+    }
+
+    /// Logit of each pair in one inference call, padded to the longest pair
+    fn score_batch(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>> {
+        let pairs: Vec<(&str, &str)> = texts.iter().map(|text| (query, *text)).collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(|e| EmbeddingError::Tokenizer(e.to_string()))?;
+        let shape = (
+            encodings.len(),
+            encodings.first().map_or(0, |encoding| encoding.len()),
+        );
+        let ids = Array2::from_shape_fn(shape, |(row, col)| encodings[row].get_ids()[col] as i64);
+        let mask = Array2::from_shape_fn(shape, |(row, col)| {
+            encodings[row].get_attention_mask()[col] as i64
+        });
+
+        let mut inputs = ort::inputs![
+            "input_ids" => Tensor::from_array(ids)?,
+            "attention_mask" => Tensor::from_array(mask)?,
+        ];
+        if self.token_type_ids {
+            // Tells the query's tokens from the chunk's
+            let types = Array2::from_shape_fn(shape, |(row, col)| {
+                encodings[row].get_type_ids()[col] as i64
+            });
+            inputs.push(("token_type_ids".into(), Tensor::from_array(types)?.into()));
+        }
+
+        let mut session = self.session.lock().unwrap_or_else(PoisonError::into_inner);
+        let outputs = session.run(inputs)?;
+        let logits = outputs[0].try_extract_array::<f32>()?;
+        let logits = logits
+            .into_dimensionality::<Ix2>()
+            .map_err(|e| EmbeddingError::Model(format!("Unexpected model output shape: {}", e)))?;
+        Ok(logits.outer_iter().map(|row| row[0]).collect())
+    }
+}
+
+impl Reranker for CrossEncoder {
+    fn score(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>> {
+        let mut scores = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(RERANK_BATCH_SIZE) {
+            scores.extend(self.score_batch(query, batch)?);
+        }
+        Ok(scores)
+    }
+}
+
+/// Reorder `results` by how well each chunk answers `query`
+///
+/// The sort is stable, so chunks the reranker scores equally keep their
+/// vector order. Scores in the results are left as the index returned them.
+pub fn rerank(
+    query: &str,
+    results: Vec<SemanticSearchResult>,
+    reranker: &dyn Reranker,
+) -> Result<Vec<SemanticSearchResult>> {
+    let texts: Vec<&str> = results.iter().map(|r| r.chunk_text.as_str()).collect();
+    let scores = reranker.score(query, &texts)?;
+    if scores.len() != results.len() {
+        return Err(EmbeddingError::Model(format!(
+            "reranker returned {} scores for {} chunks",
+            scores.len(),
+            results.len()
+        )));
+    }
+
+    let mut scored: Vec<_> = scores.into_iter().zip(results).collect();
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    Ok(scored.into_iter().map(|(_, result)| result).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str, text: &str) -> SemanticSearchResult {
+        SemanticSearchResult {
+            conversation_id: format!("conv-{}", id),
+            message_id: format!("msg-{}", id),
+            chunk_text: text.to_string(),
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_rerank_sorts_by_score_and_keeps_ties_in_order() {
+        struct ByLength;
+        impl Reranker for ByLength {
+            fn score(&self, _query: &str, texts: &[&str]) -> Result<Vec<f32>> {
+                Ok(texts.iter().map(|t| t.len() as f32).collect())
+            }
+        }
+
+        let results = vec![
+            result("a", "ab"),
+            result("b", "abcd"),
+            result("c", "cd"),
+            result("d", "a"),
+        ];
+        let reranked = rerank("query", results, &ByLength).unwrap();
+        let ids: Vec<_> = reranked.iter().map(|r| r.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["msg-b", "msg-a", "msg-c", "msg-d"]);
+    }
+
+    #[test]
+    fn test_rerank_rejects_missing_scores() {
+        struct Empty;
+        impl Reranker for Empty {
+            fn score(&self, _query: &str, _texts: &[&str]) -> Result<Vec<f32>> {
+                Ok(Vec::new())
+            }
+        }
+
+        assert!(rerank("query", vec![result("a", "text")], &Empty).is_err());
+    }
+
+    #[test]
+    fn test_cross_encoder_config() {
+        let config = CrossEncoderConfig::ms_marco_minilm("/models");
+        assert_eq!(
+            config.model_path,
+            PathBuf::from("/models/ms-marco-minilm-l6.onnx")
+        );
+        assert_eq!(
+            config.source.model_url,
+            "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2/resolve/main/onnx/model.onnx"
+        );
+    }
+
+    #[test]
+    fn test_cross_encoder_needs_the_model_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CrossEncoderConfig::ms_marco_minilm(dir.path());
+        assert!(CrossEncoder::load(config).is_err());
+    }
+}
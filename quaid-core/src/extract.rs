@@ -0,0 +1,261 @@
+//! Text extraction from downloaded attachments
+//!
+//! `quaid index attachments` runs every downloaded file through [`extract`]
+//! and stores the text in the `attachment_text` index, so search can find
+//! what was said in a PDF or a pasted source file. The file type comes from
+//! the MIME type, falling back to the extension: PDFs go through lopdf, text
+//! and source files are read as UTF-8, and images are indexed by filename.
+//! Other types (video, audio, archives) and files over [`MAX_EXTRACT_BYTES`]
+//! are skipped without being read.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Largest file read for extraction
+pub const MAX_EXTRACT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Text kept per attachment; the rest of a long document is dropped
+pub const MAX_TEXT_BYTES: usize = 1024 * 1024;
+
+/// Types outside `text/*` that are read as text
+const TEXT_MIME_TYPES: [&str; 9] = [
+    "application/json",
+    "application/xml",
+    "application/javascript",
+    "application/x-javascript",
+    "application/typescript",
+    "application/x-sh",
+    "application/x-yaml",
+    "application/yaml",
+    "application/toml",
+];
+
+/// Extensions read as text when the MIME type says nothing useful
+const TEXT_EXTENSIONS: [&str; 36] = [
+    "txt", "md", "markdown", "rst", "csv", "tsv", "log", "json", "jsonl", "yaml", "yml", "toml",
+    "xml", "html", "css", "ini", "cfg", "conf", "sql", "sh", "rs", "py", "rb", "go", "java", "kt",
+    "swift", "c", "h", "cpp", "hpp", "cs", "js", "jsx", "ts", "tsx",
+];
+
+/// What extraction found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Extraction {
+    Text(String),
+    Skipped(SkipReason),
+}
+
+/// Why a file has no extracted text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Larger than [`MAX_EXTRACT_BYTES`]
+    TooLarge,
+    /// Video, audio, archives and other types without text
+    Binary,
+    /// A PDF that lopdf can't parse, e.g. an encrypted one
+    Unreadable,
+    /// Nothing but whitespace
+    Empty,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TooLarge => "too large",
+            Self::Binary => "binary",
+            Self::Unreadable => "unreadable",
+            Self::Empty => "empty",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Pdf,
+    Text,
+    Image,
+    Binary,
+}
+
+fn kind(mime_type: &str, filename: &str) -> Kind {
+    let mime = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let extension = Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if mime == "application/pdf" || extension == "pdf" {
+        Kind::Pdf
+    } else if mime.starts_with("image/") {
+        Kind::Image
+    } else if mime.starts_with("text/")
+        || TEXT_MIME_TYPES.contains(&mime.as_str())
+        || TEXT_EXTENSIONS.contains(&extension.as_str())
+    {
+        Kind::Text
+    } else {
+        Kind::Binary
+    }
+}
+
+/// Text of the attachment at `path`
+///
+/// Only IO errors are errors; files that hold no usable text are
+/// [`Extraction::Skipped`] so they aren't tried again.
+pub fn extract(path: &Path, mime_type: &str, filename: &str) -> io::Result<Extraction> {
+    let kind = kind(mime_type, filename);
+    match kind {
+        Kind::Binary => return Ok(Extraction::Skipped(SkipReason::Binary)),
+        Kind::Image => return Ok(non_empty(filename_words(filename))),
+        Kind::Pdf | Kind::Text => {}
+    }
+    if std::fs::metadata(path)?.len() > MAX_EXTRACT_BYTES {
+        return Ok(Extraction::Skipped(SkipReason::TooLarge));
+    }
+
+    if kind == Kind::Pdf {
+        return Ok(match pdf_text(path) {
+            Some(text) => non_empty(truncate(text)),
+            None => Extraction::Skipped(SkipReason::Unreadable),
+        });
+    }
+
+    let mut bytes = Vec::new();
+    File::open(path)?
+        .take(MAX_TEXT_BYTES as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.contains(&0) {
+        // A binary file with a text extension
+        return Ok(Extraction::Skipped(SkipReason::Binary));
+    }
+    Ok(non_empty(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+fn pdf_text(path: &Path) -> Option<String> {
+    let document = lopdf::Document::load(path).ok()?;
+    let pages: Vec<u32> = document.get_pages().keys().copied().collect();
+    document.extract_text(&pages).ok()
+}
+
+/// `architecture-diagram_v2.png` as `architecture diagram v2`
+fn filename_words(filename: &str) -> String {
+    let stem = Path::new(filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    stem.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn truncate(mut text: String) -> String {
+    if text.len() > MAX_TEXT_BYTES {
+        let mut end = MAX_TEXT_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+fn non_empty(text: String) -> Extraction {
+    let text = text.trim();
+    if text.is_empty() {
+        Extraction::Skipped(SkipReason::Empty)
+    } else {
+        Extraction::Text(text.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_extracts_pdf_and_text_fixtures() {
+        let pdf = extract(
+            &fixture("migration-plan.pdf"),
+            "application/pdf",
+            "migration-plan.pdf",
+        )
+        .unwrap();
+        match pdf {
+            Extraction::Text(text) => {
+                assert!(text.contains("kubernetes migration plan"), "{}", text);
+                assert!(text.contains("Move the ingress"), "{}", text);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // The extension is enough when the MIME type is generic
+        let txt = extract(
+            &fixture("deploy-notes.txt"),
+            "application/octet-stream",
+            "deploy-notes.txt",
+        )
+        .unwrap();
+        assert!(
+            matches!(txt, Extraction::Text(ref t) if t.contains("Roll the ingress controller"))
+        );
+    }
+
+    #[test]
+    fn test_skips_binary_large_and_broken_files() {
+        let dir = tempdir().unwrap();
+        let video = dir.path().join("demo.mp4");
+        std::fs::write(&video, b"\0\0\0 ftypisom").unwrap();
+        assert_eq!(
+            extract(&video, "video/mp4", "demo.mp4").unwrap(),
+            Extraction::Skipped(SkipReason::Binary)
+        );
+
+        // Sparse, so the size check is what stops it
+        let huge = dir.path().join("dump.log");
+        File::create(&huge)
+            .unwrap()
+            .set_len(MAX_EXTRACT_BYTES + 1)
+            .unwrap();
+        assert_eq!(
+            extract(&huge, "text/plain", "dump.log").unwrap(),
+            Extraction::Skipped(SkipReason::TooLarge)
+        );
+
+        let broken = dir.path().join("broken.pdf");
+        std::fs::write(&broken, b"%PDF-1.4 not really").unwrap();
+        assert_eq!(
+            extract(&broken, "application/pdf", "broken.pdf").unwrap(),
+            Extraction::Skipped(SkipReason::Unreadable)
+        );
+
+        let blank = dir.path().join("blank.md");
+        std::fs::write(&blank, b"  \n\n ").unwrap();
+        assert_eq!(
+            extract(&blank, "text/markdown", "blank.md").unwrap(),
+            Extraction::Skipped(SkipReason::Empty)
+        );
+    }
+
+    #[test]
+    fn test_images_are_indexed_by_filename() {
+        // Never read, so the file doesn't need to exist
+        let missing = Path::new("/nonexistent/architecture-diagram_v2.png");
+        assert_eq!(
+            extract(missing, "image/png", "architecture-diagram_v2.png").unwrap(),
+            Extraction::Text("architecture diagram v2".to_string())
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Literal and regex search over message text, without the FTS index
+//!
+//! FTS tokenizes punctuation away, so `Vec<Arc<Mutex<` or an exact error
+//! message can't be found with `quaid search`. A [`Matcher`] runs over each
+//! message's extracted text line by line, as the storage scans in
+//! [`crate::storage::scan`] stream them.
+
+use regex::{Regex, RegexBuilder};
+
+/// A compiled pattern
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    regex: Regex,
+}
+
+impl Matcher {
+    /// Match `pattern` literally, or as a regex with `is_regex`
+    pub fn new(pattern: &str, is_regex: bool, ignore_case: bool) -> Result<Self, regex::Error> {
+        let pattern = if is_regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()?;
+        Ok(Self { regex })
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        self.regex.is_match(line)
+    }
+
+    /// `line` with every match wrapped in `open` and `close`
+    pub fn highlight(&self, line: &str, open: &str, close: &str) -> String {
+        self.regex
+            .replace_all(line, |caps: &regex::Captures| {
+                format!("{}{}{}", open, &caps[0], close)
+            })
+            .into_owned()
+    }
+}
+
+/// A line of a hunk, numbered from 1 within its message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkLine {
+    pub number: usize,
+    pub text: String,
+    /// `false` for context lines
+    pub is_match: bool,
+}
+
+/// Matching lines and their context; overlapping contexts are merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    pub fn matches(&self) -> usize {
+        self.lines.iter().filter(|l| l.is_match).count()
+    }
+}
+
+/// Hunks of `text` holding its first `max_matches` matching lines, with up
+/// to `context` lines either side of each
+pub fn hunks(matcher: &Matcher, text: &str, context: usize, max_matches: usize) -> Vec<Hunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let matching: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| matcher.is_match(line))
+        .map(|(i, _)| i)
+        .take(max_matches)
+        .collect();
+
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut shown_until = 0; // lines before this index are already in a hunk
+    for &i in &matching {
+        let start = i.saturating_sub(context).max(shown_until);
+        let end = (i + context + 1).min(lines.len());
+        // A window that touches the previous one extends it
+        let extend = start <= shown_until;
+        let new_lines = (start..end)
+            .filter(|&n| n >= shown_until)
+            .map(|n| HunkLine {
+                number: n + 1,
+                text: lines[n].to_string(),
+                is_match: matching.contains(&n),
+            });
+        match hunks.last_mut() {
+            Some(hunk) if extend => hunk.lines.extend(new_lines),
+            _ => hunks.push(Hunk {
+                lines: new_lines.collect(),
+            }),
+        }
+        shown_until = end;
+    }
+    hunks
+}
+
+/// grep-style lines: `12: text` for matches, `11- text` for context, `--`
+/// between hunks. Matches are wrapped in `highlight` when given.
+pub fn render_hunks(hunks: &[Hunk], matcher: &Matcher, highlight: Option<(&str, &str)>) -> String {
+    let mut out = Vec::new();
+    for (i, hunk) in hunks.iter().enumerate() {
+        if i > 0 {
+            out.push("--".to_string());
+        }
+        for line in &hunk.lines {
+            let (separator, text) = match (line.is_match, highlight) {
+                (true, Some((open, close))) => (':', matcher.highlight(&line.text, open, close)),
+                (true, None) => (':', line.text.clone()),
+                (false, _) => ('-', line.text.clone()),
+            };
+            out.push(format!("{}{} {}", line.number, separator, text));
+        }
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_patterns_keep_punctuation() {
+        // Regex syntax that FTS would tokenize away and a regex would reject
+        let text = "let shared: Vec<Arc<Mutex<State>>> = Vec::new();\nlet v: Vec<u8>;";
+        let literal = Matcher::new("Vec<Arc<Mutex<", false, false).unwrap();
+        assert_eq!(hunks(&literal, text, 0, 10).len(), 1);
+
+        let error = Matcher::new("error[E0502]: cannot borrow `x`", false, false).unwrap();
+        assert!(error.is_match("error[E0502]: cannot borrow `x` as mutable"));
+        assert!(!error.is_match("error E0502 cannot borrow x"));
+
+        assert!(Matcher::new("Vec<Arc<(", true, false).is_err());
+    }
+
+    #[test]
+    fn test_regex_and_case() {
+        let regex = Matcher::new(r"E0\d{3}", true, false).unwrap();
+        assert!(regex.is_match("error[E0499]"));
+        assert!(!Matcher::new(r"E0\d{3}", false, false)
+            .unwrap()
+            .is_match("error[E0499]"));
+
+        assert!(!Matcher::new("tokio", false, false)
+            .unwrap()
+            .is_match("Tokio runtime"));
+        assert!(Matcher::new("tokio", false, true)
+            .unwrap()
+            .is_match("Tokio runtime"));
+    }
+
+    #[test]
+    fn test_context_hunks_merge_and_render() {
+        let text = (1..=12)
+            .map(|n| {
+                if [3, 5, 11].contains(&n) {
+                    format!("line {} panic", n)
+                } else {
+                    format!("line {}", n)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let matcher = Matcher::new("panic", false, false).unwrap();
+
+        let found = hunks(&matcher, &text, 1, 10);
+        assert_eq!(found.len(), 2);
+        let numbers: Vec<_> = found[0].lines.iter().map(|l| l.number).collect();
+        assert_eq!(numbers, vec![2, 3, 4, 5, 6]);
+        assert_eq!(found[0].matches(), 2);
+
+        assert_eq!(
+            render_hunks(&found, &matcher, None),
+            "2- line 2\n3: line 3 panic\n4- line 4\n5: line 5 panic\n6- line 6\n--\n\
+             10- line 10\n11: line 11 panic\n12- line 12"
+        );
+        assert_eq!(
+            render_hunks(&hunks(&matcher, &text, 0, 1), &matcher, Some(("[", "]"))),
+            "3: line 3 [panic]"
+        );
+    }
+}
@@ -1,10 +1,19 @@
+pub mod analysis;
 pub mod credentials;
+pub mod dates;
 pub mod embeddings;
+pub mod extract;
+pub mod grep;
+pub mod mcp;
+pub mod operations;
 pub mod pipeline;
 pub mod providers;
+pub mod sample;
 pub mod storage;
+pub mod titles;
+pub mod web;
 
-pub use credentials::{CredentialStore, KeyringStore, MockStore};
+pub use credentials::{CredentialStore, FileStore, KeyringStore, MockStore};
 pub use pipeline::{Pipeline, PipelineConfig, PipelineResult};
 pub use providers::Provider;
 pub use storage::{EmbeddingsCompactor, ParquetStorageConfig, Store};
@@ -0,0 +1,324 @@
+//! Model Context Protocol messages, as exchanged by `quaid mcp serve`
+//!
+//! MCP is JSON-RPC 2.0 with one message per line on stdin/stdout. Only the
+//! parts a tools-only server needs are modelled here: requests, responses,
+//! tool descriptions and tool results. [`page`] splits long tool output into
+//! pieces with a continuation cursor.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// Latest MCP revision implemented by the server
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Older revisions the server also speaks; they ignore `structuredContent`
+const OLDER_PROTOCOL_VERSIONS: [&str; 2] = ["2025-03-26", "2024-11-05"];
+
+/// Revision to answer `initialize` with: the client's if the server knows
+/// it, otherwise the latest, which the client may then decline
+pub fn negotiate_version(requested: Option<&str>) -> &'static str {
+    OLDER_PROTOCOL_VERSIONS
+        .into_iter()
+        .find(|&version| requested == Some(version))
+        .unwrap_or(PROTOCOL_VERSION)
+}
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// A request, or a notification when `id` is missing
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl Request {
+    /// Parse one line from the client
+    pub fn parse(line: &str) -> Result<Self, RpcError> {
+        let request: Self = serde_json::from_str(line)
+            .map_err(|e| RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)))?;
+        if request.jsonrpc != JSONRPC_VERSION {
+            return Err(RpcError::new(
+                INVALID_REQUEST,
+                format!("Unsupported jsonrpc version: {}", request.jsonrpc),
+            ));
+        }
+        Ok(request)
+    }
+
+    /// Notifications get no response, not even an error
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    /// `null` when the request couldn't be parsed far enough to read its id
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn error(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+}
+
+/// A tool as listed by `tools/list`
+#[derive(Debug, Clone, Serialize)]
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// JSON Schema of the tool's arguments
+    #[serde(rename = "inputSchema")]
+    pub input_schema: Value,
+}
+
+/// Parameters of `tools/call`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// Result of `tools/call`
+///
+/// A tool that runs but fails reports it here with `is_error`, so the model
+/// sees the message; JSON-RPC errors are for malformed calls. Successful
+/// results can carry the same data as JSON in `structured_content` for
+/// clients that use it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub content: Vec<TextContent>,
+    #[serde(
+        rename = "structuredContent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub structured_content: Option<Value>,
+    #[serde(rename = "isError", default, skip_serializing_if = "is_false")]
+    pub is_error: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextContent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub text: String,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl ToolResult {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: vec![TextContent {
+                kind: "text".to_string(),
+                text: text.into(),
+            }],
+            structured_content: None,
+            is_error: false,
+        }
+    }
+
+    /// `text` for the model, with `value` as its structured form
+    pub fn structured(text: impl Into<String>, value: Value) -> Self {
+        Self {
+            structured_content: Some(value),
+            ..Self::text(text)
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            is_error: true,
+            ..Self::text(message)
+        }
+    }
+}
+
+/// One piece of a long text, and where the next one starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<'a> {
+    pub text: &'a str,
+    /// Cursor for the next piece; `None` on the last one
+    pub next_cursor: Option<String>,
+}
+
+/// The piece of `text` starting at `cursor`, at most `max_bytes` long
+///
+/// Pieces end at a line break when there is one in their second half, so
+/// messages aren't cut mid-line. Cursors are opaque to clients; a cursor that
+/// doesn't point into `text` is an invalid-params error.
+pub fn page<'a>(
+    text: &'a str,
+    cursor: Option<&str>,
+    max_bytes: usize,
+) -> Result<Page<'a>, RpcError> {
+    let start = match cursor {
+        None => 0,
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .ok()
+            .filter(|&start| start < text.len() && text.is_char_boundary(start))
+            .ok_or_else(|| RpcError::invalid_params(format!("Invalid cursor: {}", cursor)))?,
+    };
+
+    let mut end = (start + max_bytes.max(1)).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == start {
+        // A single character wider than max_bytes
+        end = start + text[start..].chars().next().map_or(0, char::len_utf8);
+    }
+    if end < text.len() {
+        let half = start + (end - start) / 2;
+        if let Some(newline) = text[half..end].rfind('\n') {
+            end = half + newline + 1;
+        }
+    }
+
+    Ok(Page {
+        text: &text[start..end],
+        next_cursor: (end < text.len()).then(|| end.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_requests_and_notifications() {
+        let request = Request::parse(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).unwrap();
+        assert_eq!(request.id, Some(json!(1)));
+        assert!(!request.is_notification());
+
+        let notification =
+            Request::parse(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).unwrap();
+        assert!(notification.is_notification());
+
+        assert_eq!(Request::parse("not json").unwrap_err().code, PARSE_ERROR);
+        assert_eq!(
+            Request::parse(r#"{"jsonrpc":"1.0","id":1,"method":"ping"}"#)
+                .unwrap_err()
+                .code,
+            INVALID_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_response_serialization() {
+        let ok = Response::success(json!(7), json!({}));
+        assert_eq!(
+            serde_json::to_value(&ok).unwrap(),
+            json!({"jsonrpc": "2.0", "id": 7, "result": {}})
+        );
+
+        let err = Response::error(Value::Null, RpcError::new(METHOD_NOT_FOUND, "nope"));
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32601, "message": "nope"}})
+        );
+
+        assert_eq!(
+            serde_json::to_value(ToolResult::error("boom")).unwrap(),
+            json!({"content": [{"type": "text", "text": "boom"}], "isError": true})
+        );
+        assert_eq!(
+            serde_json::to_value(ToolResult::structured("1 result", json!({"total": 1}))).unwrap(),
+            json!({"content": [{"type": "text", "text": "1 result"}], "structuredContent": {"total": 1}})
+        );
+    }
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(Some("2024-11-05")), "2024-11-05");
+        assert_eq!(negotiate_version(Some("2025-06-18")), PROTOCOL_VERSION);
+        assert_eq!(negotiate_version(Some("2099-01-01")), PROTOCOL_VERSION);
+        assert_eq!(negotiate_version(None), PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_pages_reassemble_to_the_original() {
+        let text = "## You\n\nHow do I añadir an ingress?\n\n## Assistant\n\nCreate one. 🚀\n";
+        for max_bytes in [1, 5, 16, 40, text.len()] {
+            let mut cursor = None;
+            let mut joined = String::new();
+            loop {
+                let page = page(text, cursor.as_deref(), max_bytes).unwrap();
+                assert!(!page.text.is_empty());
+                joined.push_str(page.text);
+                match page.next_cursor {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+            assert_eq!(joined, text, "max_bytes = {}", max_bytes);
+        }
+    }
+
+    #[test]
+    fn test_pages_break_at_lines() {
+        let text = "first line\nsecond line\nthird line\n";
+        let first = page(text, None, 28).unwrap();
+        assert_eq!(first.text, "first line\nsecond line\n");
+        assert_eq!(first.next_cursor.as_deref(), Some("23"));
+
+        assert!(page(text, Some("999"), 28).is_err());
+        assert!(page(text, Some("abc"), 28).is_err());
+    }
+}
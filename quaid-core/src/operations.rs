@@ -0,0 +1,628 @@
+//! Long-running operations (pull, reindex, export) and their progress
+//!
+//! Work reports [`ProgressEvent`]s through the [`SyncProgress`] trait. The
+//! [`Operations`] registry keeps a snapshot of every operation and fans its
+//! events out to subscribers; an HTTP front end lists the snapshots and turns
+//! the event stream into server-sent events with [`ProgressEvent::to_sse`].
+//! Each operation also has a [`CancellationToken`] the registry holds, so
+//! [`Operations::cancel`] can stop it from anywhere.
+
+use crate::providers::{self, Conversation, Message, Provider};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OperationError {
+    #[error("A {0} is already running for {1}")]
+    AlreadyRunning(OperationKind, String),
+
+    #[error("Operation {0} not found")]
+    NotFound(u64),
+
+    #[error("Operation {0} has already ended")]
+    Ended(u64),
+}
+
+pub type Result<T> = std::result::Result<T, OperationError>;
+
+/// What an operation does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Pull,
+    Reindex,
+    Export,
+}
+
+impl std::fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            OperationKind::Pull => "pull",
+            OperationKind::Reindex => "reindex",
+            OperationKind::Export => "export",
+        })
+    }
+}
+
+/// Stage an operation is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Listing what needs to be done (e.g. remote conversations)
+    Listing,
+    /// Fetching items one by one
+    Fetching,
+    /// Writing, chunking and embedding what was fetched
+    Processing,
+}
+
+/// Counts reported when an operation finishes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OperationSummary {
+    pub synced: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// A progress update from a running operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    Phase { phase: Phase },
+    Progress { done: usize, total: usize },
+    Finished { summary: OperationSummary },
+    Failed { error: String },
+    Cancelled,
+}
+
+impl ProgressEvent {
+    /// Whether no more events follow this one
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Finished { .. } | Self::Failed { .. } | Self::Cancelled
+        )
+    }
+
+    /// Event name used in the SSE `event:` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Phase { .. } => "phase",
+            Self::Progress { .. } => "progress",
+            Self::Finished { .. } => "finished",
+            Self::Failed { .. } => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Format as a server-sent event
+    pub fn to_sse(&self) -> String {
+        let data = serde_json::to_string(self).unwrap_or_default();
+        format!("event: {}\ndata: {}\n\n", self.name(), data)
+    }
+}
+
+/// Receives progress from long-running work
+pub trait SyncProgress: Send + Sync {
+    fn report(&self, event: ProgressEvent);
+}
+
+/// Discards progress (for callers that don't track it)
+pub struct NoProgress;
+
+impl SyncProgress for NoProgress {
+    fn report(&self, _event: ProgressEvent) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Point-in-time view of an operation
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationSnapshot {
+    pub id: u64,
+    pub kind: OperationKind,
+    /// What the operation runs against, e.g. `claude:<account id>`
+    pub target: String,
+    pub status: OperationStatus,
+    pub phase: Option<Phase>,
+    pub done: usize,
+    pub total: usize,
+    /// Estimated seconds left, from the rate so far
+    pub eta_secs: Option<u64>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub summary: Option<OperationSummary>,
+    pub error: Option<String>,
+}
+
+impl OperationSnapshot {
+    fn apply(&mut self, event: &ProgressEvent, now: DateTime<Utc>) {
+        match event {
+            ProgressEvent::Phase { phase } => {
+                self.phase = Some(*phase);
+                self.done = 0;
+                self.total = 0;
+                self.eta_secs = None;
+            }
+            ProgressEvent::Progress { done, total } => {
+                self.done = *done;
+                self.total = *total;
+                let elapsed = (now - self.started_at).num_milliseconds().max(0) as u64;
+                self.eta_secs = (*done > 0 && total > done)
+                    .then(|| elapsed * (total - done) as u64 / *done as u64 / 1000);
+            }
+            ProgressEvent::Finished { summary } => {
+                self.status = OperationStatus::Completed;
+                self.summary = Some(summary.clone());
+                self.finished_at = Some(now);
+                self.eta_secs = None;
+            }
+            ProgressEvent::Failed { error } => {
+                self.status = OperationStatus::Failed;
+                self.error = Some(error.clone());
+                self.finished_at = Some(now);
+                self.eta_secs = None;
+            }
+            ProgressEvent::Cancelled => {
+                self.status = OperationStatus::Cancelled;
+                self.finished_at = Some(now);
+                self.eta_secs = None;
+            }
+        }
+    }
+}
+
+struct Entry {
+    snapshot: OperationSnapshot,
+    history: Vec<ProgressEvent>,
+    sender: broadcast::Sender<ProgressEvent>,
+    cancel: CancellationToken,
+}
+
+/// Registry of running and finished operations
+#[derive(Default)]
+pub struct Operations {
+    entries: Mutex<HashMap<u64, Entry>>,
+    next_id: AtomicU64,
+}
+
+impl Operations {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Register a new operation.
+    ///
+    /// Fails if an operation of the same kind is already running for `target`,
+    /// so there is at most one pull per account at a time.
+    pub fn start(self: &Arc<Self>, kind: OperationKind, target: &str) -> Result<OperationHandle> {
+        let mut handles = self.start_all(kind, &[target.to_string()])?;
+        Ok(handles.remove(0))
+    }
+
+    /// Register one operation per target, or none if any of them is taken
+    pub fn start_all(
+        self: &Arc<Self>,
+        kind: OperationKind,
+        targets: &[String],
+    ) -> Result<Vec<OperationHandle>> {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(target) = targets.iter().find(|target| {
+            entries.values().any(|e| {
+                e.snapshot.kind == kind
+                    && &e.snapshot.target == *target
+                    && e.snapshot.status == OperationStatus::Running
+            })
+        }) {
+            return Err(OperationError::AlreadyRunning(kind, target.clone()));
+        }
+
+        let mut handles = Vec::with_capacity(targets.len());
+        for target in targets {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+            let (sender, _) = broadcast::channel(256);
+            let cancel = CancellationToken::new();
+            entries.insert(
+                id,
+                Entry {
+                    snapshot: OperationSnapshot {
+                        id,
+                        kind,
+                        target: target.clone(),
+                        status: OperationStatus::Running,
+                        phase: None,
+                        done: 0,
+                        total: 0,
+                        eta_secs: None,
+                        started_at: Utc::now(),
+                        finished_at: None,
+                        summary: None,
+                        error: None,
+                    },
+                    history: Vec::new(),
+                    sender,
+                    cancel: cancel.clone(),
+                },
+            );
+            handles.push(OperationHandle {
+                id,
+                operations: Arc::clone(self),
+                cancel,
+            });
+        }
+        Ok(handles)
+    }
+
+    /// All operations, oldest first
+    pub fn list(&self) -> Vec<OperationSnapshot> {
+        let entries = self.entries.lock().unwrap();
+        let mut snapshots: Vec<_> = entries.values().map(|e| e.snapshot.clone()).collect();
+        snapshots.sort_by_key(|s| s.id);
+        snapshots
+    }
+
+    pub fn get(&self, id: u64) -> Result<OperationSnapshot> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&id)
+            .map(|e| e.snapshot.clone())
+            .ok_or(OperationError::NotFound(id))
+    }
+
+    /// Events emitted so far plus a receiver for the ones still to come.
+    ///
+    /// Both are taken under the same lock, so no event is missed or repeated.
+    pub fn subscribe(
+        &self,
+        id: u64,
+    ) -> Result<(Vec<ProgressEvent>, broadcast::Receiver<ProgressEvent>)> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(&id).ok_or(OperationError::NotFound(id))?;
+        Ok((entry.history.clone(), entry.sender.subscribe()))
+    }
+
+    /// Ask a running operation to stop
+    ///
+    /// It reports [`ProgressEvent::Cancelled`] once it has; work already in
+    /// flight, like a Parquet write, finishes first.
+    pub fn cancel(&self, id: u64) -> Result<()> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(&id).ok_or(OperationError::NotFound(id))?;
+        if entry.snapshot.status != OperationStatus::Running {
+            return Err(OperationError::Ended(id));
+        }
+        entry.cancel.cancel();
+        Ok(())
+    }
+
+    fn record(&self, id: u64, event: ProgressEvent) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get_mut(&id) {
+            if entry.snapshot.status != OperationStatus::Running {
+                return;
+            }
+            entry.snapshot.apply(&event, Utc::now());
+            entry.history.push(event.clone());
+            // No subscribers is fine
+            let _ = entry.sender.send(event);
+        }
+    }
+}
+
+/// Reports progress for one registered operation
+#[derive(Clone)]
+pub struct OperationHandle {
+    id: u64,
+    operations: Arc<Operations>,
+    cancel: CancellationToken,
+}
+
+impl OperationHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Cancelled by [`Operations::cancel`]; the work should stop when it is
+    pub fn cancel_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+}
+
+impl SyncProgress for OperationHandle {
+    fn report(&self, event: ProgressEvent) {
+        self.operations.record(self.id, event);
+    }
+}
+
+/// Fetch conversations and their messages from a provider, reporting progress.
+///
+/// Conversations for which `skip` returns true are not fetched. Failures on
+/// individual conversations are counted rather than aborting the pull.
+pub async fn fetch_conversations(
+    provider: &dyn Provider,
+    skip: impl Fn(&Conversation) -> bool,
+    progress: &dyn SyncProgress,
+) -> providers::Result<(Vec<(Conversation, Vec<Message>)>, OperationSummary)> {
+    progress.report(ProgressEvent::Phase {
+        phase: Phase::Listing,
+    });
+    let conversations = provider.conversations().await?;
+
+    progress.report(ProgressEvent::Phase {
+        phase: Phase::Fetching,
+    });
+    let total = conversations.len();
+    let mut summary = OperationSummary::default();
+    let mut fetched = Vec::new();
+
+    for (i, conv) in conversations.iter().enumerate() {
+        if skip(conv) {
+            summary.skipped += 1;
+        } else {
+            match provider.conversation(&conv.id).await {
+                Ok(result) => {
+                    summary.synced += 1;
+                    fetched.push(result);
+                }
+                Err(_) => summary.failed += 1,
+            }
+        }
+        progress.report(ProgressEvent::Progress { done: i + 1, total });
+    }
+
+    Ok((fetched, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Attachment, MessageContent, ProviderError, ProviderId, Role};
+    use async_trait::async_trait;
+
+    struct FakeProvider {
+        ids: Vec<&'static str>,
+    }
+
+    fn conversation(id: &str) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            provider_id: "claude".to_string(),
+            title: format!("Conversation {}", id),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    #[async_trait]
+    impl Provider for FakeProvider {
+        fn id(&self) -> ProviderId {
+            ProviderId::claude()
+        }
+
+        async fn is_authenticated(&self) -> bool {
+            true
+        }
+
+        async fn authenticate(&mut self) -> providers::Result<Account> {
+            self.account().await
+        }
+
+        async fn account(&self) -> providers::Result<Account> {
+            Ok(Account {
+                id: "acct-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+        }
+
+        async fn conversations(&self) -> providers::Result<Vec<Conversation>> {
+            Ok(self.ids.iter().map(|id| conversation(id)).collect())
+        }
+
+        async fn conversation(&self, id: &str) -> providers::Result<(Conversation, Vec<Message>)> {
+            if id == "broken" {
+                return Err(ProviderError::Api("500: boom".to_string()));
+            }
+            let message = Message {
+                id: format!("{}-msg", id),
+                conversation_id: id.to_string(),
+                parent_id: None,
+                role: Role::User,
+                content: MessageContent::Text {
+                    text: "hello".to_string(),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            };
+            Ok((conversation(id), vec![message]))
+        }
+
+        async fn project_conversations(&self, _: &str) -> providers::Result<Vec<Conversation>> {
+            Ok(vec![])
+        }
+
+        async fn download_attachment(
+            &self,
+            _: &Attachment,
+            _: &std::path::Path,
+        ) -> providers::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sse_format() {
+        let event = ProgressEvent::Progress { done: 2, total: 5 };
+        assert_eq!(
+            event.to_sse(),
+            "event: progress\ndata: {\"type\":\"progress\",\"done\":2,\"total\":5}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_one_running_pull_per_target() {
+        let ops = Operations::new();
+        let first = ops.start(OperationKind::Pull, "claude:acct-1").unwrap();
+
+        assert_eq!(
+            ops.start(OperationKind::Pull, "claude:acct-1").err(),
+            Some(OperationError::AlreadyRunning(
+                OperationKind::Pull,
+                "claude:acct-1".to_string()
+            ))
+        );
+        // Other accounts and other kinds are independent
+        assert!(ops.start(OperationKind::Pull, "claude:acct-2").is_ok());
+        assert!(ops.start(OperationKind::Export, "claude:acct-1").is_ok());
+
+        first.report(ProgressEvent::Finished {
+            summary: OperationSummary::default(),
+        });
+        assert!(ops.start(OperationKind::Pull, "claude:acct-1").is_ok());
+        assert_eq!(ops.list().len(), 4);
+
+        // Several targets are registered together or not at all
+        let targets = ["claude:acct-3".to_string(), "claude:acct-2".to_string()];
+        assert_eq!(
+            ops.start_all(OperationKind::Pull, &targets).err(),
+            Some(OperationError::AlreadyRunning(
+                OperationKind::Pull,
+                "claude:acct-2".to_string()
+            ))
+        );
+        assert!(ops.start(OperationKind::Pull, "claude:acct-3").is_ok());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let ops = Operations::new();
+        let handle = ops.start(OperationKind::Pull, "claude:acct-1").unwrap();
+        assert!(!handle.cancel_token().is_cancelled());
+
+        ops.cancel(handle.id()).unwrap();
+        assert!(handle.cancel_token().is_cancelled());
+        // Still running until the work notices
+        assert_eq!(
+            ops.get(handle.id()).unwrap().status,
+            OperationStatus::Running
+        );
+
+        handle.report(ProgressEvent::Cancelled);
+        let snapshot = ops.get(handle.id()).unwrap();
+        assert_eq!(snapshot.status, OperationStatus::Cancelled);
+        assert!(snapshot.finished_at.is_some());
+        assert_eq!(
+            ops.cancel(handle.id()),
+            Err(OperationError::Ended(handle.id()))
+        );
+        assert_eq!(ops.cancel(99), Err(OperationError::NotFound(99)));
+    }
+
+    #[test]
+    fn test_snapshot_tracks_progress() {
+        let ops = Operations::new();
+        let handle = ops.start(OperationKind::Reindex, "all").unwrap();
+
+        handle.report(ProgressEvent::Phase {
+            phase: Phase::Processing,
+        });
+        handle.report(ProgressEvent::Progress { done: 3, total: 10 });
+
+        let snapshot = ops.get(handle.id()).unwrap();
+        assert_eq!(snapshot.status, OperationStatus::Running);
+        assert_eq!(snapshot.phase, Some(Phase::Processing));
+        assert_eq!((snapshot.done, snapshot.total), (3, 10));
+        assert!(snapshot.eta_secs.is_some());
+
+        handle.report(ProgressEvent::Failed {
+            error: "disk full".to_string(),
+        });
+        let snapshot = ops.get(handle.id()).unwrap();
+        assert_eq!(snapshot.status, OperationStatus::Failed);
+        assert_eq!(snapshot.error.as_deref(), Some("disk full"));
+        assert!(snapshot.finished_at.is_some());
+
+        assert_eq!(ops.get(99).unwrap_err(), OperationError::NotFound(99));
+    }
+
+    #[tokio::test]
+    async fn test_pull_streams_phases_and_summary() {
+        let ops = Operations::new();
+        let handle = ops.start(OperationKind::Pull, "claude:acct-1").unwrap();
+        let (history, mut events) = ops.subscribe(handle.id()).unwrap();
+        assert!(history.is_empty());
+
+        let provider = FakeProvider {
+            ids: vec!["a", "skip-me", "broken", "b"],
+        };
+        let (fetched, summary) = fetch_conversations(&provider, |c| c.id == "skip-me", &handle)
+            .await
+            .unwrap();
+        handle.report(ProgressEvent::Phase {
+            phase: Phase::Processing,
+        });
+        handle.report(ProgressEvent::Finished {
+            summary: summary.clone(),
+        });
+
+        assert_eq!(fetched.len(), 2);
+        assert_eq!(
+            summary,
+            OperationSummary {
+                synced: 2,
+                skipped: 1,
+                failed: 1
+            }
+        );
+
+        let mut stream = Vec::new();
+        loop {
+            let event = events.recv().await.unwrap();
+            let terminal = event.is_terminal();
+            stream.push(event.to_sse());
+            if terminal {
+                break;
+            }
+        }
+
+        let names: Vec<_> = stream
+            .iter()
+            .map(|e| e.lines().next().unwrap().trim_start_matches("event: "))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "phase", "phase", "progress", "progress", "progress", "progress", "phase",
+                "finished"
+            ]
+        );
+        assert!(stream[1].contains("\"phase\":\"fetching\""));
+        assert!(stream[7].contains("\"synced\":2"));
+
+        // Late subscribers get the full history
+        let (history, _) = ops.subscribe(handle.id()).unwrap();
+        assert_eq!(history.len(), 8);
+        assert_eq!(ops.list()[0].status, OperationStatus::Completed);
+    }
+}
@@ -0,0 +1,266 @@
+//! Embedding backfill for conversations already in the local store
+//!
+//! Builds the semantic index for conversations that were synced before
+//! embeddings existed (or whose embeddings were lost), without pulling again.
+//! Conversations that already have embeddings are skipped, so an interrupted
+//! run picks up where it left off.
+
+use super::{Result, DEFAULT_EMBED_BATCH_SIZE};
+use crate::embeddings::{Chunk, ChunkerConfig, Embedder, MessageChunker};
+use crate::operations::{Phase, ProgressEvent, SyncProgress};
+use crate::providers::Conversation;
+use crate::storage::{EmbeddingsStore, Store};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Result of a backfill run
+#[derive(Debug, Default)]
+pub struct BackfillResult {
+    pub conversations_embedded: usize,
+    pub conversations_skipped: usize,
+    pub chunks_embedded: usize,
+}
+
+/// Embeds stored conversations that have no embeddings yet
+pub struct EmbeddingBackfill {
+    embeddings_store: EmbeddingsStore,
+    embedder: Arc<dyn Embedder>,
+    chunker: MessageChunker,
+    batch_size: usize,
+}
+
+impl EmbeddingBackfill {
+    pub fn new(embeddings_store: EmbeddingsStore, embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            embeddings_store,
+            embedder,
+            chunker: MessageChunker::new(ChunkerConfig::default()),
+            batch_size: DEFAULT_EMBED_BATCH_SIZE,
+        }
+    }
+
+    /// Split messages into chunks with `chunker` instead of the defaults
+    pub fn with_chunker(mut self, chunker: MessageChunker) -> Self {
+        self.chunker = chunker;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Embed every conversation in `store` that isn't indexed yet.
+    ///
+    /// Chunks from several conversations are embedded together so the model is
+    /// called once per batch rather than once per conversation.
+    pub fn run(&self, store: &Store, progress: &dyn SyncProgress) -> Result<BackfillResult> {
+        progress.report(ProgressEvent::Phase {
+            phase: Phase::Listing,
+        });
+
+        let mut conversations = Vec::new();
+        for account in store.list_accounts()? {
+            conversations.extend(store.list_conversations(&account.id)?);
+        }
+
+        let mut indexed: HashMap<String, HashSet<String>> = HashMap::new();
+        for conv in &conversations {
+            if !indexed.contains_key(&conv.provider_id) {
+                let ids = self
+                    .embeddings_store
+                    .indexed_conversation_ids(&conv.provider_id)?;
+                indexed.insert(conv.provider_id.clone(), ids);
+            }
+        }
+
+        progress.report(ProgressEvent::Phase {
+            phase: Phase::Processing,
+        });
+
+        let total = conversations.len();
+        let mut result = BackfillResult::default();
+        let mut pending: Vec<(Conversation, Vec<Chunk>)> = Vec::new();
+        let mut pending_chunks = 0;
+
+        for (i, conv) in conversations.into_iter().enumerate() {
+            if indexed[&conv.provider_id].contains(&conv.id) {
+                result.conversations_skipped += 1;
+            } else {
+                let chunks = self.chunker.chunk_messages(&store.get_messages(&conv.id)?);
+                if !chunks.is_empty() {
+                    pending_chunks += chunks.len();
+                    pending.push((conv, chunks));
+                }
+            }
+
+            if pending_chunks >= self.batch_size {
+                self.flush(&mut pending, &mut result)?;
+                pending_chunks = 0;
+            }
+            progress.report(ProgressEvent::Progress { done: i + 1, total });
+        }
+        self.flush(&mut pending, &mut result)?;
+
+        Ok(result)
+    }
+
+    /// Embed the pending chunks in one call and write them per conversation
+    fn flush(
+        &self,
+        pending: &mut Vec<(Conversation, Vec<Chunk>)>,
+        result: &mut BackfillResult,
+    ) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let texts: Vec<&str> = pending
+            .iter()
+            .flat_map(|(_, chunks)| chunks.iter().map(|c| c.text.as_str()))
+            .collect();
+        let mut embeddings = self.embedder.embed_batch(&texts)?.into_iter();
+
+        for (conv, chunks) in pending.drain(..) {
+            let vectors: Vec<Vec<f32>> = embeddings.by_ref().take(chunks.len()).collect();
+            self.embeddings_store.write_embeddings(
+                &conv.id,
+                &conv.provider_id,
+                &chunks,
+                &vectors,
+            )?;
+            result.conversations_embedded += 1;
+            result.chunks_embedded += chunks.len();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::MockEmbeddingModel;
+    use crate::operations::NoProgress;
+    use crate::providers::{Account, Message, MessageContent, ProviderId, Role};
+    use crate::storage::ParquetStorageConfig;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    /// Mock embedder that counts model calls
+    struct CountingEmbedder {
+        inner: MockEmbeddingModel,
+        calls: AtomicUsize,
+    }
+
+    impl Embedder for CountingEmbedder {
+        fn embedding_dim(&self) -> usize {
+            384
+        }
+
+        fn embed(&self, text: &str) -> crate::embeddings::Result<Vec<f32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.inner.embed(text))
+        }
+
+        fn embed_batch(&self, texts: &[&str]) -> crate::embeddings::Result<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.inner.embed_batch(texts))
+        }
+    }
+
+    fn populated_store(conversations: usize) -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+
+        for i in 0..conversations {
+            let conv_id = format!("conv-{}", i);
+            store
+                .save_conversation(
+                    "user-1",
+                    &Conversation {
+                        id: conv_id.clone(),
+                        provider_id: "claude".to_string(),
+                        title: format!("Conversation {}", i),
+                        created_at: chrono::Utc::now(),
+                        updated_at: chrono::Utc::now(),
+                        model: None,
+                        project_id: None,
+                        project_name: None,
+                        is_archived: false,
+                    },
+                )
+                .unwrap();
+            for j in 0..2 {
+                store
+                    .save_message(&Message {
+                        id: format!("{}-msg-{}", conv_id, j),
+                        conversation_id: conv_id.clone(),
+                        parent_id: None,
+                        role: Role::User,
+                        content: MessageContent::Text {
+                            text: format!("Message {} of conversation {}", j, i),
+                        },
+                        created_at: None,
+                        model: None,
+                        speaker: None,
+                    })
+                    .unwrap();
+            }
+        }
+        store
+    }
+
+    #[test]
+    fn test_backfill_batches_and_resumes() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let store = populated_store(5);
+        let embedder = Arc::new(CountingEmbedder {
+            inner: MockEmbeddingModel::new(384),
+            calls: AtomicUsize::new(0),
+        });
+
+        let backfill =
+            EmbeddingBackfill::new(EmbeddingsStore::new(config.clone()), embedder.clone())
+                .with_batch_size(4);
+        let result = backfill.run(&store, &NoProgress).unwrap();
+
+        assert_eq!(result.conversations_embedded, 5);
+        assert_eq!(result.chunks_embedded, 10);
+        // 10 chunks in batches of at least 4 chunks
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 3);
+        assert!(config.embeddings_path("claude", "conv-4").exists());
+
+        // A second run finds everything indexed
+        let result = backfill.run(&store, &NoProgress).unwrap();
+        assert_eq!(result.conversations_embedded, 0);
+        assert_eq!(result.conversations_skipped, 5);
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_backfill_only_embeds_missing_conversations() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let store = populated_store(3);
+        let embedder = Arc::new(MockEmbeddingModel::new(384));
+
+        let backfill = EmbeddingBackfill::new(EmbeddingsStore::new(config.clone()), embedder);
+        backfill.run(&store, &NoProgress).unwrap();
+        std::fs::remove_file(config.embeddings_path("claude", "conv-1")).unwrap();
+
+        let result = backfill.run(&store, &NoProgress).unwrap();
+        assert_eq!(result.conversations_embedded, 1);
+        assert_eq!(result.conversations_skipped, 2);
+        assert!(config.embeddings_path("claude", "conv-1").exists());
+    }
+}
@@ -1,20 +1,45 @@
 //! Pipeline configuration
 
+use crate::credentials::{default_store, CredentialStore};
+use crate::embeddings::{ChunkerConfig, EmbeddingBackend};
+use crate::storage::ParquetCompression;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Chunks sent to the embedder per call
+pub const DEFAULT_EMBED_BATCH_SIZE: usize = 64;
+
+/// Chunks per embedder call in low-memory mode
+pub const LOW_MEMORY_EMBED_BATCH_SIZE: usize = 8;
+
+/// Messages buffered between stages in low-memory mode
+pub const LOW_MEMORY_CHANNEL_CAPACITY: usize = 1;
 
 /// Configuration for the processing pipeline
 #[derive(Debug, Clone)]
 pub struct PipelineConfig {
     /// Base directory for data storage
     pub data_dir: PathBuf,
-    /// Number of fetch workers (Stage 1)
+    /// Conversations fetched concurrently (Stage 1), capped per provider
     pub fetch_workers: usize,
-    /// Number of media download workers (Stage 2)
+    /// Attachments downloaded at once (Stage 2), capped per provider
     pub media_workers: usize,
     /// Number of embed/persist workers (Stage 3)
     pub embed_workers: usize,
     /// Channel buffer capacity
     pub channel_capacity: usize,
+    /// Chunks embedded per embedder call (Stage 3)
+    pub embed_batch_size: usize,
+    /// Embedder used by the embed stage
+    pub embedding_backend: EmbeddingBackend,
+    /// Where a remote embedder's API key is read from
+    pub credentials: Arc<dyn CredentialStore>,
+    /// Embed and write one conversation at a time with a lean model session
+    pub low_memory: bool,
+    /// How messages are split into chunks before embedding
+    pub chunker: ChunkerConfig,
+    /// Codec conversation files are written with
+    pub compression: ParquetCompression,
 }
 
 impl PipelineConfig {
@@ -23,10 +48,17 @@ impl PipelineConfig {
         let cpus = num_cpus::get();
         Self {
             data_dir: data_dir.as_ref().to_path_buf(),
-            fetch_workers: cpus,
+            // Fetching is network-bound, so don't tie it to the core count
+            fetch_workers: cpus.max(4),
             media_workers: cpus / 2,
             embed_workers: cpus / 2,
             channel_capacity: 100,
+            embed_batch_size: DEFAULT_EMBED_BATCH_SIZE,
+            embedding_backend: EmbeddingBackend::default(),
+            credentials: default_store(),
+            low_memory: false,
+            chunker: ChunkerConfig::default(),
+            compression: ParquetCompression::default(),
         }
     }
 
@@ -43,9 +75,72 @@ impl PipelineConfig {
             media_workers: media.max(1),
             embed_workers: embed.max(1),
             channel_capacity: 100,
+            embed_batch_size: DEFAULT_EMBED_BATCH_SIZE,
+            embedding_backend: EmbeddingBackend::default(),
+            credentials: default_store(),
+            low_memory: false,
+            chunker: ChunkerConfig::default(),
+            compression: ParquetCompression::default(),
         }
     }
 
+    /// Fetch this many conversations at once (still capped per provider)
+    pub fn with_fetch_workers(mut self, fetch_workers: usize) -> Self {
+        self.fetch_workers = fetch_workers.max(1);
+        self
+    }
+
+    /// Download this many attachments at once (still capped per provider)
+    pub fn with_media_workers(mut self, media_workers: usize) -> Self {
+        self.media_workers = media_workers.max(1);
+        self
+    }
+
+    /// Use a different embedding backend
+    pub fn with_embedding_backend(mut self, backend: EmbeddingBackend) -> Self {
+        self.embedding_backend = backend;
+        self
+    }
+
+    /// Read a remote embedder's API key from `credentials`
+    pub fn with_credentials(mut self, credentials: Arc<dyn CredentialStore>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Embed this many chunks per embedder call
+    pub fn with_embed_batch_size(mut self, batch_size: usize) -> Self {
+        self.embed_batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Split messages into chunks with `chunker` before embedding
+    pub fn with_chunker(mut self, chunker: ChunkerConfig) -> Self {
+        self.chunker = chunker;
+        self
+    }
+
+    /// Write conversation files with `compression`
+    pub fn with_compression(mut self, compression: ParquetCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Keep peak memory low at the cost of throughput
+    ///
+    /// One worker per stage, single-message channels, small embedder batches
+    /// and a single-threaded model session without a memory arena. The embed
+    /// stage takes one conversation at a time and writes it before the next.
+    pub fn with_low_memory(mut self) -> Self {
+        self.fetch_workers = 1;
+        self.media_workers = 1;
+        self.embed_workers = 1;
+        self.channel_capacity = LOW_MEMORY_CHANNEL_CAPACITY;
+        self.embed_batch_size = LOW_MEMORY_EMBED_BATCH_SIZE;
+        self.low_memory = true;
+        self
+    }
+
     /// Get models directory
     pub fn models_dir(&self) -> PathBuf {
         self.data_dir.join("models")
@@ -104,6 +199,30 @@ mod tests {
         assert_eq!(config.embed_workers, 1);
     }
 
+    #[test]
+    fn test_config_media_workers() {
+        let config = PipelineConfig::new("/tmp/test").with_media_workers(6);
+        assert_eq!(config.media_workers, 6);
+        assert_eq!(config.with_media_workers(0).media_workers, 1);
+    }
+
+    #[test]
+    fn test_config_low_memory() {
+        let config = PipelineConfig::new("/tmp/test");
+        assert!(!config.low_memory);
+
+        let config = config
+            .with_fetch_workers(8)
+            .with_media_workers(8)
+            .with_low_memory();
+        assert!(config.low_memory);
+        assert_eq!(config.fetch_workers, 1);
+        assert_eq!(config.media_workers, 1);
+        assert_eq!(config.embed_workers, 1);
+        assert_eq!(config.channel_capacity, LOW_MEMORY_CHANNEL_CAPACITY);
+        assert_eq!(config.embed_batch_size, LOW_MEMORY_EMBED_BATCH_SIZE);
+    }
+
     #[test]
     fn test_config_directories() {
         let config = PipelineConfig::new("/data/quaid");
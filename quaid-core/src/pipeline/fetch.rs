@@ -0,0 +1,273 @@
+//! Stage 1: concurrent fetch of full conversations
+//!
+//! Provider calls are async, so this stage runs directly on tokio rather than
+//! as a pipeline worker. A semaphore bounds the number of requests in flight and
+//! a token bucket shared by all of them bounds the request rate; the fetched
+//! conversations are then handed to [`Pipeline::run`](super::Pipeline::run).
+
+use crate::providers::{self, Conversation, ProviderId};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// Average spacing between requests across all workers, to stay friendly to the API
+pub const DEFAULT_REQUEST_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How hard to hit a provider while fetching
+#[derive(Debug, Clone)]
+pub struct FetchConfig {
+    /// Maximum requests in flight
+    pub concurrency: usize,
+    /// Average spacing between requests across all workers; up to
+    /// `concurrency` requests may start back to back after an idle spell
+    pub request_interval: Duration,
+    /// Stops the fetch early, keeping what has already come back
+    pub cancel: CancellationToken,
+}
+
+impl FetchConfig {
+    /// `fetch_workers` concurrent requests, capped at what the provider tolerates
+    pub fn for_provider(provider: &ProviderId, fetch_workers: usize) -> Self {
+        Self {
+            concurrency: fetch_workers.min(provider.max_concurrent_requests()).max(1),
+            request_interval: DEFAULT_REQUEST_INTERVAL,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+}
+
+/// Fetch every conversation with `fetch`, at most `config.concurrency` at a time.
+///
+/// Results come back in completion order, each paired with the listing entry
+/// it was fetched for. `on_fetched(done, total, conversation)` is called as
+/// each one finishes. Once `config.cancel` fires, requests still in flight are
+/// dropped and only the finished ones are returned.
+pub async fn fetch_all<T, F, Fut>(
+    conversations: Vec<Conversation>,
+    config: &FetchConfig,
+    fetch: F,
+    mut on_fetched: impl FnMut(usize, usize, &Conversation),
+) -> Vec<(Conversation, providers::Result<T>)>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = providers::Result<T>>,
+{
+    let semaphore = Semaphore::new(config.concurrency.max(1));
+    let bucket = TokenBucket::new(config.concurrency.max(1), config.request_interval);
+    let total = conversations.len();
+
+    let mut in_flight: FuturesUnordered<_> = conversations
+        .into_iter()
+        .map(|conv| {
+            let semaphore = &semaphore;
+            let bucket = &bucket;
+            let fetch = &fetch;
+            async move {
+                let _permit = semaphore
+                    .acquire()
+                    .await
+                    .expect("semaphore is never closed");
+                bucket.take().await;
+                let result = fetch(conv.id.clone()).await;
+                (conv, result)
+            }
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(total);
+    loop {
+        let next = tokio::select! {
+            biased;
+            _ = config.cancel.cancelled() => break,
+            next = in_flight.next() => next,
+        };
+        let Some((conv, result)) = next else { break };
+        on_fetched(results.len() + 1, total, &conv);
+        results.push((conv, result));
+    }
+    results
+}
+
+/// Rate limiter shared by every fetch worker
+///
+/// Holds up to `capacity` tokens and gains one every `interval`; each request
+/// takes a token, waiting for the next one when the bucket is empty.
+struct TokenBucket {
+    capacity: f64,
+    interval: Duration,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(capacity: usize, interval: Duration) -> Self {
+        Self {
+            capacity: capacity as f64,
+            interval,
+            state: Mutex::new((capacity as f64, Instant::now())),
+        }
+    }
+
+    async fn take(&self) {
+        if self.interval.is_zero() {
+            return;
+        }
+        // Holding the lock while waiting queues workers in arrival order
+        let mut state = self.state.lock().await;
+        let (tokens, refilled_at) = &mut *state;
+        let now = Instant::now();
+        *tokens = (*tokens
+            + now.duration_since(*refilled_at).as_secs_f64() / self.interval.as_secs_f64())
+        .min(self.capacity);
+        *refilled_at = now;
+
+        if *tokens < 1.0 {
+            let wait = self.interval.mul_f64(1.0 - *tokens);
+            tokio::time::sleep(wait).await;
+            *tokens = 1.0;
+            *refilled_at = now + wait;
+        }
+        *tokens -= 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ProviderError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn conversation(id: usize) -> Conversation {
+        Conversation {
+            id: format!("conv-{}", id),
+            provider_id: "claude".to_string(),
+            title: format!("Conversation {}", id),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    #[test]
+    fn test_fetch_config_respects_provider_cap() {
+        let capped = FetchConfig::for_provider(&ProviderId::claude(), 64);
+        assert_eq!(
+            capped.concurrency,
+            ProviderId::claude().max_concurrent_requests()
+        );
+
+        let configured = FetchConfig::for_provider(&ProviderId::claude(), 2);
+        assert_eq!(configured.concurrency, 2);
+
+        assert_eq!(
+            FetchConfig::for_provider(&ProviderId::claude(), 0).concurrency,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_bounds_concurrency() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let config = FetchConfig {
+            concurrency: 3,
+            request_interval: Duration::ZERO,
+            cancel: CancellationToken::new(),
+        };
+        let mut progress = Vec::new();
+
+        let results = fetch_all(
+            (0..10).map(conversation).collect(),
+            &config,
+            |id| {
+                let (in_flight, peak) = (&in_flight, &peak);
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    if id == "conv-7" {
+                        Err(ProviderError::Api("404: not found".to_string()))
+                    } else {
+                        Ok(id)
+                    }
+                }
+            },
+            |done, total, _| progress.push((done, total)),
+        )
+        .await;
+
+        assert_eq!(results.len(), 10);
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert_eq!(results.iter().filter(|(_, r)| r.is_err()).count(), 1);
+        for (conv, result) in &results {
+            if let Ok(id) = result {
+                assert_eq!(&conv.id, id);
+            }
+        }
+        assert_eq!(progress.last(), Some(&(10, 10)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fetch_all_rate_limits_across_workers() {
+        let config = FetchConfig {
+            concurrency: 4,
+            request_interval: Duration::from_secs(1),
+            cancel: CancellationToken::new(),
+        };
+        let start = Instant::now();
+        let started = std::sync::Mutex::new(Vec::new());
+
+        fetch_all(
+            (0..10).map(conversation).collect(),
+            &config,
+            |id| {
+                started.lock().unwrap().push(start.elapsed());
+                async move { Ok::<_, ProviderError>(id) }
+            },
+            |_, _, _| {},
+        )
+        .await;
+
+        // A burst of `concurrency`, then one request per interval
+        let started = started.into_inner().unwrap();
+        assert_eq!(started.len(), 10);
+        assert!(started[..4].iter().all(|t| *t < Duration::from_secs(1)));
+        assert!(started[9] >= Duration::from_secs(6));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_keeps_finished_results_when_cancelled() {
+        let config = FetchConfig {
+            concurrency: 1,
+            request_interval: Duration::ZERO,
+            cancel: CancellationToken::new(),
+        };
+        let cancel = config.cancel.clone();
+
+        let results = fetch_all(
+            (0..10).map(conversation).collect(),
+            &config,
+            |id| async move { Ok::<_, ProviderError>(id) },
+            |done, _, _| {
+                if done == 3 {
+                    cancel.cancel();
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+    }
+}
@@ -76,6 +76,7 @@ mod tests {
             },
             created_at: None,
             model: None,
+            speaker: None,
         }
     }
 
@@ -150,5 +151,6 @@ mod tests {
         fn assert_send_sync<T: Send + Sync>() {}
         // This would fail to compile if PipelineMessage isn't Send + Sync
         // But since our types are Clone and don't have any !Send components, it should work
+        assert_send_sync::<PipelineMessage>();
     }
 }
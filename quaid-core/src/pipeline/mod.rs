@@ -1,25 +1,38 @@
 //! Parallel processing pipeline for conversation sync
 //!
-//! Three-stage pipeline with crossbeam channels:
-//! 1. Fetch Stage - retrieve conversations from providers
+//! Three-stage pipeline of tokio tasks connected by bounded channels:
+//! 1. Fetch Stage - retrieve conversations from providers (concurrently, see [`fetch`])
 //! 2. Media Stage - download attachments
 //! 3. Embed Stage - chunk, embed, and persist
+//!
+//! A run can be cancelled through a [`CancellationToken`]. Conversations
+//! already written stay written; the rest are dropped and not counted.
 
+pub mod backfill;
 pub mod config;
+pub mod fetch;
 pub mod messages;
 pub mod stages;
 
-pub use config::PipelineConfig;
+pub use backfill::{BackfillResult, EmbeddingBackfill};
+pub use config::{
+    PipelineConfig, DEFAULT_EMBED_BATCH_SIZE, LOW_MEMORY_CHANNEL_CAPACITY,
+    LOW_MEMORY_EMBED_BATCH_SIZE,
+};
+pub use fetch::{fetch_all, FetchConfig};
 pub use messages::PipelineMessage;
+pub use tokio_util::sync::CancellationToken;
 
-use crate::embeddings::{ChunkerConfig, Embedder, EmbeddingModel, MessageChunker};
+use crate::embeddings::EmbeddingError;
 use crate::providers::{Conversation, Message};
 use crate::storage::parquet::ParquetStore;
 use crate::storage::{EmbeddingsStore, ParquetStorageConfig};
-use crossbeam_channel::bounded;
+use stages::EmbedResources;
 use std::sync::Arc;
-use std::thread::{self, JoinHandle};
 use thiserror::Error;
+use tokio::sync::mpsc::channel;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
 
 #[derive(Error, Debug)]
 pub enum PipelineError {
@@ -38,8 +51,8 @@ pub enum PipelineError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("Thread join error")]
-    ThreadJoin,
+    #[error("Pipeline task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
 }
 
 pub type Result<T> = std::result::Result<T, PipelineError>;
@@ -52,6 +65,8 @@ pub struct PipelineResult {
     pub attachments_downloaded: usize,
     pub embeddings_generated: usize,
     pub errors: Vec<String>,
+    /// The run was cancelled before every conversation was processed
+    pub cancelled: bool,
 }
 
 /// The main pipeline orchestrator
@@ -65,9 +80,13 @@ impl Pipeline {
     }
 
     /// Run the pipeline with a list of conversations to process
-    pub fn run(
+    ///
+    /// Cancelling `cancel` stops the run early; the result then only counts
+    /// conversations that were fully written.
+    pub async fn run(
         &self,
         conversations: Vec<(String, Conversation, Vec<Message>)>, // (account_id, conv, messages)
+        cancel: &CancellationToken,
     ) -> Result<PipelineResult> {
         let mut result = PipelineResult::default();
 
@@ -76,73 +95,93 @@ impl Pipeline {
         }
 
         // Create channels between stages
-        let (fetch_tx, fetch_rx) = bounded::<PipelineMessage>(self.config.channel_capacity);
-        let (media_tx, media_rx) = bounded::<PipelineMessage>(self.config.channel_capacity);
-        let (embed_tx, embed_rx) = bounded::<PipelineMessage>(self.config.channel_capacity);
+        let (fetch_tx, fetch_rx) = channel::<PipelineMessage>(self.config.channel_capacity);
+        let (media_tx, media_rx) = channel::<PipelineMessage>(self.config.channel_capacity);
+        let (embed_tx, mut embed_rx) = channel::<PipelineMessage>(self.config.channel_capacity);
 
         // Shared resources
-        let storage_config = ParquetStorageConfig::new(&self.config.data_dir);
-        let parquet_store = Arc::new(ParquetStore::new(storage_config.clone()));
-        let embeddings_store = Arc::new(EmbeddingsStore::new(storage_config.clone()));
-        let embedder: Arc<dyn Embedder> = Arc::new(
-            EmbeddingModel::load_or_download(self.config.data_dir.join("models"))?,
-        );
-        let chunker = Arc::new(MessageChunker::new(ChunkerConfig::default()));
+        let storage_config = ParquetStorageConfig::new(&self.config.data_dir)
+            .with_compression(self.config.compression);
+        let backend = self.config.embedding_backend.clone();
+        let data_dir = self.config.data_dir.clone();
+        let low_memory = self.config.low_memory;
+        let chunker = self.config.chunker.clone();
+        let credentials = self.config.credentials.clone();
+        let (embedder, chunker) = tokio::task::spawn_blocking(move || {
+            let embedder = if low_memory {
+                backend.load_low_memory(&data_dir, &credentials)?
+            } else {
+                backend.load(&data_dir, &credentials)?
+            };
+            let chunker = backend.chunker(chunker, &data_dir)?;
+            Ok::<_, EmbeddingError>((embedder, chunker))
+        })
+        .await??;
+        let metadata = self.config.embedding_backend.metadata(embedder.as_ref());
+        let embeddings_store = Arc::new(EmbeddingsStore::with_dimension(
+            storage_config.clone(),
+            metadata.dimension,
+        ));
+        embeddings_store.ensure_metadata(&metadata)?;
+        let resources = EmbedResources {
+            store: Arc::new(ParquetStore::new(storage_config.clone())),
+            embeddings_store,
+            embedder,
+            chunker: Arc::new(chunker),
+        };
 
         // Spawn stage workers
-        let mut handles: Vec<JoinHandle<Result<()>>> = Vec::new();
+        let mut tasks: JoinSet<Result<()>> = JoinSet::new();
 
-        // Stage 1: Feed conversations (single thread since we already have the data)
-        // Move fetch_tx into the feeder thread (not clone)
-        let convos = conversations;
-        handles.push(thread::spawn(move || {
-            for (account_id, conv, messages) in convos {
+        // Stage 1: Feed conversations (we already have the data)
+        let total = conversations.len();
+        let feed_cancel = cancel.clone();
+        tasks.spawn(async move {
+            for (account_id, conv, messages) in conversations {
                 let msg = PipelineMessage::ConversationFetched {
                     account_id,
                     conversation: conv,
                     messages,
                 };
-                if fetch_tx.send(msg).is_err() {
-                    break;
+                tokio::select! {
+                    biased;
+                    _ = feed_cancel.cancelled() => break,
+                    sent = fetch_tx.send(msg) => if sent.is_err() { break },
                 }
             }
             // fetch_tx dropped here, closing the channel
             Ok(())
-        }));
+        });
 
         // Stage 2: Media download workers
+        let fetch_rx = Arc::new(Mutex::new(fetch_rx));
         for _ in 0..self.config.media_workers {
-            let rx = fetch_rx.clone();
-            let tx = media_tx.clone();
-            let storage = storage_config.clone();
-
-            handles.push(thread::spawn(move || {
-                stages::media_worker(rx, tx, storage)
-            }));
+            tasks.spawn(stages::media_worker(
+                fetch_rx.clone(),
+                media_tx.clone(),
+                storage_config.clone(),
+                cancel.clone(),
+            ));
         }
-        // Drop our copies - workers have their own clones
-        drop(fetch_rx);
+        // Drop our copy so the channel closes when the workers finish
         drop(media_tx);
 
         // Stage 3: Embed and persist workers
+        let media_rx = Arc::new(Mutex::new(media_rx));
         for _ in 0..self.config.embed_workers {
-            let rx = media_rx.clone();
-            let tx = embed_tx.clone();
-            let store = parquet_store.clone();
-            let emb_store = embeddings_store.clone();
-            let emb = embedder.clone();
-            let chunk = chunker.clone();
-
-            handles.push(thread::spawn(move || {
-                stages::embed_worker(rx, tx, store, emb_store, emb, chunk)
-            }));
+            tasks.spawn(stages::embed_worker(
+                media_rx.clone(),
+                embed_tx.clone(),
+                resources.clone(),
+                self.config.embed_batch_size,
+                !self.config.low_memory,
+                cancel.clone(),
+            ));
         }
-        // Drop our copies
-        drop(media_rx);
         drop(embed_tx);
 
-        // Collect results
-        for msg in embed_rx {
+        // Collect results until every embed worker has hung up
+        while let Some(msg) = embed_rx.recv().await {
             match msg {
                 PipelineMessage::Complete {
                     conversation_id: _,
@@ -161,10 +200,12 @@ impl Pipeline {
         }
 
         // Wait for all workers to finish
-        for handle in handles {
-            handle.join().map_err(|_| PipelineError::ThreadJoin)??;
+        while let Some(joined) = tasks.join_next().await {
+            joined??;
         }
 
+        result.cancelled =
+            cancel.is_cancelled() && result.conversations_synced + result.errors.len() < total;
         Ok(result)
     }
 }
@@ -172,9 +213,20 @@ impl Pipeline {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embeddings::EmbeddingModelConfig;
     use crate::providers::{MessageContent, Role};
     use tempfile::tempdir;
 
+    /// Config whose model files are already in place, so no download is attempted
+    fn create_test_config(dir: &std::path::Path) -> PipelineConfig {
+        let config = PipelineConfig::new(dir);
+        let model = EmbeddingModelConfig::multilingual_e5_small(config.models_dir());
+        std::fs::create_dir_all(config.models_dir()).unwrap();
+        std::fs::write(&model.model_path, b"").unwrap();
+        std::fs::write(&model.tokenizer_path, b"{}").unwrap();
+        config
+    }
+
     fn create_test_conversation(id: &str) -> Conversation {
         Conversation {
             id: id.to_string(),
@@ -200,25 +252,29 @@ mod tests {
             },
             created_at: Some(chrono::Utc::now()),
             model: None,
+            speaker: None,
         }
     }
 
-    #[test]
-    fn test_pipeline_empty() {
+    #[tokio::test]
+    async fn test_pipeline_empty() {
         let dir = tempdir().unwrap();
         let config = PipelineConfig::new(dir.path());
         let pipeline = Pipeline::new(config);
 
-        let result = pipeline.run(vec![]).unwrap();
+        let result = pipeline
+            .run(vec![], &CancellationToken::new())
+            .await
+            .unwrap();
 
         assert_eq!(result.conversations_synced, 0);
         assert!(result.errors.is_empty());
     }
 
-    #[test]
-    fn test_pipeline_single_conversation() {
+    #[tokio::test]
+    async fn test_pipeline_single_conversation() {
         let dir = tempdir().unwrap();
-        let config = PipelineConfig::new(dir.path());
+        let config = create_test_config(dir.path());
         let pipeline = Pipeline::new(config);
 
         let conv = create_test_conversation("conv-1");
@@ -228,7 +284,11 @@ mod tests {
         ];
 
         let result = pipeline
-            .run(vec![("user-123".to_string(), conv, messages)])
+            .run(
+                vec![("user-123".to_string(), conv, messages)],
+                &CancellationToken::new(),
+            )
+            .await
             .unwrap();
 
         assert_eq!(result.conversations_synced, 1);
@@ -240,13 +300,8 @@ mod tests {
         assert!(parquet_path.exists());
     }
 
-    #[test]
-    fn test_pipeline_multiple_conversations() {
-        let dir = tempdir().unwrap();
-        let config = PipelineConfig::new(dir.path());
-        let pipeline = Pipeline::new(config);
-
-        let convos: Vec<_> = (0..5)
+    fn create_test_batch(count: usize) -> Vec<(String, Conversation, Vec<Message>)> {
+        (0..count)
             .map(|i| {
                 let id = format!("conv-{}", i);
                 let conv = create_test_conversation(&id);
@@ -256,12 +311,96 @@ mod tests {
                 ];
                 ("user-123".to_string(), conv, messages)
             })
-            .collect();
+            .collect()
+    }
 
-        let result = pipeline.run(convos).unwrap();
+    #[tokio::test]
+    async fn test_pipeline_multiple_conversations() {
+        let dir = tempdir().unwrap();
+        let config = create_test_config(dir.path());
+        let pipeline = Pipeline::new(config);
+
+        let result = pipeline
+            .run(create_test_batch(5), &CancellationToken::new())
+            .await
+            .unwrap();
 
         assert_eq!(result.conversations_synced, 5);
         assert_eq!(result.messages_processed, 10);
+        assert!(!result.cancelled);
+
+        // Writes go through temp files that are renamed into place
+        let leftovers = std::fs::read_dir(dir.path().join("conversations/chatgpt"))
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension().unwrap() != "parquet")
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_cancelled_before_start() {
+        let dir = tempdir().unwrap();
+        let config = create_test_config(dir.path());
+        let pipeline = Pipeline::new(config);
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = pipeline.run(create_test_batch(5), &cancel).await.unwrap();
+
+        assert!(result.cancelled);
+        assert_eq!(result.conversations_synced, 0);
+        assert_eq!(result.messages_processed, 0);
+        assert!(!dir.path().join("conversations/chatgpt").exists());
+    }
+
+    /// Every file the pipeline wrote under `dir`, except the model files
+    fn written_files(dir: &std::path::Path) -> std::collections::BTreeMap<String, Vec<u8>> {
+        let mut files = std::collections::BTreeMap::new();
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(current) = dirs.pop() {
+            for entry in std::fs::read_dir(&current).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    if !path.ends_with("models") {
+                        dirs.push(path);
+                    }
+                } else {
+                    let name = path.strip_prefix(dir).unwrap().display().to_string();
+                    files.insert(name, std::fs::read(&path).unwrap());
+                }
+            }
+        }
+        files
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_low_memory_writes_the_same_files() {
+        let mut batch = create_test_batch(4);
+        // Enough chunks that the normal run batches across conversations
+        batch[1].2 = (0..20)
+            .map(|i| create_test_message("conv-1", &format!("msg-1-{}", i), "Longer message"))
+            .collect();
+
+        let normal_dir = tempdir().unwrap();
+        let normal = Pipeline::new(create_test_config(normal_dir.path()))
+            .run(batch.clone(), &CancellationToken::new())
+            .await
+            .unwrap();
+
+        let low_memory_dir = tempdir().unwrap();
+        let low_memory = Pipeline::new(create_test_config(low_memory_dir.path()).with_low_memory())
+            .run(batch, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(low_memory.conversations_synced, 4);
+        assert_eq!(low_memory.messages_processed, normal.messages_processed);
+        assert_eq!(low_memory.embeddings_generated, normal.embeddings_generated);
+        assert!(low_memory.errors.is_empty());
+
+        let written = written_files(low_memory_dir.path());
+        assert!(written.keys().any(|name| name.starts_with("embeddings")));
+        assert_eq!(written, written_files(normal_dir.path()));
     }
 
     #[test]
@@ -272,6 +411,12 @@ mod tests {
             media_workers: 2,
             embed_workers: 2,
             channel_capacity: 50,
+            embed_batch_size: 16,
+            embedding_backend: Default::default(),
+            low_memory: false,
+            chunker: Default::default(),
+            compression: Default::default(),
+            credentials: crate::credentials::default_store(),
         };
 
         assert_eq!(config.fetch_workers, 4);
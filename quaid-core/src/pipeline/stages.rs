@@ -1,23 +1,43 @@
 //! Pipeline stage worker implementations
+//!
+//! Workers are tokio tasks. Several workers of a stage share one receiver, and
+//! blocking work (embedding, parquet writes) runs on the blocking pool.
 
 use super::messages::PipelineMessage;
 use super::Result;
-use crate::embeddings::{Embedder, MessageChunker};
+use crate::embeddings::{Chunk, Embedder, MessageChunker};
+use crate::providers::{Conversation, Message};
 use crate::storage::parquet::ParquetStore;
 use crate::storage::{EmbeddingsStore, ParquetStorageConfig};
-use crossbeam_channel::{Receiver, Sender};
 use std::sync::Arc;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// Receiving end of a stage's input, shared by that stage's workers
+pub type SharedReceiver = Arc<Mutex<Receiver<PipelineMessage>>>;
+
+/// Next message for this worker, or `None` once the channel is closed or the
+/// run is cancelled
+async fn next_message(rx: &SharedReceiver, cancel: &CancellationToken) -> Option<PipelineMessage> {
+    tokio::select! {
+        biased;
+        _ = cancel.cancelled() => None,
+        msg = async { rx.lock().await.recv().await } => msg,
+    }
+}
 
 /// Stage 2: Media download worker
 ///
 /// Receives ConversationFetched messages, downloads any attachments,
 /// and forwards MediaDownloaded messages to the next stage.
-pub fn media_worker(
-    rx: Receiver<PipelineMessage>,
+pub async fn media_worker(
+    rx: SharedReceiver,
     tx: Sender<PipelineMessage>,
     _storage_config: ParquetStorageConfig,
+    cancel: CancellationToken,
 ) -> Result<()> {
-    for msg in rx {
+    while let Some(msg) = next_message(&rx, &cancel).await {
         match msg {
             PipelineMessage::ConversationFetched {
                 account_id,
@@ -34,17 +54,17 @@ pub fn media_worker(
                     attachments: vec![], // No attachments downloaded yet
                 };
 
-                if tx.send(result).is_err() {
+                if tx.send(result).await.is_err() {
                     break; // Receiver dropped, stop processing
                 }
             }
             PipelineMessage::Shutdown => {
-                let _ = tx.send(PipelineMessage::Shutdown);
+                let _ = tx.send(PipelineMessage::Shutdown).await;
                 break;
             }
             PipelineMessage::Error { .. } => {
                 // Forward errors to next stage
-                let _ = tx.send(msg);
+                let _ = tx.send(msg).await;
             }
             _ => {} // Ignore other message types
         }
@@ -53,101 +73,215 @@ pub fn media_worker(
     Ok(())
 }
 
+/// A conversation waiting for its chunks to be embedded
+struct PendingConversation {
+    account_id: String,
+    conversation: Conversation,
+    messages: Vec<Message>,
+    chunks: Vec<Chunk>,
+}
+
+/// Shared handles the embed stage writes through
+#[derive(Clone)]
+pub struct EmbedResources {
+    pub store: Arc<ParquetStore>,
+    pub embeddings_store: Arc<EmbeddingsStore>,
+    pub embedder: Arc<dyn Embedder>,
+    pub chunker: Arc<MessageChunker>,
+}
+
 /// Stage 3: Embed and persist worker
 ///
 /// Receives MediaDownloaded messages, chunks messages, generates embeddings,
 /// and persists to parquet files.
-pub fn embed_worker(
-    rx: Receiver<PipelineMessage>,
+///
+/// Conversations already queued are taken together until `batch_size` chunks
+/// are pending, so the embedder sees full batches instead of one conversation
+/// (or one chunk) at a time. Without `batch_conversations`, each conversation
+/// is embedded and written before the next is taken, which keeps memory low.
+///
+/// On cancellation, conversations that haven't been written yet are dropped
+/// without a `Complete` message, so they are not counted as synced.
+pub async fn embed_worker(
+    rx: SharedReceiver,
     tx: Sender<PipelineMessage>,
-    store: Arc<ParquetStore>,
-    embeddings_store: Arc<EmbeddingsStore>,
-    embedder: Arc<dyn Embedder>,
-    chunker: Arc<MessageChunker>,
+    resources: EmbedResources,
+    batch_size: usize,
+    batch_conversations: bool,
+    cancel: CancellationToken,
 ) -> Result<()> {
-    for msg in rx {
-        match msg {
-            PipelineMessage::MediaDownloaded {
-                account_id,
-                conversation,
-                messages,
-                attachments: _,
-            } => {
-                let conv_id = conversation.id.clone();
-                let provider_id = conversation.provider_id.clone();
-                let messages_count = messages.len();
-
-                // Chunk all messages
-                let chunks = chunker.chunk_messages(&messages);
-                let chunks_count = chunks.len();
-
-                // Generate embeddings for chunks
-                let chunk_texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
-                let embeddings = match embedder.embed_batch(&chunk_texts) {
-                    Ok(e) => e,
-                    Err(e) => {
-                        let _ = tx.send(PipelineMessage::Error {
-                            conversation_id: conv_id.clone(),
-                            stage: "embed".to_string(),
-                            message: format!("Embedding failed: {}", e),
-                        });
-                        continue;
-                    }
-                };
+    let batch_size = batch_size.max(1);
 
-                // Write conversation to parquet
-                if let Err(e) = store.write_conversation(&account_id, &conversation, &messages) {
-                    let _ = tx.send(PipelineMessage::Error {
-                        conversation_id: conv_id.clone(),
-                        stage: "persist".to_string(),
-                        message: format!("Failed to write parquet: {}", e),
+    while let Some(msg) = next_message(&rx, &cancel).await {
+        let mut pending: Vec<PendingConversation> = Vec::new();
+        let mut next = Some(msg);
+        let mut pending_chunks = 0;
+        let mut shutdown = false;
+
+        while let Some(msg) = next.take() {
+            match msg {
+                PipelineMessage::MediaDownloaded {
+                    account_id,
+                    conversation,
+                    messages,
+                    attachments: _,
+                } => {
+                    // Chunk all messages
+                    let chunks = resources.chunker.chunk_messages(&messages);
+                    pending_chunks += chunks.len();
+                    pending.push(PendingConversation {
+                        account_id,
+                        conversation,
+                        messages,
+                        chunks,
                     });
-                    continue;
                 }
-
-                // Write embeddings to parquet
-                if !chunks.is_empty() {
-                    if let Err(e) =
-                        embeddings_store.write_embeddings(&conv_id, &provider_id, &chunks, &embeddings)
-                    {
-                        let _ = tx.send(PipelineMessage::Error {
-                            conversation_id: conv_id.clone(),
-                            stage: "embeddings".to_string(),
-                            message: format!("Failed to write embeddings: {}", e),
-                        });
-                        continue;
-                    }
+                PipelineMessage::Shutdown => {
+                    shutdown = true;
+                    break;
                 }
-
-                // Send completion
-                let _ = tx.send(PipelineMessage::Complete {
-                    conversation_id: conv_id,
-                    messages_count,
-                    chunks_count,
-                });
+                PipelineMessage::Error { .. } => {
+                    // Forward errors
+                    let _ = tx.send(msg).await;
+                }
+                _ => {} // Ignore other message types
             }
-            PipelineMessage::Shutdown => {
-                let _ = tx.send(PipelineMessage::Shutdown);
-                break;
+
+            // Don't wait for more work, only take what is already queued
+            if batch_conversations && pending_chunks < batch_size {
+                // A worker holding the lock is waiting, so nothing is queued
+                next = rx.try_lock().ok().and_then(|mut rx| rx.try_recv().ok());
             }
-            PipelineMessage::Error { .. } => {
-                // Forward errors
-                let _ = tx.send(msg);
+        }
+
+        if !pending.is_empty() && !cancel.is_cancelled() {
+            let resources = resources.clone();
+            let cancel = cancel.clone();
+            let outcomes = tokio::task::spawn_blocking(move || {
+                flush(pending, &resources, batch_size, &cancel)
+            })
+            .await?;
+
+            for outcome in outcomes {
+                if tx.send(outcome).await.is_err() {
+                    return Ok(());
+                }
             }
-            _ => {} // Ignore other message types
+        }
+
+        if shutdown {
+            let _ = tx.send(PipelineMessage::Shutdown).await;
+            break;
         }
     }
 
     Ok(())
 }
 
+/// Embed the chunks of all pending conversations, then persist each one
+///
+/// Returns the outcome for every conversation it got to; once `cancel` fires,
+/// no more batches are embedded and the remaining conversations are left
+/// unwritten.
+fn flush(
+    pending: Vec<PendingConversation>,
+    resources: &EmbedResources,
+    batch_size: usize,
+    cancel: &CancellationToken,
+) -> Vec<PipelineMessage> {
+    // Generate embeddings for chunks
+    let texts: Vec<&str> = pending
+        .iter()
+        .flat_map(|p| p.chunks.iter().map(|c| c.text.as_str()))
+        .collect();
+    let embeddings = texts
+        .chunks(batch_size)
+        .take_while(|_| !cancel.is_cancelled())
+        .map(|batch| resources.embedder.embed_batch(batch))
+        .collect::<std::result::Result<Vec<_>, _>>();
+
+    let mut embeddings = match embeddings {
+        Ok(batches) => batches.into_iter().flatten(),
+        Err(e) => {
+            return pending
+                .into_iter()
+                .map(|p| PipelineMessage::Error {
+                    conversation_id: p.conversation.id,
+                    stage: "embed".to_string(),
+                    message: format!("Embedding failed: {}", e),
+                })
+                .collect();
+        }
+    };
+
+    let mut outcomes = Vec::with_capacity(pending.len());
+    for p in pending {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let vectors: Vec<Vec<f32>> = embeddings.by_ref().take(p.chunks.len()).collect();
+        outcomes.push(persist(p, &vectors, resources));
+    }
+    outcomes
+}
+
+/// Write one conversation and its embeddings, returning the outcome
+fn persist(
+    pending: PendingConversation,
+    embeddings: &[Vec<f32>],
+    resources: &EmbedResources,
+) -> PipelineMessage {
+    let PendingConversation {
+        account_id,
+        conversation,
+        messages,
+        chunks,
+    } = pending;
+    let conv_id = conversation.id.clone();
+
+    // Write conversation to parquet
+    if let Err(e) = resources
+        .store
+        .write_conversation(&account_id, &conversation, &messages)
+    {
+        return PipelineMessage::Error {
+            conversation_id: conv_id,
+            stage: "persist".to_string(),
+            message: format!("Failed to write parquet: {}", e),
+        };
+    }
+
+    // Write embeddings to parquet
+    if !chunks.is_empty() {
+        if let Err(e) = resources.embeddings_store.write_embeddings(
+            &conv_id,
+            &conversation.provider_id,
+            &chunks,
+            embeddings,
+        ) {
+            return PipelineMessage::Error {
+                conversation_id: conv_id,
+                stage: "embeddings".to_string(),
+                message: format!("Failed to write embeddings: {}", e),
+            };
+        }
+    }
+
+    PipelineMessage::Complete {
+        conversation_id: conv_id,
+        messages_count: messages.len(),
+        chunks_count: chunks.len(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::embeddings::{ChunkerConfig, MockEmbeddingModel};
     use crate::providers::{Conversation, Message, MessageContent, Role};
-    use crossbeam_channel::bounded;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tempfile::tempdir;
+    use tokio::sync::mpsc::channel;
 
     fn create_test_conversation() -> Conversation {
         Conversation {
@@ -174,16 +308,33 @@ mod tests {
             },
             created_at: None,
             model: None,
+            speaker: None,
         }
     }
 
-    #[test]
-    fn test_media_worker_forwards_messages() {
+    fn shared(rx: Receiver<PipelineMessage>) -> SharedReceiver {
+        Arc::new(Mutex::new(rx))
+    }
+
+    fn test_resources(
+        config: &ParquetStorageConfig,
+        embedder: Arc<dyn Embedder>,
+    ) -> EmbedResources {
+        EmbedResources {
+            store: Arc::new(ParquetStore::new(config.clone())),
+            embeddings_store: Arc::new(EmbeddingsStore::new(config.clone())),
+            embedder,
+            chunker: Arc::new(MessageChunker::new(ChunkerConfig::default())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_media_worker_forwards_messages() {
         let dir = tempdir().unwrap();
         let config = ParquetStorageConfig::new(dir.path());
 
-        let (in_tx, in_rx) = bounded(10);
-        let (out_tx, out_rx) = bounded(10);
+        let (in_tx, in_rx) = channel(10);
+        let (out_tx, mut out_rx) = channel(10);
 
         // Send a conversation
         in_tx
@@ -192,14 +343,20 @@ mod tests {
                 conversation: create_test_conversation(),
                 messages: vec![create_test_message("msg-1", "Hello")],
             })
+            .await
             .unwrap();
         drop(in_tx); // Signal no more messages
 
         // Run worker
-        let handle = std::thread::spawn(move || media_worker(in_rx, out_tx, config));
+        let handle = tokio::spawn(media_worker(
+            shared(in_rx),
+            out_tx,
+            config,
+            CancellationToken::new(),
+        ));
 
         // Check output
-        let output = out_rx.recv().unwrap();
+        let output = out_rx.recv().await.unwrap();
         if let PipelineMessage::MediaDownloaded {
             account_id,
             conversation,
@@ -215,21 +372,18 @@ mod tests {
             panic!("Expected MediaDownloaded message");
         }
 
-        handle.join().unwrap().unwrap();
+        handle.await.unwrap().unwrap();
     }
 
-    #[test]
-    fn test_embed_worker_processes_messages() {
+    #[tokio::test]
+    async fn test_embed_worker_processes_messages() {
         let dir = tempdir().unwrap();
         let config = ParquetStorageConfig::new(dir.path());
 
-        let (in_tx, in_rx) = bounded(10);
-        let (out_tx, out_rx) = bounded(10);
+        let (in_tx, in_rx) = channel(10);
+        let (out_tx, mut out_rx) = channel(10);
 
-        let store = Arc::new(ParquetStore::new(config.clone()));
-        let embeddings_store = Arc::new(EmbeddingsStore::new(config));
-        let embedder: Arc<dyn Embedder> = Arc::new(MockEmbeddingModel::new(384));
-        let chunker = Arc::new(MessageChunker::new(ChunkerConfig::default()));
+        let resources = test_resources(&config, Arc::new(MockEmbeddingModel::new(384)));
 
         // Send a media downloaded message
         in_tx
@@ -242,16 +396,22 @@ mod tests {
                 ],
                 attachments: vec![],
             })
+            .await
             .unwrap();
         drop(in_tx);
 
         // Run worker
-        let handle = std::thread::spawn(move || {
-            embed_worker(in_rx, out_tx, store, embeddings_store, embedder, chunker)
-        });
+        let handle = tokio::spawn(embed_worker(
+            shared(in_rx),
+            out_tx,
+            resources,
+            64,
+            true,
+            CancellationToken::new(),
+        ));
 
         // Check output
-        let output = out_rx.recv().unwrap();
+        let output = out_rx.recv().await.unwrap();
         if let PipelineMessage::Complete {
             conversation_id,
             messages_count,
@@ -265,30 +425,224 @@ mod tests {
             panic!("Expected Complete message, got {:?}", output);
         }
 
-        handle.join().unwrap().unwrap();
+        handle.await.unwrap().unwrap();
 
         // Verify file was written
         let parquet_path = dir.path().join("conversations/chatgpt/conv-1.parquet");
         assert!(parquet_path.exists());
     }
 
+    /// Mock embedder that counts model calls
+    struct CountingEmbedder {
+        inner: MockEmbeddingModel,
+        calls: AtomicUsize,
+    }
+
+    impl Embedder for CountingEmbedder {
+        fn embedding_dim(&self) -> usize {
+            384
+        }
+
+        fn embed(&self, text: &str) -> crate::embeddings::Result<Vec<f32>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.inner.embed(text))
+        }
+
+        fn embed_batch(&self, texts: &[&str]) -> crate::embeddings::Result<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.inner.embed_batch(texts))
+        }
+    }
+
+    /// Run the embed worker over `conversations` queued conversations of four
+    /// messages each, returning (model calls, chunks embedded)
+    async fn embed_calls(
+        conversations: usize,
+        batch_size: usize,
+        batch_conversations: bool,
+    ) -> (usize, usize) {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let (in_tx, in_rx) = channel(conversations);
+        let (out_tx, mut out_rx) = channel(conversations);
+
+        for i in 0..conversations {
+            let mut conversation = create_test_conversation();
+            conversation.id = format!("conv-{}", i);
+            let messages = (0..4)
+                .map(|j| create_test_message(&format!("msg-{}-{}", i, j), "Short message"))
+                .collect();
+            in_tx
+                .send(PipelineMessage::MediaDownloaded {
+                    account_id: "user-1".to_string(),
+                    conversation,
+                    messages,
+                    attachments: vec![],
+                })
+                .await
+                .unwrap();
+        }
+        drop(in_tx);
+
+        let embedder = Arc::new(CountingEmbedder {
+            inner: MockEmbeddingModel::new(384),
+            calls: AtomicUsize::new(0),
+        });
+        embed_worker(
+            shared(in_rx),
+            out_tx,
+            test_resources(&config, embedder.clone()),
+            batch_size,
+            batch_conversations,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        let mut chunks = 0;
+        while let Some(msg) = out_rx.recv().await {
+            match msg {
+                PipelineMessage::Complete { chunks_count, .. } => chunks += chunks_count,
+                other => panic!("Expected Complete message, got {:?}", other),
+            }
+        }
+        (embedder.calls.load(Ordering::SeqCst), chunks)
+    }
+
+    #[tokio::test]
+    async fn test_embed_worker_batches_across_conversations() {
+        let (unbatched, chunks) = embed_calls(10, 1, true).await;
+        assert_eq!(chunks, 40);
+        assert_eq!(unbatched, 40);
+
+        // 40 chunks in batches of 16: 16 + 16 + 8
+        let (batched, chunks) = embed_calls(10, 16, true).await;
+        assert_eq!(chunks, 40);
+        assert_eq!(batched, 3);
+
+        let (whole, _) = embed_calls(10, 64, true).await;
+        assert_eq!(whole, 1);
+
+        // One conversation at a time: one call per conversation
+        let (one_by_one, chunks) = embed_calls(10, 64, false).await;
+        assert_eq!(chunks, 40);
+        assert_eq!(one_by_one, 10);
+    }
+
+    #[tokio::test]
+    async fn test_embed_worker_stops_when_cancelled() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+
+        let (in_tx, in_rx) = channel(10);
+        let (out_tx, mut out_rx) = channel(10);
+        in_tx
+            .send(PipelineMessage::MediaDownloaded {
+                account_id: "user-1".to_string(),
+                conversation: create_test_conversation(),
+                messages: vec![create_test_message("msg-1", "Hello")],
+                attachments: vec![],
+            })
+            .await
+            .unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        embed_worker(
+            shared(in_rx),
+            out_tx,
+            test_resources(&config, Arc::new(MockEmbeddingModel::new(384))),
+            64,
+            true,
+            cancel,
+        )
+        .await
+        .unwrap();
+
+        assert!(out_rx.recv().await.is_none());
+        assert!(!dir
+            .path()
+            .join("conversations/chatgpt/conv-1.parquet")
+            .exists());
+    }
+
+    /// Mock embedder that cancels the run once it has embedded a batch
+    struct CancellingEmbedder {
+        inner: CountingEmbedder,
+        cancel: CancellationToken,
+    }
+
+    impl Embedder for CancellingEmbedder {
+        fn embedding_dim(&self) -> usize {
+            self.inner.embedding_dim()
+        }
+
+        fn embed(&self, text: &str) -> crate::embeddings::Result<Vec<f32>> {
+            self.inner.embed(text)
+        }
+
+        fn embed_batch(&self, texts: &[&str]) -> crate::embeddings::Result<Vec<Vec<f32>>> {
+            self.cancel.cancel();
+            self.inner.embed_batch(texts)
+        }
+    }
+
     #[test]
-    fn test_workers_handle_shutdown() {
+    fn test_flush_stops_embedding_when_cancelled() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let cancel = CancellationToken::new();
+        let embedder = Arc::new(CancellingEmbedder {
+            inner: CountingEmbedder {
+                inner: MockEmbeddingModel::new(384),
+                calls: AtomicUsize::new(0),
+            },
+            cancel: cancel.clone(),
+        });
+        let resources = test_resources(&config, embedder.clone());
+
+        let messages: Vec<_> = (0..4)
+            .map(|i| create_test_message(&format!("msg-{}", i), "Short message"))
+            .collect();
+        let pending = vec![PendingConversation {
+            account_id: "user-1".to_string(),
+            conversation: create_test_conversation(),
+            chunks: resources.chunker.chunk_messages(&messages),
+            messages,
+        }];
+
+        // Four chunks in batches of one, but only the first batch is embedded
+        let outcomes = flush(pending, &resources, 1, &cancel);
+        assert!(outcomes.is_empty());
+        assert_eq!(embedder.inner.calls.load(Ordering::SeqCst), 1);
+        assert!(!dir
+            .path()
+            .join("conversations/chatgpt/conv-1.parquet")
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn test_workers_handle_shutdown() {
         let dir = tempdir().unwrap();
         let config = ParquetStorageConfig::new(dir.path());
 
-        let (in_tx, in_rx) = bounded(10);
-        let (out_tx, out_rx) = bounded(10);
+        let (in_tx, in_rx) = channel(10);
+        let (out_tx, mut out_rx) = channel(10);
 
-        in_tx.send(PipelineMessage::Shutdown).unwrap();
+        in_tx.send(PipelineMessage::Shutdown).await.unwrap();
         drop(in_tx);
 
-        let handle = std::thread::spawn(move || media_worker(in_rx, out_tx, config));
+        let handle = tokio::spawn(media_worker(
+            shared(in_rx),
+            out_tx,
+            config,
+            CancellationToken::new(),
+        ));
 
         // Should receive shutdown and exit cleanly
-        let output = out_rx.recv().unwrap();
+        let output = out_rx.recv().await.unwrap();
         assert!(matches!(output, PipelineMessage::Shutdown));
 
-        handle.join().unwrap().unwrap();
+        handle.await.unwrap().unwrap();
     }
 }
@@ -0,0 +1,278 @@
+//! Google AI Studio prompt import
+//!
+//! AI Studio has no API for saved prompts, but keeps each one as a JSON file
+//! in the "Google AI Studio" folder of the user's Drive. This module turns
+//! those files, once downloaded, into conversations; reading them from disk
+//! is left to the caller.
+
+pub mod types;
+
+use crate::providers::{Conversation, Message, MessageContent, ProviderError, Result, Role};
+use chrono::{DateTime, Utc};
+use types::*;
+
+/// Conversation id of a prompt file: its name, without a `.json` extension
+///
+/// Drive saves prompts under their title, with no extension.
+pub fn prompt_id(file_name: &str) -> String {
+    file_name
+        .strip_suffix(".json")
+        .unwrap_or(file_name)
+        .to_string()
+}
+
+/// Parse a prompt file into a conversation and its messages
+///
+/// Files don't record when the prompt was written, so `modified`, the file's
+/// modification time, is used for both timestamps. System instructions come
+/// first as a system message.
+pub fn parse_prompt(
+    id: &str,
+    json: &str,
+    modified: DateTime<Utc>,
+) -> Result<(Conversation, Vec<Message>)> {
+    let prompt: ApiPrompt =
+        serde_json::from_str(json).map_err(|e| ProviderError::Parse(format!("{}: {}", id, e)))?;
+    if prompt.chunked_prompt.is_none() && prompt.text_prompt.is_none() {
+        return Err(ProviderError::Parse(format!(
+            "{}: not an AI Studio prompt",
+            id
+        )));
+    }
+
+    let model = prompt
+        .run_settings
+        .as_ref()
+        .and_then(|settings| settings.model.as_deref())
+        .map(|model| model.trim_start_matches("models/").to_string());
+
+    let conversation = Conversation {
+        id: id.to_string(),
+        provider_id: "aistudio".to_string(),
+        title: id.to_string(),
+        created_at: modified,
+        updated_at: modified,
+        model: model.clone(),
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    };
+
+    let message = |suffix: &str, role: Role, content: MessageContent| Message {
+        id: format!("{}-{}", id, suffix),
+        conversation_id: id.to_string(),
+        parent_id: None,
+        model: (role == Role::Assistant).then(|| model.clone()).flatten(),
+        role,
+        content,
+        created_at: None,
+        speaker: None,
+    };
+
+    let mut messages = Vec::new();
+    let system = prompt
+        .system_instruction
+        .as_ref()
+        .map(ApiSystemInstruction::text)
+        .unwrap_or_default();
+    if !system.trim().is_empty() {
+        messages.push(message(
+            "system",
+            Role::System,
+            MessageContent::Text { text: system },
+        ));
+    }
+
+    if let Some(chunked) = &prompt.chunked_prompt {
+        for (index, (role, content)) in turns(&chunked.chunks).into_iter().enumerate() {
+            messages.push(message(&index.to_string(), role, content));
+        }
+    } else if let Some(text) = &prompt.text_prompt {
+        // A single-shot prompt keeps its text, not the model's output
+        if !text.text.trim().is_empty() {
+            messages.push(message(
+                "0",
+                Role::User,
+                MessageContent::Text {
+                    text: text.text.clone(),
+                },
+            ));
+        }
+    }
+
+    Ok((conversation, messages))
+}
+
+/// Group consecutive chunks of the same role into one turn each
+fn turns(chunks: &[ApiChunk]) -> Vec<(Role, MessageContent)> {
+    let mut turns: Vec<(Role, Vec<MessageContent>)> = Vec::new();
+    for chunk in chunks {
+        let role = match chunk.role.as_str() {
+            "model" => Role::Assistant,
+            _ => Role::User,
+        };
+        let parts = chunk_parts(chunk);
+        if parts.is_empty() {
+            continue;
+        }
+        match turns.last_mut() {
+            Some((last, content)) if *last == role => content.extend(parts),
+            _ => turns.push((role, parts)),
+        }
+    }
+
+    turns
+        .into_iter()
+        .map(|(role, mut parts)| {
+            let content = if parts.len() == 1 {
+                parts.remove(0)
+            } else {
+                MessageContent::Mixed { parts }
+            };
+            (role, content)
+        })
+        .collect()
+}
+
+fn chunk_parts(chunk: &ApiChunk) -> Vec<MessageContent> {
+    let mut parts = Vec::new();
+
+    if let Some(image) = &chunk.drive_image {
+        parts.push(MessageContent::Image {
+            url: format!("https://drive.google.com/file/d/{}", image.id),
+            alt: None,
+        });
+    }
+    if let Some(image) = &chunk.inline_image {
+        parts.push(MessageContent::Image {
+            url: format!("data:{};base64,{}", image.mime_type, image.data),
+            alt: None,
+        });
+    }
+
+    let texts = chunk
+        .text
+        .iter()
+        .map(|text| (text, chunk.is_thought))
+        .chain(
+            chunk
+                .parts
+                .iter()
+                .filter_map(|part| part.text.as_ref().map(|text| (text, part.thought))),
+        );
+    for (text, thought) in texts {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let text = text.clone();
+        parts.push(if thought {
+            MessageContent::Thinking { text }
+        } else {
+            MessageContent::Text { text }
+        });
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_prompt_id() {
+        assert_eq!(prompt_id("Schema review"), "Schema review");
+        assert_eq!(prompt_id("Schema review.json"), "Schema review");
+        assert_eq!(prompt_id("v1.5 notes"), "v1.5 notes");
+    }
+
+    #[test]
+    fn test_parse_chat_prompt() {
+        let modified = Utc::now();
+        let (conversation, messages) =
+            parse_prompt("Schema review", &fixture("aistudio-chat.json"), modified).unwrap();
+
+        assert_eq!(conversation.id, "Schema review");
+        assert_eq!(conversation.provider_id, "aistudio");
+        assert_eq!(conversation.model.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(conversation.updated_at, modified);
+
+        // The system instructions, then four turns
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0].id, "Schema review-system");
+        assert_eq!(messages[0].role, Role::System);
+        assert!(messages[0].model.is_none());
+
+        // The image and the question make one turn
+        assert_eq!(messages[1].role, Role::User);
+        match &messages[1].content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Image { url, .. }, MessageContent::Text { text }] => {
+                    assert_eq!(
+                        url,
+                        "https://drive.google.com/file/d/1AbCdEfGhIjKlMnOpQrStUvWxYz012345"
+                    );
+                    assert!(text.starts_with("Here is our schema diagram"));
+                }
+                other => panic!("Unexpected parts: {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+
+        // Thoughts are kept ahead of the answer
+        assert_eq!(messages[2].role, Role::Assistant);
+        assert_eq!(messages[2].model.as_deref(), Some("gemini-2.5-pro"));
+        match &messages[2].content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Thinking { text: thinking }, MessageContent::Text { text }] => {
+                    assert!(thinking.starts_with("**Reviewing the diagram**"));
+                    assert!(text.starts_with("Not quite"));
+                }
+                other => panic!("Unexpected parts: {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+
+        match &messages[3].content {
+            MessageContent::Mixed { parts } => match parts.first() {
+                Some(MessageContent::Image { url, .. }) => {
+                    assert_eq!(url, "data:image/png;base64,iVBORw0KGgo=")
+                }
+                other => panic!("Expected an inline image, got {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+        assert_eq!(messages[4].id, "Schema review-3");
+    }
+
+    #[test]
+    fn test_parse_single_shot_prompt() {
+        let (conversation, messages) =
+            parse_prompt("Haiku", &fixture("aistudio-freeform.json"), Utc::now()).unwrap();
+
+        assert_eq!(conversation.model.as_deref(), Some("gemini-1.5-flash"));
+        // Empty system instructions are left out
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, Role::User);
+        match &messages[0].content {
+            MessageContent::Text { text } => {
+                assert_eq!(text, "Write a haiku about connection pools.")
+            }
+            other => panic!("Expected text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_other_json_is_refused() {
+        assert!(parse_prompt("notes", "{\"title\": \"notes\"}", Utc::now()).is_err());
+        assert!(parse_prompt("notes", "not json", Utc::now()).is_err());
+    }
+}
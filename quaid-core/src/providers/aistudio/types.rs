@@ -0,0 +1,110 @@
+//! Google AI Studio prompt file types
+//!
+//! These types are based on the JSON files AI Studio saves prompts as in
+//! Google Drive and may need adjustment as the format evolves.
+
+use serde::Deserialize;
+
+/// A saved prompt, one per file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiPrompt {
+    #[serde(default)]
+    pub run_settings: Option<ApiRunSettings>,
+    #[serde(default)]
+    pub system_instruction: Option<ApiSystemInstruction>,
+    /// Turns of a chat prompt
+    #[serde(default)]
+    pub chunked_prompt: Option<ApiChunkedPrompt>,
+    /// The text of a single-shot (freeform) prompt
+    #[serde(default)]
+    pub text_prompt: Option<ApiTextPrompt>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiRunSettings {
+    /// e.g. `models/gemini-2.5-pro`
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// System instructions, as plain text or as parts
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiSystemInstruction {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<ApiPart>,
+}
+
+impl ApiSystemInstruction {
+    pub fn text(&self) -> String {
+        match &self.text {
+            Some(text) => text.clone(),
+            None => self
+                .parts
+                .iter()
+                .filter_map(|part| part.text.as_deref())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiChunkedPrompt {
+    #[serde(default)]
+    pub chunks: Vec<ApiChunk>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiTextPrompt {
+    #[serde(default)]
+    pub text: String,
+}
+
+/// One piece of a turn: text, the model's thoughts, or an uploaded image
+///
+/// A turn with an image and a question is saved as two chunks of the same role.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiChunk {
+    /// `user` or `model`
+    pub role: String,
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Set on the model's thoughts before its answer
+    #[serde(default)]
+    pub is_thought: bool,
+    /// Newer files split a turn into parts instead
+    #[serde(default)]
+    pub parts: Vec<ApiPart>,
+    /// An image uploaded to Drive
+    #[serde(default)]
+    pub drive_image: Option<ApiDriveFile>,
+    /// An image pasted into the prompt, saved in the file
+    #[serde(default)]
+    pub inline_image: Option<ApiInlineData>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiPart {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub thought: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiDriveFile {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiInlineData {
+    pub mime_type: String,
+    /// Base64 encoded
+    pub data: String,
+}
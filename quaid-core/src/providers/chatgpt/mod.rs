@@ -1,15 +1,24 @@
+pub mod share;
 mod types;
 
-use crate::credentials::{CredentialStore, KeyringStore};
+use crate::credentials::keys::chatgpt::{
+    COOKIES as KEYRING_USER_COOKIES, SELECTED as KEYRING_USER_SELECTED,
+    TOKEN as KEYRING_USER_TOKEN, WORKSPACE as KEYRING_USER_WORKSPACE,
+};
+use crate::credentials::{
+    account_user, default_store, CredentialError, CredentialStore, SERVICE as KEYRING_SERVICE,
+};
+use crate::providers::download::{self, DownloadSource};
 use crate::providers::{
-    Account, Attachment, Conversation, Message, MessageContent, Provider, ProviderId,
-    ProviderError, Result, Role,
+    with_retry, Account, Attachment, Conversation, Message, MessageContent, Project, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
 };
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
 use chromiumoxide::browser::{Browser, BrowserConfig};
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use reqwest::{header, Client};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -17,54 +26,110 @@ use tokio::sync::RwLock;
 pub use types::*;
 
 const BASE_URL: &str = "https://chatgpt.com";
-const API_URL: &str = "https://chatgpt.com/backend-api";
-
-const KEYRING_SERVICE: &str = "quaid";
-const KEYRING_USER_TOKEN: &str = "chatgpt-token";
-const KEYRING_USER_COOKIES: &str = "chatgpt-cookies";
+/// Cookie holding the web sign-in, split into `.0`, `.1`... when it's long
+const SESSION_COOKIE: &str = "__Secure-next-auth.session-token";
 
 /// ChatGPT provider implementation
 pub struct ChatGptProvider {
     client: Client,
+    base_url: String,
     token: Arc<RwLock<Option<String>>>,
     account_id: Arc<RwLock<Option<String>>>, // For team accounts
     credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Also list conversations archived in the web app
+    include_archived: bool,
 }
 
 impl ChatGptProvider {
     pub fn new() -> Self {
-        Self::with_credential_store(Arc::new(KeyringStore::new()))
+        Self::with_credential_store(default_store())
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
+    }
+
+    /// Create for a stored account, sending its workspace's id on every request
+    ///
+    /// Accounts saved before workspaces were synced separately keep the
+    /// workspace their sign-in opened in.
+    pub fn for_stored_account(account: &Account) -> Self {
+        Self::with_stored_account(default_store(), account)
     }
 
     /// Create with a custom credential store (for testing)
     pub fn with_credential_store(credential_store: Arc<dyn CredentialStore>) -> Self {
+        Self::with_account_credentials(credential_store, None)
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
         // Try to load token and cookies from credential store
-        let stored_token = credential_store
-            .get(KEYRING_SERVICE, KEYRING_USER_TOKEN)
-            .ok();
-        let stored_cookies = credential_store
-            .get(KEYRING_SERVICE, KEYRING_USER_COOKIES)
-            .ok();
+        let load = |user| {
+            credential_store
+                .get_for_account(KEYRING_SERVICE, user, email)
+                .ok()
+        };
+        let stored_token = load(KEYRING_USER_TOKEN);
+        let stored_cookies = load(KEYRING_USER_COOKIES);
+        let workspace_id = load(KEYRING_USER_WORKSPACE);
         let client = Self::build_client(stored_cookies.as_deref());
 
         Self {
             client,
+            base_url: BASE_URL.to_string(),
             token: Arc::new(RwLock::new(stored_token)),
-            account_id: Arc::new(RwLock::new(None)),
+            account_id: Arc::new(RwLock::new(workspace_id)),
             credential_store,
+            retry: RetryPolicy::default(),
+            include_archived: false,
+        }
+    }
+
+    /// Create with a custom credential store for a stored account
+    pub fn with_stored_account(
+        credential_store: Arc<dyn CredentialStore>,
+        account: &Account,
+    ) -> Self {
+        let mut provider = Self::with_account_credentials(credential_store, Some(&account.email));
+        if account.workspace_id.is_some() || provider.selection(&account.email).is_some() {
+            provider.account_id = Arc::new(RwLock::new(account.workspace_id.clone()));
         }
+        provider
     }
 
     /// Create with an existing token (for testing or restored sessions)
     pub fn with_token(token: String) -> Self {
         Self {
             client: Self::build_client(None),
+            base_url: BASE_URL.to_string(),
             token: Arc::new(RwLock::new(Some(token))),
             account_id: Arc::new(RwLock::new(None)),
-            credential_store: Arc::new(KeyringStore::new()),
+            credential_store: default_store(),
+            retry: RetryPolicy::default(),
+            include_archived: false,
         }
     }
 
+    /// Send requests to `base_url` instead of chatgpt.com (for testing)
+    #[cfg(test)]
+    fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Also list archived conversations, which the web app keeps out of the sidebar
+    pub fn with_include_archived(mut self, include_archived: bool) -> Self {
+        self.include_archived = include_archived;
+        self
+    }
+
     /// Build HTTP client with browser-like headers and optional cookies
     fn build_client(cookies: Option<&str>) -> Client {
         let mut headers = header::HeaderMap::new();
@@ -78,10 +143,7 @@ impl ChatGptProvider {
             header::ACCEPT,
             "application/json, text/plain, */*".parse().unwrap(),
         );
-        headers.insert(
-            header::ACCEPT_LANGUAGE,
-            "en-US,en;q=0.9".parse().unwrap(),
-        );
+        headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
         headers.insert(header::ORIGIN, BASE_URL.parse().unwrap());
         headers.insert(header::REFERER, BASE_URL.parse().unwrap());
         headers.insert("Sec-Fetch-Dest", "empty".parse().unwrap());
@@ -102,18 +164,104 @@ impl ChatGptProvider {
             .expect("Failed to create HTTP client")
     }
 
-    /// Save token to credential store
-    fn save_token(&self, token: &str) -> Result<()> {
+    /// Save one of `email`'s credentials to the credential store
+    fn save_credential(&self, user: &str, email: &str, value: &str) -> Result<()> {
         self.credential_store
-            .set(KEYRING_SERVICE, KEYRING_USER_TOKEN, token)
-            .map_err(|e| ProviderError::AuthFailed(format!("Failed to save token: {}", e)))
+            .set_for_account(KEYRING_SERVICE, user, email, value)
+            .map_err(|e| ProviderError::AuthFailed(format!("Failed to save {}: {}", user, e)))
     }
 
-    /// Save cookies to credential store
-    fn save_cookies(&self, cookies: &str) -> Result<()> {
-        self.credential_store
-            .set(KEYRING_SERVICE, KEYRING_USER_COOKIES, cookies)
-            .map_err(|e| ProviderError::AuthFailed(format!("Failed to save cookies: {}", e)))
+    /// Ids of `email`'s accounts chosen at sign-in; `None` if it predates choosing
+    fn selection(&self, email: &str) -> Option<Vec<String>> {
+        let selected = self
+            .credential_store
+            .get(KEYRING_SERVICE, &account_user(KEYRING_USER_SELECTED, email))
+            .ok()?;
+        serde_json::from_str(&selected).ok()
+    }
+
+    /// Remember which of one sign-in's accounts to sync
+    ///
+    /// Also forgets the workspace saved by older versions, so the personal
+    /// account no longer sends it.
+    pub fn save_selection(&self, email: &str, accounts: &[Account]) -> Result<()> {
+        let ids: Vec<_> = accounts.iter().map(|a| a.id.as_str()).collect();
+        let ids = serde_json::to_string(&ids).map_err(|e| ProviderError::Parse(e.to_string()))?;
+        self.save_credential(KEYRING_USER_SELECTED, email, &ids)?;
+        match self.credential_store.delete(
+            KEYRING_SERVICE,
+            &account_user(KEYRING_USER_WORKSPACE, email),
+        ) {
+            Ok(()) | Err(CredentialError::NotFound) => Ok(()),
+            Err(e) => Err(ProviderError::AuthFailed(format!(
+                "Failed to forget {}: {}",
+                KEYRING_USER_WORKSPACE, e
+            ))),
+        }
+    }
+
+    /// Whether `account` was chosen at sign-in; every account is before choosing
+    pub fn is_selected(&self, account: &Account) -> bool {
+        self.selection(&account.email)
+            .is_none_or(|ids| ids.contains(&account.id))
+    }
+
+    /// The personal account and workspaces `user` can sync, personal first
+    pub async fn available_accounts(&self, user: &Account) -> Result<Vec<Account>> {
+        let check: ApiAccountsCheck = self.api_get("/accounts/check/v4-2023-04-27").await?;
+        Ok(accounts_from_check(user, &check))
+    }
+
+    /// Sign in with an access token instead of a browser, for machines that
+    /// can't open one
+    ///
+    /// The token is checked by fetching its account before it's saved.
+    pub async fn authenticate_with_token(&mut self, token: &str) -> Result<Account> {
+        *self.token.write().await = Some(token.trim().to_string());
+        // Workspaces are separate accounts, see `available_accounts`
+        *self.account_id.write().await = None;
+        let account = self.account().await.map_err(|e| match e {
+            ProviderError::AuthFailed(_)
+            | ProviderError::Parse(_)
+            | ProviderError::TokenExpired => ProviderError::AuthFailed(
+                "ChatGPT rejected the access token; it may have expired, copy a new one"
+                    .to_string(),
+            ),
+            _ => e,
+        })?;
+        self.save_credential(KEYRING_USER_TOKEN, &account.email, token.trim())?;
+        Ok(account)
+    }
+
+    /// Sign in with the web app's session cookie, exchanging it for an
+    /// access token at `/api/auth/session`
+    ///
+    /// `cookie` is the cookie's value, or a Cookie header that includes it.
+    pub async fn authenticate_with_session_cookie(&mut self, cookie: &str) -> Result<Account> {
+        let cookies = session_cookie_header(cookie);
+        let client = Self::build_client(Some(&cookies));
+        let response = client
+            .get(format!("{}/api/auth/session", self.base_url))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ProviderError::AuthFailed(format!(
+                "ChatGPT rejected the session cookie: {}",
+                response.status()
+            )));
+        }
+        // An expired cookie gets an empty session
+        let session: ApiSession = response.json().await.map_err(|_| {
+            ProviderError::AuthFailed(format!(
+                "ChatGPT has no session for the cookie; it may have expired, copy {} again",
+                SESSION_COOKIE
+            ))
+        })?;
+
+        self.client = client;
+        let account = self.authenticate_with_token(&session.access_token).await?;
+        self.save_credential(KEYRING_USER_COOKIES, &account.email, &cookies)?;
+        Ok(account)
     }
 
     async fn get_token(&self) -> Result<String> {
@@ -121,9 +269,14 @@ impl ChatGptProvider {
         token.clone().ok_or(ProviderError::AuthRequired)
     }
 
+    /// GET an API endpoint, retrying transient failures
     async fn api_get<T: serde::de::DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        with_retry(&self.retry, || self.api_get_once(endpoint)).await
+    }
+
+    async fn api_get_once<T: serde::de::DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
         let token = self.get_token().await?;
-        let url = format!("{}{}", API_URL, endpoint);
+        let url = format!("{}/backend-api{}", self.base_url, endpoint);
 
         let mut req = self
             .client
@@ -158,25 +311,34 @@ impl ChatGptProvider {
             return Err(ProviderError::Api(format!("{}: {}", status, text)));
         }
 
-        let text = response.text().await.map_err(|e| ProviderError::Parse(e.to_string()))?;
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ProviderError::Parse(e.to_string()))?;
 
         serde_json::from_str(&text).map_err(|e| {
             // Truncate response for error message
-            let preview = if text.len() > 200 { &text[..200] } else { &text };
+            let preview = if text.len() > 200 {
+                &text[..200]
+            } else {
+                &text
+            };
             ProviderError::Parse(format!("{}: {}", e, preview))
         })
     }
 
     /// Fetch all conversations with pagination
-    async fn fetch_all_conversations(&self) -> Result<Vec<ApiConversationItem>> {
+    async fn fetch_all_conversations(&self, archived: bool) -> Result<Vec<ApiConversationItem>> {
         let mut conversations = Vec::new();
         let mut offset = 0;
         let limit = 100;
 
         loop {
-            let result: ApiConversations = self
-                .api_get(&format!("/conversations?offset={}&limit={}", offset, limit))
-                .await?;
+            let mut endpoint = format!("/conversations?offset={}&limit={}", offset, limit);
+            if archived {
+                endpoint.push_str("&is_archived=true");
+            }
+            let result: ApiConversations = self.api_get(&endpoint).await?;
 
             if result.items.is_empty() {
                 break;
@@ -185,7 +347,7 @@ impl ChatGptProvider {
             conversations.extend(result.items);
 
             if let Some(total) = result.total {
-                if offset + limit >= total as usize {
+                if offset + limit >= total {
                     break;
                 }
             }
@@ -196,6 +358,24 @@ impl ChatGptProvider {
         Ok(conversations)
     }
 
+    /// Convert a conversation listing entry to our unified format
+    ///
+    /// Entries carry `update_time` in most responses; without it, the creation
+    /// time is the best guess and `--new-only` can't see later edits.
+    fn convert_list_item(item: &ApiConversationItem, is_archived: bool) -> Conversation {
+        Conversation {
+            id: item.id.clone(),
+            provider_id: "chatgpt".to_string(),
+            title: item.title.clone(),
+            created_at: timestamp_to_datetime(item.create_time),
+            updated_at: timestamp_to_datetime(item.update_time.unwrap_or(item.create_time)),
+            model: None,
+            project_id: project_id(item.gizmo_id.as_deref()),
+            project_name: None,
+            is_archived,
+        }
+    }
+
     /// Convert API conversation to our unified format
     fn convert_conversation(api: &ApiConversation, id: &str) -> Conversation {
         Conversation {
@@ -205,7 +385,7 @@ impl ChatGptProvider {
             created_at: timestamp_to_datetime(api.create_time),
             updated_at: timestamp_to_datetime(api.update_time),
             model: extract_model_from_mapping(&api.mapping),
-            project_id: None,
+            project_id: project_id(api.gizmo_id.as_deref()),
             project_name: None,
             is_archived: api.is_archived,
         }
@@ -268,6 +448,38 @@ impl ChatGptProvider {
 
         messages
     }
+
+    /// Get a conversation with its messages and the images they carry
+    pub async fn conversation_with_attachments(
+        &self,
+        id: &str,
+    ) -> Result<(Conversation, Vec<Message>, Vec<Attachment>)> {
+        let api: ApiConversation = self.api_get(&format!("/conversation/{}", id)).await?;
+
+        let conversation = Self::convert_conversation(&api, id);
+        let messages = Self::extract_messages(&api);
+        let attachments = Self::extract_attachments(&api, &messages);
+
+        Ok((conversation, messages, attachments))
+    }
+
+    /// Images of the messages kept from a conversation, in message order
+    fn extract_attachments(api: &ApiConversation, messages: &[Message]) -> Vec<Attachment> {
+        let by_id: HashMap<String, &ApiNodeMessage> = api
+            .mapping
+            .iter()
+            .filter_map(|(node_id, node)| {
+                let msg = node.message.as_ref()?;
+                Some((msg.id.clone().unwrap_or_else(|| node_id.clone()), msg))
+            })
+            .collect();
+
+        messages
+            .iter()
+            .filter_map(|message| Some(image_attachments(by_id.get(&message.id)?, &message.id)))
+            .flatten()
+            .collect()
+    }
 }
 
 impl Default for ChatGptProvider {
@@ -286,6 +498,18 @@ impl Provider for ChatGptProvider {
         self.token.read().await.is_some()
     }
 
+    async fn validate_credentials(&self) -> Result<()> {
+        // An expired token gets a session without a user, which fails to parse
+        self.account().await.map(|_| ()).map_err(|e| match e {
+            ProviderError::AuthFailed(_)
+            | ProviderError::Parse(_)
+            | ProviderError::TokenExpired => ProviderError::AuthFailed(
+                "ChatGPT no longer accepts the saved token; run `quaid chatgpt auth`".to_string(),
+            ),
+            _ => e,
+        })
+    }
+
     async fn authenticate(&mut self) -> Result<Account> {
         // Launch browser for user to log in
         // Create a persistent user data directory so Chrome looks like a real browser
@@ -369,18 +593,35 @@ impl Provider for ChatGptProvider {
                                 continue;
                             }
 
-                            if let Some(access_token) = value.get("accessToken").and_then(|v| v.as_str()) {
+                            if let Some(access_token) =
+                                value.get("accessToken").and_then(|v| v.as_str())
+                            {
                                 if !access_token.is_empty() {
                                     println!("Authentication successful!");
 
                                     // Extract user info from the same response
                                     let user = value.get("user");
                                     let account = Account {
-                                        id: user.and_then(|u| u.get("id")).and_then(|v| v.as_str()).unwrap_or("unknown").to_string(),
+                                        id: user
+                                            .and_then(|u| u.get("id"))
+                                            .and_then(|v| v.as_str())
+                                            .unwrap_or("unknown")
+                                            .to_string(),
                                         provider: ProviderId::chatgpt(),
-                                        email: user.and_then(|u| u.get("email")).and_then(|v| v.as_str()).unwrap_or("unknown").to_string(),
-                                        name: user.and_then(|u| u.get("name")).and_then(|v| v.as_str()).map(|s| s.to_string()),
-                                        avatar_url: user.and_then(|u| u.get("picture")).and_then(|v| v.as_str()).map(|s| s.to_string()),
+                                        email: user
+                                            .and_then(|u| u.get("email"))
+                                            .and_then(|v| v.as_str())
+                                            .unwrap_or("unknown")
+                                            .to_string(),
+                                        name: user
+                                            .and_then(|u| u.get("name"))
+                                            .and_then(|v| v.as_str())
+                                            .map(|s| s.to_string()),
+                                        avatar_url: user
+                                            .and_then(|u| u.get("picture"))
+                                            .and_then(|v| v.as_str())
+                                            .map(|s| s.to_string()),
+                                        workspace_id: None,
                                     };
 
                                     break (access_token.to_string(), account);
@@ -396,27 +637,26 @@ impl Provider for ChatGptProvider {
         };
 
         // Extract cookies from the browser using CDP (gets HttpOnly cookies too)
-        let cookies = page
-            .get_cookies()
-            .await
-            .ok()
-            .map(|cookies| {
-                cookies
-                    .into_iter()
-                    .filter(|c| c.domain.contains("chatgpt.com") || c.domain.contains("openai.com"))
-                    .map(|c| format!("{}={}", c.name, c.value))
-                    .collect::<Vec<_>>()
-                    .join("; ")
-            });
+        let cookies = page.get_cookies().await.ok().map(|cookies| {
+            cookies
+                .into_iter()
+                .filter(|c| c.domain.contains("chatgpt.com") || c.domain.contains("openai.com"))
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect::<Vec<_>>()
+                .join("; ")
+        });
 
-        // Store the token in memory and credential store
+        // Store the token in memory and under the account's credentials
         *self.token.write().await = Some(token.clone());
-        self.save_token(&token)?;
+        self.save_credential(KEYRING_USER_TOKEN, &account.email, &token)?;
+
+        // Workspaces are separate accounts, see `available_accounts`
+        *self.account_id.write().await = None;
 
         // Save cookies if we got them
         if let Some(ref cookie_str) = cookies {
             if !cookie_str.is_empty() {
-                self.save_cookies(cookie_str)?;
+                self.save_credential(KEYRING_USER_COOKIES, &account.email, cookie_str)?;
                 // Rebuild client with cookies
                 self.client = Self::build_client(Some(cookie_str));
             }
@@ -433,7 +673,7 @@ impl Provider for ChatGptProvider {
     async fn account(&self) -> Result<Account> {
         // Session endpoint is at base URL, not the backend-api
         let token = self.get_token().await?;
-        let url = format!("{}/api/auth/session", BASE_URL);
+        let url = format!("{}/api/auth/session", self.base_url);
 
         let response = self
             .client
@@ -443,7 +683,9 @@ impl Provider for ChatGptProvider {
             .await?;
 
         if !response.status().is_success() {
-            return Err(ProviderError::AuthFailed("Failed to fetch session".to_string()));
+            return Err(ProviderError::AuthFailed(
+                "Failed to fetch session".to_string(),
+            ));
         }
 
         let session: ApiSession = response
@@ -457,25 +699,25 @@ impl Provider for ChatGptProvider {
             email: session.user.email,
             name: Some(session.user.name),
             avatar_url: Some(session.user.picture),
+            workspace_id: None,
         })
     }
 
     async fn conversations(&self) -> Result<Vec<Conversation>> {
-        let items = self.fetch_all_conversations().await?;
+        let mut items: Vec<_> = self
+            .fetch_all_conversations(false)
+            .await?
+            .into_iter()
+            .map(|item| (item, false))
+            .collect();
+        if self.include_archived {
+            let archived = self.fetch_all_conversations(true).await?;
+            items.extend(archived.into_iter().map(|item| (item, true)));
+        }
 
         Ok(items
             .iter()
-            .map(|item| Conversation {
-                id: item.id.clone(),
-                provider_id: "chatgpt".to_string(),
-                title: item.title.clone(),
-                created_at: timestamp_to_datetime(item.create_time),
-                updated_at: timestamp_to_datetime(item.create_time), // API doesn't give update_time in list
-                model: None,
-                project_id: None,
-                project_name: None,
-                is_archived: false,
-            })
+            .map(|(item, is_archived)| Self::convert_list_item(item, *is_archived))
             .collect())
     }
 
@@ -490,14 +732,14 @@ impl Provider for ChatGptProvider {
 
     async fn project_conversations(&self, project_id: &str) -> Result<Vec<Conversation>> {
         let mut conversations = Vec::new();
-        let mut offset = 0;
+        let mut cursor = 0;
         let limit = 50;
 
         loop {
             let result: ApiProjectConversations = self
                 .api_get(&format!(
                     "/gizmos/{}/conversations?cursor={}&limit={}",
-                    project_id, offset, limit
+                    project_id, cursor, limit
                 ))
                 .await?;
 
@@ -507,65 +749,151 @@ impl Provider for ChatGptProvider {
 
             for item in &result.items {
                 conversations.push(Conversation {
-                    id: item.id.clone(),
-                    provider_id: "chatgpt".to_string(),
-                    title: item.title.clone(),
-                    created_at: timestamp_to_datetime(item.create_time),
-                    updated_at: timestamp_to_datetime(item.create_time),
-                    model: None,
                     project_id: Some(project_id.to_string()),
-                    project_name: None,
-                    is_archived: false,
+                    ..Self::convert_list_item(item, false)
                 });
             }
 
-            if result.cursor.is_none() {
-                break;
+            match result.cursor {
+                Some(next) if next > cursor => cursor = next,
+                _ => break,
             }
-
-            offset += limit;
         }
 
         Ok(conversations)
     }
 
-    async fn download_attachment(
-        &self,
-        attachment: &Attachment,
-        path: &Path,
-    ) -> Result<()> {
-        // Get signed download URL
+    async fn projects(&self) -> Result<Vec<Project>> {
+        let mut projects = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut endpoint = "/gizmos/snorlax/sidebar?conversations_per_gizmo=0".to_string();
+            if let Some(cursor) = &cursor {
+                endpoint.push_str(&format!("&cursor={}", cursor));
+            }
+            let result: ApiProjectsResponse = self.api_get(&endpoint).await?;
+
+            projects.extend(result.items.into_iter().filter_map(|item| {
+                let gizmo = item.gizmo.gizmo;
+                Some(Project {
+                    id: project_id(Some(&gizmo.id))?,
+                    provider_id: "chatgpt".to_string(),
+                    name: gizmo.display?.name,
+                    description: None,
+                    created_at: None,
+                })
+            }));
+
+            match result.cursor {
+                Some(next) if cursor.as_ref() != Some(&next) => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(projects)
+    }
+
+    async fn attachment_source(&self, attachment: &Attachment) -> Result<Option<DownloadSource>> {
+        // Files are served from short-lived signed URLs
         let file_id = attachment
             .download_url
             .strip_prefix("file-service://")
             .unwrap_or(&attachment.download_url);
 
-        let download_info: ApiFileDownload =
-            self.api_get(&format!("/files/{}/download", file_id)).await?;
+        let download_info: ApiFileDownload = self
+            .api_get(&format!("/files/{}/download", file_id))
+            .await?;
 
         match download_info {
             ApiFileDownload::Success { download_url, .. } => {
-                // Download the file
-                let response = self.client.get(&download_url).send().await?;
-                let bytes = response.bytes().await?;
-
-                // Write to path
-                tokio::fs::write(path, bytes)
-                    .await
-                    .map_err(|e| ProviderError::Api(format!("Failed to write file: {}", e)))?;
-
-                Ok(())
+                Ok(Some(DownloadSource::new(download_url)))
             }
             ApiFileDownload::Error { error_message, .. } => Err(ProviderError::Api(
                 error_message.unwrap_or_else(|| "Unknown download error".to_string()),
             )),
         }
     }
+
+    async fn download_attachment(&self, attachment: &Attachment, path: &Path) -> Result<()> {
+        let source = self
+            .attachment_source(attachment)
+            .await?
+            .ok_or_else(|| ProviderError::Api("No download URL".to_string()))?;
+
+        let response = download::resume(self.client.get(&source.url), path)
+            .await
+            .send()
+            .await?;
+        download::save_response(response, path, attachment.size_bytes).await
+    }
 }
 
 // Helper functions
 
 /// Find Chrome/Chromium executable on the system
+/// One account per active entry of an `/accounts/check` response
+///
+/// The personal entry is `user` itself. Each workspace becomes an account of
+/// its own with the same email, named after the workspace. The same entry can
+/// be listed under several keys (one of them `default`), so ids are deduplicated.
+/// Without any active entry, `user` alone is returned.
+pub fn accounts_from_check(user: &Account, check: &ApiAccountsCheck) -> Vec<Account> {
+    let mut keys: Vec<&String> = check.account_ordering.iter().collect();
+    let mut unordered: Vec<&String> = check
+        .accounts
+        .keys()
+        .filter(|key| !check.account_ordering.contains(key))
+        .collect();
+    unordered.sort();
+    keys.extend(unordered);
+
+    let mut personal = None;
+    let mut workspaces: Vec<Account> = Vec::new();
+    for key in keys {
+        let Some(info) = check.accounts.get(key) else {
+            continue;
+        };
+        let detail = &info.account;
+        if detail.is_deactivated {
+            continue;
+        }
+        if detail.structure.as_deref() != Some("workspace") {
+            personal.get_or_insert_with(|| user.clone());
+            continue;
+        }
+        let workspace_id = detail.account_id.as_ref().unwrap_or(key);
+        if workspaces
+            .iter()
+            .any(|a| a.workspace_id.as_ref() == Some(workspace_id))
+        {
+            continue;
+        }
+        workspaces.push(Account {
+            id: format!("{}-{}", user.id, workspace_id),
+            name: detail.name.clone(),
+            workspace_id: Some(workspace_id.clone()),
+            ..user.clone()
+        });
+    }
+
+    if personal.is_none() && workspaces.is_empty() {
+        return vec![user.clone()];
+    }
+    personal.into_iter().chain(workspaces).collect()
+}
+
+/// Cookie header for a pasted session cookie: as given when it names the
+/// cookie, which keeps every chunk of a split one, or else just its value
+fn session_cookie_header(cookie: &str) -> String {
+    let cookie = cookie.trim();
+    if cookie.contains(SESSION_COOKIE) {
+        cookie.to_string()
+    } else {
+        format!("{}={}", SESSION_COOKIE, cookie)
+    }
+}
+
 fn find_chrome() -> Option<std::path::PathBuf> {
     let candidates = if cfg!(target_os = "macos") {
         vec![
@@ -598,6 +926,13 @@ fn find_chrome() -> Option<std::path::PathBuf> {
     None
 }
 
+/// Projects are gizmos with a `g-p-` id; other gizmos are custom GPTs
+fn project_id(gizmo_id: Option<&str>) -> Option<String> {
+    gizmo_id
+        .filter(|id| id.starts_with("g-p-"))
+        .map(String::from)
+}
+
 fn timestamp_to_datetime(ts: f64) -> DateTime<Utc> {
     DateTime::from_timestamp(ts as i64, ((ts.fract()) * 1_000_000_000.0) as u32)
         .unwrap_or_else(Utc::now)
@@ -627,7 +962,15 @@ fn convert_api_message(msg: &ApiNodeMessage, node_id: &str) -> Option<Message> {
         _ => return None,
     };
 
-    let content = convert_content(&msg.content)?;
+    let aggregate = msg
+        .metadata
+        .as_ref()
+        .and_then(|m| m.aggregate_result.as_ref());
+    let content = match aggregate {
+        Some(result) => convert_execution(&msg.content, result, msg.author.name.as_deref())
+            .or_else(|| convert_content(&msg.content))?,
+        None => convert_content(&msg.content)?,
+    };
 
     Some(Message {
         id: msg.id.clone().unwrap_or_else(|| node_id.to_string()),
@@ -637,9 +980,134 @@ fn convert_api_message(msg: &ApiNodeMessage, node_id: &str) -> Option<Message> {
         content,
         created_at: msg.create_time.map(timestamp_to_datetime),
         model: msg.metadata.as_ref().and_then(|m| m.model_slug.clone()),
+        speaker: None,
     })
 }
 
+/// Attachments for the images in a message, with their dimensions
+///
+/// Covers image pointers among its parts (uploads and generated images) and
+/// the images a code-interpreter run drew. Pointers are downloaded through
+/// [`Provider::attachment_source`], so they're kept as the download URL.
+fn image_attachments(msg: &ApiNodeMessage, message_id: &str) -> Vec<Attachment> {
+    let mut images: Vec<(&str, Option<u32>, Option<u32>, u64)> = Vec::new();
+
+    let parts = msg.content.get("parts").and_then(|p| p.as_array());
+    for part in parts.into_iter().flatten() {
+        if part.get("content_type").and_then(|c| c.as_str()) != Some("image_asset_pointer") {
+            continue;
+        }
+        let number = |key: &str| part.get(key).and_then(|v| v.as_u64());
+        if let Some(pointer) = part.get("asset_pointer").and_then(|p| p.as_str()) {
+            images.push((
+                pointer,
+                number("width").map(|w| w as u32),
+                number("height").map(|h| h as u32),
+                number("size_bytes").unwrap_or(0),
+            ));
+        }
+    }
+
+    let executed = msg
+        .metadata
+        .as_ref()
+        .and_then(|m| m.aggregate_result.as_ref())
+        .and_then(|r| r.messages.as_ref());
+    for message in executed.into_iter().flatten() {
+        if message.message_type != "image" {
+            continue;
+        }
+        if let Some(url) = &message.image_url {
+            images.push((url, message.width, message.height, 0));
+        }
+    }
+
+    images
+        .into_iter()
+        .map(|(pointer, width, height, size_bytes)| {
+            let file_id = pointer.rsplit("://").next().unwrap_or(pointer);
+            Attachment {
+                id: file_id.to_string(),
+                message_id: message_id.to_string(),
+                filename: format!("{}.png", file_id),
+                mime_type: "image/png".to_string(),
+                size_bytes,
+                download_url: pointer.to_string(),
+                width,
+                height,
+                sha256: None,
+            }
+        })
+        .collect()
+}
+
+/// A code-interpreter run: the code, what it printed, the images it drew
+/// and a line with how it ended
+///
+/// `tool` is the tool that ran the code, `python` unless named otherwise.
+/// `None` when the result holds none of those.
+fn convert_execution(
+    content: &serde_json::Value,
+    result: &ApiAggregateResult,
+    tool: Option<&str>,
+) -> Option<MessageContent> {
+    let mut parts = Vec::new();
+
+    if let Some(code) = result.code.as_ref().filter(|c| !c.trim().is_empty()) {
+        parts.push(MessageContent::Code {
+            language: tool.unwrap_or("python").to_string(),
+            code: code.clone(),
+        });
+    }
+    let output = content.get("text").and_then(|t| t.as_str());
+    if let Some(output) = output.filter(|o| !o.trim().is_empty()) {
+        parts.push(MessageContent::Code {
+            language: "output".to_string(),
+            code: output.to_string(),
+        });
+    }
+    for message in result.messages.iter().flatten() {
+        if message.message_type != "image" {
+            continue;
+        }
+        if let Some(url) = &message.image_url {
+            let alt = match (message.width, message.height) {
+                (Some(width), Some(height)) => format!("Output image, {}x{}", width, height),
+                _ => "Output image".to_string(),
+            };
+            parts.push(MessageContent::Image {
+                url: url.clone(),
+                alt: Some(alt),
+            });
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+
+    let mut summary = vec![result.status.as_deref().unwrap_or("finished").to_string()];
+    if let (Some(start), Some(end)) = (result.start_time, result.end_time) {
+        summary.push(format!("{:.1}s", (end - start).max(0.0)));
+    }
+    if let Some(run_id) = &result.run_id {
+        summary.push(format!("run {}", run_id));
+    }
+    parts.push(MessageContent::Text {
+        text: format!("[execution] {}", summary.join(", ")),
+    });
+
+    Some(MessageContent::Mixed { parts })
+}
+
+/// The asset pointer of a voice recording part
+///
+/// Recordings from voice mode nest theirs next to a (usually empty) video
+/// pointer.
+fn audio_pointer(part: &serde_json::Value) -> Option<&str> {
+    let audio = part.get("audio_asset_pointer").unwrap_or(part);
+    audio.get("asset_pointer")?.as_str()
+}
+
 fn convert_content(content: &serde_json::Value) -> Option<MessageContent> {
     let content_type = content.get("content_type")?.as_str()?;
 
@@ -665,6 +1133,9 @@ fn convert_content(content: &serde_json::Value) -> Option<MessageContent> {
         "multimodal_text" => {
             let parts = content.get("parts")?.as_array()?;
             let mut message_parts = Vec::new();
+            // Voice messages carry what was said and the recording as
+            // separate parts, the transcription usually first
+            let mut transcript: Option<String> = None;
 
             for part in parts {
                 if let Some(text) = part.as_str() {
@@ -683,9 +1154,23 @@ fn convert_content(content: &serde_json::Value) -> Option<MessageContent> {
                             }
                         }
                         "audio_transcription" => {
-                            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
-                                message_parts.push(MessageContent::Text {
-                                    text: format!("[audio] {}", text),
+                            let Some(text) = part.get("text").and_then(|t| t.as_str()) else {
+                                continue;
+                            };
+                            // A recording that came first takes the transcription
+                            match message_parts.last_mut() {
+                                Some(MessageContent::Audio {
+                                    transcript: recording @ None,
+                                    ..
+                                }) => *recording = Some(text.to_string()),
+                                _ => transcript = Some(text.to_string()),
+                            }
+                        }
+                        "audio_asset_pointer" | "real_time_user_audio_video_asset_pointer" => {
+                            if let Some(url) = audio_pointer(part) {
+                                message_parts.push(MessageContent::Audio {
+                                    url: url.to_string(),
+                                    transcript: transcript.take(),
                                 });
                             }
                         }
@@ -694,6 +1179,13 @@ fn convert_content(content: &serde_json::Value) -> Option<MessageContent> {
                 }
             }
 
+            // Without a recording, the transcription is all there is
+            if let Some(text) = transcript {
+                message_parts.push(MessageContent::Text {
+                    text: format!("[audio] {}", text),
+                });
+            }
+
             if message_parts.len() == 1 {
                 Some(message_parts.remove(0))
             } else {
@@ -785,6 +1277,205 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_convert_voice_message_keeps_recording_and_transcript() {
+        let content = serde_json::json!({
+            "content_type": "multimodal_text",
+            "parts": [
+                {
+                    "content_type": "audio_transcription",
+                    "text": "What's the weather like in Porto?",
+                    "direction": "in"
+                },
+                {
+                    "content_type": "real_time_user_audio_video_asset_pointer",
+                    "audio_asset_pointer": {
+                        "content_type": "audio_asset_pointer",
+                        "asset_pointer": "sediment://file_00000000a1b2",
+                        "size_bytes": 96044,
+                        "format": "wav"
+                    },
+                    "video_container_asset_pointer": null
+                },
+                {
+                    "content_type": "audio_asset_pointer",
+                    "asset_pointer": "file-service://file-reply",
+                    "format": "wav"
+                },
+                {
+                    "content_type": "audio_transcription",
+                    "text": "Sunny, around 24 degrees.",
+                    "direction": "out"
+                }
+            ]
+        });
+
+        let MessageContent::Mixed { parts } = convert_content(&content).unwrap() else {
+            panic!("Expected Mixed content");
+        };
+        match parts.as_slice() {
+            [MessageContent::Audio {
+                url: asked,
+                transcript: question,
+            }, MessageContent::Audio {
+                url: answered,
+                transcript: answer,
+            }] => {
+                assert_eq!(asked, "sediment://file_00000000a1b2");
+                assert_eq!(
+                    question.as_deref(),
+                    Some("What's the weather like in Porto?")
+                );
+                assert_eq!(answered, "file-service://file-reply");
+                assert_eq!(answer.as_deref(), Some("Sunny, around 24 degrees."));
+            }
+            other => panic!("Unexpected parts: {:?}", other),
+        }
+
+        // The transcripts are what search indexes
+        let text = crate::storage::extract_text_content(&MessageContent::Mixed { parts });
+        assert!(text.contains("Porto") && text.contains("24 degrees"));
+
+        // A transcription without its recording stays text
+        let content = serde_json::json!({
+            "content_type": "multimodal_text",
+            "parts": [{"content_type": "audio_transcription", "text": "Hello?"}]
+        });
+        assert!(matches!(
+            convert_content(&content),
+            Some(MessageContent::Text { text }) if text == "[audio] Hello?"
+        ));
+    }
+
+    fn execution_message(aggregate_result: serde_json::Value) -> ApiNodeMessage {
+        serde_json::from_value(serde_json::json!({
+            "id": "msg-tool",
+            "author": {"role": "tool", "name": "python", "metadata": {}},
+            "content": {"content_type": "execution_output", "text": "   x   y\n0  1  2\n"},
+            "create_time": 1725512345.0,
+            "metadata": {"aggregate_result": aggregate_result},
+            "recipient": "all"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_execution_output_keeps_code_images_and_run() {
+        let msg = execution_message(serde_json::json!({
+            "status": "success",
+            "run_id": "run-42",
+            "start_time": 1725512340.0,
+            "end_time": 1725512342.5,
+            "code": "import pandas as pd\ndf.plot()",
+            "messages": [
+                {"message_type": "stream", "sender": "server", "time": 1725512341.0},
+                {
+                    "message_type": "image",
+                    "sender": "server",
+                    "image_url": "file-service://file-plot",
+                    "width": 800,
+                    "height": 600,
+                    "time": 1725512342.0
+                }
+            ]
+        }));
+
+        let message = convert_api_message(&msg, "node-1").unwrap();
+        assert_eq!(message.role, Role::Tool);
+        let MessageContent::Mixed { parts } = message.content else {
+            panic!("Expected Mixed content");
+        };
+        assert!(matches!(
+            &parts[0],
+            MessageContent::Code { language, code } if language == "python" && code.starts_with("import pandas")
+        ));
+        assert!(matches!(
+            &parts[1],
+            MessageContent::Code { language, code } if language == "output" && code.contains("x   y")
+        ));
+        assert!(matches!(
+            &parts[2],
+            MessageContent::Image { url, alt } if url == "file-service://file-plot"
+                && alt.as_deref() == Some("Output image, 800x600")
+        ));
+        assert!(matches!(
+            &parts[3],
+            MessageContent::Text { text } if text == "[execution] success, 2.5s, run run-42"
+        ));
+        assert_eq!(parts.len(), 4);
+    }
+
+    #[test]
+    fn test_images_become_attachments_with_dimensions() {
+        let upload: ApiNodeMessage = serde_json::from_value(serde_json::json!({
+            "id": "msg-user",
+            "author": {"role": "user", "metadata": {}},
+            "content": {
+                "content_type": "multimodal_text",
+                "parts": [
+                    "What's in this image?",
+                    {
+                        "content_type": "image_asset_pointer",
+                        "asset_pointer": "file-service://file-photo",
+                        "size_bytes": 48213,
+                        "width": 1024,
+                        "height": 768
+                    }
+                ]
+            },
+            "recipient": "all"
+        }))
+        .unwrap();
+        let attachments = image_attachments(&upload, "msg-user");
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].id, "file-photo");
+        assert_eq!(attachments[0].message_id, "msg-user");
+        assert_eq!(attachments[0].download_url, "file-service://file-photo");
+        assert_eq!(attachments[0].size_bytes, 48213);
+        assert_eq!(
+            (attachments[0].width, attachments[0].height),
+            (Some(1024), Some(768))
+        );
+
+        let run = execution_message(serde_json::json!({
+            "status": "success",
+            "messages": [{
+                "message_type": "image",
+                "image_url": "file-service://file-plot",
+                "width": 800,
+                "height": 600
+            }]
+        }));
+        let attachments = image_attachments(&run, "msg-tool");
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].filename, "file-plot.png");
+        assert_eq!(
+            (attachments[0].width, attachments[0].height),
+            (Some(800), Some(600))
+        );
+
+        assert!(image_attachments(&execution_message(serde_json::json!({})), "m").is_empty());
+    }
+
+    #[test]
+    fn test_execution_output_without_code_or_images() {
+        let msg = execution_message(serde_json::json!({"status": "success", "messages": []}));
+        let content = convert_api_message(&msg, "node-1").unwrap().content;
+        let MessageContent::Mixed { parts } = content else {
+            panic!("Expected Mixed content");
+        };
+        assert_eq!(parts.len(), 2);
+        assert!(matches!(&parts[0], MessageContent::Code { language, .. } if language == "output"));
+
+        let mut msg = execution_message(serde_json::json!({"status": "success", "messages": []}));
+        msg.content = serde_json::json!({"content_type": "execution_output", "text": ""});
+        msg.metadata = None;
+        assert!(matches!(
+            convert_api_message(&msg, "node-1").unwrap().content,
+            MessageContent::Code { language, code } if language == "output" && code.is_empty()
+        ));
+    }
+
     #[tokio::test]
     async fn test_provider_unauthenticated() {
         let provider = ChatGptProvider::with_credential_store(Arc::new(MockStore::new()));
@@ -806,11 +1497,251 @@ mod tests {
 
     #[tokio::test]
     async fn test_provider_with_stored_credentials() {
-        let store = MockStore::with_credentials(vec![
-            (KEYRING_SERVICE, KEYRING_USER_TOKEN, "stored-token"),
-        ]);
+        let store = MockStore::with_credentials(vec![(
+            KEYRING_SERVICE,
+            KEYRING_USER_TOKEN,
+            "stored-token",
+        )]);
         let provider = ChatGptProvider::with_credential_store(Arc::new(store));
         assert!(provider.is_authenticated().await);
         assert_eq!(provider.get_token().await.unwrap(), "stored-token");
     }
+
+    #[tokio::test]
+    async fn test_provider_loads_selected_account() {
+        let store =
+            MockStore::with_credentials(vec![(KEYRING_SERVICE, KEYRING_USER_TOKEN, "personal")]);
+        let work_email = "work@example.com";
+        for (user, value) in [
+            (KEYRING_USER_TOKEN, "work-token"),
+            (KEYRING_USER_WORKSPACE, "ws-1"),
+        ] {
+            store
+                .set_for_account(KEYRING_SERVICE, user, work_email, value)
+                .unwrap();
+        }
+        let store = Arc::new(store);
+
+        let work = ChatGptProvider::with_account_credentials(store.clone(), Some(work_email));
+        assert_eq!(work.get_token().await.unwrap(), "work-token");
+        assert_eq!(work.account_id.read().await.as_deref(), Some("ws-1"));
+
+        let personal = ChatGptProvider::with_account_credentials(store, Some("me@example.com"));
+        assert_eq!(personal.get_token().await.unwrap(), "personal");
+        assert!(personal.account_id.read().await.is_none());
+    }
+
+    fn user() -> Account {
+        Account {
+            id: "user-abc".to_string(),
+            provider: ProviderId::chatgpt(),
+            email: "me@example.com".to_string(),
+            name: Some("Me".to_string()),
+            avatar_url: None,
+            workspace_id: None,
+        }
+    }
+
+    fn accounts_check_fixture() -> ApiAccountsCheck {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/chatgpt-accounts-check.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_accounts_from_check() {
+        let accounts = accounts_from_check(&user(), &accounts_check_fixture());
+        let summary: Vec<_> = accounts
+            .iter()
+            .map(|a| (a.id.as_str(), a.workspace_id.as_deref(), a.name.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("user-abc", None, Some("Me")),
+                (
+                    "user-abc-e1a9b3c7-0000-4000-8000-000000000004",
+                    Some("e1a9b3c7-0000-4000-8000-000000000004"),
+                    Some("Research Lab")
+                ),
+                (
+                    "user-abc-9b7e5a10-0000-4000-8000-000000000002",
+                    Some("9b7e5a10-0000-4000-8000-000000000002"),
+                    Some("Acme")
+                ),
+            ]
+        );
+        assert!(accounts.iter().all(|a| a.email == "me@example.com"));
+    }
+
+    #[test]
+    fn test_accounts_from_empty_check_is_the_user() {
+        let check = ApiAccountsCheck {
+            accounts: Default::default(),
+            account_ordering: vec![],
+        };
+        let accounts = accounts_from_check(&user(), &check);
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].id, "user-abc");
+    }
+
+    #[tokio::test]
+    async fn test_stored_accounts_send_their_workspace() {
+        let store = Arc::new(MockStore::new());
+        let email = "me@example.com";
+        for (user, value) in [
+            (KEYRING_USER_TOKEN, "token"),
+            (KEYRING_USER_WORKSPACE, "ws-legacy"),
+        ] {
+            store
+                .set_for_account(KEYRING_SERVICE, user, email, value)
+                .unwrap();
+        }
+        let personal = user();
+        let accounts = accounts_from_check(&personal, &accounts_check_fixture());
+        let acme = &accounts[2];
+
+        // Before choosing, the personal account keeps the workspace it signed in to
+        let legacy = ChatGptProvider::with_stored_account(store.clone(), &personal);
+        assert_eq!(legacy.account_id.read().await.as_deref(), Some("ws-legacy"));
+        assert!(legacy.is_selected(acme));
+
+        legacy
+            .save_selection(email, &[personal.clone(), acme.clone()])
+            .unwrap();
+        let provider = ChatGptProvider::with_stored_account(store.clone(), &personal);
+        assert!(provider.account_id.read().await.is_none());
+        assert!(provider.is_selected(&personal));
+        assert!(provider.is_selected(acme));
+        assert!(!provider.is_selected(&accounts[1]));
+
+        let provider = ChatGptProvider::with_stored_account(store, acme);
+        assert_eq!(provider.get_token().await.unwrap(), "token");
+        assert_eq!(
+            provider.account_id.read().await.as_deref(),
+            acme.workspace_id.as_deref()
+        );
+    }
+
+    #[test]
+    fn test_list_item_uses_update_time() {
+        let json = r#"{
+            "items": [
+                {"id": "edited", "title": "Edited", "create_time": 1700000000.0, "update_time": 1710000000.5},
+                {"id": "untouched", "title": "Untouched", "create_time": 1700000000.0}
+            ],
+            "limit": 100,
+            "offset": 0,
+            "total": 2
+        }"#;
+        let response: ApiConversations = serde_json::from_str(json).unwrap();
+
+        let edited = ChatGptProvider::convert_list_item(&response.items[0], false);
+        assert_eq!(edited.created_at.timestamp(), 1_700_000_000);
+        assert_eq!(edited.updated_at.timestamp(), 1_710_000_000);
+
+        let untouched = ChatGptProvider::convert_list_item(&response.items[1], false);
+        assert_eq!(untouched.updated_at, untouched.created_at);
+    }
+
+    #[test]
+    fn test_project_ids_exclude_custom_gpts() {
+        let json = r#"{
+            "items": [
+                {"gizmo": {"gizmo": {"id": "g-p-abc", "display": {"name": "Thesis"}}}},
+                {"gizmo": {"gizmo": {"id": "g-xyz", "display": {"name": "Code Copilot"}}}}
+            ],
+            "cursor": null
+        }"#;
+        let response: ApiProjectsResponse = serde_json::from_str(json).unwrap();
+        let ids: Vec<_> = response
+            .items
+            .iter()
+            .filter_map(|item| project_id(Some(&item.gizmo.gizmo.id)))
+            .collect();
+        assert_eq!(ids, vec!["g-p-abc"]);
+        assert_eq!(project_id(None), None);
+    }
+
+    fn session_json() -> serde_json::Value {
+        serde_json::json!({
+            "accessToken": "access-1",
+            "expires": "2025-02-01T00:00:00.000Z",
+            "user": {
+                "id": "user-1",
+                "email": "me@example.com",
+                "name": "Me",
+                "picture": "https://example.com/me.png"
+            }
+        })
+    }
+
+    #[test]
+    fn test_session_cookie_header() {
+        assert_eq!(
+            session_cookie_header(" abc\n"),
+            "__Secure-next-auth.session-token=abc"
+        );
+        let split = "__Secure-next-auth.session-token.0=a; __Secure-next-auth.session-token.1=b";
+        assert_eq!(session_cookie_header(split), split);
+    }
+
+    #[tokio::test]
+    async fn test_session_cookie_is_exchanged_for_a_token() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/auth/session"))
+            .and(header(
+                "cookie",
+                "__Secure-next-auth.session-token=cookie-1",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(session_json()))
+            .mount(&server)
+            .await;
+
+        let store = MockStore::new();
+        let mut provider = ChatGptProvider::with_credential_store(Arc::new(store.clone()))
+            .with_base_url(&server.uri());
+        let account = provider
+            .authenticate_with_session_cookie("cookie-1")
+            .await
+            .unwrap();
+
+        assert_eq!(account.id, "user-1");
+        assert_eq!(provider.get_token().await.unwrap(), "access-1");
+        let saved = |user| {
+            store
+                .get_for_account(KEYRING_SERVICE, user, Some("me@example.com"))
+                .unwrap()
+        };
+        assert_eq!(saved(KEYRING_USER_TOKEN), "access-1");
+        assert_eq!(
+            saved(KEYRING_USER_COOKIES),
+            "__Secure-next-auth.session-token=cookie-1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_credentials_are_rejected() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // ChatGPT answers an expired session with an empty one
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/auth/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+
+        let mut provider = ChatGptProvider::with_credential_store(Arc::new(MockStore::new()))
+            .with_base_url(&server.uri());
+        let cookie = provider.authenticate_with_session_cookie("old").await;
+        assert!(matches!(cookie, Err(ProviderError::AuthFailed(m)) if m.contains("expired")));
+        let token = provider.authenticate_with_token("old-token").await;
+        assert!(matches!(token, Err(ProviderError::AuthFailed(m)) if m.contains("expired")));
+    }
 }
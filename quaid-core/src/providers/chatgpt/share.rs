@@ -0,0 +1,291 @@
+//! Import of public share links (`chatgpt.com/share/<id>`)
+//!
+//! A share page embeds the shared conversation in the shape the conversation
+//! endpoint returns, so no sign-in is needed. Older pages carry it in Next.js'
+//! `__NEXT_DATA__` script; newer ones stream it to React Router as a
+//! turbo-stream payload, a flattened JSON encoding, over
+//! `streamController.enqueue(...)` calls.
+
+use super::{ApiConversation, ChatGptProvider, BASE_URL};
+use crate::providers::{Conversation, Message, ProviderError, Result};
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Reported when a share page has no conversation, or answers 404
+const REMOVED: &str = "This share link was removed or has expired";
+
+/// Nesting past which a turbo-stream payload is taken to be malformed
+const MAX_DEPTH: usize = 256;
+
+/// The conversation embedded in a share page
+#[derive(Debug, Clone, Deserialize)]
+struct ApiSharedConversation {
+    /// Id of the conversation that was shared, as its owner syncs it
+    #[serde(default)]
+    conversation_id: Option<String>,
+    #[serde(flatten)]
+    conversation: ApiConversation,
+}
+
+/// Share id from a share link, or a bare id
+///
+/// Accepts `chatgpt.com` and `chat.openai.com` links, with or without a
+/// query string or trailing slash.
+pub fn share_id(url: &str) -> Option<String> {
+    let url = url.trim();
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let id = match path.split_once("/share/") {
+        Some((_, rest)) => rest.split('/').next().unwrap_or_default(),
+        None if !path.contains('/') => path,
+        None => return None,
+    };
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then(|| id.to_string())
+}
+
+/// Fetch the share page for `share_id` and parse its conversation
+pub async fn fetch_share(share_id: &str) -> Result<(Conversation, Vec<Message>)> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}/share/{}", BASE_URL, share_id))
+        .header(
+            header::USER_AGENT,
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+        )
+        .header(header::ACCEPT, "text/html")
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+        return Err(ProviderError::Api(REMOVED.to_string()));
+    }
+    if !status.is_success() {
+        return Err(ProviderError::Api(format!(
+            "{}: share page for {}",
+            status, share_id
+        )));
+    }
+
+    parse_share_page(share_id, &response.text().await?)
+}
+
+/// Parse the conversation out of a share page's HTML
+///
+/// It keeps the id of the conversation that was shared, so a share of a
+/// conversation already pulled is recognised as the same one; `share_id` is
+/// used only when the page doesn't say.
+pub fn parse_share_page(share_id: &str, html: &str) -> Result<(Conversation, Vec<Message>)> {
+    let payload = next_data(html)
+        .or_else(|| stream_payload(html))
+        .ok_or_else(|| ProviderError::Parse("No conversation data in share page".to_string()))?;
+
+    let data =
+        find_conversation(&payload).ok_or_else(|| ProviderError::Api(REMOVED.to_string()))?;
+    let shared: ApiSharedConversation = serde_json::from_value(data.clone())
+        .map_err(|e| ProviderError::Parse(format!("share {}: {}", share_id, e)))?;
+
+    let id = shared.conversation_id.as_deref().unwrap_or(share_id);
+    let conversation = ChatGptProvider::convert_conversation(&shared.conversation, id);
+    let messages = ChatGptProvider::extract_messages(&shared.conversation)
+        .into_iter()
+        .map(|message| Message {
+            conversation_id: id.to_string(),
+            ..message
+        })
+        .collect();
+    Ok((conversation, messages))
+}
+
+/// The JSON of the legacy `__NEXT_DATA__` script
+fn next_data(html: &str) -> Option<Value> {
+    let start = html.find("id=\"__NEXT_DATA__\"")?;
+    let body = &html[start..];
+    let body = &body[body.find('>')? + 1..];
+    let json = &body[..body.find("</script>")?];
+    serde_json::from_str(json).ok()
+}
+
+/// The decoded turbo-stream payload passed to `streamController.enqueue`
+///
+/// The payload can be split over several calls. Its first line holds the
+/// page data; later lines resolve deferred values, which share pages don't use.
+fn stream_payload(html: &str) -> Option<Value> {
+    const ENQUEUE: &str = "streamController.enqueue(";
+
+    let mut stream = String::new();
+    for (start, _) in html.match_indices(ENQUEUE) {
+        let literal = &html[start + ENQUEUE.len()..];
+        // The argument is a JSON string literal followed by `);`
+        if let Some(Ok(chunk)) = serde_json::Deserializer::from_str(literal)
+            .into_iter::<String>()
+            .next()
+        {
+            stream.push_str(&chunk);
+        }
+    }
+
+    let values: Vec<Value> = serde_json::from_str(stream.lines().next()?).ok()?;
+    Some(hydrate(&values, 0, 0))
+}
+
+/// Rebuild the value at `index` of a flattened turbo-stream payload
+///
+/// Objects map `_<key index>` to value indices and arrays list value indices;
+/// negative indices stand for values JSON can't hold. Dates keep their
+/// milliseconds; other tagged types aren't used by share pages and read as null.
+fn hydrate(values: &[Value], index: i64, depth: usize) -> Value {
+    if index < 0 || depth > MAX_DEPTH {
+        // -4 is negative zero; the rest are null, undefined, NaN, infinities and holes
+        return if index == -4 {
+            Value::from(0)
+        } else {
+            Value::Null
+        };
+    }
+    let Some(value) = values.get(index as usize) else {
+        return Value::Null;
+    };
+
+    match value {
+        Value::Array(items) => match items.first() {
+            Some(Value::String(tag)) if tag == "D" => items.get(1).cloned().unwrap_or_default(),
+            Some(Value::String(_)) => Value::Null,
+            _ => items
+                .iter()
+                .map(|item| hydrate(values, item.as_i64().unwrap_or(-5), depth + 1))
+                .collect(),
+        },
+        Value::Object(fields) => {
+            let mut object = Map::new();
+            for (key, item) in fields {
+                let key = key
+                    .strip_prefix('_')
+                    .and_then(|key| key.parse::<usize>().ok())
+                    .and_then(|key| values.get(key))
+                    .and_then(Value::as_str);
+                if let Some(key) = key {
+                    let item = hydrate(values, item.as_i64().unwrap_or(-5), depth + 1);
+                    object.insert(key.to_string(), item);
+                }
+            }
+            Value::Object(object)
+        }
+        other => other.clone(),
+    }
+}
+
+/// The first object holding a conversation mapping, searched depth first
+fn find_conversation(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Object(object) => {
+            if object.contains_key("mapping") && object.contains_key("current_node") {
+                return Some(value);
+            }
+            object.values().find_map(find_conversation)
+        }
+        Value::Array(items) => items.iter().find_map(find_conversation),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{MessageContent, Role};
+    use std::path::Path;
+
+    const SHARE_ID: &str = "6669a3c0-9c1f-8004-b5d6-e7f8a9b0c1d2";
+    const CONVERSATION_ID: &str = "6669a3c0-7d2e-8004-a1b2-3c4d5e6f7a8b";
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn assert_shared_conversation(conversation: &Conversation, messages: &[Message]) {
+        assert_eq!(conversation.id, CONVERSATION_ID);
+        assert_eq!(conversation.provider_id, "chatgpt");
+        assert_eq!(conversation.title, "Borrow checker and closures");
+        assert_eq!(conversation.created_at.timestamp(), 1718193600);
+        assert_eq!(conversation.model.as_deref(), Some("gpt-4o"));
+
+        // The hidden system message is left out
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::User);
+        assert_eq!(messages[1].role, Role::Assistant);
+        assert!(messages
+            .iter()
+            .all(|m| m.conversation_id == CONVERSATION_ID));
+        match &messages[1].content {
+            MessageContent::Text { text } => assert!(text.starts_with("`iter()` already holds")),
+            other => panic!("Expected text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_share_id() {
+        assert_eq!(
+            share_id("https://chatgpt.com/share/6669a3c0-9c1f-8004-b5d6-e7f8a9b0c1d2").as_deref(),
+            Some(SHARE_ID)
+        );
+        assert_eq!(
+            share_id("https://chat.openai.com/share/abc-123/?utm_source=x").as_deref(),
+            Some("abc-123")
+        );
+        assert_eq!(share_id("abc-123").as_deref(), Some("abc-123"));
+        assert_eq!(share_id("https://chatgpt.com/c/abc-123"), None);
+        assert_eq!(share_id("https://chatgpt.com/share/"), None);
+    }
+
+    #[test]
+    fn test_parse_next_data_page() {
+        let (conversation, messages) =
+            parse_share_page(SHARE_ID, &fixture("chatgpt-share-next-data.html")).unwrap();
+        assert_shared_conversation(&conversation, &messages);
+    }
+
+    #[test]
+    fn test_parse_streamed_page() {
+        let (conversation, messages) =
+            parse_share_page(SHARE_ID, &fixture("chatgpt-share-stream.html")).unwrap();
+        assert_shared_conversation(&conversation, &messages);
+    }
+
+    #[test]
+    fn test_removed_share() {
+        let next_data = r#"<script id="__NEXT_DATA__" type="application/json">{"props":{"pageProps":{"statusCode":404}},"page":"/_error"}</script>"#;
+        let stream = r#"<script>window.__reactRouterContext.streamController.enqueue("[{\"_1\":2},\"loaderData\",{\"_3\":-7},\"routes/share.$shareId.($action)\"]\n");</script>"#;
+
+        for html in [next_data, stream] {
+            match parse_share_page(SHARE_ID, html) {
+                Err(ProviderError::Api(message)) => assert_eq!(message, REMOVED),
+                other => panic!("Expected a removed share, got {:?}", other),
+            }
+        }
+        assert!(matches!(
+            parse_share_page(SHARE_ID, "<html>Just a moment...</html>"),
+            Err(ProviderError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_hydrate_turbo_stream() {
+        let values: Vec<Value> = serde_json::from_str(
+            r#"[{"_1":2,"_3":4,"_5":-5,"_6":7},"tags",[8,8],"when",["D",1718193600000],"gone","n",1.5,"rust"]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            hydrate(&values, 0, 0),
+            serde_json::json!({
+                "tags": ["rust", "rust"],
+                "when": 1718193600000i64,
+                "gone": null,
+                "n": 1.5
+            })
+        );
+    }
+}
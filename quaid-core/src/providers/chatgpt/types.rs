@@ -140,6 +140,8 @@ pub struct ApiConversationItem {
     pub create_time: f64,
     #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
     pub update_time: Option<f64>,
+    #[serde(default)]
+    pub gizmo_id: Option<String>,
 }
 
 /// Full conversation from /backend-api/conversation/:id
@@ -158,6 +160,9 @@ pub struct ApiConversation {
     pub moderation_results: Vec<serde_json::Value>,
     #[serde(default)]
     pub safe_urls: Vec<String>,
+    /// Project or custom GPT the conversation belongs to
+    #[serde(default)]
+    pub gizmo_id: Option<String>,
 }
 
 /// A node in the conversation graph
@@ -259,6 +264,8 @@ pub struct ApiExecutionMessage {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ApiProjectsResponse {
     pub items: Vec<ApiGizmoWrapper>,
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -0,0 +1,315 @@
+//! Claude Code session import
+//!
+//! Claude Code keeps each session on disk as a JSONL file under
+//! `~/.claude/projects/<project>/`, one entry per line, appended to as the
+//! session goes. User and assistant entries carry a message in the shape of
+//! the Messages API; tool results come back as user entries holding
+//! `tool_result` blocks. Reading the files is left to the caller.
+
+use super::tool_result_text;
+use super::types::ApiContentBlock;
+use crate::providers::{Conversation, Message, MessageContent, Role};
+use crate::titles::heuristic_title;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// One line of a session file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeEntry {
+    /// `user`, `assistant`, `system` or `summary`
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub uuid: Option<String>,
+    #[serde(default)]
+    pub parent_uuid: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Directory the session ran in
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Set on entries Claude Code adds itself, like slash command caveats
+    #[serde(default)]
+    pub is_meta: bool,
+    /// Set on the turns of a sub-agent
+    #[serde(default)]
+    pub is_sidechain: bool,
+    #[serde(default)]
+    pub message: Option<CodeMessage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CodeMessage {
+    pub role: String,
+    /// `<synthetic>` on errors Claude Code reports as assistant messages
+    #[serde(default)]
+    pub model: Option<String>,
+    pub content: CodeContent,
+}
+
+/// A user's typed prompt, or content blocks
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum CodeContent {
+    Text(String),
+    Blocks(Vec<ApiContentBlock>),
+}
+
+/// Parse a session file into a conversation per session id it holds
+///
+/// Lines that don't parse, like one still being written, are skipped, as are
+/// Claude Code's own meta entries and sub-agent turns. Thinking is kept ahead
+/// of the answer only with `include_thinking`.
+pub fn parse_session_file(
+    jsonl: &str,
+    include_thinking: bool,
+) -> Vec<(Conversation, Vec<Message>)> {
+    let mut order: Vec<String> = Vec::new();
+    let mut sessions: HashMap<String, Vec<CodeEntry>> = HashMap::new();
+    for line in jsonl.lines().filter(|line| !line.trim().is_empty()) {
+        let Ok(entry) = serde_json::from_str::<CodeEntry>(line) else {
+            continue;
+        };
+        let Some(session_id) = entry.session_id.clone() else {
+            continue;
+        };
+        if !sessions.contains_key(&session_id) {
+            order.push(session_id.clone());
+        }
+        sessions.entry(session_id).or_default().push(entry);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|id| {
+            let entries = sessions.remove(&id)?;
+            convert_session(&id, &entries, include_thinking)
+        })
+        .collect()
+}
+
+/// A session's conversation, or `None` if nothing in it was said
+fn convert_session(
+    session_id: &str,
+    entries: &[CodeEntry],
+    include_thinking: bool,
+) -> Option<(Conversation, Vec<Message>)> {
+    let messages: Vec<Message> = entries
+        .iter()
+        .filter_map(|entry| convert_entry(session_id, entry, include_thinking))
+        .collect();
+    if messages.is_empty() {
+        return None;
+    }
+
+    let created_at = messages.iter().filter_map(|m| m.created_at).min()?;
+    let updated_at = messages.iter().filter_map(|m| m.created_at).max()?;
+    let title = heuristic_title(&messages)
+        .or_else(|| entries.iter().find_map(|entry| entry.cwd.clone()))
+        .unwrap_or_else(|| "Untitled".to_string());
+
+    let conversation = Conversation {
+        id: session_id.to_string(),
+        provider_id: "claude-code".to_string(),
+        title,
+        created_at,
+        updated_at,
+        model: messages.iter().rev().find_map(|m| m.model.clone()),
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    };
+    Some((conversation, messages))
+}
+
+fn convert_entry(session_id: &str, entry: &CodeEntry, include_thinking: bool) -> Option<Message> {
+    if entry.is_meta || entry.is_sidechain || !matches!(entry.kind.as_str(), "user" | "assistant") {
+        return None;
+    }
+    let message = entry.message.as_ref()?;
+
+    let id = entry.uuid.clone()?;
+
+    let mut parts = Vec::new();
+    let blocks: &[ApiContentBlock] = match &message.content {
+        CodeContent::Text(text) => {
+            if !text.trim().is_empty() {
+                parts.push(MessageContent::Text { text: text.clone() });
+            }
+            &[]
+        }
+        CodeContent::Blocks(blocks) => blocks,
+    };
+    for block in blocks {
+        match block {
+            ApiContentBlock::Text { text } => {
+                if !text.trim().is_empty() {
+                    parts.push(MessageContent::Text { text: text.clone() });
+                }
+            }
+            ApiContentBlock::Thinking { thinking } => {
+                if include_thinking && !thinking.trim().is_empty() {
+                    parts.push(MessageContent::Thinking {
+                        text: thinking.clone(),
+                    });
+                }
+            }
+            ApiContentBlock::ToolUse { name, input, .. } => {
+                parts.push(MessageContent::Code {
+                    language: name.clone(),
+                    code: serde_json::to_string_pretty(input).unwrap_or_default(),
+                });
+            }
+            ApiContentBlock::ToolResult { content, .. } => {
+                let output = tool_result_text(content);
+                if !output.trim().is_empty() {
+                    parts.push(MessageContent::Code {
+                        language: "output".to_string(),
+                        code: output,
+                    });
+                }
+            }
+            ApiContentBlock::Unknown => {}
+        }
+    }
+
+    let role = if message.role == "assistant" {
+        Role::Assistant
+    } else if !blocks.is_empty()
+        && blocks
+            .iter()
+            .all(|block| matches!(block, ApiContentBlock::ToolResult { .. }))
+    {
+        Role::Tool
+    } else {
+        Role::User
+    };
+    let content = match parts.len() {
+        0 => return None,
+        1 => parts.remove(0),
+        _ => MessageContent::Mixed { parts },
+    };
+
+    Some(Message {
+        id,
+        conversation_id: session_id.to_string(),
+        parent_id: entry.parent_uuid.clone(),
+        role,
+        content,
+        created_at: entry.timestamp,
+        model: message
+            .model
+            .clone()
+            .filter(|model| !model.starts_with('<')),
+        speaker: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const SESSION_ID: &str = "3f2a9c1e-8b4d-4e6f-9a0b-1c2d3e4f5a6b";
+
+    fn fixture() -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join("claude-code-session.jsonl");
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_entries_are_grouped_by_session() {
+        let sessions = parse_session_file(&fixture(), false);
+        assert_eq!(sessions.len(), 2);
+
+        let (conversation, messages) = &sessions[0];
+        assert_eq!(conversation.id, SESSION_ID);
+        assert_eq!(conversation.provider_id, "claude-code");
+        assert_eq!(
+            conversation.title,
+            "The orders pagination test fails about one run"
+        );
+        assert_eq!(
+            conversation.model.as_deref(),
+            Some("claude-sonnet-4-20250514")
+        );
+        assert_eq!(
+            conversation.created_at.to_rfc3339(),
+            "2025-07-14T09:12:10.004+00:00"
+        );
+        assert_eq!(
+            conversation.updated_at.to_rfc3339(),
+            "2025-07-14T09:12:21.648+00:00"
+        );
+
+        // The /init meta entry, the thinking-only entry and the sub-agent
+        // prompt are left out
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].role, Role::User);
+        assert!(messages[0].parent_id.is_some());
+        assert!(messages.iter().all(|m| m.conversation_id == SESSION_ID));
+    }
+
+    #[test]
+    fn test_tool_use_and_results() {
+        let sessions = parse_session_file(&fixture(), false);
+        let messages = &sessions[0].1;
+
+        assert_eq!(messages[1].role, Role::Assistant);
+        assert_eq!(
+            messages[1].model.as_deref(),
+            Some("claude-sonnet-4-20250514")
+        );
+        match &messages[1].content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Text { text }, MessageContent::Code { language, code }] => {
+                    assert!(text.starts_with("Let me look"));
+                    assert_eq!(language, "Grep");
+                    assert!(code.contains("\"pattern\": \"ORDER BY created_at\""));
+                }
+                other => panic!("Unexpected parts: {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+
+        assert_eq!(messages[2].role, Role::Tool);
+        assert_eq!(messages[2].id, "0a1b2c3d-0000-4000-8000-000000000005");
+        match &messages[2].content {
+            MessageContent::Code { language, code } => {
+                assert_eq!(language, "output");
+                assert!(code.starts_with("src/orders/list.rs:42:"));
+            }
+            other => panic!("Expected tool output, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_thinking_is_kept_when_asked() {
+        let sessions = parse_session_file(&fixture(), true);
+        let messages = &sessions[0].1;
+        assert_eq!(messages.len(), 5);
+        assert!(matches!(
+            &messages[1].content,
+            MessageContent::Thinking { text } if text.starts_with("Flaky ordering")
+        ));
+    }
+
+    #[test]
+    fn test_partial_lines_and_synthetic_errors() {
+        let sessions = parse_session_file(&fixture(), false);
+        let (conversation, messages) = &sessions[1];
+
+        // The last line is cut off mid-write; the system entry isn't a message
+        assert_eq!(messages.len(), 2);
+        assert_eq!(conversation.title, "Bump the version");
+        // Errors Claude Code made up don't count as a model
+        assert!(messages[1].model.is_none());
+        assert!(conversation.model.is_none());
+    }
+}
@@ -2,56 +2,79 @@
 //!
 //! Syncs conversations from claude.ai using reverse-engineered API endpoints.
 
+pub mod code;
 pub mod types;
 
-use crate::credentials::{CredentialStore, KeyringStore};
+use crate::credentials::keys::claude::{COOKIES as KEYRING_USER_COOKIES, ORG as KEYRING_USER_ORG};
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
 use crate::providers::{
-    Account, Attachment, Conversation, Message, MessageContent, Provider, ProviderId,
-    ProviderError, Result, Role,
+    download, with_retry, Account, Attachment, Conversation, Message, MessageContent, Project,
+    Provider, ProviderError, ProviderId, Result, RetryPolicy, Role,
 };
 use async_trait::async_trait;
 use reqwest::{header, Client};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 use types::*;
 
 const API_BASE: &str = "https://claude.ai/api";
-const KEYRING_SERVICE: &str = "quaid";
-const KEYRING_USER_COOKIES: &str = "claude-cookies";
-const KEYRING_USER_ORG: &str = "claude-org-id";
+/// Conversations listed per request
+const CONVERSATIONS_PAGE: usize = 100;
 
 /// Claude.ai provider
 pub struct ClaudeProvider {
     client: Client,
+    api_base: String,
     cookies: Option<String>,
     org_id: Option<String>,
     #[allow(dead_code)]
     account: Option<ApiAccount>,
     credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Keep extended thinking blocks of assistant messages
+    include_thinking: bool,
 }
 
 impl ClaudeProvider {
     /// Create a new Claude provider, loading credentials from keyring if available
     pub fn new() -> Self {
-        Self::with_credential_store(Arc::new(KeyringStore::new()))
+        Self::with_credential_store(default_store())
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
     }
 
     /// Create with a custom credential store (for testing)
     pub fn with_credential_store(credential_store: Arc<dyn CredentialStore>) -> Self {
+        Self::with_account_credentials(credential_store, None)
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
         let cookies = credential_store
-            .get(KEYRING_SERVICE, KEYRING_USER_COOKIES)
+            .get_for_account(KEYRING_SERVICE, KEYRING_USER_COOKIES, email)
             .ok();
         let org_id = credential_store
-            .get(KEYRING_SERVICE, KEYRING_USER_ORG)
+            .get_for_account(KEYRING_SERVICE, KEYRING_USER_ORG, email)
             .ok();
         let client = build_client(cookies.as_deref());
 
         Self {
             client,
+            api_base: API_BASE.to_string(),
             cookies,
             org_id,
             account: None,
             credential_store,
+            retry: RetryPolicy::default(),
+            include_thinking: false,
         }
     }
 
@@ -62,51 +85,172 @@ impl ClaudeProvider {
         let client = build_client(cookies.as_deref());
         Self {
             client,
+            api_base: API_BASE.to_string(),
             cookies,
             org_id,
             account: None,
             credential_store: Arc::new(MockStore::new()),
+            retry: RetryPolicy::default(),
+            include_thinking: false,
         }
     }
 
+    /// Send API requests to `api_base` instead of claude.ai (for testing)
+    #[cfg(test)]
+    fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.to_string();
+        self
+    }
+
+    /// Keep extended thinking, which is left out by default so it doesn't
+    /// crowd search results and summaries
+    pub fn with_include_thinking(mut self, include_thinking: bool) -> Self {
+        self.include_thinking = include_thinking;
+        self
+    }
+
+    /// Use `org_id` instead of the stored or first listed organization
+    pub fn with_org_id(mut self, org_id: impl Into<String>) -> Self {
+        self.org_id = Some(org_id.into());
+        self
+    }
+
     /// Get the organization ID, fetching if not cached
     async fn get_org_id(&self) -> Result<String> {
         if let Some(ref org_id) = self.org_id {
             return Ok(org_id.clone());
         }
 
-        let url = format!("{}/organizations", API_BASE);
-        let resp = self.client.get(&url).send().await?;
+        let url = format!("{}/organizations", self.api_base);
+        let orgs: Vec<ApiOrganization> = self.get_json(&url).await?;
+
+        orgs.first()
+            .map(|o| o.uuid.clone())
+            .ok_or_else(|| ProviderError::Api("No organizations found".to_string()))
+    }
+
+    /// List every conversation of `org_id`, a page at a time
+    ///
+    /// Stops at the first short page, or one with nothing new in case the
+    /// endpoint ignores `offset` and repeats itself.
+    async fn fetch_all_conversations(&self, org_id: &str) -> Result<Vec<ApiConversationItem>> {
+        let mut conversations: Vec<ApiConversationItem> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut offset = 0;
+
+        loop {
+            let url = format!(
+                "{}/organizations/{}/chat_conversations?limit={}&offset={}",
+                self.api_base, org_id, CONVERSATIONS_PAGE, offset
+            );
+            let page: Vec<ApiConversationItem> = self.get_json(&url).await?;
+            let full = page.len() >= CONVERSATIONS_PAGE;
+            let before = conversations.len();
+            conversations.extend(page.into_iter().filter(|c| seen.insert(c.uuid.clone())));
+
+            if !full || conversations.len() == before {
+                break;
+            }
+            offset += CONVERSATIONS_PAGE;
+        }
+
+        Ok(conversations)
+    }
+
+    /// GET a JSON endpoint, retrying transient failures
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        with_retry(&self.retry, || self.get_json_once(url)).await
+    }
+
+    async fn get_json_once<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let resp = self.client.get(url).send().await?;
 
         let status = resp.status();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60);
+            return Err(ProviderError::RateLimited(retry_after));
+        }
+
         let body = resp.text().await?;
 
         if !status.is_success() {
             return Err(ProviderError::Api(format!(
-                "GET {} failed with {}: {}",
-                url,
+                "{}: GET {} failed: {}",
                 status,
+                url,
                 truncate_body(&body, 500)
             )));
         }
 
-        let orgs: Vec<ApiOrganization> = serde_json::from_str(&body).map_err(|e| {
+        serde_json::from_str(&body).map_err(|e| {
             ProviderError::Parse(format!(
-                "Failed to parse organizations: {}. Body: {}",
+                "Failed to parse {}: {}. Body: {}",
+                url,
                 e,
                 truncate_body(&body, 500)
             ))
-        })?;
+        })
+    }
 
-        orgs.first()
-            .map(|o| o.uuid.clone())
-            .ok_or_else(|| ProviderError::Api("No organizations found".to_string()))
+    /// Sign in with the Cookie header of a signed-in browser's claude.ai
+    /// request, for machines that can't open one
+    ///
+    /// The cookies are checked by fetching the organization and account
+    /// before they're saved under the account.
+    pub async fn authenticate_with_cookies(&mut self, cookies: &str) -> Result<Account> {
+        let cookies = cookies.trim();
+        if !cookies.contains("sessionKey=") {
+            return Err(ProviderError::AuthFailed(
+                "the cookies have no sessionKey; copy the whole Cookie header of a claude.ai request"
+                    .to_string(),
+            ));
+        }
+        self.cookies = Some(cookies.to_string());
+        self.client = build_client(Some(cookies));
+        self.org_id = None;
+
+        let org_id = match self.get_org_id().await {
+            Err(e) if matches!(e.http_status(), Some(401 | 403)) => {
+                return Err(ProviderError::AuthFailed(
+                    "claude.ai rejected the cookies; they may have expired, copy them again"
+                        .to_string(),
+                ));
+            }
+            result => result?,
+        };
+        self.org_id = Some(org_id);
+        let account = self.account().await?;
+        self.save_credentials(&account.email)?;
+        Ok(account)
+    }
+
+    /// Save the cookies and organization under `email`'s credentials, so
+    /// other accounts keep theirs
+    fn save_credentials(&self, email: &str) -> Result<()> {
+        let credentials = [
+            (KEYRING_USER_COOKIES, self.cookies.as_deref()),
+            (KEYRING_USER_ORG, self.org_id.as_deref()),
+        ];
+        for (user, value) in credentials {
+            let Some(value) = value else { continue };
+            self.credential_store
+                .set_for_account(KEYRING_SERVICE, user, email, value)
+                .map_err(|e| {
+                    ProviderError::AuthFailed(format!("Failed to save {}: {}", user, e))
+                })?;
+        }
+        Ok(())
     }
 
     /// Fetch user account info
     async fn fetch_account(&self) -> Result<ApiAccount> {
         // Try to get account info from the bootstrap endpoint
-        let url = format!("{}/bootstrap", API_BASE);
+        let url = format!("{}/bootstrap", self.api_base);
         let resp = self.client.get(&url).send().await?;
 
         let status = resp.status();
@@ -145,14 +289,29 @@ impl ClaudeProvider {
             created_at: api_conv.created_at,
             updated_at: api_conv.updated_at,
             model: api_conv.model.clone(),
-            project_id: api_conv.project_uuid.clone(),
-            project_name: None, // Would need separate project fetch
+            project_id: project_id(api_conv.project_uuid.as_ref(), api_conv.project.as_ref()),
+            project_name: project_name(api_conv.project.as_ref()),
             is_archived: false, // Claude doesn't seem to have this
         }
     }
 
+    /// Convert a conversation's messages in order, so artifact revisions
+    /// build on the versions before them
+    fn convert_messages(&self, conv_id: &str, api_msgs: &[ApiChatMessage]) -> Vec<Message> {
+        let mut artifacts = Artifacts::default();
+        api_msgs
+            .iter()
+            .map(|m| self.convert_message(conv_id, m, &mut artifacts))
+            .collect()
+    }
+
     /// Convert Claude API message to our domain model
-    fn convert_message(&self, conv_id: &str, api_msg: &ApiChatMessage) -> Message {
+    fn convert_message(
+        &self,
+        conv_id: &str,
+        api_msg: &ApiChatMessage,
+        artifacts: &mut Artifacts,
+    ) -> Message {
         let role = match api_msg.sender.as_str() {
             "human" => Role::User,
             "assistant" => Role::Assistant,
@@ -166,26 +325,59 @@ impl ClaudeProvider {
             }
         } else {
             // Has structured content - combine text with content blocks
-            let mut parts = vec![MessageContent::Text {
-                text: api_msg.text.clone(),
-            }];
+            let mut parts = Vec::new();
+            if !api_msg.text.is_empty() {
+                parts.push(MessageContent::Text {
+                    text: api_msg.text.clone(),
+                });
+            }
 
             for block in &api_msg.content {
                 match block {
                     ApiContentBlock::Text { text } => {
                         parts.push(MessageContent::Text { text: text.clone() });
                     }
+                    ApiContentBlock::Thinking { thinking } => {
+                        if self.include_thinking && !thinking.trim().is_empty() {
+                            parts.push(MessageContent::Thinking {
+                                text: thinking.clone(),
+                            });
+                        }
+                    }
                     ApiContentBlock::ToolUse { name, input, .. } => {
-                        parts.push(MessageContent::Code {
-                            language: name.clone(),
-                            code: serde_json::to_string_pretty(input).unwrap_or_default(),
+                        let artifact = (name == "artifacts")
+                            .then(|| serde_json::from_value::<ApiArtifact>(input.clone()).ok())
+                            .flatten();
+                        parts.push(match artifact {
+                            Some(artifact) => artifacts.document(artifact),
+                            None => MessageContent::Code {
+                                language: name.clone(),
+                                code: serde_json::to_string_pretty(input).unwrap_or_default(),
+                            },
                         });
                     }
-                    _ => {}
+                    ApiContentBlock::ToolResult { name, content, .. } => {
+                        // Artifact results only acknowledge the call
+                        if name.as_deref() == Some("artifacts") {
+                            continue;
+                        }
+                        let output = tool_result_text(content);
+                        if !output.trim().is_empty() {
+                            parts.push(MessageContent::Code {
+                                language: "output".to_string(),
+                                code: output,
+                            });
+                        }
+                    }
+                    ApiContentBlock::Unknown => {}
                 }
             }
 
-            if parts.len() == 1 {
+            if parts.is_empty() {
+                MessageContent::Text {
+                    text: String::new(),
+                }
+            } else if parts.len() == 1 {
                 parts.pop().unwrap()
             } else {
                 MessageContent::Mixed { parts }
@@ -200,9 +392,24 @@ impl ClaudeProvider {
             content,
             created_at: api_msg.created_at,
             model: None, // Model is at conversation level in Claude
+            speaker: None,
         }
     }
 
+    /// Knowledge documents uploaded to a project
+    pub async fn project_docs(&self, project_id: &str) -> Result<Vec<ApiProjectDoc>> {
+        if self.cookies.is_none() {
+            return Err(ProviderError::AuthRequired);
+        }
+
+        let org_id = self.get_org_id().await?;
+        let url = format!(
+            "{}/organizations/{}/projects/{}/docs",
+            self.api_base, org_id, project_id
+        );
+        self.get_json(&url).await
+    }
+
     /// Fetch a conversation with its attachments (for sync)
     pub async fn conversation_with_attachments(
         &self,
@@ -215,25 +422,13 @@ impl ClaudeProvider {
         let org_id = self.get_org_id().await?;
         let url = format!(
             "{}/organizations/{}/chat_conversations/{}",
-            API_BASE, org_id, id
+            self.api_base, org_id, id
         );
 
-        let api_conv: ApiConversation = self
-            .client
-            .get(&url)
-            .send()
-            .await?
-            .error_for_status()
-            .map_err(|e| ProviderError::Api(e.to_string()))?
-            .json()
-            .await?;
+        let api_conv: ApiConversation = self.get_json(&url).await?;
 
         let conversation = self.convert_conversation(&api_conv);
-        let messages: Vec<Message> = api_conv
-            .chat_messages
-            .iter()
-            .map(|m| self.convert_message(id, m))
-            .collect();
+        let messages = self.convert_messages(id, &api_conv.chat_messages);
         let attachments = self.extract_attachments(&api_conv);
 
         Ok((conversation, messages, attachments))
@@ -254,6 +449,9 @@ impl ClaudeProvider {
                         mime_type: file.mime_type(),
                         size_bytes: file.file_size.unwrap_or(0),
                         download_url: uuid.to_string(), // We use file_uuid as the download identifier
+                        width: None,
+                        height: None,
+                        sha256: None,
                     });
                 }
             }
@@ -272,9 +470,15 @@ impl ClaudeProvider {
                         id: id.clone(),
                         message_id: msg.uuid.clone(),
                         filename: att.file_name.clone(),
-                        mime_type: att.file_type.clone().unwrap_or_else(|| "application/octet-stream".to_string()),
+                        mime_type: att
+                            .file_type
+                            .clone()
+                            .unwrap_or_else(|| "application/octet-stream".to_string()),
                         size_bytes: att.file_size.unwrap_or(0),
                         download_url: id.clone(),
+                        width: None,
+                        height: None,
+                        sha256: None,
                     });
                 }
             }
@@ -300,6 +504,29 @@ impl Provider for ClaudeProvider {
         self.cookies.is_some() && self.org_id.is_some()
     }
 
+    async fn validate_credentials(&self) -> Result<()> {
+        if self.cookies.is_none() {
+            return Err(ProviderError::AuthRequired);
+        }
+
+        let url = format!("{}/organizations", self.api_base);
+        let orgs: Vec<ApiOrganization> = match self.get_json(&url).await {
+            Err(e) if matches!(e.http_status(), Some(401 | 403)) => {
+                return Err(ProviderError::AuthFailed(
+                    "claude.ai no longer accepts the saved session; run `quaid claude auth`"
+                        .to_string(),
+                ));
+            }
+            result => result?,
+        };
+        if orgs.is_empty() {
+            return Err(ProviderError::AuthFailed(
+                "The signed-in claude.ai account has no organizations".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     async fn authenticate(&mut self) -> Result<Account> {
         // Browser-based authentication flow
         use chromiumoxide::browser::{Browser, BrowserConfig};
@@ -387,15 +614,6 @@ impl Provider for ClaudeProvider {
                 // Fetch org ID
                 let org_id = self.get_org_id().await?;
                 self.org_id = Some(org_id.clone());
-
-                // Save to credential store
-                if let Err(e) = self.credential_store.set(KEYRING_SERVICE, KEYRING_USER_COOKIES, cookie_str) {
-                    eprintln!("Warning: failed to save cookies: {}", e);
-                }
-                if let Err(e) = self.credential_store.set(KEYRING_SERVICE, KEYRING_USER_ORG, &org_id) {
-                    eprintln!("Warning: failed to save org ID: {}", e);
-                }
-
                 println!("Authentication successful!");
             }
         }
@@ -406,7 +624,11 @@ impl Provider for ClaudeProvider {
             ));
         }
 
-        self.account().await
+        let account = self.account().await?;
+        if let Err(e) = self.save_credentials(&account.email) {
+            eprintln!("Warning: {}", e);
+        }
+        Ok(account)
     }
 
     async fn account(&self) -> Result<Account> {
@@ -419,9 +641,13 @@ impl Provider for ClaudeProvider {
         Ok(Account {
             id: api_account.uuid.clone(),
             provider: ProviderId::claude(),
-            email: api_account.email.clone().unwrap_or_else(|| "unknown".to_string()),
+            email: api_account
+                .email
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
             name: api_account.best_name(),
             avatar_url: api_account.avatar_url,
+            workspace_id: None,
         })
     }
 
@@ -431,17 +657,7 @@ impl Provider for ClaudeProvider {
         }
 
         let org_id = self.get_org_id().await?;
-        let url = format!("{}/organizations/{}/chat_conversations", API_BASE, org_id);
-
-        let api_convs: Vec<ApiConversationItem> = self
-            .client
-            .get(&url)
-            .send()
-            .await?
-            .error_for_status()
-            .map_err(|e| ProviderError::Api(e.to_string()))?
-            .json()
-            .await?;
+        let api_convs = self.fetch_all_conversations(&org_id).await?;
 
         let conversations = api_convs
             .iter()
@@ -452,8 +668,8 @@ impl Provider for ClaudeProvider {
                 created_at: c.created_at,
                 updated_at: c.updated_at,
                 model: c.model.clone(),
-                project_id: c.project_uuid.clone(),
-                project_name: None,
+                project_id: project_id(c.project_uuid.as_ref(), c.project.as_ref()),
+                project_name: project_name(c.project.as_ref()),
                 is_archived: false,
             })
             .collect();
@@ -469,25 +685,13 @@ impl Provider for ClaudeProvider {
         let org_id = self.get_org_id().await?;
         let url = format!(
             "{}/organizations/{}/chat_conversations/{}",
-            API_BASE, org_id, id
+            self.api_base, org_id, id
         );
 
-        let api_conv: ApiConversation = self
-            .client
-            .get(&url)
-            .send()
-            .await?
-            .error_for_status()
-            .map_err(|e| ProviderError::Api(e.to_string()))?
-            .json()
-            .await?;
+        let api_conv: ApiConversation = self.get_json(&url).await?;
 
         let conversation = self.convert_conversation(&api_conv);
-        let messages: Vec<Message> = api_conv
-            .chat_messages
-            .iter()
-            .map(|m| self.convert_message(id, m))
-            .collect();
+        let messages = self.convert_messages(id, &api_conv.chat_messages);
 
         Ok((conversation, messages))
     }
@@ -501,38 +705,35 @@ impl Provider for ClaudeProvider {
             .collect())
     }
 
-    async fn download_attachment(
-        &self,
-        attachment: &Attachment,
-        path: &Path,
-    ) -> Result<()> {
+    async fn projects(&self) -> Result<Vec<Project>> {
         if self.cookies.is_none() {
             return Err(ProviderError::AuthRequired);
         }
 
         let org_id = self.get_org_id().await?;
+        let url = format!("{}/organizations/{}/projects", self.api_base, org_id);
+        let projects: Vec<ApiProject> = self.get_json(&url).await?;
 
-        // The download_url should be the file_uuid
-        // URL pattern: /api/{org_id}/files/{file_uuid}/preview
-        let file_uuid = &attachment.download_url;
-        let url = format!("{}/{}/files/{}/preview", API_BASE, org_id, file_uuid);
-
-        let response = self.client.get(&url).send().await?;
+        Ok(projects.into_iter().map(convert_project).collect())
+    }
 
-        if !response.status().is_success() {
-            return Err(ProviderError::Api(format!(
-                "Failed to download file: {}",
-                response.status()
-            )));
+    async fn download_attachment(&self, attachment: &Attachment, path: &Path) -> Result<()> {
+        if self.cookies.is_none() {
+            return Err(ProviderError::AuthRequired);
         }
 
-        let bytes = response.bytes().await?;
+        let org_id = self.get_org_id().await?;
 
-        tokio::fs::write(path, bytes)
-            .await
-            .map_err(|e| ProviderError::Api(format!("Failed to write file: {}", e)))?;
+        // The download_url should be the file_uuid
+        // URL pattern: /api/{org_id}/files/{file_uuid}/preview
+        let file_uuid = &attachment.download_url;
+        let url = format!("{}/{}/files/{}/preview", self.api_base, org_id, file_uuid);
 
-        Ok(())
+        let response = download::resume(self.client.get(&url), path)
+            .await
+            .send()
+            .await?;
+        download::save_response(response, path, attachment.size_bytes).await
     }
 }
 
@@ -550,11 +751,11 @@ fn build_client(cookies: Option<&str>) -> Client {
         header::ACCEPT,
         "application/json, text/plain, */*".parse().unwrap(),
     );
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
     headers.insert(
-        header::ACCEPT_LANGUAGE,
-        "en-US,en;q=0.9".parse().unwrap(),
+        header::ACCEPT_ENCODING,
+        "gzip, deflate, br".parse().unwrap(),
     );
-    headers.insert(header::ACCEPT_ENCODING, "gzip, deflate, br".parse().unwrap());
     headers.insert("Sec-Fetch-Dest", "empty".parse().unwrap());
     headers.insert("Sec-Fetch-Mode", "cors".parse().unwrap());
     headers.insert("Sec-Fetch-Site", "same-origin".parse().unwrap());
@@ -624,6 +825,168 @@ fn find_chrome() -> Option<std::path::PathBuf> {
     None
 }
 
+/// The project a conversation is in, from its id or its embedded project
+fn project_id(uuid: Option<&String>, project: Option<&ApiProjectRef>) -> Option<String> {
+    uuid.or(project.map(|p| &p.uuid)).cloned()
+}
+
+/// The project's name, when the conversation embeds it; otherwise it's named
+/// from the projects table on save
+fn project_name(project: Option<&ApiProjectRef>) -> Option<String> {
+    project.and_then(|p| p.name.clone())
+}
+
+fn convert_project(api: ApiProject) -> Project {
+    Project {
+        id: api.uuid,
+        provider_id: "claude".to_string(),
+        name: api.name,
+        description: api.description.filter(|d| !d.trim().is_empty()),
+        created_at: api.created_at,
+    }
+}
+
+/// Latest version of each artifact in a conversation
+#[derive(Debug, Default)]
+struct Artifacts {
+    latest: HashMap<String, ArtifactVersion>,
+}
+
+#[derive(Debug, Clone)]
+struct ArtifactVersion {
+    title: String,
+    mime: String,
+    text: String,
+    revision: u32,
+}
+
+impl Artifacts {
+    /// The whole document after an artifact call
+    ///
+    /// Every version is a document of its own, so revisions read as full
+    /// texts instead of piling up in one. An update whose base isn't known
+    /// keeps just the replacement text.
+    fn document(&mut self, artifact: ApiArtifact) -> MessageContent {
+        let previous = self.latest.get(&artifact.id);
+        let revision = previous.map_or(1, |p| p.revision + 1);
+        let title = artifact
+            .title
+            .clone()
+            .or_else(|| previous.map(|p| p.title.clone()))
+            .unwrap_or_else(|| artifact.id.clone());
+        let mime = match artifact.artifact_type.as_deref() {
+            Some("application/vnd.ant.code") => artifact
+                .language
+                .as_ref()
+                .map(|language| format!("text/x-{}", language.to_lowercase()))
+                .unwrap_or_else(|| "text/plain".to_string()),
+            Some(mime) => mime.to_string(),
+            None => previous.map_or_else(|| "text/plain".to_string(), |p| p.mime.clone()),
+        };
+
+        let edit = artifact.old_str.as_deref().zip(artifact.new_str.as_deref());
+        let (text, excerpt) = match (artifact.command.as_deref(), edit, previous) {
+            (Some("update"), Some((old, new)), Some(previous)) if previous.text.contains(old) => {
+                (previous.text.replacen(old, new, 1), false)
+            }
+            (Some("update"), Some((_, new)), _) => (new.to_string(), true),
+            _ => (artifact.content.unwrap_or_default(), false),
+        };
+
+        let heading = match (revision, excerpt) {
+            (1, false) => title.clone(),
+            (_, false) => format!("{} (revision {})", title, revision),
+            (_, true) => format!("{} (partial revision)", title),
+        };
+        if !excerpt {
+            self.latest.insert(
+                artifact.id,
+                ArtifactVersion {
+                    title,
+                    mime: mime.clone(),
+                    text: text.clone(),
+                    revision,
+                },
+            );
+        } else if let Some(version) = self.latest.get_mut(&artifact.id) {
+            version.revision = revision;
+        }
+
+        MessageContent::Document {
+            title: heading,
+            mime,
+            text,
+        }
+    }
+}
+
+/// Text of a tool result, given as a string or a list of text blocks
+fn tool_result_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Id of the conversation holding a project's knowledge documents
+pub fn project_docs_id(project_id: &str) -> String {
+    format!("project-docs-{}", project_id)
+}
+
+/// A project's knowledge documents as one conversation, a message per document
+///
+/// The conversation's id is derived from the project's, so it's replaced on
+/// the next pull. `None` for a project without documents.
+pub fn project_docs_conversation(
+    project: &Project,
+    docs: &[ApiProjectDoc],
+) -> Option<(Conversation, Vec<Message>)> {
+    if docs.is_empty() {
+        return None;
+    }
+    let id = project_docs_id(&project.id);
+    let dates = || docs.iter().filter_map(|d| d.created_at);
+    // Undated documents get a fixed date, so they aren't seen as changed each pull
+    let created_at = project
+        .created_at
+        .or_else(|| dates().min())
+        .unwrap_or_default();
+    let updated_at = dates().max().unwrap_or(created_at).max(created_at);
+
+    let conversation = Conversation {
+        id: id.clone(),
+        provider_id: "claude".to_string(),
+        title: format!("{} (project knowledge)", project.name),
+        created_at,
+        updated_at,
+        model: None,
+        project_id: Some(project.id.clone()),
+        project_name: Some(project.name.clone()),
+        is_archived: false,
+    };
+    let messages = docs
+        .iter()
+        .map(|doc| Message {
+            id: format!("{}-{}", id, doc.uuid),
+            conversation_id: id.clone(),
+            parent_id: None,
+            role: Role::System,
+            content: MessageContent::Text {
+                text: format!("# {}\n\n{}", doc.file_name, doc.content),
+            },
+            created_at: doc.created_at,
+            model: None,
+            speaker: None,
+        })
+        .collect();
+    Some((conversation, messages))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -666,7 +1029,7 @@ mod tests {
             content: vec![],
         };
 
-        let msg = provider.convert_message("conv-1", &api_msg);
+        let msg = provider.convert_message("conv-1", &api_msg, &mut Artifacts::default());
         assert_eq!(msg.role, Role::User);
         assert_eq!(msg.id, "msg-1");
         assert_eq!(msg.conversation_id, "conv-1");
@@ -686,7 +1049,7 @@ mod tests {
             content: vec![],
         };
 
-        let msg = provider.convert_message("conv-1", &api_msg);
+        let msg = provider.convert_message("conv-1", &api_msg, &mut Artifacts::default());
         assert_eq!(msg.role, Role::Assistant);
         match msg.content {
             MessageContent::Text { text } => assert!(text.contains("How can I help")),
@@ -707,6 +1070,7 @@ mod tests {
             summary: None,
             model: Some("claude-3-opus".to_string()),
             project_uuid: Some("proj-1".to_string()),
+            project: None,
         };
 
         let conv = provider.convert_conversation(&api_conv);
@@ -717,6 +1081,238 @@ mod tests {
         assert_eq!(conv.project_id, Some("proj-1".to_string()));
     }
 
+    fn fixture<T: serde::de::DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_list_items_name_their_project() {
+        let items: Vec<ApiConversationItem> = serde_json::from_str(
+            r#"[
+                {"uuid": "c-1", "name": "Ingress", "created_at": "2025-01-16T12:00:00Z",
+                 "updated_at": "2025-01-16T12:00:00Z",
+                 "project": {"uuid": "p-1", "name": "Kubernetes migration"}},
+                {"uuid": "c-2", "name": "Loose", "created_at": "2025-01-16T12:00:00Z",
+                 "updated_at": "2025-01-16T12:00:00Z", "project_uuid": "p-2"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            project_id(items[0].project_uuid.as_ref(), items[0].project.as_ref()).as_deref(),
+            Some("p-1")
+        );
+        assert_eq!(
+            project_name(items[0].project.as_ref()).as_deref(),
+            Some("Kubernetes migration")
+        );
+        assert_eq!(
+            project_id(items[1].project_uuid.as_ref(), items[1].project.as_ref()).as_deref(),
+            Some("p-2")
+        );
+        assert_eq!(project_name(items[1].project.as_ref()), None);
+    }
+
+    #[test]
+    fn test_projects_are_converted_and_stored() {
+        let projects: Vec<Project> = fixture::<Vec<ApiProject>>("claude-projects.json")
+            .into_iter()
+            .map(convert_project)
+            .collect();
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0].name, "Kubernetes migration");
+        assert_eq!(
+            projects[0].description.as_deref(),
+            Some("Moving the monolith onto the new cluster")
+        );
+        assert_eq!(projects[1].description, None);
+
+        let store = crate::Store::in_memory().unwrap();
+        let account = Account {
+            id: "user-1".to_string(),
+            provider: ProviderId::claude(),
+            email: "me@example.com".to_string(),
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        };
+        store.save_account(&account).unwrap();
+        let provider = ClaudeProvider::with_credentials(None, None);
+        let now = Utc::now();
+        let conv = provider.convert_conversation(&ApiConversation {
+            uuid: "conv-1".to_string(),
+            name: "Ingress".to_string(),
+            created_at: now,
+            updated_at: now,
+            chat_messages: vec![],
+            summary: None,
+            model: None,
+            project_uuid: Some(projects[0].id.clone()),
+            project: None,
+        });
+        store.save_conversation(&account.id, &conv).unwrap();
+        for project in &projects {
+            store.save_project(project).unwrap();
+        }
+
+        assert_eq!(
+            store
+                .get_conversation("conv-1")
+                .unwrap()
+                .unwrap()
+                .project_name
+                .as_deref(),
+            Some("Kubernetes migration")
+        );
+        let mut stored = store.list_projects(Some("claude")).unwrap();
+        stored.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(stored, projects);
+    }
+
+    #[test]
+    fn test_project_docs_become_a_conversation() {
+        let project = convert_project(fixture::<Vec<ApiProject>>("claude-projects.json").remove(0));
+        let docs: Vec<ApiProjectDoc> = fixture("claude-project-docs.json");
+
+        let (conv, messages) = project_docs_conversation(&project, &docs).unwrap();
+        assert_eq!(conv.id, format!("project-docs-{}", project.id));
+        assert_eq!(conv.title, "Kubernetes migration (project knowledge)");
+        assert_eq!(conv.project_name.as_deref(), Some("Kubernetes migration"));
+        assert_eq!(Some(conv.created_at), project.created_at);
+        assert_eq!(Some(conv.updated_at), docs[1].created_at);
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages
+            .iter()
+            .all(|m| m.conversation_id == conv.id && m.role == Role::System));
+        match &messages[0].content {
+            MessageContent::Text { text } => {
+                assert!(
+                    text.starts_with("# runbook.md\n\nDrain the node"),
+                    "{}",
+                    text
+                )
+            }
+            other => panic!("Expected text, got {:?}", other),
+        }
+
+        assert!(project_docs_conversation(&project, &[]).is_none());
+    }
+
+    #[test]
+    fn test_artifacts_become_a_document_per_revision() {
+        let provider = ClaudeProvider::with_credentials(None, None);
+        let api: ApiConversation = fixture("claude-artifact-conversation.json");
+        let messages = provider.convert_messages(&api.uuid, &api.chat_messages);
+        assert_eq!(messages.len(), 4);
+        assert!(matches!(
+            &messages[0].content,
+            MessageContent::Text { text } if text.starts_with("Write a small Python client")
+        ));
+
+        // The artifact call's acknowledgement is left out
+        let MessageContent::Mixed { parts } = &messages[1].content else {
+            panic!("Expected Mixed content");
+        };
+        assert_eq!(parts.len(), 3);
+        match &parts[1] {
+            MessageContent::Document { title, mime, text } => {
+                assert_eq!(title, "Status API client");
+                assert_eq!(mime, "text/x-python");
+                assert!(text.starts_with("import requests\n"));
+            }
+            other => panic!("Expected a document, got {:?}", other),
+        }
+
+        let MessageContent::Mixed { parts } = &messages[3].content else {
+            panic!("Expected Mixed content");
+        };
+        assert!(matches!(
+            &parts[1],
+            MessageContent::Code { language, code } if language == "output" && code.contains("Retry-After")
+        ));
+        let documents: Vec<(&str, &str)> = parts
+            .iter()
+            .filter_map(|part| match part {
+                MessageContent::Document { title, text, .. } => {
+                    Some((title.as_str(), text.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].0, "Status API client (revision 2)");
+        assert!(documents[0].1.contains("for attempt in range(3)"));
+        assert_eq!(documents[0].1.matches("def fetch_status").count(), 1);
+        assert_eq!(documents[1].0, "Status API client (revision 3)");
+        assert!(documents[1]
+            .1
+            .starts_with("import time\n\nimport requests\n"));
+        assert!(documents[1].1.contains("time.sleep(2 ** attempt)"));
+    }
+
+    #[test]
+    fn test_update_of_unknown_artifact_keeps_the_replacement() {
+        let mut artifacts = Artifacts::default();
+        let update: ApiArtifact = serde_json::from_value(serde_json::json!({
+            "id": "notes",
+            "command": "update",
+            "old_str": "draft",
+            "new_str": "final"
+        }))
+        .unwrap();
+
+        match artifacts.document(update) {
+            MessageContent::Document { title, mime, text } => {
+                assert_eq!(title, "notes (partial revision)");
+                assert_eq!(mime, "text/plain");
+                assert_eq!(text, "final");
+            }
+            other => panic!("Expected a document, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_thinking_is_kept_only_when_asked_for() {
+        let api_msg: ApiChatMessage = serde_json::from_value(serde_json::json!({
+            "uuid": "msg-3",
+            "sender": "assistant",
+            "text": "",
+            "content": [
+                {"type": "thinking", "thinking": "They want the sum; 2 + 2 is 4."},
+                {"type": "text", "text": "4"}
+            ]
+        }))
+        .unwrap();
+
+        let without = ClaudeProvider::with_credentials(None, None).convert_message(
+            "conv-1",
+            &api_msg,
+            &mut Artifacts::default(),
+        );
+        match without.content {
+            MessageContent::Text { text } => assert_eq!(text, "4"),
+            other => panic!("Expected Text content, got {:?}", other),
+        }
+
+        let with = ClaudeProvider::with_credentials(None, None)
+            .with_include_thinking(true)
+            .convert_message("conv-1", &api_msg, &mut Artifacts::default());
+        match with.content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Thinking { text: thinking }, MessageContent::Text { text }] => {
+                    assert_eq!(thinking, "They want the sum; 2 + 2 is 4.");
+                    assert_eq!(text, "4");
+                }
+                other => panic!("Expected thinking then text, got {:?}", other),
+            },
+            other => panic!("Expected Mixed content, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_build_client_with_cookies() {
         let client = build_client(Some("session=test123"));
@@ -729,4 +1325,139 @@ mod tests {
         let client = build_client(None);
         assert!(client.get("https://example.com").build().is_ok());
     }
+
+    fn conversation_items(from: usize, count: usize) -> serde_json::Value {
+        (from..from + count)
+            .map(|i| {
+                serde_json::json!({
+                    "uuid": format!("conv-{}", i),
+                    "name": format!("Chat {}", i),
+                    "created_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2025-01-02T00:00:00Z",
+                    "project_uuid": (i == 0).then_some("proj-1"),
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_conversations_are_listed_a_page_at_a_time() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let pages = [
+            (0, CONVERSATIONS_PAGE),
+            (CONVERSATIONS_PAGE, CONVERSATIONS_PAGE),
+            (2 * CONVERSATIONS_PAGE, 7),
+        ];
+        for (offset, count) in pages {
+            Mock::given(method("GET"))
+                .and(path("/organizations/org-1/chat_conversations"))
+                .and(query_param("limit", CONVERSATIONS_PAGE.to_string()))
+                .and(query_param("offset", offset.to_string()))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(conversation_items(offset, count)),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let provider = ClaudeProvider::with_credentials(Some("session=x".into()), None)
+            .with_org_id("org-1")
+            .with_api_base(&server.uri());
+        let conversations = provider.conversations().await.unwrap();
+
+        assert_eq!(conversations.len(), 2 * CONVERSATIONS_PAGE + 7);
+        assert_eq!(conversations[0].id, "conv-0");
+        assert_eq!(conversations[0].project_id.as_deref(), Some("proj-1"));
+        assert_eq!(conversations.last().unwrap().title, "Chat 206");
+        assert!(conversations.iter().all(|c| c.provider_id == "claude"));
+    }
+
+    #[tokio::test]
+    async fn test_listing_stops_when_offset_is_ignored() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(conversation_items(0, CONVERSATIONS_PAGE)),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let provider = ClaudeProvider::with_credentials(Some("session=x".into()), None)
+            .with_org_id("org-1")
+            .with_api_base(&server.uri());
+        let conversations = provider.conversations().await.unwrap();
+        assert_eq!(conversations.len(), CONVERSATIONS_PAGE);
+    }
+
+    #[tokio::test]
+    async fn test_pasted_cookies_are_checked_and_saved() {
+        use crate::credentials::MockStore;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/organizations"))
+            .and(header("cookie", "sessionKey=sk-ant-1; lastActiveOrg=org-1"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{"uuid": "org-1"}])),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/bootstrap"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "account": {"uuid": "user-1", "email_address": "me@example.com"}
+            })))
+            .mount(&server)
+            .await;
+
+        let store = MockStore::new();
+        let mut provider = ClaudeProvider::with_credential_store(Arc::new(store.clone()))
+            .with_api_base(&server.uri());
+        let account = provider
+            .authenticate_with_cookies("sessionKey=sk-ant-1; lastActiveOrg=org-1\n")
+            .await
+            .unwrap();
+
+        assert_eq!(account.email, "me@example.com");
+        let saved = |user| {
+            store
+                .get_for_account(KEYRING_SERVICE, user, Some("me@example.com"))
+                .unwrap()
+        };
+        assert_eq!(
+            saved(KEYRING_USER_COOKIES),
+            "sessionKey=sk-ant-1; lastActiveOrg=org-1"
+        );
+        assert_eq!(saved(KEYRING_USER_ORG), "org-1");
+    }
+
+    #[tokio::test]
+    async fn test_expired_or_incomplete_cookies_are_rejected() {
+        use crate::credentials::MockStore;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let mut provider = ClaudeProvider::with_credential_store(Arc::new(MockStore::new()))
+            .with_api_base(&server.uri());
+        let expired = provider.authenticate_with_cookies("sessionKey=old").await;
+        assert!(matches!(expired, Err(ProviderError::AuthFailed(m)) if m.contains("expired")));
+        let missing = provider.authenticate_with_cookies("other=1").await;
+        assert!(matches!(missing, Err(ProviderError::AuthFailed(m)) if m.contains("sessionKey")));
+    }
 }
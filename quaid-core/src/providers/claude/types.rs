@@ -32,6 +32,8 @@ pub struct ApiConversationItem {
     pub is_starred: bool,
     #[serde(default)]
     pub project_uuid: Option<String>,
+    #[serde(default)]
+    pub project: Option<ApiProjectRef>,
 }
 
 /// Full conversation from /api/organizations/{org}/chat_conversations/{id}
@@ -48,6 +50,15 @@ pub struct ApiConversation {
     pub model: Option<String>,
     #[serde(default)]
     pub project_uuid: Option<String>,
+    #[serde(default)]
+    pub project: Option<ApiProjectRef>,
+}
+
+/// The project a conversation belongs to, as embedded in conversations
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiProjectRef {
+    pub uuid: String,
+    pub name: Option<String>,
 }
 
 /// A message in a Claude conversation
@@ -73,21 +84,58 @@ pub enum ApiContentBlock {
     Text {
         text: String,
     },
+    /// Extended thinking that came before the answer
+    Thinking {
+        #[serde(default)]
+        thinking: String,
+    },
     #[serde(rename = "tool_use")]
     ToolUse {
+        #[serde(default)]
         id: String,
         name: String,
         input: serde_json::Value,
     },
     #[serde(rename = "tool_result")]
     ToolResult {
+        #[serde(default)]
         tool_use_id: String,
+        /// The tool that ran, e.g. `artifacts`
+        #[serde(default)]
+        name: Option<String>,
+        /// A string, or a list of text blocks
         content: serde_json::Value,
+        #[serde(default)]
+        is_error: bool,
     },
     #[serde(other)]
     Unknown,
 }
 
+/// Input of an `artifacts` tool call: a new artifact or a revision of one
+///
+/// `create` and `rewrite` carry the whole `content`; `update` replaces
+/// `old_str` with `new_str` in the artifact's last version.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiArtifact {
+    pub id: String,
+    #[serde(default)]
+    pub command: Option<String>,
+    /// MIME type, e.g. `application/vnd.ant.code` or `text/markdown`
+    #[serde(rename = "type", default)]
+    pub artifact_type: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub old_str: Option<String>,
+    #[serde(default)]
+    pub new_str: Option<String>,
+}
+
 /// Attachment metadata (legacy format)
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ApiAttachment {
@@ -166,6 +214,16 @@ pub struct ApiProject {
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+/// Project knowledge document from /api/organizations/{org}/projects/{id}/docs
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiProjectDoc {
+    pub uuid: String,
+    pub file_name: String,
+    #[serde(default)]
+    pub content: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
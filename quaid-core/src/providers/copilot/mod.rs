@@ -0,0 +1,704 @@
+//! Microsoft Copilot provider implementation
+//!
+//! Syncs threads from copilot.microsoft.com through the conversation-history
+//! endpoints of the web app, signed in with the cookies of a browser session.
+
+pub mod types;
+
+use crate::credentials::keys::copilot::COOKIES as KEYRING_USER_COOKIES;
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::download;
+use crate::providers::{
+    with_retry, Account, Attachment, Conversation, Message, MessageContent, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
+};
+use async_trait::async_trait;
+use reqwest::{header, Client};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use types::*;
+
+const BASE_URL: &str = "https://copilot.microsoft.com";
+const API_URL: &str = "https://copilot.microsoft.com/c/api";
+
+/// Copilot provider
+pub struct CopilotProvider {
+    client: Client,
+    /// `Cookie` header of the signed-in browser session
+    cookies: Option<String>,
+    credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Threads from the last listing, which has the titles their history lacks
+    listed: Mutex<HashMap<String, ApiConversation>>,
+}
+
+impl CopilotProvider {
+    /// Create a new Copilot provider, loading credentials from keyring if available
+    pub fn new() -> Self {
+        Self::with_account_credentials(default_store(), None)
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
+        let cookies = credential_store
+            .get_for_account(KEYRING_SERVICE, KEYRING_USER_COOKIES, email)
+            .ok();
+
+        Self {
+            client: build_client(),
+            cookies,
+            credential_store,
+            retry: RetryPolicy::default(),
+            listed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// GET `endpoint` of the API and parse its JSON body, retrying transient failures
+    async fn api_get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        with_retry(&self.retry, || self.api_get_once(endpoint)).await
+    }
+
+    async fn api_get_once<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let cookies = self.cookies.as_ref().ok_or(ProviderError::AuthRequired)?;
+        let url = format!("{}{}", API_URL, endpoint);
+        let resp = self
+            .client
+            .get(&url)
+            .header(header::COOKIE, cookies)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60);
+            return Err(ProviderError::RateLimited(retry_after));
+        }
+        if status == 401 || status == 403 {
+            return Err(ProviderError::TokenExpired);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: GET {} failed: {}",
+                status,
+                url,
+                truncate_body(&body, 500)
+            )));
+        }
+        serde_json::from_str(&body).map_err(|e| {
+            ProviderError::Parse(format!(
+                "Failed to parse response: {}. Body: {}",
+                e,
+                truncate_body(&body, 500)
+            ))
+        })
+    }
+
+    /// Every thread, newest first
+    async fn list_threads(&self) -> Result<Vec<ApiConversation>> {
+        let mut threads = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let page: ApiConversationPage = self
+                .api_get(&page_endpoint("/conversations", token.as_deref()))
+                .await?;
+            threads.extend(page.results);
+            match page.continuation_token {
+                Some(next) if !next.is_empty() && token.as_ref() != Some(&next) => {
+                    token = Some(next)
+                }
+                _ => return Ok(threads),
+            }
+        }
+    }
+
+    /// A thread's messages, oldest first
+    async fn read_history(&self, id: &str) -> Result<Vec<ApiMessage>> {
+        let mut messages = Vec::new();
+        let mut token: Option<String> = None;
+        let endpoint = format!("/conversations/{}/history", id);
+        loop {
+            let page: ApiHistoryPage = self
+                .api_get(&page_endpoint(&endpoint, token.as_deref()))
+                .await?;
+            messages.extend(page.results);
+            match page.continuation_token {
+                Some(next) if !next.is_empty() && token.as_ref() != Some(&next) => {
+                    token = Some(next)
+                }
+                _ => break,
+            }
+        }
+        messages.sort_by_key(|m| m.created_at);
+        Ok(messages)
+    }
+
+    fn listed_or_bare(&self, id: &str) -> ApiConversation {
+        self.listed
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| ApiConversation {
+                id: id.to_string(),
+                title: None,
+                created_at: None,
+                updated_at: None,
+            })
+    }
+
+    /// Fetch a thread with its generated images (for sync)
+    pub async fn conversation_with_attachments(
+        &self,
+        id: &str,
+    ) -> Result<(Conversation, Vec<Message>, Vec<Attachment>)> {
+        let history = self.read_history(id).await?;
+        let listed = self.listed_or_bare(id);
+
+        Ok((
+            convert_thread(&listed, &history),
+            convert_messages(id, &history),
+            extract_attachments(&history),
+        ))
+    }
+}
+
+impl Default for CopilotProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for CopilotProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::copilot()
+    }
+
+    async fn is_authenticated(&self) -> bool {
+        self.cookies.is_some()
+    }
+
+    async fn authenticate(&mut self) -> Result<Account> {
+        // Browser-based authentication flow
+        use chromiumoxide::browser::{Browser, BrowserConfig};
+        use futures::StreamExt;
+
+        println!("Opening browser for Copilot authentication...");
+        println!("Please sign in to your Microsoft account.");
+
+        // Set up user data dir to persist session
+        let user_data_dir = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("quaid")
+            .join("copilot-chrome-profile");
+        std::fs::create_dir_all(&user_data_dir).ok();
+
+        let mut builder = BrowserConfig::builder()
+            .with_head()
+            .user_data_dir(&user_data_dir)
+            .arg("--disable-blink-features=AutomationControlled")
+            .arg("--disable-infobars")
+            .arg("--no-first-run")
+            .window_size(1280, 900);
+
+        // Try to find Chrome on the system
+        if let Some(chrome_path) = find_chrome() {
+            builder = builder.chrome_executable(chrome_path);
+        }
+
+        let config = builder
+            .build()
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                if event.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let page = browser
+            .new_page(BASE_URL)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        // Copilot sets cookies before sign-in too, so the session counts as
+        // signed in once the API knows who the user is
+        println!("Waiting for login... (this window will close automatically)");
+
+        let account = loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+            let url = page.url().await.ok().flatten().unwrap_or_default();
+            if !url.starts_with(BASE_URL) {
+                continue;
+            }
+            let found = page.get_cookies().await.unwrap_or_default();
+            let cookies = found
+                .iter()
+                .filter(|c| c.domain.ends_with("copilot.microsoft.com"))
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect::<Vec<_>>()
+                .join("; ");
+            if cookies.is_empty() {
+                continue;
+            }
+
+            self.cookies = Some(cookies);
+            match self.account().await {
+                Ok(account) => {
+                    println!("Login detected!");
+                    break account;
+                }
+                Err(_) => self.cookies = None,
+            }
+        };
+
+        // Close browser
+        drop(browser);
+        handle.abort();
+
+        // Save under the signed-in account, so other accounts keep theirs
+        if let Some(cookies) = &self.cookies {
+            if let Err(e) = self.credential_store.set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_COOKIES,
+                &account.email,
+                cookies,
+            ) {
+                eprintln!("Warning: failed to save cookies: {}", e);
+            }
+        }
+        println!("Authentication successful!");
+        Ok(account)
+    }
+
+    async fn account(&self) -> Result<Account> {
+        let user: ApiUser = self.api_get("/user").await?;
+        let email = user
+            .email
+            .ok_or_else(|| ProviderError::Api("Not signed in to Copilot".to_string()))?;
+
+        Ok(Account {
+            id: user.id,
+            provider: ProviderId::copilot(),
+            email,
+            name: user.display_name,
+            avatar_url: None,
+            workspace_id: None,
+        })
+    }
+
+    async fn conversations(&self) -> Result<Vec<Conversation>> {
+        let threads = self.list_threads().await?;
+        let conversations = threads.iter().map(|t| convert_thread(t, &[])).collect();
+
+        let mut listed = self.listed.lock().unwrap();
+        listed.extend(threads.into_iter().map(|t| (t.id.clone(), t)));
+        Ok(conversations)
+    }
+
+    async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
+        let history = self.read_history(id).await?;
+        let listed = self.listed_or_bare(id);
+
+        Ok((
+            convert_thread(&listed, &history),
+            convert_messages(id, &history),
+        ))
+    }
+
+    async fn project_conversations(&self, _project_id: &str) -> Result<Vec<Conversation>> {
+        Ok(Vec::new())
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment, path: &Path) -> Result<()> {
+        // Generated images are served from Bing's image CDN without cookies
+        let request = self.client.get(&attachment.download_url);
+        let response = download::resume(request, path).await.send().await?;
+        download::save_response(response, path, attachment.size_bytes).await
+    }
+}
+
+/// `endpoint`, continuing from `token` when there's one
+fn page_endpoint(endpoint: &str, token: Option<&str>) -> String {
+    match token {
+        Some(token) => format!("{}?continuationToken={}", endpoint, token),
+        None => endpoint.to_string(),
+    }
+}
+
+/// A listed thread, dated by its messages when they're known
+///
+/// Untitled threads are named after their first prompt.
+fn convert_thread(thread: &ApiConversation, history: &[ApiMessage]) -> Conversation {
+    let dates = || history.iter().filter_map(|m| m.created_at);
+    let created_at = thread
+        .created_at
+        .or_else(|| dates().min())
+        .or(thread.updated_at)
+        .unwrap_or_default();
+    let updated_at = thread
+        .updated_at
+        .into_iter()
+        .chain(dates())
+        .max()
+        .unwrap_or(created_at)
+        .max(created_at);
+    let title = match thread.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => history
+            .iter()
+            .filter(|m| role(&m.author) == Some(Role::User))
+            .flat_map(|m| &m.content)
+            .find_map(|content| match content {
+                ApiContent::Text { text } => text.lines().find(|l| !l.trim().is_empty()),
+                _ => None,
+            })
+            .map(|line| line.chars().take(80).collect::<String>())
+            .unwrap_or_else(|| "Untitled".to_string()),
+    };
+
+    Conversation {
+        id: thread.id.clone(),
+        provider_id: "copilot".to_string(),
+        title,
+        created_at,
+        updated_at,
+        model: None,
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    }
+}
+
+fn role(author: &str) -> Option<Role> {
+    match author {
+        "human" | "user" => Some(Role::User),
+        "ai" | "bot" | "assistant" => Some(Role::Assistant),
+        _ => None,
+    }
+}
+
+/// Each message of the user and Copilot, with adaptive cards as their text
+///
+/// Messages from other authors, or with nothing left to show, are left out.
+fn convert_messages(conv_id: &str, history: &[ApiMessage]) -> Vec<Message> {
+    history
+        .iter()
+        .filter_map(|msg| {
+            let role = role(&msg.author)?;
+            let mut parts: Vec<MessageContent> = msg
+                .content
+                .iter()
+                .filter_map(|content| match content {
+                    ApiContent::Text { text } if !text.trim().is_empty() => {
+                        Some(MessageContent::Text { text: text.clone() })
+                    }
+                    ApiContent::Image { url, prompt, .. } => Some(MessageContent::Image {
+                        url: url.clone(),
+                        alt: prompt.clone(),
+                    }),
+                    ApiContent::AdaptiveCard { card } => {
+                        let text = card_text(card);
+                        (!text.is_empty()).then_some(MessageContent::Text { text })
+                    }
+                    _ => None,
+                })
+                .collect();
+            let content = match parts.len() {
+                0 => return None,
+                1 => parts.remove(0),
+                _ => MessageContent::Mixed { parts },
+            };
+
+            Some(Message {
+                id: msg.id.clone(),
+                conversation_id: conv_id.to_string(),
+                parent_id: None, // Copilot keeps a flat list of messages
+                role,
+                content,
+                created_at: msg.created_at,
+                model: None,
+                speaker: None,
+            })
+        })
+        .collect()
+}
+
+/// Generated images of each message, in message order
+fn extract_attachments(history: &[ApiMessage]) -> Vec<Attachment> {
+    history
+        .iter()
+        .flat_map(|msg| {
+            msg.content.iter().filter_map(move |content| {
+                let ApiContent::Image { url, id, .. } = content else {
+                    return None;
+                };
+                let id = id.clone().unwrap_or_else(|| url_file_name(url).to_string());
+                Some(Attachment {
+                    filename: format!("{}.jpg", id),
+                    id,
+                    message_id: msg.id.clone(),
+                    mime_type: "image/jpeg".to_string(),
+                    size_bytes: 0,
+                    download_url: url.clone(),
+                    width: None,
+                    height: None,
+                    sha256: None,
+                })
+            })
+        })
+        .collect()
+}
+
+/// The last path segment of `url`, without its query
+fn url_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Build HTTP client with browser-like headers
+fn build_client() -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+    headers.insert(header::ORIGIN, BASE_URL.parse().unwrap());
+    headers.insert(
+        header::REFERER,
+        "https://copilot.microsoft.com/".parse().unwrap(),
+    );
+
+    Client::builder()
+        .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Safely truncate a string at a char boundary
+fn truncate_body(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Find a valid char boundary
+        let mut end = max_len;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+/// Find Chrome/Chromium executable on the system
+fn find_chrome() -> Option<std::path::PathBuf> {
+    let candidates = if cfg!(target_os = "macos") {
+        vec![
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+            "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ]
+    } else if cfg!(target_os = "linux") {
+        vec![
+            "/usr/bin/google-chrome",
+            "/usr/bin/google-chrome-stable",
+            "/usr/bin/chromium",
+            "/usr/bin/chromium-browser",
+            "/snap/bin/chromium",
+        ]
+    } else {
+        // Windows - chromiumoxide should handle this
+        vec![]
+    };
+
+    for candidate in candidates {
+        let path = std::path::PathBuf::from(candidate);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::MockStore;
+
+    fn fixture<T: DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_provider_id() {
+        let provider = CopilotProvider::with_account_credentials(Arc::new(MockStore::new()), None);
+        assert_eq!(provider.id(), ProviderId::copilot());
+    }
+
+    #[test]
+    fn test_authenticated_with_stored_cookies() {
+        let store = Arc::new(MockStore::new());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider =
+            CopilotProvider::with_account_credentials(store.clone(), Some("ana@example.com"));
+        assert!(!rt.block_on(provider.is_authenticated()));
+
+        store
+            .set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_COOKIES,
+                "ana@example.com",
+                "_U=session",
+            )
+            .unwrap();
+        let provider = CopilotProvider::with_account_credentials(store, Some("ana@example.com"));
+        assert!(rt.block_on(provider.is_authenticated()));
+    }
+
+    #[test]
+    fn test_thread_list_becomes_conversations() {
+        let page: ApiConversationPage = fixture("copilot-conversations.json");
+        assert_eq!(page.continuation_token.as_deref(), Some("eyJvZmZzZXQiOjJ9"));
+
+        let conversations: Vec<_> = page
+            .results
+            .iter()
+            .map(|t| convert_thread(t, &[]))
+            .collect();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].id, "hX2aPq9WkT3nLm7c");
+        assert_eq!(conversations[0].title, "Ferry times to Ilha da Culatra");
+        assert_eq!(conversations[0].provider_id, "copilot");
+        assert_eq!(
+            conversations[0].created_at.to_rfc3339(),
+            "2024-06-03T08:12:00+00:00"
+        );
+        assert_eq!(
+            conversations[0].updated_at.to_rfc3339(),
+            "2024-06-03T08:14:30+00:00"
+        );
+        assert_eq!(
+            conversations[0].web_url().as_deref(),
+            Some("https://copilot.microsoft.com/chats/hX2aPq9WkT3nLm7c")
+        );
+        assert_eq!(conversations[1].title, "Untitled");
+    }
+
+    #[test]
+    fn test_history_becomes_messages() {
+        let page: ApiHistoryPage = fixture("copilot-history.json");
+        assert_eq!(page.continuation_token, None);
+        let messages = convert_messages("hX2aPq9WkT3nLm7c", &page.results);
+
+        // The empty system notice is left out
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].role, Role::User);
+        assert!(matches!(
+            &messages[0].content,
+            MessageContent::Text { text } if text == "When do ferries leave Olhão for Culatra?"
+        ));
+
+        // Adaptive cards degrade to their text
+        assert_eq!(messages[1].role, Role::Assistant);
+        assert!(matches!(
+            &messages[1].content,
+            MessageContent::Text { text }
+                if text == "Three ferries leave daily.\nFirst departure: 07:15\nFare: €2.20"
+        ));
+
+        // Generated images keep their prompt
+        match &messages[3].content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Text { text }, MessageContent::Image { url, alt }, ..] => {
+                    assert_eq!(text, "Here's a sketch of the crossing.");
+                    assert!(url.starts_with("https://th.bing.com/th/id/OIG2."));
+                    assert_eq!(alt.as_deref(), Some("watercolor ferry crossing a lagoon"));
+                }
+                other => panic!("Unexpected parts: {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+        assert!(messages.iter().all(|m| m.parent_id.is_none()));
+
+        // Untitled threads are named after the first prompt
+        let thread = ApiConversation {
+            id: "hX2aPq9WkT3nLm7c".to_string(),
+            title: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let conversation = convert_thread(&thread, &page.results);
+        assert_eq!(
+            conversation.title,
+            "When do ferries leave Olhão for Culatra?"
+        );
+        assert_eq!(
+            conversation.updated_at.to_rfc3339(),
+            "2024-06-03T08:14:30+00:00"
+        );
+    }
+
+    #[test]
+    fn test_generated_images_become_attachments() {
+        let page: ApiHistoryPage = fixture("copilot-history.json");
+        let attachments = extract_attachments(&page.results);
+        assert_eq!(attachments.len(), 2);
+
+        assert_eq!(attachments[0].id, "img-7f3a");
+        assert_eq!(attachments[0].filename, "img-7f3a.jpg");
+        assert_eq!(attachments[0].message_id, "msg-4");
+        assert_eq!(attachments[0].mime_type, "image/jpeg");
+        assert!(attachments[0].download_url.contains("pid=ImgGn"));
+
+        // Without an id, the image is named after its URL
+        assert_eq!(attachments[1].id, "OIG2.qR5tVw8xYz");
+        assert_eq!(attachments[1].filename, "OIG2.qR5tVw8xYz.jpg");
+    }
+
+    #[test]
+    fn test_page_endpoint_continues_from_token() {
+        assert_eq!(page_endpoint("/conversations", None), "/conversations");
+        assert_eq!(
+            page_endpoint("/conversations", Some("eyJvZmZzZXQiOjJ9")),
+            "/conversations?continuationToken=eyJvZmZzZXQiOjJ9"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Copilot web API response types
+//!
+//! These types are based on the conversation-history endpoints the
+//! copilot.microsoft.com web app calls and may need adjustment as the API
+//! evolves.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The signed-in user
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiUser {
+    pub id: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+/// One page of the thread list, newest first
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiConversationPage {
+    #[serde(default)]
+    pub results: Vec<ApiConversation>,
+    /// Where the next page starts; missing on the last one
+    #[serde(default)]
+    pub continuation_token: Option<String>,
+}
+
+/// A thread in the list
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiConversation {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// One page of a thread's messages, oldest first
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiHistoryPage {
+    #[serde(default)]
+    pub results: Vec<ApiMessage>,
+    /// Where the next page starts; missing on the last one
+    #[serde(default)]
+    pub continuation_token: Option<String>,
+}
+
+/// A message in a thread
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiMessage {
+    pub id: String,
+    /// `human` or `ai`
+    pub author: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub content: Vec<ApiContent>,
+}
+
+/// A part of a message
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ApiContent {
+    Text {
+        text: String,
+    },
+    /// An image Copilot generated
+    Image {
+        url: String,
+        #[serde(default)]
+        id: Option<String>,
+        /// What the image was generated from
+        #[serde(default)]
+        prompt: Option<String>,
+    },
+    /// Rich content from older Bing Chat turns, rendered by the web app
+    AdaptiveCard {
+        card: Value,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// The text an adaptive card shows, one block per line
+///
+/// Walks the card's elements in order, taking the text of text blocks and
+/// runs, and the titles and values of fact sets; layout is dropped.
+pub fn card_text(card: &Value) -> String {
+    let mut lines = Vec::new();
+    collect_card_text(card, &mut lines);
+    lines.join("\n")
+}
+
+fn collect_card_text(element: &Value, lines: &mut Vec<String>) {
+    match element {
+        Value::Array(elements) => {
+            for element in elements {
+                collect_card_text(element, lines);
+            }
+        }
+        Value::Object(fields) => {
+            let text = |key: &str| fields.get(key).and_then(Value::as_str).map(str::trim);
+            match fields.get("type").and_then(Value::as_str) {
+                Some("TextBlock") | Some("TextRun") => {
+                    if let Some(text) = text("text").filter(|t| !t.is_empty()) {
+                        lines.push(text.to_string());
+                    }
+                }
+                Some("Fact") | None if fields.contains_key("value") => {
+                    if let (Some(title), Some(value)) = (text("title"), text("value")) {
+                        lines.push(format!("{}: {}", title, value));
+                    }
+                }
+                _ => {
+                    for (key, value) in fields {
+                        if key != "type" {
+                            collect_card_text(value, lines);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_card_text_reads_blocks_in_order() {
+        let card = json!({
+            "type": "AdaptiveCard",
+            "version": "1.0",
+            "body": [
+                {"type": "TextBlock", "text": "Three ferries leave daily.", "wrap": true},
+                {
+                    "type": "ColumnSet",
+                    "columns": [{
+                        "type": "Column",
+                        "items": [{
+                            "type": "RichTextBlock",
+                            "inlines": [{"type": "TextRun", "text": "First at 07:15"}]
+                        }]
+                    }]
+                },
+                {"type": "Image", "url": "https://example.com/map.png"},
+                {"type": "FactSet", "facts": [{"title": "Fare", "value": "€4.50"}]},
+                {"type": "TextBlock", "text": "  "}
+            ]
+        });
+        assert_eq!(
+            card_text(&card),
+            "Three ferries leave daily.\nFirst at 07:15\nFare: €4.50"
+        );
+        assert_eq!(card_text(&json!({"type": "AdaptiveCard"})), "");
+    }
+
+    #[test]
+    fn test_unknown_content_types_are_kept_apart() {
+        let content: Vec<ApiContent> = serde_json::from_value(json!([
+            {"type": "text", "text": "Hi"},
+            {"type": "citation", "url": "https://example.com"}
+        ]))
+        .unwrap();
+        assert!(matches!(content[0], ApiContent::Text { .. }));
+        assert!(matches!(content[1], ApiContent::Unknown));
+    }
+}
@@ -0,0 +1,721 @@
+//! DeepSeek provider implementation
+//!
+//! Syncs chat sessions from chat.deepseek.com through the `/api/v0` endpoints
+//! of the web app, with the bearer token the app keeps after sign-in.
+
+pub mod types;
+
+use crate::credentials::keys::deepseek::{
+    COOKIES as KEYRING_USER_COOKIES, TOKEN as KEYRING_USER_TOKEN,
+};
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::{
+    with_retry, Account, Attachment, Conversation, Message, MessageContent, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
+};
+use async_trait::async_trait;
+use reqwest::{header, Client};
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use types::*;
+
+const BASE_URL: &str = "https://chat.deepseek.com";
+const API_URL: &str = "https://chat.deepseek.com/api/v0";
+
+/// Sessions per page of the listing
+const PAGE_SIZE: usize = 50;
+
+/// `code`s of a missing or revoked token
+const AUTH_CODES: [i64; 2] = [40002, 40003];
+
+/// DeepSeek provider
+pub struct DeepSeekProvider {
+    client: Client,
+    token: Option<String>,
+    /// `Cookie` header of the signed-in browser session, if it had any
+    cookies: Option<String>,
+    credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Keep R1's reasoning as thinking content
+    include_thinking: bool,
+    /// Sessions from the last listing, which have the titles and pins their
+    /// history may lack
+    listed: Mutex<HashMap<String, ApiSession>>,
+}
+
+impl DeepSeekProvider {
+    /// Create a new DeepSeek provider, loading credentials from keyring if available
+    pub fn new() -> Self {
+        Self::with_account_credentials(default_store(), None)
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
+        let load = |user: &str| {
+            credential_store
+                .get_for_account(KEYRING_SERVICE, user, email)
+                .ok()
+        };
+        let token = load(KEYRING_USER_TOKEN);
+        let cookies = load(KEYRING_USER_COOKIES);
+
+        Self {
+            client: build_client(),
+            token,
+            cookies,
+            credential_store,
+            retry: RetryPolicy::default(),
+            include_thinking: false,
+            listed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keep R1's reasoning alongside its answers, as thinking content
+    pub fn with_include_thinking(mut self, include_thinking: bool) -> Self {
+        self.include_thinking = include_thinking;
+        self
+    }
+
+    /// GET `endpoint` of the API and unwrap its `biz_data`, retrying transient failures
+    async fn api_get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        with_retry(&self.retry, || self.api_get_once(endpoint)).await
+    }
+
+    async fn api_get_once<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let token = self.token.as_ref().ok_or(ProviderError::AuthRequired)?;
+        let url = format!("{}{}", API_URL, endpoint);
+        let mut request = self.client.get(&url).bearer_auth(token);
+        if let Some(cookies) = &self.cookies {
+            request = request.header(header::COOKIE, cookies);
+        }
+        let resp = request.send().await?;
+
+        let status = resp.status();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60);
+            return Err(ProviderError::RateLimited(retry_after));
+        }
+        if status == 401 || status == 403 {
+            return Err(ProviderError::TokenExpired);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: GET {} failed: {}",
+                status,
+                url,
+                truncate_body(&body, 500)
+            )));
+        }
+        let envelope: ApiEnvelope<T> = serde_json::from_str(&body).map_err(|e| {
+            ProviderError::Parse(format!(
+                "Failed to parse response: {}. Body: {}",
+                e,
+                truncate_body(&body, 500)
+            ))
+        })?;
+        biz_data(envelope, &url)
+    }
+
+    /// Every session, following the listing's cursor to the end
+    async fn list_sessions(&self) -> Result<Vec<ApiSession>> {
+        let mut sessions: Vec<ApiSession> = Vec::new();
+        let mut seen = HashSet::new();
+        loop {
+            let page: ApiSessionPage = self.api_get(&sessions_endpoint(sessions.last())).await?;
+            let before = sessions.len();
+            // The cursor is inclusive, so a page can repeat the last session
+            for session in page.chat_sessions {
+                if seen.insert(session.id.clone()) {
+                    sessions.push(session);
+                }
+            }
+            if !page.has_more || sessions.len() == before {
+                return Ok(sessions);
+            }
+        }
+    }
+
+    /// A session with its messages, oldest first
+    async fn read_history(&self, id: &str) -> Result<ApiHistory> {
+        let mut history: ApiHistory = self
+            .api_get(&format!("/chat/history_messages?chat_session_id={}", id))
+            .await?;
+        history
+            .chat_messages
+            .sort_by(|a, b| a.inserted_at.total_cmp(&b.inserted_at));
+        Ok(history)
+    }
+}
+
+impl Default for DeepSeekProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for DeepSeekProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::deepseek()
+    }
+
+    async fn is_authenticated(&self) -> bool {
+        self.token.is_some()
+    }
+
+    async fn authenticate(&mut self) -> Result<Account> {
+        // Browser-based authentication flow
+        use chromiumoxide::browser::{Browser, BrowserConfig};
+        use futures::StreamExt;
+
+        println!("Opening browser for DeepSeek authentication...");
+        println!("Please sign in to your DeepSeek account.");
+
+        // Set up user data dir to persist session
+        let user_data_dir = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("quaid")
+            .join("deepseek-chrome-profile");
+        std::fs::create_dir_all(&user_data_dir).ok();
+
+        let mut builder = BrowserConfig::builder()
+            .with_head()
+            .user_data_dir(&user_data_dir)
+            .arg("--disable-blink-features=AutomationControlled")
+            .arg("--disable-infobars")
+            .arg("--no-first-run")
+            .window_size(1280, 900);
+
+        // Try to find Chrome on the system
+        if let Some(chrome_path) = find_chrome() {
+            builder = builder.chrome_executable(chrome_path);
+        }
+
+        let config = builder
+            .build()
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                if event.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let page = browser
+            .new_page(BASE_URL)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        // The web app keeps its token in local storage once signed in
+        println!("Waiting for login... (this window will close automatically)");
+
+        let account = loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+            let url = page.url().await.ok().flatten().unwrap_or_default();
+            if !url.starts_with(BASE_URL) || url.contains("sign_in") {
+                continue;
+            }
+            let stored: Option<String> =
+                match page.evaluate("localStorage.getItem('userToken')").await {
+                    Ok(result) => result.into_value().ok().flatten(),
+                    Err(_) => continue,
+                };
+            let Some(token) = stored.as_deref().and_then(stored_token) else {
+                continue;
+            };
+
+            let found = page.get_cookies().await.unwrap_or_default();
+            let cookies = found
+                .iter()
+                .filter(|c| c.domain.ends_with("deepseek.com"))
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            self.token = Some(token);
+            self.cookies = (!cookies.is_empty()).then_some(cookies);
+            match self.account().await {
+                Ok(account) => {
+                    println!("Login detected!");
+                    break account;
+                }
+                Err(_) => self.token = None,
+            }
+        };
+
+        // Close browser
+        drop(browser);
+        handle.abort();
+
+        // Save under the signed-in account, so other accounts keep theirs
+        let credentials = [
+            (KEYRING_USER_TOKEN, &self.token),
+            (KEYRING_USER_COOKIES, &self.cookies),
+        ];
+        for (user, value) in credentials {
+            let Some(value) = value else { continue };
+            if let Err(e) =
+                self.credential_store
+                    .set_for_account(KEYRING_SERVICE, user, &account.email, value)
+            {
+                eprintln!("Warning: failed to save {}: {}", user, e);
+            }
+        }
+        println!("Authentication successful!");
+        Ok(account)
+    }
+
+    async fn account(&self) -> Result<Account> {
+        let user: ApiUser = self.api_get("/users/current").await?;
+        let email = user
+            .email
+            .filter(|e| !e.is_empty())
+            .or(user.mobile_number)
+            .unwrap_or_else(|| user.id.clone());
+
+        Ok(Account {
+            id: user.id,
+            provider: ProviderId::deepseek(),
+            email,
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        })
+    }
+
+    async fn conversations(&self) -> Result<Vec<Conversation>> {
+        let sessions = self.list_sessions().await?;
+        let conversations = sessions.iter().map(|s| convert_session(s, &[])).collect();
+
+        let mut listed = self.listed.lock().unwrap();
+        listed.extend(sessions.into_iter().map(|s| (s.id.clone(), s)));
+        Ok(conversations)
+    }
+
+    async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
+        let mut history = self.read_history(id).await?;
+        if let Some(listed) = self.listed.lock().unwrap().get(id) {
+            history.chat_session.title = history
+                .chat_session
+                .title
+                .take()
+                .or_else(|| listed.title.clone());
+        }
+
+        Ok((
+            convert_session(&history.chat_session, &history.chat_messages),
+            convert_messages(id, &history.chat_messages, self.include_thinking),
+        ))
+    }
+
+    async fn project_conversations(&self, _project_id: &str) -> Result<Vec<Conversation>> {
+        Ok(Vec::new())
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment, _path: &Path) -> Result<()> {
+        Err(ProviderError::Api(format!(
+            "DeepSeek attachments can't be downloaded yet ({})",
+            attachment.filename
+        )))
+    }
+}
+
+/// The `biz_data` of a response, or the error it reports
+fn biz_data<T>(envelope: ApiEnvelope<T>, url: &str) -> Result<T> {
+    if AUTH_CODES.contains(&envelope.code) {
+        return Err(ProviderError::TokenExpired);
+    }
+    if envelope.code != 0 {
+        return Err(ProviderError::Api(format!(
+            "GET {} failed: {} {}",
+            url, envelope.code, envelope.msg
+        )));
+    }
+    let data = envelope
+        .data
+        .ok_or_else(|| ProviderError::Parse(format!("GET {} returned no data", url)))?;
+    if data.biz_code != 0 {
+        return Err(ProviderError::Api(format!(
+            "GET {} failed: {} {}",
+            url, data.biz_code, data.biz_msg
+        )));
+    }
+    data.biz_data
+        .ok_or_else(|| ProviderError::Parse(format!("GET {} returned no data", url)))
+}
+
+/// The token in the web app's `userToken` local storage entry
+fn stored_token(entry: &str) -> Option<String> {
+    let entry: serde_json::Value = serde_json::from_str(entry).ok()?;
+    entry
+        .get("value")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// The session listing, continuing from the last session of the previous page
+fn sessions_endpoint(after: Option<&ApiSession>) -> String {
+    let endpoint = format!("/chat_session/fetch_page?count={}", PAGE_SIZE);
+    match after {
+        Some(last) => format!(
+            "{}&lte_cursor.updated_at={}&lte_cursor.pinned={}",
+            endpoint, last.updated_at, last.pinned
+        ),
+        None => endpoint,
+    }
+}
+
+/// A session, dated by its messages when they're known
+///
+/// Untitled sessions are named after their first prompt, and attributed to
+/// the model of their last reply.
+fn convert_session(session: &ApiSession, messages: &[ApiMessage]) -> Conversation {
+    let dates = || messages.iter().filter_map(|m| timestamp(m.inserted_at));
+    let updated = timestamp(session.updated_at);
+    let created_at = session
+        .inserted_at
+        .and_then(timestamp)
+        .or_else(|| dates().min())
+        .or(updated)
+        .unwrap_or_default();
+    let updated_at = updated
+        .into_iter()
+        .chain(dates())
+        .max()
+        .unwrap_or(created_at)
+        .max(created_at);
+    let title = match session.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => messages
+            .iter()
+            .filter(|m| role(&m.role) == Some(Role::User))
+            .find_map(|m| m.content.lines().find(|l| !l.trim().is_empty()))
+            .map(|line| line.chars().take(80).collect::<String>())
+            .unwrap_or_else(|| "Untitled".to_string()),
+    };
+    let model = messages
+        .iter()
+        .rev()
+        .filter(|m| role(&m.role) == Some(Role::Assistant))
+        .find_map(message_model);
+
+    Conversation {
+        id: session.id.clone(),
+        provider_id: "deepseek".to_string(),
+        title,
+        created_at,
+        updated_at,
+        model,
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    }
+}
+
+fn role(role: &str) -> Option<Role> {
+    match role {
+        "USER" => Some(Role::User),
+        "ASSISTANT" => Some(Role::Assistant),
+        _ => None,
+    }
+}
+
+/// The model behind a reply: the one recorded, or else the API name of the
+/// mode it was answered in
+fn message_model(msg: &ApiMessage) -> Option<String> {
+    if !msg.model.is_empty() {
+        return Some(msg.model.clone());
+    }
+    (role(&msg.role) == Some(Role::Assistant)).then(|| {
+        if msg.thinking_enabled {
+            "deepseek-reasoner".to_string()
+        } else {
+            "deepseek-chat".to_string()
+        }
+    })
+}
+
+/// Each message of the user and DeepSeek, with R1's reasoning ahead of its
+/// answer when `include_thinking`
+///
+/// Message ids only count within a session, so they're prefixed with the
+/// session's id; messages with nothing to show are left out.
+fn convert_messages(
+    conv_id: &str,
+    messages: &[ApiMessage],
+    include_thinking: bool,
+) -> Vec<Message> {
+    let message_id = |id: i64| format!("{}:{}", conv_id, id);
+    messages
+        .iter()
+        .filter_map(|msg| {
+            let role = role(&msg.role)?;
+            let mut parts = Vec::new();
+            if include_thinking {
+                if let Some(thinking) = msg.thinking_content.as_ref() {
+                    if !thinking.trim().is_empty() {
+                        parts.push(MessageContent::Thinking {
+                            text: thinking.clone(),
+                        });
+                    }
+                }
+            }
+            if !msg.content.trim().is_empty() {
+                parts.push(MessageContent::Text {
+                    text: msg.content.clone(),
+                });
+            }
+            let content = match parts.len() {
+                0 => return None,
+                1 => parts.remove(0),
+                _ => MessageContent::Mixed { parts },
+            };
+
+            Some(Message {
+                id: message_id(msg.message_id),
+                conversation_id: conv_id.to_string(),
+                parent_id: msg.parent_id.map(message_id),
+                role,
+                content,
+                created_at: timestamp(msg.inserted_at),
+                model: message_model(msg),
+                speaker: None,
+            })
+        })
+        .collect()
+}
+
+/// Build HTTP client with browser-like headers
+fn build_client() -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+    headers.insert(header::ORIGIN, BASE_URL.parse().unwrap());
+    headers.insert(
+        header::REFERER,
+        "https://chat.deepseek.com/".parse().unwrap(),
+    );
+    headers.insert("x-client-platform", "web".parse().unwrap());
+
+    Client::builder()
+        .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Safely truncate a string at a char boundary
+fn truncate_body(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Find a valid char boundary
+        let mut end = max_len;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+/// Find Chrome/Chromium executable on the system
+fn find_chrome() -> Option<std::path::PathBuf> {
+    let candidates = if cfg!(target_os = "macos") {
+        vec![
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+            "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ]
+    } else if cfg!(target_os = "linux") {
+        vec![
+            "/usr/bin/google-chrome",
+            "/usr/bin/google-chrome-stable",
+            "/usr/bin/chromium",
+            "/usr/bin/chromium-browser",
+            "/snap/bin/chromium",
+        ]
+    } else {
+        // Windows - chromiumoxide should handle this
+        vec![]
+    };
+
+    for candidate in candidates {
+        let path = std::path::PathBuf::from(candidate);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::MockStore;
+
+    const SESSION_ID: &str = "7c1f0a9e-3b2d-4e5f-8a6b-9c0d1e2f3a4b";
+
+    fn fixture<T: DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let envelope: ApiEnvelope<T> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        biz_data(envelope, name).unwrap()
+    }
+
+    #[test]
+    fn test_provider_id() {
+        let provider = DeepSeekProvider::with_account_credentials(Arc::new(MockStore::new()), None);
+        assert_eq!(provider.id(), ProviderId::deepseek());
+    }
+
+    #[test]
+    fn test_authenticated_with_stored_token() {
+        let store = Arc::new(MockStore::new());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider =
+            DeepSeekProvider::with_account_credentials(store.clone(), Some("ana@example.com"));
+        assert!(!rt.block_on(provider.is_authenticated()));
+
+        store
+            .set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_TOKEN,
+                "ana@example.com",
+                "token",
+            )
+            .unwrap();
+        let provider = DeepSeekProvider::with_account_credentials(store, Some("ana@example.com"));
+        assert!(rt.block_on(provider.is_authenticated()));
+    }
+
+    #[test]
+    fn test_session_list_becomes_conversations() {
+        let page: ApiSessionPage = fixture("deepseek-sessions.json");
+        assert!(page.has_more);
+
+        let conversations: Vec<_> = page
+            .chat_sessions
+            .iter()
+            .map(|s| convert_session(s, &[]))
+            .collect();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].id, SESSION_ID);
+        assert_eq!(conversations[0].title, "Prime sieve in Rust");
+        assert_eq!(conversations[0].provider_id, "deepseek");
+        assert_eq!(
+            conversations[0].updated_at.to_rfc3339(),
+            "2025-02-03T10:15:42.500+00:00"
+        );
+        assert_eq!(
+            conversations[0].web_url().as_deref(),
+            Some("https://chat.deepseek.com/a/chat/s/7c1f0a9e-3b2d-4e5f-8a6b-9c0d1e2f3a4b")
+        );
+        assert_eq!(conversations[1].title, "Untitled");
+
+        // The next page starts at the last session
+        assert_eq!(
+            sessions_endpoint(page.chat_sessions.last()),
+            "/chat_session/fetch_page?count=50&lte_cursor.updated_at=1738400000.25&lte_cursor.pinned=false"
+        );
+        assert_eq!(sessions_endpoint(None), "/chat_session/fetch_page?count=50");
+    }
+
+    #[test]
+    fn test_history_becomes_messages() {
+        let history: ApiHistory = fixture("deepseek-history.json");
+        let messages = convert_messages(SESSION_ID, &history.chat_messages, false);
+
+        // The reply still streaming when the page was saved is left out
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].id, format!("{}:1", SESSION_ID));
+        assert_eq!(messages[0].role, Role::User);
+        assert_eq!(messages[0].model, None);
+        assert_eq!(messages[1].parent_id, Some(format!("{}:1", SESSION_ID)));
+        assert_eq!(messages[1].model.as_deref(), Some("deepseek-reasoner"));
+
+        // Reasoning is dropped by default
+        assert!(matches!(
+            &messages[1].content,
+            MessageContent::Text { text } if text.starts_with("Here's a sieve")
+        ));
+        assert_eq!(messages[3].model.as_deref(), Some("deepseek-chat"));
+
+        // Or kept ahead of the answer
+        let with_thinking = convert_messages(SESSION_ID, &history.chat_messages, true);
+        match &with_thinking[1].content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Thinking { text: thinking }, MessageContent::Text { text }] => {
+                    assert!(thinking.starts_with("The user wants primes"));
+                    assert!(text.starts_with("Here's a sieve"));
+                }
+                other => panic!("Unexpected parts: {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+
+        let conversation = convert_session(&history.chat_session, &history.chat_messages);
+        assert_eq!(
+            conversation.created_at.to_rfc3339(),
+            "2025-02-03T10:14:00+00:00"
+        );
+        assert_eq!(conversation.model.as_deref(), Some("deepseek-chat"));
+    }
+
+    #[test]
+    fn test_auth_failures_need_a_new_sign_in() {
+        let envelope: ApiEnvelope<ApiUser> = serde_json::from_str(
+            r#"{"code": 40003, "msg": "Authorization Failed (invalid token)", "data": null}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            biz_data(envelope, "/users/current"),
+            Err(ProviderError::TokenExpired)
+        ));
+
+        assert_eq!(
+            stored_token(r#"{"value":"abc123","__version":"0"}"#).as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(stored_token("null"), None);
+    }
+}
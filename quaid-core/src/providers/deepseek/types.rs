@@ -0,0 +1,97 @@
+//! DeepSeek web API response types
+//!
+//! These types are based on the `/api/v0` endpoints the chat.deepseek.com
+//! web app calls and may need adjustment as the API evolves.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// Every response: `code` is non-zero when the request itself failed, and
+/// `biz_code` when the API refused it
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiEnvelope<T> {
+    pub code: i64,
+    #[serde(default)]
+    pub msg: String,
+    pub data: Option<ApiData<T>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiData<T> {
+    pub biz_code: i64,
+    #[serde(default)]
+    pub biz_msg: String,
+    pub biz_data: Option<T>,
+}
+
+/// The signed-in user
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiUser {
+    pub id: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Set instead of the email for accounts registered by phone
+    #[serde(default)]
+    pub mobile_number: Option<String>,
+}
+
+/// One page of the session list: pinned sessions first, then most recently
+/// updated first
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiSessionPage {
+    #[serde(default)]
+    pub chat_sessions: Vec<ApiSession>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// A chat session
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiSession {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Seconds since the epoch, with a fraction
+    pub updated_at: f64,
+    /// Seconds since the epoch; only in a session's history
+    #[serde(default)]
+    pub inserted_at: Option<f64>,
+}
+
+/// `history_messages` result
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiHistory {
+    pub chat_session: ApiSession,
+    #[serde(default)]
+    pub chat_messages: Vec<ApiMessage>,
+}
+
+/// A message in a session; regenerated replies share a parent
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiMessage {
+    /// Counts up from 1 within the session
+    pub message_id: i64,
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    /// Often empty; see `thinking_enabled`
+    #[serde(default)]
+    pub model: String,
+    /// `USER` or `ASSISTANT`
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+    /// Whether DeepThink (R1) answered the message
+    #[serde(default)]
+    pub thinking_enabled: bool,
+    /// R1's reasoning before its answer
+    #[serde(default, alias = "reasoning_content")]
+    pub thinking_content: Option<String>,
+    pub inserted_at: f64,
+}
+
+/// Seconds since the epoch as a time
+pub fn timestamp(seconds: f64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_millis((seconds * 1000.0).round() as i64)
+}
@@ -0,0 +1,743 @@
+//! Resumable attachment downloads
+//!
+//! Content is streamed to `<path>.part` and renamed once complete, so a
+//! download interrupted by a flaky connection resumes with an HTTP Range
+//! request instead of starting over. Providers whose files sit behind signed,
+//! expiring URLs hand out a [`DownloadSource`]; when the link is rejected a
+//! fresh one is resolved and the download carries on.
+//!
+//! Providers that download through [`Provider::download_attachment`] use
+//! [`resume`] and [`save_response`] for the same part-file handling.
+//!
+//! Failures are classified: [`DownloadError::Permanent`] ones (the file is
+//! gone, or the provider won't serve it) are recorded so later pulls skip
+//! them, while [`DownloadError::Transient`] ones stay pending.
+
+use super::{Attachment, Provider, ProviderError, RetryPolicy};
+use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+
+/// Where an attachment's content can be fetched from right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadSource {
+    pub url: String,
+    /// Extra request headers, like an API key
+    pub headers: Vec<(String, String)>,
+}
+
+impl DownloadSource {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Why a download gave up
+#[derive(Error, Debug)]
+pub enum DownloadError {
+    /// Retrying won't help: the file is gone or the provider won't serve it
+    #[error("{0}")]
+    Permanent(String),
+
+    /// Retries ran out on failures that may clear up on a later run
+    #[error("{0}")]
+    Transient(String),
+
+    /// Larger than [`Downloader::with_max_size`] allows, in bytes
+    #[error("{0} bytes is over the size limit")]
+    TooLarge(u64),
+}
+
+impl DownloadError {
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, DownloadError::Permanent(_))
+    }
+}
+
+impl From<ProviderError> for DownloadError {
+    fn from(e: ProviderError) -> Self {
+        match e {
+            ProviderError::TooLarge(size) => DownloadError::TooLarge(size),
+            ProviderError::Api(_) | ProviderError::Parse(_) if !e.is_retryable() => {
+                DownloadError::Permanent(e.to_string())
+            }
+            _ => DownloadError::Transient(e.to_string()),
+        }
+    }
+}
+
+/// Outcome of one request for the rest of a file
+enum Fetch {
+    Complete,
+    /// The connection ended before the whole file arrived
+    Interrupted(String),
+    /// The link was refused, most likely because it expired
+    Expired(StatusCode),
+    /// Worth retrying after the backoff delay
+    Retry(String),
+}
+
+/// Downloads attachments with retries, resume and link refresh
+pub struct Downloader {
+    client: Client,
+    retry: RetryPolicy,
+    max_size: Option<u64>,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Downloader {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            retry: RetryPolicy::default(),
+            max_size: None,
+        }
+    }
+
+    /// Retry with `retry` instead of the default policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Refuse files larger than `bytes`
+    ///
+    /// Checked against the attachment's known size before downloading, the
+    /// response's length when it has one, and the file once written.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Download `attachment` to `path`
+    ///
+    /// Providers without a [`DownloadSource`] download through
+    /// [`Provider::download_attachment`], retried but not resumable.
+    pub async fn download(
+        &self,
+        provider: &dyn Provider,
+        attachment: &Attachment,
+        path: &Path,
+    ) -> Result<(), DownloadError> {
+        self.check_size(attachment.size_bytes)?;
+        let part = part_path(path);
+        let mut source: Option<DownloadSource> = None;
+        let mut refreshed_after_refusal = false;
+        let mut last_error = String::new();
+
+        for attempt in 1..=self.retry.max_attempts {
+            self.retry.gate.wait().await;
+
+            let fresh = source.is_none();
+            let current = match source.clone() {
+                Some(current) => current,
+                None => match provider.attachment_source(attachment).await {
+                    Ok(Some(resolved)) => {
+                        source = Some(resolved.clone());
+                        resolved
+                    }
+                    Ok(None) => {
+                        return self
+                            .download_with_provider(provider, attachment, path)
+                            .await
+                    }
+                    Err(e) if e.is_retryable() => {
+                        last_error = e.to_string();
+                        self.backoff(attempt).await;
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                },
+            };
+
+            let fetch = self.fetch(&current, attachment, &part).await?;
+            let refused_again = refreshed_after_refusal && fresh;
+            refreshed_after_refusal = false;
+            match fetch {
+                Fetch::Complete => {
+                    tokio::fs::rename(&part, path)
+                        .await
+                        .map_err(|e| write_error(&e))?;
+                    return Ok(());
+                }
+                // Resume right away; the server is answering
+                Fetch::Interrupted(reason) => last_error = reason,
+                Fetch::Expired(status) if refused_again => {
+                    return Err(DownloadError::Permanent(format!(
+                        "{}: refused even with a fresh download link",
+                        status
+                    )));
+                }
+                Fetch::Expired(status) => {
+                    last_error = format!("{}: download link expired", status);
+                    source = None;
+                    refreshed_after_refusal = true;
+                }
+                Fetch::Retry(reason) => {
+                    last_error = reason;
+                    self.backoff(attempt).await;
+                }
+            }
+        }
+
+        Err(DownloadError::Transient(last_error))
+    }
+
+    async fn download_with_provider(
+        &self,
+        provider: &dyn Provider,
+        attachment: &Attachment,
+        path: &Path,
+    ) -> Result<(), DownloadError> {
+        super::with_retry(&self.retry, || {
+            provider.download_attachment(attachment, path)
+        })
+        .await
+        .map_err(DownloadError::from)?;
+
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| write_error(&e))?
+            .len();
+        if let Err(e) = self.check_size(size) {
+            tokio::fs::remove_file(path)
+                .await
+                .map_err(|e| write_error(&e))?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn check_size(&self, size: u64) -> Result<(), DownloadError> {
+        match self.max_size {
+            Some(max) if size > max => Err(DownloadError::TooLarge(size)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn backoff(&self, attempt: u32) {
+        if attempt < self.retry.max_attempts {
+            tokio::time::sleep(self.retry.backoff(attempt)).await;
+        }
+    }
+
+    /// Request whatever `part` is still missing and append it
+    async fn fetch(
+        &self,
+        source: &DownloadSource,
+        attachment: &Attachment,
+        part: &Path,
+    ) -> Result<Fetch, DownloadError> {
+        let offset = match tokio::fs::metadata(part).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        let mut request = self.client.get(&source.url);
+        for (name, value) in &source.headers {
+            request = request.header(name, value);
+        }
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={}-", offset));
+        }
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return Ok(Fetch::Retry(e.to_string())),
+        };
+
+        let status = response.status();
+        let append = match status {
+            StatusCode::PARTIAL_CONTENT => true,
+            // The server ignored the range, so start the file over
+            StatusCode::OK => false,
+            // Nothing past what we already have
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(Fetch::Complete),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::GONE => {
+                return Ok(Fetch::Expired(status))
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                self.retry.gate.pause_for(retry_after(&response));
+                return Ok(Fetch::Retry(format!("{}: rate limited", status)));
+            }
+            _ if status.is_server_error() => {
+                return Ok(Fetch::Retry(format!("{}: server error", status)))
+            }
+            _ => {
+                return Err(DownloadError::Permanent(format!(
+                    "{}: download refused",
+                    status
+                )))
+            }
+        };
+
+        if let Some(length) = response.content_length() {
+            let total = if append { offset + length } else { length };
+            if let Err(e) = self.check_size(total) {
+                remove_part(part).await?;
+                return Err(e);
+            }
+        }
+
+        let written = write_body(response, part, append).await?;
+        let expected = attachment.size_bytes;
+        match written {
+            Err(reason) => Ok(Fetch::Interrupted(reason)),
+            Ok(size) if expected > 0 && size < expected => Ok(Fetch::Interrupted(format!(
+                "got {} of {} bytes",
+                size, expected
+            ))),
+            // Most likely a resume that stitched together two versions of the file
+            Ok(size) if expected > 0 && size > expected => {
+                remove_part(part).await?;
+                Ok(Fetch::Retry(format!(
+                    "got {} bytes, expected {}; starting over",
+                    size, expected
+                )))
+            }
+            Ok(size) => match self.check_size(size) {
+                Ok(()) => Ok(Fetch::Complete),
+                Err(e) => {
+                    remove_part(part).await?;
+                    Err(e)
+                }
+            },
+        }
+    }
+}
+
+/// Stream `response` into `part`, returning the file's size afterwards, or why
+/// the body ended early
+async fn write_body(
+    mut response: Response,
+    part: &Path,
+    append: bool,
+) -> Result<Result<u64, String>, DownloadError> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(part)
+        .await
+        .map_err(|e| write_error(&e))?;
+
+    let mut body_error = None;
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => file.write_all(&chunk).await.map_err(|e| write_error(&e))?,
+            Ok(None) => break,
+            Err(e) => {
+                body_error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+    file.flush().await.map_err(|e| write_error(&e))?;
+
+    let size = file.metadata().await.map_err(|e| write_error(&e))?.len();
+    Ok(body_error.map_or(Ok(size), Err))
+}
+
+async fn remove_part(part: &Path) -> Result<(), DownloadError> {
+    match tokio::fs::remove_file(part).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(write_error(&e)),
+        _ => Ok(()),
+    }
+}
+
+/// `request` asking only for what a previous attempt at `path` didn't get,
+/// if it left a part file
+pub async fn resume(request: RequestBuilder, path: &Path) -> RequestBuilder {
+    match tokio::fs::metadata(part_path(path)).await {
+        Ok(metadata) if metadata.len() > 0 => {
+            request.header(header::RANGE, format!("bytes={}-", metadata.len()))
+        }
+        _ => request,
+    }
+}
+
+/// Stream a download `response` into `path` through `<path>.part`
+///
+/// `206 Partial Content` is appended to the part file and any other success
+/// replaces it. If the connection drops the part file is kept, so a later
+/// attempt made with [`resume`] only asks for the rest. The file is moved
+/// into place once its size matches `expected_size`, when that is known.
+pub async fn save_response(
+    mut response: Response,
+    path: &Path,
+    expected_size: u64,
+) -> Result<(), ProviderError> {
+    let part = part_path(path);
+    let status = response.status();
+    let write_failed =
+        |e: std::io::Error| ProviderError::Api(format!("Failed to write file: {}", e));
+
+    // Nothing past what the part file holds: it's complete
+    let complete = status == StatusCode::RANGE_NOT_SATISFIABLE && part.exists();
+    if !complete {
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!("{}: download failed", status)));
+        }
+        let append = status == StatusCode::PARTIAL_CONTENT;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&part)
+            .await
+            .map_err(write_failed)?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await.map_err(write_failed)?;
+        }
+        file.flush().await.map_err(write_failed)?;
+    }
+
+    let size = tokio::fs::metadata(&part)
+        .await
+        .map_err(write_failed)?
+        .len();
+    if expected_size > 0 && size != expected_size {
+        tokio::fs::remove_file(&part).await.map_err(write_failed)?;
+        return Err(ProviderError::Api(format!(
+            "Downloaded {} bytes, expected {}",
+            size, expected_size
+        )));
+    }
+    tokio::fs::rename(&part, path).await.map_err(write_failed)?;
+    Ok(())
+}
+
+/// Disk errors may clear up (a full disk gets space), so they aren't final
+fn write_error(e: &std::io::Error) -> DownloadError {
+    DownloadError::Transient(format!("Failed to write file: {}", e))
+}
+
+fn retry_after(response: &Response) -> Duration {
+    let secs = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+/// Where the unfinished download of `path` is kept
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, Message, ProviderId, Result};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Hands out `urls` in turn as the attachment's download link
+    struct SignedUrls {
+        urls: Mutex<Vec<String>>,
+        resolved: AtomicUsize,
+    }
+
+    impl SignedUrls {
+        fn new(urls: &[String]) -> Self {
+            Self {
+                urls: Mutex::new(urls.iter().rev().cloned().collect()),
+                resolved: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for SignedUrls {
+        fn id(&self) -> ProviderId {
+            ProviderId::chatgpt()
+        }
+
+        async fn is_authenticated(&self) -> bool {
+            true
+        }
+
+        async fn authenticate(&mut self) -> Result<Account> {
+            unimplemented!()
+        }
+
+        async fn account(&self) -> Result<Account> {
+            unimplemented!()
+        }
+
+        async fn conversations(&self) -> Result<Vec<Conversation>> {
+            unimplemented!()
+        }
+
+        async fn conversation(&self, _: &str) -> Result<(Conversation, Vec<Message>)> {
+            unimplemented!()
+        }
+
+        async fn project_conversations(&self, _: &str) -> Result<Vec<Conversation>> {
+            unimplemented!()
+        }
+
+        async fn attachment_source(&self, _: &Attachment) -> Result<Option<DownloadSource>> {
+            self.resolved.fetch_add(1, Ordering::SeqCst);
+            let url = self.urls.lock().unwrap().pop();
+            Ok(Some(DownloadSource::new(url.expect("no more links"))))
+        }
+
+        async fn download_attachment(&self, _: &Attachment, _: &Path) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    fn attachment(size_bytes: u64) -> Attachment {
+        Attachment {
+            id: "att-1".to_string(),
+            message_id: "msg-1".to_string(),
+            filename: "talk.mp4".to_string(),
+            mime_type: "video/mp4".to_string(),
+            size_bytes,
+            download_url: "file-service://file-1".to_string(),
+            width: None,
+            height: None,
+            sha256: None,
+        }
+    }
+
+    fn downloader() -> Downloader {
+        Downloader::new().with_retry(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::ZERO,
+            jitter: false,
+            ..RetryPolicy::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_resumes_with_range() {
+        let server = MockServer::start().await;
+        let content: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        let (first_half, second_half) = content.split_at(2048);
+
+        Mock::given(method("GET"))
+            .and(path("/file"))
+            .and(header("range", "bytes=2048-"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(second_half))
+            .expect(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/file"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(first_half))
+            .up_to_n_times(1)
+            .with_priority(2)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("talk.mp4");
+        let provider = SignedUrls::new(&[format!("{}/file", server.uri())]);
+        downloader()
+            .download(&provider, &attachment(4096), &dest)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), content);
+        assert!(!part_path(&dest).exists());
+        assert_eq!(provider.resolved.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_link_is_refreshed() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/expired"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("AuthenticationFailed"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hello".to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("talk.mp4");
+        let provider = SignedUrls::new(&[
+            format!("{}/expired", server.uri()),
+            format!("{}/fresh", server.uri()),
+        ]);
+        downloader()
+            .download(&provider, &attachment(5), &dest)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), b"hello");
+        assert_eq!(provider.resolved.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_classified() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/gone"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/down"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/forbidden"))
+            .respond_with(ResponseTemplate::new(403))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("talk.mp4");
+        let download = |urls: &[&str]| {
+            let urls: Vec<_> = urls
+                .iter()
+                .map(|url| format!("{}{}", server.uri(), url))
+                .collect();
+            let dest = dest.clone();
+            async move {
+                downloader()
+                    .download(&SignedUrls::new(&urls), &attachment(5), &dest)
+                    .await
+                    .unwrap_err()
+            }
+        };
+
+        assert!(download(&["/gone"]).await.is_permanent());
+        // Still failing after every retry, but it may come back
+        let down = download(&["/down"]).await;
+        assert!(!down.is_permanent());
+        assert!(down.to_string().starts_with("503"));
+        // A fresh link doesn't help either
+        assert!(download(&["/forbidden", "/forbidden"]).await.is_permanent());
+        assert!(!dest.exists());
+    }
+
+    #[tokio::test]
+    async fn test_size_limit_and_oversized_files() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/big"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![7u8; 10]))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("talk.mp4");
+        let provider = SignedUrls::new(&[format!("{}/big", server.uri())]);
+
+        // Known to be too big: nothing is requested
+        let err = downloader()
+            .with_max_size(5)
+            .download(&provider, &attachment(10), &dest)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DownloadError::TooLarge(10)));
+        assert_eq!(provider.resolved.load(Ordering::SeqCst), 0);
+
+        // Unknown size: the response length gives it away
+        let err = downloader()
+            .with_max_size(5)
+            .download(&provider, &attachment(0), &dest)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DownloadError::TooLarge(10)));
+        assert!(!dest.exists() && !part_path(&dest).exists());
+
+        // More bytes than the provider said: start over, then give up
+        let provider = SignedUrls::new(&[format!("{}/big", server.uri())]);
+        let err = Downloader::new()
+            .with_retry(RetryPolicy {
+                max_attempts: 1,
+                base_delay: Duration::ZERO,
+                jitter: false,
+                ..RetryPolicy::default()
+            })
+            .download(&provider, &attachment(8), &dest)
+            .await
+            .unwrap_err();
+        assert!(!err.is_permanent());
+        assert!(err.to_string().contains("expected 8"), "{}", err);
+        assert!(!part_path(&dest).exists());
+    }
+
+    #[tokio::test]
+    async fn test_save_response_resumes_and_checks_size() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/file"))
+            .and(header("range", "bytes=5-"))
+            .respond_with(ResponseTemplate::new(206).set_body_bytes(b" world".to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/short"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hel".to_vec()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = Client::new();
+        let dest = dir.path().join("notes.txt");
+        std::fs::write(part_path(&dest), b"hello").unwrap();
+        let response = resume(client.get(format!("{}/file", server.uri())), &dest)
+            .await
+            .send()
+            .await
+            .unwrap();
+        save_response(response, &dest, 11).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"hello world");
+        assert!(!part_path(&dest).exists());
+
+        let short = dir.path().join("short.txt");
+        let response = resume(client.get(format!("{}/short", server.uri())), &short)
+            .await
+            .send()
+            .await
+            .unwrap();
+        let err = save_response(response, &short, 5).await.unwrap_err();
+        assert!(
+            err.to_string().contains("Downloaded 3 bytes, expected 5"),
+            "{}",
+            err
+        );
+        assert!(!short.exists() && !part_path(&short).exists());
+    }
+}
@@ -7,45 +7,74 @@
 
 pub mod types;
 
-use crate::credentials::{CredentialStore, KeyringStore};
+use crate::credentials::keys::fathom::API_KEY as KEYRING_API_KEY;
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::ingest::{WebhookEvent, WebhookParser};
 use crate::providers::{
-    Account, Attachment, Conversation, Message, MessageContent, Provider, ProviderId,
-    ProviderError, Result, Role,
+    download, with_retry, Account, Attachment, Conversation, Message, MessageContent, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
 };
 use async_trait::async_trait;
-use reqwest::{header, Client};
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::{header, Client, StatusCode};
 use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
 use types::*;
 
 const API_BASE: &str = "https://api.fathom.ai/external/v1";
-const KEYRING_SERVICE: &str = "quaid";
-const KEYRING_API_KEY: &str = "fathom-api-key";
 
 /// Fathom.video provider
 pub struct FathomProvider {
     client: Client,
     api_key: Arc<RwLock<Option<String>>>,
     credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Which meetings are listed
+    filter: MeetingFilter,
+    /// Meetings with transcripts, listed once for [`Provider::conversation`]
+    meetings: OnceCell<Vec<ApiMeeting>>,
+    /// Register each meeting's recording as an attachment
+    include_recordings: bool,
+    /// Refuse recordings larger than this many bytes
+    max_recording_size: Option<u64>,
 }
 
 impl FathomProvider {
     /// Create a new Fathom provider, loading API key from keyring if available
     pub fn new() -> Self {
-        Self::with_credential_store(Arc::new(KeyringStore::new()))
+        Self::with_credential_store(default_store())
+    }
+
+    /// Create for the account signed in as `email`, using its own API key
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
     }
 
     /// Create with a custom credential store (for testing)
     pub fn with_credential_store(credential_store: Arc<dyn CredentialStore>) -> Self {
+        Self::with_account_credentials(credential_store, None)
+    }
+
+    /// Create with a custom credential store, loading `email`'s API key
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
         let api_key = credential_store
-            .get(KEYRING_SERVICE, KEYRING_API_KEY)
+            .get_for_account(KEYRING_SERVICE, KEYRING_API_KEY, email)
             .ok();
 
         Self {
             client: build_client(),
             api_key: Arc::new(RwLock::new(api_key)),
             credential_store,
+            retry: RetryPolicy::default(),
+            filter: MeetingFilter::default(),
+            meetings: OnceCell::new(),
+            include_recordings: false,
+            max_recording_size: None,
         }
     }
 
@@ -54,10 +83,48 @@ impl FathomProvider {
         Self {
             client: build_client(),
             api_key: Arc::new(RwLock::new(Some(api_key))),
-            credential_store: Arc::new(KeyringStore::new()),
+            credential_store: default_store(),
+            retry: RetryPolicy::default(),
+            filter: MeetingFilter::default(),
+            meetings: OnceCell::new(),
+            include_recordings: false,
+            max_recording_size: None,
         }
     }
 
+    /// Only pull meetings recorded by one of `teams`
+    pub fn with_teams(mut self, teams: Vec<String>) -> Self {
+        self.filter.teams = teams;
+        self
+    }
+
+    /// Only list meetings created after `created_after`, when there's one
+    pub fn with_created_after(mut self, created_after: Option<DateTime<Utc>>) -> Self {
+        self.filter.created_after = created_after;
+        self
+    }
+
+    /// Only list meetings created before `created_before`, when there's one
+    pub fn with_created_before(mut self, created_before: Option<DateTime<Utc>>) -> Self {
+        self.filter.created_before = created_before;
+        self
+    }
+
+    /// Register meeting recordings as attachments, for pulls to download
+    pub fn with_include_recordings(mut self, include_recordings: bool) -> Self {
+        self.include_recordings = include_recordings;
+        self
+    }
+
+    /// Refuse recordings larger than `bytes`, when set
+    ///
+    /// Recordings are listed without a size, so it's checked against the
+    /// download's length before any of it is written.
+    pub fn with_max_recording_size(mut self, bytes: Option<u64>) -> Self {
+        self.max_recording_size = bytes;
+        self
+    }
+
     /// Get the current API key
     async fn get_api_key(&self) -> Result<String> {
         self.api_key
@@ -67,8 +134,12 @@ impl FathomProvider {
             .ok_or(ProviderError::AuthRequired)
     }
 
-    /// Make an authenticated GET request
+    /// Make an authenticated GET request, retrying transient failures
     async fn api_get<T: serde::de::DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        with_retry(&self.retry, || self.api_get_once(endpoint)).await
+    }
+
+    async fn api_get_once<T: serde::de::DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
         let api_key = self.get_api_key().await?;
         let url = format!("{}{}", API_BASE, endpoint);
 
@@ -97,22 +168,37 @@ impl FathomProvider {
 
         if !status.is_success() {
             let text = response.text().await.unwrap_or_default();
-            return Err(ProviderError::Api(format!("{}: {}", status, truncate(&text, 500))));
+            return Err(ProviderError::Api(format!(
+                "{}: {}",
+                status,
+                truncate(&text, 500)
+            )));
         }
 
         let text = response.text().await?;
-        serde_json::from_str(&text).map_err(|e| {
-            ProviderError::Parse(format!("{}: {}", e, truncate(&text, 200)))
-        })
+        serde_json::from_str(&text)
+            .map_err(|e| ProviderError::Parse(format!("{}: {}", e, truncate(&text, 200))))
     }
 
-    /// Fetch all meetings with transcripts (public for efficient bulk sync)
-    pub async fn fetch_all_meetings_with_transcripts(&self) -> Result<Vec<ApiMeeting>> {
-        self.fetch_all_meetings(true).await
+    /// One page of meetings with transcripts, starting at `cursor` or at the
+    /// newest meeting (public for resumable bulk sync)
+    ///
+    /// The page's `next_cursor` picks up where it ends, also in a later run.
+    pub async fn meetings_page(&self, cursor: Option<&str>) -> Result<ApiMeetingsResponse> {
+        let endpoint = meetings_endpoint(true, &self.filter, cursor);
+        let mut page: ApiMeetingsResponse = self.api_get(&endpoint).await?;
+        if !self.filter.teams.is_empty() {
+            page.items.retain(|m| self.in_teams(m));
+        }
+        Ok(page)
     }
 
-    /// Convert a meeting to conversation + messages (public for bulk sync)
-    pub fn meeting_to_data(&self, meeting: &ApiMeeting) -> (Conversation, Vec<Message>) {
+    /// Convert a meeting to conversation + messages + the recording, when
+    /// recordings are included (public for bulk sync)
+    pub fn meeting_to_data(
+        &self,
+        meeting: &ApiMeeting,
+    ) -> (Conversation, Vec<Message>, Vec<Attachment>) {
         let conversation = Self::meeting_to_conversation(meeting);
         let mut messages = Self::transcript_to_messages(&meeting.id(), &meeting.transcript);
 
@@ -120,7 +206,70 @@ impl FathomProvider {
             messages.insert(0, summary_msg);
         }
 
-        (conversation, messages)
+        let attachments = messages
+            .first()
+            .filter(|_| self.include_recordings)
+            .and_then(|first| Self::recording_attachment(meeting, &first.id))
+            .into_iter()
+            .collect();
+
+        (conversation, messages, attachments)
+    }
+
+    /// The meeting recording as an attachment on `message_id`
+    ///
+    /// `download_url` holds either the media URL or the share page, which
+    /// [`Provider::download_attachment`] resolves at download time.
+    fn recording_attachment(meeting: &ApiMeeting, message_id: &str) -> Option<Attachment> {
+        let source = meeting.recording_source()?;
+        let id = meeting.id();
+
+        Some(Attachment {
+            id: format!("{}-recording", id),
+            message_id: message_id.to_string(),
+            filename: format!("fathom-{}.mp4", id),
+            mime_type: "video/mp4".to_string(),
+            size_bytes: 0,
+            download_url: source.to_string(),
+            width: None,
+            height: None,
+            sha256: None,
+        })
+    }
+
+    /// Turn a share page URL into a direct media URL
+    ///
+    /// Media URLs are returned as is. Share pages are fetched and searched for
+    /// the video they embed.
+    async fn resolve_recording_url(&self, url: &str) -> Result<String> {
+        if is_media_url(url) {
+            return Ok(url.to_string());
+        }
+
+        let response = self
+            .client
+            .get(url)
+            .header(header::ACCEPT, "*/*")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: could not open share page {}",
+                response.status(),
+                url
+            )));
+        }
+        if is_video_response(&response) {
+            return Ok(response.url().to_string());
+        }
+
+        let html = response.text().await?;
+        find_media_url(&html).ok_or_else(|| {
+            ProviderError::Api(format!(
+                "Recording isn't downloadable from {} (no video on the share page)",
+                url
+            ))
+        })
     }
 
     /// Fetch all meetings with pagination
@@ -129,14 +278,7 @@ impl FathomProvider {
         let mut cursor: Option<String> = None;
 
         loop {
-            let mut endpoint = "/meetings?limit=100".to_string();
-            if include_transcript {
-                endpoint.push_str("&include_transcript=true");
-            }
-            if let Some(ref c) = cursor {
-                endpoint.push_str(&format!("&cursor={}", c));
-            }
-
+            let endpoint = meetings_endpoint(include_transcript, &self.filter, cursor.as_deref());
             let response: ApiMeetingsResponse = self.api_get(&endpoint).await?;
             meetings.extend(response.items);
 
@@ -146,9 +288,23 @@ impl FathomProvider {
             }
         }
 
+        if !self.filter.teams.is_empty() {
+            meetings.retain(|m| self.in_teams(m));
+        }
         Ok(meetings)
     }
 
+    /// Whether `meeting` was recorded by one of the selected teams
+    fn in_teams(&self, meeting: &ApiMeeting) -> bool {
+        let team = meeting.recorded_by.as_ref().and_then(|r| r.team.as_deref());
+        team.is_some_and(|team| {
+            self.filter
+                .teams
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(team))
+        })
+    }
+
     /// Convert a Fathom meeting to our Conversation type
     fn meeting_to_conversation(meeting: &ApiMeeting) -> Conversation {
         let updated_at = meeting
@@ -174,28 +330,21 @@ impl FathomProvider {
         transcript
             .iter()
             .enumerate()
-            .map(|(idx, entry)| {
-                // Format text with speaker name
-                let speaker = entry
-                    .speaker
-                    .display_name
-                    .clone()
-                    .unwrap_or_else(|| "Speaker".to_string());
-                let text = format!("**{}**: {}", speaker, entry.text);
-
-                Message {
-                    id: format!("{}-{}", meeting_id, idx),
-                    conversation_id: meeting_id.to_string(),
-                    parent_id: if idx > 0 {
-                        Some(format!("{}-{}", meeting_id, idx - 1))
-                    } else {
-                        None
-                    },
-                    role: Role::User, // All transcript entries are "user" speech
-                    content: MessageContent::Text { text },
-                    created_at: None, // Individual timestamps are relative, not absolute
-                    model: None,
-                }
+            .map(|(idx, entry)| Message {
+                id: format!("{}-{}", meeting_id, idx),
+                conversation_id: meeting_id.to_string(),
+                parent_id: if idx > 0 {
+                    Some(format!("{}-{}", meeting_id, idx - 1))
+                } else {
+                    None
+                },
+                role: Role::User, // All transcript entries are "user" speech
+                content: MessageContent::Text {
+                    text: entry.text.clone(),
+                },
+                created_at: None, // Individual timestamps are relative, not absolute
+                model: None,
+                speaker: entry.speaker.display_name.clone(),
             })
             .collect()
     }
@@ -233,6 +382,7 @@ impl FathomProvider {
             content: MessageContent::Text { text: content },
             created_at: Some(meeting.created_at),
             model: Some("fathom-ai".to_string()),
+            speaker: None,
         })
     }
 }
@@ -253,6 +403,20 @@ impl Provider for FathomProvider {
         self.api_key.read().await.is_some()
     }
 
+    async fn validate_credentials(&self) -> Result<()> {
+        self.api_get::<ApiMeetingsResponse>("/meetings?limit=1")
+            .await
+            .map(|_| ())
+            .map_err(|e| match e {
+                ProviderError::AuthFailed(_) => ProviderError::AuthFailed(
+                    "Fathom rejected the API key; create a new one at \
+                     https://fathom.video/api_settings/new and run `quaid fathom auth`"
+                        .to_string(),
+                ),
+                _ => e,
+            })
+    }
+
     async fn authenticate(&mut self) -> Result<Account> {
         // Fathom uses API keys, not OAuth
         // User needs to provide their API key manually
@@ -263,7 +427,9 @@ impl Provider for FathomProvider {
 
         // Read from stdin
         use std::io::{self, Write};
-        io::stdout().flush().map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+        io::stdout()
+            .flush()
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
 
         let mut api_key = String::new();
         io::stdin()
@@ -280,14 +446,23 @@ impl Provider for FathomProvider {
         *self.api_key.write().await = Some(api_key.clone());
 
         // Try to fetch meetings to validate the key
-        match self.api_get::<ApiMeetingsResponse>("/meetings?limit=1").await {
+        match self
+            .api_get::<ApiMeetingsResponse>("/meetings?limit=1")
+            .await
+        {
             Ok(_) => {
-                // Save to credential store
-                if let Err(e) = self.credential_store.set(KEYRING_SERVICE, KEYRING_API_KEY, &api_key) {
+                // Save under the key's account, so other accounts keep theirs
+                let account = self.account().await?;
+                if let Err(e) = self.credential_store.set_for_account(
+                    KEYRING_SERVICE,
+                    KEYRING_API_KEY,
+                    &account.email,
+                    &api_key,
+                ) {
                     eprintln!("Warning: failed to save API key: {}", e);
                 }
                 println!("API key validated and saved!");
-                self.account().await
+                Ok(account)
             }
             Err(e) => {
                 *self.api_key.write().await = None;
@@ -317,6 +492,7 @@ impl Provider for FathomProvider {
             email: email.unwrap_or_else(|| "unknown".to_string()),
             name,
             avatar_url: None,
+            workspace_id: None,
         })
     }
 
@@ -326,25 +502,19 @@ impl Provider for FathomProvider {
     }
 
     async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
-        // Fetch all meetings with transcripts and find the one we need
-        // (Fathom API doesn't have a single-meeting endpoint)
-        let meetings = self.fetch_all_meetings(true).await?;
+        // Fathom API doesn't have a single-meeting endpoint, so meetings with
+        // transcripts are listed on the first call and looked up after that
+        let meetings = self
+            .meetings
+            .get_or_try_init(|| self.fetch_all_meetings(true))
+            .await?;
 
         let meeting = meetings
-            .into_iter()
+            .iter()
             .find(|m| m.id() == id)
             .ok_or_else(|| ProviderError::Api(format!("Meeting {} not found", id)))?;
 
-        let conversation = Self::meeting_to_conversation(&meeting);
-
-        // Build messages from transcript
-        let mut messages = Self::transcript_to_messages(id, &meeting.transcript);
-
-        // Add summary as a special message at the beginning
-        if let Some(summary_msg) = Self::build_summary_message(&meeting) {
-            messages.insert(0, summary_msg);
-        }
-
+        let (conversation, messages, _) = self.meeting_to_data(meeting);
         Ok((conversation, messages))
     }
 
@@ -357,30 +527,136 @@ impl Provider for FathomProvider {
             .collect())
     }
 
-    async fn download_attachment(
-        &self,
-        _attachment: &Attachment,
-        _path: &Path,
-    ) -> Result<()> {
-        // Fathom doesn't have traditional attachments
-        // Video recordings might be downloadable via share_url
-        Err(ProviderError::Api(
-            "Attachment download not supported for Fathom".to_string(),
-        ))
+    async fn download_attachment(&self, attachment: &Attachment, path: &Path) -> Result<()> {
+        // The only Fathom attachments are meeting recordings
+        let api_key = self.get_api_key().await?;
+        let media_url = self.resolve_recording_url(&attachment.download_url).await?;
+
+        let request = self
+            .client
+            .get(&media_url)
+            .header("X-Api-Key", &api_key)
+            .header(header::ACCEPT, "*/*");
+        let response = download::resume(request, path).await.send().await?;
+
+        let status = response.status();
+        if status == 401 || status == 403 || status == 404 {
+            return Err(ProviderError::Api(format!(
+                "{}: recording isn't downloadable on this plan",
+                status
+            )));
+        }
+        // 416: an earlier attempt already got the whole recording
+        if status == StatusCode::RANGE_NOT_SATISFIABLE {
+            return download::save_response(response, path, attachment.size_bytes).await;
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: failed to download recording",
+                status
+            )));
+        }
+        if !is_video_response(&response) {
+            return Err(ProviderError::Api(
+                "Recording link didn't return a video".to_string(),
+            ));
+        }
+
+        if let Some(size) = recording_size(&response) {
+            // Left by a run that stopped before recording the download
+            if is_downloaded(path, size).await {
+                return Ok(());
+            }
+            if self.max_recording_size.is_some_and(|max| size > max) {
+                return Err(ProviderError::TooLarge(size));
+            }
+        }
+
+        // Recordings can be large, so stream them to disk
+        download::save_response(response, path, attachment.size_bytes).await
     }
 }
 
+/// Parses Fathom's "new meeting content ready" webhook
+///
+/// The payload is the same meeting object returned by `GET /meetings`.
+pub struct FathomWebhookParser;
+
+impl WebhookParser for FathomWebhookParser {
+    fn provider(&self) -> ProviderId {
+        ProviderId::fathom()
+    }
+
+    fn parse(&self, body: &[u8]) -> Result<WebhookEvent> {
+        let meeting: ApiMeeting = serde_json::from_slice(body)
+            .map_err(|e| ProviderError::Parse(format!("Invalid Fathom webhook: {}", e)))?;
+
+        Ok(WebhookEvent {
+            provider: self.provider(),
+            conversation_id: meeting.id(),
+            created_at: Some(meeting.created_at),
+        })
+    }
+}
+
+/// Which meetings `GET /meetings` lists
+#[derive(Debug, Clone, Default)]
+struct MeetingFilter {
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    /// Recording teams, by name; all teams when empty
+    teams: Vec<String>,
+}
+
+/// `GET /meetings` for one page of up to 100 meetings
+fn meetings_endpoint(
+    include_transcript: bool,
+    filter: &MeetingFilter,
+    cursor: Option<&str>,
+) -> String {
+    let mut endpoint = "/meetings?limit=100".to_string();
+    if include_transcript {
+        endpoint.push_str("&include_transcript=true");
+    }
+    for (param, time) in [
+        ("created_after", filter.created_after),
+        ("created_before", filter.created_before),
+    ] {
+        if let Some(time) = time {
+            endpoint.push_str(&format!(
+                "&{}={}",
+                param,
+                time.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ));
+        }
+    }
+    for team in &filter.teams {
+        endpoint.push_str(&format!("&teams[]={}", encode_query(team)));
+    }
+    if let Some(c) = cursor {
+        endpoint.push_str(&format!("&cursor={}", c));
+    }
+    endpoint
+}
+
+/// Percent-encode a query parameter value
+fn encode_query(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
 /// Build HTTP client with appropriate headers
 fn build_client() -> Client {
     let mut headers = header::HeaderMap::new();
-    headers.insert(
-        header::ACCEPT,
-        "application/json".parse().unwrap(),
-    );
-    headers.insert(
-        header::CONTENT_TYPE,
-        "application/json".parse().unwrap(),
-    );
+    headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+    headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
 
     Client::builder()
         .default_headers(headers)
@@ -388,6 +664,65 @@ fn build_client() -> Client {
         .expect("Failed to build HTTP client")
 }
 
+/// Whether `url` points straight at a video file
+fn is_media_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    [".mp4", ".m4v", ".mov", ".webm"]
+        .iter()
+        .any(|ext| path.ends_with(ext))
+}
+
+/// Whether the response body is video (or opaque bytes) rather than a page
+fn is_video_response(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("video/") || ct.starts_with("application/octet-stream"))
+}
+
+/// Full size of the recording a response is for, whether it holds all of it
+/// or the rest of a resumed download
+fn recording_size(response: &reqwest::Response) -> Option<u64> {
+    let total = response
+        .headers()
+        .get(header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|range| range.rsplit_once('/'))
+        .and_then(|(_, total)| total.parse().ok());
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT => total,
+        _ => response.content_length(),
+    }
+}
+
+/// Whether `path` already holds the whole recording of `size` bytes
+async fn is_downloaded(path: &Path, size: u64) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.len() == size)
+}
+
+/// Find the video a share page embeds, via `og:video` or a `<video>`/`<source>` tag
+fn find_media_url(html: &str) -> Option<String> {
+    let candidates = html.split(['"', '\'']).filter(|s| s.starts_with("http"));
+    for candidate in candidates {
+        let url = candidate.replace("&amp;", "&");
+        if is_media_url(&url) {
+            return Some(url);
+        }
+    }
+
+    // Signed URLs often have no extension, so fall back to og:video
+    let start = html.find("og:video")?;
+    let rest = &html[start..];
+    let content = rest.find("content=")? + "content=".len();
+    let quote = rest[content..].chars().next()?;
+    let value = &rest[content + 1..];
+    let end = value.find(quote)?;
+    Some(value[..end].replace("&amp;", "&"))
+}
+
 /// Truncate a string safely at char boundaries
 fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
@@ -426,13 +761,42 @@ mod tests {
 
     #[tokio::test]
     async fn test_provider_with_stored_credentials() {
-        let store = MockStore::with_credentials(vec![
-            (KEYRING_SERVICE, KEYRING_API_KEY, "stored-api-key"),
-        ]);
+        let store =
+            MockStore::with_credentials(vec![(KEYRING_SERVICE, KEYRING_API_KEY, "stored-api-key")]);
         let provider = FathomProvider::with_credential_store(Arc::new(store));
         assert!(provider.is_authenticated().await);
     }
 
+    #[test]
+    fn test_meetings_endpoint_resumes_after_the_last_sync() {
+        let all = MeetingFilter::default();
+        assert_eq!(meetings_endpoint(false, &all, None), "/meetings?limit=100");
+
+        let filter = MeetingFilter {
+            created_after: "2025-01-15T10:00:00.250Z".parse().ok(),
+            ..Default::default()
+        };
+        assert_eq!(
+            meetings_endpoint(true, &filter, Some("eyJwYWdlIjoyfQ")),
+            "/meetings?limit=100&include_transcript=true\
+             &created_after=2025-01-15T10:00:00Z&cursor=eyJwYWdlIjoyfQ"
+        );
+    }
+
+    #[test]
+    fn test_meetings_endpoint_filters_by_date_and_team() {
+        let filter = MeetingFilter {
+            created_after: "2025-01-01T00:00:00Z".parse().ok(),
+            created_before: "2025-02-01T00:00:00Z".parse().ok(),
+            teams: vec!["Sales".to_string(), "R&D Europe".to_string()],
+        };
+        assert_eq!(
+            meetings_endpoint(false, &filter, None),
+            "/meetings?limit=100&created_after=2025-01-01T00:00:00Z\
+             &created_before=2025-02-01T00:00:00Z&teams[]=Sales&teams[]=R%26D%20Europe"
+        );
+    }
+
     #[test]
     fn test_meeting_to_conversation() {
         let meeting = ApiMeeting {
@@ -440,6 +804,7 @@ mod tests {
             meeting_title: None,
             url: "https://fathom.video/calls/meeting-123".to_string(),
             share_url: None,
+            recording_url: None,
             created_at: chrono::Utc::now(),
             scheduled_start_time: None,
             scheduled_end_time: None,
@@ -461,6 +826,40 @@ mod tests {
         assert_eq!(conv.provider_id, "fathom");
     }
 
+    #[test]
+    fn test_teams_filter() {
+        let meeting = |team: Option<&str>| ApiMeeting {
+            title: Some("Team Sync".to_string()),
+            meeting_title: None,
+            url: "https://fathom.video/calls/meeting-123".to_string(),
+            share_url: None,
+            recording_url: None,
+            created_at: chrono::Utc::now(),
+            scheduled_start_time: None,
+            scheduled_end_time: None,
+            recording_start_time: None,
+            recording_end_time: None,
+            meeting_type: None,
+            transcript_language: None,
+            calendar_invitees: vec![],
+            recorded_by: Some(ApiRecordedBy {
+                name: None,
+                email: None,
+                team: team.map(str::to_string),
+            }),
+            transcript: vec![],
+            default_summary: None,
+            action_items: vec![],
+            crm_matches: None,
+        };
+
+        let provider =
+            FathomProvider::with_api_key("key".to_string()).with_teams(vec!["Sales".to_string()]);
+        assert!(provider.in_teams(&meeting(Some("sales"))));
+        assert!(!provider.in_teams(&meeting(Some("Engineering"))));
+        assert!(!provider.in_teams(&meeting(None)));
+    }
+
     #[test]
     fn test_transcript_to_messages() {
         let transcript = vec![
@@ -487,11 +886,11 @@ mod tests {
         assert_eq!(messages[0].id, "meeting-1-0");
         assert_eq!(messages[1].parent_id, Some("meeting-1-0".to_string()));
 
+        assert_eq!(messages[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(messages[1].speaker.as_deref(), Some("Bob"));
+
         match &messages[0].content {
-            MessageContent::Text { text } => {
-                assert!(text.contains("Alice"));
-                assert!(text.contains("Hello everyone"));
-            }
+            MessageContent::Text { text } => assert_eq!(text, "Hello everyone"),
             _ => panic!("Expected Text content"),
         }
     }
@@ -503,6 +902,7 @@ mod tests {
             meeting_title: None,
             url: "https://fathom.video/calls/meeting-123".to_string(),
             share_url: None,
+            recording_url: None,
             created_at: chrono::Utc::now(),
             scheduled_start_time: None,
             scheduled_end_time: None,
@@ -517,15 +917,13 @@ mod tests {
                 template_name: Some("Default".to_string()),
                 markdown_formatted: Some("## Summary\nGreat meeting!".to_string()),
             }),
-            action_items: vec![
-                ApiActionItem {
-                    description: "Follow up on proposal".to_string(),
-                    assignee: Some(ApiAssignee {
-                        name: Some("Alice".to_string()),
-                        email: None,
-                    }),
-                },
-            ],
+            action_items: vec![ApiActionItem {
+                description: "Follow up on proposal".to_string(),
+                assignee: Some(ApiAssignee {
+                    name: Some("Alice".to_string()),
+                    email: None,
+                }),
+            }],
             crm_matches: None,
         };
 
@@ -544,6 +942,199 @@ mod tests {
         }
     }
 
+    fn meeting_with_recording(share_url: &str) -> ApiMeeting {
+        serde_json::from_value(serde_json::json!({
+            "title": "Team Sync",
+            "url": "https://fathom.video/calls/meeting-123",
+            "share_url": share_url,
+            "created_at": "2025-01-10T12:00:00Z",
+            "transcript": [
+                { "speaker": { "display_name": "Alice" }, "text": "Hello everyone" }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_meeting_to_data_adds_recording_attachment() {
+        let meeting = meeting_with_recording("https://fathom.video/share/abc");
+        let provider = FathomProvider::with_api_key("test-key".to_string());
+        let (_, _, attachments) = provider.meeting_to_data(&meeting);
+        assert!(attachments.is_empty());
+
+        let provider = provider.with_include_recordings(true);
+        let (_, messages, attachments) = provider.meeting_to_data(&meeting);
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].id, "meeting-123-recording");
+        assert_eq!(attachments[0].message_id, messages[0].id);
+        assert_eq!(attachments[0].mime_type, "video/mp4");
+        assert_eq!(
+            attachments[0].download_url,
+            "https://fathom.video/share/abc"
+        );
+
+        let mut no_link = meeting;
+        no_link.share_url = None;
+        let (_, _, attachments) = provider.meeting_to_data(&no_link);
+        assert!(attachments.is_empty());
+    }
+
+    #[test]
+    fn test_find_media_url() {
+        let html =
+            r#"<video><source src="https://cdn.fathom.video/rec/abc.mp4?sig=x&amp;t=1"></video>"#;
+        assert_eq!(
+            find_media_url(html).as_deref(),
+            Some("https://cdn.fathom.video/rec/abc.mp4?sig=x&t=1")
+        );
+
+        let og = r#"<meta property="og:video" content="https://cdn.fathom.video/signed/abc">"#;
+        assert_eq!(
+            find_media_url(og).as_deref(),
+            Some("https://cdn.fathom.video/signed/abc")
+        );
+
+        assert_eq!(
+            find_media_url("<p>Upgrade to download recordings</p>"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_media_url_in_share_page() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join("fathom-share-page.html");
+        let html = std::fs::read_to_string(path).unwrap();
+        // The thumbnail and page links are passed over for the video itself
+        assert_eq!(
+            find_media_url(&html).as_deref(),
+            Some("https://cdn.fathom.video/recordings/Xk2pQ9/video.mp4?Expires=1736510400&Signature=f0a1b2c3")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_recording_from_share_page() {
+        use wiremock::matchers::{header as header_is, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let page = format!(
+            r#"<html><meta property="og:video" content="{}/media/abc"></html>"#,
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/share/abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/media/abc"))
+            .and(header_is("X-Api-Key", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("mp4 bytes", "video/mp4"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider =
+            FathomProvider::with_api_key("test-key".to_string()).with_include_recordings(true);
+        let meeting = meeting_with_recording(&format!("{}/share/abc", server.uri()));
+        let (_, _, attachments) = provider.meeting_to_data(&meeting);
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join(&attachments[0].filename);
+        provider
+            .download_attachment(&attachments[0], &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"mp4 bytes");
+    }
+
+    #[tokio::test]
+    async fn test_download_recording_not_available_on_plan() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let provider =
+            FathomProvider::with_api_key("test-key".to_string()).with_include_recordings(true);
+        let mut meeting = meeting_with_recording("");
+        meeting.recording_url = Some(format!("{}/media/abc.mp4", server.uri()));
+        let (_, _, attachments) = provider.meeting_to_data(&meeting);
+
+        let dir = tempfile::tempdir().unwrap();
+        let err = provider
+            .download_attachment(&attachments[0], &dir.path().join("rec.mp4"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("isn't downloadable"), "{}", err);
+        assert!(!dir.path().join("rec.mp4").exists());
+    }
+
+    /// A provider including recordings, and the attachment for one served
+    /// as `mp4 bytes` from a mock server
+    async fn recording_server() -> (wiremock::MockServer, FathomProvider, Attachment) {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("mp4 bytes", "video/mp4"))
+            .mount(&server)
+            .await;
+
+        let provider =
+            FathomProvider::with_api_key("test-key".to_string()).with_include_recordings(true);
+        let mut meeting = meeting_with_recording("");
+        meeting.recording_url = Some(format!("{}/media/abc.mp4", server.uri()));
+        let (_, _, mut attachments) = provider.meeting_to_data(&meeting);
+        (server, provider, attachments.remove(0))
+    }
+
+    #[tokio::test]
+    async fn test_download_skips_recording_already_on_disk() {
+        let (_server, provider, attachment) = recording_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("rec.mp4");
+
+        // Same size as the recording: taken to be it, and left alone
+        std::fs::write(&dest, "old bytes").unwrap();
+        provider
+            .download_attachment(&attachment, &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"old bytes");
+
+        // Any other size is downloaded again
+        std::fs::write(&dest, "old").unwrap();
+        provider
+            .download_attachment(&attachment, &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"mp4 bytes");
+    }
+
+    #[tokio::test]
+    async fn test_download_refuses_recording_over_max_size() {
+        let (_server, provider, attachment) = recording_server().await;
+        let provider = provider.with_max_recording_size(Some(4));
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("rec.mp4");
+
+        let err = provider
+            .download_attachment(&attachment, &dest)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::TooLarge(9)), "{}", err);
+        assert!(!dest.exists());
+        assert!(!download::part_path(&dest).exists());
+    }
+
     #[test]
     fn test_truncate() {
         assert_eq!(truncate("hello", 10), "hello");
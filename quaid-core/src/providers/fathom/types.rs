@@ -36,6 +36,9 @@ pub struct ApiMeeting {
     pub url: String,
     #[serde(default)]
     pub share_url: Option<String>,
+    /// Direct link to the recording media, on plans that expose it
+    #[serde(default, alias = "video_url")]
+    pub recording_url: Option<String>,
     pub created_at: DateTime<Utc>,
     #[serde(default)]
     pub scheduled_start_time: Option<DateTime<Utc>>,
@@ -66,11 +69,16 @@ pub struct ApiMeeting {
 impl ApiMeeting {
     /// Get meeting ID from URL (e.g., https://fathom.video/calls/123 -> 123)
     pub fn id(&self) -> String {
-        self.url
-            .rsplit('/')
-            .next()
-            .unwrap_or(&self.url)
-            .to_string()
+        self.url.rsplit('/').next().unwrap_or(&self.url).to_string()
+    }
+
+    /// Where the recording can be fetched from: the media URL if the API gave
+    /// one, otherwise the public share page
+    pub fn recording_source(&self) -> Option<&str> {
+        self.recording_url
+            .as_deref()
+            .or(self.share_url.as_deref())
+            .filter(|url| !url.is_empty())
     }
 
     /// Get the best available title for this meeting
@@ -235,6 +243,7 @@ mod tests {
             meeting_title: Some("Calendar Title".to_string()),
             url: "https://fathom.video/calls/test-123".to_string(),
             share_url: None,
+            recording_url: None,
             created_at: Utc::now(),
             scheduled_start_time: None,
             scheduled_end_time: None,
@@ -276,7 +285,11 @@ mod tests {
         };
 
         assert_eq!(summary.template_name, Some("Default".to_string()));
-        assert!(summary.markdown_formatted.as_ref().unwrap().contains("Summary"));
+        assert!(summary
+            .markdown_formatted
+            .as_ref()
+            .unwrap()
+            .contains("Summary"));
     }
 
     #[test]
@@ -291,6 +304,9 @@ mod tests {
 
         let item: ApiActionItem = serde_json::from_str(json).unwrap();
         assert_eq!(item.description, "Review the proposal by Friday");
-        assert_eq!(item.assignee.as_ref().unwrap().name, Some("Charlie".to_string()));
+        assert_eq!(
+            item.assignee.as_ref().unwrap().name,
+            Some("Charlie".to_string())
+        );
     }
 }
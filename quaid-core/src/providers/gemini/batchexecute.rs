@@ -0,0 +1,141 @@
+//! Codec for Google's `batchexecute` RPC protocol
+//!
+//! A request names an RPC and passes its arguments as a JSON string inside the
+//! `f.req` form field. The response starts with an XSSI guard, followed by
+//! JSON chunks, each preceded by its length. A chunk is a list of envelopes,
+//! and a call's result is the `wrb.fr` envelope carrying its RPC id. The
+//! result is itself a JSON string of nested arrays, read by position with
+//! [`at`].
+
+use crate::providers::{ProviderError, Result};
+use serde_json::{json, Value};
+
+/// Guard line prepended to every response
+const XSSI_PREFIX: &str = ")]}'";
+
+static NULL: Value = Value::Null;
+
+/// The `f.req` form value that calls `rpc_id` with `args`
+pub fn encode_request(rpc_id: &str, args: &Value) -> String {
+    json!([[[rpc_id, args.to_string(), null, "generic"]]]).to_string()
+}
+
+/// Every envelope in a response body, in order
+///
+/// The chunk lengths count UTF-16 code units, which doesn't match byte
+/// offsets once there's non-ASCII text, so chunks are found by parsing
+/// JSON values one after another and skipping the lengths.
+pub fn decode_envelopes(body: &str) -> Result<Vec<Value>> {
+    let body = body.trim_start();
+    let body = body.strip_prefix(XSSI_PREFIX).unwrap_or(body);
+
+    let mut envelopes = Vec::new();
+    for chunk in serde_json::Deserializer::from_str(body).into_iter::<Value>() {
+        let chunk = chunk
+            .map_err(|e| ProviderError::Parse(format!("Malformed batchexecute response: {}", e)))?;
+        if let Value::Array(items) = chunk {
+            envelopes.extend(items.into_iter().filter(Value::is_array));
+        }
+    }
+    Ok(envelopes)
+}
+
+/// The result of `rpc_id`, parsed from its `wrb.fr` envelope
+///
+/// A call the server refused has no result but a status code in the
+/// envelope, reported as an API error.
+pub fn decode_response(body: &str, rpc_id: &str) -> Result<Value> {
+    let envelopes = decode_envelopes(body)?;
+    let envelope = envelopes
+        .iter()
+        .find(|e| at(e, &[0]) == "wrb.fr" && at(e, &[1]) == rpc_id)
+        .ok_or_else(|| {
+            ProviderError::Parse(format!("No result for {} in batchexecute response", rpc_id))
+        })?;
+
+    match at(envelope, &[2]).as_str() {
+        Some(payload) => serde_json::from_str(payload)
+            .map_err(|e| ProviderError::Parse(format!("Malformed {} result: {}", rpc_id, e))),
+        None => Err(ProviderError::Api(format!(
+            "{} failed with status {}",
+            rpc_id,
+            at(envelope, &[5, 0])
+        ))),
+    }
+}
+
+/// The value at `path` in nested arrays; `Null` when there's nothing there
+pub fn at<'a>(value: &'a Value, path: &[usize]) -> &'a Value {
+    path.iter()
+        .try_fold(value, |value, &i| value.get(i))
+        .unwrap_or(&NULL)
+}
+
+/// The string at `path`, if there's one
+pub fn str_at<'a>(value: &'a Value, path: &[usize]) -> Option<&'a str> {
+    at(value, path).as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_encode_request_nests_arguments_as_a_string() {
+        let f_req = encode_request("MaZiqc", &json!([13, null, [0, null, 1]]));
+        assert_eq!(
+            f_req,
+            r#"[[["MaZiqc","[13,null,[0,null,1]]",null,"generic"]]]"#
+        );
+    }
+
+    #[test]
+    fn test_decode_response_finds_the_rpc_result() {
+        let result = decode_response(&fixture("gemini-list-chats.txt"), "MaZiqc").unwrap();
+        assert_eq!(str_at(&result, &[2, 0, 0]), Some("c_3f9a1b2c4d5e6f70"));
+        assert_eq!(
+            str_at(&result, &[2, 1, 1]),
+            Some("Café recommendations in Lisbon")
+        );
+    }
+
+    #[test]
+    fn test_decode_response_ignores_chunk_lengths() {
+        // The second chunk's length is in UTF-16 units and off by the
+        // multi-byte characters before it
+        let body = ")]}'\n\n60\n[[\"wrb.fr\",\"hNvQHb\",\"[[\\\"ünïcödé\\\"]]\",null,null,null,\"generic\"]]\n25\n[[\"di\",91],[\"af.httprm\",90,\"-1\",3]]\n";
+        let result = decode_response(body, "hNvQHb").unwrap();
+        assert_eq!(str_at(&result, &[0, 0]), Some("ünïcödé"));
+        assert_eq!(decode_envelopes(body).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_refused_call_is_an_api_error() {
+        let body = ")]}'\n\n[[\"wrb.fr\",\"hNvQHb\",null,null,null,[7],\"generic\"]]";
+        match decode_response(body, "hNvQHb") {
+            Err(ProviderError::Api(message)) => assert_eq!(message, "hNvQHb failed with status 7"),
+            other => panic!("Expected an API error, got {:?}", other),
+        }
+        assert!(matches!(
+            decode_response(body, "MaZiqc"),
+            Err(ProviderError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_at_reads_past_the_end_as_null() {
+        let value = json!([["a", ["b"]], 3]);
+        assert_eq!(str_at(&value, &[0, 1, 0]), Some("b"));
+        assert_eq!(at(&value, &[1]), 3);
+        assert!(at(&value, &[0, 5, 2]).is_null());
+        assert!(at(&value, &[1, 0]).is_null());
+    }
+}
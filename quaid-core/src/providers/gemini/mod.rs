@@ -0,0 +1,750 @@
+//! Gemini provider implementation
+//!
+//! Syncs chats from gemini.google.com through the web app's `batchexecute`
+//! RPCs, signed in with the `__Secure-1PSID` and `__Secure-1PSIDTS` cookies.
+//! Google expires 1PSIDTS within hours; the browser rotates it in the
+//! background and so does this provider, whenever the app stops recognising
+//! the session.
+
+pub mod batchexecute;
+pub mod types;
+
+use crate::credentials::keys::gemini::{PSID as KEYRING_USER_PSID, PSIDTS as KEYRING_USER_PSIDTS};
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::{
+    with_retry, Account, Attachment, Conversation, Message, MessageContent, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
+};
+use async_trait::async_trait;
+use batchexecute::{decode_response, encode_request};
+use reqwest::{header, Client};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use types::*;
+
+const APP_URL: &str = "https://gemini.google.com/app";
+const BATCHEXECUTE_URL: &str = "https://gemini.google.com/_/BardChatUi/data/batchexecute";
+const ROTATE_URL: &str = "https://accounts.google.com/RotateCookies";
+/// What the browser posts to rotate cookies
+const ROTATE_BODY: &str = r#"[000,"-0000000000000000000"]"#;
+
+const PSID: &str = "__Secure-1PSID";
+const PSIDTS: &str = "__Secure-1PSIDTS";
+
+/// Lists recent chats
+const LIST_CHATS: &str = "MaZiqc";
+/// Reads a chat's history
+const READ_CHAT: &str = "hNvQHb";
+/// Chats per page of the list, as the web app asks for
+const CHATS_PER_PAGE: u32 = 13;
+/// Turns per page of a chat's history
+const TURNS_PER_PAGE: u32 = 100;
+
+/// The session cookies
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cookies {
+    psid: String,
+    psidts: Option<String>,
+}
+
+impl Cookies {
+    fn header(&self) -> String {
+        match &self.psidts {
+            Some(psidts) => format!("{}={}; {}={}", PSID, self.psid, PSIDTS, psidts),
+            None => format!("{}={}", PSID, self.psid),
+        }
+    }
+}
+
+/// Gemini provider
+pub struct GeminiProvider {
+    client: Client,
+    /// Replaced when 1PSIDTS is rotated
+    cookies: Mutex<Option<Cookies>>,
+    /// Account the cookies are saved under, to save rotated ones there too
+    email: Option<String>,
+    /// Token and build of the app page, fetched once per provider
+    session: tokio::sync::Mutex<Option<AppPage>>,
+    credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Chats from the last listing, which has the titles a chat's history lacks
+    listed: Mutex<HashMap<String, ApiChat>>,
+    request_id: AtomicU32,
+}
+
+impl GeminiProvider {
+    /// Create a new Gemini provider, loading credentials from keyring if available
+    pub fn new() -> Self {
+        Self::with_account_credentials(default_store(), None)
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
+        let psid = credential_store
+            .get_for_account(KEYRING_SERVICE, KEYRING_USER_PSID, email)
+            .ok();
+        let psidts = credential_store
+            .get_for_account(KEYRING_SERVICE, KEYRING_USER_PSIDTS, email)
+            .ok();
+
+        Self {
+            client: build_client(),
+            cookies: Mutex::new(psid.map(|psid| Cookies { psid, psidts })),
+            email: email.map(str::to_string),
+            session: tokio::sync::Mutex::new(None),
+            credential_store,
+            retry: RetryPolicy::default(),
+            listed: Mutex::new(HashMap::new()),
+            request_id: AtomicU32::new(100_000),
+        }
+    }
+
+    fn cookies(&self) -> Result<Cookies> {
+        self.cookies
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(ProviderError::AuthRequired)
+    }
+
+    /// The app page, with the token every RPC needs
+    ///
+    /// A page without a token means the session lapsed, so 1PSIDTS is
+    /// rotated and the page fetched again.
+    async fn session(&self) -> Result<AppPage> {
+        let mut session = self.session.lock().await;
+        if let Some(page) = session.as_ref() {
+            return Ok(page.clone());
+        }
+
+        let mut page = self.fetch_app_page().await?;
+        if page.at.is_none() {
+            self.rotate_cookies().await?;
+            page = self.fetch_app_page().await?;
+        }
+        if page.at.is_none() {
+            return Err(ProviderError::TokenExpired);
+        }
+        *session = Some(page.clone());
+        Ok(page)
+    }
+
+    async fn fetch_app_page(&self) -> Result<AppPage> {
+        let resp = self
+            .client
+            .get(APP_URL)
+            .header(header::COOKIE, self.cookies()?.header())
+            .send()
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: GET {} failed: {}",
+                status,
+                APP_URL,
+                truncate_body(&body, 500)
+            )));
+        }
+        Ok(AppPage::parse(&body))
+    }
+
+    /// Trade 1PSIDTS for a fresh one, saving it with the account's credentials
+    ///
+    /// Fails with `TokenExpired` when Google won't rotate it, meaning 1PSID
+    /// itself is no longer valid and the user has to sign in again.
+    pub async fn rotate_cookies(&self) -> Result<()> {
+        let mut cookies = self.cookies()?;
+        let resp = self
+            .client
+            .post(ROTATE_URL)
+            .header(header::COOKIE, cookies.header())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(ROTATE_BODY)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ProviderError::TokenExpired);
+        }
+        let psidts = resp
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|value| set_cookie_value(value, PSIDTS))
+            .ok_or(ProviderError::TokenExpired)?;
+
+        cookies.psidts = Some(psidts.to_string());
+        if let Some(email) = &self.email {
+            if let Err(e) = self.credential_store.set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_PSIDTS,
+                email,
+                psidts,
+            ) {
+                eprintln!("Warning: failed to save rotated Gemini cookie: {}", e);
+            }
+        }
+        *self.cookies.lock().unwrap() = Some(cookies);
+        Ok(())
+    }
+
+    /// Call `rpc_id` with `args`, retrying transient failures
+    async fn call(&self, rpc_id: &str, args: Value) -> Result<Value> {
+        let session = self.session().await?;
+        with_retry(&self.retry, || self.call_once(&session, rpc_id, &args)).await
+    }
+
+    async fn call_once(&self, session: &AppPage, rpc_id: &str, args: &Value) -> Result<Value> {
+        let request_id = self.request_id.fetch_add(100_000, Ordering::Relaxed);
+        let resp = self
+            .client
+            .post(BATCHEXECUTE_URL)
+            .query(&[
+                ("rpcids", rpc_id),
+                ("source-path", "/app"),
+                ("bl", session.build.as_deref().unwrap_or_default()),
+                ("hl", "en"),
+                ("_reqid", &request_id.to_string()),
+                ("rt", "c"),
+            ])
+            .header(header::COOKIE, self.cookies()?.header())
+            .form(&[
+                ("f.req", encode_request(rpc_id, args)),
+                ("at", session.at.clone().unwrap_or_default()),
+            ])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60);
+            return Err(ProviderError::RateLimited(retry_after));
+        }
+        if status == 401 {
+            return Err(ProviderError::TokenExpired);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: {} failed: {}",
+                status,
+                rpc_id,
+                truncate_body(&body, 500)
+            )));
+        }
+        decode_response(&body, rpc_id)
+    }
+
+    /// Every chat in the recent chats list
+    async fn list_chats(&self) -> Result<Vec<ApiChat>> {
+        let mut chats = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let result = self
+                .call(
+                    LIST_CHATS,
+                    json!([CHATS_PER_PAGE, page_token, [0, null, 1]]),
+                )
+                .await?;
+            let page = ApiChatPage::from_value(&result);
+            chats.extend(page.chats);
+            match page.next_page {
+                Some(next) if page_token.as_ref() != Some(&next) => page_token = Some(next),
+                _ => return Ok(chats),
+            }
+        }
+    }
+
+    /// A chat's turns, oldest first
+    async fn read_chat(&self, id: &str) -> Result<Vec<ApiTurn>> {
+        let mut turns = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let result = self
+                .call(
+                    READ_CHAT,
+                    json!([id, TURNS_PER_PAGE, page_token, 1, [0], [4], null, 1]),
+                )
+                .await?;
+            let page = ApiTurnPage::from_value(&result);
+            turns.extend(page.turns);
+            match page.next_page {
+                Some(next) if page_token.as_ref() != Some(&next) => page_token = Some(next),
+                _ => break,
+            }
+        }
+        turns.reverse();
+        Ok(turns)
+    }
+}
+
+impl Default for GeminiProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::gemini()
+    }
+
+    async fn is_authenticated(&self) -> bool {
+        self.cookies.lock().unwrap().is_some()
+    }
+
+    async fn authenticate(&mut self) -> Result<Account> {
+        // Browser-based authentication flow
+        use chromiumoxide::browser::{Browser, BrowserConfig};
+        use futures::StreamExt;
+
+        println!("Opening browser for Gemini authentication...");
+        println!("Please log in to your Google account.");
+
+        // Set up user data dir to persist session
+        let user_data_dir = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("quaid")
+            .join("gemini-chrome-profile");
+        std::fs::create_dir_all(&user_data_dir).ok();
+
+        let mut builder = BrowserConfig::builder()
+            .with_head()
+            .user_data_dir(&user_data_dir)
+            .arg("--disable-blink-features=AutomationControlled")
+            .arg("--disable-infobars")
+            .arg("--no-first-run")
+            .window_size(1280, 900);
+
+        // Try to find Chrome on the system
+        if let Some(chrome_path) = find_chrome() {
+            builder = builder.chrome_executable(chrome_path);
+        }
+
+        let config = builder
+            .build()
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                if event.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let page = browser
+            .new_page(APP_URL)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        // Signed in once the app is open with a session cookie set
+        println!("Waiting for login... (this window will close automatically)");
+
+        let cookies = loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+            let url = page.url().await.ok().flatten().unwrap_or_default();
+            if !url.starts_with(APP_URL) {
+                continue;
+            }
+            let found = page.get_cookies().await.unwrap_or_default();
+            let value = |name: &str| {
+                found
+                    .iter()
+                    .find(|c| c.name == name && c.domain.ends_with("google.com"))
+                    .map(|c| c.value.clone())
+            };
+            if let Some(psid) = value(PSID) {
+                println!("Login detected!");
+                break Cookies {
+                    psid,
+                    psidts: value(PSIDTS),
+                };
+            }
+        };
+
+        // Close browser
+        drop(browser);
+        handle.abort();
+
+        *self.cookies.lock().unwrap() = Some(cookies.clone());
+        *self.session.lock().await = None;
+
+        // Save under the signed-in account, so other accounts keep theirs
+        let account = self.account().await?;
+        let credentials = [
+            (KEYRING_USER_PSID, Some(cookies.psid.as_str())),
+            (KEYRING_USER_PSIDTS, cookies.psidts.as_deref()),
+        ];
+        for (user, value) in credentials {
+            let Some(value) = value else { continue };
+            if let Err(e) =
+                self.credential_store
+                    .set_for_account(KEYRING_SERVICE, user, &account.email, value)
+            {
+                eprintln!("Warning: failed to save cookies: {}", e);
+            }
+        }
+        self.email = Some(account.email.clone());
+        println!("Authentication successful!");
+        Ok(account)
+    }
+
+    async fn account(&self) -> Result<Account> {
+        let page = self.session().await?;
+        let email = page
+            .email
+            .ok_or_else(|| ProviderError::Api("Signed-in email not found".to_string()))?;
+
+        Ok(Account {
+            id: page.user_id.unwrap_or_else(|| email.clone()),
+            provider: ProviderId::gemini(),
+            email,
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        })
+    }
+
+    async fn conversations(&self) -> Result<Vec<Conversation>> {
+        let chats = self.list_chats().await?;
+        let conversations = chats.iter().map(|chat| convert_chat(chat, &[])).collect();
+
+        let mut listed = self.listed.lock().unwrap();
+        listed.extend(chats.into_iter().map(|chat| (chat.id.clone(), chat)));
+        Ok(conversations)
+    }
+
+    async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
+        let turns = self.read_chat(id).await?;
+        let chat = self
+            .listed
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| ApiChat {
+                id: id.to_string(),
+                title: String::new(),
+                pinned: false,
+                updated_at: None,
+            });
+
+        Ok((convert_chat(&chat, &turns), convert_turns(id, &turns)))
+    }
+
+    async fn project_conversations(&self, _project_id: &str) -> Result<Vec<Conversation>> {
+        Ok(Vec::new())
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment, _path: &Path) -> Result<()> {
+        Err(ProviderError::Api(format!(
+            "Gemini attachments can't be downloaded yet ({})",
+            attachment.filename
+        )))
+    }
+}
+
+/// A chat as a conversation, dated by its turns when they're known
+///
+/// Untitled chats are named after their first prompt.
+fn convert_chat(chat: &ApiChat, turns: &[ApiTurn]) -> Conversation {
+    let dates = || turns.iter().filter_map(|t| t.created_at);
+    let created_at = dates().min().or(chat.updated_at).unwrap_or_default();
+    let updated_at = chat
+        .updated_at
+        .into_iter()
+        .chain(dates())
+        .max()
+        .unwrap_or(created_at)
+        .max(created_at);
+    let title = if chat.title.trim().is_empty() {
+        turns
+            .first()
+            .and_then(|t| t.prompt.lines().next())
+            .map(|line| line.chars().take(80).collect::<String>())
+            .filter(|line| !line.trim().is_empty())
+            .unwrap_or_else(|| "Untitled".to_string())
+    } else {
+        chat.title.clone()
+    };
+
+    Conversation {
+        id: chat.id.clone(),
+        provider_id: "gemini".to_string(),
+        title,
+        created_at,
+        updated_at,
+        model: None,
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    }
+}
+
+/// Each turn as the user's prompt followed by the kept reply, each replying
+/// to the message before it
+fn convert_turns(conv_id: &str, turns: &[ApiTurn]) -> Vec<Message> {
+    let mut messages: Vec<Message> = Vec::with_capacity(turns.len() * 2);
+    for turn in turns {
+        let exchange = [
+            (&turn.response_id, Role::User, &turn.prompt),
+            (&turn.reply_id, Role::Assistant, &turn.reply),
+        ];
+        for (id, role, text) in exchange {
+            if text.trim().is_empty() {
+                continue;
+            }
+            messages.push(Message {
+                id: id.clone(),
+                conversation_id: conv_id.to_string(),
+                parent_id: messages.last().map(|m| m.id.clone()),
+                role,
+                content: MessageContent::Text { text: text.clone() },
+                created_at: turn.created_at,
+                model: None,
+                speaker: None,
+            });
+        }
+    }
+    messages
+}
+
+/// The value `name` is set to by a `Set-Cookie` header, if that's the cookie it sets
+fn set_cookie_value<'a>(set_cookie: &'a str, name: &str) -> Option<&'a str> {
+    let (cookie_name, value) = set_cookie.split(';').next()?.split_once('=')?;
+    (cookie_name.trim() == name && !value.is_empty()).then_some(value)
+}
+
+/// Build HTTP client with browser-like headers
+///
+/// Cookies are sent by hand rather than kept in a cookie store, so a rotated
+/// 1PSIDTS replaces the old one on the next request.
+fn build_client() -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+    headers.insert(header::ORIGIN, "https://gemini.google.com".parse().unwrap());
+    headers.insert(
+        header::REFERER,
+        "https://gemini.google.com/".parse().unwrap(),
+    );
+    headers.insert("X-Same-Domain", "1".parse().unwrap());
+
+    Client::builder()
+        .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Safely truncate a string at a char boundary
+fn truncate_body(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Find a valid char boundary
+        let mut end = max_len;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+/// Find Chrome/Chromium executable on the system
+fn find_chrome() -> Option<std::path::PathBuf> {
+    let candidates = if cfg!(target_os = "macos") {
+        vec![
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+            "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ]
+    } else if cfg!(target_os = "linux") {
+        vec![
+            "/usr/bin/google-chrome",
+            "/usr/bin/google-chrome-stable",
+            "/usr/bin/chromium",
+            "/usr/bin/chromium-browser",
+            "/snap/bin/chromium",
+        ]
+    } else {
+        // Windows - chromiumoxide should handle this
+        vec![]
+    };
+
+    for candidate in candidates {
+        let path = std::path::PathBuf::from(candidate);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::MockStore;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_provider_id() {
+        let provider = GeminiProvider::with_account_credentials(Arc::new(MockStore::new()), None);
+        assert_eq!(provider.id(), ProviderId::gemini());
+    }
+
+    #[test]
+    fn test_authenticated_with_stored_cookies() {
+        let store = Arc::new(MockStore::new());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider =
+            GeminiProvider::with_account_credentials(store.clone(), Some("ana@example.com"));
+        assert!(!rt.block_on(provider.is_authenticated()));
+
+        store
+            .set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_PSID,
+                "ana@example.com",
+                "psid",
+            )
+            .unwrap();
+        store
+            .set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_PSIDTS,
+                "ana@example.com",
+                "ts",
+            )
+            .unwrap();
+        let provider = GeminiProvider::with_account_credentials(store, Some("ana@example.com"));
+        assert!(rt.block_on(provider.is_authenticated()));
+        assert_eq!(
+            provider.cookies().unwrap().header(),
+            "__Secure-1PSID=psid; __Secure-1PSIDTS=ts"
+        );
+    }
+
+    #[test]
+    fn test_listed_chats_become_conversations() {
+        let result = decode_response(&fixture("gemini-list-chats.txt"), LIST_CHATS).unwrap();
+        let page = ApiChatPage::from_value(&result);
+        assert_eq!(page.next_page, None);
+
+        let conversations: Vec<_> = page.chats.iter().map(|c| convert_chat(c, &[])).collect();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].id, "c_3f9a1b2c4d5e6f70");
+        assert_eq!(conversations[0].title, "Sourdough starter schedule");
+        assert_eq!(conversations[0].provider_id, "gemini");
+        assert_eq!(
+            conversations[0].web_url().as_deref(),
+            Some("https://gemini.google.com/app/3f9a1b2c4d5e6f70")
+        );
+        assert_eq!(
+            conversations[1].updated_at.to_rfc3339(),
+            "2024-04-28T17:30:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_chat_history_becomes_alternating_messages() {
+        let result = decode_response(&fixture("gemini-read-chat.txt"), READ_CHAT).unwrap();
+        let mut turns = ApiTurnPage::from_value(&result).turns;
+        turns.reverse();
+        let chat = ApiChat {
+            id: "c_3f9a1b2c4d5e6f70".to_string(),
+            title: String::new(),
+            pinned: false,
+            updated_at: None,
+        };
+
+        let conversation = convert_chat(&chat, &turns);
+        assert_eq!(
+            conversation.title,
+            "How often should I feed a sourdough starter?"
+        );
+        assert_eq!(
+            conversation.created_at.to_rfc3339(),
+            "2024-05-01T08:00:00+00:00"
+        );
+        assert_eq!(
+            conversation.updated_at.to_rfc3339(),
+            "2024-05-01T08:05:00+00:00"
+        );
+
+        let messages = convert_turns(&chat.id, &turns);
+        let summary: Vec<_> = messages
+            .iter()
+            .map(|m| (m.id.as_str(), m.role.clone(), m.parent_id.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("r_a1", Role::User, None),
+                ("rc_a1b", Role::Assistant, Some("r_a1")),
+                ("r_a2", Role::User, Some("rc_a1b")),
+                ("rc_a2a", Role::Assistant, Some("r_a2")),
+            ]
+        );
+        match &messages[1].content {
+            MessageContent::Text { text } => assert!(text.starts_with("Once a day")),
+            other => panic!("Expected Text content, got {:?}", other),
+        }
+        match &messages[2].content {
+            MessageContent::Text { text } => assert_eq!(text, "Und im Kühlschrank?"),
+            other => panic!("Expected Text content, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_set_cookie_value() {
+        let header = "__Secure-1PSIDTS=sidts-new; expires=Thu, 01-May-2025 08:00:00 GMT; path=/; domain=.google.com; Secure; HttpOnly";
+        assert_eq!(set_cookie_value(header, PSIDTS), Some("sidts-new"));
+        assert_eq!(set_cookie_value(header, PSID), None);
+        assert_eq!(set_cookie_value("__Secure-1PSIDTS=; path=/", PSIDTS), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! Gemini web app RPC results
+//!
+//! Results are untyped nested arrays, so each type here is read by position.
+//! The positions are what the web app used when these were captured; entries
+//! missing the parts that matter are skipped rather than failing the pull.
+
+use super::batchexecute::{at, str_at};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// A chat in the recent chats list (`MaZiqc`)
+///
+/// `[id, title, pinned, _, _, [seconds, nanos], ...]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiChat {
+    /// `c_`-prefixed chat id
+    pub id: String,
+    pub title: String,
+    pub pinned: bool,
+    /// Last activity
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl ApiChat {
+    pub fn from_value(value: &Value) -> Option<Self> {
+        Some(Self {
+            id: str_at(value, &[0])?.to_string(),
+            title: str_at(value, &[1]).unwrap_or_default().to_string(),
+            pinned: at(value, &[2]).as_bool().unwrap_or(false),
+            updated_at: timestamp(at(value, &[5])),
+        })
+    }
+}
+
+/// One page of the recent chats list: `[_, next_page_token, [chat, ...]]`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiChatPage {
+    pub chats: Vec<ApiChat>,
+    pub next_page: Option<String>,
+}
+
+impl ApiChatPage {
+    pub fn from_value(value: &Value) -> Self {
+        Self {
+            chats: at(value, &[2])
+                .as_array()
+                .map(|chats| chats.iter().filter_map(ApiChat::from_value).collect())
+                .unwrap_or_default(),
+            next_page: page_token(at(value, &[1])),
+        }
+    }
+}
+
+/// A prompt and the reply that was kept, from a chat's history (`hNvQHb`)
+///
+/// `[[chat_id, response_id], [chat_id, response_id, reply_id],
+///   [[prompt, ...], ...], [[candidate, ...], ...], [seconds, nanos]]`
+///
+/// Gemini drafts several candidate replies; the kept one is named by
+/// `reply_id`. A candidate is `[candidate_id, [text, ...], ...]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiTurn {
+    /// `r_`-prefixed id of the exchange
+    pub response_id: String,
+    /// `rc_`-prefixed id of the kept reply
+    pub reply_id: String,
+    pub prompt: String,
+    pub reply: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl ApiTurn {
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let response_id = str_at(value, &[0, 1]).or_else(|| str_at(value, &[1, 1]))?;
+        let candidates = at(value, &[3, 0]).as_array()?;
+        let chosen = str_at(value, &[1, 2]);
+        let candidate = candidates
+            .iter()
+            .find(|c| chosen.is_some() && str_at(c, &[0]) == chosen)
+            .or_else(|| candidates.first())?;
+
+        Some(Self {
+            response_id: response_id.to_string(),
+            reply_id: str_at(candidate, &[0])
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{}-reply", response_id)),
+            prompt: str_at(value, &[2, 0, 0]).unwrap_or_default().to_string(),
+            reply: str_at(candidate, &[1, 0]).unwrap_or_default().to_string(),
+            created_at: timestamp(at(value, &[4])),
+        })
+    }
+}
+
+/// One page of a chat's history: `[[turn, ...], next_page_token, ...]`,
+/// newest turn first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiTurnPage {
+    pub turns: Vec<ApiTurn>,
+    pub next_page: Option<String>,
+}
+
+impl ApiTurnPage {
+    pub fn from_value(value: &Value) -> Self {
+        Self {
+            turns: at(value, &[0])
+                .as_array()
+                .map(|turns| turns.iter().filter_map(ApiTurn::from_value).collect())
+                .unwrap_or_default(),
+            next_page: page_token(at(value, &[1])),
+        }
+    }
+}
+
+/// Account details embedded in the app page's `WIZ_global_data`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppPage {
+    /// Anti-forgery token sent as `at` with every call (`SNlM0e`)
+    pub at: Option<String>,
+    /// Build label sent as `bl` (`cfb2h`)
+    pub build: Option<String>,
+    /// Signed-in email (`oPEP7c`)
+    pub email: Option<String>,
+    /// Google account id (`qDCSke`)
+    pub user_id: Option<String>,
+}
+
+impl AppPage {
+    pub fn parse(html: &str) -> Self {
+        Self {
+            at: wiz_value(html, "SNlM0e"),
+            build: wiz_value(html, "cfb2h"),
+            email: wiz_value(html, "oPEP7c"),
+            user_id: wiz_value(html, "qDCSke"),
+        }
+    }
+}
+
+/// A string value of `WIZ_global_data`, as in `"SNlM0e":"token"`
+fn wiz_value(html: &str, key: &str) -> Option<String> {
+    let start = html.find(&format!("\"{}\":\"", key))? + key.len() + 4;
+    let len = html[start..].find('"')?;
+    Some(html[start..start + len].to_string()).filter(|v| !v.is_empty())
+}
+
+/// A `[seconds, nanos]` timestamp
+fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    let seconds = at(value, &[0]).as_i64()?;
+    let nanos = at(value, &[1]).as_u64().unwrap_or(0);
+    DateTime::from_timestamp(seconds, nanos as u32)
+}
+
+fn page_token(value: &Value) -> Option<String> {
+    value.as_str().filter(|t| !t.is_empty()).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_chat_page_skips_entries_without_an_id() {
+        let page = ApiChatPage::from_value(&json!([
+            null,
+            "next-token",
+            [
+                ["c_1", "Plans", true, null, null, [1714550400, 500000000]],
+                [null, "Broken"],
+                ["c_2"]
+            ]
+        ]));
+        assert_eq!(page.next_page.as_deref(), Some("next-token"));
+        assert_eq!(page.chats.len(), 2);
+        assert!(page.chats[0].pinned);
+        assert_eq!(
+            page.chats[0].updated_at.unwrap().to_rfc3339(),
+            "2024-05-01T08:00:00.500+00:00"
+        );
+        assert_eq!(page.chats[1].title, "");
+        assert_eq!(page.chats[1].updated_at, None);
+    }
+
+    #[test]
+    fn test_turn_keeps_the_chosen_candidate() {
+        let turn = ApiTurn::from_value(&json!([
+            ["c_1", "r_1"],
+            ["c_1", "r_1", "rc_b"],
+            [["Name a color"], 1],
+            [[["rc_a", ["Red"]], ["rc_b", ["Blue"]]]],
+            [1714550400, 0]
+        ]))
+        .unwrap();
+        assert_eq!(turn.response_id, "r_1");
+        assert_eq!(turn.reply_id, "rc_b");
+        assert_eq!(turn.prompt, "Name a color");
+        assert_eq!(turn.reply, "Blue");
+
+        // Without a chosen reply, the first candidate is kept
+        let turn = ApiTurn::from_value(&json!([
+            ["c_1", "r_2"],
+            ["c_1", "r_2"],
+            [["Again"]],
+            [[["rc_c", ["Green"]]]]
+        ]))
+        .unwrap();
+        assert_eq!(turn.reply, "Green");
+        assert_eq!(turn.created_at, None);
+    }
+
+    #[test]
+    fn test_app_page_reads_wiz_global_data() {
+        let html = r#"<script>window.WIZ_global_data = {"cfb2h":"boq_assistant-bard-web-server_20240501.07_p0","oPEP7c":"ana@example.com","qDCSke":"104857600","SNlM0e":"AFWLbD2x:1714550400000"};</script>"#;
+        let page = AppPage::parse(html);
+        assert_eq!(page.at.as_deref(), Some("AFWLbD2x:1714550400000"));
+        assert_eq!(
+            page.build.as_deref(),
+            Some("boq_assistant-bard-web-server_20240501.07_p0")
+        );
+        assert_eq!(page.email.as_deref(), Some("ana@example.com"));
+        assert_eq!(page.user_id.as_deref(), Some("104857600"));
+
+        // Signed out: the page has no token
+        assert_eq!(AppPage::parse("<html></html>"), AppPage::default());
+    }
+}
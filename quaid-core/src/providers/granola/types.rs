@@ -108,6 +108,39 @@ pub struct ApiDocument {
     /// Notes as ProseMirror content structure
     #[serde(default)]
     pub notes: Option<serde_json::Value>,
+    #[serde(default)]
+    pub people: Option<ApiPeople>,
+    /// The calendar event the meeting was recorded for
+    #[serde(default)]
+    pub google_calendar_event: Option<ApiCalendarEvent>,
+}
+
+/// Who created a document and who was in the meeting
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ApiPeople {
+    #[serde(default)]
+    pub creator: Option<ApiPerson>,
+    #[serde(default)]
+    pub attendees: Vec<ApiPerson>,
+}
+
+/// A meeting attendee, from the document or its calendar event
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiPerson {
+    #[serde(default, alias = "displayName")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Set on rooms and other calendar resources, which aren't people
+    #[serde(default)]
+    pub resource: bool,
+}
+
+/// Calendar event linked to a document
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ApiCalendarEvent {
+    #[serde(default)]
+    pub attendees: Vec<ApiPerson>,
 }
 
 /// Folder reference within a document
@@ -242,7 +275,8 @@ mod tests {
     fn test_parse_credentials_file() {
         // The file format has nested JSON strings
         let workos_tokens = r#"{"access_token":"eyJhbGciOiJS...","refresh_token":"refresh_abc123","expires_in":21600,"obtained_at":1705329600000,"token_type":"Bearer"}"#;
-        let user_info = r#"{"id":"user-123","email":"test@example.com","user_metadata":{"name":"Test User"}}"#;
+        let user_info =
+            r#"{"id":"user-123","email":"test@example.com","user_metadata":{"name":"Test User"}}"#;
 
         let json = format!(
             r#"{{"workos_tokens":"{}","user_info":"{}","session_id":"session_123"}}"#,
@@ -0,0 +1,821 @@
+//! Grok provider implementation
+//!
+//! Syncs conversations from x.com/i/grok through the GraphQL queries the web
+//! app makes, signed in with the `auth_token` and `ct0` cookies. X checks
+//! `ct0` twice: as a cookie and, echoed back, as the CSRF header.
+
+pub mod types;
+
+use crate::credentials::keys::grok::{
+    AUTH_TOKEN as KEYRING_USER_AUTH_TOKEN, CT0 as KEYRING_USER_CT0,
+};
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::download;
+use crate::providers::{
+    with_retry, Account, Attachment, Conversation, Message, MessageContent, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::{header, Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use types::*;
+
+const APP_URL: &str = "https://x.com/i/grok";
+const GRAPHQL_URL: &str = "https://x.com/i/api/graphql";
+const SETTINGS_URL: &str = "https://x.com/i/api/1.1/account/settings.json";
+/// The public bearer token the x.com web app sends with every API call
+const WEB_BEARER: &str = "Bearer AAAAAAAAAAAAAAAAAAAAANRILgAAAAAAnNwIzUejRCOuH5E6I8xnZz4puTs%3D1Zv7ttfk8LF81IUq16cHjhLTvJu4FA33AGWWjCpTnA";
+
+/// Persisted query ids of the web app; x.com changes them now and then, and
+/// stale ones fail with a 404
+const HISTORY_QUERY: (&str, &str) = ("9Hyh5D4-WXLnExZkONSkZg", "GrokHistory");
+const ITEMS_QUERY: (&str, &str) = ("0aWhJJmFlxkxv9TAUJPanA", "GrokConversationItemsByRestId");
+
+/// The session cookies
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cookies {
+    auth_token: String,
+    ct0: String,
+}
+
+impl Cookies {
+    fn header(&self) -> String {
+        format!("auth_token={}; ct0={}", self.auth_token, self.ct0)
+    }
+}
+
+/// Grok provider
+pub struct GrokProvider {
+    client: Client,
+    cookies: Option<Cookies>,
+    /// Numeric X user id, known once signed in through the browser
+    user_id: Option<String>,
+    credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Conversations from the last listing, which has the titles their items lack
+    listed: Mutex<HashMap<String, ApiHistoryItem>>,
+}
+
+impl GrokProvider {
+    /// Create a new Grok provider, loading credentials from keyring if available
+    pub fn new() -> Self {
+        Self::with_account_credentials(default_store(), None)
+    }
+
+    /// Create for the account signed in as `handle`, using its own credentials
+    pub fn for_account(handle: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(handle))
+    }
+
+    /// Create with a custom credential store, loading `handle`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        handle: Option<&str>,
+    ) -> Self {
+        let load = |user: &str| {
+            credential_store
+                .get_for_account(KEYRING_SERVICE, user, handle)
+                .ok()
+        };
+        let cookies = match (load(KEYRING_USER_AUTH_TOKEN), load(KEYRING_USER_CT0)) {
+            (Some(auth_token), Some(ct0)) => Some(Cookies { auth_token, ct0 }),
+            _ => None,
+        };
+
+        Self {
+            client: build_client(),
+            cookies,
+            user_id: None,
+            credential_store,
+            retry: RetryPolicy::default(),
+            listed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `request` signed in with the session cookies and CSRF token
+    fn signed(&self, request: RequestBuilder) -> Result<RequestBuilder> {
+        let cookies = self.cookies.as_ref().ok_or(ProviderError::AuthRequired)?;
+        Ok(request
+            .header(header::COOKIE, cookies.header())
+            .header("x-csrf-token", &cookies.ct0))
+    }
+
+    /// GET `url` and parse its JSON body, retrying transient failures
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        with_retry(&self.retry, || async {
+            let resp = self
+                .signed(self.client.get(url).query(query))?
+                .send()
+                .await?;
+
+            let status = resp.status();
+            if status == 429 {
+                // X reports when the rate limit window resets, not how long to wait
+                let retry_after = resp
+                    .headers()
+                    .get("x-rate-limit-reset")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .map(|reset| (reset - Utc::now().timestamp()).max(1) as u64)
+                    .unwrap_or(60);
+                return Err(ProviderError::RateLimited(retry_after));
+            }
+            if status == 401 || status == 403 {
+                return Err(ProviderError::TokenExpired);
+            }
+
+            let body = resp.text().await?;
+            if !status.is_success() {
+                return Err(ProviderError::Api(format!(
+                    "{}: GET {} failed: {}",
+                    status,
+                    url,
+                    truncate_body(&body, 500)
+                )));
+            }
+            serde_json::from_str(&body).map_err(|e| {
+                ProviderError::Parse(format!(
+                    "Failed to parse response: {}. Body: {}",
+                    e,
+                    truncate_body(&body, 500)
+                ))
+            })
+        })
+        .await
+    }
+
+    /// Run a persisted GraphQL `query` with `variables`
+    async fn graphql<T: DeserializeOwned>(
+        &self,
+        query: (&str, &str),
+        variables: Value,
+    ) -> Result<T> {
+        let (query_id, operation) = query;
+        let url = format!("{}/{}/{}", GRAPHQL_URL, query_id, operation);
+        let response: GraphqlResponse<T> = self
+            .get_json(&url, &[("variables", variables.to_string())])
+            .await?;
+        graphql_data(response, operation)
+    }
+
+    /// Every conversation in the history, newest first
+    async fn list_history(&self) -> Result<Vec<ApiHistoryItem>> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let variables = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let data: HistoryData = self.graphql(HISTORY_QUERY, variables).await?;
+            let page = data.grok_conversation_history;
+            let empty = page.items.is_empty();
+            items.extend(page.items);
+            match page.cursor {
+                Some(next) if !empty && cursor.as_ref() != Some(&next) => cursor = Some(next),
+                _ => return Ok(items),
+            }
+        }
+    }
+
+    /// A conversation's items, oldest first
+    async fn read_items(&self, id: &str) -> Result<Vec<ApiItem>> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let variables = match &cursor {
+                Some(cursor) => json!({ "restId": id, "cursor": cursor }),
+                None => json!({ "restId": id }),
+            };
+            let data: ItemsData = self.graphql(ITEMS_QUERY, variables).await?;
+            let page = data.grok_conversation_items_by_rest_id;
+            let empty = page.items.is_empty();
+            items.extend(page.items);
+            match page.cursor {
+                Some(next) if !empty && cursor.as_ref() != Some(&next) => cursor = Some(next),
+                _ => break,
+            }
+        }
+        items.sort_by_key(|item| item.created_at_ms);
+        Ok(items)
+    }
+
+    fn listed_or_bare(&self, id: &str) -> ApiHistoryItem {
+        self.listed
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| ApiHistoryItem {
+                grok_conversation: ApiConversationRef {
+                    rest_id: id.to_string(),
+                },
+                title: None,
+                created_at_ms: None,
+            })
+    }
+
+    /// Fetch a conversation with its attachments (for sync)
+    pub async fn conversation_with_attachments(
+        &self,
+        id: &str,
+    ) -> Result<(Conversation, Vec<Message>, Vec<Attachment>)> {
+        let items = self.read_items(id).await?;
+        let listed = self.listed_or_bare(id);
+
+        Ok((
+            convert_conversation(&listed, &items),
+            convert_items(id, &items),
+            extract_attachments(&items),
+        ))
+    }
+}
+
+impl Default for GrokProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for GrokProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::grok()
+    }
+
+    async fn is_authenticated(&self) -> bool {
+        self.cookies.is_some()
+    }
+
+    async fn authenticate(&mut self) -> Result<Account> {
+        // Browser-based authentication flow
+        use chromiumoxide::browser::{Browser, BrowserConfig};
+        use futures::StreamExt;
+
+        println!("Opening browser for Grok authentication...");
+        println!("Please log in to your X account.");
+
+        // Set up user data dir to persist session
+        let user_data_dir = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("quaid")
+            .join("grok-chrome-profile");
+        std::fs::create_dir_all(&user_data_dir).ok();
+
+        let mut builder = BrowserConfig::builder()
+            .with_head()
+            .user_data_dir(&user_data_dir)
+            .arg("--disable-blink-features=AutomationControlled")
+            .arg("--disable-infobars")
+            .arg("--no-first-run")
+            .window_size(1280, 900);
+
+        // Try to find Chrome on the system
+        if let Some(chrome_path) = find_chrome() {
+            builder = builder.chrome_executable(chrome_path);
+        }
+
+        let config = builder
+            .build()
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                if event.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let page = browser
+            .new_page(APP_URL)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        // Signed in once Grok is open with both session cookies set
+        println!("Waiting for login... (this window will close automatically)");
+
+        let (cookies, user_id) = loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+            let url = page.url().await.ok().flatten().unwrap_or_default();
+            if !url.starts_with(APP_URL) {
+                continue;
+            }
+            let found = page.get_cookies().await.unwrap_or_default();
+            let value = |name: &str| {
+                found
+                    .iter()
+                    .find(|c| c.name == name && c.domain.ends_with("x.com"))
+                    .map(|c| c.value.clone())
+            };
+            if let (Some(auth_token), Some(ct0)) = (value("auth_token"), value("ct0")) {
+                println!("Login detected!");
+                let user_id = value("twid").and_then(|twid| user_id_from_twid(&twid));
+                break (Cookies { auth_token, ct0 }, user_id);
+            }
+        };
+
+        // Close browser
+        drop(browser);
+        handle.abort();
+
+        self.cookies = Some(cookies.clone());
+        self.user_id = user_id;
+
+        // Save under the signed-in account, so other accounts keep theirs
+        let account = self.account().await?;
+        let credentials = [
+            (KEYRING_USER_AUTH_TOKEN, &cookies.auth_token),
+            (KEYRING_USER_CT0, &cookies.ct0),
+        ];
+        for (user, value) in credentials {
+            if let Err(e) =
+                self.credential_store
+                    .set_for_account(KEYRING_SERVICE, user, &account.email, value)
+            {
+                eprintln!("Warning: failed to save cookies: {}", e);
+            }
+        }
+        println!("Authentication successful!");
+        Ok(account)
+    }
+
+    async fn account(&self) -> Result<Account> {
+        let settings: ApiSettings = self.get_json(SETTINGS_URL, &[]).await?;
+        let handle = format!("@{}", settings.screen_name);
+
+        Ok(Account {
+            id: self.user_id.clone().unwrap_or_else(|| handle.clone()),
+            provider: ProviderId::grok(),
+            email: handle,
+            name: Some(settings.screen_name),
+            avatar_url: None,
+            workspace_id: None,
+        })
+    }
+
+    async fn conversations(&self) -> Result<Vec<Conversation>> {
+        let history = self.list_history().await?;
+        let conversations = history
+            .iter()
+            .map(|item| convert_conversation(item, &[]))
+            .collect();
+
+        let mut listed = self.listed.lock().unwrap();
+        listed.extend(
+            history
+                .into_iter()
+                .map(|item| (item.grok_conversation.rest_id.clone(), item)),
+        );
+        Ok(conversations)
+    }
+
+    async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
+        let items = self.read_items(id).await?;
+        let listed = self.listed_or_bare(id);
+
+        Ok((
+            convert_conversation(&listed, &items),
+            convert_items(id, &items),
+        ))
+    }
+
+    async fn project_conversations(&self, _project_id: &str) -> Result<Vec<Conversation>> {
+        Ok(Vec::new())
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment, path: &Path) -> Result<()> {
+        // Media on ton.x.com is only served to the signed-in account
+        let request = self.signed(self.client.get(&attachment.download_url))?;
+        let response = download::resume(request, path).await.send().await?;
+        download::save_response(response, path, attachment.size_bytes).await
+    }
+}
+
+/// The data of a GraphQL response, or its errors when it has none
+fn graphql_data<T>(response: GraphqlResponse<T>, operation: &str) -> Result<T> {
+    response.data.ok_or_else(|| {
+        let messages: Vec<_> = response.errors.into_iter().map(|e| e.message).collect();
+        ProviderError::Api(format!("{} failed: {}", operation, messages.join("; ")))
+    })
+}
+
+/// A listed conversation, dated by its items when they're known
+///
+/// Untitled conversations are named after their first prompt.
+fn convert_conversation(listed: &ApiHistoryItem, items: &[ApiItem]) -> Conversation {
+    let dates = || items.iter().filter_map(ApiItem::created_at);
+    let listed_at = listed
+        .created_at_ms
+        .and_then(chrono::DateTime::from_timestamp_millis);
+    let created_at = dates().min().or(listed_at).unwrap_or_default();
+    let updated_at = listed_at
+        .into_iter()
+        .chain(dates())
+        .max()
+        .unwrap_or(created_at)
+        .max(created_at);
+    let title = match listed.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => items
+            .iter()
+            .find(|item| item.sender_type == "User")
+            .and_then(|item| item.message.lines().next())
+            .map(|line| line.chars().take(80).collect::<String>())
+            .filter(|line| !line.trim().is_empty())
+            .unwrap_or_else(|| "Untitled".to_string()),
+    };
+
+    Conversation {
+        id: listed.grok_conversation.rest_id.clone(),
+        provider_id: "grok".to_string(),
+        title,
+        created_at,
+        updated_at,
+        model: None,
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    }
+}
+
+/// The user's and Grok's items as messages, with the images Grok generated
+///
+/// Other senders, like system notices, are left out.
+fn convert_items(conv_id: &str, items: &[ApiItem]) -> Vec<Message> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let role = match item.sender_type.as_str() {
+                "User" => Role::User,
+                "Agent" => Role::Assistant,
+                _ => return None,
+            };
+
+            let mut parts = Vec::new();
+            if !item.message.trim().is_empty() {
+                parts.push(MessageContent::Text {
+                    text: item.message.clone(),
+                });
+            }
+            parts.extend(item.media_urls.iter().map(|url| MessageContent::Image {
+                url: url.clone(),
+                alt: None,
+            }));
+            let content = match parts.len() {
+                0 => return None,
+                1 => parts.remove(0),
+                _ => MessageContent::Mixed { parts },
+            };
+
+            Some(Message {
+                id: item.chat_item_id.clone(),
+                conversation_id: conv_id.to_string(),
+                parent_id: None, // Grok keeps a flat list of items
+                role,
+                content,
+                created_at: item.created_at(),
+                model: None,
+                speaker: None,
+            })
+        })
+        .collect()
+}
+
+/// Generated images and uploaded files of each item, in item order
+fn extract_attachments(items: &[ApiItem]) -> Vec<Attachment> {
+    let mut attachments = Vec::new();
+
+    for item in items {
+        for url in &item.media_urls {
+            let name = url_file_name(url);
+            let (stem, extension) = name.rsplit_once('.').unwrap_or((name, "jpg"));
+            attachments.push(Attachment {
+                id: stem.to_string(),
+                message_id: item.chat_item_id.clone(),
+                filename: format!("{}.{}", stem, extension),
+                mime_type: image_mime_type(extension).to_string(),
+                size_bytes: 0,
+                download_url: url.clone(),
+                width: None,
+                height: None,
+                sha256: None,
+            });
+        }
+
+        for file in &item.file_attachments {
+            let filename = match &file.file_name {
+                Some(name) => format!("{}-{}", file.media_id, name),
+                None => file.media_id.clone(),
+            };
+            attachments.push(Attachment {
+                id: file.media_id.clone(),
+                message_id: item.chat_item_id.clone(),
+                filename,
+                mime_type: file
+                    .mime_type
+                    .clone()
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+                size_bytes: 0,
+                download_url: file.url.clone(),
+                width: None,
+                height: None,
+                sha256: None,
+            });
+        }
+    }
+
+    attachments
+}
+
+/// The last path segment of `url`, without its query
+fn url_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn image_mime_type(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        _ => "image/jpeg",
+    }
+}
+
+/// The numeric user id in a `twid` cookie, as in `u%3D1234` or `"u=1234"`
+fn user_id_from_twid(twid: &str) -> Option<String> {
+    let twid = twid.trim_matches('"');
+    let id = twid
+        .strip_prefix("u%3D")
+        .or_else(|| twid.strip_prefix("u="))?;
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then(|| id.to_string())
+}
+
+/// Build HTTP client with browser-like headers
+///
+/// Cookies are sent per request rather than kept in a cookie store, since
+/// the CSRF header has to match the `ct0` cookie.
+fn build_client() -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+    headers.insert(header::AUTHORIZATION, WEB_BEARER.parse().unwrap());
+    headers.insert(header::ORIGIN, "https://x.com".parse().unwrap());
+    headers.insert(header::REFERER, "https://x.com/i/grok".parse().unwrap());
+    headers.insert("x-twitter-auth-type", "OAuth2Session".parse().unwrap());
+    headers.insert("x-twitter-active-user", "yes".parse().unwrap());
+
+    Client::builder()
+        .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Safely truncate a string at a char boundary
+fn truncate_body(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Find a valid char boundary
+        let mut end = max_len;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+/// Find Chrome/Chromium executable on the system
+fn find_chrome() -> Option<std::path::PathBuf> {
+    let candidates = if cfg!(target_os = "macos") {
+        vec![
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+            "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ]
+    } else if cfg!(target_os = "linux") {
+        vec![
+            "/usr/bin/google-chrome",
+            "/usr/bin/google-chrome-stable",
+            "/usr/bin/chromium",
+            "/usr/bin/chromium-browser",
+            "/snap/bin/chromium",
+        ]
+    } else {
+        // Windows - chromiumoxide should handle this
+        vec![]
+    };
+
+    for candidate in candidates {
+        let path = std::path::PathBuf::from(candidate);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::MockStore;
+
+    fn fixture<T: DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let response: GraphqlResponse<T> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        graphql_data(response, name).unwrap()
+    }
+
+    fn items() -> Vec<ApiItem> {
+        let data: ItemsData = fixture("grok-conversation-items.json");
+        let mut items = data.grok_conversation_items_by_rest_id.items;
+        items.sort_by_key(|item| item.created_at_ms);
+        items
+    }
+
+    #[test]
+    fn test_provider_id() {
+        let provider = GrokProvider::with_account_credentials(Arc::new(MockStore::new()), None);
+        assert_eq!(provider.id(), ProviderId::grok());
+    }
+
+    #[test]
+    fn test_authenticated_with_both_cookies() {
+        let store = Arc::new(MockStore::new());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        store
+            .set_for_account(KEYRING_SERVICE, KEYRING_USER_AUTH_TOKEN, "@ana", "token")
+            .unwrap();
+        let provider = GrokProvider::with_account_credentials(store.clone(), Some("@ana"));
+        assert!(!rt.block_on(provider.is_authenticated()));
+
+        store
+            .set_for_account(KEYRING_SERVICE, KEYRING_USER_CT0, "@ana", "csrf")
+            .unwrap();
+        let provider = GrokProvider::with_account_credentials(store, Some("@ana"));
+        assert!(rt.block_on(provider.is_authenticated()));
+        assert_eq!(
+            provider.cookies.unwrap().header(),
+            "auth_token=token; ct0=csrf"
+        );
+    }
+
+    #[test]
+    fn test_history_becomes_conversations() {
+        let data: HistoryData = fixture("grok-history.json");
+        let page = data.grok_conversation_history;
+        assert!(page.cursor.is_some());
+
+        let conversations: Vec<_> = page
+            .items
+            .iter()
+            .map(|item| convert_conversation(item, &[]))
+            .collect();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].id, "1787654321098765432");
+        assert_eq!(conversations[0].title, "Tide pools near Monterey");
+        assert_eq!(conversations[0].provider_id, "grok");
+        assert_eq!(
+            conversations[0].updated_at.to_rfc3339(),
+            "2024-05-01T16:30:00+00:00"
+        );
+        assert_eq!(
+            conversations[0].web_url().as_deref(),
+            Some("https://x.com/i/grok?conversation=1787654321098765432")
+        );
+        assert_eq!(conversations[1].title, "Untitled");
+    }
+
+    #[test]
+    fn test_items_become_messages_with_generated_images() {
+        let items = items();
+        let messages = convert_items("1787654321098765432", &items);
+
+        // The system notice is left out
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::User);
+        assert!(matches!(
+            &messages[0].content,
+            MessageContent::Text { text } if text.starts_with("Paint me a tide pool")
+        ));
+        assert_eq!(messages[1].role, Role::Assistant);
+        assert!(messages.iter().all(|m| m.parent_id.is_none()));
+        match &messages[1].content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Text { text }, MessageContent::Image { url, .. }] => {
+                    assert_eq!(text, "Here's a watercolor of one at low tide.");
+                    assert!(url.ends_with("/1787654400000000099.jpg"));
+                }
+                other => panic!("Unexpected parts: {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+
+        // Untitled conversations are named after the first prompt
+        let listed = ApiHistoryItem {
+            grok_conversation: ApiConversationRef {
+                rest_id: "1787654321098765432".to_string(),
+            },
+            title: None,
+            created_at_ms: None,
+        };
+        let conversation = convert_conversation(&listed, &items);
+        assert_eq!(
+            conversation.title,
+            "Paint me a tide pool like the one in this photo"
+        );
+        assert_eq!(
+            conversation.created_at.to_rfc3339(),
+            "2024-05-01T16:29:59+00:00"
+        );
+        assert_eq!(
+            conversation.updated_at.to_rfc3339(),
+            "2024-05-01T16:31:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_extracts_generated_images_and_uploads() {
+        let attachments = extract_attachments(&items());
+        assert_eq!(attachments.len(), 2);
+
+        let upload = &attachments[0];
+        assert_eq!(upload.id, "1787654399000000010");
+        assert_eq!(upload.message_id, "1787654400000000001");
+        assert_eq!(upload.filename, "1787654399000000010-asilomar.png");
+        assert_eq!(upload.mime_type, "image/png");
+
+        let image = &attachments[1];
+        assert_eq!(image.id, "1787654400000000099");
+        assert_eq!(image.message_id, "1787654400000000002");
+        assert_eq!(image.filename, "1787654400000000099.jpg");
+        assert_eq!(image.mime_type, "image/jpeg");
+        assert!(image.download_url.starts_with("https://ton.x.com/"));
+    }
+
+    #[test]
+    fn test_failed_query_reports_its_errors() {
+        let response: GraphqlResponse<HistoryData> = serde_json::from_str(
+            r#"{"errors":[{"message":"Query: Unspecified"},{"message":"Rate limit"}]}"#,
+        )
+        .unwrap();
+        match graphql_data(response, "GrokHistory") {
+            Err(ProviderError::Api(message)) => {
+                assert_eq!(
+                    message,
+                    "GrokHistory failed: Query: Unspecified; Rate limit"
+                )
+            }
+            other => panic!("Expected an API error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_user_id_from_twid() {
+        assert_eq!(user_id_from_twid("u%3D1234567").as_deref(), Some("1234567"));
+        assert_eq!(user_id_from_twid("\"u=42\"").as_deref(), Some("42"));
+        assert_eq!(user_id_from_twid("u%3D"), None);
+        assert_eq!(user_id_from_twid("x=42"), None);
+    }
+
+    #[test]
+    fn test_url_file_name_drops_the_query() {
+        assert_eq!(
+            url_file_name("https://pbs.twimg.com/media/abc.png?name=large"),
+            "abc.png"
+        );
+        assert_eq!(url_file_name("https://ton.x.com/i/ton/data/x/123"), "123");
+    }
+}
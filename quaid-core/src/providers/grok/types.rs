@@ -0,0 +1,105 @@
+//! Grok GraphQL response types
+//!
+//! These types are based on the GraphQL queries the x.com web app makes for
+//! Grok and may need adjustment as the API evolves.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// A GraphQL response; `data` is missing when the query failed outright
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphqlResponse<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphqlError {
+    pub message: String,
+}
+
+/// `GrokHistory` result
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryData {
+    pub grok_conversation_history: ApiHistoryPage,
+}
+
+/// One page of the conversation list, newest first
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiHistoryPage {
+    #[serde(default)]
+    pub items: Vec<ApiHistoryItem>,
+    /// Where the next page starts; missing on the last one
+    pub cursor: Option<String>,
+}
+
+/// A conversation in the list
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiHistoryItem {
+    pub grok_conversation: ApiConversationRef,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Last activity, in milliseconds since the epoch
+    pub created_at_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiConversationRef {
+    pub rest_id: String,
+}
+
+/// `GrokConversationItemsByRestId` result
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemsData {
+    pub grok_conversation_items_by_rest_id: ApiItemsPage,
+}
+
+/// One page of a conversation's messages
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiItemsPage {
+    #[serde(default)]
+    pub items: Vec<ApiItem>,
+    /// Where the next (older) page starts; missing on the last one
+    pub cursor: Option<String>,
+}
+
+/// A message in a conversation
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiItem {
+    pub chat_item_id: String,
+    #[serde(default)]
+    pub message: String,
+    /// `User` or `Agent`
+    pub sender_type: String,
+    pub created_at_ms: Option<i64>,
+    /// Images Grok generated for the reply
+    #[serde(default)]
+    pub media_urls: Vec<String>,
+    /// Files the user uploaded with the prompt
+    #[serde(default)]
+    pub file_attachments: Vec<ApiFileAttachment>,
+}
+
+impl ApiItem {
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at_ms.and_then(DateTime::from_timestamp_millis)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiFileAttachment {
+    pub media_id: String,
+    #[serde(default)]
+    pub file_name: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    pub url: String,
+}
+
+/// `account/settings.json` result, for the signed-in handle
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiSettings {
+    pub screen_name: String,
+}
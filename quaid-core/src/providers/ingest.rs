@@ -0,0 +1,351 @@
+//! Push-based ingestion of provider webhooks
+//!
+//! Providers that can notify us about new content (e.g. Fathom's "new meeting"
+//! webhook) register a [`WebhookParser`] that turns the raw payload into the id of
+//! the conversation to sync. [`IngestQueue`] tracks the resulting sync jobs and
+//! dedupes repeated deliveries for the same conversation while a job is in flight.
+//!
+//! This module is transport-agnostic: an HTTP front end (`quaid serve`) checks
+//! the delivery's signature with [`verify_signature`], hands the body to
+//! [`WebhookRegistry::parse`] and enqueues the event.
+//!
+//! Deliveries are signed the way the Standard Webhooks spec describes, which
+//! is what Fathom sends: `webhook-id` and `webhook-timestamp` headers, and a
+//! `webhook-signature` listing `v1,<base64>` HMAC-SHA256 signatures of
+//! `{id}.{timestamp}.{body}`. The secret is base64, behind a `whsec_` prefix
+//! as Fathom shows it.
+
+use super::{ProviderError, ProviderId, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A parsed webhook delivery: which conversation on which provider changed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WebhookEvent {
+    pub provider: ProviderId,
+    pub conversation_id: String,
+    /// When the conversation was created, if the payload says; narrows the
+    /// listing for providers that can't fetch one conversation by id
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Parses a provider's webhook payload
+pub trait WebhookParser: Send + Sync {
+    /// Provider whose payloads this parser understands
+    fn provider(&self) -> ProviderId;
+
+    /// Extract the event from a raw request body
+    fn parse(&self, body: &[u8]) -> Result<WebhookEvent>;
+}
+
+/// Webhook parsers keyed by provider id
+#[derive(Default)]
+pub struct WebhookRegistry {
+    parsers: HashMap<String, Box<dyn WebhookParser>>,
+}
+
+impl WebhookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with parsers for every provider that supports webhooks
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(super::fathom::FathomWebhookParser));
+        registry
+    }
+
+    pub fn register(&mut self, parser: Box<dyn WebhookParser>) {
+        self.parsers.insert(parser.provider().0, parser);
+    }
+
+    /// Whether payloads from `provider` can be parsed
+    pub fn supports(&self, provider: &str) -> bool {
+        self.parsers.contains_key(provider)
+    }
+
+    /// Parse a payload for the given provider
+    pub fn parse(&self, provider: &str, body: &[u8]) -> Result<WebhookEvent> {
+        let parser = self
+            .parsers
+            .get(provider)
+            .ok_or_else(|| ProviderError::Api(format!("No webhook support for {}", provider)))?;
+        parser.parse(body)
+    }
+}
+
+/// Header with the delivery's unique id
+pub const ID_HEADER: &str = "webhook-id";
+/// Header with when the delivery was sent, in Unix seconds
+pub const TIMESTAMP_HEADER: &str = "webhook-timestamp";
+/// Header with the delivery's signatures
+pub const SIGNATURE_HEADER: &str = "webhook-signature";
+
+/// How far a delivery's timestamp may be from now; older ones may be replays
+const TIMESTAMP_TOLERANCE_SECS: i64 = 5 * 60;
+
+/// The signed headers of one delivery
+#[derive(Debug, Clone, Copy)]
+pub struct SignedDelivery<'a> {
+    pub id: &'a str,
+    pub timestamp: &'a str,
+    pub signature: &'a str,
+}
+
+/// The HMAC key behind a `whsec_` secret, if it is base64
+fn signing_key(secret: &str) -> Option<hmac::Key> {
+    let secret = secret.trim();
+    let encoded = secret.strip_prefix("whsec_").unwrap_or(secret);
+    let key = BASE64.decode(encoded).ok().filter(|key| !key.is_empty())?;
+    Some(hmac::Key::new(hmac::HMAC_SHA256, &key))
+}
+
+/// Whether deliveries can be checked against `secret`
+pub fn is_valid_secret(secret: &str) -> bool {
+    signing_key(secret).is_some()
+}
+
+fn signed_content(id: &str, timestamp: &str, body: &[u8]) -> Vec<u8> {
+    [id.as_bytes(), b".", timestamp.as_bytes(), b".", body].concat()
+}
+
+/// The `webhook-signature` a provider sends for this delivery
+pub fn sign(secret: &str, id: &str, timestamp: i64, body: &[u8]) -> Option<String> {
+    let key = signing_key(secret)?;
+    let content = signed_content(id, &timestamp.to_string(), body);
+    let tag = hmac::sign(&key, &content);
+    Some(format!("v1,{}", BASE64.encode(tag.as_ref())))
+}
+
+/// Check a delivery's signature against the configured secret
+///
+/// The timestamp must be within five minutes of `now`. Any one `v1`
+/// signature in the header may match, as while a secret is being rotated.
+pub fn verify_signature(
+    secret: &str,
+    delivery: SignedDelivery<'_>,
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> bool {
+    let Some(key) = signing_key(secret) else {
+        return false;
+    };
+    let timestamp = delivery.timestamp.trim();
+    let Ok(sent) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    if (now.timestamp() - sent).abs() > TIMESTAMP_TOLERANCE_SECS {
+        return false;
+    }
+    let content = signed_content(delivery.id, timestamp, body);
+    delivery
+        .signature
+        .split_whitespace()
+        .filter_map(|entry| entry.strip_prefix("v1,"))
+        .filter_map(|signature| BASE64.decode(signature).ok())
+        // Compares in constant time
+        .any(|tag| hmac::verify(&key, &content, &tag).is_ok())
+}
+
+/// Lifecycle of an ingest job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed(String),
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+/// A targeted sync triggered by a webhook
+#[derive(Debug, Clone)]
+pub struct IngestJob {
+    pub id: u64,
+    pub event: WebhookEvent,
+    pub status: JobStatus,
+}
+
+/// In-memory job tracker for webhook-triggered syncs
+#[derive(Default)]
+pub struct IngestQueue {
+    jobs: Mutex<Vec<IngestJob>>,
+}
+
+impl IngestQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enqueue a sync for `event`.
+    ///
+    /// Returns the job id and whether a new job was created. A delivery for a
+    /// conversation that already has a pending or running job reuses that job.
+    pub fn enqueue(&self, event: WebhookEvent) -> (u64, bool) {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        if let Some(existing) = jobs.iter().find(|j| {
+            j.event.provider == event.provider
+                && j.event.conversation_id == event.conversation_id
+                && matches!(j.status, JobStatus::Pending | JobStatus::Running)
+        }) {
+            return (existing.id, false);
+        }
+
+        let id = jobs.len() as u64 + 1;
+        jobs.push(IngestJob {
+            id,
+            event,
+            status: JobStatus::Pending,
+        });
+        (id, true)
+    }
+
+    /// Update the status of a job
+    pub fn set_status(&self, id: u64, status: JobStatus) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.iter_mut().find(|j| j.id == id) {
+            job.status = status;
+        }
+    }
+
+    /// Look up a job by id
+    pub fn get(&self, id: u64) -> Option<IngestJob> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|j| j.id == id)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FATHOM_PAYLOAD: &str = r#"{
+        "url": "https://fathom.video/calls/meeting-42",
+        "title": "Customer call",
+        "created_at": "2025-01-15T10:00:00Z",
+        "transcript": []
+    }"#;
+
+    /// Secret and signature from the Standard Webhooks reference tests
+    const SECRET: &str = "whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw";
+    const ID: &str = "msg_p5jXN8AQM9LWM0D4loKWxJek";
+    const TIMESTAMP: i64 = 1614265330;
+    const BODY: &str = r#"{"test": 2432232314}"#;
+    const SIGNATURE: &str = "v1,g0hM9SsE+OTPJTGt/tmIKtSyZlE3uFJELVlNIOLJ1OE=";
+
+    fn delivery(signature: &str) -> SignedDelivery<'_> {
+        SignedDelivery {
+            id: ID,
+            timestamp: "1614265330",
+            signature,
+        }
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let body = BODY.as_bytes();
+        let now = DateTime::from_timestamp(TIMESTAMP + 60, 0).unwrap();
+        assert_eq!(
+            sign(SECRET, ID, TIMESTAMP, body).as_deref(),
+            Some(SIGNATURE)
+        );
+
+        assert!(verify_signature(SECRET, delivery(SIGNATURE), body, now));
+        // Any one signature may match
+        let rotated = format!("v1,bm90IGl0 {}", SIGNATURE);
+        assert!(verify_signature(SECRET, delivery(&rotated), body, now));
+
+        assert!(!verify_signature(SECRET, delivery(SIGNATURE), b"{}", now));
+        assert!(!verify_signature(
+            SECRET,
+            delivery("v1,bm90IGl0"),
+            body,
+            now
+        ));
+        assert!(!verify_signature(
+            SECRET,
+            delivery(&SIGNATURE.replace("v1,", "v2,")),
+            body,
+            now
+        ));
+        let other = sign("whsec_c2VjcmV0", ID, TIMESTAMP, body).unwrap();
+        assert!(!verify_signature(SECRET, delivery(&other), body, now));
+
+        // Too old or too far ahead
+        let later = DateTime::from_timestamp(TIMESTAMP + 600, 0).unwrap();
+        let earlier = DateTime::from_timestamp(TIMESTAMP - 600, 0).unwrap();
+        assert!(!verify_signature(SECRET, delivery(SIGNATURE), body, later));
+        assert!(!verify_signature(
+            SECRET,
+            delivery(SIGNATURE),
+            body,
+            earlier
+        ));
+
+        assert!(!is_valid_secret(""));
+        assert!(!is_valid_secret("whsec_not base64!"));
+        assert!(!verify_signature("", delivery(SIGNATURE), body, now));
+    }
+
+    #[test]
+    fn test_registry_parses_fathom_payload() {
+        let registry = WebhookRegistry::with_defaults();
+        let event = registry.parse("fathom", FATHOM_PAYLOAD.as_bytes()).unwrap();
+        assert_eq!(event.provider, ProviderId::fathom());
+        assert_eq!(event.conversation_id, "meeting-42");
+        assert_eq!(
+            event.created_at.unwrap().to_rfc3339(),
+            "2025-01-15T10:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_registry_rejects_unknown_provider_and_bad_payload() {
+        let registry = WebhookRegistry::with_defaults();
+        assert!(registry
+            .parse("chatgpt", FATHOM_PAYLOAD.as_bytes())
+            .is_err());
+        assert!(registry.parse("fathom", b"not json").is_err());
+    }
+
+    #[test]
+    fn test_queue_dedupes_in_flight_events() {
+        let registry = WebhookRegistry::with_defaults();
+        let queue = IngestQueue::new();
+
+        let event = registry.parse("fathom", FATHOM_PAYLOAD.as_bytes()).unwrap();
+        let (first, created) = queue.enqueue(event.clone());
+        assert!(created);
+
+        let (second, created) = queue.enqueue(event.clone());
+        assert!(!created);
+        assert_eq!(first, second);
+
+        // Once finished, a new delivery schedules a fresh sync
+        queue.set_status(first, JobStatus::Done);
+        let (third, created) = queue.enqueue(event);
+        assert!(created);
+        assert_ne!(first, third);
+        assert_eq!(queue.get(first).unwrap().status, JobStatus::Done);
+        assert_eq!(queue.get(third).unwrap().status, JobStatus::Pending);
+    }
+}
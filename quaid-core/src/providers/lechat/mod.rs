@@ -0,0 +1,621 @@
+//! Mistral Le Chat provider implementation
+//!
+//! Syncs chats from chat.mistral.ai through the JSON API of the web app,
+//! signed in with the cookies of a browser session.
+
+pub mod types;
+
+use crate::credentials::keys::lechat::COOKIES as KEYRING_USER_COOKIES;
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::{
+    with_retry, Account, Attachment, Conversation, Message, MessageContent, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
+};
+use async_trait::async_trait;
+use reqwest::{header, Client};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use types::*;
+
+const BASE_URL: &str = "https://chat.mistral.ai";
+const API_URL: &str = "https://chat.mistral.ai/api";
+
+/// Chats per page of the listing
+const PAGE_SIZE: usize = 50;
+
+/// Le Chat provider
+pub struct LeChatProvider {
+    client: Client,
+    /// `Cookie` header of the signed-in browser session
+    cookies: Option<String>,
+    credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Chats from the last listing, which have the titles and models their
+    /// messages lack
+    listed: Mutex<HashMap<String, ApiChat>>,
+}
+
+impl LeChatProvider {
+    /// Create a new Le Chat provider, loading credentials from keyring if available
+    pub fn new() -> Self {
+        Self::with_account_credentials(default_store(), None)
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
+        let cookies = credential_store
+            .get_for_account(KEYRING_SERVICE, KEYRING_USER_COOKIES, email)
+            .ok();
+
+        Self {
+            client: build_client(),
+            cookies,
+            credential_store,
+            retry: RetryPolicy::default(),
+            listed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// GET `endpoint` of the API and parse its JSON body, retrying transient failures
+    async fn api_get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        with_retry(&self.retry, || self.api_get_once(endpoint)).await
+    }
+
+    async fn api_get_once<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let cookies = self.cookies.as_ref().ok_or(ProviderError::AuthRequired)?;
+        let url = format!("{}{}", API_URL, endpoint);
+        let resp = self
+            .client
+            .get(&url)
+            .header(header::COOKIE, cookies)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60);
+            return Err(ProviderError::RateLimited(retry_after));
+        }
+        if status == 401 || status == 403 {
+            return Err(ProviderError::TokenExpired);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: GET {} failed: {}",
+                status,
+                url,
+                truncate_body(&body, 500)
+            )));
+        }
+        serde_json::from_str(&body).map_err(|e| {
+            ProviderError::Parse(format!(
+                "Failed to parse response: {}. Body: {}",
+                e,
+                truncate_body(&body, 500)
+            ))
+        })
+    }
+
+    /// Every chat, most recently updated first
+    async fn list_chats(&self) -> Result<Vec<ApiChat>> {
+        let mut chats = Vec::new();
+        for page in 0.. {
+            let listing: ApiChatPage = self.api_get(&chats_endpoint(page)).await?;
+            let last = !listing.has_more || listing.items.is_empty();
+            chats.extend(listing.items);
+            if last {
+                break;
+            }
+        }
+        Ok(chats)
+    }
+
+    /// A chat's messages, oldest first
+    async fn read_messages(&self, id: &str) -> Result<Vec<ApiMessage>> {
+        let mut page: ApiMessages = self.api_get(&format!("/chats/{}/messages", id)).await?;
+        page.messages.sort_by_key(|m| m.created_at);
+        Ok(page.messages)
+    }
+}
+
+impl Default for LeChatProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for LeChatProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::lechat()
+    }
+
+    async fn is_authenticated(&self) -> bool {
+        self.cookies.is_some()
+    }
+
+    async fn authenticate(&mut self) -> Result<Account> {
+        // Browser-based authentication flow
+        use chromiumoxide::browser::{Browser, BrowserConfig};
+        use futures::StreamExt;
+
+        println!("Opening browser for Le Chat authentication...");
+        println!("Please sign in to your Mistral account.");
+
+        // Set up user data dir to persist session
+        let user_data_dir = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("quaid")
+            .join("lechat-chrome-profile");
+        std::fs::create_dir_all(&user_data_dir).ok();
+
+        let mut builder = BrowserConfig::builder()
+            .with_head()
+            .user_data_dir(&user_data_dir)
+            .arg("--disable-blink-features=AutomationControlled")
+            .arg("--disable-infobars")
+            .arg("--no-first-run")
+            .window_size(1280, 900);
+
+        // Try to find Chrome on the system
+        if let Some(chrome_path) = find_chrome() {
+            builder = builder.chrome_executable(chrome_path);
+        }
+
+        let config = builder
+            .build()
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                if event.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let page = browser
+            .new_page(BASE_URL)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        // Sign-in happens on auth.mistral.ai; the session counts once the
+        // browser is back on Le Chat and the API knows who the user is
+        println!("Waiting for login... (this window will close automatically)");
+
+        let account = loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+            let url = page.url().await.ok().flatten().unwrap_or_default();
+            if !url.starts_with(BASE_URL) {
+                continue;
+            }
+            let found = page.get_cookies().await.unwrap_or_default();
+            let cookies = found
+                .iter()
+                .filter(|c| c.domain.ends_with("mistral.ai"))
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect::<Vec<_>>()
+                .join("; ");
+            if cookies.is_empty() {
+                continue;
+            }
+
+            self.cookies = Some(cookies);
+            match self.account().await {
+                Ok(account) => {
+                    println!("Login detected!");
+                    break account;
+                }
+                Err(_) => self.cookies = None,
+            }
+        };
+
+        // Close browser
+        drop(browser);
+        handle.abort();
+
+        // Save under the signed-in account, so other accounts keep theirs
+        if let Some(cookies) = &self.cookies {
+            if let Err(e) = self.credential_store.set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_COOKIES,
+                &account.email,
+                cookies,
+            ) {
+                eprintln!("Warning: failed to save cookies: {}", e);
+            }
+        }
+        println!("Authentication successful!");
+        Ok(account)
+    }
+
+    async fn account(&self) -> Result<Account> {
+        let user: ApiUser = self.api_get("/users/me").await?;
+
+        Ok(Account {
+            id: user.id,
+            provider: ProviderId::lechat(),
+            email: user.email,
+            name: user.name,
+            avatar_url: None,
+            workspace_id: None,
+        })
+    }
+
+    async fn conversations(&self) -> Result<Vec<Conversation>> {
+        let chats = self.list_chats().await?;
+        let conversations = chats.iter().map(|c| convert_chat(c, &[])).collect();
+
+        let mut listed = self.listed.lock().unwrap();
+        listed.extend(chats.into_iter().map(|c| (c.id.clone(), c)));
+        Ok(conversations)
+    }
+
+    async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
+        let messages = self.read_messages(id).await?;
+        let chat = self.listed.lock().unwrap().get(id).cloned();
+        let conversation = match chat {
+            Some(chat) => convert_chat(&chat, &messages),
+            None => {
+                let chat: ApiChat = self.api_get(&format!("/chats/{}", id)).await?;
+                convert_chat(&chat, &messages)
+            }
+        };
+
+        Ok((conversation, convert_messages(id, &messages)))
+    }
+
+    async fn project_conversations(&self, _project_id: &str) -> Result<Vec<Conversation>> {
+        Ok(Vec::new())
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment, _path: &Path) -> Result<()> {
+        Err(ProviderError::Api(format!(
+            "Le Chat attachments can't be downloaded yet ({})",
+            attachment.filename
+        )))
+    }
+}
+
+/// The chat listing from `page`, counting from 0
+fn chats_endpoint(page: usize) -> String {
+    format!("/chats?page={}&pageSize={}", page, PAGE_SIZE)
+}
+
+/// A listed chat, with its last update taken from its messages when they're known
+///
+/// Chats held with an agent are attributed to the agent rather than the
+/// model behind it; untitled chats are named after their first prompt.
+fn convert_chat(chat: &ApiChat, messages: &[ApiMessage]) -> Conversation {
+    let updated_at = chat
+        .updated_at
+        .into_iter()
+        .chain(messages.iter().filter_map(|m| m.created_at))
+        .max()
+        .unwrap_or(chat.created_at)
+        .max(chat.created_at);
+    let title = match chat.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => messages
+            .iter()
+            .filter(|m| m.role == "user")
+            .find_map(|m| m.content.lines().find(|l| !l.trim().is_empty()))
+            .map(|line| line.chars().take(80).collect::<String>())
+            .unwrap_or_else(|| "Untitled".to_string()),
+    };
+    let model = chat
+        .agent
+        .as_ref()
+        .map(|agent| agent.name.clone().unwrap_or_else(|| agent.id.clone()))
+        .or_else(|| chat.model.clone());
+
+    Conversation {
+        id: chat.id.clone(),
+        provider_id: "lechat".to_string(),
+        title,
+        created_at: chat.created_at,
+        updated_at,
+        model,
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    }
+}
+
+fn role(role: &str) -> Option<Role> {
+    match role {
+        "user" => Some(Role::User),
+        "assistant" => Some(Role::Assistant),
+        "system" => Some(Role::System),
+        "tool" => Some(Role::Tool),
+        _ => None,
+    }
+}
+
+/// Each message with a known role and something to say
+fn convert_messages(conv_id: &str, messages: &[ApiMessage]) -> Vec<Message> {
+    messages
+        .iter()
+        .filter_map(|msg| {
+            let role = role(&msg.role)?;
+            let mut parts = split_code_blocks(&msg.content);
+            let content = match parts.len() {
+                0 => return None,
+                1 => parts.remove(0),
+                _ => MessageContent::Mixed { parts },
+            };
+
+            Some(Message {
+                id: msg.id.clone(),
+                conversation_id: conv_id.to_string(),
+                parent_id: msg.parent_id.clone(),
+                role,
+                content,
+                created_at: msg.created_at,
+                model: msg.model.clone(),
+                speaker: None,
+            })
+        })
+        .collect()
+}
+
+/// Markdown as text and code parts, one per fenced block and the text between
+///
+/// An unclosed fence runs to the end of the message, as the web app renders
+/// it while a reply is still streaming.
+fn split_code_blocks(markdown: &str) -> Vec<MessageContent> {
+    let mut parts = Vec::new();
+    for (i, section) in markdown.split("```").enumerate() {
+        if i % 2 == 1 {
+            let (language, code) = section.split_once('\n').unwrap_or(("", section));
+            let code = code.trim_end();
+            if !code.is_empty() {
+                parts.push(MessageContent::Code {
+                    language: language.trim().to_string(),
+                    code: code.to_string(),
+                });
+            }
+        } else if !section.trim().is_empty() {
+            parts.push(MessageContent::Text {
+                text: section.trim().to_string(),
+            });
+        }
+    }
+    parts
+}
+
+/// Build HTTP client with browser-like headers
+fn build_client() -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+    headers.insert(header::ORIGIN, BASE_URL.parse().unwrap());
+    headers.insert(header::REFERER, "https://chat.mistral.ai/".parse().unwrap());
+
+    Client::builder()
+        .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Safely truncate a string at a char boundary
+fn truncate_body(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Find a valid char boundary
+        let mut end = max_len;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+/// Find Chrome/Chromium executable on the system
+fn find_chrome() -> Option<std::path::PathBuf> {
+    let candidates = if cfg!(target_os = "macos") {
+        vec![
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+            "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ]
+    } else if cfg!(target_os = "linux") {
+        vec![
+            "/usr/bin/google-chrome",
+            "/usr/bin/google-chrome-stable",
+            "/usr/bin/chromium",
+            "/usr/bin/chromium-browser",
+            "/snap/bin/chromium",
+        ]
+    } else {
+        // Windows - chromiumoxide should handle this
+        vec![]
+    };
+
+    for candidate in candidates {
+        let path = std::path::PathBuf::from(candidate);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::MockStore;
+
+    fn fixture<T: DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_provider_id() {
+        let provider = LeChatProvider::with_account_credentials(Arc::new(MockStore::new()), None);
+        assert_eq!(provider.id(), ProviderId::lechat());
+    }
+
+    #[test]
+    fn test_authenticated_with_stored_cookies() {
+        let store = Arc::new(MockStore::new());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider =
+            LeChatProvider::with_account_credentials(store.clone(), Some("ana@example.com"));
+        assert!(!rt.block_on(provider.is_authenticated()));
+
+        store
+            .set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_COOKIES,
+                "ana@example.com",
+                "ory_session=abc",
+            )
+            .unwrap();
+        let provider = LeChatProvider::with_account_credentials(store, Some("ana@example.com"));
+        assert!(rt.block_on(provider.is_authenticated()));
+    }
+
+    #[test]
+    fn test_chat_list_becomes_conversations() {
+        let page: ApiChatPage = fixture("lechat-chats.json");
+        assert!(page.has_more);
+
+        let conversations: Vec<_> = page.items.iter().map(|c| convert_chat(c, &[])).collect();
+        assert_eq!(conversations.len(), 3);
+        assert_eq!(conversations[0].id, "5b0f3c1e-8d2a-4f6b-9c7e-1a2b3c4d5e6f");
+        assert_eq!(conversations[0].title, "Dedupe a CSV in pandas");
+        assert_eq!(conversations[0].provider_id, "lechat");
+        assert_eq!(
+            conversations[0].model.as_deref(),
+            Some("mistral-large-latest")
+        );
+        assert_eq!(
+            conversations[0].created_at.to_rfc3339(),
+            "2024-09-12T09:30:00+00:00"
+        );
+        assert_eq!(
+            conversations[0].updated_at.to_rfc3339(),
+            "2024-09-12T09:31:40+00:00"
+        );
+        assert_eq!(
+            conversations[0].web_url().as_deref(),
+            Some("https://chat.mistral.ai/chat/5b0f3c1e-8d2a-4f6b-9c7e-1a2b3c4d5e6f")
+        );
+
+        // Agent chats are attributed to the agent
+        assert_eq!(conversations[1].model.as_deref(), Some("Recipe Helper"));
+        assert_eq!(conversations[2].title, "Untitled");
+        assert_eq!(conversations[2].updated_at, conversations[2].created_at);
+    }
+
+    #[test]
+    fn test_messages_split_code_blocks() {
+        let page: ApiMessages = fixture("lechat-messages.json");
+        let messages = convert_messages("5b0f3c1e-8d2a-4f6b-9c7e-1a2b3c4d5e6f", &page.messages);
+
+        // The empty message is left out
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].role, Role::User);
+        assert!(matches!(
+            &messages[0].content,
+            MessageContent::Text { text } if text == "How do I drop duplicate rows in a CSV with pandas?"
+        ));
+
+        assert_eq!(messages[1].role, Role::Assistant);
+        assert_eq!(messages[1].model.as_deref(), Some("mistral-large-latest"));
+        assert_eq!(messages[1].parent_id.as_deref(), Some("msg-1"));
+        match &messages[1].content {
+            MessageContent::Mixed { parts } => match parts.as_slice() {
+                [MessageContent::Text { text: intro }, MessageContent::Code { language, code }, MessageContent::Text { text: outro }] =>
+                {
+                    assert_eq!(intro, "Use `drop_duplicates`:");
+                    assert_eq!(language, "python");
+                    assert_eq!(
+                        code,
+                        "import pandas as pd\n\ndf = pd.read_csv(\"rows.csv\")\ndf.drop_duplicates().to_csv(\"rows.csv\", index=False)"
+                    );
+                    assert_eq!(outro, "Pass `subset=` to compare only some columns.");
+                }
+                other => panic!("Unexpected parts: {:?}", other),
+            },
+            other => panic!("Expected mixed content, got {:?}", other),
+        }
+
+        // A reply that is only code stays a single code block
+        assert!(matches!(
+            &messages[2].content,
+            MessageContent::Code { language, code } if language.is_empty() && code == "subset=[\"email\"]"
+        ));
+
+        // Untitled chats are named after the first prompt
+        let mut chat: ApiChatPage = fixture("lechat-chats.json");
+        let untitled = chat.items.remove(2);
+        let conversation = convert_chat(&untitled, &page.messages);
+        assert_eq!(
+            conversation.title,
+            "How do I drop duplicate rows in a CSV with pandas?"
+        );
+        assert_eq!(
+            conversation.updated_at.to_rfc3339(),
+            "2024-09-12T09:31:40+00:00"
+        );
+    }
+
+    #[test]
+    fn test_unclosed_fence_runs_to_the_end() {
+        let parts = split_code_blocks("Try this:\n```sh\ncargo test\n");
+        assert!(matches!(
+            parts.as_slice(),
+            [MessageContent::Text { .. }, MessageContent::Code { language, code }]
+                if language == "sh" && code == "cargo test"
+        ));
+        assert!(split_code_blocks("  \n").is_empty());
+    }
+
+    #[test]
+    fn test_chats_endpoint_pages_from_zero() {
+        assert_eq!(chats_endpoint(0), "/chats?page=0&pageSize=50");
+        assert_eq!(chats_endpoint(2), "/chats?page=2&pageSize=50");
+    }
+}
@@ -0,0 +1,76 @@
+//! Le Chat web API response types
+//!
+//! These types are based on the JSON endpoints the chat.mistral.ai web app
+//! calls and may need adjustment as the API evolves.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// The signed-in user
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiUser {
+    pub id: String,
+    pub email: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// One page of the chat list, most recently updated first
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiChatPage {
+    #[serde(default)]
+    pub items: Vec<ApiChat>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// A chat in the list
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiChat {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Model the chat was started with, e.g. `mistral-large-latest`
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Agent the chat was held with, if not the default assistant
+    #[serde(default)]
+    pub agent: Option<ApiAgent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiAgent {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// A chat's messages
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiMessages {
+    #[serde(default)]
+    pub messages: Vec<ApiMessage>,
+}
+
+/// A message in a chat
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiMessage {
+    pub id: String,
+    /// `user`, `assistant`, `system` or `tool`
+    pub role: String,
+    /// Markdown, with code in fenced blocks
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
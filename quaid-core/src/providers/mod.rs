@@ -1,12 +1,28 @@
+pub mod aistudio;
 pub mod chatgpt;
 pub mod claude;
+pub mod copilot;
+pub mod deepseek;
+pub mod download;
 pub mod fathom;
+pub mod gemini;
 pub mod granola;
+pub mod grok;
+pub mod ingest;
+pub mod lechat;
+pub mod otter;
+pub mod poe;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use thiserror::Error;
+use tokio::time::Instant;
 
 #[derive(Error, Debug)]
 pub enum ProviderError {
@@ -30,10 +46,138 @@ pub enum ProviderError {
 
     #[error("Parse error: {0}")]
     Parse(String),
+
+    #[error("{0} bytes is over the size limit")]
+    TooLarge(u64),
 }
 
 pub type Result<T> = std::result::Result<T, ProviderError>;
 
+impl ProviderError {
+    /// Whether the failure is transient and the request worth retrying.
+    ///
+    /// HTTP failures are reported as `Api("<status>: <body>")`, so a 5xx is
+    /// recognised by its leading status code.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ProviderError::Network(e) => !e.is_decode(),
+            ProviderError::RateLimited(_) => true,
+            ProviderError::Api(_) => self
+                .http_status()
+                .is_some_and(|code| (500..600).contains(&code)),
+            _ => false,
+        }
+    }
+
+    /// The HTTP status an `Api("<status>: <body>")` failure leads with
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            ProviderError::Api(message) => message.get(..3)?.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Backoff settings for [`with_retry`]
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on each subsequent one
+    pub base_delay: Duration,
+    /// Upper bound for the exponential delay
+    pub max_delay: Duration,
+    /// Randomise each backoff delay between half and all of it, so
+    /// concurrent workers don't retry in lockstep
+    pub jitter: bool,
+    /// Pause shared by every request made with this policy (and its clones)
+    pub gate: RateLimitGate,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            gate: RateLimitGate::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        delay / 2 + delay.mul_f64((random % 1000) as f64 / 2000.0)
+    }
+}
+
+/// A pause that applies to every request to a provider, not just the one
+/// that was rate limited
+///
+/// Providers hold one in their [`RetryPolicy`]; when any request gets a 429,
+/// all requests in flight wait out the retry-after before trying again.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitGate {
+    until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimitGate {
+    /// Hold every request for at least `duration` from now
+    pub fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut current = self.until.lock().unwrap();
+        if current.is_none_or(|current| current < until) {
+            *current = Some(until);
+        }
+    }
+
+    /// Wait until the pause, if any, is over
+    pub async fn wait(&self) {
+        let until = *self.until.lock().unwrap();
+        if let Some(until) = until {
+            tokio::time::sleep_until(until).await;
+        }
+    }
+}
+
+/// Run `op`, retrying transient failures with exponential backoff and jitter.
+///
+/// `RateLimited(secs)` pauses the policy's [`RateLimitGate`] for the time the
+/// server asked, holding back every request that shares it, instead of the
+/// backoff delay. Errors that aren't [retryable](ProviderError::is_retryable)
+/// are returned immediately.
+pub async fn with_retry<T, F, Fut>(policy: &RetryPolicy, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        policy.gate.wait().await;
+        match op().await {
+            Err(e) if attempt < policy.max_attempts && e.is_retryable() => {
+                match e {
+                    ProviderError::RateLimited(secs) => {
+                        policy.gate.pause_for(Duration::from_secs(secs))
+                    }
+                    _ => tokio::time::sleep(policy.backoff(attempt)).await,
+                }
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Unique identifier for a provider (e.g., "chatgpt", "claude", "gemini")
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ProviderId(pub String);
@@ -47,6 +191,11 @@ impl ProviderId {
         Self("claude".to_string())
     }
 
+    /// Claude Code sessions, imported from their files rather than pulled
+    pub fn claude_code() -> Self {
+        Self("claude-code".to_string())
+    }
+
     pub fn fathom() -> Self {
         Self("fathom".to_string())
     }
@@ -54,6 +203,48 @@ impl ProviderId {
     pub fn granola() -> Self {
         Self("granola".to_string())
     }
+
+    pub fn gemini() -> Self {
+        Self("gemini".to_string())
+    }
+
+    pub fn grok() -> Self {
+        Self("grok".to_string())
+    }
+
+    pub fn copilot() -> Self {
+        Self("copilot".to_string())
+    }
+
+    pub fn lechat() -> Self {
+        Self("lechat".to_string())
+    }
+
+    pub fn deepseek() -> Self {
+        Self("deepseek".to_string())
+    }
+
+    pub fn poe() -> Self {
+        Self("poe".to_string())
+    }
+
+    pub fn otter() -> Self {
+        Self("otter".to_string())
+    }
+
+    /// Google AI Studio prompts, imported from files rather than pulled
+    pub fn aistudio() -> Self {
+        Self("aistudio".to_string())
+    }
+
+    /// Concurrent requests this provider tolerates before rate limiting kicks in
+    pub fn max_concurrent_requests(&self) -> usize {
+        match self.0.as_str() {
+            "chatgpt" | "claude" => 4,
+            "granola" => 3,
+            _ => 2,
+        }
+    }
 }
 
 impl std::fmt::Display for ProviderId {
@@ -63,13 +254,39 @@ impl std::fmt::Display for ProviderId {
 }
 
 /// Account information for a provider
+///
+/// One sign-in can hold several accounts: a ChatGPT user in a team workspace
+/// gets one for their personal conversations and one per workspace, all with
+/// the same email and credentials.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
     pub id: String,
     pub provider: ProviderId,
     pub email: String,
+    /// The user's name, or the workspace's for a workspace account
     pub name: Option<String>,
     pub avatar_url: Option<String>,
+    /// ChatGPT workspace whose conversations this account holds; `None` for
+    /// a personal account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
+}
+
+impl Account {
+    /// Whether this is the account imported conversations are kept under,
+    /// which has nothing to pull
+    pub fn is_local(&self) -> bool {
+        self.email == "local"
+    }
+
+    /// The email, with the workspace name for a workspace account
+    pub fn label(&self) -> String {
+        match (&self.workspace_id, &self.name) {
+            (Some(_), Some(name)) => format!("{} ({})", self.email, name),
+            (Some(workspace_id), None) => format!("{} ({})", self.email, workspace_id),
+            (None, _) => self.email.clone(),
+        }
+    }
 }
 
 /// A conversation from any provider
@@ -86,6 +303,40 @@ pub struct Conversation {
     pub is_archived: bool,
 }
 
+impl Conversation {
+    /// Link to the conversation in the provider's web app, if it has one
+    pub fn web_url(&self) -> Option<String> {
+        match self.provider_id.as_str() {
+            "chatgpt" => Some(format!("https://chatgpt.com/c/{}", self.id)),
+            "claude" => Some(format!("https://claude.ai/chat/{}", self.id)),
+            "fathom" => Some(format!("https://fathom.video/calls/{}", self.id)),
+            "gemini" => Some(format!(
+                "https://gemini.google.com/app/{}",
+                self.id.trim_start_matches("c_")
+            )),
+            "grok" => Some(format!("https://x.com/i/grok?conversation={}", self.id)),
+            "copilot" => Some(format!("https://copilot.microsoft.com/chats/{}", self.id)),
+            "lechat" => Some(format!("https://chat.mistral.ai/chat/{}", self.id)),
+            "deepseek" => Some(format!("https://chat.deepseek.com/a/chat/s/{}", self.id)),
+            "poe" => Some(format!("https://poe.com/chat/{}", self.id)),
+            "otter" => Some(format!("https://otter.ai/u/{}", self.id)),
+            _ => None,
+        }
+    }
+}
+
+/// A project (ChatGPT project, Claude project) grouping conversations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub provider_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
 /// A message within a conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -96,6 +347,9 @@ pub struct Message {
     pub content: MessageContent,
     pub created_at: Option<DateTime<Utc>>,
     pub model: Option<String>,
+    /// Who said it, for meeting transcripts with several people on the call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -107,14 +361,73 @@ pub enum Role {
     Tool,
 }
 
+impl Role {
+    /// Lowercase name, as stored in SQLite and parquet
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::System => "system",
+            Role::Tool => "tool",
+        }
+    }
+
+    /// Parse a lowercase role name
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "user" => Some(Role::User),
+            "assistant" => Some(Role::Assistant),
+            "system" => Some(Role::System),
+            "tool" => Some(Role::Tool),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum MessageContent {
-    Text { text: String },
-    Code { language: String, code: String },
-    Image { url: String, alt: Option<String> },
-    Audio { url: String, transcript: Option<String> },
-    Mixed { parts: Vec<MessageContent> },
+    Text {
+        text: String,
+    },
+    Code {
+        language: String,
+        code: String,
+    },
+    Image {
+        url: String,
+        alt: Option<String>,
+    },
+    Audio {
+        url: String,
+        transcript: Option<String>,
+    },
+    /// A standalone document written in the conversation, like a Claude
+    /// artifact; source code has a `text/x-<language>` MIME type
+    Document {
+        title: String,
+        mime: String,
+        text: String,
+    },
+    /// A model's reasoning before its answer, kept apart from the answer
+    Thinking {
+        text: String,
+    },
+    Mixed {
+        parts: Vec<MessageContent>,
+    },
+}
+
+/// Code-fence language for a document's MIME type; empty when there's none
+pub fn document_language(mime: &str) -> &str {
+    match mime {
+        "text/markdown" => "markdown",
+        "text/html" => "html",
+        "image/svg+xml" => "svg",
+        "application/vnd.ant.react" => "jsx",
+        "application/vnd.ant.mermaid" => "mermaid",
+        _ => mime.strip_prefix("text/x-").unwrap_or(""),
+    }
 }
 
 /// Attachment metadata
@@ -126,6 +439,14 @@ pub struct Attachment {
     pub mime_type: String,
     pub size_bytes: u64,
     pub download_url: String,
+    /// Pixel dimensions of images, when the provider reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Hex sha256 of the content, once downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
 }
 
 /// Progress callback for long-running operations
@@ -140,6 +461,17 @@ pub trait Provider: Send + Sync {
     /// Check if the provider is authenticated
     async fn is_authenticated(&self) -> bool;
 
+    /// Make a cheap authenticated request, to tell credentials the provider
+    /// still accepts from ones that were saved but have since expired
+    ///
+    /// Errors say what to do next. Defaults to fetching the account.
+    async fn validate_credentials(&self) -> Result<()> {
+        if !self.is_authenticated().await {
+            return Err(ProviderError::AuthRequired);
+        }
+        self.account().await.map(|_| ())
+    }
+
     /// Authenticate the user (opens browser for OAuth flow)
     async fn authenticate(&mut self) -> Result<Account>;
 
@@ -155,9 +487,29 @@ pub trait Provider: Send + Sync {
     /// Get conversations for a specific project
     async fn project_conversations(&self, project_id: &str) -> Result<Vec<Conversation>>;
 
+    /// List the account's projects; providers without projects have none
+    async fn projects(&self) -> Result<Vec<Project>> {
+        Ok(Vec::new())
+    }
+
+    /// Where an attachment can be fetched from right now, for providers whose
+    /// download links expire
+    ///
+    /// Called again whenever the link is refused. Providers that return `None`
+    /// are downloaded with [`Provider::download_attachment`] instead.
+    async fn attachment_source(
+        &self,
+        _attachment: &Attachment,
+    ) -> Result<Option<download::DownloadSource>> {
+        Ok(None)
+    }
+
     /// Download an attachment to a local path
-    async fn download_attachment(&self, attachment: &Attachment, path: &std::path::Path)
-        -> Result<()>;
+    async fn download_attachment(
+        &self,
+        attachment: &Attachment,
+        path: &std::path::Path,
+    ) -> Result<()>;
 }
 
 #[cfg(test)]
@@ -179,6 +531,34 @@ mod tests {
         assert_eq!(ProviderId::granola().to_string(), "granola");
     }
 
+    #[test]
+    fn test_conversation_web_url() {
+        let mut conv = Conversation {
+            id: "abc-123".to_string(),
+            provider_id: "claude".to_string(),
+            title: "Test".to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        assert_eq!(
+            conv.web_url().as_deref(),
+            Some("https://claude.ai/chat/abc-123")
+        );
+
+        conv.provider_id = "chatgpt".to_string();
+        assert_eq!(
+            conv.web_url().as_deref(),
+            Some("https://chatgpt.com/c/abc-123")
+        );
+
+        conv.provider_id = "granola".to_string();
+        assert_eq!(conv.web_url(), None);
+    }
+
     #[test]
     fn test_role_serialization() {
         let user = Role::User;
@@ -189,6 +569,133 @@ mod tests {
         assert_eq!(parsed, Role::User);
     }
 
+    #[test]
+    fn test_role_as_str_round_trip() {
+        for role in [Role::User, Role::Assistant, Role::System, Role::Tool] {
+            assert_eq!(Role::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(Role::parse("robot"), None);
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(ProviderError::RateLimited(1).is_retryable());
+        assert!(ProviderError::Api("502 Bad Gateway: upstream".to_string()).is_retryable());
+        assert!(!ProviderError::Api("404 Not Found: gone".to_string()).is_retryable());
+        assert!(!ProviderError::Api("No organizations found".to_string()).is_retryable());
+        assert!(!ProviderError::AuthFailed("Invalid API key".to_string()).is_retryable());
+        assert!(!ProviderError::Parse("bad json".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn test_http_status() {
+        let forbidden = ProviderError::Api("403 Forbidden: GET /organizations failed".to_string());
+        assert_eq!(forbidden.http_status(), Some(403));
+        assert_eq!(
+            ProviderError::Api("No organizations found".to_string()).http_status(),
+            None
+        );
+        assert_eq!(ProviderError::AuthRequired.http_status(), None);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_recovers_from_server_errors() {
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let result = with_retry(&fast_policy(), || async {
+            if calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                Err(ProviderError::Api("503 Service Unavailable: ".to_string()))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_gives_up_after_max_attempts() {
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let result: Result<()> = with_retry(&fast_policy(), || async {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(ProviderError::RateLimited(0))
+        })
+        .await;
+
+        assert!(matches!(result, Err(ProviderError::RateLimited(0))));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::default();
+        for attempt in 1..8 {
+            let full = RetryPolicy {
+                jitter: false,
+                ..policy.clone()
+            }
+            .backoff(attempt);
+            let jittered = policy.backoff(attempt);
+            assert!(jittered >= full / 2 && jittered <= full, "{:?}", jittered);
+        }
+        assert_eq!(
+            RetryPolicy {
+                jitter: false,
+                ..policy
+            }
+            .backoff(10),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_pauses_every_request_sharing_the_policy() {
+        let policy = fast_policy();
+        let limited = std::sync::atomic::AtomicBool::new(false);
+        let start = Instant::now();
+
+        // One request is told to back off for 30s; the other starts later
+        // but must not overtake the pause
+        let first = with_retry(&policy, || async {
+            if !limited.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                Err(ProviderError::RateLimited(30))
+            } else {
+                Ok(Instant::now())
+            }
+        });
+        let second = async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            with_retry(&policy, || async { Ok::<_, ProviderError>(Instant::now()) }).await
+        };
+        let (first, second) = tokio::join!(first, second);
+
+        assert!(first.unwrap() - start >= Duration::from_secs(30));
+        assert!(second.unwrap() - start >= Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_propagates_non_retryable_errors() {
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let result: Result<()> = with_retry(&fast_policy(), || async {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(ProviderError::TokenExpired)
+        })
+        .await;
+
+        assert!(matches!(result, Err(ProviderError::TokenExpired)));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_message_content_text() {
         let content = MessageContent::Text {
@@ -0,0 +1,666 @@
+//! Otter.ai provider implementation
+//!
+//! Syncs meeting transcripts, summaries and recordings from Otter through the
+//! JSON API of the otter.ai web app, signed in with an email and password or
+//! with the cookies of a browser session.
+
+pub mod types;
+
+use crate::credentials::keys::otter::COOKIES as KEYRING_USER_COOKIES;
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::{
+    download, with_retry, Account, Attachment, Conversation, Message, MessageContent, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
+};
+use async_trait::async_trait;
+use reqwest::{header, Client, StatusCode};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use types::*;
+
+const BASE_URL: &str = "https://otter.ai";
+const API_URL: &str = "https://otter.ai/forward/api/v1";
+
+/// Speeches per page of the listing
+const PAGE_SIZE: usize = 45;
+
+/// Otter.ai provider
+pub struct OtterProvider {
+    client: Client,
+    /// `Cookie` header of the signed-in session
+    cookies: Option<String>,
+    credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Id of the signed-in user, which every listing asks for
+    user_id: Mutex<Option<i64>>,
+}
+
+impl OtterProvider {
+    /// Create a new Otter provider, loading credentials from keyring if available
+    pub fn new() -> Self {
+        Self::with_account_credentials(default_store(), None)
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
+        let cookies = credential_store
+            .get_for_account(KEYRING_SERVICE, KEYRING_USER_COOKIES, email)
+            .ok();
+
+        Self {
+            client: build_client(),
+            cookies,
+            credential_store,
+            retry: RetryPolicy::default(),
+            user_id: Mutex::new(None),
+        }
+    }
+
+    /// GET `endpoint` of the API and parse its JSON body, retrying transient failures
+    async fn api_get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        with_retry(&self.retry, || self.api_get_once(endpoint)).await
+    }
+
+    async fn api_get_once<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let cookies = self.cookies.as_ref().ok_or(ProviderError::AuthRequired)?;
+        let url = format!("{}{}", API_URL, endpoint);
+        let resp = self
+            .client
+            .get(&url)
+            .header(header::COOKIE, cookies)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60);
+            return Err(ProviderError::RateLimited(retry_after));
+        }
+        if status == 401 || status == 403 {
+            return Err(ProviderError::TokenExpired);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: GET {} failed: {}",
+                status,
+                url,
+                truncate_body(&body, 500)
+            )));
+        }
+        serde_json::from_str(&body).map_err(|e| {
+            ProviderError::Parse(format!(
+                "Failed to parse response: {}. Body: {}",
+                e,
+                truncate_body(&body, 500)
+            ))
+        })
+    }
+
+    /// Sign in with an email and password, returning the session's cookies
+    async fn login(&self, email: &str, password: &str) -> Result<String> {
+        let resp = self
+            .client
+            .get(format!("{}/login", API_URL))
+            .query(&[("username", email)])
+            .basic_auth(email, Some(password))
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if status == 401 || status == 403 {
+            return Err(ProviderError::AuthFailed(
+                "Otter didn't accept that email and password".to_string(),
+            ));
+        }
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::AuthFailed(format!(
+                "{}: sign-in failed: {}",
+                status,
+                truncate_body(&body, 200)
+            )));
+        }
+
+        session_cookies(resp.headers()).ok_or_else(|| {
+            ProviderError::AuthFailed("Otter signed in without starting a session".to_string())
+        })
+    }
+
+    async fn user(&self) -> Result<ApiUser> {
+        let response: ApiUserResponse = self.api_get("/user").await?;
+        *self.user_id.lock().unwrap() = Some(response.user.id);
+        Ok(response.user)
+    }
+
+    /// Id of the signed-in user, asking the API the first time
+    async fn user_id(&self) -> Result<i64> {
+        let known = *self.user_id.lock().unwrap();
+        match known {
+            Some(id) => Ok(id),
+            None => Ok(self.user().await?.id),
+        }
+    }
+
+    /// Every speech the user owns, most recent first
+    async fn list_speeches(&self) -> Result<Vec<ApiSpeech>> {
+        let user_id = self.user_id().await?;
+        let mut speeches: Vec<ApiSpeech> = Vec::new();
+        let mut cursor: Option<(i64, String)> = None;
+        loop {
+            let page: ApiSpeechPage = self
+                .api_get(&speeches_endpoint(user_id, cursor.as_ref()))
+                .await?;
+            let next = match (page.last_load_ts, page.last_load_speech_id) {
+                (Some(ts), Some(id)) => Some((ts, id)),
+                _ => None,
+            };
+            let empty = page.speeches.is_empty();
+            for speech in page.speeches {
+                if !speeches.iter().any(|s| s.otid == speech.otid) {
+                    speeches.push(speech);
+                }
+            }
+            // Stop on a cursor that doesn't move, too
+            if page.end_of_list || empty || next.is_none() || next == cursor {
+                break;
+            }
+            cursor = next;
+        }
+        Ok(speeches)
+    }
+
+    /// A speech with its transcript, speakers and recording link
+    async fn read_speech(&self, otid: &str) -> Result<ApiSpeech> {
+        let user_id = self.user_id().await?;
+        let response: ApiSpeechResponse = self
+            .api_get(&format!("/speech?userid={}&otid={}", user_id, otid))
+            .await?;
+        Ok(response.speech)
+    }
+
+    /// Fetch a speech with its recording (for sync)
+    pub async fn conversation_with_attachments(
+        &self,
+        id: &str,
+    ) -> Result<(Conversation, Vec<Message>, Vec<Attachment>)> {
+        let speech = self.read_speech(id).await?;
+        let messages = speech_to_messages(&speech);
+        let attachments = messages
+            .first()
+            .and_then(|first| recording_attachment(&speech, &first.id))
+            .into_iter()
+            .collect();
+
+        Ok((convert_speech(&speech), messages, attachments))
+    }
+}
+
+impl Default for OtterProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for OtterProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::otter()
+    }
+
+    async fn is_authenticated(&self) -> bool {
+        self.cookies.is_some()
+    }
+
+    async fn authenticate(&mut self) -> Result<Account> {
+        println!("Sign in with your Otter email and password.");
+        println!("Accounts that sign in with Google or Microsoft can leave the email empty");
+        println!("and paste the Cookie header of a signed-in otter.ai tab instead.");
+        println!();
+
+        let email = prompt("Otter email: ")?;
+        let cookies = if email.is_empty() {
+            let cookies = prompt("Cookie header: ")?;
+            if cookies.is_empty() {
+                return Err(ProviderError::AuthFailed("No cookies provided".to_string()));
+            }
+            cookies
+        } else {
+            let password = prompt("Password: ")?;
+            self.login(&email, &password).await?
+        };
+
+        self.cookies = Some(cookies.clone());
+        let account = match self.account().await {
+            Ok(account) => account,
+            Err(e) => {
+                self.cookies = None;
+                return Err(ProviderError::AuthFailed(format!(
+                    "Otter didn't accept the session: {}",
+                    e
+                )));
+            }
+        };
+
+        // Save under the signed-in account, so other accounts keep theirs
+        if let Err(e) = self.credential_store.set_for_account(
+            KEYRING_SERVICE,
+            KEYRING_USER_COOKIES,
+            &account.email,
+            &cookies,
+        ) {
+            eprintln!("Warning: failed to save cookies: {}", e);
+        }
+        println!("Authentication successful!");
+        Ok(account)
+    }
+
+    async fn account(&self) -> Result<Account> {
+        let user = self.user().await?;
+
+        Ok(Account {
+            id: format!("otter-{}", user.id),
+            provider: ProviderId::otter(),
+            email: user.email,
+            name: user.name,
+            avatar_url: user.avatar_url,
+            workspace_id: None,
+        })
+    }
+
+    async fn conversations(&self) -> Result<Vec<Conversation>> {
+        let speeches = self.list_speeches().await?;
+        Ok(speeches.iter().map(convert_speech).collect())
+    }
+
+    async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
+        let speech = self.read_speech(id).await?;
+        Ok((convert_speech(&speech), speech_to_messages(&speech)))
+    }
+
+    async fn project_conversations(&self, _project_id: &str) -> Result<Vec<Conversation>> {
+        Ok(Vec::new())
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment, path: &Path) -> Result<()> {
+        // The only Otter attachments are recordings, behind signed links or
+        // on otter.ai itself, where they need the session
+        let mut request = self
+            .client
+            .get(&attachment.download_url)
+            .header(header::ACCEPT, "*/*");
+        if attachment.download_url.starts_with(BASE_URL) {
+            let cookies = self.cookies.as_ref().ok_or(ProviderError::AuthRequired)?;
+            request = request.header(header::COOKIE, cookies);
+        }
+        let response = download::resume(request, path).await.send().await?;
+
+        let status = response.status();
+        if status == 401 || status == 403 {
+            return Err(ProviderError::Api(format!(
+                "{}: recording link expired; pull again for a fresh one",
+                status
+            )));
+        }
+        // 416: an earlier attempt already got the whole recording
+        if status == StatusCode::RANGE_NOT_SATISFIABLE {
+            return download::save_response(response, path, attachment.size_bytes).await;
+        }
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: failed to download recording",
+                status
+            )));
+        }
+
+        // Recordings can be long, so stream them to disk
+        download::save_response(response, path, attachment.size_bytes).await
+    }
+}
+
+/// The speech listing after `cursor`, or from the most recent speech
+fn speeches_endpoint(user_id: i64, cursor: Option<&(i64, String)>) -> String {
+    let mut endpoint = format!(
+        "/speeches?userid={}&folder=0&page_size={}&source=owned",
+        user_id, PAGE_SIZE
+    );
+    if let Some((ts, id)) = cursor {
+        endpoint.push_str(&format!("&last_load_ts={}&last_load_speech_id={}", ts, id));
+    }
+    endpoint
+}
+
+/// The `Cookie` header for the session a sign-in response starts
+fn session_cookies(headers: &header::HeaderMap) -> Option<String> {
+    let cookies: Vec<&str> = headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(|v| v.split(';').next())
+        .map(str::trim)
+        .filter(|c| {
+            c.split_once('=')
+                .is_some_and(|(_, value)| !value.is_empty())
+        })
+        .collect();
+    cookies
+        .iter()
+        .any(|c| c.starts_with("sessionid="))
+        .then(|| cookies.join("; "))
+}
+
+fn convert_speech(speech: &ApiSpeech) -> Conversation {
+    let title = speech
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or("Untitled meeting");
+
+    Conversation {
+        id: speech.otid.clone(),
+        provider_id: "otter".to_string(),
+        title: title.to_string(),
+        created_at: speech.created_at(),
+        updated_at: speech.updated_at(),
+        model: None,
+        project_id: None,
+        project_name: None,
+        is_archived: false,
+    }
+}
+
+/// The summary, when Otter wrote one, then a message per transcript stretch
+fn speech_to_messages(speech: &ApiSpeech) -> Vec<Message> {
+    let mut messages: Vec<Message> = summary_message(speech).into_iter().collect();
+    messages.extend(transcripts_to_messages(speech));
+    messages
+}
+
+/// Convert transcript stretches to Messages, labelled with their speaker
+fn transcripts_to_messages(speech: &ApiSpeech) -> Vec<Message> {
+    let otid = &speech.otid;
+    speech
+        .transcripts
+        .iter()
+        .filter(|t| !t.transcript.trim().is_empty())
+        .enumerate()
+        .map(|(idx, transcript)| Message {
+            id: format!("{}-{}", otid, idx),
+            conversation_id: otid.clone(),
+            parent_id: if idx > 0 {
+                Some(format!("{}-{}", otid, idx - 1))
+            } else {
+                None
+            },
+            role: Role::User, // All transcript stretches are speech
+            content: MessageContent::Text {
+                text: transcript.transcript.trim().to_string(),
+            },
+            created_at: None, // Offsets are relative to the recording
+            model: None,
+            speaker: speech.speaker_name(transcript.speaker_id),
+        })
+        .collect()
+}
+
+fn summary_message(speech: &ApiSpeech) -> Option<Message> {
+    let summary = speech.abstract_summary.as_deref()?.trim();
+    if summary.is_empty() {
+        return None;
+    }
+
+    Some(Message {
+        id: format!("{}-summary", speech.otid),
+        conversation_id: speech.otid.clone(),
+        parent_id: None,
+        role: Role::Assistant, // Summary is AI-generated
+        content: MessageContent::Text {
+            text: summary.to_string(),
+        },
+        created_at: Some(speech.created_at()),
+        model: Some("otter-ai".to_string()),
+        speaker: None,
+    })
+}
+
+/// The speech's recording as an attachment on `message_id`
+fn recording_attachment(speech: &ApiSpeech, message_id: &str) -> Option<Attachment> {
+    let url = speech.audio_url.as_deref().filter(|u| !u.is_empty())?;
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    let (extension, mime_type) = if path.ends_with(".m4a") || path.ends_with(".mp4") {
+        ("m4a", "audio/mp4")
+    } else if path.ends_with(".wav") {
+        ("wav", "audio/wav")
+    } else {
+        ("mp3", "audio/mpeg")
+    };
+
+    Some(Attachment {
+        id: format!("{}-recording", speech.otid),
+        message_id: message_id.to_string(),
+        filename: format!("otter-{}.{}", speech.otid, extension),
+        mime_type: mime_type.to_string(),
+        size_bytes: 0,
+        download_url: url.to_string(),
+        width: None,
+        height: None,
+        sha256: None,
+    })
+}
+
+/// Ask for a line on stdin, trimmed
+fn prompt(label: &str) -> Result<String> {
+    use std::io::{self, Write};
+
+    print!("{}", label);
+    io::stdout()
+        .flush()
+        .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+    let mut line = String::new();
+    io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+    Ok(line.trim().to_string())
+}
+
+/// Build HTTP client with browser-like headers
+fn build_client() -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+    headers.insert(header::REFERER, "https://otter.ai/".parse().unwrap());
+
+    Client::builder()
+        .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Safely truncate a string at a char boundary
+fn truncate_body(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Find a valid char boundary
+        let mut end = max_len;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::MockStore;
+
+    fn fixture<T: DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_provider_id() {
+        let provider = OtterProvider::with_account_credentials(Arc::new(MockStore::new()), None);
+        assert_eq!(provider.id(), ProviderId::otter());
+    }
+
+    #[test]
+    fn test_authenticated_with_stored_session() {
+        let store = Arc::new(MockStore::new());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider =
+            OtterProvider::with_account_credentials(store.clone(), Some("ana@example.com"));
+        assert!(!rt.block_on(provider.is_authenticated()));
+
+        store
+            .set_for_account(
+                KEYRING_SERVICE,
+                KEYRING_USER_COOKIES,
+                "ana@example.com",
+                "sessionid=abc",
+            )
+            .unwrap();
+        let provider = OtterProvider::with_account_credentials(store, Some("ana@example.com"));
+        assert!(rt.block_on(provider.is_authenticated()));
+    }
+
+    #[test]
+    fn test_session_cookies_from_sign_in() {
+        let mut headers = header::HeaderMap::new();
+        for cookie in [
+            "csrftoken=tok123; expires=Fri, 17 Oct 2027 10:00:00 GMT; Path=/",
+            "sessionid=sess456; HttpOnly; Path=/; SameSite=Lax",
+            "messages=; expires=Thu, 01 Jan 1970 00:00:00 GMT; Path=/",
+        ] {
+            headers.append(header::SET_COOKIE, cookie.parse().unwrap());
+        }
+        assert_eq!(
+            session_cookies(&headers).as_deref(),
+            Some("csrftoken=tok123; sessionid=sess456")
+        );
+
+        headers.remove(header::SET_COOKIE);
+        headers.append(header::SET_COOKIE, "csrftoken=tok123".parse().unwrap());
+        assert_eq!(session_cookies(&headers), None);
+    }
+
+    #[test]
+    fn test_speeches_endpoint_pages_from_the_last_loaded() {
+        assert_eq!(
+            speeches_endpoint(42, None),
+            "/speeches?userid=42&folder=0&page_size=45&source=owned"
+        );
+        assert_eq!(
+            speeches_endpoint(42, Some(&(1718000000, "abc".to_string()))),
+            "/speeches?userid=42&folder=0&page_size=45&source=owned&last_load_ts=1718000000&last_load_speech_id=abc"
+        );
+    }
+
+    #[test]
+    fn test_convert_speeches() {
+        let page: ApiSpeechPage = fixture("otter-speeches.json");
+        assert!(!page.end_of_list);
+        assert_eq!(page.last_load_ts, Some(1717405200));
+        assert_eq!(page.last_load_speech_id.as_deref(), Some("Qm3xT8vYk2pL"));
+
+        let conversations: Vec<_> = page.speeches.iter().map(convert_speech).collect();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].id, "Zp7rW1nQd4sE");
+        assert_eq!(conversations[0].title, "Roadmap sync");
+        assert_eq!(conversations[0].provider_id, "otter");
+        assert_eq!(
+            conversations[0].created_at.to_rfc3339(),
+            "2024-06-10T14:00:00+00:00"
+        );
+        assert_eq!(
+            conversations[0].updated_at.to_rfc3339(),
+            "2024-06-10T15:12:00+00:00"
+        );
+        assert_eq!(
+            conversations[0].web_url().as_deref(),
+            Some("https://otter.ai/u/Zp7rW1nQd4sE")
+        );
+
+        // Untitled, and never modified after the recording ended
+        assert_eq!(conversations[1].title, "Untitled meeting");
+        assert_eq!(
+            conversations[1].updated_at.to_rfc3339(),
+            "2024-06-03T09:45:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_speech_to_messages() {
+        let speech = fixture::<ApiSpeechResponse>("otter-speech.json").speech;
+        let messages = speech_to_messages(&speech);
+
+        // The summary, then the three stretches with something said
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].id, "Zp7rW1nQd4sE-summary");
+        assert_eq!(messages[0].role, Role::Assistant);
+        assert_eq!(messages[0].model.as_deref(), Some("otter-ai"));
+
+        assert_eq!(messages[1].id, "Zp7rW1nQd4sE-0");
+        assert_eq!(messages[1].parent_id, None);
+        assert_eq!(messages[1].role, Role::User);
+        assert_eq!(messages[1].speaker.as_deref(), Some("Ana Lima"));
+        match &messages[1].content {
+            MessageContent::Text { text } => {
+                assert_eq!(text, "Let's go over the roadmap for Q3.")
+            }
+            _ => panic!("Expected Text content"),
+        }
+        assert_eq!(messages[2].speaker.as_deref(), Some("Ben Okafor"));
+        assert_eq!(messages[2].parent_id.as_deref(), Some("Zp7rW1nQd4sE-0"));
+        // Not told apart from the others yet
+        assert_eq!(messages[3].speaker, None);
+    }
+
+    #[test]
+    fn test_recording_is_attached_to_the_first_message() {
+        let speech = fixture::<ApiSpeechResponse>("otter-speech.json").speech;
+        let attachment = recording_attachment(&speech, "Zp7rW1nQd4sE-summary").unwrap();
+        assert_eq!(attachment.id, "Zp7rW1nQd4sE-recording");
+        assert_eq!(attachment.message_id, "Zp7rW1nQd4sE-summary");
+        assert_eq!(attachment.filename, "otter-Zp7rW1nQd4sE.m4a");
+        assert_eq!(attachment.mime_type, "audio/mp4");
+        assert!(attachment.download_url.starts_with("https://"));
+
+        let without = ApiSpeech {
+            audio_url: None,
+            ..speech
+        };
+        assert!(recording_attachment(&without, "Zp7rW1nQd4sE-summary").is_none());
+    }
+}
@@ -0,0 +1,125 @@
+//! Otter.ai API response types
+//!
+//! These types are based on the `/forward/api/v1` endpoints the otter.ai web
+//! app calls and may need adjustment as the API evolves.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// `user` result
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiUserResponse {
+    pub user: ApiUser,
+}
+
+/// The signed-in user
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiUser {
+    pub id: i64,
+    pub email: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+}
+
+/// One page of `speeches`, most recent first
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiSpeechPage {
+    #[serde(default)]
+    pub speeches: Vec<ApiSpeech>,
+    /// Set on the last page
+    #[serde(default)]
+    pub end_of_list: bool,
+    /// Where the next page starts
+    #[serde(default)]
+    pub last_load_ts: Option<i64>,
+    #[serde(default)]
+    pub last_load_speech_id: Option<String>,
+}
+
+/// `speech` result
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiSpeechResponse {
+    pub speech: ApiSpeech,
+}
+
+/// A recorded or imported conversation
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiSpeech {
+    /// Id in the speech's URL
+    pub otid: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Seconds since the epoch
+    pub created_at: i64,
+    /// Seconds since the epoch
+    #[serde(default)]
+    pub modified_time: Option<i64>,
+    /// Seconds since the epoch
+    #[serde(default)]
+    pub end_time: Option<i64>,
+    /// Only in `speech`
+    #[serde(default)]
+    pub transcripts: Vec<ApiTranscript>,
+    /// Only in `speech`
+    #[serde(default)]
+    pub speakers: Vec<ApiSpeaker>,
+    /// The summary Otter writes once the speech is processed
+    #[serde(default)]
+    pub abstract_summary: Option<String>,
+    /// Signed link to the recording; only in `speech`
+    #[serde(default)]
+    pub audio_url: Option<String>,
+}
+
+impl ApiSpeech {
+    pub fn created_at(&self) -> DateTime<Utc> {
+        timestamp(self.created_at).unwrap_or_default()
+    }
+
+    /// When the speech last changed, falling back to when the recording ended
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.modified_time
+            .or(self.end_time)
+            .and_then(timestamp)
+            .unwrap_or_else(|| self.created_at())
+            .max(self.created_at())
+    }
+
+    /// Name of the speaker Otter labelled `speaker_id`
+    pub fn speaker_name(&self, speaker_id: Option<i64>) -> Option<String> {
+        let speaker_id = speaker_id?;
+        self.speakers
+            .iter()
+            .find(|s| s.id == speaker_id)
+            .map(|s| s.speaker_name.clone())
+            .filter(|name| !name.trim().is_empty())
+    }
+}
+
+/// A stretch of the transcript said by one speaker
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiTranscript {
+    #[serde(default)]
+    pub transcript: String,
+    /// Unset until Otter tells the speakers apart
+    #[serde(default)]
+    pub speaker_id: Option<i64>,
+    /// Milliseconds into the recording
+    #[serde(default)]
+    pub start_offset: Option<i64>,
+}
+
+/// A speaker of a speech, named by Otter or the user
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiSpeaker {
+    pub id: i64,
+    #[serde(default)]
+    pub speaker_name: String,
+}
+
+/// Seconds since the epoch as a time
+pub fn timestamp(seconds: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(seconds, 0)
+}
@@ -0,0 +1,754 @@
+//! Poe provider implementation
+//!
+//! Syncs chats from poe.com through the persisted GraphQL queries of the web
+//! app, signed in with the `p-b` session cookie. Every chat is held with one
+//! bot, which becomes the conversation's model and project.
+
+pub mod types;
+
+use crate::credentials::keys::poe::{FORMKEY as KEYRING_USER_FORMKEY, PB as KEYRING_USER_PB};
+use crate::credentials::{default_store, CredentialStore, SERVICE as KEYRING_SERVICE};
+use crate::providers::{
+    with_retry, Account, Attachment, Conversation, Message, MessageContent, Project, Provider,
+    ProviderError, ProviderId, Result, RetryPolicy, Role,
+};
+use async_trait::async_trait;
+use reqwest::{header, Client};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use types::*;
+
+const BASE_URL: &str = "https://poe.com";
+const GRAPHQL_URL: &str = "https://poe.com/api/gql_POST";
+
+/// Persisted query names and hashes of the web app; Poe changes them now
+/// and then, and stale ones fail with `PersistedQueryNotFound`
+const VIEWER_QUERY: (&str, &str) = (
+    "settingsPageQuery",
+    "5b0e1e8c1d7a4f27b8f2d1c3e9a6b4f0c2d8e7a1b3f5c9d0e4a6b8c2d1f3e5a7",
+);
+const CHATS_QUERY: (&str, &str) = (
+    "ChatsHistoryPageQuery",
+    "b3ea7d28c5a6f0e1d9b4c8a2e7f3d6b1a0c5e9f2d8b7a4c3e6f1d0b9a8c7e2f4",
+);
+const CHAT_QUERY: (&str, &str) = (
+    "ChatPageQuery",
+    "9f1c4e7b2a8d5f3e0c6b9a1d4e7f2c8b5a3d0e6f9c2b8a5d1e4f7c0b3a6d9e2f",
+);
+const MESSAGES_QUERY: (&str, &str) = (
+    "ChatPaginationQuery",
+    "e2a5d8c1b4f7e0a3d6c9b2f5e8a1d4c7b0f3e6a9d2c5b8f1e4a7d0c3b6f9e2a5",
+);
+
+/// Chats or messages asked for per page
+const PAGE_SIZE: usize = 50;
+
+/// Poe provider
+pub struct PoeProvider {
+    client: Client,
+    /// The `p-b` session cookie
+    session: Option<String>,
+    /// `poe-formkey` header the web app sends with its queries, if known
+    formkey: Option<String>,
+    credential_store: Arc<dyn CredentialStore>,
+    /// Shared by every request, so a 429 pauses them all
+    retry: RetryPolicy,
+    /// Chats from the last listing, by chat code
+    listed: Mutex<HashMap<String, ApiChat>>,
+}
+
+impl PoeProvider {
+    /// Create a new Poe provider, loading credentials from keyring if available
+    pub fn new() -> Self {
+        Self::with_account_credentials(default_store(), None)
+    }
+
+    /// Create for the account signed in as `email`, using its own credentials
+    pub fn for_account(email: &str) -> Self {
+        Self::with_account_credentials(default_store(), Some(email))
+    }
+
+    /// Create with a custom credential store, loading `email`'s credentials
+    pub fn with_account_credentials(
+        credential_store: Arc<dyn CredentialStore>,
+        email: Option<&str>,
+    ) -> Self {
+        let load = |user: &str| {
+            credential_store
+                .get_for_account(KEYRING_SERVICE, user, email)
+                .ok()
+        };
+        let session = load(KEYRING_USER_PB);
+        let formkey = load(KEYRING_USER_FORMKEY);
+
+        Self {
+            client: build_client(),
+            session,
+            formkey,
+            credential_store,
+            retry: RetryPolicy::default(),
+            listed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run a persisted GraphQL `query` with `variables`, retrying transient failures
+    async fn graphql<T: DeserializeOwned>(
+        &self,
+        query: (&str, &str),
+        variables: Value,
+    ) -> Result<T> {
+        let response: GraphqlResponse<T> =
+            with_retry(&self.retry, || self.graphql_once(query, &variables)).await?;
+        graphql_data(response, query.0)
+    }
+
+    async fn graphql_once<T: DeserializeOwned>(
+        &self,
+        query: (&str, &str),
+        variables: &Value,
+    ) -> Result<T> {
+        let session = self.session.as_ref().ok_or(ProviderError::AuthRequired)?;
+        let (name, hash) = query;
+        let mut request = self
+            .client
+            .post(GRAPHQL_URL)
+            .header(header::COOKIE, format!("p-b={}", session))
+            .json(&json!({
+                "queryName": name,
+                "variables": variables,
+                "extensions": { "hash": hash },
+            }));
+        if let Some(formkey) = &self.formkey {
+            request = request.header("poe-formkey", formkey);
+        }
+        let resp = request.send().await?;
+
+        let status = resp.status();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60);
+            return Err(ProviderError::RateLimited(retry_after));
+        }
+        if status == 401 || status == 403 {
+            return Err(ProviderError::TokenExpired);
+        }
+
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::Api(format!(
+                "{}: {} failed: {}",
+                status,
+                name,
+                truncate_body(&body, 500)
+            )));
+        }
+        serde_json::from_str(&body).map_err(|e| {
+            ProviderError::Parse(format!(
+                "Failed to parse response: {}. Body: {}",
+                e,
+                truncate_body(&body, 500)
+            ))
+        })
+    }
+
+    /// Every chat, most recently active first
+    async fn list_chats(&self) -> Result<Vec<ApiChat>> {
+        let mut chats = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let data: ChatsData = self
+                .graphql(CHATS_QUERY, json!({ "count": PAGE_SIZE, "cursor": cursor }))
+                .await?;
+            let page = data.chats;
+            let empty = page.edges.is_empty();
+            chats.extend(page.edges.into_iter().map(|edge| edge.node));
+            match page.page_info.end_cursor {
+                Some(next)
+                    if page.page_info.has_next_page && !empty && cursor.as_ref() != Some(&next) =>
+                {
+                    cursor = Some(next)
+                }
+                _ => return Ok(chats),
+            }
+        }
+    }
+
+    /// A chat with all its messages, oldest first
+    ///
+    /// The chat comes with its latest page of messages; older ones are
+    /// fetched a page at a time, each before the earliest seen so far.
+    async fn read_chat(&self, code: &str) -> Result<(ApiChat, Vec<ApiMessage>)> {
+        let data: ChatPageData = self
+            .graphql(CHAT_QUERY, json!({ "chatCode": code }))
+            .await?;
+        let mut chat = data.chat_of_code;
+        let Some(mut page) = chat.messages_connection.take() else {
+            return Ok((chat, Vec::new()));
+        };
+
+        let mut messages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let empty = page.edges.is_empty();
+            messages.extend(page.edges.into_iter().map(|edge| edge.node));
+            let before = match page.page_info.start_cursor {
+                Some(before)
+                    if page.page_info.has_previous_page
+                        && !empty
+                        && cursor.as_ref() != Some(&before) =>
+                {
+                    before
+                }
+                _ => break,
+            };
+            let data: ChatMessagesData = self
+                .graphql(
+                    MESSAGES_QUERY,
+                    json!({ "id": chat.id, "before": before, "count": PAGE_SIZE }),
+                )
+                .await?;
+            page = data.node.messages_connection;
+            cursor = Some(before);
+        }
+
+        Ok((chat, sorted_messages(messages)))
+    }
+
+    /// The listed chats, listing them first if that hasn't happened yet
+    async fn listed_chats(&self) -> Result<Vec<ApiChat>> {
+        let listed: Vec<ApiChat> = self.listed.lock().unwrap().values().cloned().collect();
+        if !listed.is_empty() {
+            return Ok(listed);
+        }
+        let chats = self.list_chats().await?;
+        let mut listed = self.listed.lock().unwrap();
+        listed.extend(chats.iter().map(|c| (c.chat_code.clone(), c.clone())));
+        Ok(chats)
+    }
+}
+
+impl Default for PoeProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for PoeProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::poe()
+    }
+
+    async fn is_authenticated(&self) -> bool {
+        self.session.is_some()
+    }
+
+    async fn authenticate(&mut self) -> Result<Account> {
+        // Browser-based authentication flow
+        use chromiumoxide::browser::{Browser, BrowserConfig};
+        use futures::StreamExt;
+
+        println!("Opening browser for Poe authentication...");
+        println!("Please sign in to your Poe account.");
+
+        // Set up user data dir to persist session
+        let user_data_dir = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("quaid")
+            .join("poe-chrome-profile");
+        std::fs::create_dir_all(&user_data_dir).ok();
+
+        let mut builder = BrowserConfig::builder()
+            .with_head()
+            .user_data_dir(&user_data_dir)
+            .arg("--disable-blink-features=AutomationControlled")
+            .arg("--disable-infobars")
+            .arg("--no-first-run")
+            .window_size(1280, 900);
+
+        // Try to find Chrome on the system
+        if let Some(chrome_path) = find_chrome() {
+            builder = builder.chrome_executable(chrome_path);
+        }
+
+        let config = builder
+            .build()
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let (browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                if event.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let page = browser
+            .new_page(format!("{}/login", BASE_URL))
+            .await
+            .map_err(|e| ProviderError::AuthFailed(e.to_string()))?;
+
+        // Poe sets `p-b` before sign-in too, so the session counts once the
+        // browser has left the login page and the viewer query knows the user
+        println!("Waiting for login... (this window will close automatically)");
+
+        let account = loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+            let url = page.url().await.ok().flatten().unwrap_or_default();
+            if !url.starts_with(BASE_URL) || url.contains("/login") {
+                continue;
+            }
+            let found = page.get_cookies().await.unwrap_or_default();
+            let Some(session) = found
+                .iter()
+                .find(|c| c.name == "p-b" && c.domain.ends_with("poe.com"))
+                .map(|c| c.value.clone())
+            else {
+                continue;
+            };
+            self.formkey = page
+                .evaluate("document.querySelector('meta[name=\"poe-formkey\"]')?.content ?? null")
+                .await
+                .ok()
+                .and_then(|result| result.into_value::<Option<String>>().ok().flatten());
+
+            self.session = Some(session);
+            match self.account().await {
+                Ok(account) => {
+                    println!("Login detected!");
+                    break account;
+                }
+                Err(_) => self.session = None,
+            }
+        };
+
+        // Close browser
+        drop(browser);
+        handle.abort();
+
+        // Save under the signed-in account, so other accounts keep theirs
+        let credentials = [
+            (KEYRING_USER_PB, &self.session),
+            (KEYRING_USER_FORMKEY, &self.formkey),
+        ];
+        for (user, value) in credentials {
+            let Some(value) = value else { continue };
+            if let Err(e) =
+                self.credential_store
+                    .set_for_account(KEYRING_SERVICE, user, &account.email, value)
+            {
+                eprintln!("Warning: failed to save {}: {}", user, e);
+            }
+        }
+        println!("Authentication successful!");
+        Ok(account)
+    }
+
+    async fn account(&self) -> Result<Account> {
+        let data: ViewerData = self.graphql(VIEWER_QUERY, json!({})).await?;
+        let viewer = data.viewer;
+        let id = viewer.uid.to_string();
+        let email = viewer
+            .primary_email
+            .filter(|e| !e.is_empty())
+            .or(viewer.primary_phone_number)
+            .unwrap_or_else(|| id.clone());
+
+        Ok(Account {
+            id,
+            provider: ProviderId::poe(),
+            email,
+            name: viewer.poe_user.and_then(|u| u.full_name),
+            avatar_url: None,
+            workspace_id: None,
+        })
+    }
+
+    async fn conversations(&self) -> Result<Vec<Conversation>> {
+        let chats = self.list_chats().await?;
+        let conversations = chats.iter().map(|c| convert_chat(c, &[])).collect();
+
+        let mut listed = self.listed.lock().unwrap();
+        listed.extend(chats.into_iter().map(|c| (c.chat_code.clone(), c)));
+        Ok(conversations)
+    }
+
+    async fn conversation(&self, id: &str) -> Result<(Conversation, Vec<Message>)> {
+        let (chat, messages) = self.read_chat(id).await?;
+
+        Ok((
+            convert_chat(&chat, &messages),
+            convert_messages(id, &messages),
+        ))
+    }
+
+    /// One project per bot the listed chats were held with
+    async fn projects(&self) -> Result<Vec<Project>> {
+        Ok(bot_projects(&self.listed_chats().await?))
+    }
+
+    async fn project_conversations(&self, project_id: &str) -> Result<Vec<Conversation>> {
+        Ok(self
+            .listed_chats()
+            .await?
+            .iter()
+            .filter(|c| {
+                c.default_bot_object
+                    .as_ref()
+                    .is_some_and(|bot| bot.handle == project_id)
+            })
+            .map(|c| convert_chat(c, &[]))
+            .collect())
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment, _path: &Path) -> Result<()> {
+        Err(ProviderError::Api(format!(
+            "Poe attachments can't be downloaded yet ({})",
+            attachment.filename
+        )))
+    }
+}
+
+/// The data of a GraphQL response, or its errors when it has none
+fn graphql_data<T>(response: GraphqlResponse<T>, operation: &str) -> Result<T> {
+    response.data.ok_or_else(|| {
+        let messages: Vec<_> = response.errors.into_iter().map(|e| e.message).collect();
+        ProviderError::Api(format!("{} failed: {}", operation, messages.join("; ")))
+    })
+}
+
+/// `messages` oldest first, each once; pages can overlap at their cursors
+fn sorted_messages(messages: Vec<ApiMessage>) -> Vec<ApiMessage> {
+    let mut seen = HashSet::new();
+    let mut messages: Vec<_> = messages
+        .into_iter()
+        .filter(|m| seen.insert(m.message_id))
+        .collect();
+    messages.sort_by_key(|m| (m.creation_time, m.message_id));
+    messages
+}
+
+/// The bots of `chats` as projects, by name
+fn bot_projects(chats: &[ApiChat]) -> Vec<Project> {
+    let bots: BTreeMap<&str, &ApiBot> = chats
+        .iter()
+        .filter_map(|c| c.default_bot_object.as_ref())
+        .map(|bot| (bot.handle.as_str(), bot))
+        .collect();
+    let mut projects: Vec<Project> = bots
+        .into_values()
+        .map(|bot| Project {
+            id: bot.handle.clone(),
+            provider_id: "poe".to_string(),
+            name: bot.name().to_string(),
+            description: None,
+            created_at: None,
+        })
+        .collect();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+    projects
+}
+
+/// A chat, filed under its bot and dated by its messages when they're known
+///
+/// Untitled chats are named after their first prompt.
+fn convert_chat(chat: &ApiChat, messages: &[ApiMessage]) -> Conversation {
+    let dates = || messages.iter().filter_map(ApiMessage::created_at);
+    let active_at = chat
+        .last_interaction_time
+        .and_then(chrono::DateTime::from_timestamp_micros);
+    let created_at = dates().min().or(active_at).unwrap_or_default();
+    let updated_at = active_at
+        .into_iter()
+        .chain(dates())
+        .max()
+        .unwrap_or(created_at)
+        .max(created_at);
+    let title = match chat.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => messages
+            .iter()
+            .filter(|m| m.author == "human")
+            .find_map(|m| m.text.lines().find(|l| !l.trim().is_empty()))
+            .map(|line| line.chars().take(80).collect::<String>())
+            .unwrap_or_else(|| "Untitled".to_string()),
+    };
+    let bot = chat.default_bot_object.as_ref();
+
+    Conversation {
+        id: chat.chat_code.clone(),
+        provider_id: "poe".to_string(),
+        title,
+        created_at,
+        updated_at,
+        model: bot.map(|b| b.name().to_string()),
+        project_id: bot.map(|b| b.handle.clone()),
+        project_name: bot.map(|b| b.name().to_string()),
+        is_archived: false,
+    }
+}
+
+/// The user's and the bots' messages, each reply attributed to its bot
+///
+/// Context resets (`chat_break`) and empty messages are left out.
+fn convert_messages(conv_id: &str, messages: &[ApiMessage]) -> Vec<Message> {
+    messages
+        .iter()
+        .filter(|m| m.author != "chat_break" && !m.text.trim().is_empty())
+        .map(|msg| {
+            let (role, model) = match msg.author.as_str() {
+                "human" => (Role::User, None),
+                author => (
+                    Role::Assistant,
+                    Some(msg.bot.as_ref().map_or(author, ApiBot::name).to_string()),
+                ),
+            };
+
+            Message {
+                id: msg.message_id.to_string(),
+                conversation_id: conv_id.to_string(),
+                parent_id: None, // Poe keeps a flat list of messages
+                role,
+                content: MessageContent::Text {
+                    text: msg.text.clone(),
+                },
+                created_at: msg.created_at(),
+                model,
+                speaker: None,
+            }
+        })
+        .collect()
+}
+
+/// Build HTTP client with browser-like headers
+fn build_client() -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
+            .parse()
+            .unwrap(),
+    );
+    headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+    headers.insert(header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+    headers.insert(header::ORIGIN, BASE_URL.parse().unwrap());
+    headers.insert(header::REFERER, "https://poe.com/".parse().unwrap());
+
+    Client::builder()
+        .default_headers(headers)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Safely truncate a string at a char boundary
+fn truncate_body(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Find a valid char boundary
+        let mut end = max_len;
+        while end > 0 && !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+/// Find Chrome/Chromium executable on the system
+fn find_chrome() -> Option<std::path::PathBuf> {
+    let candidates = if cfg!(target_os = "macos") {
+        vec![
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+            "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+        ]
+    } else if cfg!(target_os = "linux") {
+        vec![
+            "/usr/bin/google-chrome",
+            "/usr/bin/google-chrome-stable",
+            "/usr/bin/chromium",
+            "/usr/bin/chromium-browser",
+            "/snap/bin/chromium",
+        ]
+    } else {
+        // Windows - chromiumoxide should handle this
+        vec![]
+    };
+
+    for candidate in candidates {
+        let path = std::path::PathBuf::from(candidate);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::MockStore;
+
+    fn fixture<T: DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let response: GraphqlResponse<T> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        graphql_data(response, name).unwrap()
+    }
+
+    /// The chat page and the older page before it, as `read_chat` collects them
+    fn chat_with_messages() -> (ApiChat, Vec<ApiMessage>) {
+        let mut chat = fixture::<ChatPageData>("poe-chat.json").chat_of_code;
+        let latest = chat.messages_connection.take().unwrap();
+        let older = fixture::<ChatMessagesData>("poe-chat-older.json")
+            .node
+            .messages_connection;
+        let messages = latest
+            .edges
+            .into_iter()
+            .chain(older.edges)
+            .map(|edge| edge.node)
+            .collect();
+        (chat, sorted_messages(messages))
+    }
+
+    #[test]
+    fn test_provider_id() {
+        let provider = PoeProvider::with_account_credentials(Arc::new(MockStore::new()), None);
+        assert_eq!(provider.id(), ProviderId::poe());
+    }
+
+    #[test]
+    fn test_authenticated_with_stored_session() {
+        let store = Arc::new(MockStore::new());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider =
+            PoeProvider::with_account_credentials(store.clone(), Some("ana@example.com"));
+        assert!(!rt.block_on(provider.is_authenticated()));
+
+        store
+            .set_for_account(KEYRING_SERVICE, KEYRING_USER_PB, "ana@example.com", "pb")
+            .unwrap();
+        let provider = PoeProvider::with_account_credentials(store, Some("ana@example.com"));
+        assert!(rt.block_on(provider.is_authenticated()));
+    }
+
+    #[test]
+    fn test_chats_are_filed_under_their_bot() {
+        let data: ChatsData = fixture("poe-chats.json");
+        assert!(data.chats.page_info.has_next_page);
+        assert_eq!(data.chats.page_info.end_cursor.as_deref(), Some("3"));
+
+        let chats: Vec<_> = data.chats.edges.into_iter().map(|e| e.node).collect();
+        let conversations: Vec<_> = chats.iter().map(|c| convert_chat(c, &[])).collect();
+        assert_eq!(conversations.len(), 3);
+        assert_eq!(conversations[0].id, "2vx8kq3m1n7p");
+        assert_eq!(conversations[0].title, "Tide tables for Faro");
+        assert_eq!(conversations[0].provider_id, "poe");
+        assert_eq!(conversations[0].model.as_deref(), Some("Claude-3.5-Sonnet"));
+        assert_eq!(
+            conversations[0].project_id.as_deref(),
+            Some("claude_3_igloo")
+        );
+        assert_eq!(
+            conversations[0].project_name.as_deref(),
+            Some("Claude-3.5-Sonnet")
+        );
+        assert_eq!(
+            conversations[0].updated_at.to_rfc3339(),
+            "2024-07-14T16:20:05+00:00"
+        );
+        assert_eq!(
+            conversations[0].web_url().as_deref(),
+            Some("https://poe.com/chat/2vx8kq3m1n7p")
+        );
+
+        // Two chats with GPT-4o make one project
+        let projects = bot_projects(&chats);
+        let names: Vec<_> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Claude-3.5-Sonnet", "GPT-4o"]);
+        assert_eq!(projects[1].id, "gpt4_o");
+    }
+
+    #[test]
+    fn test_message_pages_join_oldest_first() {
+        let (chat, messages) = chat_with_messages();
+        // The overlapping message at the cursor is kept once
+        let ids: Vec<_> = messages.iter().map(|m| m.message_id).collect();
+        assert_eq!(ids, [4101, 4102, 4103, 4104, 4105, 4106]);
+
+        let converted = convert_messages(&chat.chat_code, &messages);
+        // The context reset is left out
+        assert_eq!(converted.len(), 5);
+        assert_eq!(converted[0].role, Role::User);
+        assert_eq!(converted[0].model, None);
+        assert_eq!(converted[1].role, Role::Assistant);
+        assert_eq!(converted[1].model.as_deref(), Some("Claude-3.5-Sonnet"));
+        assert!(matches!(
+            &converted[1].content,
+            MessageContent::Text { text } if text.starts_with("High tide in Faro")
+        ));
+
+        // A bot @-mentioned into the chat answers under its own name
+        assert_eq!(converted[4].model.as_deref(), Some("Web-Search"));
+
+        let conversation = convert_chat(&chat, &messages);
+        assert_eq!(
+            conversation.created_at.to_rfc3339(),
+            "2024-07-14T16:02:00+00:00"
+        );
+        assert_eq!(
+            conversation.updated_at.to_rfc3339(),
+            "2024-07-14T16:20:05+00:00"
+        );
+    }
+
+    #[test]
+    fn test_untitled_chat_named_after_first_prompt() {
+        let (mut chat, messages) = chat_with_messages();
+        chat.title = None;
+        assert_eq!(
+            convert_chat(&chat, &messages).title,
+            "When is high tide in Faro tomorrow?"
+        );
+        assert_eq!(convert_chat(&chat, &[]).title, "Untitled");
+    }
+
+    #[test]
+    fn test_failed_query_reports_its_errors() {
+        let response: GraphqlResponse<ChatsData> = serde_json::from_str(
+            r#"{"data": null, "errors": [{"message": "PersistedQueryNotFound"}]}"#,
+        )
+        .unwrap();
+        match graphql_data(response, "ChatsHistoryPageQuery") {
+            Err(ProviderError::Api(message)) => {
+                assert_eq!(
+                    message,
+                    "ChatsHistoryPageQuery failed: PersistedQueryNotFound"
+                )
+            }
+            other => panic!("Expected an API error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Poe GraphQL response types
+//!
+//! These types are based on the persisted GraphQL queries the poe.com web
+//! app makes and may need adjustment as the API evolves.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// A GraphQL response; `data` is missing when the query failed outright
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphqlResponse<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphqlError {
+    pub message: String,
+}
+
+/// A page of a Relay connection
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiConnection<T> {
+    pub edges: Vec<ApiEdge<T>>,
+    #[serde(default)]
+    pub page_info: ApiPageInfo,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiEdge<T> {
+    pub node: T,
+}
+
+/// Where the neighbouring pages of a connection start
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiPageInfo {
+    #[serde(default)]
+    pub end_cursor: Option<String>,
+    #[serde(default)]
+    pub has_next_page: bool,
+    #[serde(default)]
+    pub start_cursor: Option<String>,
+    #[serde(default)]
+    pub has_previous_page: bool,
+}
+
+/// `settingsPageQuery` result
+#[derive(Debug, Clone, Deserialize)]
+pub struct ViewerData {
+    pub viewer: ApiViewer,
+}
+
+/// The signed-in user
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiViewer {
+    pub uid: i64,
+    #[serde(default)]
+    pub primary_email: Option<String>,
+    /// Set instead of the email for accounts registered by phone
+    #[serde(default)]
+    pub primary_phone_number: Option<String>,
+    #[serde(default)]
+    pub poe_user: Option<ApiPoeUser>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiPoeUser {
+    #[serde(default)]
+    pub full_name: Option<String>,
+}
+
+/// `ChatsHistoryPageQuery` result: chats, most recently active first
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatsData {
+    pub chats: ApiConnection<ApiChat>,
+}
+
+/// `ChatPageQuery` result: a chat with its latest messages
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatPageData {
+    pub chat_of_code: ApiChat,
+}
+
+/// `ChatPaginationQuery` result: the messages before a cursor
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatMessagesData {
+    pub node: ApiChatMessages,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiChatMessages {
+    pub messages_connection: ApiConnection<ApiMessage>,
+}
+
+/// A chat with one bot
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiChat {
+    /// Relay id, used to page through the chat's messages
+    pub id: String,
+    /// Short code in the chat's URL
+    pub chat_code: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Microseconds since the epoch
+    #[serde(default)]
+    pub last_interaction_time: Option<i64>,
+    #[serde(default)]
+    pub default_bot_object: Option<ApiBot>,
+    /// Latest messages; only in `ChatPageQuery`
+    #[serde(default)]
+    pub messages_connection: Option<ApiConnection<ApiMessage>>,
+}
+
+/// A bot, official or user-made
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiBot {
+    pub handle: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+impl ApiBot {
+    pub fn name(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.handle)
+    }
+}
+
+/// A message in a chat
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiMessage {
+    pub message_id: i64,
+    #[serde(default)]
+    pub text: String,
+    /// `human`, `chat_break` for a context reset, or the replying bot's nickname
+    pub author: String,
+    /// The replying bot, which can differ from the chat's when it was @-mentioned
+    #[serde(default)]
+    pub bot: Option<ApiBot>,
+    /// Microseconds since the epoch
+    pub creation_time: i64,
+}
+
+impl ApiMessage {
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_micros(self.creation_time)
+    }
+}
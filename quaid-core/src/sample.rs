@@ -0,0 +1,457 @@
+//! Conversation sampling for fine-tuning datasets
+//!
+//! [`select`] picks conversation ids by a [`SampleStrategy`]; the random
+//! strategies are driven by a seeded generator, so the same seed over the same
+//! archive picks the same conversations. [`write_openai_jsonl`] turns the
+//! selection into OpenAI chat-format JSONL, one `{"messages": [...]}` object
+//! per line.
+
+use crate::providers::{Message, Role};
+use crate::storage::analytics::{ConversationSize, MonthCount};
+use crate::storage::{extract_text_content, Result, Store};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Rough characters per token, matching the chunker's estimate
+const CHARS_PER_TOKEN: usize = 4;
+
+/// How conversations are picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleStrategy {
+    /// Uniformly at random
+    Random,
+    /// At random within each month, in proportion to the month's share
+    StratifiedByMonth,
+    /// The conversations with the most content
+    Longest,
+}
+
+impl SampleStrategy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(Self::Random),
+            "stratified-by-month" => Some(Self::StratifiedByMonth),
+            "longest" => Some(Self::Longest),
+            _ => None,
+        }
+    }
+}
+
+/// What to sample and how to render it
+#[derive(Debug, Clone)]
+pub struct SampleOptions {
+    pub n: usize,
+    pub provider: Option<String>,
+    pub strategy: SampleStrategy,
+    pub seed: u64,
+    /// Keep tool messages instead of dropping them
+    pub include_tools: bool,
+    /// Mask email addresses and secret-looking tokens
+    pub redact: bool,
+}
+
+/// Outcome of [`write_openai_jsonl`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SampleReport {
+    pub written: usize,
+    /// Selected conversations left out because they have no assistant reply
+    pub skipped: usize,
+    pub estimated_tokens: usize,
+}
+
+/// One training example in OpenAI chat format
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatExample {
+    pub messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMessage {
+    pub role: &'static str,
+    pub content: String,
+}
+
+impl ChatExample {
+    pub fn estimated_tokens(&self) -> usize {
+        self.messages
+            .iter()
+            .map(|m| m.content.chars().count().div_ceil(CHARS_PER_TOKEN))
+            .sum()
+    }
+}
+
+/// Pick up to `options.n` conversation ids from the store
+pub fn select(store: &Store, options: &SampleOptions) -> Result<Vec<String>> {
+    let provider = options.provider.as_deref();
+    let sizes = store.conversation_sizes(provider)?;
+    let months = match options.strategy {
+        SampleStrategy::StratifiedByMonth => store.conversations_per_month(provider)?,
+        _ => Vec::new(),
+    };
+    Ok(select_from(
+        &sizes,
+        &months,
+        options.strategy,
+        options.n,
+        options.seed,
+    ))
+}
+
+/// [`select`] over already-loaded sizes and per-month counts
+pub fn select_from(
+    sizes: &[ConversationSize],
+    months: &[MonthCount],
+    strategy: SampleStrategy,
+    n: usize,
+    seed: u64,
+) -> Vec<String> {
+    let mut rng = SplitMix64(seed);
+    match strategy {
+        SampleStrategy::Random => {
+            let mut ids: Vec<&str> = sizes.iter().map(|s| s.id.as_str()).collect();
+            rng.shuffle(&mut ids);
+            ids.into_iter().take(n).map(String::from).collect()
+        }
+        SampleStrategy::Longest => {
+            let mut sorted: Vec<&ConversationSize> = sizes.iter().collect();
+            sorted.sort_by(|a, b| b.content_chars.cmp(&a.content_chars).then(a.id.cmp(&b.id)));
+            sorted.into_iter().take(n).map(|s| s.id.clone()).collect()
+        }
+        SampleStrategy::StratifiedByMonth => {
+            let mut by_month: HashMap<String, Vec<&str>> = HashMap::new();
+            for size in sizes {
+                let month = size.created_at.format("%Y-%m").to_string();
+                by_month.entry(month).or_default().push(&size.id);
+            }
+            let mut ids = Vec::new();
+            for (month, quota) in month_quotas(months, n) {
+                let mut pool = by_month.remove(&month).unwrap_or_default();
+                rng.shuffle(&mut pool);
+                ids.extend(pool.into_iter().take(quota).map(String::from));
+            }
+            ids
+        }
+    }
+}
+
+/// Split `n` across months in proportion to their size (largest remainder)
+fn month_quotas(months: &[MonthCount], n: usize) -> Vec<(String, usize)> {
+    let total: usize = months.iter().map(|m| m.conversations).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    let n = n.min(total);
+
+    let mut quotas: Vec<(String, usize, usize)> = months
+        .iter()
+        .map(|m| {
+            let share = n * m.conversations;
+            (m.month.clone(), share / total, share % total)
+        })
+        .collect();
+
+    let assigned: usize = quotas.iter().map(|(_, quota, _)| quota).sum();
+    let mut order: Vec<usize> = (0..quotas.len()).collect();
+    // Stable sort keeps earlier months first among equal remainders
+    order.sort_by(|&a, &b| quotas[b].2.cmp(&quotas[a].2));
+    for &i in order.iter().take(n - assigned) {
+        quotas[i].1 += 1;
+    }
+
+    quotas
+        .into_iter()
+        .map(|(month, quota, _)| (month, quota))
+        .collect()
+}
+
+/// A conversation as a training example, or `None` if it has no assistant reply
+pub fn to_chat_example(
+    messages: &[Message],
+    include_tools: bool,
+    redact_text: bool,
+) -> Option<ChatExample> {
+    let messages: Vec<ChatMessage> = messages
+        .iter()
+        .filter_map(|message| {
+            let role = match message.role {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+                Role::Tool if include_tools => "tool",
+                Role::Tool => return None,
+            };
+            let text = extract_text_content(&message.content);
+            let text = text.trim();
+            if text.is_empty() {
+                return None;
+            }
+            let content = if redact_text {
+                redact(text)
+            } else {
+                text.to_string()
+            };
+            Some(ChatMessage { role, content })
+        })
+        .collect();
+
+    messages
+        .iter()
+        .any(|m| m.role == "assistant")
+        .then_some(ChatExample { messages })
+}
+
+/// Write the conversations in `ids` as OpenAI chat-format JSONL
+pub fn write_openai_jsonl<W: Write>(
+    store: &Store,
+    ids: &[String],
+    options: &SampleOptions,
+    mut writer: W,
+) -> Result<SampleReport> {
+    let mut report = SampleReport::default();
+    for id in ids {
+        let messages = store.get_messages(id)?;
+        match to_chat_example(&messages, options.include_tools, options.redact) {
+            Some(example) => {
+                serde_json::to_writer(&mut writer, &example)?;
+                writeln!(writer)?;
+                report.written += 1;
+                report.estimated_tokens += example.estimated_tokens();
+            }
+            None => report.skipped += 1,
+        }
+    }
+    writer.flush()?;
+    Ok(report)
+}
+
+/// Mask email addresses and secret-looking tokens (API keys, access tokens)
+pub fn redact(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end_matches(char::is_whitespace);
+        let is_punctuation = |c: char| "()[]{}<>\"'`,;:.!?".contains(c);
+        let core = word.trim_start_matches(is_punctuation);
+        let prefix = &word[..word.len() - core.len()];
+        let core = core.trim_end_matches(is_punctuation);
+        let suffix = &piece[prefix.len() + core.len()..];
+
+        redacted.push_str(prefix);
+        if is_email(core) {
+            redacted.push_str("[email]");
+        } else if is_secret(core) {
+            redacted.push_str("[secret]");
+        } else {
+            redacted.push_str(core);
+        }
+        redacted.push_str(suffix);
+    }
+    redacted
+}
+
+fn is_email(word: &str) -> bool {
+    let Some((local, domain)) = word.split_once('@') else {
+        return false;
+    };
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._%+-".contains(c))
+    };
+    valid(local)
+        && valid(domain)
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+}
+
+fn is_secret(word: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "sk-",
+        "ghp_",
+        "gho_",
+        "github_pat_",
+        "xoxb-",
+        "xoxp-",
+        "AKIA",
+    ];
+    let token_chars = word
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !token_chars {
+        return false;
+    }
+    if word.len() >= 16 && PREFIXES.iter().any(|p| word.starts_with(p)) {
+        return true;
+    }
+    word.len() >= 32
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Small seeded generator, so samples are reproducible across platforms
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Fisher-Yates shuffle
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::MessageContent;
+    use chrono::{TimeZone, Utc};
+
+    /// 30 conversations in January, 10 in February, 0-39 messages long
+    fn sizes() -> Vec<ConversationSize> {
+        (0..40)
+            .map(|i| ConversationSize {
+                id: format!("conv-{:02}", i),
+                provider_id: "chatgpt".to_string(),
+                created_at: Utc
+                    .with_ymd_and_hms(2024, if i < 30 { 1 } else { 2 }, 1 + i % 28, 0, 0, 0)
+                    .unwrap(),
+                messages: i as usize,
+                content_chars: (i as usize * 37) % 101,
+            })
+            .collect()
+    }
+
+    fn months() -> Vec<MonthCount> {
+        vec![
+            MonthCount {
+                month: "2024-01".to_string(),
+                conversations: 30,
+            },
+            MonthCount {
+                month: "2024-02".to_string(),
+                conversations: 10,
+            },
+        ]
+    }
+
+    fn message(role: Role, text: &str) -> Message {
+        Message {
+            id: format!("msg-{}", text.len()),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn test_random_is_deterministic_for_a_seed() {
+        let sizes = sizes();
+        let first = select_from(&sizes, &[], SampleStrategy::Random, 10, 42);
+        let again = select_from(&sizes, &[], SampleStrategy::Random, 10, 42);
+        let other = select_from(&sizes, &[], SampleStrategy::Random, 10, 7);
+
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        let mut unique = first.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 10);
+
+        assert_eq!(
+            select_from(&sizes, &[], SampleStrategy::Random, 100, 42).len(),
+            40
+        );
+    }
+
+    #[test]
+    fn test_stratified_sample_follows_month_shares() {
+        let sizes = sizes();
+        let first = select_from(&sizes, &months(), SampleStrategy::StratifiedByMonth, 8, 42);
+        let again = select_from(&sizes, &months(), SampleStrategy::StratifiedByMonth, 8, 42);
+        assert_eq!(first, again);
+
+        let february = first.iter().filter(|id| id.as_str() >= "conv-30").count();
+        assert_eq!(first.len(), 8);
+        assert_eq!(february, 2);
+
+        // Largest remainder: 3 of 40 split 30/10 is 2.25/0.75, so 2 and 1
+        assert_eq!(
+            month_quotas(&months(), 3),
+            vec![("2024-01".to_string(), 2), ("2024-02".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_longest_ignores_seed() {
+        let sizes = sizes();
+        let longest = select_from(&sizes, &[], SampleStrategy::Longest, 3, 1);
+        assert_eq!(
+            longest,
+            select_from(&sizes, &[], SampleStrategy::Longest, 3, 2)
+        );
+        let max = sizes.iter().map(|s| s.content_chars).max().unwrap();
+        let top = sizes.iter().find(|s| s.id == longest[0]).unwrap();
+        assert_eq!(top.content_chars, max);
+    }
+
+    #[test]
+    fn test_chat_example_format() {
+        let messages = vec![
+            message(Role::System, "Be brief."),
+            message(Role::User, "Look up the weather"),
+            message(Role::Tool, "{\"temp\": 21}"),
+            message(Role::Assistant, "It's 21 degrees."),
+            message(Role::User, "   "),
+        ];
+
+        let example = to_chat_example(&messages, false, false).unwrap();
+        let line = serde_json::to_string(&example).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+        let roles: Vec<&str> = parsed["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["system", "user", "assistant"]);
+        assert_eq!(parsed["messages"][2]["content"], "It's 21 degrees.");
+
+        let with_tools = to_chat_example(&messages, true, false).unwrap();
+        assert_eq!(with_tools.messages[2].role, "tool");
+        assert_eq!(with_tools.estimated_tokens(), 3 + 5 + 3 + 4);
+
+        assert!(to_chat_example(&messages[..2], false, false).is_none());
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("Mail (jane.doe@example.com) the key sk-abcdef1234567890XYZ, thanks."),
+            "Mail ([email]) the key [secret], thanks."
+        );
+        assert_eq!(
+            redact("token:\n  a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8\n"),
+            "token:\n  [secret]\n"
+        );
+        let untouched = "Use @decorator and rust-analyzer-2024 on localhost.";
+        assert_eq!(redact(untouched), untouched);
+    }
+}
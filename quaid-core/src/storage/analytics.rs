@@ -0,0 +1,163 @@
+//! Aggregate queries over the archive
+//!
+//! Cheap per-conversation and per-month figures computed in SQLite, for
+//! commands that need to reason about the whole archive (sampling, stats)
+//! without loading every message.
+
+use super::{Result, Store};
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+/// Size of one stored conversation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationSize {
+    pub id: String,
+    pub provider_id: String,
+    pub created_at: DateTime<Utc>,
+    pub messages: usize,
+    /// Length of the stored message content, a proxy for how much text it holds
+    pub content_chars: usize,
+}
+
+/// Conversations created in one calendar month (UTC)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonthCount {
+    /// `YYYY-MM`
+    pub month: String,
+    pub conversations: usize,
+}
+
+impl Store {
+    /// Every conversation with its message count and content size, oldest first
+    pub fn conversation_sizes(&self, provider: Option<&str>) -> Result<Vec<ConversationSize>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.provider_id, c.created_at,
+                    COUNT(m.id), COALESCE(SUM(LENGTH(m.content_json)), 0)
+             FROM conversations c
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             WHERE ?1 IS NULL OR c.provider_id = ?1
+             GROUP BY c.id
+             ORDER BY c.created_at, c.id",
+        )?;
+        let sizes = stmt
+            .query_map(params![provider], |row| {
+                let created_at: String = row.get(2)?;
+                Ok(ConversationSize {
+                    id: row.get(0)?,
+                    provider_id: row.get(1)?,
+                    created_at: DateTime::parse_from_rfc3339(&created_at)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or_default(),
+                    messages: row.get::<_, i64>(3)? as usize,
+                    content_chars: row.get::<_, i64>(4)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(sizes)
+    }
+
+    /// Number of conversations created each month, oldest month first
+    pub fn conversations_per_month(&self, provider: Option<&str>) -> Result<Vec<MonthCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT substr(created_at, 1, 7) AS month, COUNT(*)
+             FROM conversations
+             WHERE ?1 IS NULL OR provider_id = ?1
+             GROUP BY month
+             ORDER BY month",
+        )?;
+        let months = stmt
+            .query_map(params![provider], |row| {
+                Ok(MonthCount {
+                    month: row.get(0)?,
+                    conversations: row.get::<_, i64>(1)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(months)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, Message, MessageContent, ProviderId, Role};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_sizes_and_months() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        let dates = [(2024, 1, 5), (2024, 1, 20), (2024, 3, 2)];
+        for (i, (y, m, d)) in dates.into_iter().enumerate() {
+            let created_at = Utc.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap();
+            store
+                .save_conversation(
+                    "user-1",
+                    &Conversation {
+                        id: format!("conv-{}", i),
+                        provider_id: "claude".to_string(),
+                        title: format!("Conversation {}", i),
+                        created_at,
+                        updated_at: created_at,
+                        model: None,
+                        project_id: None,
+                        project_name: None,
+                        is_archived: false,
+                    },
+                )
+                .unwrap();
+            for j in 0..i {
+                store
+                    .save_message(&Message {
+                        id: format!("msg-{}-{}", i, j),
+                        conversation_id: format!("conv-{}", i),
+                        parent_id: None,
+                        role: Role::User,
+                        content: MessageContent::Text {
+                            text: "hello".to_string(),
+                        },
+                        created_at: None,
+                        model: None,
+                        speaker: None,
+                    })
+                    .unwrap();
+            }
+        }
+
+        let sizes = store.conversation_sizes(None).unwrap();
+        assert_eq!(
+            sizes.iter().map(|s| s.messages).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(sizes[0].content_chars, 0);
+        assert!(sizes[2].content_chars > sizes[1].content_chars);
+        assert!(store
+            .conversation_sizes(Some("chatgpt"))
+            .unwrap()
+            .is_empty());
+
+        let months = store.conversations_per_month(Some("claude")).unwrap();
+        assert_eq!(
+            months,
+            vec![
+                MonthCount {
+                    month: "2024-01".to_string(),
+                    conversations: 2
+                },
+                MonthCount {
+                    month: "2024-03".to_string(),
+                    conversations: 1
+                },
+            ]
+        );
+    }
+}
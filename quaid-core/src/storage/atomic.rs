@@ -0,0 +1,93 @@
+//! Atomic file replacement
+//!
+//! Parquet files are written to a temporary sibling and renamed over the
+//! target once complete, so an interrupted write never leaves a truncated file
+//! where a reader (or the next `--new-only` pull) would find it.
+
+use super::Result;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// A file being written in place of `path`
+///
+/// Dropping it without calling [`commit`](Self::commit) removes the
+/// temporary file and leaves any existing file at `path` untouched.
+pub(crate) struct AtomicFile {
+    path: PathBuf,
+    temp_path: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    /// Create the temporary file, returning the guard and a handle to write to
+    pub(crate) fn create(path: &Path) -> Result<(Self, File)> {
+        let temp_path = temp_path(path);
+        let file = File::create(&temp_path)?;
+        Ok((
+            Self {
+                path: path.to_path_buf(),
+                temp_path,
+                committed: false,
+            },
+            file,
+        ))
+    }
+
+    /// Move the finished file into place
+    pub(crate) fn commit(mut self) -> Result<()> {
+        fs::rename(&self.temp_path, &self.path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// `conv.parquet` -> `conv.parquet.tmp`, which `*.parquet` globs don't match
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_commit_replaces_target() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("conv.parquet");
+        fs::write(&path, b"old").unwrap();
+
+        let (target, mut file) = AtomicFile::create(&path).unwrap();
+        file.write_all(b"new").unwrap();
+        drop(file);
+        assert_eq!(fs::read(&path).unwrap(), b"old");
+
+        target.commit().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(!dir.path().join("conv.parquet.tmp").exists());
+    }
+
+    #[test]
+    fn test_drop_without_commit_cleans_up() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("conv.parquet");
+
+        let (target, mut file) = AtomicFile::create(&path).unwrap();
+        file.write_all(b"partial").unwrap();
+        drop(target);
+
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
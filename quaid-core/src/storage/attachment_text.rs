@@ -0,0 +1,240 @@
+//! Full-text index over attachment contents
+//!
+//! Text pulled out of downloaded files by [`crate::extract`] goes into the
+//! `attachment_text` FTS table, next to the attachment and message it came
+//! from. Attachments get a `text_indexed_at` once tried, whether or not any
+//! text came out, so `quaid index attachments` only looks at new downloads.
+
+use super::attachments::attachment_from_row;
+use super::{push_search_filters, AttachmentFile, Result, SearchFilters, SearchHit, Store};
+use rusqlite::params;
+
+pub(super) const CREATE_ATTACHMENT_TEXT: &str =
+    "CREATE VIRTUAL TABLE IF NOT EXISTS attachment_text USING fts5(
+         content,
+         attachment_id UNINDEXED,
+         message_id UNINDEXED
+     );";
+
+impl Store {
+    /// Downloaded attachments that haven't been through text extraction
+    pub fn attachments_without_text(&self) -> Result<Vec<AttachmentFile>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, message_id, filename, mime_type, size_bytes, download_url, width, height,
+                    sha256, local_path
+             FROM attachments
+             WHERE local_path IS NOT NULL AND text_indexed_at IS NULL
+             ORDER BY id",
+        )?;
+        let files = stmt
+            .query_map([], |row| {
+                Ok(AttachmentFile {
+                    attachment: attachment_from_row(row)?,
+                    local_path: row.get(9)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(files)
+    }
+
+    /// Index the text extracted from attachment `id`, or record that it had none
+    pub fn save_attachment_text(&self, id: &str, text: Option<&str>) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM attachment_text WHERE attachment_id = ?1",
+            params![id],
+        )?;
+        if let Some(text) = text {
+            tx.execute(
+                "INSERT INTO attachment_text (content, attachment_id, message_id)
+                 SELECT ?1, id, message_id FROM attachments WHERE id = ?2",
+                params![text, id],
+            )?;
+        }
+        tx.execute(
+            "UPDATE attachments SET text_indexed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Attachments matching `query`, best first, as hits on their conversation
+    ///
+    /// Snippets start with the attachment's filename, which is also kept in
+    /// [`SearchHit::attachment`].
+    pub(super) fn search_attachment_text(
+        &self,
+        query: &str,
+        limit: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        let mut sql = String::from(
+            r#"
+            SELECT m.conversation_id, c.title, c.provider_id, c.updated_at,
+                   snippet(attachment_text, 0, '<mark>', '</mark>', '...', 32) as snippet,
+                   rank, a.filename
+            FROM attachment_text
+            JOIN attachments a ON a.id = attachment_text.attachment_id
+            JOIN messages m ON m.id = attachment_text.message_id
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE attachment_text MATCH ?
+            "#,
+        );
+        let mut values: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(query.to_string())];
+        push_search_filters(filters, &mut sql, &mut values);
+        sql.push_str(" ORDER BY rank LIMIT ?");
+        values.push(Box::new(limit as i64));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let hits = stmt
+            .query_map(
+                rusqlite::params_from_iter(values.iter().map(|v| v.as_ref())),
+                |row| {
+                    let filename: String = row.get(6)?;
+                    Ok(SearchHit {
+                        conversation_id: row.get(0)?,
+                        title: row.get(1)?,
+                        provider_id: row.get(2)?,
+                        updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                            .map(|dt| dt.with_timezone(&chrono::Utc))
+                            .unwrap_or_else(|_| chrono::Utc::now()),
+                        snippet: format!("[{}] {}", filename, row.get::<_, String>(4)?),
+                        rank: row.get(5)?,
+                        attachment: Some(filename),
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{extract, Extraction};
+    use crate::providers::{
+        Account, Attachment, Conversation, Message, MessageContent, ProviderId, Role,
+    };
+    use std::path::Path;
+
+    fn setup() -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        let conv = Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "claude".to_string(),
+            title: "Cluster move".to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        store.save_conversation("user-1", &conv).unwrap();
+        store
+            .save_message(&Message {
+                id: "msg-1".to_string(),
+                conversation_id: "conv-1".to_string(),
+                parent_id: None,
+                role: Role::User,
+                content: MessageContent::Text {
+                    text: "Here is the plan".to_string(),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            })
+            .unwrap();
+        store
+    }
+
+    fn downloaded(store: &Store, id: &str, filename: &str, mime_type: &str) {
+        store
+            .save_attachment(&Attachment {
+                id: id.to_string(),
+                message_id: "msg-1".to_string(),
+                filename: filename.to_string(),
+                mime_type: mime_type.to_string(),
+                size_bytes: 1,
+                download_url: id.to_string(),
+                width: None,
+                height: None,
+                sha256: None,
+            })
+            .unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(filename);
+        store
+            .mark_attachment_downloaded(id, &path.to_string_lossy())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_attachment_hits_are_opt_in_and_marked() {
+        let store = setup();
+        downloaded(&store, "att-pdf", "migration-plan.pdf", "application/pdf");
+        downloaded(&store, "att-txt", "deploy-notes.txt", "text/plain");
+
+        let pending = store.attachments_without_text().unwrap();
+        assert_eq!(pending.len(), 2);
+        for file in pending {
+            let path = file.local_path.unwrap();
+            let text = match extract(
+                Path::new(&path),
+                &file.attachment.mime_type,
+                &file.attachment.filename,
+            )
+            .unwrap()
+            {
+                Extraction::Text(text) => Some(text),
+                Extraction::Skipped(_) => None,
+            };
+            store
+                .save_attachment_text(&file.attachment.id, text.as_deref())
+                .unwrap();
+        }
+        assert!(store.attachments_without_text().unwrap().is_empty());
+
+        let messages_only = SearchFilters::default();
+        assert!(store
+            .search("ingress", 10, &messages_only)
+            .unwrap()
+            .is_empty());
+
+        let with_attachments = SearchFilters {
+            include_attachments: true,
+            ..Default::default()
+        };
+        let hits = store.search("ingress", 10, &with_attachments).unwrap();
+        assert_eq!(hits.len(), 2);
+        let mut files: Vec<_> = hits.iter().filter_map(|h| h.attachment.clone()).collect();
+        files.sort();
+        assert_eq!(files, vec!["deploy-notes.txt", "migration-plan.pdf"]);
+        assert!(hits.iter().all(|h| h.conversation_id == "conv-1"));
+        assert!(hits
+            .iter()
+            .any(|h| h.snippet.starts_with("[migration-plan.pdf] ")));
+
+        // Messages and attachments share the limit
+        let plan = store.search("plan", 1, &with_attachments).unwrap();
+        assert_eq!(plan.len(), 1);
+
+        // Re-indexing replaces the old text
+        store.save_attachment_text("att-txt", None).unwrap();
+        let hits = store.search("ingress", 10, &with_attachments).unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+}
@@ -0,0 +1,749 @@
+//! Deduplication of downloaded attachment content
+//!
+//! Providers often attach the same file to many messages (Claude re-sends
+//! project knowledge files on every conversation). Downloads are hashed, and a
+//! row whose content is already on disk points at the existing file through
+//! `duplicate_of` instead of keeping its own copy.
+//!
+//! Rows also keep the provider and account of their conversation, so pending
+//! downloads go through the account that can fetch them, and `quaid
+//! attachments` can list and check them without walking messages.
+
+use super::{Result, Store};
+use crate::providers::Attachment;
+use rusqlite::{params, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// An attachment whose content is already on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadedCopy {
+    pub id: String,
+    pub local_path: String,
+    pub sha256: Option<String>,
+}
+
+/// What recording a download did with the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachmentDownload {
+    /// First copy of this content; the file was kept
+    Stored { sha256: String },
+    /// Same content as an earlier attachment; the file now points at its copy
+    Duplicate { of: String, sha256: String },
+}
+
+/// An attachment and where its content is, once downloaded
+#[derive(Debug, Clone)]
+pub struct AttachmentFile {
+    pub attachment: Attachment,
+    /// `None` until downloaded; duplicates share their original's path
+    pub local_path: Option<String>,
+}
+
+/// Where an attachment's download stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentState {
+    Pending,
+    Downloaded,
+    /// Shares the file of an identical attachment
+    Duplicate,
+    /// Given up on until `quaid attachments retry`
+    Failed,
+}
+
+impl AttachmentState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Downloaded => "downloaded",
+            Self::Duplicate => "duplicate",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// An attachment with its owner and download state
+#[derive(Debug, Clone)]
+pub struct AttachmentEntry {
+    pub file: AttachmentFile,
+    /// `None` for attachments whose message was never saved
+    pub provider_id: Option<String>,
+    pub account_id: Option<String>,
+    pub state: AttachmentState,
+    /// Why the download was given up on, or the last transient error
+    pub error: Option<String>,
+}
+
+/// Which attachments [`Store::list_attachments`] returns; all by default
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentFilter {
+    /// Only ones still to download, leaving out failed ones
+    pub pending: bool,
+    pub provider: Option<String>,
+    /// A MIME type like `image/png`, or a whole type like `image` or `image/*`
+    pub mime: Option<String>,
+}
+
+/// Hex sha256 of a file's content
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// An attachment from `id, message_id, filename, mime_type, size_bytes,
+/// download_url, width, height, sha256`
+pub(super) fn attachment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        filename: row.get(2)?,
+        mime_type: row.get(3)?,
+        size_bytes: row.get::<_, i64>(4)? as u64,
+        download_url: row.get(5)?,
+        width: row.get(6)?,
+        height: row.get(7)?,
+        sha256: row.get(8)?,
+    })
+}
+
+impl Store {
+    /// A downloaded attachment with the same provider file id and size, if any
+    ///
+    /// When one exists the download can be skipped and the row marked as a
+    /// duplicate with [`Store::mark_attachment_duplicate`].
+    pub fn find_downloaded_copy(&self, attachment: &Attachment) -> Result<Option<DownloadedCopy>> {
+        let copy = self
+            .conn
+            .query_row(
+                "SELECT id, local_path, sha256 FROM attachments
+                 WHERE download_url = ?1 AND size_bytes = ?2 AND id != ?3
+                   AND local_path IS NOT NULL AND duplicate_of IS NULL
+                 LIMIT 1",
+                params![
+                    attachment.download_url,
+                    attachment.size_bytes as i64,
+                    attachment.id
+                ],
+                |row| {
+                    Ok(DownloadedCopy {
+                        id: row.get(0)?,
+                        local_path: row.get(1)?,
+                        sha256: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(copy.filter(|c| Path::new(&c.local_path).exists()))
+    }
+
+    /// Point an attachment at content another row already downloaded
+    pub fn mark_attachment_duplicate(&self, id: &str, copy: &DownloadedCopy) -> Result<()> {
+        self.conn.execute(
+            "UPDATE attachments
+             SET local_path = ?1, sha256 = ?2, duplicate_of = ?3, downloaded_at = CURRENT_TIMESTAMP
+             WHERE id = ?4",
+            params![copy.local_path, copy.sha256, copy.id, id],
+        )?;
+        Ok(())
+    }
+
+    /// Record a finished download at `path`
+    ///
+    /// The file is hashed; if another attachment already has the same content
+    /// on disk, this file is removed and the row marked as its duplicate.
+    pub fn record_attachment_download(&self, id: &str, path: &Path) -> Result<AttachmentDownload> {
+        let sha256 = sha256_file(path)?;
+        let local_path = path.to_string_lossy().to_string();
+
+        let existing: Option<(String, String)> = self
+            .conn
+            .query_row(
+                "SELECT id, local_path FROM attachments
+                 WHERE sha256 = ?1 AND id != ?2
+                   AND local_path IS NOT NULL AND duplicate_of IS NULL
+                 LIMIT 1",
+                params![sha256, id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        match existing {
+            Some((of, existing_path)) if Path::new(&existing_path).exists() => {
+                if existing_path != local_path {
+                    std::fs::remove_file(path)?;
+                }
+                let copy = DownloadedCopy {
+                    id: of.clone(),
+                    local_path: existing_path,
+                    sha256: Some(sha256.clone()),
+                };
+                self.mark_attachment_duplicate(id, &copy)?;
+                Ok(AttachmentDownload::Duplicate { of, sha256 })
+            }
+            _ => {
+                self.conn.execute(
+                    "UPDATE attachments
+                     SET local_path = ?1, sha256 = ?2, duplicate_of = NULL,
+                         downloaded_at = CURRENT_TIMESTAMP, failed_at = NULL, failure_reason = NULL,
+                         last_error = NULL
+                     WHERE id = ?3",
+                    params![local_path, sha256, id],
+                )?;
+                Ok(AttachmentDownload::Stored { sha256 })
+            }
+        }
+    }
+
+    /// Give up on downloading an attachment, keeping the reason
+    ///
+    /// Failed attachments are left out of the pending lists, so later pulls
+    /// don't retry them until [`Store::retry_failed_attachments`].
+    pub fn mark_attachment_failed(&self, id: &str, reason: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE attachments
+             SET failed_at = CURRENT_TIMESTAMP, failure_reason = ?1,
+                 attempts = attempts + 1, last_error = ?1
+             WHERE id = ?2",
+            params![reason, id],
+        )?;
+        Ok(())
+    }
+
+    /// Count a failed download attempt that leaves the attachment pending
+    pub fn record_attachment_error(&self, id: &str, error: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE attachments SET attempts = attempts + 1, last_error = ?1 WHERE id = ?2",
+            params![error, id],
+        )?;
+        Ok(())
+    }
+
+    /// Download attempts so far and the last error, if any
+    pub fn attachment_attempts(&self, id: &str) -> Result<(u32, Option<String>)> {
+        let attempts = self.conn.query_row(
+            "SELECT attempts, last_error FROM attachments WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(attempts)
+    }
+
+    /// Put attachments given up on back in the pending list, for all
+    /// providers or one; returns how many
+    pub fn retry_failed_attachments(&self, provider_id: Option<&str>) -> Result<usize> {
+        let count = self.conn.execute(
+            "UPDATE attachments SET failed_at = NULL, failure_reason = NULL
+             WHERE failed_at IS NOT NULL AND local_path IS NULL
+               AND (?1 IS NULL OR message_id IN (
+                   SELECT m.id FROM messages m
+                   JOIN conversations c ON c.id = m.conversation_id
+                   WHERE c.provider_id = ?1))",
+            params![provider_id],
+        )?;
+        Ok(count)
+    }
+
+    /// Failure reason of an attachment given up on, if any
+    pub fn attachment_failure(&self, id: &str) -> Result<Option<String>> {
+        let reason = self
+            .conn
+            .query_row(
+                "SELECT failure_reason FROM attachments WHERE id = ?1 AND failed_at IS NOT NULL",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(reason)
+    }
+
+    /// An attachment by id, with its local path if it was downloaded
+    pub fn get_attachment_file(&self, id: &str) -> Result<Option<AttachmentFile>> {
+        let file = self
+            .conn
+            .query_row(
+                "SELECT id, message_id, filename, mime_type, size_bytes, download_url, width, height,
+                        sha256, local_path
+                 FROM attachments WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(AttachmentFile {
+                        attachment: attachment_from_row(row)?,
+                        local_path: row.get(9)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(file)
+    }
+
+    /// Attachments of every message in a conversation, downloaded or not
+    pub fn conversation_attachment_files(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<AttachmentFile>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.message_id, a.filename, a.mime_type, a.size_bytes, a.download_url,
+                    a.width, a.height, a.sha256, a.local_path
+             FROM attachments a
+             JOIN messages m ON m.id = a.message_id
+             WHERE m.conversation_id = ?1
+             ORDER BY m.rowid, a.id",
+        )?;
+        let files = stmt
+            .query_map(params![conversation_id], |row| {
+                Ok(AttachmentFile {
+                    attachment: attachment_from_row(row)?,
+                    local_path: row.get(9)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(files)
+    }
+
+    /// Attachments matching `filter`, in the order they were saved
+    pub fn list_attachments(&self, filter: &AttachmentFilter) -> Result<Vec<AttachmentEntry>> {
+        let mut sql = String::from(
+            "SELECT id, message_id, filename, mime_type, size_bytes, download_url, width, height,
+                    sha256, local_path, provider_id, account_id, duplicate_of IS NOT NULL,
+                    failed_at IS NOT NULL, COALESCE(failure_reason, last_error)
+             FROM attachments WHERE 1=1",
+        );
+        let mut values: Vec<String> = Vec::new();
+        if filter.pending {
+            sql.push_str(" AND local_path IS NULL AND failed_at IS NULL");
+        }
+        if let Some(provider) = &filter.provider {
+            sql.push_str(" AND provider_id = ?");
+            values.push(provider.clone());
+        }
+        if let Some(mime) = &filter.mime {
+            let mime = mime.trim_end_matches('*').trim_end_matches('/');
+            if mime.contains('/') {
+                sql.push_str(" AND mime_type = ? COLLATE NOCASE");
+                values.push(mime.to_string());
+            } else {
+                sql.push_str(" AND mime_type LIKE ?");
+                values.push(format!("{}/%", mime));
+            }
+        }
+        sql.push_str(" ORDER BY rowid");
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let entries = stmt
+            .query_map(rusqlite::params_from_iter(&values), |row| {
+                let local_path: Option<String> = row.get(9)?;
+                let state = if local_path.is_none() {
+                    if row.get(13)? {
+                        AttachmentState::Failed
+                    } else {
+                        AttachmentState::Pending
+                    }
+                } else if row.get(12)? {
+                    AttachmentState::Duplicate
+                } else {
+                    AttachmentState::Downloaded
+                };
+                Ok(AttachmentEntry {
+                    file: AttachmentFile {
+                        attachment: attachment_from_row(row)?,
+                        local_path,
+                    },
+                    provider_id: row.get(10)?,
+                    account_id: row.get(11)?,
+                    state,
+                    error: row.get(14)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// Downloaded attachments whose file is no longer on disk
+    pub fn missing_attachment_files(&self) -> Result<Vec<AttachmentFile>> {
+        Ok(self
+            .list_attachments(&AttachmentFilter::default())?
+            .into_iter()
+            .map(|entry| entry.file)
+            .filter(|file| {
+                file.local_path
+                    .as_deref()
+                    .is_some_and(|path| !Path::new(path).exists())
+            })
+            .collect())
+    }
+
+    /// Check that downloaded attachments are still on disk
+    ///
+    /// Rows whose file is gone go back to pending, so the next download
+    /// fetches them again; text already extracted from them stays searchable
+    /// until then. Returns the attachments put back.
+    pub fn verify_attachment_files(&self) -> Result<Vec<AttachmentFile>> {
+        let missing = self.missing_attachment_files()?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        for file in &missing {
+            tx.execute(
+                "UPDATE attachments
+                 SET local_path = NULL, downloaded_at = NULL, sha256 = NULL, duplicate_of = NULL,
+                     text_indexed_at = NULL
+                 WHERE id = ?1",
+                params![file.attachment.id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(missing)
+    }
+
+    /// Ids of the attachments collapsed onto `id`
+    pub fn attachment_duplicates(&self, id: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM attachments WHERE duplicate_of = ?1 ORDER BY id")?;
+        let ids = stmt
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, Message, MessageContent, ProviderId, Role};
+    use tempfile::tempdir;
+
+    fn setup() -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        for i in 0..2 {
+            let conv = Conversation {
+                id: format!("conv-{}", i),
+                provider_id: "claude".to_string(),
+                title: format!("Conversation {}", i),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+                model: None,
+                project_id: None,
+                project_name: None,
+                is_archived: false,
+            };
+            store.save_conversation("user-1", &conv).unwrap();
+            store
+                .save_message(&Message {
+                    id: format!("msg-{}", i),
+                    conversation_id: conv.id.clone(),
+                    parent_id: None,
+                    role: Role::User,
+                    content: MessageContent::Text {
+                        text: "See the attached spec".to_string(),
+                    },
+                    created_at: None,
+                    model: None,
+                    speaker: None,
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    fn attachment(id: &str, message_id: &str, download_url: &str) -> Attachment {
+        Attachment {
+            id: id.to_string(),
+            message_id: message_id.to_string(),
+            filename: format!("{}-spec.md", id),
+            mime_type: "text/markdown".to_string(),
+            size_bytes: 11,
+            download_url: download_url.to_string(),
+            width: None,
+            height: None,
+            sha256: None,
+        }
+    }
+
+    #[test]
+    fn test_identical_downloads_share_one_file() {
+        let dir = tempdir().unwrap();
+        let store = setup();
+        let first = attachment("att-1", "msg-0", "file-a");
+        let second = attachment("att-2", "msg-1", "file-b");
+        store.save_attachment(&first).unwrap();
+        store.save_attachment(&second).unwrap();
+
+        let first_path = dir.path().join(&first.filename);
+        let second_path = dir.path().join(&second.filename);
+        std::fs::write(&first_path, b"same bytes!").unwrap();
+        std::fs::write(&second_path, b"same bytes!").unwrap();
+
+        let stored = store
+            .record_attachment_download("att-1", &first_path)
+            .unwrap();
+        assert!(matches!(stored, AttachmentDownload::Stored { .. }));
+        let duplicate = store
+            .record_attachment_download("att-2", &second_path)
+            .unwrap();
+        assert!(matches!(duplicate, AttachmentDownload::Duplicate { ref of, .. } if of == "att-1"));
+
+        assert!(first_path.exists());
+        assert!(!second_path.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert_eq!(store.attachment_duplicates("att-1").unwrap(), vec!["att-2"]);
+        let file = store.get_attachment_file("att-2").unwrap().unwrap();
+        assert_eq!(file.attachment.filename, "att-2-spec.md");
+        assert_eq!(
+            file.local_path.as_deref(),
+            Some(first_path.to_string_lossy().as_ref())
+        );
+        assert!(store.get_attachment_file("att-9").unwrap().is_none());
+        let files = store.conversation_attachment_files("conv-1").unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].attachment.id, "att-2");
+
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.attachments, 2);
+        assert_eq!(stats.attachment_files, 1);
+        assert!(store.get_pending_attachments().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_known_file_id_short_circuits_download() {
+        let dir = tempdir().unwrap();
+        let store = setup();
+        let first = attachment("att-1", "msg-0", "file-a");
+        let second = attachment("att-2", "msg-1", "file-a");
+        store.save_attachment(&first).unwrap();
+        store.save_attachment(&second).unwrap();
+
+        assert!(store.find_downloaded_copy(&first).unwrap().is_none());
+        let path = dir.path().join(&first.filename);
+        std::fs::write(&path, b"same bytes!").unwrap();
+        store.record_attachment_download("att-1", &path).unwrap();
+
+        let copy = store.find_downloaded_copy(&second).unwrap().unwrap();
+        assert_eq!(copy.id, "att-1");
+        assert_eq!(copy.sha256, Some(sha256_file(&path).unwrap()));
+
+        store.mark_attachment_duplicate("att-2", &copy).unwrap();
+        assert!(store.get_pending_attachments().unwrap().is_empty());
+        assert_eq!(store.stats().unwrap().attachment_files, 1);
+
+        // A different size is a different file, even under the same id
+        let resized = Attachment {
+            size_bytes: 12,
+            ..second
+        };
+        assert!(store.find_downloaded_copy(&resized).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_failed_attachments_leave_the_queue() {
+        let dir = tempdir().unwrap();
+        let store = setup();
+        store
+            .save_attachment(&attachment("att-1", "msg-0", "file-a"))
+            .unwrap();
+        store
+            .save_attachment(&attachment("att-2", "msg-1", "file-b"))
+            .unwrap();
+
+        store.mark_attachment_failed("att-1", "404: gone").unwrap();
+        let pending = store
+            .get_pending_attachments_for_provider("claude")
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, "att-2");
+        let stats = store.stats().unwrap();
+        assert_eq!(
+            (stats.attachments_pending, stats.attachments_failed),
+            (1, 1)
+        );
+        assert_eq!(
+            store.attachment_failure("att-1").unwrap().as_deref(),
+            Some("404: gone")
+        );
+        assert!(store.attachment_failure("att-2").unwrap().is_none());
+
+        // Transient errors are counted but stay pending
+        store.record_attachment_error("att-2", "timed out").unwrap();
+        store.record_attachment_error("att-2", "503").unwrap();
+        assert_eq!(
+            store.attachment_attempts("att-2").unwrap(),
+            (2, Some("503".to_string()))
+        );
+        assert_eq!(store.get_pending_attachments().unwrap().len(), 1);
+
+        // Retrying puts the failed one back in the queue, keeping its history
+        assert_eq!(store.retry_failed_attachments(Some("chatgpt")).unwrap(), 0);
+        assert_eq!(store.retry_failed_attachments(Some("claude")).unwrap(), 1);
+        assert_eq!(store.get_pending_attachments().unwrap().len(), 2);
+        assert_eq!(
+            store.attachment_attempts("att-1").unwrap(),
+            (1, Some("404: gone".to_string()))
+        );
+        store.mark_attachment_failed("att-1", "404: gone").unwrap();
+
+        // A later successful download clears the failure
+        let path = dir.path().join("att-1-spec.md");
+        std::fs::write(&path, b"late bytes!").unwrap();
+        store.record_attachment_download("att-1", &path).unwrap();
+        assert!(store.attachment_failure("att-1").unwrap().is_none());
+        assert_eq!(store.attachment_attempts("att-1").unwrap(), (2, None));
+        assert_eq!(store.stats().unwrap().attachments_failed, 0);
+    }
+
+    #[test]
+    fn test_list_queue_and_verify_by_account() {
+        let dir = tempdir().unwrap();
+        let store = setup();
+        store
+            .save_account(&Account {
+                id: "user-2".to_string(),
+                provider: ProviderId::claude(),
+                email: "work@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        let mut work = store.get_conversation("conv-1").unwrap().unwrap();
+        work.id = "conv-work".to_string();
+        store.save_conversation("user-2", &work).unwrap();
+        store
+            .save_message(&Message {
+                id: "msg-work".to_string(),
+                conversation_id: "conv-work".to_string(),
+                parent_id: None,
+                role: Role::User,
+                content: MessageContent::Text {
+                    text: "Diagram attached".to_string(),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            })
+            .unwrap();
+
+        store
+            .save_attachment(&attachment("att-1", "msg-0", "file-a"))
+            .unwrap();
+        store
+            .save_attachment(&Attachment {
+                mime_type: "image/png".to_string(),
+                ..attachment("att-2", "msg-work", "file-b")
+            })
+            .unwrap();
+        // Saved before its message, so the owner is filled in once it is known
+        store
+            .save_attachment(&attachment("att-3", "msg-late", "file-c"))
+            .unwrap();
+
+        let all = store
+            .list_attachments(&AttachmentFilter::default())
+            .unwrap();
+        let owners: Vec<_> = all
+            .iter()
+            .map(|e| (e.file.attachment.id.as_str(), e.account_id.as_deref()))
+            .collect();
+        assert_eq!(
+            owners,
+            vec![
+                ("att-1", Some("user-1")),
+                ("att-2", Some("user-2")),
+                ("att-3", None)
+            ]
+        );
+        assert!(all.iter().all(|e| e.state == AttachmentState::Pending));
+        assert_eq!(all[1].provider_id.as_deref(), Some("claude"));
+
+        // Each account only downloads its own
+        let ids = |account: &str| -> Vec<String> {
+            store
+                .get_pending_attachments_for_account(account)
+                .unwrap()
+                .into_iter()
+                .map(|a| a.id)
+                .collect()
+        };
+        assert_eq!(ids("user-1"), vec!["att-1"]);
+        assert_eq!(ids("user-2"), vec!["att-2"]);
+
+        store
+            .save_message(&Message {
+                id: "msg-late".to_string(),
+                conversation_id: "conv-0".to_string(),
+                parent_id: None,
+                role: Role::Assistant,
+                content: MessageContent::Text {
+                    text: "Here it is".to_string(),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            })
+            .unwrap();
+        store
+            .save_attachment(&attachment("att-3", "msg-late", "file-c"))
+            .unwrap();
+        assert_eq!(ids("user-1"), vec!["att-1", "att-3"]);
+
+        let images = AttachmentFilter {
+            mime: Some("image/*".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(store.list_attachments(&images).unwrap().len(), 1);
+        let png = AttachmentFilter {
+            mime: Some("IMAGE/PNG".to_string()),
+            provider: Some("claude".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(store.list_attachments(&png).unwrap().len(), 1);
+        let chatgpt = AttachmentFilter {
+            provider: Some("chatgpt".to_string()),
+            ..Default::default()
+        };
+        assert!(store.list_attachments(&chatgpt).unwrap().is_empty());
+
+        let path = dir.path().join("att-1-spec.md");
+        std::fs::write(&path, b"spec bytes!").unwrap();
+        store.record_attachment_download("att-1", &path).unwrap();
+        store.mark_attachment_failed("att-2", "404: gone").unwrap();
+        let pending = AttachmentFilter {
+            pending: true,
+            ..Default::default()
+        };
+        let pending = store.list_attachments(&pending).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].file.attachment.id, "att-3");
+        let all = store
+            .list_attachments(&AttachmentFilter::default())
+            .unwrap();
+        assert_eq!(all[0].state, AttachmentState::Downloaded);
+        assert_eq!(all[1].state, AttachmentState::Failed);
+        assert_eq!(all[1].error.as_deref(), Some("404: gone"));
+
+        // Files that are still there are left alone
+        assert!(store.verify_attachment_files().unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+        let missing = store.verify_attachment_files().unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].attachment.id, "att-1");
+        assert!(store
+            .get_attachment_file("att-1")
+            .unwrap()
+            .unwrap()
+            .local_path
+            .is_none());
+        assert_eq!(ids("user-1"), vec!["att-1", "att-3"]);
+    }
+}
@@ -3,11 +3,17 @@
 //! Consolidates per-conversation parquet files into a single file per provider
 //! to reduce file handle usage during semantic search.
 
+use super::atomic::AtomicFile;
 use super::{ParquetStorageConfig, Result, StorageError};
-use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use arrow::array::{Array, BooleanArray, StringArray};
+use arrow::compute::filter_record_batch;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
 use parquet::arrow::ArrowWriter;
 use parquet::file::properties::WriterProperties;
+use std::collections::HashSet;
 use std::fs::{self, File};
+use std::path::Path;
 
 /// Compactor for consolidating embeddings parquet files
 pub struct EmbeddingsCompactor {
@@ -47,7 +53,10 @@ impl EmbeddingsCompactor {
     /// Compact embeddings for a single provider
     ///
     /// Reads all parquet files in embeddings/{provider}/*.parquet and
-    /// writes them to embeddings/{provider}.parquet
+    /// writes them to embeddings/{provider}.parquet, together with the rows
+    /// of an earlier consolidated file. A conversation embedded again since
+    /// the last compaction keeps only its new chunks, and no chunk id is
+    /// written twice.
     pub fn compact_provider(&self, provider: &str) -> Result<Option<CompactionResult>> {
         let source_dir = self.config.embeddings_dir(provider);
         let output_path = self.config.consolidated_embeddings_path(provider);
@@ -73,7 +82,8 @@ impl EmbeddingsCompactor {
         }
 
         // Create output file
-        let output_file = File::create(&output_path)?;
+        let previous = output_path.exists().then(|| output_path.clone());
+        let (target, output_file) = AtomicFile::create(&output_path)?;
 
         // Read first file to get schema
         let first_file = File::open(&parquet_files[0])?;
@@ -92,17 +102,31 @@ impl EmbeddingsCompactor {
         let mut total_rows = 0;
         let files_merged = parquet_files.len();
 
-        // Read and write all files
+        // Per-conversation files are rewritten on every pull, so they hold the
+        // newest chunks; each (conversation, chunk id) pair is written once
+        let mut written = HashSet::new();
+        let mut fresh_conversations = HashSet::new();
         for file_path in &parquet_files {
-            let file = File::open(file_path)?;
-            let builder = ParquetRecordBatchReaderBuilder::try_new(file)
-                .map_err(|e| StorageError::Parquet(e.to_string()))?;
-            let reader = builder
-                .build()
-                .map_err(|e| StorageError::Parquet(e.to_string()))?;
-
-            for batch_result in reader {
-                let batch = batch_result?;
+            for batch_result in read_batches(file_path)? {
+                let batch = retain_rows(&batch_result?, |conversation_id, chunk_id| {
+                    fresh_conversations.insert(conversation_id.to_string());
+                    written.insert((conversation_id.to_string(), chunk_id.to_string()))
+                })?;
+                total_rows += batch.num_rows();
+                writer
+                    .write(&batch)
+                    .map_err(|e| StorageError::Parquet(e.to_string()))?;
+            }
+        }
+
+        // Carry over the previous consolidated file, minus the conversations
+        // that were embedded again since
+        if let Some(previous) = previous {
+            for batch_result in read_batches(&previous)? {
+                let batch = retain_rows(&batch_result?, |conversation_id, chunk_id| {
+                    !fresh_conversations.contains(conversation_id)
+                        && written.insert((conversation_id.to_string(), chunk_id.to_string()))
+                })?;
                 total_rows += batch.num_rows();
                 writer
                     .write(&batch)
@@ -113,6 +137,7 @@ impl EmbeddingsCompactor {
         writer
             .close()
             .map_err(|e| StorageError::Parquet(e.to_string()))?;
+        target.commit()?;
 
         // Remove old directory after successful write
         fs::remove_dir_all(&source_dir)?;
@@ -181,6 +206,34 @@ impl EmbeddingsCompactor {
     }
 }
 
+/// Read every batch of a parquet file
+pub(super) fn read_batches(path: &Path) -> Result<ParquetRecordBatchReader> {
+    ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+        .and_then(|builder| builder.build())
+        .map_err(|e| StorageError::Parquet(e.to_string()))
+}
+
+/// Rows of `batch` for which `keep(conversation_id, chunk_id)` is true
+pub(super) fn retain_rows(
+    batch: &RecordBatch,
+    mut keep: impl FnMut(&str, &str) -> bool,
+) -> Result<RecordBatch> {
+    let conversation_ids = string_column(batch, "conversation_id")?;
+    let chunk_ids = string_column(batch, "chunk_id")?;
+    let mask: Vec<bool> = (0..batch.num_rows())
+        .map(|i| keep(conversation_ids.value(i), chunk_ids.value(i)))
+        .collect();
+    filter_record_batch(batch, &BooleanArray::from(mask))
+        .map_err(|e| StorageError::Parquet(e.to_string()))
+}
+
+fn string_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a StringArray> {
+    batch
+        .column_by_name(name)
+        .and_then(|column| column.as_any().downcast_ref::<StringArray>())
+        .ok_or_else(|| StorageError::Parquet(format!("{} is not a string column", name)))
+}
+
 /// Status of a provider's embeddings
 #[derive(Debug)]
 pub struct ProviderStatus {
@@ -220,7 +273,12 @@ mod tests {
             let chunks = vec![create_test_chunk(&format!("msg-{}", i), 0, "Hello world")];
             let embeddings = vec![create_test_embedding()];
             store
-                .write_embeddings(&format!("conv-{}", i), "test_provider", &chunks, &embeddings)
+                .write_embeddings(
+                    &format!("conv-{}", i),
+                    "test_provider",
+                    &chunks,
+                    &embeddings,
+                )
                 .unwrap();
         }
 
@@ -239,12 +297,63 @@ mod tests {
         assert_eq!(result.total_rows, 5);
 
         // Verify consolidated file exists
-        assert!(config.consolidated_embeddings_path("test_provider").exists());
+        assert!(config
+            .consolidated_embeddings_path("test_provider")
+            .exists());
 
         // Verify source directory is removed
         assert!(!source_dir.exists());
     }
 
+    #[test]
+    fn test_compacting_repulled_conversations_does_not_duplicate_chunks() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let store = EmbeddingsStore::new(config.clone());
+        let compactor = EmbeddingsCompactor::new(config.clone());
+        let pull = |conversation: &str, texts: &[&str]| {
+            let chunks: Vec<_> = texts
+                .iter()
+                .enumerate()
+                .map(|(i, text)| create_test_chunk(&format!("{}-msg", conversation), i, text))
+                .collect();
+            let embeddings = vec![create_test_embedding(); chunks.len()];
+            store
+                .write_embeddings(conversation, "test_provider", &chunks, &embeddings)
+                .unwrap();
+        };
+        let consolidated_rows = || {
+            let statuses = compactor.status().unwrap();
+            assert!(statuses[0].is_consolidated);
+            statuses[0].total_rows
+        };
+
+        pull("conv-1", &["first", "second"]);
+        pull("conv-2", &["other"]);
+        compactor.compact_provider("test_provider").unwrap();
+        assert_eq!(consolidated_rows(), 3);
+
+        // The same conversation pulled again, twice, between compactions
+        pull("conv-1", &["first", "second"]);
+        compactor.compact_provider("test_provider").unwrap();
+        pull("conv-1", &["first", "second"]);
+        let result = compactor
+            .compact_provider("test_provider")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.total_rows, 3);
+        assert_eq!(consolidated_rows(), 3);
+
+        // A conversation that lost a chunk drops the stale one
+        pull("conv-1", &["first"]);
+        compactor.compact_provider("test_provider").unwrap();
+        assert_eq!(consolidated_rows(), 2);
+        assert_eq!(
+            store.indexed_conversation_ids("test_provider").unwrap(),
+            HashSet::from(["conv-1".to_string(), "conv-2".to_string()])
+        );
+    }
+
     #[test]
     fn test_compact_nonexistent_provider() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,394 @@
+//! Merging duplicate accounts and conversations
+//!
+//! Signing in again can store the same account under a new id, with the email
+//! cased differently so `UNIQUE(provider, email, workspace_id)` doesn't catch
+//! it, and a provider that changes conversation ids makes the next pull store
+//! every conversation twice. [`Store::dedupe`] finds both and folds each
+//! duplicate into the copy that's kept, one transaction per merge.
+
+use super::{Result, Store};
+use rusqlite::params;
+
+/// Rows that are the same account or conversation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicates {
+    /// The row that's kept: the newest account, the latest pulled conversation
+    pub keep: String,
+    /// Rows merged into `keep`
+    pub duplicates: Vec<String>,
+    /// Email of the account, or title of the conversation
+    pub label: String,
+}
+
+/// Messages [`Store::merge_conversations`] moved over or dropped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergedMessages {
+    /// Messages only the duplicate had, now in the kept conversation
+    pub moved: usize,
+    /// Messages the kept conversation already had
+    pub dropped: usize,
+}
+
+/// Result of [`Store::dedupe`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupeReport {
+    pub accounts: Vec<Duplicates>,
+    pub conversations: Vec<Duplicates>,
+    pub messages: MergedMessages,
+}
+
+impl Store {
+    /// Accounts signed in more than once: same provider, workspace and email,
+    /// ignoring case and surrounding whitespace
+    pub fn duplicate_accounts(&self) -> Result<Vec<Duplicates>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT provider || char(0) || lower(trim(email)) || char(0) || workspace_id,
+                    id, email
+             FROM accounts
+             ORDER BY 1, created_at DESC, rowid DESC",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(group_duplicates(rows))
+    }
+
+    /// Conversations stored more than once: same provider, title and creation time
+    pub fn duplicate_conversations(&self) -> Result<Vec<Duplicates>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT provider_id || char(0) || title || char(0) || created_at, id, title
+             FROM conversations
+             ORDER BY 1, updated_at DESC, rowid DESC",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(group_duplicates(rows))
+    }
+
+    /// Move everything of account `duplicate` to `keep` and delete `duplicate`
+    ///
+    /// The pull history and sync cursors of `keep` win; `duplicate`'s only
+    /// carry over when `keep` has none for that provider.
+    pub fn merge_accounts(&self, keep: &str, duplicate: &str) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE conversations SET account_id = ?1 WHERE account_id = ?2",
+            params![keep, duplicate],
+        )?;
+        tx.execute(
+            "UPDATE attachments SET account_id = ?1 WHERE account_id = ?2",
+            params![keep, duplicate],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE sync_state SET account_id = ?1 WHERE account_id = ?2",
+            params![keep, duplicate],
+        )?;
+        tx.execute(
+            "DELETE FROM sync_state WHERE account_id = ?1",
+            params![duplicate],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE sync_cursors SET account_id = ?1 WHERE account_id = ?2",
+            params![keep, duplicate],
+        )?;
+        tx.execute(
+            "DELETE FROM sync_cursors WHERE account_id = ?1",
+            params![duplicate],
+        )?;
+        tx.execute("DELETE FROM accounts WHERE id = ?1", params![duplicate])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Fold conversation `duplicate` into `keep` and delete `duplicate`
+    ///
+    /// A message `keep` already has, with the same role, time and content, is
+    /// dropped, its attachments and replies moving to `keep`'s copy. The rest
+    /// move over as they are.
+    pub fn merge_conversations(&self, keep: &str, duplicate: &str) -> Result<MergedMessages> {
+        let tx = self.conn.unchecked_transaction()?;
+        let pairs = {
+            let mut stmt = tx.prepare(
+                "SELECT d.id, d.fts_rowid,
+                        (SELECT k.id FROM messages k
+                         WHERE k.conversation_id = ?1 AND k.role = d.role
+                           AND k.content_json = d.content_json
+                           AND k.created_at IS d.created_at
+                         LIMIT 1)
+                 FROM messages d WHERE d.conversation_id = ?2",
+            )?;
+            let pairs = stmt
+                .query_map(params![keep, duplicate], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<i64>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            pairs
+        };
+
+        let mut merged = MergedMessages::default();
+        for (id, fts_rowid, kept) in pairs {
+            let Some(kept) = kept else {
+                merged.moved += 1;
+                continue;
+            };
+            tx.execute(
+                "UPDATE attachments SET message_id = ?1 WHERE message_id = ?2",
+                params![kept, id],
+            )?;
+            tx.execute(
+                "UPDATE attachment_text SET message_id = ?1 WHERE message_id = ?2",
+                params![kept, id],
+            )?;
+            tx.execute(
+                "UPDATE messages SET parent_id = ?1 WHERE parent_id = ?2",
+                params![kept, id],
+            )?;
+            tx.execute(
+                "DELETE FROM messages_fts WHERE rowid = ?1",
+                params![fts_rowid],
+            )?;
+            tx.execute("DELETE FROM messages WHERE id = ?1", params![id])?;
+            merged.dropped += 1;
+        }
+
+        tx.execute(
+            "UPDATE messages_fts SET conversation_id = ?1 WHERE conversation_id = ?2",
+            params![keep, duplicate],
+        )?;
+        tx.execute(
+            "UPDATE messages SET conversation_id = ?1 WHERE conversation_id = ?2",
+            params![keep, duplicate],
+        )?;
+        tx.execute(
+            "UPDATE conversation_revisions SET conversation_id = ?1 WHERE conversation_id = ?2",
+            params![keep, duplicate],
+        )?;
+        tx.execute(
+            "DELETE FROM conversations WHERE id = ?1",
+            params![duplicate],
+        )?;
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    /// Merge every duplicate account, then every duplicate conversation
+    ///
+    /// With `dry_run`, only reports what would be merged; message counts are
+    /// then left at zero.
+    pub fn dedupe(&self, dry_run: bool) -> Result<DedupeReport> {
+        let mut report = DedupeReport {
+            accounts: self.duplicate_accounts()?,
+            ..Default::default()
+        };
+        if !dry_run {
+            for set in &report.accounts {
+                for duplicate in &set.duplicates {
+                    self.merge_accounts(&set.keep, duplicate)?;
+                }
+            }
+        }
+
+        report.conversations = self.duplicate_conversations()?;
+        if !dry_run {
+            for set in &report.conversations {
+                for duplicate in &set.duplicates {
+                    let merged = self.merge_conversations(&set.keep, duplicate)?;
+                    report.messages.moved += merged.moved;
+                    report.messages.dropped += merged.dropped;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Sets of two or more rows sharing a key, from `(key, id, label)` rows
+/// sorted by key with the row to keep first
+fn group_duplicates(rows: Vec<(String, String, String)>) -> Vec<Duplicates> {
+    let mut sets: Vec<(String, Duplicates)> = Vec::new();
+    for (key, id, label) in rows {
+        match sets.last_mut() {
+            Some((last, set)) if *last == key => set.duplicates.push(id),
+            _ => sets.push((
+                key,
+                Duplicates {
+                    keep: id,
+                    duplicates: Vec::new(),
+                    label,
+                },
+            )),
+        }
+    }
+    sets.into_iter()
+        .map(|(_, set)| set)
+        .filter(|set| !set.duplicates.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, Message, MessageContent, ProviderId, Role};
+    use crate::storage::SearchFilters;
+    use chrono::{TimeZone, Utc};
+
+    fn account(id: &str, email: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            provider: ProviderId::claude(),
+            email: email.to_string(),
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        }
+    }
+
+    fn conversation(id: &str) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            provider_id: "claude".to_string(),
+            title: "Trip to Lisbon".to_string(),
+            created_at: Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 5, 1, 9, 30, 0).unwrap(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    fn message(id: &str, conversation_id: &str, minute: u32, text: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: Some(Utc.with_ymd_and_hms(2024, 5, 1, 9, minute, 0).unwrap()),
+            model: None,
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn test_reauthenticated_account_is_merged_into_the_newest() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&account("old", "Ana@Example.com"))
+            .unwrap();
+        store
+            .save_account(&account("new", "ana@example.com"))
+            .unwrap();
+        store
+            .save_account(&account("other", "bo@example.com"))
+            .unwrap();
+        store
+            .save_conversation("old", &conversation("conv-1"))
+            .unwrap();
+        store.record_pull("claude", "old", true, 1).unwrap();
+
+        let duplicates = store.duplicate_accounts().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].keep, "new");
+        assert_eq!(duplicates[0].duplicates, vec!["old"]);
+
+        store.merge_accounts("new", "old").unwrap();
+        let ids: Vec<_> = store
+            .list_accounts()
+            .unwrap()
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        assert!(!ids.contains(&"old".to_string()));
+        assert_eq!(store.list_conversations("new").unwrap().len(), 1);
+        assert!(store.sync_state("claude", "new").unwrap().is_some());
+        assert!(store.sync_state("claude", "old").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_repulled_conversation_keeps_one_copy_of_each_message() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&account("acc", "ana@example.com"))
+            .unwrap();
+        store
+            .save_conversation("acc", &conversation("conv-old"))
+            .unwrap();
+        store
+            .save_conversation("acc", &conversation("conv-new"))
+            .unwrap();
+        store
+            .save_message(&message("old-1", "conv-old", 1, "Flights in May?"))
+            .unwrap();
+        store
+            .save_message(&message(
+                "old-2",
+                "conv-old",
+                2,
+                "Only the old copy has this",
+            ))
+            .unwrap();
+        store
+            .save_message(&message("new-1", "conv-new", 1, "Flights in May?"))
+            .unwrap();
+
+        let report = store.dedupe(false).unwrap();
+        assert_eq!(report.conversations.len(), 1);
+        assert_eq!(report.conversations[0].keep, "conv-new");
+        assert_eq!(
+            report.messages,
+            MergedMessages {
+                moved: 1,
+                dropped: 1
+            }
+        );
+
+        assert!(store.get_conversation("conv-old").unwrap().is_none());
+        let ids: Vec<_> = store
+            .get_messages("conv-new")
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["new-1", "old-2"]);
+
+        let flights = store
+            .search("flights", 10, &SearchFilters::default())
+            .unwrap();
+        assert_eq!(flights.len(), 1);
+        let moved = store.search("copy", 10, &SearchFilters::default()).unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].conversation_id, "conv-new");
+        assert!(store.check_fts(100).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&account("acc", "ana@example.com"))
+            .unwrap();
+        store
+            .save_account(&account("acc-2", "ANA@example.com"))
+            .unwrap();
+        store
+            .save_conversation("acc", &conversation("conv-1"))
+            .unwrap();
+        store
+            .save_conversation("acc-2", &conversation("conv-2"))
+            .unwrap();
+
+        let report = store.dedupe(true).unwrap();
+        assert_eq!(report.accounts.len(), 1);
+        assert_eq!(report.conversations.len(), 1);
+        assert_eq!(store.list_accounts().unwrap().len(), 2);
+        assert_eq!(store.stats().unwrap().conversations, 2);
+    }
+}
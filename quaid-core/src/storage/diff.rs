@@ -0,0 +1,373 @@
+//! Change detection between incoming conversations and the local store
+//!
+//! A conversation is identified by its id and compared by a hash of its
+//! content (title and messages), so re-fetching or re-importing something
+//! that hasn't changed is recognised even when timestamps moved.
+//!
+//! Before fetching at all, `--new-only` pulls compare the `updated_at` a
+//! listing reports with the stored one; [`SyncDecision`] records which rule
+//! decided, so skipped conversations can be explained.
+
+use super::{Result, Store};
+use crate::providers::{Conversation, Message};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// How an incoming conversation relates to the stored copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Not in the store yet
+    New,
+    /// Stored with different content
+    Updated {
+        /// Incoming message count minus stored message count
+        message_delta: i64,
+    },
+    /// Stored with the same content
+    Identical,
+}
+
+/// Change detected for one conversation
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationChange {
+    pub conversation_id: String,
+    pub title: String,
+    pub kind: ChangeKind,
+}
+
+/// Changes for a whole batch of incoming conversations
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffSummary {
+    pub new: usize,
+    pub updated: usize,
+    pub identical: usize,
+    pub changes: Vec<ConversationChange>,
+}
+
+impl DiffSummary {
+    fn push(&mut self, change: ConversationChange) {
+        match change.kind {
+            ChangeKind::New => self.new += 1,
+            ChangeKind::Updated { .. } => self.updated += 1,
+            ChangeKind::Identical => self.identical += 1,
+        }
+        self.changes.push(change);
+    }
+}
+
+/// Rule that decided whether a pull fetches a listed conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipRule {
+    /// Not a `--new-only` pull, so everything is fetched
+    FullPull,
+    /// Not in the store yet
+    New,
+    /// Stored, but never written to Parquet (e.g. the pull was cancelled)
+    NotIndexed,
+    /// The provider reports a later `updated_at` than the stored one
+    RemoteNewer,
+    /// The stored `updated_at` is in the future, so it can't be compared
+    FutureLocal,
+    /// The provider's `updated_at` is not later than the stored one
+    NotUpdated,
+    /// Fetched, but the content matches the stored copy
+    Identical,
+}
+
+impl SkipRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipRule::FullPull => "full_pull",
+            SkipRule::New => "new",
+            SkipRule::NotIndexed => "not_indexed",
+            SkipRule::RemoteNewer => "remote_newer",
+            SkipRule::FutureLocal => "future_local",
+            SkipRule::NotUpdated => "not_updated",
+            SkipRule::Identical => "identical",
+        }
+    }
+}
+
+/// Whether a pull fetches (or keeps) one listed conversation, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncDecision {
+    pub conversation_id: String,
+    pub title: String,
+    pub local_updated_at: Option<DateTime<Utc>>,
+    pub remote_updated_at: DateTime<Utc>,
+    pub rule: SkipRule,
+}
+
+impl SyncDecision {
+    fn new(
+        conversation: &Conversation,
+        local_updated_at: Option<DateTime<Utc>>,
+        rule: SkipRule,
+    ) -> Self {
+        Self {
+            conversation_id: conversation.id.clone(),
+            title: conversation.title.clone(),
+            local_updated_at,
+            remote_updated_at: conversation.updated_at,
+            rule,
+        }
+    }
+
+    /// Whether the conversation is left as stored
+    pub fn skips(&self) -> bool {
+        matches!(self.rule, SkipRule::NotUpdated | SkipRule::Identical)
+    }
+}
+
+/// Hash of the parts of a conversation a user would notice changing
+///
+/// Messages are hashed in id order, so the order a provider returns them in
+/// doesn't matter.
+pub fn content_hash(conversation: &Conversation, messages: &[Message]) -> String {
+    let mut sorted: Vec<&Message> = messages.iter().collect();
+    sorted.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut hasher = Sha256::new();
+    hasher.update(conversation.title.as_bytes());
+    for message in sorted {
+        hasher.update([0]);
+        hasher.update(message.id.as_bytes());
+        hasher.update([0]);
+        hasher.update(message.role.as_str().as_bytes());
+        hasher.update([0]);
+        hasher.update(serde_json::to_vec(&message.content).unwrap_or_default());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+impl Store {
+    /// Compare one incoming conversation with the stored copy
+    pub fn diff_conversation(
+        &self,
+        conversation: &Conversation,
+        messages: &[Message],
+    ) -> Result<ChangeKind> {
+        let Some(local) = self.get_conversation(&conversation.id)? else {
+            return Ok(ChangeKind::New);
+        };
+        let local_messages = self.get_messages(&conversation.id)?;
+
+        if content_hash(&local, &local_messages) == content_hash(conversation, messages) {
+            Ok(ChangeKind::Identical)
+        } else {
+            Ok(ChangeKind::Updated {
+                message_delta: messages.len() as i64 - local_messages.len() as i64,
+            })
+        }
+    }
+
+    /// Decide whether a pull needs to fetch a listed conversation
+    ///
+    /// `indexed` says whether its Parquet file exists; a conversation saved to
+    /// SQLite but never indexed is fetched again even if it hasn't changed.
+    pub fn sync_decision(
+        &self,
+        listed: &Conversation,
+        new_only: bool,
+        indexed: bool,
+    ) -> Result<SyncDecision> {
+        let local_updated_at = self.get_conversation_updated_at(&listed.id)?;
+        let rule = match local_updated_at {
+            _ if !new_only => SkipRule::FullPull,
+            None => SkipRule::New,
+            Some(_) if !indexed => SkipRule::NotIndexed,
+            Some(local) if super::skew::is_future(local, Utc::now()) => SkipRule::FutureLocal,
+            Some(local) if listed.updated_at > local => SkipRule::RemoteNewer,
+            Some(_) => SkipRule::NotUpdated,
+        };
+        Ok(SyncDecision::new(listed, local_updated_at, rule))
+    }
+
+    /// Skip decision for a fetched conversation whose content is already stored
+    ///
+    /// Returns `None` if it is new or changed and should be saved.
+    pub fn identical_decision(
+        &self,
+        conversation: &Conversation,
+        messages: &[Message],
+    ) -> Result<Option<SyncDecision>> {
+        if self.diff_conversation(conversation, messages)? != ChangeKind::Identical {
+            return Ok(None);
+        }
+        let local_updated_at = self.get_conversation_updated_at(&conversation.id)?;
+        Ok(Some(SyncDecision::new(
+            conversation,
+            local_updated_at,
+            SkipRule::Identical,
+        )))
+    }
+
+    /// Compare a batch of incoming conversations with the store, writing nothing
+    pub fn diff_conversations<'a>(
+        &self,
+        incoming: impl IntoIterator<Item = (&'a Conversation, &'a [Message])>,
+    ) -> Result<DiffSummary> {
+        let mut summary = DiffSummary::default();
+        for (conversation, messages) in incoming {
+            summary.push(ConversationChange {
+                conversation_id: conversation.id.clone(),
+                title: conversation.title.clone(),
+                kind: self.diff_conversation(conversation, messages)?,
+            });
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, MessageContent, ProviderId, Role};
+
+    fn fixture() -> Vec<(Conversation, Vec<Message>)> {
+        (0..3)
+            .map(|i| {
+                let conv = Conversation {
+                    id: format!("conv-{}", i),
+                    provider_id: "chatgpt".to_string(),
+                    title: format!("Conversation {}", i),
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                    model: None,
+                    project_id: None,
+                    project_name: None,
+                    is_archived: false,
+                };
+                let messages = (0..2)
+                    .map(|j| Message {
+                        id: format!("msg-{}-{}", i, j),
+                        conversation_id: conv.id.clone(),
+                        parent_id: None,
+                        role: if j == 0 { Role::User } else { Role::Assistant },
+                        content: MessageContent::Text {
+                            text: format!("Message {} of conversation {}", j, i),
+                        },
+                        created_at: None,
+                        model: None,
+                        speaker: None,
+                    })
+                    .collect();
+                (conv, messages)
+            })
+            .collect()
+    }
+
+    fn diff(store: &Store, incoming: &[(Conversation, Vec<Message>)]) -> DiffSummary {
+        store
+            .diff_conversations(incoming.iter().map(|(c, m)| (c, m.as_slice())))
+            .unwrap()
+    }
+
+    fn import(store: &Store, incoming: &[(Conversation, Vec<Message>)]) {
+        for (conv, messages) in incoming {
+            store.save_conversation("user-1", conv).unwrap();
+            for message in messages {
+                store.save_message(message).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_diff_detects_new_identical_and_updated() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::chatgpt(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        let export = fixture();
+
+        let summary = diff(&store, &export);
+        assert_eq!((summary.new, summary.updated, summary.identical), (3, 0, 0));
+        assert!(store.get_conversation("conv-0").unwrap().is_none());
+
+        import(&store, &export);
+        let summary = diff(&store, &export);
+        assert_eq!((summary.new, summary.updated, summary.identical), (0, 0, 3));
+
+        let mut edited = export[1].1[1].clone();
+        edited.content = MessageContent::Text {
+            text: "Edited locally".to_string(),
+        };
+        store.save_message(&edited).unwrap();
+
+        let summary = diff(&store, &export);
+        assert_eq!((summary.new, summary.updated, summary.identical), (0, 1, 2));
+        let updated = summary
+            .changes
+            .iter()
+            .find(|c| c.kind != ChangeKind::Identical)
+            .unwrap();
+        assert_eq!(updated.conversation_id, "conv-1");
+        assert_eq!(updated.kind, ChangeKind::Updated { message_delta: 0 });
+    }
+
+    #[test]
+    fn test_sync_decision_for_new_updated_and_unchanged() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::chatgpt(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        let (conv, messages) = fixture().remove(0);
+        let rule = |conv: &Conversation, new_only, indexed| {
+            store.sync_decision(conv, new_only, indexed).unwrap().rule
+        };
+
+        assert_eq!(rule(&conv, true, false), SkipRule::New);
+        assert_eq!(rule(&conv, false, false), SkipRule::FullPull);
+
+        import(&store, &[(conv.clone(), messages.clone())]);
+        let unchanged = store.sync_decision(&conv, true, true).unwrap();
+        assert_eq!(unchanged.rule, SkipRule::NotUpdated);
+        assert_eq!(unchanged.local_updated_at, Some(conv.updated_at));
+        assert!(unchanged.skips());
+        assert_eq!(rule(&conv, true, false), SkipRule::NotIndexed);
+        assert_eq!(rule(&conv, false, true), SkipRule::FullPull);
+
+        let mut updated = conv.clone();
+        updated.updated_at = conv.updated_at + chrono::Duration::minutes(5);
+        let decision = store.sync_decision(&updated, true, true).unwrap();
+        assert_eq!(decision.rule, SkipRule::RemoteNewer);
+        assert_eq!(decision.remote_updated_at, updated.updated_at);
+        assert!(!decision.skips());
+
+        // Fetched anyway, but nothing changed
+        let identical = store.identical_decision(&updated, &messages).unwrap();
+        assert_eq!(identical.unwrap().rule, SkipRule::Identical);
+        assert!(store
+            .identical_decision(&updated, &messages[..1])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_content_hash_ignores_message_order() {
+        let (conv, mut messages) = fixture().remove(0);
+        let forward = content_hash(&conv, &messages);
+        messages.reverse();
+        assert_eq!(content_hash(&conv, &messages), forward);
+
+        messages.pop();
+        assert_ne!(content_hash(&conv, &messages), forward);
+    }
+}
@@ -2,7 +2,11 @@
 //!
 //! Provides SQL queries across multiple parquet files using DuckDB's glob support.
 
-use super::{ParquetStorageConfig, Result, SearchResult, SemanticSearchResult};
+use super::{
+    extract_text_content, EmbeddingsStore, ParquetStorageConfig, Result, SearchFilters,
+    SearchResult, SemanticSearchResult, StorageError,
+};
+use crate::grep::Matcher;
 use crate::providers::{Conversation, Message, MessageContent, Role};
 use chrono::{DateTime, TimeZone, Utc};
 use duckdb::{params, Connection};
@@ -25,8 +29,8 @@ impl DuckDbQuery {
         Self { conn, config }
     }
 
-    /// Query all conversations across all providers
-    pub fn list_all_conversations(&self) -> Result<Vec<Conversation>> {
+    /// Query all conversations across all providers that match `filters`
+    pub fn list_all_conversations(&self, filters: &SearchFilters) -> Result<Vec<Conversation>> {
         let glob_pattern = self
             .config
             .base_dir
@@ -54,9 +58,11 @@ impl DuckDbQuery {
                 conv_project_name,
                 conv_is_archived
             FROM read_parquet('{}')
+            WHERE TRUE{}
             ORDER BY conv_updated_at DESC
             "#,
-            glob_str
+            glob_str,
+            Self::filter_clause(filters)
         ))?;
 
         let conversations = stmt
@@ -78,8 +84,12 @@ impl DuckDbQuery {
         Ok(conversations)
     }
 
-    /// Query conversations for a specific provider
-    pub fn list_conversations_by_provider(&self, provider: &str) -> Result<Vec<Conversation>> {
+    /// Query conversations for a specific provider that match `filters`
+    pub fn list_conversations_by_provider(
+        &self,
+        provider: &str,
+        filters: &SearchFilters,
+    ) -> Result<Vec<Conversation>> {
         let glob_pattern = self
             .config
             .base_dir
@@ -106,9 +116,11 @@ impl DuckDbQuery {
                 conv_project_name,
                 conv_is_archived
             FROM read_parquet('{}')
+            WHERE TRUE{}
             ORDER BY conv_updated_at DESC
             "#,
-            glob_str
+            glob_str,
+            Self::filter_clause(filters)
         ))?;
 
         let conversations = stmt
@@ -131,7 +143,53 @@ impl DuckDbQuery {
     }
 
     /// Search messages across all conversations using LIKE pattern matching
-    pub fn search_messages(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+    pub fn search_messages(
+        &self,
+        query: &str,
+        limit: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchResult>> {
+        let search_pattern = format!("%{}%", query.replace('%', "\\%").replace('_', "\\_"));
+        self.find_messages(
+            "msg_content_json ILIKE ?",
+            &search_pattern,
+            limit,
+            filters,
+            |content_json| Self::extract_snippet(content_json, query),
+        )
+    }
+
+    /// Search messages whose content matches a regular expression
+    ///
+    /// Matched with DuckDB's `regexp_matches` against each message's content
+    /// JSON, where quotes and newlines are escaped; `(?i)` ignores case. Every
+    /// parquet file is scanned, so this is much slower than FTS.
+    pub fn search_messages_regex(
+        &self,
+        pattern: &str,
+        limit: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchResult>> {
+        let matcher = Matcher::new(pattern, true, false)
+            .map_err(|e| StorageError::InvalidPattern(e.to_string()))?;
+        self.find_messages(
+            "regexp_matches(msg_content_json, ?)",
+            pattern,
+            limit,
+            filters,
+            |content_json| Self::regex_snippet(content_json, &matcher),
+        )
+    }
+
+    /// Messages matching `predicate`, a SQL condition on one `?` bound to `param`
+    fn find_messages(
+        &self,
+        predicate: &str,
+        param: &str,
+        limit: usize,
+        filters: &SearchFilters,
+        snippet: impl Fn(&str) -> String,
+    ) -> Result<Vec<SearchResult>> {
         let glob_pattern = self
             .config
             .base_dir
@@ -145,31 +203,31 @@ impl DuckDbQuery {
             return Ok(vec![]);
         }
 
-        let search_pattern = format!("%{}%", query.replace('%', "\\%").replace('_', "\\_"));
-
         let mut stmt = self.conn.prepare(&format!(
             r#"
             SELECT
                 conv_id,
-                msg_content_json
+                msg_content_json,
+                msg_role
             FROM read_parquet('{}')
-            WHERE msg_content_json ILIKE ?
+            WHERE {}{}
             LIMIT ?
             "#,
-            glob_str
+            glob_str,
+            predicate,
+            Self::filter_clause(filters)
         ))?;
 
         let results = stmt
-            .query_map(params![search_pattern, limit as i64], |row| {
+            .query_map(params![param, limit as i64], |row| {
                 let conv_id: String = row.get(0)?;
                 let content_json: String = row.get(1)?;
-
-                // Extract snippet from content
-                let snippet = Self::extract_snippet(&content_json, query);
+                let role: String = row.get(2)?;
 
                 Ok(SearchResult {
                     conversation_id: conv_id,
-                    snippet,
+                    role: Role::parse(&role).unwrap_or(Role::User),
+                    snippet: snippet(&content_json),
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -281,6 +339,7 @@ impl DuckDbQuery {
                     content,
                     created_at: Some(Self::parse_timestamp(row.get::<_, i64>(5).ok())),
                     model: row.get(6).ok(),
+                    speaker: None,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -288,6 +347,47 @@ impl DuckDbQuery {
         Ok(messages)
     }
 
+    /// SQL predicates (each prefixed with `AND`) restricting conversation parquet rows
+    ///
+    /// Values are inlined: timestamps are integers and roles come from a fixed set,
+    /// so only the provider needs quoting.
+    fn filter_clause(filters: &SearchFilters) -> String {
+        let mut clause = String::new();
+
+        if let Some(provider) = &filters.provider {
+            clause.push_str(&format!(
+                " AND conv_provider_id = '{}'",
+                provider.replace('\'', "''")
+            ));
+        }
+        if let Some(since) = filters.since {
+            clause.push_str(&format!(
+                " AND epoch_ms(conv_created_at) >= {}",
+                since.timestamp_millis()
+            ));
+        }
+        if let Some(until) = filters.until {
+            clause.push_str(&format!(
+                " AND epoch_ms(conv_created_at) <= {}",
+                until.timestamp_millis()
+            ));
+        }
+        if !filters.roles.is_empty() {
+            let roles = filters
+                .roles
+                .iter()
+                .map(|r| format!("'{}'", r.as_str()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            clause.push_str(&format!(" AND msg_role IN ({})", roles));
+        }
+        if filters.exclude_archived {
+            clause.push_str(" AND NOT conv_is_archived");
+        }
+
+        clause
+    }
+
     /// Check if any parquet files match the glob pattern
     fn has_parquet_files(&self, glob_pattern: &str) -> Result<bool> {
         // First, check if the parent directory exists
@@ -304,7 +404,10 @@ impl DuckDbQuery {
 
         // Try to query the glob - if no files match, DuckDB will error
         let result = self.conn.query_row(
-            &format!("SELECT COUNT(*) FROM read_parquet('{}') LIMIT 1", glob_pattern),
+            &format!(
+                "SELECT COUNT(*) FROM read_parquet('{}') LIMIT 1",
+                glob_pattern
+            ),
             [],
             |row| row.get::<_, i64>(0),
         );
@@ -332,6 +435,21 @@ impl DuckDbQuery {
             .unwrap_or_else(Utc::now)
     }
 
+    /// The first line of a message's text matching `matcher`, with matches
+    /// marked like FTS snippets
+    ///
+    /// A match only in the JSON, e.g. across an escaped newline, falls back to
+    /// the start of the text.
+    fn regex_snippet(content_json: &str, matcher: &Matcher) -> String {
+        let text = serde_json::from_str::<MessageContent>(content_json)
+            .map(|content| extract_text_content(&content))
+            .unwrap_or_else(|_| content_json.to_string());
+        match text.lines().find(|line| matcher.is_match(line)) {
+            Some(line) => matcher.highlight(line.trim(), "<mark>", "</mark>"),
+            None => Self::extract_snippet(content_json, ""),
+        }
+    }
+
     /// Extract a snippet around the search query from content JSON
     fn extract_snippet(content_json: &str, query: &str) -> String {
         // Try to parse as MessageContent and extract text
@@ -339,11 +457,14 @@ impl DuckDbQuery {
             match content {
                 MessageContent::Text { text } => text,
                 MessageContent::Code { code, .. } => code,
+                MessageContent::Document { text, .. } => text,
+                MessageContent::Thinking { text } => text,
                 MessageContent::Mixed { parts } => parts
                     .iter()
                     .filter_map(|p| match p {
                         MessageContent::Text { text } => Some(text.clone()),
                         MessageContent::Code { code, .. } => Some(code.clone()),
+                        MessageContent::Document { text, .. } => Some(text.clone()),
                         _ => None,
                     })
                     .collect::<Vec<_>>()
@@ -392,13 +513,10 @@ impl DuckDbQuery {
         &self,
         query_embedding: &[f32],
         limit: usize,
+        filters: &SearchFilters,
     ) -> Result<Vec<SemanticSearchResult>> {
         // Try consolidated files first (embeddings/*.parquet)
-        let consolidated_pattern = self
-            .config
-            .base_dir
-            .join("embeddings")
-            .join("*.parquet");
+        let consolidated_pattern = self.config.base_dir.join("embeddings").join("*.parquet");
         let consolidated_str = consolidated_pattern.to_string_lossy();
 
         // Fall back to per-conversation files (embeddings/*/*.parquet)
@@ -419,6 +537,17 @@ impl DuckDbQuery {
             return Ok(vec![]);
         };
 
+        // Vectors from a different model can't be compared with this index
+        let dimension = query_embedding.len();
+        if let Some(metadata) = EmbeddingsStore::new(self.config.clone()).read_metadata()? {
+            if metadata.dimension != dimension {
+                return Err(StorageError::EmbeddingsMismatch(format!(
+                    "index was built with {} {} ({} dims), query embedding has {} dims",
+                    metadata.backend, metadata.model, metadata.dimension, dimension
+                )));
+            }
+        }
+
         // Convert query embedding to DuckDB list format
         let embedding_str = format!(
             "[{}]",
@@ -429,21 +558,47 @@ impl DuckDbQuery {
                 .join(",")
         );
 
+        // Filters live on the conversation files, so restrict chunks to matching messages
+        let clause = Self::filter_clause(filters);
+        let filter_sql = if clause.is_empty() {
+            String::new()
+        } else {
+            let conv_glob = self
+                .config
+                .base_dir
+                .join("conversations")
+                .join("*")
+                .join("*.parquet");
+            let conv_glob = conv_glob.to_string_lossy();
+            if !self.has_parquet_files(&conv_glob)? {
+                return Ok(vec![]);
+            }
+            format!(
+                "WHERE message_id IN (SELECT msg_id FROM read_parquet('{}') WHERE TRUE{})",
+                conv_glob, clause
+            )
+        };
+
         // Query embeddings and compute L2 distance
-        // DuckDB can compute list operations directly
+        // DuckDB can compute list operations directly. A chunk written more
+        // than once (e.g. by an uncompacted re-pull) is returned once.
         let sql = format!(
             r#"
             SELECT
                 conversation_id,
-                message_id,
-                text,
-                list_distance(embedding, {embedding}::FLOAT[384]) as distance
+                any_value(message_id),
+                any_value(text),
+                min(list_distance(embedding, {embedding}::FLOAT[{dimension}])) as distance
             FROM read_parquet('{glob}')
+            {filter}
+            GROUP BY conversation_id, chunk_id
             ORDER BY distance ASC
             LIMIT {limit}
             "#,
             embedding = embedding_str,
+            dimension = dimension,
             glob = glob_str,
+            filter = filter_sql,
             limit = limit
         );
 
@@ -472,17 +627,18 @@ impl DuckDbQuery {
         query: &str,
         query_embedding: &[f32],
         limit: usize,
+        filters: &SearchFilters,
     ) -> Result<Vec<SemanticSearchResult>> {
         // Get FTS candidates (broader set)
-        let fts_results = self.search_messages(query, limit * 3)?;
+        let fts_results = self.search_messages(query, limit * 3, filters)?;
 
         if fts_results.is_empty() {
             // Fall back to pure semantic search
-            return self.search_semantic(query_embedding, limit);
+            return self.search_semantic(query_embedding, limit, filters);
         }
 
         // Get semantic results
-        let semantic_results = self.search_semantic(query_embedding, limit * 3)?;
+        let semantic_results = self.search_semantic(query_embedding, limit * 3, filters)?;
 
         if semantic_results.is_empty() {
             // Convert FTS results to SemanticSearchResult
@@ -517,13 +673,11 @@ impl DuckDbQuery {
         // Add semantic scores
         for (rank, result) in semantic_results.iter().enumerate() {
             let score = 1.0 / (K + rank as f32);
-            let entry = combined
-                .entry(result.conversation_id.clone())
-                .or_insert((
-                    result.message_id.clone(),
-                    result.chunk_text.clone(),
-                    0.0,
-                ));
+            let entry = combined.entry(result.conversation_id.clone()).or_insert((
+                result.message_id.clone(),
+                result.chunk_text.clone(),
+                0.0,
+            ));
             entry.0 = result.message_id.clone();
             entry.1 = result.chunk_text.clone();
             entry.2 += score;
@@ -540,7 +694,11 @@ impl DuckDbQuery {
             })
             .collect();
 
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
         results.truncate(limit);
 
         Ok(results)
@@ -579,6 +737,7 @@ mod tests {
             },
             created_at: Some(Utc::now()),
             model: None,
+            speaker: None,
         }
     }
 
@@ -592,14 +751,18 @@ mod tests {
             create_test_message("conv-1", "msg-1", "Hello world"),
             create_test_message("conv-1", "msg-2", "How are you doing today?"),
         ];
-        store.write_conversation("user-123", &conv1, &messages1).unwrap();
+        store
+            .write_conversation("user-123", &conv1, &messages1)
+            .unwrap();
 
         let conv2 = create_test_conversation("conv-2", "Second Conversation");
         let messages2 = vec![
             create_test_message("conv-2", "msg-3", "This is a test message"),
             create_test_message("conv-2", "msg-4", "Testing search functionality"),
         ];
-        store.write_conversation("user-123", &conv2, &messages2).unwrap();
+        store
+            .write_conversation("user-123", &conv2, &messages2)
+            .unwrap();
 
         config
     }
@@ -610,20 +773,77 @@ mod tests {
         let config = setup_test_data(dir.path());
         let query = DuckDbQuery::new(config).unwrap();
 
-        let conversations = query.list_all_conversations().unwrap();
+        let conversations = query
+            .list_all_conversations(&SearchFilters::default())
+            .unwrap();
         assert_eq!(conversations.len(), 2);
     }
 
+    #[test]
+    fn test_list_conversations_created_in_range() {
+        let dir = tempdir().unwrap();
+        let config = setup_test_data(dir.path());
+        let mut conv = create_test_conversation("conv-3", "Old Conversation");
+        conv.created_at = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let message = create_test_message("conv-3", "msg-5", "Hi");
+        ParquetStore::new(config.clone())
+            .write_conversation("user-123", &conv, &[message])
+            .unwrap();
+        let query = DuckDbQuery::new(config).unwrap();
+
+        let ids = |filters: SearchFilters| {
+            let mut ids: Vec<_> = query
+                .list_conversations_by_provider("chatgpt", &filters)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let new_year = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            ids(SearchFilters {
+                until: Some(new_year),
+                ..Default::default()
+            }),
+            ["conv-3"]
+        );
+        assert_eq!(
+            ids(SearchFilters {
+                since: Some(new_year),
+                ..Default::default()
+            }),
+            ["conv-1", "conv-2"]
+        );
+        assert_eq!(
+            query
+                .list_all_conversations(&SearchFilters {
+                    since: Some(new_year),
+                    until: Some(new_year),
+                    ..Default::default()
+                })
+                .unwrap()
+                .len(),
+            0
+        );
+    }
+
     #[test]
     fn test_list_conversations_by_provider() {
         let dir = tempdir().unwrap();
         let config = setup_test_data(dir.path());
         let query = DuckDbQuery::new(config).unwrap();
 
-        let conversations = query.list_conversations_by_provider("chatgpt").unwrap();
+        let filters = SearchFilters::default();
+        let conversations = query
+            .list_conversations_by_provider("chatgpt", &filters)
+            .unwrap();
         assert_eq!(conversations.len(), 2);
 
-        let empty = query.list_conversations_by_provider("claude").unwrap();
+        let empty = query
+            .list_conversations_by_provider("claude", &filters)
+            .unwrap();
         assert!(empty.is_empty());
     }
 
@@ -633,9 +853,48 @@ mod tests {
         let config = setup_test_data(dir.path());
         let query = DuckDbQuery::new(config).unwrap();
 
-        let results = query.search_messages("test", 10).unwrap();
+        let results = query
+            .search_messages("test", 10, &SearchFilters::default())
+            .unwrap();
         assert!(!results.is_empty());
-        assert!(results.iter().any(|r| r.snippet.to_lowercase().contains("test")));
+        assert!(results
+            .iter()
+            .any(|r| r.snippet.to_lowercase().contains("test")));
+    }
+
+    #[test]
+    fn test_search_messages_regex() {
+        let dir = tempdir().unwrap();
+        let config = setup_test_data(dir.path());
+        let query = DuckDbQuery::new(config).unwrap();
+        let filters = SearchFilters::default();
+
+        let results = query
+            .search_messages_regex(r"[Tt]est\w* (message|search)", 10, &filters)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.conversation_id == "conv-2"));
+        assert!(results
+            .iter()
+            .any(|r| r.snippet == "<mark>Testing search</mark> functionality"));
+
+        // Case-sensitive unless asked otherwise, and limited
+        assert!(query
+            .search_messages_regex("HELLO", 10, &filters)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            query
+                .search_messages_regex("(?i)hello|test", 1, &filters)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert!(matches!(
+            query.search_messages_regex("(unclosed", 10, &filters),
+            Err(StorageError::InvalidPattern(_))
+        ));
     }
 
     #[test]
@@ -644,10 +903,56 @@ mod tests {
         let config = setup_test_data(dir.path());
         let query = DuckDbQuery::new(config).unwrap();
 
-        let results = query.search_messages("xyznonexistent", 10).unwrap();
+        let results = query
+            .search_messages("xyznonexistent", 10, &SearchFilters::default())
+            .unwrap();
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_search_messages_filters_before_limit() {
+        let dir = tempdir().unwrap();
+        let config = setup_test_data(dir.path());
+        let store = ParquetStore::new(config.clone());
+
+        let mut conv = create_test_conversation("conv-3", "Claude Conversation");
+        conv.provider_id = "claude".to_string();
+        conv.created_at = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let mut reply = create_test_message("conv-3", "msg-5", "A test from the assistant");
+        reply.role = Role::Assistant;
+        store
+            .write_conversation("user-123", &conv, &[reply])
+            .unwrap();
+
+        let query = DuckDbQuery::new(config).unwrap();
+
+        let filters = SearchFilters {
+            provider: Some("claude".to_string()),
+            ..Default::default()
+        };
+        let results = query.search_messages("test", 1, &filters).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, "conv-3");
+
+        let filters = SearchFilters {
+            until: Some(Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        let results = query.search_messages("test", 1, &filters).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, "conv-3");
+
+        let filters = SearchFilters {
+            roles: vec![Role::User],
+            since: Some(Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        let results = query.search_messages("test", 10, &filters).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.conversation_id == "conv-2"));
+        assert!(results.iter().all(|r| r.role == Role::User));
+    }
+
     #[test]
     fn test_count_messages() {
         let dir = tempdir().unwrap();
@@ -696,8 +1001,14 @@ mod tests {
         let query = DuckDbQuery::new(config).unwrap();
 
         // All queries should return empty, not error
-        assert!(query.list_all_conversations().unwrap().is_empty());
-        assert!(query.search_messages("test", 10).unwrap().is_empty());
+        assert!(query
+            .list_all_conversations(&SearchFilters::default())
+            .unwrap()
+            .is_empty());
+        assert!(query
+            .search_messages("test", 10, &SearchFilters::default())
+            .unwrap()
+            .is_empty());
         assert_eq!(query.count_messages().unwrap(), 0);
         assert_eq!(query.count_conversations().unwrap(), 0);
     }
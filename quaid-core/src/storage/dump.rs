@@ -0,0 +1,180 @@
+//! Standalone SQLite dump of conversations
+//!
+//! Writes a self-contained database with plain `conversations` and `messages`
+//! tables. Unlike the main store it has no FTS index and no raw provider JSON,
+//! so it can be opened directly in notebooks or shared with other tools.
+
+use super::{content_type_name, extract_text_content, Result};
+use crate::providers::{Account, Conversation, Message};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Write conversations and their messages to a new SQLite file at `path`.
+///
+/// An existing file at `path` is replaced. Returns the number of messages written.
+pub fn write_sqlite_dump(
+    path: &Path,
+    conversations: &[(Account, Conversation, Vec<Message>)],
+) -> Result<usize> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE conversations (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            account_email TEXT NOT NULL,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            model TEXT,
+            project_id TEXT,
+            project_name TEXT,
+            is_archived INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE messages (
+            id TEXT PRIMARY KEY,
+            conversation_id TEXT NOT NULL,
+            parent_id TEXT,
+            role TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            text TEXT NOT NULL,
+            content_json TEXT NOT NULL,
+            created_at TEXT,
+            model TEXT,
+            speaker TEXT,
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+        );
+
+        CREATE INDEX idx_messages_conversation ON messages(conversation_id);
+        "#,
+    )?;
+
+    let tx = conn.transaction()?;
+    let mut message_count = 0;
+    {
+        let mut conv_stmt = tx.prepare(
+            "INSERT OR REPLACE INTO conversations
+             (id, provider, account_email, title, created_at, updated_at, model, project_id, project_name, is_archived)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+        let mut msg_stmt = tx.prepare(
+            "INSERT OR REPLACE INTO messages
+             (id, conversation_id, parent_id, role, content_type, text, content_json, created_at, model, speaker)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+
+        for (account, conv, messages) in conversations {
+            conv_stmt.execute(params![
+                conv.id,
+                account.provider.0,
+                account.email,
+                conv.title,
+                conv.created_at.to_rfc3339(),
+                conv.updated_at.to_rfc3339(),
+                conv.model,
+                conv.project_id,
+                conv.project_name,
+                conv.is_archived as i32,
+            ])?;
+
+            for msg in messages {
+                msg_stmt.execute(params![
+                    msg.id,
+                    msg.conversation_id,
+                    msg.parent_id,
+                    format!("{:?}", msg.role).to_lowercase(),
+                    content_type_name(&msg.content),
+                    extract_text_content(&msg.content),
+                    serde_json::to_string(&msg.content)?,
+                    msg.created_at.map(|dt| dt.to_rfc3339()),
+                    msg.model,
+                    msg.speaker,
+                ])?;
+                message_count += 1;
+            }
+        }
+    }
+    tx.commit()?;
+
+    Ok(message_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{MessageContent, ProviderId, Role};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_sqlite_dump() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("dump.db");
+
+        let account = Account {
+            id: "user-1".to_string(),
+            provider: ProviderId::claude(),
+            email: "me@example.com".to_string(),
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        };
+        let conv = Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "claude".to_string(),
+            title: "Commas, \"quotes\"".to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        let message = Message {
+            id: "msg-1".to_string(),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role: Role::Assistant,
+            content: MessageContent::Text {
+                text: "line one\nline two".to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+
+        let written = write_sqlite_dump(&path, &[(account, conv, vec![message])]).unwrap();
+        assert_eq!(written, 1);
+
+        let conn = Connection::open(&path).unwrap();
+        let (provider, title): (String, String) = conn
+            .query_row("SELECT provider, title FROM conversations", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(provider, "claude");
+        assert_eq!(title, "Commas, \"quotes\"");
+
+        let (role, text): (String, String) = conn
+            .query_row("SELECT role, text FROM messages", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(role, "assistant");
+        assert_eq!(text, "line one\nline two");
+
+        // No FTS tables in the dump
+        let fts: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name LIKE '%fts%'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(fts, 0);
+    }
+}
@@ -2,28 +2,114 @@
 //!
 //! Stores chunk embeddings for semantic search capabilities.
 
+use super::atomic::AtomicFile;
+use super::compactor::{read_batches, retain_rows};
 use super::{ParquetStorageConfig, Result, StorageError};
 use crate::embeddings::Chunk;
-use arrow::array::{ArrayRef, FixedSizeListArray, Float32Array, Int32Array, StringBuilder};
+use arrow::array::{
+    Array, ArrayRef, FixedSizeListArray, Float32Array, Int32Array, StringArray, StringBuilder,
+};
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
-use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::{ArrowWriter, ProjectionMask};
 use parquet::basic::Compression;
 use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::sync::Arc;
 
 /// Embedding dimension for multilingual-e5-small
 pub const EMBEDDING_DIM: i32 = 384;
 
+/// Which embedder produced the vectors in an index
+///
+/// Stored as `embeddings/metadata.json` so vectors from different models
+/// never end up in the same index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingsMetadata {
+    pub backend: String,
+    pub model: String,
+    pub dimension: usize,
+}
+
+impl EmbeddingsMetadata {
+    /// Metadata for the built-in local model
+    pub fn local_default() -> Self {
+        Self {
+            backend: "local".to_string(),
+            model: "multilingual-e5-small".to_string(),
+            dimension: EMBEDDING_DIM as usize,
+        }
+    }
+}
+
 /// Store for embeddings in Parquet format
 pub struct EmbeddingsStore {
     config: ParquetStorageConfig,
+    dimension: i32,
 }
 
 impl EmbeddingsStore {
     pub fn new(config: ParquetStorageConfig) -> Self {
-        Self { config }
+        Self::with_dimension(config, EMBEDDING_DIM as usize)
+    }
+
+    /// Store for vectors of a non-default dimension
+    pub fn with_dimension(config: ParquetStorageConfig, dimension: usize) -> Self {
+        Self {
+            config,
+            dimension: dimension as i32,
+        }
+    }
+
+    /// Read the recorded embedder metadata, if any
+    pub fn read_metadata(&self) -> Result<Option<EmbeddingsMetadata>> {
+        let path = self.config.embeddings_metadata_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    /// Record `metadata` for this index, refusing to mix embedders.
+    ///
+    /// Indexes written before metadata was recorded are assumed to come from
+    /// the local model.
+    pub fn ensure_metadata(&self, metadata: &EmbeddingsMetadata) -> Result<()> {
+        let recorded = self.read_metadata()?;
+        let existing = match &recorded {
+            Some(existing) => Some(existing.clone()),
+            None if !self.config.list_embedding_providers()?.is_empty() => {
+                Some(EmbeddingsMetadata::local_default())
+            }
+            None => None,
+        };
+
+        if let Some(existing) = existing {
+            if existing != *metadata {
+                return Err(StorageError::EmbeddingsMismatch(format!(
+                    "index was built with {} {} ({} dims), refusing to add {} {} ({} dims)",
+                    existing.backend,
+                    existing.model,
+                    existing.dimension,
+                    metadata.backend,
+                    metadata.model,
+                    metadata.dimension
+                )));
+            }
+        }
+
+        if recorded.is_none() {
+            let path = self.config.embeddings_metadata_path();
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(metadata)?)?;
+        }
+        Ok(())
     }
 
     /// Write embeddings for a conversation to Parquet
@@ -48,12 +134,12 @@ impl EmbeddingsStore {
 
         // Validate embedding dimensions
         for (i, emb) in embeddings.iter().enumerate() {
-            if emb.len() != EMBEDDING_DIM as usize {
+            if emb.len() != self.dimension as usize {
                 return Err(StorageError::Serialization(format!(
                     "Embedding {} has dimension {}, expected {}",
                     i,
                     emb.len(),
-                    EMBEDDING_DIM
+                    self.dimension
                 )));
             }
         }
@@ -66,7 +152,7 @@ impl EmbeddingsStore {
         let schema = self.embeddings_schema();
         let batch = self.create_record_batch(conversation_id, chunks, embeddings, &schema)?;
 
-        let file = File::create(&path)?;
+        let (target, file) = AtomicFile::create(&path)?;
         let props = WriterProperties::builder()
             .set_compression(Compression::ZSTD(Default::default()))
             .build();
@@ -81,10 +167,113 @@ impl EmbeddingsStore {
         writer
             .close()
             .map_err(|e| StorageError::Parquet(e.to_string()))?;
+        target.commit()?;
 
         Ok(())
     }
 
+    /// Ids of the conversations that already have embeddings for `provider`,
+    /// whether in per-conversation files or the consolidated file
+    pub fn indexed_conversation_ids(&self, provider_id: &str) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+
+        let dir = self.config.embeddings_dir(provider_id);
+        if dir.exists() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|e| e == "parquet") {
+                    if let Some(stem) = path.file_stem() {
+                        ids.insert(stem.to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+
+        let consolidated = self.config.consolidated_embeddings_path(provider_id);
+        if consolidated.exists() {
+            let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&consolidated)?)
+                .map_err(|e| StorageError::Parquet(e.to_string()))?;
+            // Column 1 is conversation_id
+            let mask = ProjectionMask::roots(builder.parquet_schema(), [1]);
+            let reader = builder
+                .with_projection(mask)
+                .build()
+                .map_err(|e| StorageError::Parquet(e.to_string()))?;
+
+            for batch in reader {
+                let batch = batch?;
+                let column = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .ok_or_else(|| {
+                        StorageError::Parquet("conversation_id is not a string column".to_string())
+                    })?;
+                for i in 0..column.len() {
+                    ids.insert(column.value(i).to_string());
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Drop the embeddings of `conversation_ids` for `provider`, returning how
+    /// many conversations had any
+    ///
+    /// Removes their per-conversation files and rewrites the consolidated file
+    /// without their rows.
+    pub fn remove_conversations(
+        &self,
+        provider_id: &str,
+        conversation_ids: &HashSet<String>,
+    ) -> Result<usize> {
+        let mut removed = HashSet::new();
+
+        for id in conversation_ids {
+            let path = self.config.embeddings_path(provider_id, id);
+            if path.exists() {
+                fs::remove_file(&path)?;
+                removed.insert(id.clone());
+            }
+        }
+
+        let consolidated = self.config.consolidated_embeddings_path(provider_id);
+        if consolidated.exists() {
+            let schema = ParquetRecordBatchReaderBuilder::try_new(File::open(&consolidated)?)
+                .map_err(|e| StorageError::Parquet(e.to_string()))?
+                .schema()
+                .clone();
+            let (target, file) = AtomicFile::create(&consolidated)?;
+            let props = WriterProperties::builder()
+                .set_compression(Compression::ZSTD(Default::default()))
+                .build();
+            let mut writer = ArrowWriter::try_new(file, schema, Some(props))
+                .map_err(|e| StorageError::Parquet(e.to_string()))?;
+
+            for batch in read_batches(&consolidated)? {
+                let batch = retain_rows(&batch?, |conversation_id, _| {
+                    if conversation_ids.contains(conversation_id) {
+                        removed.insert(conversation_id.to_string());
+                        false
+                    } else {
+                        true
+                    }
+                })?;
+                writer
+                    .write(&batch)
+                    .map_err(|e| StorageError::Parquet(e.to_string()))?;
+            }
+
+            writer
+                .close()
+                .map_err(|e| StorageError::Parquet(e.to_string()))?;
+            target.commit()?;
+        }
+
+        Ok(removed.len())
+    }
+
     fn embeddings_schema(&self) -> Arc<Schema> {
         Arc::new(Schema::new(vec![
             Field::new("chunk_id", DataType::Utf8, false),
@@ -96,7 +285,7 @@ impl EmbeddingsStore {
                 "embedding",
                 DataType::FixedSizeList(
                     Arc::new(Field::new("item", DataType::Float32, false)),
-                    EMBEDDING_DIM,
+                    self.dimension,
                 ),
                 false,
             ),
@@ -133,7 +322,7 @@ impl EmbeddingsStore {
         let values = Float32Array::from(flat_embeddings);
         let embedding_array = FixedSizeListArray::try_new(
             Arc::new(Field::new("item", DataType::Float32, false)),
-            EMBEDDING_DIM,
+            self.dimension,
             Arc::new(values),
             None,
         )
@@ -168,7 +357,9 @@ mod tests {
     }
 
     fn create_test_embedding() -> Vec<f32> {
-        (0..EMBEDDING_DIM).map(|i| i as f32 / EMBEDDING_DIM as f32).collect()
+        (0..EMBEDDING_DIM)
+            .map(|i| i as f32 / EMBEDDING_DIM as f32)
+            .collect()
     }
 
     #[test]
@@ -193,6 +384,73 @@ mod tests {
         assert!(path.exists());
     }
 
+    #[test]
+    fn test_indexed_conversation_ids() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let store = EmbeddingsStore::new(config.clone());
+        let chunks = vec![create_test_chunk("msg-1", 0, "Hello")];
+        let embeddings = vec![create_test_embedding()];
+
+        assert!(store
+            .indexed_conversation_ids("chatgpt")
+            .unwrap()
+            .is_empty());
+
+        store
+            .write_embeddings("conv-1", "chatgpt", &chunks, &embeddings)
+            .unwrap();
+        store
+            .write_embeddings("conv-2", "chatgpt", &chunks, &embeddings)
+            .unwrap();
+        crate::storage::EmbeddingsCompactor::new(config)
+            .compact_provider("chatgpt")
+            .unwrap();
+        store
+            .write_embeddings("conv-3", "chatgpt", &chunks, &embeddings)
+            .unwrap();
+
+        let ids = store.indexed_conversation_ids("chatgpt").unwrap();
+        let expected: HashSet<String> = ["conv-1", "conv-2", "conv-3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_remove_conversations() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let store = EmbeddingsStore::new(config.clone());
+        let chunks = vec![create_test_chunk("msg-1", 0, "Hello")];
+        let embeddings = vec![create_test_embedding()];
+
+        for id in ["conv-1", "conv-2"] {
+            store
+                .write_embeddings(id, "chatgpt", &chunks, &embeddings)
+                .unwrap();
+        }
+        crate::storage::EmbeddingsCompactor::new(config)
+            .compact_provider("chatgpt")
+            .unwrap();
+        for id in ["conv-3", "conv-4"] {
+            store
+                .write_embeddings(id, "chatgpt", &chunks, &embeddings)
+                .unwrap();
+        }
+
+        let gone: HashSet<String> = ["conv-1", "conv-3", "conv-5"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(store.remove_conversations("chatgpt", &gone).unwrap(), 2);
+        assert_eq!(
+            store.indexed_conversation_ids("chatgpt").unwrap(),
+            HashSet::from(["conv-2".to_string(), "conv-4".to_string()])
+        );
+    }
+
     #[test]
     fn test_write_empty_embeddings() {
         let dir = tempdir().unwrap();
@@ -265,4 +523,70 @@ mod tests {
         let path = config.embeddings_path("chatgpt", "conv-1");
         assert!(path.exists());
     }
+
+    #[test]
+    fn test_metadata_refuses_mixing_with_existing_local_embeddings() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let local = EmbeddingsStore::new(config.clone());
+
+        // Index written before metadata existed
+        local
+            .write_embeddings(
+                "conv-1",
+                "chatgpt",
+                &[create_test_chunk("msg-1", 0, "Hello")],
+                &[create_test_embedding()],
+            )
+            .unwrap();
+        assert!(local.read_metadata().unwrap().is_none());
+
+        let remote_meta = EmbeddingsMetadata {
+            backend: "remote".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            dimension: 1536,
+        };
+        let remote = EmbeddingsStore::with_dimension(config, 1536);
+        assert!(matches!(
+            remote.ensure_metadata(&remote_meta),
+            Err(StorageError::EmbeddingsMismatch(_))
+        ));
+
+        // The local model is still accepted and gets recorded
+        local
+            .ensure_metadata(&EmbeddingsMetadata::local_default())
+            .unwrap();
+        assert_eq!(
+            local.read_metadata().unwrap(),
+            Some(EmbeddingsMetadata::local_default())
+        );
+    }
+
+    #[test]
+    fn test_metadata_recorded_for_new_index() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let store = EmbeddingsStore::with_dimension(config, 8);
+        let meta = EmbeddingsMetadata {
+            backend: "remote".to_string(),
+            model: "test-embedding".to_string(),
+            dimension: 8,
+        };
+
+        store.ensure_metadata(&meta).unwrap();
+        store
+            .write_embeddings(
+                "conv-1",
+                "chatgpt",
+                &[create_test_chunk("msg-1", 0, "Hello")],
+                &[vec![0.5; 8]],
+            )
+            .unwrap();
+
+        assert_eq!(store.read_metadata().unwrap(), Some(meta.clone()));
+        assert!(store.ensure_metadata(&meta).is_ok());
+        assert!(store
+            .ensure_metadata(&EmbeddingsMetadata::local_default())
+            .is_err());
+    }
 }
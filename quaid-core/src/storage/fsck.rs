@@ -0,0 +1,450 @@
+//! Consistency checks and repair for the SQLite store
+//!
+//! The full-text index is a separate FTS5 table filled by
+//! [`Store::save_message`]. Each index row carries the id of the message it
+//! was built from, and its rowid is the message's `fts_rowid`, a plain column.
+//! Older stores keyed index rows by the message's implicit rowid, which VACUUM
+//! may renumber, so search snippets ended up attached to the wrong
+//! conversations. Opening such a store rebuilds the index.
+
+use super::{extract_text_content, Result, Store};
+use crate::providers::MessageContent;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// The full-text index table, as created by the schema
+const CREATE_FTS: &str = "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+    content,
+    conversation_id,
+    message_id UNINDEXED
+);";
+
+/// Result of [`Store::check_fts`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FtsCheck {
+    /// Index rows examined
+    pub checked: usize,
+    /// Message ids whose index row doesn't match the message's text
+    pub mismatched: Vec<String>,
+}
+
+impl FtsCheck {
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty()
+    }
+}
+
+impl Store {
+    /// Index `text` for a message, replacing whatever was indexed for it before
+    pub(super) fn index_message_text(
+        &self,
+        message_id: &str,
+        conversation_id: &str,
+        text: &str,
+    ) -> Result<()> {
+        let fts_rowid: Option<i64> = self
+            .conn
+            .prepare_cached("SELECT fts_rowid FROM messages WHERE id = ?1")?
+            .query_row(params![message_id], |row| row.get(0))
+            .optional()?
+            .flatten();
+        let Some(fts_rowid) = fts_rowid else {
+            return Ok(());
+        };
+
+        self.conn
+            .prepare_cached("DELETE FROM messages_fts WHERE rowid = ?1")?
+            .execute(params![fts_rowid])?;
+        if !text.is_empty() {
+            self.conn
+                .prepare_cached(
+                    "INSERT INTO messages_fts (rowid, content, conversation_id, message_id)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )?
+                .execute(params![fts_rowid, text, conversation_id, message_id])?;
+        }
+        Ok(())
+    }
+
+    /// Rebuild the index of a store created before index rows carried message ids
+    pub(super) fn migrate_fts(&self) -> Result<()> {
+        let has_message_id: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('messages_fts') WHERE name = 'message_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_message_id {
+            self.conn.execute_batch("DROP TABLE messages_fts;")?;
+            // Inside the migration's transaction already
+            fill_fts(&self.conn)?;
+        }
+        Ok(())
+    }
+
+    /// Re-index every message from its stored content, returning how many were indexed
+    ///
+    /// Messages without text aren't counted. Also recovers a store whose index
+    /// table was dropped, and messages inserted by hand without an `fts_rowid`.
+    pub fn rebuild_fts(&self) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let indexed = fill_fts(&tx)?;
+        tx.commit()?;
+        Ok(indexed)
+    }
+
+    /// Compare up to `sample` random index rows against the messages they point at
+    ///
+    /// A row fails the check when its message is gone, belongs to another
+    /// conversation, or no longer has the indexed text.
+    pub fn check_fts(&self, sample: usize) -> Result<FtsCheck> {
+        let mut stmt = self.conn.prepare(
+            "SELECT f.rowid, f.message_id, f.conversation_id, f.content,
+                    m.conversation_id, m.content_json
+             FROM messages_fts f
+             LEFT JOIN messages m ON m.id = f.message_id
+             ORDER BY random()
+             LIMIT ?1",
+        )?;
+        let mut rows = stmt.query(params![sample as i64])?;
+
+        let mut check = FtsCheck::default();
+        while let Some(row) = rows.next()? {
+            check.checked += 1;
+            let fts_rowid: i64 = row.get(0)?;
+            let message_id: Option<String> = row.get(1)?;
+            let indexed_conversation: String = row.get(2)?;
+            let indexed_text: String = row.get(3)?;
+            let conversation_id: Option<String> = row.get(4)?;
+            let content_json: Option<String> = row.get(5)?;
+
+            let matches = match (&conversation_id, &content_json) {
+                (Some(conversation_id), Some(content_json)) => {
+                    *conversation_id == indexed_conversation
+                        && stored_text(content_json) == indexed_text
+                }
+                _ => false,
+            };
+            if !matches {
+                check
+                    .mismatched
+                    .push(message_id.unwrap_or_else(|| format!("fts row {}", fts_rowid)));
+            }
+        }
+        Ok(check)
+    }
+
+    /// Count index rows whose message no longer exists
+    ///
+    /// Searches can still match them and turn up conversations that are gone.
+    /// [`Store::rebuild_fts`] drops them.
+    pub fn orphaned_fts_rows(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM messages_fts f
+             WHERE NOT EXISTS (SELECT 1 FROM messages m WHERE m.id = f.message_id)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Compact the database file
+    ///
+    /// Safe for the index: rows are keyed by `fts_rowid` and message id, which
+    /// VACUUM leaves alone.
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+}
+
+/// The text indexed for a message's stored `content_json`
+fn stored_text(content_json: &str) -> String {
+    serde_json::from_str::<MessageContent>(content_json)
+        .map(|content| extract_text_content(&content))
+        .unwrap_or_else(|_| content_json.to_string())
+}
+
+/// Body of [`Store::rebuild_fts`], on the caller's connection or transaction
+///
+/// SQLite can't nest transactions, so migrations, which already run in one,
+/// call this directly.
+fn fill_fts(conn: &Connection) -> Result<usize> {
+    conn.execute_batch(CREATE_FTS)?;
+    conn.execute_batch(
+        "DELETE FROM messages_fts;
+         UPDATE messages
+         SET fts_rowid = (SELECT COALESCE(MAX(fts_rowid), 0) FROM messages) + rowid
+         WHERE fts_rowid IS NULL;",
+    )?;
+
+    let mut indexed = 0;
+    let mut select =
+        conn.prepare("SELECT id, conversation_id, content_json, fts_rowid FROM messages")?;
+    let mut insert = conn.prepare(
+        "INSERT INTO messages_fts (rowid, content, conversation_id, message_id)
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    let mut rows = select.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let conversation_id: String = row.get(1)?;
+        let text = stored_text(&row.get::<_, String>(2)?);
+        if !text.is_empty() {
+            insert.execute(params![row.get::<_, i64>(3)?, text, conversation_id, id])?;
+            indexed += 1;
+        }
+    }
+    Ok(indexed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, Message, ProviderId, Role};
+    use crate::storage::SearchFilters;
+
+    fn store_with_messages(count: usize) -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        for i in 0..count {
+            store
+                .save_conversation(
+                    "user-1",
+                    &Conversation {
+                        id: format!("conv-{}", i),
+                        provider_id: "claude".to_string(),
+                        title: format!("Conversation {}", i),
+                        created_at: chrono::Utc::now(),
+                        updated_at: chrono::Utc::now(),
+                        model: None,
+                        project_id: None,
+                        project_name: None,
+                        is_archived: false,
+                    },
+                )
+                .unwrap();
+            store
+                .save_message(&Message {
+                    id: format!("msg-{}", i),
+                    conversation_id: format!("conv-{}", i),
+                    parent_id: None,
+                    role: Role::User,
+                    content: MessageContent::Text {
+                        text: format!("topic{} notes", i),
+                    },
+                    created_at: None,
+                    model: None,
+                    speaker: None,
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    /// What VACUUM does to a table when it can't copy it page by page: rows
+    /// are re-inserted without their rowid, closing the gaps. Recent SQLite
+    /// versions usually avoid this, but don't promise to, so do it directly.
+    fn renumber_messages(store: &Store) {
+        store
+            .conn
+            .execute_batch(
+                "CREATE TEMP TABLE messages_copy AS SELECT * FROM messages;
+                 DELETE FROM messages;
+                 INSERT INTO messages SELECT * FROM messages_copy ORDER BY id;
+                 DROP TABLE messages_copy;",
+            )
+            .unwrap();
+        store.vacuum().unwrap();
+    }
+
+    /// Delete every other message (and its index row), leaving rowid gaps
+    fn delete_even_messages(store: &Store, count: usize) {
+        for i in (0..count).step_by(2) {
+            store
+                .conn
+                .execute(
+                    "DELETE FROM messages_fts WHERE message_id = ?1",
+                    params![format!("msg-{}", i)],
+                )
+                .unwrap();
+            store
+                .conn
+                .execute(
+                    "DELETE FROM messages WHERE id = ?1",
+                    params![format!("msg-{}", i)],
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_renumbered_rowids_corrupt_rowid_keyed_index() {
+        let store = store_with_messages(10);
+        // The old layout: index rows keyed by the messages' implicit rowid
+        store
+            .conn
+            .execute_batch(
+                "DROP TABLE messages_fts;
+                 CREATE VIRTUAL TABLE messages_fts USING fts5(
+                     content, conversation_id, message_id UNINDEXED
+                 );
+                 INSERT INTO messages_fts (rowid, content, conversation_id)
+                 SELECT rowid, json_extract(content_json, '$.text'), conversation_id
+                 FROM messages;
+                 UPDATE messages_fts SET message_id =
+                     (SELECT id FROM messages WHERE messages.rowid = messages_fts.rowid);",
+            )
+            .unwrap();
+        delete_even_messages(&store, 10);
+        renumber_messages(&store);
+
+        // What the old search join saw: snippets pointing at other messages
+        let misattributed: i64 = store
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM messages_fts f JOIN messages m ON f.rowid = m.rowid
+                 WHERE f.conversation_id != m.conversation_id",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(misattributed > 0);
+    }
+
+    #[test]
+    fn test_renumbered_rowids_keep_index_linked() {
+        let store = store_with_messages(10);
+        delete_even_messages(&store, 10);
+        renumber_messages(&store);
+
+        let check = store.check_fts(100).unwrap();
+        assert_eq!(check.checked, 5);
+        assert!(check.is_ok(), "{:?}", check.mismatched);
+
+        let hits = store
+            .search("topic7", 10, &SearchFilters::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, "conv-7");
+
+        // Updating a message after VACUUM replaces its index row
+        store
+            .save_message(&Message {
+                id: "msg-7".to_string(),
+                conversation_id: "conv-7".to_string(),
+                parent_id: None,
+                role: Role::User,
+                content: MessageContent::Text {
+                    text: "rewritten".to_string(),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            })
+            .unwrap();
+        assert!(store
+            .search("topic7", 10, &SearchFilters::default())
+            .unwrap()
+            .is_empty());
+        assert!(store.check_fts(100).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_check_detects_and_rebuild_repairs() {
+        let store = store_with_messages(4);
+        store
+            .conn
+            .execute(
+                "UPDATE messages_fts SET content = 'stale' WHERE message_id = 'msg-1'",
+                [],
+            )
+            .unwrap();
+
+        let check = store.check_fts(100).unwrap();
+        assert_eq!(check.mismatched, vec!["msg-1".to_string()]);
+
+        assert_eq!(store.rebuild_fts().unwrap(), 4);
+        assert!(store.check_fts(100).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_orphaned_index_rows_are_counted_and_dropped_by_rebuild() {
+        let store = store_with_messages(3);
+        assert_eq!(store.orphaned_fts_rows().unwrap(), 0);
+
+        store
+            .conn
+            .execute("DELETE FROM messages WHERE id = 'msg-1'", [])
+            .unwrap();
+        assert_eq!(store.orphaned_fts_rows().unwrap(), 1);
+
+        assert_eq!(store.rebuild_fts().unwrap(), 2);
+        assert_eq!(store.orphaned_fts_rows().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_rebuild_recreates_a_missing_index() {
+        let store = store_with_messages(3);
+        store
+            .conn
+            .execute_batch(
+                "DROP TABLE messages_fts;
+                 INSERT INTO messages (id, conversation_id, role, content_type, content_json)
+                 VALUES ('imported', 'conv-0', 'user', 'text', '{\"type\":\"text\",\"text\":\"bulk import\"}');",
+            )
+            .unwrap();
+
+        assert_eq!(store.rebuild_fts().unwrap(), 4);
+        assert!(store.check_fts(100).unwrap().is_ok());
+        let hits = store.search("bulk", 10, &SearchFilters::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, "conv-0");
+    }
+
+    #[test]
+    fn test_old_index_is_rebuilt_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quaid.db");
+        {
+            let store = Store::open(&path).unwrap();
+            store
+                .save_account(&Account {
+                    id: "user-1".to_string(),
+                    provider: ProviderId::claude(),
+                    email: "me@example.com".to_string(),
+                    name: None,
+                    avatar_url: None,
+                    workspace_id: None,
+                })
+                .unwrap();
+        }
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "DROP TABLE messages_fts;
+             CREATE VIRTUAL TABLE messages_fts USING fts5(content, conversation_id);
+             INSERT INTO conversations (id, account_id, provider_id, title, created_at, updated_at)
+             VALUES ('conv-1', 'user-1', 'claude', 'Old', '2024-01-01T00:00:00+00:00',
+                     '2024-01-01T00:00:00+00:00');
+             INSERT INTO messages (id, conversation_id, role, content_type, content_json, fts_rowid)
+             VALUES ('msg-1', 'conv-1', 'user', 'text', '{\"type\":\"text\",\"text\":\"legacy notes\"}', 1);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let store = Store::open(&path).unwrap();
+        let hits = store
+            .search("legacy", 10, &SearchFilters::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, "conv-1");
+        assert!(store.check_fts(10).unwrap().is_ok());
+    }
+}
@@ -0,0 +1,492 @@
+//! Versioned schema migrations
+//!
+//! The schema version is kept in SQLite's `user_version` pragma. Opening a
+//! store applies, in order, every migration newer than the stored version.
+//! Each step runs in a transaction with the update of its version, so a step
+//! that fails partway leaves the store as it was before it. A store without a
+//! version is at v0. New schema changes go in a new step at the end of
+//! [`MIGRATIONS`], never in an existing one, so stores already past it get
+//! them too.
+
+use super::{attachment_text, Result, StorageError, Store};
+use rusqlite::params;
+
+/// A step taking the schema from the version before it to `version`
+struct Migration {
+    version: u32,
+    apply: fn(&Store) -> Result<()>,
+}
+
+/// Every migration, oldest first
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        apply: Store::schema_v1,
+    },
+    Migration {
+        version: 2,
+        apply: Store::schema_v2,
+    },
+    Migration {
+        version: 3,
+        apply: Store::schema_v3,
+    },
+    Migration {
+        version: 4,
+        apply: Store::schema_v4,
+    },
+    Migration {
+        version: 5,
+        apply: Store::schema_v5,
+    },
+    Migration {
+        version: 6,
+        apply: Store::schema_v6,
+    },
+];
+
+/// Version of the schema this build creates
+const SCHEMA_VERSION: u32 = 6;
+
+/// Apply the migrations a store hasn't had yet
+///
+/// Fails without touching the store when it was migrated by a newer build.
+pub(super) fn run(store: &Store) -> Result<()> {
+    let current = store.schema_version()?;
+    if current > SCHEMA_VERSION {
+        return Err(StorageError::SchemaTooNew {
+            found: current,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    apply(store, MIGRATIONS.iter().filter(|m| m.version > current))
+}
+
+/// Apply `migrations` in order, each with its version in one transaction
+fn apply<'a>(store: &Store, migrations: impl Iterator<Item = &'a Migration>) -> Result<()> {
+    for migration in migrations {
+        let tx = store.conn.unchecked_transaction()?;
+        (migration.apply)(store)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+impl Store {
+    /// Schema version of the database, 0 before versioning
+    pub fn schema_version(&self) -> Result<u32> {
+        Ok(self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    /// v1: the schema as it was before versioning
+    ///
+    /// Stores from before then carry no version and any mix of older tables,
+    /// so every statement here checks what's there first.
+    fn schema_v1(&self) -> Result<()> {
+        self.conn.execute_batch(
+            r#"
+            -- Accounts table
+            CREATE TABLE IF NOT EXISTS accounts (
+                id TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                email TEXT NOT NULL,
+                name TEXT,
+                avatar_url TEXT,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                workspace_id TEXT NOT NULL DEFAULT '',
+                UNIQUE(provider, email, workspace_id)
+            );
+
+            -- Conversations table
+            CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                account_id TEXT NOT NULL,
+                provider_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                model TEXT,
+                project_id TEXT,
+                project_name TEXT,
+                is_archived INTEGER DEFAULT 0,
+                raw_json TEXT,
+                web_url TEXT,
+                FOREIGN KEY (account_id) REFERENCES accounts(id)
+            );
+
+            -- Projects table (names for conversations.project_id)
+            CREATE TABLE IF NOT EXISTS projects (
+                id TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT,
+                created_at TEXT
+            );
+
+            -- Outcome of the last pulls of each account
+            CREATE TABLE IF NOT EXISTS sync_state (
+                provider TEXT NOT NULL,
+                account_id TEXT NOT NULL,
+                last_pull_at TEXT,
+                last_full_pull_at TEXT,
+                conversations_seen INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                PRIMARY KEY (provider, account_id)
+            );
+
+            -- Messages table
+            CREATE TABLE IF NOT EXISTS messages (
+                id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL,
+                parent_id TEXT,
+                role TEXT NOT NULL,
+                content_type TEXT NOT NULL,
+                content_json TEXT NOT NULL,
+                created_at TEXT,
+                model TEXT,
+                raw_json TEXT,
+                fts_rowid INTEGER,
+                FOREIGN KEY (conversation_id) REFERENCES conversations(id)
+            );
+
+            -- Attachments table
+            CREATE TABLE IF NOT EXISTS attachments (
+                id TEXT PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                download_url TEXT NOT NULL,
+                width INTEGER,
+                height INTEGER,
+                local_path TEXT,
+                downloaded_at TEXT,
+                sha256 TEXT,
+                duplicate_of TEXT,
+                failed_at TEXT,
+                failure_reason TEXT,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                provider_id TEXT,
+                account_id TEXT,
+                FOREIGN KEY (message_id) REFERENCES messages(id)
+            );
+
+            -- Full-text search on messages
+            CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                content,
+                conversation_id,
+                message_id UNINDEXED
+            );
+
+            -- Note: FTS is populated manually via save_message, not triggers
+            -- This avoids issues with json_extract on complex content types.
+            -- FTS rows are keyed by messages.fts_rowid, never messages.rowid:
+            -- VACUUM may renumber implicit rowids.
+
+            -- Indexes
+            CREATE INDEX IF NOT EXISTS idx_conversations_account ON conversations(account_id);
+            CREATE INDEX IF NOT EXISTS idx_conversations_updated ON conversations(updated_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages(conversation_id);
+            CREATE INDEX IF NOT EXISTS idx_attachments_message ON attachments(message_id);
+            "#,
+        )?;
+
+        // Columns added after the first release
+        self.add_column_if_missing("attachments", "sha256", "TEXT")?;
+        self.add_column_if_missing("attachments", "duplicate_of", "TEXT")?;
+        self.add_column_if_missing("conversations", "web_url", "TEXT")?;
+        self.add_column_if_missing("messages", "fts_rowid", "INTEGER")?;
+        self.add_column_if_missing("attachments", "failed_at", "TEXT")?;
+        self.add_column_if_missing("attachments", "failure_reason", "TEXT")?;
+        self.add_column_if_missing("attachments", "text_indexed_at", "TEXT")?;
+        self.add_column_if_missing("attachments", "attempts", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("attachments", "last_error", "TEXT")?;
+        self.add_column_if_missing("attachments", "provider_id", "TEXT")?;
+        self.add_column_if_missing("attachments", "account_id", "TEXT")?;
+        self.add_column_if_missing("projects", "description", "TEXT")?;
+        self.add_column_if_missing("projects", "created_at", "TEXT")?;
+        self.add_column_if_missing("attachments", "width", "INTEGER")?;
+        self.add_column_if_missing("attachments", "height", "INTEGER")?;
+        self.migrate_accounts()?;
+        self.conn
+            .execute_batch(attachment_text::CREATE_ATTACHMENT_TEXT)?;
+        self.conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_attachments_sha256 ON attachments(sha256);
+             CREATE INDEX IF NOT EXISTS idx_attachments_account ON attachments(account_id);
+             UPDATE attachments SET (provider_id, account_id) = (
+                 SELECT c.provider_id, c.account_id FROM messages m
+                 JOIN conversations c ON c.id = m.conversation_id
+                 WHERE m.id = attachments.message_id)
+             WHERE account_id IS NULL;
+             UPDATE messages SET fts_rowid = rowid WHERE fts_rowid IS NULL;
+             CREATE UNIQUE INDEX IF NOT EXISTS idx_messages_fts_rowid ON messages(fts_rowid);",
+        )?;
+        self.migrate_fts()?;
+        Ok(())
+    }
+
+    /// v2: where paginated listings stopped, so interrupted pulls resume
+    fn schema_v2(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sync_cursors (
+                 provider TEXT NOT NULL,
+                 account_id TEXT NOT NULL,
+                 cursor TEXT,
+                 last_synced_at TEXT,
+                 PRIMARY KEY (provider, account_id)
+             );",
+        )?;
+        Ok(())
+    }
+
+    /// v3: who spoke each line of a meeting transcript
+    fn schema_v3(&self) -> Result<()> {
+        self.add_column_if_missing("messages", "speaker", "TEXT")
+    }
+
+    /// v4: files imported from disk, so re-imports skip the unchanged ones
+    fn schema_v4(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS imported_files (
+                 provider TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 modified_at TEXT NOT NULL,
+                 imported_at TEXT NOT NULL,
+                 PRIMARY KEY (provider, path)
+             );",
+        )?;
+        Ok(())
+    }
+
+    /// v5: when a full pull found a conversation deleted upstream
+    fn schema_v5(&self) -> Result<()> {
+        self.add_column_if_missing("conversations", "remote_deleted_at", "TEXT")
+    }
+
+    /// v6: earlier versions of conversations, kept by `--keep-history` pulls
+    fn schema_v6(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversation_revisions (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 conversation_id TEXT NOT NULL,
+                 replaced_at TEXT NOT NULL,
+                 title TEXT NOT NULL,
+                 messages_json TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_conversation_revisions_conversation
+                 ON conversation_revisions(conversation_id);",
+        )?;
+        Ok(())
+    }
+
+    fn add_column_if_missing(&self, table: &str, column: &str, decl: &str) -> Result<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            self.conn.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {};",
+                table, column, decl
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Rebuild an accounts table that predates workspaces
+    ///
+    /// Its `UNIQUE(provider, email)` allows one account per sign-in, and SQLite
+    /// can't alter a constraint in place.
+    fn migrate_accounts(&self) -> Result<()> {
+        let has_workspace: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('accounts') WHERE name = 'workspace_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        // Part of the migration's transaction
+        if !has_workspace {
+            self.conn.execute_batch(
+                "CREATE TABLE accounts_new (
+                     id TEXT PRIMARY KEY,
+                     provider TEXT NOT NULL,
+                     email TEXT NOT NULL,
+                     name TEXT,
+                     avatar_url TEXT,
+                     created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                     workspace_id TEXT NOT NULL DEFAULT '',
+                     UNIQUE(provider, email, workspace_id)
+                 );
+                 INSERT INTO accounts_new (id, provider, email, name, avatar_url, created_at)
+                 SELECT id, provider, email, name, avatar_url, created_at FROM accounts;
+                 DROP TABLE accounts;
+                 ALTER TABLE accounts_new RENAME TO accounts;",
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ProviderId;
+    use crate::storage::SearchFilters;
+    use rusqlite::Connection;
+
+    /// The tables of a store from the first release, before versioning
+    const SCHEMA_V0: &str = r#"
+        CREATE TABLE accounts (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            email TEXT NOT NULL,
+            name TEXT,
+            avatar_url TEXT,
+            created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(provider, email)
+        );
+        CREATE TABLE conversations (
+            id TEXT PRIMARY KEY,
+            account_id TEXT NOT NULL,
+            provider_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            model TEXT,
+            project_id TEXT,
+            project_name TEXT,
+            is_archived INTEGER DEFAULT 0,
+            raw_json TEXT
+        );
+        CREATE TABLE messages (
+            id TEXT PRIMARY KEY,
+            conversation_id TEXT NOT NULL,
+            parent_id TEXT,
+            role TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            content_json TEXT NOT NULL,
+            created_at TEXT,
+            model TEXT,
+            raw_json TEXT
+        );
+        CREATE TABLE attachments (
+            id TEXT PRIMARY KEY,
+            message_id TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            download_url TEXT NOT NULL,
+            local_path TEXT,
+            downloaded_at TEXT
+        );
+        CREATE VIRTUAL TABLE messages_fts USING fts5(content, conversation_id);
+
+        INSERT INTO accounts (id, provider, email, name)
+        VALUES ('user-1', 'claude', 'me@example.com', 'Me');
+        INSERT INTO conversations (id, account_id, provider_id, title, created_at, updated_at)
+        VALUES ('conv-1', 'user-1', 'claude', 'Ingress notes',
+                '2024-05-01T10:00:00+00:00', '2024-05-01T10:05:00+00:00');
+        INSERT INTO messages (id, conversation_id, role, content_type, content_json)
+        VALUES ('msg-1', 'conv-1', 'user', 'text',
+                '{"type":"text","text":"How do I route traffic with a kubernetes ingress?"}');
+        INSERT INTO messages_fts (rowid, content, conversation_id)
+        VALUES (1, 'How do I route traffic with a kubernetes ingress?', 'conv-1');
+        INSERT INTO attachments (id, message_id, filename, mime_type, size_bytes, download_url)
+        VALUES ('att-1', 'msg-1', 'diagram.png', 'image/png', 1024, 'file-1');
+    "#;
+
+    #[test]
+    fn test_migrations_are_numbered_in_order() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as u32 + 1);
+        }
+        assert_eq!(MIGRATIONS.last().unwrap().version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_unversioned_store_is_migrated_without_data_loss() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quaid.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(SCHEMA_V0)
+            .unwrap();
+
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), SCHEMA_VERSION);
+
+        let account = store
+            .get_account(&ProviderId::claude(), "me@example.com")
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.name.as_deref(), Some("Me"));
+        let conv = store.get_conversation("conv-1").unwrap().unwrap();
+        assert_eq!(conv.title, "Ingress notes");
+        assert_eq!(store.get_messages("conv-1").unwrap().len(), 1);
+
+        let hits = store
+            .search("kubernetes", 10, &SearchFilters::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, "conv-1");
+
+        // Attachments pick up the account of their conversation
+        let pending = store.get_pending_attachments_for_account("user-1").unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].filename, "diagram.png");
+        drop(store);
+
+        // Opening again finds nothing left to do
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), SCHEMA_VERSION);
+        assert_eq!(store.stats().unwrap().messages, 1);
+    }
+
+    #[test]
+    fn test_failed_migration_is_rolled_back() {
+        let store = Store::in_memory().unwrap();
+        let failing = Migration {
+            version: SCHEMA_VERSION + 1,
+            apply: |store| {
+                store
+                    .conn
+                    .execute_batch("CREATE TABLE half_done (id TEXT);")?;
+                Err(StorageError::Serialization("step failed".to_string()))
+            },
+        };
+
+        assert!(apply(&store, [&failing].into_iter()).is_err());
+        assert_eq!(store.schema_version().unwrap(), SCHEMA_VERSION);
+        let created: bool = store
+            .conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE name = 'half_done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!created);
+    }
+
+    #[test]
+    fn test_store_from_a_newer_build_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quaid.db");
+        let store = Store::open(&path).unwrap();
+        store
+            .conn
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        drop(store);
+
+        assert!(matches!(
+            Store::open(&path),
+            Err(StorageError::SchemaTooNew { found, supported })
+                if found == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION
+        ));
+    }
+}
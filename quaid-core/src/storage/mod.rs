@@ -2,18 +2,49 @@
 //!
 //! Stores conversations, messages, and attachments with full-text search support.
 
+pub mod analytics;
+mod atomic;
+pub mod attachment_text;
+pub mod attachments;
 pub mod compactor;
+pub mod dedupe;
+pub mod diff;
 pub mod duckdb;
+pub mod dump;
 pub mod embeddings;
+pub mod fsck;
+mod migrations;
 pub mod parquet;
+pub mod remote_deletions;
+pub mod revisions;
+pub mod scan;
+pub mod skew;
+pub mod sync_state;
 pub mod traits;
 
+pub use analytics::{ConversationSize, MonthCount};
+pub use attachments::{
+    AttachmentDownload, AttachmentEntry, AttachmentFile, AttachmentFilter, AttachmentState,
+    DownloadedCopy,
+};
 pub use compactor::{CompactionResult, EmbeddingsCompactor, ProviderStatus};
+pub use dedupe::{DedupeReport, Duplicates, MergedMessages};
+pub use diff::{ChangeKind, ConversationChange, DiffSummary, SkipRule, SyncDecision};
 pub use embeddings::EmbeddingsStore;
+pub use fsck::FtsCheck;
+pub use remote_deletions::RemoteDeletions;
+pub use revisions::{Revision, RevisionDiff};
+pub use scan::ScannedMessage;
+pub use skew::ClampedTimestamp;
+pub use sync_state::{AccountStats, SyncCursor, SyncState};
 pub use traits::*;
 
-use crate::providers::{Account, Attachment, Conversation, Message, ProviderId};
+use crate::providers::{Account, Attachment, Conversation, Message, Project, ProviderId};
+use attachments::{attachment_from_row, sha256_file};
+use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::Path;
 use thiserror::Error;
 
@@ -42,10 +73,22 @@ pub enum StorageError {
 
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+
+    #[error("Embeddings mismatch: {0}")]
+    EmbeddingsMismatch(String),
+
+    #[error("Invalid regex: {0}")]
+    InvalidPattern(String),
+
+    #[error("Database schema v{found} needs a newer quaid (this one supports v{supported})")]
+    SchemaTooNew { found: u32, supported: u32 },
 }
 
 pub type Result<T> = std::result::Result<T, StorageError>;
 
+/// How long a connection waits for another one's lock before failing
+pub const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// Main storage interface
 pub struct Store {
     conn: Connection,
@@ -53,8 +96,13 @@ pub struct Store {
 
 impl Store {
     /// Open or create a store at the given path
+    ///
+    /// The database uses WAL so readers (search, the web viewer) don't block
+    /// a running pull, and waits up to [`BUSY_TIMEOUT`] for a lock.
     pub fn open(path: &Path) -> Result<Self> {
         let conn = Connection::open(path)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
         let store = Self { conn };
         store.migrate()?;
         Ok(store)
@@ -68,81 +116,9 @@ impl Store {
         Ok(store)
     }
 
-    /// Run database migrations
+    /// Bring the schema up to date; see [`migrations`]
     fn migrate(&self) -> Result<()> {
-        self.conn.execute_batch(
-            r#"
-            -- Accounts table
-            CREATE TABLE IF NOT EXISTS accounts (
-                id TEXT PRIMARY KEY,
-                provider TEXT NOT NULL,
-                email TEXT NOT NULL,
-                name TEXT,
-                avatar_url TEXT,
-                created_at TEXT DEFAULT CURRENT_TIMESTAMP,
-                UNIQUE(provider, email)
-            );
-
-            -- Conversations table
-            CREATE TABLE IF NOT EXISTS conversations (
-                id TEXT PRIMARY KEY,
-                account_id TEXT NOT NULL,
-                provider_id TEXT NOT NULL,
-                title TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                model TEXT,
-                project_id TEXT,
-                project_name TEXT,
-                is_archived INTEGER DEFAULT 0,
-                raw_json TEXT,
-                FOREIGN KEY (account_id) REFERENCES accounts(id)
-            );
-
-            -- Messages table
-            CREATE TABLE IF NOT EXISTS messages (
-                id TEXT PRIMARY KEY,
-                conversation_id TEXT NOT NULL,
-                parent_id TEXT,
-                role TEXT NOT NULL,
-                content_type TEXT NOT NULL,
-                content_json TEXT NOT NULL,
-                created_at TEXT,
-                model TEXT,
-                raw_json TEXT,
-                FOREIGN KEY (conversation_id) REFERENCES conversations(id)
-            );
-
-            -- Attachments table
-            CREATE TABLE IF NOT EXISTS attachments (
-                id TEXT PRIMARY KEY,
-                message_id TEXT NOT NULL,
-                filename TEXT NOT NULL,
-                mime_type TEXT NOT NULL,
-                size_bytes INTEGER NOT NULL,
-                download_url TEXT NOT NULL,
-                local_path TEXT,
-                downloaded_at TEXT,
-                FOREIGN KEY (message_id) REFERENCES messages(id)
-            );
-
-            -- Full-text search on messages
-            CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
-                content,
-                conversation_id
-            );
-
-            -- Note: FTS is populated manually via save_message, not triggers
-            -- This avoids issues with json_extract on complex content types
-
-            -- Indexes
-            CREATE INDEX IF NOT EXISTS idx_conversations_account ON conversations(account_id);
-            CREATE INDEX IF NOT EXISTS idx_conversations_updated ON conversations(updated_at DESC);
-            CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages(conversation_id);
-            CREATE INDEX IF NOT EXISTS idx_attachments_message ON attachments(message_id);
-            "#,
-        )?;
-        Ok(())
+        migrations::run(self)
     }
 
     // Account operations
@@ -150,12 +126,13 @@ impl Store {
     pub fn save_account(&self, account: &Account) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO accounts (id, provider, email, name, avatar_url)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            INSERT INTO accounts (id, provider, email, name, avatar_url, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
             ON CONFLICT(id) DO UPDATE SET
                 email = excluded.email,
                 name = excluded.name,
-                avatar_url = excluded.avatar_url
+                avatar_url = excluded.avatar_url,
+                workspace_id = excluded.workspace_id
             "#,
             params![
                 account.id,
@@ -163,25 +140,20 @@ impl Store {
                 account.email,
                 account.name,
                 account.avatar_url,
+                account.workspace_id.as_deref().unwrap_or(""),
             ],
         )?;
         Ok(())
     }
 
+    /// The personal account signed in as `email`, not one of its workspaces
     pub fn get_account(&self, provider: &ProviderId, email: &str) -> Result<Option<Account>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, provider, email, name, avatar_url FROM accounts WHERE provider = ?1 AND email = ?2"
+            "SELECT id, provider, email, name, avatar_url, NULLIF(workspace_id, '') FROM accounts
+             WHERE provider = ?1 AND email = ?2 AND workspace_id = ''",
         )?;
 
-        let result = stmt.query_row(params![provider.to_string(), email], |row| {
-            Ok(Account {
-                id: row.get(0)?,
-                provider: ProviderId(row.get(1)?),
-                email: row.get(2)?,
-                name: row.get(3)?,
-                avatar_url: row.get(4)?,
-            })
-        });
+        let result = stmt.query_row(params![provider.to_string(), email], account_from_row);
 
         match result {
             Ok(account) => Ok(Some(account)),
@@ -191,37 +163,83 @@ impl Store {
     }
 
     pub fn list_accounts(&self) -> Result<Vec<Account>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, provider, email, name, avatar_url FROM accounts")?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, provider, email, name, avatar_url, NULLIF(workspace_id, '') FROM accounts",
+        )?;
 
         let accounts = stmt
-            .query_map([], |row| {
-                Ok(Account {
-                    id: row.get(0)?,
-                    provider: ProviderId(row.get(1)?),
-                    email: row.get(2)?,
-                    name: row.get(3)?,
-                    avatar_url: row.get(4)?,
-                })
-            })?
+            .query_map([], account_from_row)?
             .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(accounts)
     }
 
+    /// Delete an account with its conversations, messages, attachments and
+    /// pull history, returning the conversations deleted
+    ///
+    /// Their Parquet files and embeddings are left to the caller.
+    pub fn delete_account(&self, account_id: &str) -> Result<Vec<Conversation>> {
+        let conversations = self.list_conversations(account_id)?;
+        let tx = self.conn.unchecked_transaction()?;
+        for conv in &conversations {
+            tx.execute(
+                "DELETE FROM attachment_text WHERE message_id IN
+                     (SELECT id FROM messages WHERE conversation_id = ?1)",
+                params![conv.id],
+            )?;
+            tx.execute(
+                "DELETE FROM messages_fts WHERE conversation_id = ?1",
+                params![conv.id],
+            )?;
+            tx.execute(
+                "DELETE FROM messages WHERE conversation_id = ?1",
+                params![conv.id],
+            )?;
+            tx.execute(
+                "DELETE FROM conversation_revisions WHERE conversation_id = ?1",
+                params![conv.id],
+            )?;
+        }
+        for table in ["attachments", "conversations", "sync_state", "sync_cursors"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE account_id = ?1", table),
+                params![account_id],
+            )?;
+        }
+        tx.execute("DELETE FROM accounts WHERE id = ?1", params![account_id])?;
+        tx.commit()?;
+        Ok(conversations)
+    }
+
     // Conversation operations
 
+    /// Save a conversation, clamping timestamps too far in the future
+    ///
+    /// See [`skew`]; each clamped timestamp is reported on stderr.
     pub fn save_conversation(&self, account_id: &str, conv: &Conversation) -> Result<()> {
+        warn_clamped(&self.write_conversation(account_id, conv)?);
+        Ok(())
+    }
+
+    fn write_conversation(
+        &self,
+        account_id: &str,
+        conv: &Conversation,
+    ) -> Result<Vec<ClampedTimestamp>> {
+        let (conv, clamped) = skew::sanitize_conversation(conv, chrono::Utc::now());
         self.conn.execute(
             r#"
-            INSERT INTO conversations (id, account_id, provider_id, title, created_at, updated_at, model, project_id, project_name, is_archived)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            INSERT INTO conversations (id, account_id, provider_id, title, created_at, updated_at, model, project_id, project_name, is_archived, raw_json)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8,
+                    COALESCE(?9, (SELECT name FROM projects WHERE id = ?8)), ?10, ?11)
             ON CONFLICT(id) DO UPDATE SET
                 title = excluded.title,
                 updated_at = excluded.updated_at,
                 model = excluded.model,
-                is_archived = excluded.is_archived
+                project_id = excluded.project_id,
+                project_name = excluded.project_name,
+                is_archived = excluded.is_archived,
+                raw_json = COALESCE(excluded.raw_json, raw_json)
             "#,
             params![
                 conv.id,
@@ -234,9 +252,10 @@ impl Store {
                 conv.project_id,
                 conv.project_name,
                 conv.is_archived as i32,
+                skew::raw_json(&clamped),
             ],
         )?;
-        Ok(())
+        Ok(clamped)
     }
 
     /// Get just the updated_at timestamp for a conversation (for incremental sync)
@@ -268,23 +287,7 @@ impl Store {
              FROM conversations WHERE id = ?1"
         )?;
 
-        let result = stmt.query_row(params![id], |row| {
-            Ok(Conversation {
-                id: row.get(0)?,
-                provider_id: row.get(1)?,
-                title: row.get(2)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or_else(|_| chrono::Utc::now()),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or_else(|_| chrono::Utc::now()),
-                model: row.get(5)?,
-                project_id: row.get(6)?,
-                project_name: row.get(7)?,
-                is_archived: row.get::<_, i32>(8)? != 0,
-            })
-        });
+        let result = stmt.query_row(params![id], conversation_from_row);
 
         match result {
             Ok(conv) => Ok(Some(conv)),
@@ -293,6 +296,67 @@ impl Store {
         }
     }
 
+    /// Remember the provider's own link to a conversation
+    pub fn set_conversation_web_url(&self, id: &str, url: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE conversations SET web_url = ?2 WHERE id = ?1",
+            params![id, url],
+        )?;
+        Ok(())
+    }
+
+    /// Link to a conversation in its provider's web app: the one recorded at
+    /// sync time, or else one derived from the provider and id
+    pub fn conversation_web_url(&self, conv: &Conversation) -> Result<Option<String>> {
+        let stored: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT web_url FROM conversations WHERE id = ?1",
+                params![conv.id],
+                |row| row.get(0),
+            )
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+        Ok(stored.or_else(|| conv.web_url()))
+    }
+
+    /// Note in a conversation's `raw_json` where it came from, like `share`
+    /// for one imported from a share link rather than pulled
+    pub fn set_conversation_source(&self, id: &str, source: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE conversations SET raw_json = json_set(COALESCE(raw_json, '{}'), '$.source', ?2)
+             WHERE id = ?1",
+            params![id, source],
+        )?;
+        Ok(())
+    }
+
+    /// Where a conversation came from, if it wasn't pulled
+    pub fn conversation_source(&self, id: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT json_extract(raw_json, '$.source') FROM conversations WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(source) => Ok(source),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Set a conversation's title. Returns false if the conversation doesn't exist.
+    pub fn rename_conversation(&self, id: &str, title: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE conversations SET title = ?2 WHERE id = ?1",
+            params![id, title],
+        )?;
+        Ok(updated > 0)
+    }
+
     pub fn list_conversations(&self, account_id: &str) -> Result<Vec<Conversation>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, provider_id, title, created_at, updated_at, model, project_id, project_name, is_archived
@@ -300,77 +364,271 @@ impl Store {
         )?;
 
         let convs = stmt
+            .query_map(params![account_id], conversation_from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(convs)
+    }
+
+    /// Up to `limit` of an account's conversations, most recently updated
+    /// first, skipping the first `offset`
+    ///
+    /// Ties on `updated_at` are broken by id, so consecutive pages neither
+    /// repeat nor miss a conversation.
+    pub fn get_conversations_page(
+        &self,
+        account_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<ConversationPage> {
+        let total: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM conversations WHERE account_id = ?1",
+            params![account_id],
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, provider_id, title, created_at, updated_at, model, project_id, project_name, is_archived
+             FROM conversations WHERE account_id = ?1 ORDER BY updated_at DESC, id
+             LIMIT ?2 OFFSET ?3",
+        )?;
+        let conversations = stmt
+            .query_map(
+                params![account_id, limit as i64, offset as i64],
+                conversation_from_row,
+            )?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(ConversationPage {
+            conversations,
+            offset,
+            total: total as usize,
+        })
+    }
+
+    /// Up to `limit` conversations of every account, or of `provider`'s, most
+    /// recently updated first, starting just past `after`
+    ///
+    /// `created` bounds the creation time on either side. Paging by the last
+    /// conversation seen rather than by offset keeps pages stable while new
+    /// conversations are being pulled, and doesn't rescan skipped rows.
+    pub fn get_conversations_after(
+        &self,
+        provider: Option<&str>,
+        created: (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+        after: Option<&ConversationCursor>,
+        limit: usize,
+    ) -> Result<Vec<Conversation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, provider_id, title, created_at, updated_at, model, project_id, project_name, is_archived
+             FROM conversations
+             WHERE (?1 IS NULL OR account_id IN (SELECT id FROM accounts WHERE provider = ?1))
+               AND (?2 IS NULL OR created_at >= ?2)
+               AND (?3 IS NULL OR created_at <= ?3)
+               AND (?4 IS NULL OR updated_at < ?4 OR (updated_at = ?4 AND id > ?5))
+             ORDER BY updated_at DESC, id
+             LIMIT ?6",
+        )?;
+        let conversations = stmt
+            .query_map(
+                params![
+                    provider,
+                    created.0.map(|at| at.to_rfc3339()),
+                    created.1.map(|at| at.to_rfc3339()),
+                    after.map(|cursor| cursor.updated_at.to_rfc3339()),
+                    after.map(|cursor| cursor.id.as_str()),
+                    limit as i64,
+                ],
+                conversation_from_row,
+            )?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(conversations)
+    }
+
+    /// Creation time of an account's newest stored conversation
+    pub fn newest_conversation_created_at(
+        &self,
+        account_id: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        self.newest_conversation_time("created_at", account_id)
+    }
+
+    /// Update time of an account's most recently updated stored conversation
+    pub fn newest_conversation_updated_at(
+        &self,
+        account_id: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        self.newest_conversation_time("updated_at", account_id)
+    }
+
+    fn newest_conversation_time(
+        &self,
+        column: &str,
+        account_id: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let newest: Option<String> = self.conn.query_row(
+            &format!(
+                "SELECT MAX({}) FROM conversations WHERE account_id = ?1",
+                column
+            ),
+            params![account_id],
+            |row| row.get(0),
+        )?;
+        Ok(newest.and_then(|s| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .ok()
+        }))
+    }
+
+    /// Mark a conversation archived, or not, without pulling it again
+    pub fn set_conversation_archived(&self, id: &str, archived: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE conversations SET is_archived = ?2 WHERE id = ?1",
+            params![id, archived as i32],
+        )?;
+        Ok(())
+    }
+
+    /// Number of stored messages in each of an account's conversations
+    ///
+    /// Conversations without messages are counted as 0 rather than left out,
+    /// which is how a failed sync usually shows.
+    pub fn message_counts(&self, account_id: &str) -> Result<HashMap<String, usize>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, COUNT(m.id) FROM conversations c
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             WHERE c.account_id = ?1
+             GROUP BY c.id",
+        )?;
+        let counts = stmt
             .query_map(params![account_id], |row| {
-                Ok(Conversation {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<SqliteResult<HashMap<_, _>>>()?;
+        Ok(counts)
+    }
+
+    /// Save a project and name the stored conversations that belong to it
+    pub fn save_project(&self, project: &Project) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO projects (id, provider, name, description, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(id) DO UPDATE SET
+                 name = excluded.name,
+                 description = excluded.description,
+                 created_at = excluded.created_at",
+            params![
+                project.id,
+                project.provider_id,
+                project.name,
+                project.description,
+                project.created_at.map(|dt| dt.to_rfc3339()),
+            ],
+        )?;
+        self.conn.execute(
+            "UPDATE conversations SET project_name = ?2 WHERE project_id = ?1",
+            params![project.id, project.name],
+        )?;
+        Ok(())
+    }
+
+    /// Projects seen during sync, by provider then name
+    pub fn list_projects(&self, provider: Option<&str>) -> Result<Vec<Project>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, provider, name, description, created_at FROM projects
+             WHERE ?1 IS NULL OR provider = ?1
+             ORDER BY provider, name",
+        )?;
+        let projects = stmt
+            .query_map(params![provider], |row| {
+                let created_at: Option<String> = row.get(4)?;
+                Ok(Project {
                     id: row.get(0)?,
                     provider_id: row.get(1)?,
-                    title: row.get(2)?,
-                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                        .unwrap_or_else(|_| chrono::Utc::now()),
-                    updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                        .unwrap_or_else(|_| chrono::Utc::now()),
-                    model: row.get(5)?,
-                    project_id: row.get(6)?,
-                    project_name: row.get(7)?,
-                    is_archived: row.get::<_, i32>(8)? != 0,
+                    name: row.get(2)?,
+                    description: row.get(3)?,
+                    created_at: created_at.and_then(|s| {
+                        chrono::DateTime::parse_from_rfc3339(&s)
+                            .map(|dt| dt.with_timezone(&chrono::Utc))
+                            .ok()
+                    }),
                 })
             })?
             .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(projects)
+    }
 
-        Ok(convs)
+    /// Save a conversation and its messages in one transaction
+    ///
+    /// Either everything is written, FTS rows included, or nothing is.
+    /// Returns the timestamps that were clamped for being in the future, which
+    /// are also reported on stderr.
+    pub fn save_conversation_with_messages(
+        &self,
+        account_id: &str,
+        conv: &Conversation,
+        messages: &[Message],
+    ) -> Result<Vec<ClampedTimestamp>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut clamped = self.write_conversation(account_id, conv)?;
+        for message in messages {
+            clamped.extend(self.write_message(message)?);
+        }
+        tx.commit()?;
+        warn_clamped(&clamped);
+        Ok(clamped)
     }
 
     // Message operations
 
+    /// Save a message, clamping a `created_at` too far in the future
     pub fn save_message(&self, message: &Message) -> Result<()> {
+        warn_clamped(self.write_message(message)?.as_slice());
+        Ok(())
+    }
+
+    fn write_message(&self, message: &Message) -> Result<Option<ClampedTimestamp>> {
+        let (message, clamped) = skew::sanitize_message(message, chrono::Utc::now());
+        let message = &message;
         let content_json = serde_json::to_string(&message.content)?;
-        let content_type = match &message.content {
-            crate::providers::MessageContent::Text { .. } => "text",
-            crate::providers::MessageContent::Code { .. } => "code",
-            crate::providers::MessageContent::Image { .. } => "image",
-            crate::providers::MessageContent::Audio { .. } => "audio",
-            crate::providers::MessageContent::Mixed { .. } => "mixed",
-        };
+        let content_type = content_type_name(&message.content);
 
         // Extract text content for FTS indexing
         let text_content = extract_text_content(&message.content);
 
-        self.conn.execute(
+        let mut upsert = self.conn.prepare_cached(
             r#"
-            INSERT INTO messages (id, conversation_id, parent_id, role, content_type, content_json, created_at, model)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO messages (id, conversation_id, parent_id, role, content_type, content_json, created_at, model, raw_json, speaker, fts_rowid)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
+                    (SELECT COALESCE(MAX(fts_rowid), 0) + 1 FROM messages))
             ON CONFLICT(id) DO UPDATE SET
-                content_json = excluded.content_json
+                content_json = excluded.content_json,
+                speaker = excluded.speaker
             "#,
-            params![
-                message.id,
-                message.conversation_id,
-                message.parent_id,
-                format!("{:?}", message.role).to_lowercase(),
-                content_type,
-                content_json,
-                message.created_at.map(|dt| dt.to_rfc3339()),
-                message.model,
-            ],
         )?;
+        upsert.execute(params![
+            message.id,
+            message.conversation_id,
+            message.parent_id,
+            format!("{:?}", message.role).to_lowercase(),
+            content_type,
+            content_json,
+            message.created_at.map(|dt| dt.to_rfc3339()),
+            message.model,
+            skew::raw_json(clamped.as_slice()),
+            message.speaker,
+        ])?;
 
         // Update FTS index
-        if !text_content.is_empty() {
-            self.conn.execute(
-                "INSERT OR REPLACE INTO messages_fts (rowid, content, conversation_id)
-                 SELECT rowid, ?1, ?2 FROM messages WHERE id = ?3",
-                params![text_content, message.conversation_id, message.id],
-            )?;
-        }
+        self.index_message_text(&message.id, &message.conversation_id, &text_content)?;
 
-        Ok(())
+        Ok(clamped)
     }
 
     pub fn get_messages(&self, conversation_id: &str) -> Result<Vec<Message>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, conversation_id, parent_id, role, content_json, created_at, model
+            "SELECT id, conversation_id, parent_id, role, content_json, created_at, model, speaker
              FROM messages WHERE conversation_id = ?1 ORDER BY created_at ASC",
         )?;
 
@@ -386,10 +644,8 @@ impl Store {
                 };
 
                 let content_json: String = row.get(4)?;
-                let content: crate::providers::MessageContent =
-                    serde_json::from_str(&content_json).unwrap_or(crate::providers::MessageContent::Text {
-                        text: content_json,
-                    });
+                let content: crate::providers::MessageContent = serde_json::from_str(&content_json)
+                    .unwrap_or(crate::providers::MessageContent::Text { text: content_json });
 
                 let created_at: Option<String> = row.get(5)?;
                 let created_at = created_at.and_then(|s| {
@@ -406,6 +662,7 @@ impl Store {
                     content,
                     created_at,
                     model: row.get(6)?,
+                    speaker: row.get(7)?,
                 })
             })?
             .collect::<SqliteResult<Vec<_>>>()?;
@@ -415,35 +672,83 @@ impl Store {
 
     // Search operations
 
-    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(String, String)>> {
-        let mut stmt = self.conn.prepare(
+    /// Messages matching `query`, best first
+    ///
+    /// With `filters.include_attachments`, text extracted from attachments is
+    /// searched too and ranked alongside the messages.
+    pub fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SearchHit>> {
+        let mut sql = String::from(
             r#"
-            SELECT m.conversation_id, snippet(messages_fts, 0, '<mark>', '</mark>', '...', 32) as snippet
+            SELECT m.conversation_id, c.title, c.provider_id, c.updated_at,
+                   snippet(messages_fts, 0, '<mark>', '</mark>', '...', 32) as snippet,
+                   rank
             FROM messages_fts
-            JOIN messages m ON messages_fts.rowid = m.rowid
-            WHERE messages_fts MATCH ?1
-            ORDER BY rank
-            LIMIT ?2
+            JOIN messages m ON m.id = messages_fts.message_id
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE messages_fts MATCH ?
             "#,
-        )?;
+        );
+        let mut values: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(query.to_string())];
+        push_search_filters(filters, &mut sql, &mut values);
+        sql.push_str(" ORDER BY rank LIMIT ?");
+        values.push(Box::new(limit as i64));
 
+        let mut stmt = self.conn.prepare(&sql)?;
         let results = stmt
-            .query_map(params![query, limit as i64], |row| {
-                Ok((row.get(0)?, row.get(1)?))
-            })?
+            .query_map(
+                rusqlite::params_from_iter(values.iter().map(|v| v.as_ref())),
+                |row| {
+                    Ok(SearchHit {
+                        conversation_id: row.get(0)?,
+                        title: row.get(1)?,
+                        provider_id: row.get(2)?,
+                        updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                            .map(|dt| dt.with_timezone(&chrono::Utc))
+                            .unwrap_or_else(|_| chrono::Utc::now()),
+                        snippet: row.get(4)?,
+                        rank: row.get(5)?,
+                        attachment: None,
+                    })
+                },
+            )?
             .collect::<SqliteResult<Vec<_>>>()?;
 
+        if !filters.include_attachments {
+            return Ok(results);
+        }
+        // FTS5 ranks (bm25) from both tables are on the same scale
+        let mut results = results;
+        results.extend(self.search_attachment_text(query, limit, filters)?);
+        results.sort_by(|a, b| a.rank.total_cmp(&b.rank));
+        results.truncate(limit);
         Ok(results)
     }
 
     // Attachment operations
 
+    /// Queue an attachment for download under the account that owns its message
     pub fn save_attachment(&self, attachment: &Attachment) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT INTO attachments (id, message_id, filename, mime_type, size_bytes, download_url)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-            ON CONFLICT(id) DO NOTHING
+            INSERT INTO attachments
+                (id, message_id, filename, mime_type, size_bytes, download_url,
+                 width, height, sha256, provider_id, account_id)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, c.provider_id, c.account_id
+            FROM (SELECT 1)
+            LEFT JOIN messages m ON m.id = ?2
+            LEFT JOIN conversations c ON c.id = m.conversation_id
+            WHERE true
+            ON CONFLICT(id) DO UPDATE SET
+                width = COALESCE(excluded.width, attachments.width),
+                height = COALESCE(excluded.height, attachments.height),
+                sha256 = COALESCE(attachments.sha256, excluded.sha256),
+                provider_id = COALESCE(attachments.provider_id, excluded.provider_id),
+                account_id = COALESCE(attachments.account_id, excluded.account_id)
             "#,
             params![
                 attachment.id,
@@ -452,36 +757,76 @@ impl Store {
                 attachment.mime_type,
                 attachment.size_bytes as i64,
                 attachment.download_url,
+                attachment.width,
+                attachment.height,
+                attachment.sha256,
             ],
         )?;
         Ok(())
     }
 
+    /// Record that an attachment is at `local_path`, hashing the file when
+    /// it can be read
     pub fn mark_attachment_downloaded(&self, id: &str, local_path: &str) -> Result<()> {
+        let sha256 = sha256_file(Path::new(local_path)).ok();
         self.conn.execute(
-            "UPDATE attachments SET local_path = ?1, downloaded_at = CURRENT_TIMESTAMP WHERE id = ?2",
-            params![local_path, id],
+            "UPDATE attachments
+             SET local_path = ?1, sha256 = COALESCE(?2, sha256), downloaded_at = CURRENT_TIMESTAMP
+             WHERE id = ?3",
+            params![local_path, sha256, id],
         )?;
         Ok(())
     }
 
     pub fn get_pending_attachments(&self) -> Result<Vec<Attachment>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, message_id, filename, mime_type, size_bytes, download_url
-             FROM attachments WHERE local_path IS NULL",
+            "SELECT id, message_id, filename, mime_type, size_bytes, download_url, width, height,
+                    sha256
+             FROM attachments WHERE local_path IS NULL AND failed_at IS NULL",
         )?;
 
         let attachments = stmt
-            .query_map([], |row| {
-                Ok(Attachment {
-                    id: row.get(0)?,
-                    message_id: row.get(1)?,
-                    filename: row.get(2)?,
-                    mime_type: row.get(3)?,
-                    size_bytes: row.get::<_, i64>(4)? as u64,
-                    download_url: row.get(5)?,
-                })
-            })?
+            .query_map([], attachment_from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(attachments)
+    }
+
+    /// Attachments not downloaded yet (and not given up on), for conversations
+    /// from one provider
+    pub fn get_pending_attachments_for_provider(
+        &self,
+        provider_id: &str,
+    ) -> Result<Vec<Attachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.message_id, a.filename, a.mime_type, a.size_bytes, a.download_url,
+                    a.width, a.height, a.sha256
+             FROM attachments a
+             JOIN messages m ON m.id = a.message_id
+             JOIN conversations c ON c.id = m.conversation_id
+             WHERE a.local_path IS NULL AND a.failed_at IS NULL AND c.provider_id = ?1",
+        )?;
+
+        let attachments = stmt
+            .query_map(params![provider_id], attachment_from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(attachments)
+    }
+
+    /// Attachments not downloaded yet (and not given up on), for one account's
+    /// conversations
+    pub fn get_pending_attachments_for_account(&self, account_id: &str) -> Result<Vec<Attachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, message_id, filename, mime_type, size_bytes, download_url, width, height,
+                    sha256
+             FROM attachments
+             WHERE local_path IS NULL AND failed_at IS NULL AND account_id = ?1
+             ORDER BY rowid",
+        )?;
+
+        let attachments = stmt
+            .query_map(params![account_id], attachment_from_row)?
             .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(attachments)
@@ -493,35 +838,248 @@ impl Store {
         let accounts: i64 = self
             .conn
             .query_row("SELECT COUNT(*) FROM accounts", [], |row| row.get(0))?;
-        let conversations: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM conversations", [], |row| row.get(0))?;
+        let conversations: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM conversations", [], |row| row.get(0))?;
         let messages: i64 = self
             .conn
             .query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))?;
-        let attachments: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM attachments", [], |row| row.get(0))?;
+        let deleted_remotely: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM conversations WHERE remote_deleted_at IS NOT NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        let attachments: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM attachments", [], |row| row.get(0))?;
+        let attachment_files: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM attachments WHERE local_path IS NOT NULL AND duplicate_of IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        let (attachments_pending, attachments_failed): (i64, i64) = self.conn.query_row(
+            "SELECT COUNT(*) FILTER (WHERE failed_at IS NULL),
+                    COUNT(*) FILTER (WHERE failed_at IS NOT NULL)
+             FROM attachments WHERE local_path IS NULL",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
 
         Ok(StoreStats {
             accounts: accounts as usize,
             conversations: conversations as usize,
             messages: messages as usize,
+            conversations_deleted_remotely: deleted_remotely as usize,
             attachments: attachments as usize,
+            attachment_files: attachment_files as usize,
+            attachments_pending: attachments_pending as usize,
+            attachments_failed: attachments_failed as usize,
         })
     }
+
+    /// Conversation, message and attachment totals grouped by provider
+    pub fn stats_by_provider(&self) -> Result<Vec<ProviderStats>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT c.provider_id,
+                   COUNT(*),
+                   MIN(c.created_at),
+                   MAX(c.created_at),
+                   (SELECT COUNT(*) FROM messages m
+                    JOIN conversations mc ON mc.id = m.conversation_id
+                    WHERE mc.provider_id = c.provider_id),
+                   (SELECT COALESCE(SUM(a.size_bytes), 0) FROM attachments a
+                    JOIN messages m ON m.id = a.message_id
+                    JOIN conversations ac ON ac.id = m.conversation_id
+                    WHERE ac.provider_id = c.provider_id AND a.local_path IS NOT NULL
+                      AND a.duplicate_of IS NULL)
+            FROM conversations c
+            GROUP BY c.provider_id
+            ORDER BY c.provider_id
+            "#,
+        )?;
+
+        let parse = |s: Option<String>| {
+            s.and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+        };
+
+        let stats = stmt
+            .query_map([], |row| {
+                Ok(ProviderStats {
+                    provider: row.get(0)?,
+                    conversations: row.get::<_, i64>(1)? as usize,
+                    earliest: parse(row.get(2)?),
+                    latest: parse(row.get(3)?),
+                    messages: row.get::<_, i64>(4)? as usize,
+                    attachment_bytes: row.get::<_, i64>(5)? as u64,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(stats)
+    }
+}
+
+/// A conversation from a row of `id, provider_id, title, created_at,
+/// updated_at, model, project_id, project_name, is_archived`
+fn conversation_from_row(row: &rusqlite::Row) -> SqliteResult<Conversation> {
+    Ok(Conversation {
+        id: row.get(0)?,
+        provider_id: row.get(1)?,
+        title: row.get(2)?,
+        created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now()),
+        updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now()),
+        model: row.get(5)?,
+        project_id: row.get(6)?,
+        project_name: row.get(7)?,
+        is_archived: row.get::<_, i32>(8)? != 0,
+    })
 }
 
+/// A slice of an account's conversations, from [`Store::get_conversations_page`]
 #[derive(Debug, Clone)]
+pub struct ConversationPage {
+    pub conversations: Vec<Conversation>,
+    /// Conversations skipped before this page
+    pub offset: usize,
+    /// Conversations the account has in all
+    pub total: usize,
+}
+
+/// Where a page from [`Store::get_conversations_after`] ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationCursor {
+    pub updated_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl ConversationCursor {
+    /// Cursor for the page after `conversation`
+    pub fn after(conversation: &Conversation) -> Self {
+        Self {
+            updated_at: conversation.updated_at,
+            id: conversation.id.clone(),
+        }
+    }
+
+    /// Opaque form handed to API clients
+    pub fn encode(&self) -> String {
+        format!("{}~{}", self.updated_at.to_rfc3339(), self.id)
+    }
+
+    /// Parse what [`ConversationCursor::encode`] produced
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let (updated_at, id) = cursor.split_once('~')?;
+        Some(Self {
+            updated_at: DateTime::parse_from_rfc3339(updated_at)
+                .ok()?
+                .with_timezone(&Utc),
+            id: id.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct StoreStats {
     pub accounts: usize,
     pub conversations: usize,
     pub messages: usize,
+    /// Conversations a full pull found deleted upstream
+    pub conversations_deleted_remotely: usize,
     pub attachments: usize,
+    /// Downloaded attachment files on disk, after collapsing duplicates
+    pub attachment_files: usize,
+    /// Attachments still to be downloaded
+    pub attachments_pending: usize,
+    /// Attachments whose download failed for good
+    pub attachments_failed: usize,
+}
+
+/// Per-provider breakdown returned by [`Store::stats_by_provider`]
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStats {
+    pub provider: String,
+    pub conversations: usize,
+    pub messages: usize,
+    pub earliest: Option<chrono::DateTime<chrono::Utc>>,
+    pub latest: Option<chrono::DateTime<chrono::Utc>>,
+    /// Size of attachments that have been downloaded
+    pub attachment_bytes: u64,
+}
+
+/// An account from `id, provider, email, name, avatar_url, workspace_id`
+fn account_from_row(row: &rusqlite::Row) -> SqliteResult<Account> {
+    Ok(Account {
+        id: row.get(0)?,
+        provider: ProviderId(row.get(1)?),
+        email: row.get(2)?,
+        name: row.get(3)?,
+        avatar_url: row.get(4)?,
+        workspace_id: row.get(5)?,
+    })
+}
+
+fn warn_clamped(clamped: &[ClampedTimestamp]) {
+    for timestamp in clamped {
+        eprintln!("Warning: {}", timestamp);
+    }
+}
+
+/// Short name of a content variant, as stored in `messages.content_type`
+pub fn content_type_name(content: &crate::providers::MessageContent) -> &'static str {
+    match content {
+        crate::providers::MessageContent::Text { .. } => "text",
+        crate::providers::MessageContent::Code { .. } => "code",
+        crate::providers::MessageContent::Image { .. } => "image",
+        crate::providers::MessageContent::Audio { .. } => "audio",
+        crate::providers::MessageContent::Document { .. } => "document",
+        crate::providers::MessageContent::Thinking { .. } => "thinking",
+        crate::providers::MessageContent::Mixed { .. } => "mixed",
+    }
 }
 
 /// Extract searchable text from message content
-fn extract_text_content(content: &crate::providers::MessageContent) -> String {
+/// Append the conversation and message conditions of `filters` to a search
+/// query over `messages m` joined to `conversations c`
+fn push_search_filters(
+    filters: &SearchFilters,
+    sql: &mut String,
+    values: &mut Vec<Box<dyn rusqlite::ToSql>>,
+) {
+    if let Some(provider) = &filters.provider {
+        sql.push_str(" AND c.provider_id = ?");
+        values.push(Box::new(provider.clone()));
+    }
+    if let Some(account_id) = &filters.account_id {
+        sql.push_str(" AND c.account_id = ?");
+        values.push(Box::new(account_id.clone()));
+    }
+    if let Some(since) = filters.since {
+        sql.push_str(" AND julianday(c.created_at) >= julianday(?)");
+        values.push(Box::new(since.to_rfc3339()));
+    }
+    if let Some(until) = filters.until {
+        sql.push_str(" AND julianday(c.created_at) <= julianday(?)");
+        values.push(Box::new(until.to_rfc3339()));
+    }
+    if !filters.roles.is_empty() {
+        let placeholders = vec!["?"; filters.roles.len()].join(", ");
+        sql.push_str(&format!(" AND m.role IN ({})", placeholders));
+        for role in &filters.roles {
+            values.push(Box::new(role.as_str()));
+        }
+    }
+    if filters.exclude_archived {
+        sql.push_str(" AND c.is_archived = 0");
+    }
+}
+
+pub fn extract_text_content(content: &crate::providers::MessageContent) -> String {
     match content {
         crate::providers::MessageContent::Text { text } => text.clone(),
         crate::providers::MessageContent::Code { code, .. } => code.clone(),
@@ -529,6 +1087,10 @@ fn extract_text_content(content: &crate::providers::MessageContent) -> String {
         crate::providers::MessageContent::Audio { transcript, .. } => {
             transcript.clone().unwrap_or_default()
         }
+        crate::providers::MessageContent::Document { title, text, .. } => {
+            format!("{}\n{}", title, text)
+        }
+        crate::providers::MessageContent::Thinking { text } => text.clone(),
         crate::providers::MessageContent::Mixed { parts } => parts
             .iter()
             .map(extract_text_content)
@@ -549,6 +1111,7 @@ mod tests {
             email: "test@example.com".to_string(),
             name: Some("Test User".to_string()),
             avatar_url: None,
+            workspace_id: None,
         }
     }
 
@@ -577,6 +1140,7 @@ mod tests {
             },
             created_at: Some(chrono::Utc::now()),
             model: None,
+            speaker: None,
         }
     }
 
@@ -620,6 +1184,99 @@ mod tests {
         assert_eq!(accounts.len(), 2);
     }
 
+    #[test]
+    fn test_delete_account() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        let mut other = create_test_account();
+        other.id = "user-456".to_string();
+        store.save_account(&account).unwrap();
+        store.save_account(&other).unwrap();
+
+        let conv = create_test_conversation();
+        store
+            .save_conversation_with_messages(&account.id, &conv, &[create_test_message(&conv.id)])
+            .unwrap();
+        let mut kept = create_test_conversation();
+        kept.id = "conv-456".to_string();
+        store.save_conversation(&other.id, &kept).unwrap();
+
+        let deleted = store.delete_account(&account.id).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, "conv-123");
+        assert!(store.get_conversation("conv-123").unwrap().is_none());
+        assert!(store.get_messages("conv-123").unwrap().is_empty());
+        let indexed: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM messages_fts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(indexed, 0);
+        assert_eq!(store.list_accounts().unwrap().len(), 1);
+        assert!(store.get_conversation("conv-456").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_workspace_accounts_share_an_email() {
+        let store = Store::in_memory().unwrap();
+        let personal = create_test_account();
+        let mut workspace = create_test_account();
+        workspace.id = "user-123-ws-1".to_string();
+        workspace.name = Some("Acme".to_string());
+        workspace.workspace_id = Some("ws-1".to_string());
+
+        store.save_account(&personal).unwrap();
+        store.save_account(&workspace).unwrap();
+
+        let retrieved = store
+            .get_account(&ProviderId::chatgpt(), "test@example.com")
+            .unwrap()
+            .unwrap();
+        assert_eq!(retrieved.id, "user-123");
+        assert_eq!(retrieved.workspace_id, None);
+
+        let mut accounts = store.list_accounts().unwrap();
+        accounts.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[1].workspace_id.as_deref(), Some("ws-1"));
+        assert_eq!(accounts[1].label(), "test@example.com (Acme)");
+    }
+
+    #[test]
+    fn test_old_accounts_table_is_rebuilt_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quaid.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE accounts (
+                     id TEXT PRIMARY KEY,
+                     provider TEXT NOT NULL,
+                     email TEXT NOT NULL,
+                     name TEXT,
+                     avatar_url TEXT,
+                     created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                     UNIQUE(provider, email)
+                 );
+                 INSERT INTO accounts (id, provider, email, name)
+                 VALUES ('user-123', 'chatgpt', 'test@example.com', 'Test User');",
+            )
+            .unwrap();
+        }
+
+        let store = Store::open(&path).unwrap();
+        let personal = store
+            .get_account(&ProviderId::chatgpt(), "test@example.com")
+            .unwrap()
+            .unwrap();
+        assert_eq!(personal.name.as_deref(), Some("Test User"));
+
+        let mut workspace = create_test_account();
+        workspace.id = "user-123-ws-1".to_string();
+        workspace.workspace_id = Some("ws-1".to_string());
+        store.save_account(&workspace).unwrap();
+        assert_eq!(store.list_accounts().unwrap().len(), 2);
+    }
+
     #[test]
     fn test_save_and_get_conversation() {
         let store = Store::in_memory().unwrap();
@@ -634,6 +1291,67 @@ mod tests {
         assert_eq!(retrieved.title, conv.title);
     }
 
+    #[test]
+    fn test_rename_conversation() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+        let conv = create_test_conversation();
+        store.save_conversation(&account.id, &conv).unwrap();
+
+        assert!(store.rename_conversation(&conv.id, "Renamed").unwrap());
+        let retrieved = store.get_conversation(&conv.id).unwrap().unwrap();
+        assert_eq!(retrieved.title, "Renamed");
+
+        assert!(!store.rename_conversation("missing", "Nope").unwrap());
+    }
+
+    #[test]
+    fn test_conversation_source() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+        let conv = create_test_conversation();
+        store.save_conversation(&account.id, &conv).unwrap();
+        assert_eq!(store.conversation_source(&conv.id).unwrap(), None);
+
+        store.set_conversation_source(&conv.id, "share").unwrap();
+        assert_eq!(
+            store.conversation_source(&conv.id).unwrap().as_deref(),
+            Some("share")
+        );
+
+        // Saving it again keeps the note
+        store.save_conversation(&account.id, &conv).unwrap();
+        assert_eq!(
+            store.conversation_source(&conv.id).unwrap().as_deref(),
+            Some("share")
+        );
+        assert_eq!(store.conversation_source("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_conversation_web_url() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+        let conv = create_test_conversation();
+        store.save_conversation(&account.id, &conv).unwrap();
+
+        assert_eq!(
+            store.conversation_web_url(&conv).unwrap(),
+            Some(format!("https://chatgpt.com/c/{}", conv.id))
+        );
+
+        store
+            .set_conversation_web_url(&conv.id, "https://chatgpt.com/g/g-abc/c/conv-123")
+            .unwrap();
+        assert_eq!(
+            store.conversation_web_url(&conv).unwrap().as_deref(),
+            Some("https://chatgpt.com/g/g-abc/c/conv-123")
+        );
+    }
+
     #[test]
     fn test_list_conversations() {
         let store = Store::in_memory().unwrap();
@@ -652,6 +1370,280 @@ mod tests {
         assert_eq!(convs.len(), 2);
     }
 
+    #[test]
+    fn test_newest_conversation_created_at() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+        assert!(store
+            .newest_conversation_created_at(&account.id)
+            .unwrap()
+            .is_none());
+
+        let newest = chrono::Utc::now() - chrono::Duration::hours(1);
+        for (i, age) in [3, 1, 2].into_iter().enumerate() {
+            let mut conv = create_test_conversation();
+            conv.id = format!("conv-{}", i);
+            conv.created_at = newest - chrono::Duration::days(age - 1);
+            store.save_conversation(&account.id, &conv).unwrap();
+        }
+        assert_eq!(
+            store
+                .newest_conversation_created_at(&account.id)
+                .unwrap()
+                .map(|dt| dt.timestamp()),
+            Some(newest.timestamp())
+        );
+    }
+
+    #[test]
+    fn test_newest_conversation_updated_at() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        let now = chrono::Utc::now();
+        for (i, age) in [2, 0, 5].into_iter().enumerate() {
+            let mut conv = create_test_conversation();
+            conv.id = format!("conv-{}", i);
+            conv.created_at = now - chrono::Duration::days(10);
+            conv.updated_at = now - chrono::Duration::days(age);
+            store.save_conversation(&account.id, &conv).unwrap();
+        }
+        assert_eq!(
+            store
+                .newest_conversation_updated_at(&account.id)
+                .unwrap()
+                .map(|dt| dt.timestamp()),
+            Some(now.timestamp())
+        );
+    }
+
+    #[test]
+    fn test_set_conversation_archived() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+        let conv = create_test_conversation();
+        store.save_conversation(&account.id, &conv).unwrap();
+
+        store.set_conversation_archived(&conv.id, true).unwrap();
+        assert!(
+            store
+                .get_conversation(&conv.id)
+                .unwrap()
+                .unwrap()
+                .is_archived
+        );
+        store.set_conversation_archived(&conv.id, false).unwrap();
+        assert!(
+            !store
+                .get_conversation(&conv.id)
+                .unwrap()
+                .unwrap()
+                .is_archived
+        );
+    }
+
+    #[test]
+    fn test_message_counts() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        let conv = create_test_conversation();
+        let mut empty = create_test_conversation();
+        empty.id = "conv-456".to_string();
+        store.save_conversation(&account.id, &conv).unwrap();
+        store.save_conversation(&account.id, &empty).unwrap();
+        for i in 0..3 {
+            let mut msg = create_test_message(&conv.id);
+            msg.id = format!("msg-{}", i);
+            store.save_message(&msg).unwrap();
+        }
+
+        let counts = store.message_counts(&account.id).unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["conv-123"], 3);
+        assert_eq!(counts["conv-456"], 0);
+        assert!(store.message_counts("other-account").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_conversations_page() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        let start = chrono::Utc::now();
+        for i in 0..5 {
+            let mut conv = create_test_conversation();
+            conv.id = format!("conv-{}", i);
+            conv.updated_at = start + chrono::Duration::minutes(i);
+            store.save_conversation(&account.id, &conv).unwrap();
+        }
+
+        let first = store.get_conversations_page(&account.id, 0, 2).unwrap();
+        assert_eq!(first.total, 5);
+        let ids: Vec<_> = first.conversations.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["conv-4", "conv-3"]);
+
+        let last = store.get_conversations_page(&account.id, 4, 2).unwrap();
+        assert_eq!(last.offset, 4);
+        assert_eq!(last.conversations.len(), 1);
+        assert_eq!(last.conversations[0].id, "conv-0");
+
+        let past_end = store.get_conversations_page(&account.id, 10, 2).unwrap();
+        assert!(past_end.conversations.is_empty());
+        assert_eq!(past_end.total, 5);
+    }
+
+    #[test]
+    fn test_conversations_after_cursor() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        // Two share an updated_at, so the id breaks the tie
+        let start = chrono::Utc::now() - chrono::Duration::hours(1);
+        for (i, minutes) in [0, 1, 1, 2].into_iter().enumerate() {
+            let mut conv = create_test_conversation();
+            conv.id = format!("conv-{}", i);
+            conv.updated_at = start + chrono::Duration::minutes(minutes);
+            store.save_conversation(&account.id, &conv).unwrap();
+        }
+
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = store
+                .get_conversations_after(None, (None, None), cursor.as_ref(), 3)
+                .unwrap();
+            let Some(last) = page.last() else { break };
+            let encoded = ConversationCursor::after(last).encode();
+            cursor = Some(ConversationCursor::decode(&encoded).unwrap());
+            ids.extend(page.into_iter().map(|c| c.id));
+        }
+        assert_eq!(ids, ["conv-3", "conv-1", "conv-2", "conv-0"]);
+
+        let chatgpt = store
+            .get_conversations_after(Some("chatgpt"), (None, None), None, 10)
+            .unwrap();
+        assert_eq!(chatgpt.len(), 4);
+        let claude = store
+            .get_conversations_after(Some("claude"), (None, None), None, 10)
+            .unwrap();
+        assert!(claude.is_empty());
+        assert!(ConversationCursor::decode("not a cursor").is_none());
+    }
+
+    #[test]
+    fn test_projects_name_their_conversations() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        // Saved before the project is known, then named when it is
+        let mut early = create_test_conversation();
+        early.project_id = Some("g-p-1".to_string());
+        store.save_conversation(&account.id, &early).unwrap();
+        assert_eq!(
+            store
+                .get_conversation(&early.id)
+                .unwrap()
+                .unwrap()
+                .project_name,
+            None
+        );
+
+        let project = Project {
+            id: "g-p-1".to_string(),
+            provider_id: "chatgpt".to_string(),
+            name: "Thesis".to_string(),
+            description: None,
+            created_at: None,
+        };
+        store.save_project(&project).unwrap();
+        assert_eq!(
+            store
+                .get_conversation(&early.id)
+                .unwrap()
+                .unwrap()
+                .project_name
+                .as_deref(),
+            Some("Thesis")
+        );
+
+        // Saved after the project is known
+        let mut late = create_test_conversation();
+        late.id = "conv-456".to_string();
+        late.project_id = Some("g-p-1".to_string());
+        store.save_conversation(&account.id, &late).unwrap();
+        assert_eq!(
+            store
+                .get_conversation(&late.id)
+                .unwrap()
+                .unwrap()
+                .project_name
+                .as_deref(),
+            Some("Thesis")
+        );
+
+        assert_eq!(store.list_projects(None).unwrap(), vec![project]);
+        assert!(store.list_projects(Some("claude")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_save_conversation_with_messages_is_atomic() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+        let conv = create_test_conversation();
+        let messages: Vec<Message> = (0..5)
+            .map(|i| Message {
+                id: format!("msg-{}", i),
+                ..create_test_message(&conv.id)
+            })
+            .collect();
+
+        store
+            .conn
+            .execute_batch(
+                "CREATE TEMP TRIGGER fail_msg_3 BEFORE INSERT ON messages
+                 WHEN NEW.id = 'msg-3' BEGIN SELECT RAISE(ABORT, 'disk full'); END;",
+            )
+            .unwrap();
+        assert!(store
+            .save_conversation_with_messages(&account.id, &conv, &messages)
+            .is_err());
+        assert!(store.get_conversation(&conv.id).unwrap().is_none());
+        assert!(store.get_messages(&conv.id).unwrap().is_empty());
+        assert!(store
+            .search("Hello", 10, &SearchFilters::default())
+            .unwrap()
+            .is_empty());
+
+        store
+            .conn
+            .execute_batch("DROP TRIGGER fail_msg_3;")
+            .unwrap();
+        store
+            .save_conversation_with_messages(&account.id, &conv, &messages)
+            .unwrap();
+        assert_eq!(store.get_messages(&conv.id).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_open_uses_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::open(&dir.path().join("quaid.db")).unwrap();
+        let mode: String = store
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+    }
+
     #[test]
     fn test_save_and_get_messages() {
         let store = Store::in_memory().unwrap();
@@ -669,6 +1661,33 @@ mod tests {
         assert_eq!(messages[0].id, msg.id);
     }
 
+    #[test]
+    fn test_speaker_is_kept_out_of_the_text() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        let conv = create_test_conversation();
+        store.save_conversation(&account.id, &conv).unwrap();
+
+        let msg = Message {
+            speaker: Some("Alice".to_string()),
+            ..create_test_message(&conv.id)
+        };
+        store.save_message(&msg).unwrap();
+
+        let messages = store.get_messages(&conv.id).unwrap();
+        assert_eq!(messages[0].speaker.as_deref(), Some("Alice"));
+        let hits = store
+            .search("hello", 10, &SearchFilters::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(store
+            .search("alice", 10, &SearchFilters::default())
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_search_messages() {
         let store = Store::in_memory().unwrap();
@@ -681,9 +1700,127 @@ mod tests {
         let msg = create_test_message(&conv.id);
         store.save_message(&msg).unwrap();
 
-        let results = store.search("hello", 10).unwrap();
+        let results = store
+            .search("hello", 10, &SearchFilters::default())
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, conv.id);
+        assert_eq!(results[0].title, conv.title);
+        assert_eq!(results[0].provider_id, "chatgpt");
+        assert!(results[0].snippet.contains("<mark>Hello</mark>"));
+    }
+
+    #[test]
+    fn test_thinking_round_trips_and_is_searchable() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        let conv = create_test_conversation();
+        store.save_conversation(&account.id, &conv).unwrap();
+
+        let msg = Message {
+            content: MessageContent::Thinking {
+                text: "Maybe a bloom filter would do".to_string(),
+            },
+            ..create_test_message(&conv.id)
+        };
+        store.save_message(&msg).unwrap();
+
+        let content_type: String = store
+            .conn
+            .query_row(
+                "SELECT content_type FROM messages WHERE id = ?1",
+                [&msg.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(content_type, "thinking");
+
+        match &store.get_messages(&conv.id).unwrap()[0].content {
+            MessageContent::Thinking { text } => assert_eq!(text, "Maybe a bloom filter would do"),
+            other => panic!("Expected Thinking content, got {:?}", other),
+        }
+
+        let results = store
+            .search("bloom", 10, &SearchFilters::default())
+            .unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_search_filters_apply_before_limit() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        // Ten matching chatgpt conversations from 2024, one claude one from 2025
+        for i in 0..10 {
+            let mut conv = create_test_conversation();
+            conv.id = format!("old-{}", i);
+            conv.created_at = "2024-03-01T12:00:00Z".parse().unwrap();
+            store.save_conversation(&account.id, &conv).unwrap();
+
+            let mut msg = create_test_message(&conv.id);
+            msg.id = format!("old-msg-{}", i);
+            store.save_message(&msg).unwrap();
+        }
+
+        let mut conv = create_test_conversation();
+        conv.id = "new".to_string();
+        conv.provider_id = "claude".to_string();
+        conv.created_at = "2025-01-10T12:00:00Z".parse().unwrap();
+        store.save_conversation(&account.id, &conv).unwrap();
+
+        let mut msg = create_test_message(&conv.id);
+        msg.id = "new-msg".to_string();
+        msg.role = crate::providers::Role::Assistant;
+        store.save_message(&msg).unwrap();
+
+        // A post-filter over the first result would find nothing
+        let by_provider = SearchFilters {
+            provider: Some("claude".to_string()),
+            ..Default::default()
+        };
+        let results = store.search("hello", 1, &by_provider).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, "new");
+
+        let by_date = SearchFilters {
+            since: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let results = store.search("hello", 1, &by_date).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, "new");
+
+        let by_role = SearchFilters {
+            roles: vec![crate::providers::Role::Assistant],
+            ..Default::default()
+        };
+        let results = store.search("hello", 5, &by_role).unwrap();
         assert_eq!(results.len(), 1);
-        assert_eq!(results[0].0, conv.id);
+        assert_eq!(results[0].conversation_id, "new");
+
+        let either_role = SearchFilters {
+            roles: vec![
+                crate::providers::Role::User,
+                crate::providers::Role::Assistant,
+            ],
+            ..Default::default()
+        };
+        let results = store.search("hello", 20, &either_role).unwrap();
+        assert_eq!(results.len(), 11);
+
+        let until = SearchFilters {
+            until: Some("2024-12-31T23:59:59Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let results = store.search("hello", 20, &until).unwrap();
+        assert_eq!(results.len(), 10);
+        assert!(results
+            .iter()
+            .all(|hit| hit.conversation_id.starts_with("old-")));
     }
 
     #[test]
@@ -705,11 +1842,22 @@ mod tests {
             mime_type: "image/png".to_string(),
             size_bytes: 1024,
             download_url: "file-service://abc123".to_string(),
+            width: None,
+            height: None,
+            sha256: None,
         };
         store.save_attachment(&attachment).unwrap();
 
         let pending = store.get_pending_attachments().unwrap();
         assert_eq!(pending.len(), 1);
+        let for_provider = store
+            .get_pending_attachments_for_provider("chatgpt")
+            .unwrap();
+        assert_eq!(for_provider.len(), 1);
+        assert!(store
+            .get_pending_attachments_for_provider("fathom")
+            .unwrap()
+            .is_empty());
 
         store
             .mark_attachment_downloaded(&attachment.id, "/path/to/image.png")
@@ -719,6 +1867,58 @@ mod tests {
         assert_eq!(pending.len(), 0);
     }
 
+    #[test]
+    fn test_attachment_dimensions_and_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+        let conv = create_test_conversation();
+        store.save_conversation(&account.id, &conv).unwrap();
+        let msg = create_test_message(&conv.id);
+        store.save_message(&msg).unwrap();
+
+        let attachment = Attachment {
+            id: "att-plot".to_string(),
+            message_id: msg.id.clone(),
+            filename: "plot.png".to_string(),
+            mime_type: "image/png".to_string(),
+            size_bytes: 3,
+            download_url: "file-service://plot".to_string(),
+            width: Some(800),
+            height: Some(600),
+            sha256: None,
+        };
+        store.save_attachment(&attachment).unwrap();
+        // Saving again without dimensions keeps the ones already known
+        store
+            .save_attachment(&Attachment {
+                width: None,
+                height: None,
+                ..attachment.clone()
+            })
+            .unwrap();
+        let pending = store.get_pending_attachments().unwrap();
+        assert_eq!(
+            (pending[0].width, pending[0].height),
+            (Some(800), Some(600))
+        );
+        assert_eq!(pending[0].sha256, None);
+
+        let path = dir.path().join("plot.png");
+        std::fs::write(&path, b"abc").unwrap();
+        store
+            .mark_attachment_downloaded(&attachment.id, &path.to_string_lossy())
+            .unwrap();
+
+        let file = store.get_attachment_file(&attachment.id).unwrap().unwrap();
+        assert_eq!(
+            file.attachment.sha256.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(file.attachment.width, Some(800));
+    }
+
     #[test]
     fn test_stats() {
         let store = Store::in_memory().unwrap();
@@ -736,4 +1936,66 @@ mod tests {
         assert_eq!(stats.conversations, 1);
         assert_eq!(stats.messages, 1);
     }
+
+    #[test]
+    fn test_stats_by_provider() {
+        let store = Store::in_memory().unwrap();
+        let account = create_test_account();
+        store.save_account(&account).unwrap();
+
+        let day = |d: u32| {
+            chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, 2024, 3, d, 12, 0, 0).unwrap()
+        };
+        for (id, provider, created) in [
+            ("c1", "chatgpt", day(1)),
+            ("c2", "chatgpt", day(20)),
+            ("c3", "claude", day(5)),
+        ] {
+            let mut conv = create_test_conversation();
+            conv.id = id.to_string();
+            conv.provider_id = provider.to_string();
+            conv.created_at = created;
+            store.save_conversation(&account.id, &conv).unwrap();
+
+            let mut msg = create_test_message(id);
+            msg.id = format!("{}-msg", id);
+            store.save_message(&msg).unwrap();
+        }
+
+        for (id, local_path) in [("a1", Some("/tmp/a1.png")), ("a2", None)] {
+            store
+                .save_attachment(&Attachment {
+                    id: id.to_string(),
+                    message_id: "c1-msg".to_string(),
+                    filename: format!("{}.png", id),
+                    mime_type: "image/png".to_string(),
+                    size_bytes: 1000,
+                    download_url: "https://example.com".to_string(),
+                    width: None,
+                    height: None,
+                    sha256: None,
+                })
+                .unwrap();
+            if let Some(path) = local_path {
+                store.mark_attachment_downloaded(id, path).unwrap();
+            }
+        }
+
+        let stats = store.stats_by_provider().unwrap();
+        assert_eq!(stats.len(), 2);
+
+        let chatgpt = &stats[0];
+        assert_eq!(chatgpt.provider, "chatgpt");
+        assert_eq!(chatgpt.conversations, 2);
+        assert_eq!(chatgpt.messages, 2);
+        assert_eq!(chatgpt.earliest, Some(day(1)));
+        assert_eq!(chatgpt.latest, Some(day(20)));
+        assert_eq!(chatgpt.attachment_bytes, 1000);
+
+        let claude = &stats[1];
+        assert_eq!(claude.provider, "claude");
+        assert_eq!(claude.conversations, 1);
+        assert_eq!(claude.messages, 1);
+        assert_eq!(claude.attachment_bytes, 0);
+    }
 }
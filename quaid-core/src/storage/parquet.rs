@@ -2,7 +2,9 @@
 //!
 //! Stores each conversation as a separate parquet file with its messages.
 
-use super::{ParquetStorageConfig, Result, StorageError};
+use super::atomic::AtomicFile;
+use super::scan::ScannedMessage;
+use super::{ParquetCompression, ParquetStorageConfig, Result, StorageError};
 use crate::providers::{Conversation, Message, MessageContent, Role};
 use arrow::array::{
     Array, ArrayRef, BooleanArray, RecordBatch, StringArray, TimestampMillisecondArray,
@@ -10,11 +12,16 @@ use arrow::array::{
 use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use chrono::{DateTime, Utc};
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
-use parquet::arrow::ArrowWriter;
+use parquet::arrow::{ArrowWriter, ProjectionMask};
+use parquet::basic::{Compression, ZstdLevel};
 use parquet::file::properties::WriterProperties;
 use std::fs::{self, File};
+use std::ops::ControlFlow;
 use std::sync::Arc;
 
+/// Rows decoded at a time by [`ParquetStore::scan_messages`]
+const SCAN_BATCH_ROWS: usize = 256;
+
 /// Parquet-based conversation storage
 ///
 /// Stores each conversation as a separate parquet file:
@@ -64,7 +71,22 @@ impl ParquetStore {
         ])
     }
 
+    /// The parquet codec for the configured compression
+    fn codec(&self) -> Result<Compression> {
+        Ok(match self.config.compression {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Zstd { level } => Compression::ZSTD(
+                ZstdLevel::try_new(level).map_err(|e| StorageError::Parquet(e.to_string()))?,
+            ),
+        })
+    }
+
     /// Write a conversation with its messages to a parquet file
+    ///
+    /// Conversation fields repeat on every row so DuckDB can filter messages
+    /// by them; dictionary encoding stores each value once per file.
+    #[allow(clippy::type_complexity)]
     pub fn write_conversation(
         &self,
         _account_id: &str,
@@ -78,11 +100,11 @@ impl ParquetStore {
             fs::create_dir_all(parent)?;
         }
 
-        let file = File::create(&path)?;
+        let (target, file) = AtomicFile::create(&path)?;
         let schema = Arc::new(Self::combined_schema());
 
         let props = WriterProperties::builder()
-            .set_compression(parquet::basic::Compression::ZSTD(Default::default()))
+            .set_compression(self.codec()?)
             .build();
 
         let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))
@@ -102,7 +124,15 @@ impl ParquetStore {
         let conv_is_archiveds: Vec<bool> = vec![conv.is_archived; num_rows];
 
         // Message data
-        let (msg_ids, msg_parent_ids, msg_roles, msg_content_types, msg_content_jsons, msg_created_ats, msg_models): (
+        let (
+            msg_ids,
+            msg_parent_ids,
+            msg_roles,
+            msg_content_types,
+            msg_content_jsons,
+            msg_created_ats,
+            msg_models,
+        ): (
             Vec<String>,
             Vec<Option<String>>,
             Vec<String>,
@@ -130,6 +160,8 @@ impl ParquetStore {
                         MessageContent::Code { .. } => "code",
                         MessageContent::Image { .. } => "image",
                         MessageContent::Audio { .. } => "audio",
+                        MessageContent::Document { .. } => "document",
+                        MessageContent::Thinking { .. } => "thinking",
                         MessageContent::Mixed { .. } => "mixed",
                     };
                     let content_json = serde_json::to_string(&m.content).unwrap_or_default();
@@ -171,14 +203,10 @@ impl ParquetStore {
                 Arc::new(StringArray::from(conv_ids)) as ArrayRef,
                 Arc::new(StringArray::from(conv_provider_ids)) as ArrayRef,
                 Arc::new(StringArray::from(conv_titles)) as ArrayRef,
-                Arc::new(
-                    TimestampMillisecondArray::from(conv_created_ats)
-                        .with_timezone("UTC"),
-                ) as ArrayRef,
-                Arc::new(
-                    TimestampMillisecondArray::from(conv_updated_ats)
-                        .with_timezone("UTC"),
-                ) as ArrayRef,
+                Arc::new(TimestampMillisecondArray::from(conv_created_ats).with_timezone("UTC"))
+                    as ArrayRef,
+                Arc::new(TimestampMillisecondArray::from(conv_updated_ats).with_timezone("UTC"))
+                    as ArrayRef,
                 Arc::new(StringArray::from(conv_models)) as ArrayRef,
                 Arc::new(StringArray::from(conv_project_ids)) as ArrayRef,
                 Arc::new(StringArray::from(conv_project_names)) as ArrayRef,
@@ -188,10 +216,8 @@ impl ParquetStore {
                 Arc::new(StringArray::from(msg_roles)) as ArrayRef,
                 Arc::new(StringArray::from(msg_content_types)) as ArrayRef,
                 Arc::new(StringArray::from(msg_content_jsons)) as ArrayRef,
-                Arc::new(
-                    TimestampMillisecondArray::from(msg_created_ats)
-                        .with_timezone("UTC"),
-                ) as ArrayRef,
+                Arc::new(TimestampMillisecondArray::from(msg_created_ats).with_timezone("UTC"))
+                    as ArrayRef,
                 Arc::new(StringArray::from(msg_models)) as ArrayRef,
             ],
         )?;
@@ -202,6 +228,7 @@ impl ParquetStore {
         writer
             .close()
             .map_err(|e| StorageError::Parquet(e.to_string()))?;
+        target.commit()?;
 
         Ok(path)
     }
@@ -221,14 +248,14 @@ impl ParquetStore {
         let file = File::open(&path)?;
         let builder = ParquetRecordBatchReaderBuilder::try_new(file)
             .map_err(|e| StorageError::Parquet(e.to_string()))?;
-        let mut reader = builder
+        let reader = builder
             .build()
             .map_err(|e| StorageError::Parquet(e.to_string()))?;
 
         let mut conversation: Option<Conversation> = None;
         let mut messages: Vec<Message> = Vec::new();
 
-        while let Some(batch_result) = reader.next() {
+        for batch_result in reader {
             let batch = batch_result?;
 
             // Extract conversation from first row
@@ -253,17 +280,13 @@ impl ParquetStore {
                 let conv_created_at = batch
                     .column_by_name("conv_created_at")
                     .and_then(|c| c.as_any().downcast_ref::<TimestampMillisecondArray>())
-                    .and_then(|a| {
-                        DateTime::from_timestamp_millis(a.value(0))
-                    })
+                    .and_then(|a| DateTime::from_timestamp_millis(a.value(0)))
                     .unwrap_or_else(Utc::now);
 
                 let conv_updated_at = batch
                     .column_by_name("conv_updated_at")
                     .and_then(|c| c.as_any().downcast_ref::<TimestampMillisecondArray>())
-                    .and_then(|a| {
-                        DateTime::from_timestamp_millis(a.value(0))
-                    })
+                    .and_then(|a| DateTime::from_timestamp_millis(a.value(0)))
                     .unwrap_or_else(Utc::now);
 
                 let conv_model = batch
@@ -356,12 +379,10 @@ impl ParquetStore {
                         _ => Role::User,
                     };
 
-                    let content: MessageContent =
-                        serde_json::from_str(content_jsons.value(i)).unwrap_or(
-                            MessageContent::Text {
-                                text: content_jsons.value(i).to_string(),
-                            },
-                        );
+                    let content: MessageContent = serde_json::from_str(content_jsons.value(i))
+                        .unwrap_or(MessageContent::Text {
+                            text: content_jsons.value(i).to_string(),
+                        });
 
                     let parent_id = msg_parent_ids.and_then(|a| {
                         if a.is_null(i) {
@@ -395,6 +416,7 @@ impl ParquetStore {
                         content,
                         created_at,
                         model,
+                        speaker: None,
                     });
                 }
             }
@@ -424,6 +446,119 @@ impl ParquetStore {
 
         Ok(ids)
     }
+
+    /// Providers with a conversations directory
+    pub fn providers(&self) -> Result<Vec<String>> {
+        let dir = self.config.base_dir.join("conversations");
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut providers = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                providers.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        providers.sort();
+        Ok(providers)
+    }
+
+    /// Call `f` with every message of a provider, one file at a time
+    ///
+    /// Only the columns `f` sees are decoded, [`SCAN_BATCH_ROWS`] rows at a
+    /// time, so memory stays flat however large the conversations are.
+    /// Returns `Break` if `f` stopped the scan.
+    pub fn scan_messages<F>(&self, provider: &str, mut f: F) -> Result<ControlFlow<()>>
+    where
+        F: FnMut(ScannedMessage) -> ControlFlow<()>,
+    {
+        let mut ids = self.list_conversation_ids(provider)?;
+        ids.sort();
+        for id in ids {
+            let file = File::open(self.config.conversation_path(provider, &id))?;
+            let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+                .map_err(|e| StorageError::Parquet(e.to_string()))?;
+            let mask = ProjectionMask::columns(
+                builder.parquet_schema(),
+                [
+                    "conv_id",
+                    "conv_title",
+                    "msg_id",
+                    "msg_role",
+                    "msg_content_json",
+                ],
+            );
+            let reader = builder
+                .with_projection(mask)
+                .with_batch_size(SCAN_BATCH_ROWS)
+                .build()
+                .map_err(|e| StorageError::Parquet(e.to_string()))?;
+
+            for batch in reader {
+                let batch = batch?;
+                let column = |name: &str| {
+                    batch
+                        .column_by_name(name)
+                        .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                        .ok_or_else(|| {
+                            StorageError::Parquet(format!("{}.parquet has no {} column", id, name))
+                        })
+                };
+                let (conv_ids, titles, msg_ids, roles, contents) = (
+                    column("conv_id")?,
+                    column("conv_title")?,
+                    column("msg_id")?,
+                    column("msg_role")?,
+                    column("msg_content_json")?,
+                );
+                for i in 0..batch.num_rows() {
+                    // Skip placeholder rows (empty message id)
+                    if msg_ids.value(i).is_empty() {
+                        continue;
+                    }
+                    let message = ScannedMessage {
+                        provider_id: provider.to_string(),
+                        conversation_id: conv_ids.value(i).to_string(),
+                        conversation_title: titles.value(i).to_string(),
+                        message_id: msg_ids.value(i).to_string(),
+                        role: Role::parse(roles.value(i)).unwrap_or(Role::User),
+                        content_json: contents.value(i).to_string(),
+                    };
+                    if f(message).is_break() {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Bytes of parquet on disk for a provider (conversations and embeddings)
+    pub fn disk_usage(&self, provider: &str) -> Result<u64> {
+        let mut total = 0;
+        for dir in [
+            self.config.base_dir.join("conversations").join(provider),
+            self.config.embeddings_dir(provider),
+        ] {
+            if !dir.exists() {
+                continue;
+            }
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().map(|e| e == "parquet").unwrap_or(false) {
+                    total += fs::metadata(&path)?.len();
+                }
+            }
+        }
+
+        let consolidated = self.config.consolidated_embeddings_path(provider);
+        if consolidated.exists() {
+            total += fs::metadata(&consolidated)?.len();
+        }
+
+        Ok(total)
+    }
 }
 
 #[cfg(test)]
@@ -456,6 +591,7 @@ mod tests {
             },
             created_at: Some(Utc::now()),
             model: None,
+            speaker: None,
         }
     }
 
@@ -476,10 +612,7 @@ mod tests {
             .unwrap();
 
         assert!(path.exists());
-        assert_eq!(
-            path,
-            config.conversation_path("chatgpt", "conv-123")
-        );
+        assert_eq!(path, config.conversation_path("chatgpt", "conv-123"));
     }
 
     #[test]
@@ -588,6 +721,24 @@ mod tests {
         assert!(ids.is_empty());
     }
 
+    #[test]
+    fn test_disk_usage() {
+        let dir = tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let store = ParquetStore::new(config.clone());
+
+        assert_eq!(store.disk_usage("chatgpt").unwrap(), 0);
+
+        let conv = create_test_conversation();
+        store.write_conversation("user-123", &conv, &[]).unwrap();
+
+        let written = std::fs::metadata(config.conversation_path("chatgpt", &conv.id))
+            .unwrap()
+            .len();
+        assert_eq!(store.disk_usage("chatgpt").unwrap(), written);
+        assert_eq!(store.disk_usage("claude").unwrap(), 0);
+    }
+
     #[test]
     fn test_message_content_types() {
         let dir = tempdir().unwrap();
@@ -606,6 +757,7 @@ mod tests {
                 },
                 created_at: Some(Utc::now()),
                 model: None,
+                speaker: None,
             },
             Message {
                 id: "msg-2".to_string(),
@@ -618,6 +770,19 @@ mod tests {
                 },
                 created_at: Some(Utc::now()),
                 model: Some("gpt-4".to_string()),
+                speaker: None,
+            },
+            Message {
+                id: "msg-3".to_string(),
+                conversation_id: conv.id.clone(),
+                parent_id: Some("msg-2".to_string()),
+                role: Role::Assistant,
+                content: MessageContent::Thinking {
+                    text: "Check the signature first".to_string(),
+                },
+                created_at: Some(Utc::now()),
+                model: None,
+                speaker: None,
             },
         ];
 
@@ -628,7 +793,7 @@ mod tests {
         let result = store.read_conversation("chatgpt", "conv-123").unwrap();
         let (_, read_messages) = result.unwrap();
 
-        assert_eq!(read_messages.len(), 2);
+        assert_eq!(read_messages.len(), 3);
 
         // Check first message
         assert!(matches!(
@@ -643,6 +808,82 @@ mod tests {
         ));
         assert_eq!(read_messages[1].parent_id, Some("msg-1".to_string()));
         assert_eq!(read_messages[1].role, Role::Assistant);
+
+        match &read_messages[2].content {
+            MessageContent::Thinking { text } => assert_eq!(text, "Check the signature first"),
+            other => panic!("Expected Thinking content, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_compression_is_configurable() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = tempdir().unwrap();
+        let conv = create_test_conversation();
+        let messages = vec![create_test_message(&conv.id, "msg-1", "Hello!")];
+        let codec = |compression| {
+            let config = ParquetStorageConfig::new(dir.path()).with_compression(compression);
+            let path = ParquetStore::new(config)
+                .write_conversation("user-123", &conv, &messages)
+                .unwrap();
+            let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+            reader.metadata().row_group(0).column(0).compression()
+        };
+
+        assert!(matches!(
+            codec(ParquetCompression::default()),
+            Compression::ZSTD(_)
+        ));
+        assert!(matches!(
+            codec(ParquetCompression::Zstd { level: 19 }),
+            Compression::ZSTD(_)
+        ));
+        assert_eq!(codec(ParquetCompression::Snappy), Compression::SNAPPY);
+        assert_eq!(
+            codec(ParquetCompression::Uncompressed),
+            Compression::UNCOMPRESSED
+        );
+
+        // Files read back whatever they were written with
+        let store = ParquetStore::new(ParquetStorageConfig::new(dir.path()));
+        let (read_conv, read_messages) = store
+            .read_conversation("chatgpt", "conv-123")
+            .unwrap()
+            .unwrap();
+        assert_eq!(read_conv.title, conv.title);
+        assert_eq!(read_messages.len(), 1);
+
+        let invalid = ParquetStorageConfig::new(dir.path())
+            .with_compression(ParquetCompression::Zstd { level: 0 });
+        assert!(ParquetStore::new(invalid)
+            .write_conversation("user-123", &conv, &messages)
+            .is_err());
+    }
+
+    #[test]
+    fn test_conversation_fields_are_dictionary_encoded() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = tempdir().unwrap();
+        let store = ParquetStore::new(ParquetStorageConfig::new(dir.path()));
+        let conv = create_test_conversation();
+        let messages: Vec<Message> = (0..500)
+            .map(|i| create_test_message(&conv.id, &format!("msg-{}", i), "Hello!"))
+            .collect();
+        let path = store
+            .write_conversation("user-123", &conv, &messages)
+            .unwrap();
+
+        // Repeated per row, but dictionary-encoded to a single value
+        let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+        let row_group = reader.metadata().row_group(0);
+        let title = row_group
+            .columns()
+            .iter()
+            .find(|column| column.column_path().string() == "conv_title")
+            .unwrap();
+        assert!(title.dictionary_page_offset().is_some());
     }
 
     #[test]
@@ -674,4 +915,37 @@ mod tests {
 
         assert_eq!(read_messages.len(), 2);
     }
+
+    #[test]
+    fn test_failed_overwrite_keeps_previous_file() {
+        let dir = tempdir().unwrap();
+        let store = ParquetStore::new(ParquetStorageConfig::new(dir.path()));
+        let conv = create_test_conversation();
+        let messages = vec![create_test_message(&conv.id, "msg-1", "Good version")];
+        let path = store
+            .write_conversation("user-123", &conv, &messages)
+            .unwrap();
+
+        // The codec is rejected after the temporary file is created
+        let failing = ParquetStore::new(
+            ParquetStorageConfig::new(dir.path())
+                .with_compression(ParquetCompression::Zstd { level: 0 }),
+        );
+        let updated = vec![create_test_message(&conv.id, "msg-2", "Lost version")];
+        assert!(failing
+            .write_conversation("user-123", &conv, &updated)
+            .is_err());
+
+        let (_, read_messages) = store
+            .read_conversation("chatgpt", "conv-123")
+            .unwrap()
+            .unwrap();
+        assert_eq!(read_messages.len(), 1);
+        assert_eq!(read_messages[0].id, "msg-1");
+        let files: Vec<_> = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, [path.file_name().unwrap().to_os_string()]);
+    }
 }
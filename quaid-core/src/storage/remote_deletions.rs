@@ -0,0 +1,183 @@
+//! Conversations deleted upstream
+//!
+//! Deleting a conversation on the provider's site doesn't delete it here.
+//! Instead, a full listing of an account is compared with what's stored for
+//! it, and conversations it no longer has get a `remote_deleted_at` time.
+//! One that shows up again is cleared. Only listings known to be complete
+//! are compared; an incremental one would make everything it left out look
+//! deleted.
+
+use super::{Result, Store};
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+
+/// What comparing a listing with the stored conversations changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteDeletions {
+    /// Conversations the listing no longer has
+    pub deleted: Vec<String>,
+    /// Conversations marked deleted before that the listing has again
+    pub restored: Vec<String>,
+}
+
+/// Compare the stored conversations of an account, each with whether it's
+/// already marked deleted, with the ids a complete listing of it returned
+fn compare_listing(stored: &[(String, bool)], listed: &HashSet<String>) -> RemoteDeletions {
+    let mut changes = RemoteDeletions::default();
+    for (id, marked) in stored {
+        match (listed.contains(id), marked) {
+            (false, false) => changes.deleted.push(id.clone()),
+            (true, true) => changes.restored.push(id.clone()),
+            _ => {}
+        }
+    }
+    changes
+}
+
+impl Store {
+    /// Mark the stored conversations of `provider`'s `account_id` that a
+    /// complete listing no longer has as deleted upstream
+    ///
+    /// Conversations of other accounts, or of the same account id under
+    /// another provider, are left alone.
+    pub fn record_remote_listing(
+        &self,
+        provider: &str,
+        account_id: &str,
+        listed: &HashSet<String>,
+    ) -> Result<RemoteDeletions> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, remote_deleted_at IS NOT NULL FROM conversations
+             WHERE account_id = ?1 AND provider_id = ?2",
+        )?;
+        let stored = stmt
+            .query_map(params![account_id, provider], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, bool)>>>()?;
+        let changes = compare_listing(&stored, listed);
+
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction()?;
+        for id in &changes.deleted {
+            tx.execute(
+                "UPDATE conversations SET remote_deleted_at = ?2 WHERE id = ?1",
+                params![id, now],
+            )?;
+        }
+        for id in &changes.restored {
+            tx.execute(
+                "UPDATE conversations SET remote_deleted_at = NULL WHERE id = ?1",
+                params![id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(changes)
+    }
+
+    /// When each of an account's conversations deleted upstream was found gone
+    pub fn remotely_deleted(&self, account_id: &str) -> Result<HashMap<String, DateTime<Utc>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, remote_deleted_at FROM conversations
+             WHERE account_id = ?1 AND remote_deleted_at IS NOT NULL",
+        )?;
+        let rows = stmt
+            .query_map(params![account_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, at)| {
+                let at = DateTime::parse_from_rfc3339(&at).ok()?;
+                Some((id, at.with_timezone(&Utc)))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, ProviderId};
+
+    fn ids(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn save(store: &Store, provider: ProviderId, account_id: &str, ids: &[&str]) {
+        store
+            .save_account(&Account {
+                id: account_id.to_string(),
+                provider: provider.clone(),
+                email: format!("{}@example.com", account_id),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        for id in ids {
+            let conversation = Conversation {
+                id: id.to_string(),
+                provider_id: provider.0.clone(),
+                title: id.to_string(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                model: None,
+                project_id: None,
+                project_name: None,
+                is_archived: false,
+            };
+            store.save_conversation(account_id, &conversation).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compare_listing() {
+        let stored = vec![
+            ("kept".to_string(), false),
+            ("deleted".to_string(), false),
+            ("still-deleted".to_string(), true),
+            ("back".to_string(), true),
+        ];
+        let changes = compare_listing(&stored, &ids(&["kept", "back", "new"]));
+        assert_eq!(changes.deleted, ["deleted"]);
+        assert_eq!(changes.restored, ["back"]);
+    }
+
+    #[test]
+    fn test_record_remote_listing_is_scoped_to_the_account() {
+        let store = Store::in_memory().unwrap();
+        save(&store, ProviderId::claude(), "user-1", &["a", "b"]);
+        save(&store, ProviderId::claude(), "user-2", &["c"]);
+        save(&store, ProviderId::chatgpt(), "user-3", &["d"]);
+
+        let changes = store
+            .record_remote_listing("claude", "user-1", &ids(&["a"]))
+            .unwrap();
+        assert_eq!(changes.deleted, ["b"]);
+        assert_eq!(
+            store
+                .remotely_deleted("user-1")
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            ["b"]
+        );
+        assert!(store.remotely_deleted("user-2").unwrap().is_empty());
+        assert!(store.remotely_deleted("user-3").unwrap().is_empty());
+
+        // Listed again, it's no longer marked; marked ones aren't marked twice
+        let changes = store
+            .record_remote_listing("claude", "user-1", &ids(&["b"]))
+            .unwrap();
+        assert_eq!(changes.deleted, ["a"]);
+        assert_eq!(changes.restored, ["b"]);
+        assert!(store
+            .record_remote_listing("claude", "user-1", &ids(&["b"]))
+            .unwrap()
+            .deleted
+            .is_empty());
+    }
+}
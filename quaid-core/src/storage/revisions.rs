@@ -0,0 +1,279 @@
+//! Earlier versions of conversations
+//!
+//! Pulls overwrite a conversation in place when its title or messages change
+//! upstream. With `--keep-history`, the stored version is first copied to
+//! `conversation_revisions`, stamped with the time of the pull that replaced
+//! it. Only the newest revisions of each conversation are kept.
+
+use super::diff::content_hash;
+use super::{Result, Store};
+use crate::providers::{Conversation, Message};
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// A stored version of a conversation that a later pull replaced
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub conversation_id: String,
+    /// When the pull that replaced this version ran
+    pub replaced_at: DateTime<Utc>,
+    pub title: String,
+    pub messages: Vec<Message>,
+}
+
+/// How two versions of a conversation differ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RevisionDiff {
+    /// The old and new title, if it changed
+    pub title: Option<(String, String)>,
+    /// Ids of messages only the newer version has
+    pub added: Vec<String>,
+    /// Ids of messages only the older version has
+    pub removed: Vec<String>,
+    /// Ids of messages in both whose role or content changed
+    pub edited: Vec<String>,
+}
+
+impl RevisionDiff {
+    /// Whether the versions are the same
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.edited.is_empty()
+    }
+}
+
+/// Hash of a message's role and content, which edits and regenerations change
+pub fn message_hash(message: &Message) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(message.role.as_str().as_bytes());
+    hasher.update([0]);
+    hasher.update(serde_json::to_vec(&message.content).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Compare an older version of a conversation with a newer one
+///
+/// Messages are matched by id, so those listed keep the newer version's order
+/// (the older one's for removed messages).
+pub fn diff_versions(
+    old_title: &str,
+    old_messages: &[Message],
+    new_title: &str,
+    new_messages: &[Message],
+) -> RevisionDiff {
+    let old: HashMap<&str, String> = old_messages
+        .iter()
+        .map(|m| (m.id.as_str(), message_hash(m)))
+        .collect();
+    let new: HashMap<&str, &Message> = new_messages.iter().map(|m| (m.id.as_str(), m)).collect();
+
+    let mut diff = RevisionDiff {
+        title: (old_title != new_title).then(|| (old_title.to_string(), new_title.to_string())),
+        ..Default::default()
+    };
+    for message in new_messages {
+        match old.get(message.id.as_str()) {
+            None => diff.added.push(message.id.clone()),
+            Some(hash) if *hash != message_hash(message) => diff.edited.push(message.id.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old_messages
+        .iter()
+        .filter(|m| !new.contains_key(m.id.as_str()))
+        .map(|m| m.id.clone())
+        .collect();
+    diff
+}
+
+impl Store {
+    /// Keep the stored version of `conversation` as a revision if the
+    /// incoming `messages` change it, then drop all but the newest `keep`
+    ///
+    /// Returns whether a revision was recorded; new and unchanged
+    /// conversations don't get one.
+    pub fn record_revision(
+        &self,
+        conversation: &Conversation,
+        messages: &[Message],
+        keep: usize,
+    ) -> Result<bool> {
+        let Some(stored) = self.get_conversation(&conversation.id)? else {
+            return Ok(false);
+        };
+        let stored_messages = self.get_messages(&conversation.id)?;
+        if content_hash(&stored, &stored_messages) == content_hash(conversation, messages) {
+            return Ok(false);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO conversation_revisions (conversation_id, replaced_at, title, messages_json)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                stored.id,
+                Utc::now().to_rfc3339(),
+                stored.title,
+                serde_json::to_string(&stored_messages)?,
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM conversation_revisions
+             WHERE conversation_id = ?1 AND id NOT IN (
+                 SELECT id FROM conversation_revisions WHERE conversation_id = ?1
+                 ORDER BY id DESC LIMIT ?2
+             )",
+            params![stored.id, keep as i64],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Revisions kept of a conversation, oldest first
+    pub fn revisions(&self, conversation_id: &str) -> Result<Vec<Revision>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT replaced_at, title, messages_json FROM conversation_revisions
+             WHERE conversation_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![conversation_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(replaced_at, title, messages_json)| {
+                Ok(Revision {
+                    conversation_id: conversation_id.to_string(),
+                    replaced_at: DateTime::parse_from_rfc3339(&replaced_at)
+                        .map(|at| at.with_timezone(&Utc))
+                        .unwrap_or_default(),
+                    title,
+                    messages: serde_json::from_str(&messages_json)?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, MessageContent, ProviderId, Role};
+
+    fn conversation(title: &str) -> Conversation {
+        Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "chatgpt".to_string(),
+            title: title.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    fn message(id: &str, text: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        }
+    }
+
+    /// Pull `title` and `messages` the way `quaid pull --keep-history` does
+    fn pull(store: &Store, title: &str, messages: &[Message], keep: usize) -> bool {
+        let conv = conversation(title);
+        let recorded = store.record_revision(&conv, messages, keep).unwrap();
+        store
+            .save_conversation_with_messages("user-1", &conv, messages)
+            .unwrap();
+        recorded
+    }
+
+    #[test]
+    fn test_diff_versions() {
+        let old = [
+            message("a", "Hi"),
+            message("b", "Old answer"),
+            message("c", "Bye"),
+        ];
+        let new = [
+            message("a", "Hi"),
+            message("b", "New answer"),
+            message("d", "More"),
+        ];
+
+        let diff = diff_versions("Draft", &old, "Final", &new);
+        assert_eq!(diff.title, Some(("Draft".to_string(), "Final".to_string())));
+        assert_eq!(diff.added, ["d"]);
+        assert_eq!(diff.removed, ["c"]);
+        assert_eq!(diff.edited, ["b"]);
+        assert!(diff_versions("Final", &new, "Final", &new).is_empty());
+    }
+
+    #[test]
+    fn test_two_pulls_with_modified_messages() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::chatgpt(),
+                email: "user@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+
+        let first = [message("a", "Question"), message("b", "First answer")];
+        assert!(!pull(&store, "Draft", &first, 10));
+        // Pulling the same content again keeps no revision
+        assert!(!pull(&store, "Draft", &first, 10));
+
+        let second = [message("a", "Question"), message("b", "Regenerated answer")];
+        assert!(pull(&store, "Renamed", &second, 10));
+
+        let revisions = store.revisions("conv-1").unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].title, "Draft");
+        let current = store.get_messages("conv-1").unwrap();
+        let diff = diff_versions(
+            &revisions[0].title,
+            &revisions[0].messages,
+            "Renamed",
+            &current,
+        );
+        assert_eq!(
+            diff.title,
+            Some(("Draft".to_string(), "Renamed".to_string()))
+        );
+        assert_eq!(diff.edited, ["b"]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+
+        // Only the newest revisions are kept
+        let third = [message("a", "Question"), message("c", "Another answer")];
+        assert!(pull(&store, "Renamed", &third, 1));
+        let revisions = store.revisions("conv-1").unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].title, "Renamed");
+        assert!(revisions[0].messages.iter().any(|m| m.id == "b"));
+    }
+}
@@ -0,0 +1,202 @@
+//! Message-at-a-time scans over the whole archive
+//!
+//! For work that has to look at every message, like `quaid grep`, without
+//! loading whole conversations. [`Store::scan_messages`] walks SQLite row by
+//! row; [`ParquetStore::scan_messages`](super::parquet::ParquetStore::scan_messages)
+//! reads Parquet files a batch at a time. Both hand messages to a callback
+//! grouped by conversation, and stop when it returns `ControlFlow::Break`.
+
+use super::{Result, Store};
+use crate::providers::{MessageContent, Role};
+use rusqlite::params;
+use std::ops::ControlFlow;
+
+/// One stored message and the conversation it belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedMessage {
+    pub provider_id: String,
+    pub conversation_id: String,
+    pub conversation_title: String,
+    pub message_id: String,
+    pub role: Role,
+    pub content_json: String,
+}
+
+impl ScannedMessage {
+    /// The message content, or the raw JSON as text if it doesn't parse
+    pub fn content(&self) -> MessageContent {
+        serde_json::from_str(&self.content_json).unwrap_or_else(|_| MessageContent::Text {
+            text: self.content_json.clone(),
+        })
+    }
+}
+
+impl Store {
+    /// Call `f` with every message, or `provider`'s, newest conversation first
+    ///
+    /// Returns `Break` if `f` stopped the scan.
+    pub fn scan_messages<F>(&self, provider: Option<&str>, mut f: F) -> Result<ControlFlow<()>>
+    where
+        F: FnMut(ScannedMessage) -> ControlFlow<()>,
+    {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.provider_id, c.id, c.title, m.id, m.role, m.content_json
+             FROM messages m
+             JOIN conversations c ON c.id = m.conversation_id
+             WHERE ?1 IS NULL OR c.provider_id = ?1
+             ORDER BY c.updated_at DESC, c.id, m.rowid",
+        )?;
+        let mut rows = stmt.query(params![provider])?;
+        while let Some(row) = rows.next()? {
+            let role: String = row.get(4)?;
+            let message = ScannedMessage {
+                provider_id: row.get(0)?,
+                conversation_id: row.get(1)?,
+                conversation_title: row.get(2)?,
+                message_id: row.get(3)?,
+                role: Role::parse(&role).unwrap_or(Role::User),
+                content_json: row.get(5)?,
+            };
+            if f(message).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, Message, ProviderId};
+    use crate::storage::parquet::ParquetStore;
+    use crate::storage::ParquetStorageConfig;
+    use chrono::{TimeZone, Utc};
+
+    fn conversation(provider: &str, id: &str, day: u32) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            provider_id: provider.to_string(),
+            title: format!("Title {}", id),
+            created_at: Utc.with_ymd_and_hms(2024, 1, day, 9, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 1, day, 9, 0, 0).unwrap(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    fn messages(conversation_id: &str, count: usize) -> Vec<Message> {
+        (0..count)
+            .map(|i| Message {
+                id: format!("{}-msg-{}", conversation_id, i),
+                conversation_id: conversation_id.to_string(),
+                parent_id: None,
+                role: if i % 2 == 0 {
+                    Role::User
+                } else {
+                    Role::Assistant
+                },
+                content: MessageContent::Text {
+                    text: format!("line {}", i),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sqlite_scan_filters_and_stops() {
+        let store = Store::in_memory().unwrap();
+        for (id, provider) in [
+            ("user-1", ProviderId::claude()),
+            ("user-2", ProviderId::chatgpt()),
+        ] {
+            store
+                .save_account(&Account {
+                    id: id.to_string(),
+                    provider,
+                    email: "a@example.com".to_string(),
+                    name: None,
+                    avatar_url: None,
+                    workspace_id: None,
+                })
+                .unwrap();
+        }
+        for (account, conv) in [
+            ("user-1", conversation("claude", "old", 1)),
+            ("user-1", conversation("claude", "new", 2)),
+            ("user-2", conversation("chatgpt", "other", 3)),
+        ] {
+            store
+                .save_conversation_with_messages(account, &conv, &messages(&conv.id, 2))
+                .unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let flow = store
+            .scan_messages(Some("claude"), |m| {
+                seen.push(m.message_id);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert!(flow.is_continue());
+        assert_eq!(
+            seen,
+            vec!["new-msg-0", "new-msg-1", "old-msg-0", "old-msg-1"]
+        );
+
+        let mut count = 0;
+        let flow = store
+            .scan_messages(None, |_| {
+                count += 1;
+                if count == 3 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert!(flow.is_break());
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_parquet_scan_reads_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let parquet = ParquetStore::new(ParquetStorageConfig::new(dir.path()));
+        let big = conversation("claude", "big", 1);
+        parquet
+            .write_conversation("user-1", &big, &messages("big", 1000))
+            .unwrap();
+        let small = conversation("claude", "small", 2);
+        parquet
+            .write_conversation("user-1", &small, &messages("small", 1))
+            .unwrap();
+
+        let mut seen = Vec::new();
+        let flow = parquet
+            .scan_messages("claude", |m| {
+                seen.push((m.conversation_id, m.message_id, m.role));
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert!(flow.is_continue());
+        assert_eq!(seen.len(), 1001);
+        assert_eq!(
+            seen[0],
+            ("big".to_string(), "big-msg-0".to_string(), Role::User)
+        );
+        assert_eq!(seen[999].1, "big-msg-999");
+        assert_eq!(seen[1000].0, "small");
+
+        assert_eq!(parquet.providers().unwrap(), vec!["claude"]);
+        assert!(parquet
+            .scan_messages("chatgpt", |_| ControlFlow::Continue(()))
+            .unwrap()
+            .is_continue());
+    }
+}
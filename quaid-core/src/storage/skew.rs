@@ -0,0 +1,260 @@
+//! Timestamps from providers whose clocks run ahead
+//!
+//! Some Granola documents and ChatGPT messages arrive dated hours in the
+//! future. Saved as-is, they sit at the top of `quaid list` forever and make
+//! `--new-only` skip real updates, which look older than the stored time.
+//! Anything more than [`MAX_CLOCK_SKEW_MINUTES`] ahead is saved as the time of
+//! the save instead; the original goes into the row's `raw_json`.
+
+use crate::providers::{Conversation, Message};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::fmt;
+
+/// How far ahead of the local clock a timestamp may be before it's clamped
+pub const MAX_CLOCK_SKEW_MINUTES: i64 = 10;
+
+/// A timestamp that was saved as `clamped_to` instead of `original`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClampedTimestamp {
+    pub conversation_id: String,
+    /// Set when the timestamp was a message's
+    pub message_id: Option<String>,
+    /// `created_at` or `updated_at`
+    pub field: &'static str,
+    pub original: DateTime<Utc>,
+    pub clamped_to: DateTime<Utc>,
+}
+
+impl fmt::Display for ClampedTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "conversation {}", self.conversation_id)?;
+        if let Some(message_id) = &self.message_id {
+            write!(f, " message {}", message_id)?;
+        }
+        write!(
+            f,
+            " has {} {} in the future; saved as {}",
+            self.field,
+            self.original.to_rfc3339(),
+            self.clamped_to.to_rfc3339()
+        )
+    }
+}
+
+/// Whether `at` is further ahead of `now` than the allowed skew
+pub fn is_future(at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    at > now + Duration::minutes(MAX_CLOCK_SKEW_MINUTES)
+}
+
+/// `conv` with future timestamps moved back to `now`, and what was moved
+pub fn sanitize_conversation(
+    conv: &Conversation,
+    now: DateTime<Utc>,
+) -> (Conversation, Vec<ClampedTimestamp>) {
+    let mut sanitized = conv.clone();
+    let mut clamped = Vec::new();
+    for (field, at) in [
+        ("created_at", &mut sanitized.created_at),
+        ("updated_at", &mut sanitized.updated_at),
+    ] {
+        if is_future(*at, now) {
+            clamped.push(ClampedTimestamp {
+                conversation_id: conv.id.clone(),
+                message_id: None,
+                field,
+                original: *at,
+                clamped_to: now,
+            });
+            *at = now;
+        }
+    }
+    (sanitized, clamped)
+}
+
+/// `message` with a future `created_at` moved back to `now`, and what was moved
+pub fn sanitize_message(
+    message: &Message,
+    now: DateTime<Utc>,
+) -> (Message, Option<ClampedTimestamp>) {
+    let mut sanitized = message.clone();
+    let clamped = match message.created_at {
+        Some(at) if is_future(at, now) => {
+            sanitized.created_at = Some(now);
+            Some(ClampedTimestamp {
+                conversation_id: message.conversation_id.clone(),
+                message_id: Some(message.id.clone()),
+                field: "created_at",
+                original: at,
+                clamped_to: now,
+            })
+        }
+        _ => None,
+    };
+    (sanitized, clamped)
+}
+
+/// `raw_json` recording the original values of clamped timestamps
+pub fn raw_json(clamped: &[ClampedTimestamp]) -> Option<String> {
+    if clamped.is_empty() {
+        return None;
+    }
+    let originals: serde_json::Map<_, _> = clamped
+        .iter()
+        .map(|c| (c.field.to_string(), c.original.to_rfc3339().into()))
+        .collect();
+    Some(serde_json::json!({ "future_timestamps": originals }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, MessageContent, ProviderId, Role};
+    use crate::storage::{SkipRule, Store};
+
+    fn future_conversation(now: DateTime<Utc>) -> (Conversation, Vec<Message>) {
+        let conv = Conversation {
+            id: "doc-1".to_string(),
+            provider_id: "granola".to_string(),
+            title: "Standup".to_string(),
+            created_at: now - Duration::hours(1),
+            updated_at: now + Duration::hours(5),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        let messages = vec![Message {
+            id: "doc-1-notes".to_string(),
+            conversation_id: "doc-1".to_string(),
+            parent_id: None,
+            role: Role::Assistant,
+            content: MessageContent::Text {
+                text: "Notes".to_string(),
+            },
+            created_at: Some(now + Duration::hours(5)),
+            model: None,
+            speaker: None,
+        }];
+        (conv, messages)
+    }
+
+    fn store() -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "granola-user".to_string(),
+                provider: ProviderId::granola(),
+                email: "a@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_small_skew_is_kept() {
+        let now = Utc::now();
+        let (mut conv, _) = future_conversation(now);
+        conv.updated_at = now + Duration::minutes(MAX_CLOCK_SKEW_MINUTES - 1);
+
+        let (sanitized, clamped) = sanitize_conversation(&conv, now);
+        assert!(clamped.is_empty());
+        assert_eq!(sanitized.updated_at, conv.updated_at);
+    }
+
+    #[test]
+    fn test_save_clamps_future_timestamps_and_warns() {
+        let store = store();
+        let before = Utc::now();
+        let (conv, messages) = future_conversation(before);
+
+        let clamped = store
+            .save_conversation_with_messages("granola-user", &conv, &messages)
+            .unwrap();
+        let after = Utc::now();
+
+        assert_eq!(clamped.len(), 2);
+        assert_eq!(clamped[0].field, "updated_at");
+        assert_eq!(clamped[0].original, conv.updated_at);
+        assert!(clamped[0]
+            .to_string()
+            .starts_with("conversation doc-1 has updated_at"));
+        assert_eq!(clamped[1].message_id.as_deref(), Some("doc-1-notes"));
+
+        let stored = store.get_conversation("doc-1").unwrap().unwrap();
+        assert!(stored.updated_at >= before && stored.updated_at <= after);
+        assert_eq!(stored.created_at, conv.created_at);
+        let stored_messages = store.get_messages("doc-1").unwrap();
+        assert!(stored_messages[0].created_at.unwrap() <= after);
+
+        let raw: String = store
+            .conn
+            .query_row(
+                "SELECT raw_json FROM conversations WHERE id = 'doc-1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let raw: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(
+            raw["future_timestamps"]["updated_at"],
+            conv.updated_at.to_rfc3339()
+        );
+    }
+
+    #[test]
+    fn test_update_after_clamp_is_not_skipped() {
+        let store = store();
+        let now = Utc::now();
+        let (conv, messages) = future_conversation(now);
+        store
+            .save_conversation_with_messages("granola-user", &conv, &messages)
+            .unwrap();
+
+        // The same skewed listing again is fetched and left to the content check
+        let decision = store.sync_decision(&conv, true, true).unwrap();
+        assert!(!decision.skips());
+
+        // A genuine edit with a correct clock, shortly after the save
+        let edited = Conversation {
+            updated_at: Utc::now() + Duration::seconds(1),
+            ..conv.clone()
+        };
+        let decision = store.sync_decision(&edited, true, true).unwrap();
+        assert_eq!(decision.rule, SkipRule::RemoteNewer);
+
+        // An old, unchanged listing is still skipped
+        let old = Conversation {
+            updated_at: now - Duration::hours(2),
+            ..conv
+        };
+        assert!(store.sync_decision(&old, true, true).unwrap().skips());
+    }
+
+    #[test]
+    fn test_future_timestamp_stored_before_clamping_is_not_trusted() {
+        let store = store();
+        let now = Utc::now();
+        let (conv, _) = future_conversation(now);
+        store.save_conversation("granola-user", &conv).unwrap();
+        // As saved by versions that didn't clamp
+        store
+            .conn
+            .execute(
+                "UPDATE conversations SET updated_at = ?1 WHERE id = 'doc-1'",
+                [conv.updated_at.to_rfc3339()],
+            )
+            .unwrap();
+
+        let edited = Conversation {
+            updated_at: now,
+            ..conv
+        };
+        let decision = store.sync_decision(&edited, true, true).unwrap();
+        assert_eq!(decision.rule, SkipRule::FutureLocal);
+        assert!(!decision.skips());
+    }
+}
@@ -0,0 +1,397 @@
+//! When each account was last pulled, and how it went
+//!
+//! Pulls record their outcome per (provider, account) so `quaid status` can
+//! report on every account without contacting the providers. Providers that
+//! list page by page also keep where they stopped, so an interrupted pull
+//! picks up from there. Imports from files on disk remember each file's
+//! modification time, so re-imports skip the files that haven't changed.
+
+use super::{Result, Store};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+
+/// Sync history of one account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncState {
+    pub provider: String,
+    pub account_id: String,
+    /// Last pull that finished, full or `--new-only`
+    pub last_pull_at: Option<DateTime<Utc>>,
+    /// Last pull that looked at every conversation
+    pub last_full_pull_at: Option<DateTime<Utc>>,
+    /// Conversations the provider listed on the last pull
+    pub conversations_seen: usize,
+    /// Why the most recent pull failed, cleared by the next one that finishes
+    pub last_error: Option<String>,
+}
+
+/// How far a paginated listing of one account got
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncCursor {
+    pub provider: String,
+    pub account_id: String,
+    /// Next page of a listing that didn't finish; `None` once one does
+    pub cursor: Option<String>,
+    /// Creation time of the newest item synced by a finished listing
+    pub last_synced_at: Option<DateTime<Utc>>,
+}
+
+/// What's stored for one account
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AccountStats {
+    pub conversations: usize,
+    pub messages: usize,
+    pub pending_attachments: usize,
+}
+
+impl Store {
+    /// Record a finished pull that listed `conversations_seen` conversations
+    pub fn record_pull(
+        &self,
+        provider: &str,
+        account_id: &str,
+        full: bool,
+        conversations_seen: usize,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO sync_state
+                 (provider, account_id, last_pull_at, last_full_pull_at, conversations_seen)
+             VALUES (?1, ?2, ?3, CASE WHEN ?4 THEN ?3 END, ?5)
+             ON CONFLICT(provider, account_id) DO UPDATE SET
+                 last_pull_at = excluded.last_pull_at,
+                 last_full_pull_at = COALESCE(excluded.last_full_pull_at, last_full_pull_at),
+                 conversations_seen = excluded.conversations_seen,
+                 last_error = NULL",
+            params![provider, account_id, now, full, conversations_seen as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Record why a pull failed, keeping the times of earlier successful ones
+    pub fn record_pull_error(&self, provider: &str, account_id: &str, error: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO sync_state (provider, account_id, last_error) VALUES (?1, ?2, ?3)
+             ON CONFLICT(provider, account_id) DO UPDATE SET last_error = excluded.last_error",
+            params![provider, account_id, error],
+        )?;
+        Ok(())
+    }
+
+    /// Sync history of one account, if it was ever pulled
+    pub fn sync_state(&self, provider: &str, account_id: &str) -> Result<Option<SyncState>> {
+        let state = self
+            .conn
+            .query_row(
+                "SELECT provider, account_id, last_pull_at, last_full_pull_at,
+                        conversations_seen, last_error
+                 FROM sync_state WHERE provider = ?1 AND account_id = ?2",
+                params![provider, account_id],
+                sync_state_from_row,
+            )
+            .optional()?;
+        Ok(state)
+    }
+
+    /// How far the last listing of an account got, if one was recorded
+    pub fn sync_cursor(&self, provider: &str, account_id: &str) -> Result<Option<SyncCursor>> {
+        let cursor = self
+            .conn
+            .query_row(
+                "SELECT provider, account_id, cursor, last_synced_at
+                 FROM sync_cursors WHERE provider = ?1 AND account_id = ?2",
+                params![provider, account_id],
+                |row| {
+                    Ok(SyncCursor {
+                        provider: row.get(0)?,
+                        account_id: row.get(1)?,
+                        cursor: row.get(2)?,
+                        last_synced_at: parse_time(row.get(3)?),
+                    })
+                },
+            )
+            .optional()?;
+        Ok(cursor)
+    }
+
+    /// Record the page a listing continues from, once the pages before it are saved
+    pub fn save_sync_cursor(&self, provider: &str, account_id: &str, cursor: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO sync_cursors (provider, account_id, cursor) VALUES (?1, ?2, ?3)
+             ON CONFLICT(provider, account_id) DO UPDATE SET cursor = excluded.cursor",
+            params![provider, account_id, cursor],
+        )?;
+        Ok(())
+    }
+
+    /// Record a listing that reached its last page, synced up to `last_synced_at`
+    pub fn finish_sync_cursor(
+        &self,
+        provider: &str,
+        account_id: &str,
+        last_synced_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO sync_cursors (provider, account_id, cursor, last_synced_at)
+             VALUES (?1, ?2, NULL, ?3)
+             ON CONFLICT(provider, account_id) DO UPDATE SET
+                 cursor = NULL,
+                 last_synced_at = COALESCE(excluded.last_synced_at, last_synced_at)",
+            params![provider, account_id, last_synced_at.map(|t| t.to_rfc3339())],
+        )?;
+        Ok(())
+    }
+
+    /// Modification time `path` had when it was last imported, if it was
+    pub fn imported_file_mtime(&self, provider: &str, path: &str) -> Result<Option<DateTime<Utc>>> {
+        let modified_at: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT modified_at FROM imported_files WHERE provider = ?1 AND path = ?2",
+                params![provider, path],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(parse_time(modified_at))
+    }
+
+    /// Record that `path` was imported as it was at `modified_at`
+    pub fn record_imported_file(
+        &self,
+        provider: &str,
+        path: &str,
+        modified_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO imported_files (provider, path, modified_at, imported_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(provider, path) DO UPDATE SET
+                 modified_at = excluded.modified_at,
+                 imported_at = excluded.imported_at",
+            params![
+                provider,
+                path,
+                modified_at.to_rfc3339(),
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Account a stored conversation was pulled into
+    pub fn conversation_account_id(&self, conversation_id: &str) -> Result<Option<String>> {
+        let account_id = self
+            .conn
+            .query_row(
+                "SELECT account_id FROM conversations WHERE id = ?1",
+                params![conversation_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(account_id)
+    }
+
+    /// Conversations, messages and pending attachments stored for an account
+    pub fn account_stats(&self, account_id: &str) -> Result<AccountStats> {
+        let stats = self.conn.query_row(
+            "SELECT
+                 (SELECT COUNT(*) FROM conversations WHERE account_id = ?1),
+                 (SELECT COUNT(*) FROM messages m
+                  JOIN conversations c ON c.id = m.conversation_id
+                  WHERE c.account_id = ?1),
+                 (SELECT COUNT(*) FROM attachments a
+                  JOIN messages m ON m.id = a.message_id
+                  JOIN conversations c ON c.id = m.conversation_id
+                  WHERE c.account_id = ?1 AND a.local_path IS NULL AND a.failed_at IS NULL)",
+            params![account_id],
+            |row| {
+                Ok(AccountStats {
+                    conversations: row.get::<_, i64>(0)? as usize,
+                    messages: row.get::<_, i64>(1)? as usize,
+                    pending_attachments: row.get::<_, i64>(2)? as usize,
+                })
+            },
+        )?;
+        Ok(stats)
+    }
+}
+
+fn sync_state_from_row(row: &Row) -> rusqlite::Result<SyncState> {
+    Ok(SyncState {
+        provider: row.get(0)?,
+        account_id: row.get(1)?,
+        last_pull_at: parse_time(row.get(2)?),
+        last_full_pull_at: parse_time(row.get(3)?),
+        conversations_seen: row.get::<_, i64>(4)? as usize,
+        last_error: row.get(5)?,
+    })
+}
+
+fn parse_time(s: Option<String>) -> Option<DateTime<Utc>> {
+    s.and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, ProviderId};
+
+    #[test]
+    fn test_pull_outcomes_are_recorded() {
+        let store = Store::in_memory().unwrap();
+        assert!(store.sync_state("claude", "user-1").unwrap().is_none());
+
+        store.record_pull("claude", "user-1", true, 12).unwrap();
+        let full = store.sync_state("claude", "user-1").unwrap().unwrap();
+        assert_eq!(full.conversations_seen, 12);
+        assert!(full.last_full_pull_at.is_some());
+        assert_eq!(full.last_pull_at, full.last_full_pull_at);
+
+        store
+            .record_pull_error("claude", "user-1", "Token expired")
+            .unwrap();
+        let failed = store.sync_state("claude", "user-1").unwrap().unwrap();
+        assert_eq!(failed.last_error.as_deref(), Some("Token expired"));
+        assert_eq!(failed.last_pull_at, full.last_pull_at);
+
+        // An incremental pull keeps the last full pull and clears the error
+        store.record_pull("claude", "user-1", false, 14).unwrap();
+        let incremental = store.sync_state("claude", "user-1").unwrap().unwrap();
+        assert_eq!(incremental.conversations_seen, 14);
+        assert_eq!(incremental.last_full_pull_at, full.last_full_pull_at);
+        assert!(incremental.last_error.is_none());
+
+        // Errors before any successful pull leave the times empty
+        store
+            .record_pull_error("chatgpt", "user-2", "Not authenticated")
+            .unwrap();
+        let never = store.sync_state("chatgpt", "user-2").unwrap().unwrap();
+        assert!(never.last_pull_at.is_none());
+        assert_eq!(never.conversations_seen, 0);
+    }
+
+    #[test]
+    fn test_imported_files_remember_their_mtime() {
+        let store = Store::in_memory().unwrap();
+        let path = "/home/dev/.claude/projects/shop/3f2a.jsonl";
+        assert!(store
+            .imported_file_mtime("claude-code", path)
+            .unwrap()
+            .is_none());
+
+        let first = DateTime::parse_from_rfc3339("2025-07-14T09:12:21.648213Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        store
+            .record_imported_file("claude-code", path, first)
+            .unwrap();
+        assert_eq!(
+            store.imported_file_mtime("claude-code", path).unwrap(),
+            Some(first)
+        );
+        assert!(store
+            .imported_file_mtime("aistudio", path)
+            .unwrap()
+            .is_none());
+
+        let appended = first + chrono::Duration::minutes(5);
+        store
+            .record_imported_file("claude-code", path, appended)
+            .unwrap();
+        assert_eq!(
+            store.imported_file_mtime("claude-code", path).unwrap(),
+            Some(appended)
+        );
+    }
+
+    #[test]
+    fn test_sync_cursor_resumes_until_the_listing_finishes() {
+        let store = Store::in_memory().unwrap();
+        assert!(store.sync_cursor("fathom", "user-1").unwrap().is_none());
+
+        store
+            .save_sync_cursor("fathom", "user-1", "page-2")
+            .unwrap();
+        store
+            .save_sync_cursor("fathom", "user-1", "page-3")
+            .unwrap();
+        let interrupted = store.sync_cursor("fathom", "user-1").unwrap().unwrap();
+        assert_eq!(interrupted.cursor.as_deref(), Some("page-3"));
+        assert!(interrupted.last_synced_at.is_none());
+
+        let newest = DateTime::parse_from_rfc3339("2025-01-15T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        store
+            .finish_sync_cursor("fathom", "user-1", Some(newest))
+            .unwrap();
+        let finished = store.sync_cursor("fathom", "user-1").unwrap().unwrap();
+        assert!(finished.cursor.is_none());
+        assert_eq!(finished.last_synced_at, Some(newest));
+
+        // A listing that found nothing keeps the time of the one before
+        store
+            .save_sync_cursor("fathom", "user-1", "page-2")
+            .unwrap();
+        store.finish_sync_cursor("fathom", "user-1", None).unwrap();
+        let empty = store.sync_cursor("fathom", "user-1").unwrap().unwrap();
+        assert!(empty.cursor.is_none());
+        assert_eq!(empty.last_synced_at, Some(newest));
+        assert!(store.sync_cursor("fathom", "user-2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_account_stats() {
+        let store = Store::in_memory().unwrap();
+        for (id, email) in [("user-1", "a@example.com"), ("user-2", "b@example.com")] {
+            store
+                .save_account(&Account {
+                    id: id.to_string(),
+                    provider: ProviderId::claude(),
+                    email: email.to_string(),
+                    name: None,
+                    avatar_url: None,
+                    workspace_id: None,
+                })
+                .unwrap();
+        }
+        for (conv, account) in [
+            ("conv-1", "user-1"),
+            ("conv-2", "user-1"),
+            ("conv-3", "user-2"),
+        ] {
+            store
+                .save_conversation(
+                    account,
+                    &Conversation {
+                        id: conv.to_string(),
+                        provider_id: "claude".to_string(),
+                        title: conv.to_string(),
+                        created_at: Utc::now(),
+                        updated_at: Utc::now(),
+                        model: None,
+                        project_id: None,
+                        project_name: None,
+                        is_archived: false,
+                    },
+                )
+                .unwrap();
+        }
+
+        assert_eq!(
+            store.conversation_account_id("conv-3").unwrap().as_deref(),
+            Some("user-2")
+        );
+        assert!(store.conversation_account_id("conv-4").unwrap().is_none());
+
+        let stats = store.account_stats("user-1").unwrap();
+        assert_eq!(stats.conversations, 2);
+        assert_eq!(stats.messages, 0);
+        assert_eq!(
+            store.account_stats("user-3").unwrap(),
+            AccountStats::default()
+        );
+    }
+}
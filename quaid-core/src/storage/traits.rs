@@ -2,8 +2,9 @@
 //!
 //! These traits define the interface for different storage backends (SQLite, Parquet, etc.)
 
-use crate::providers::{Attachment, Conversation, Message};
+use crate::providers::{Attachment, Conversation, Message, Role};
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::path::Path;
 
 /// Result type for storage operations
@@ -36,13 +37,18 @@ pub trait MessageStorage: Send + Sync {
 /// Trait for full-text search
 pub trait SearchStorage: Send + Sync {
     /// Search messages by text query
-    fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>>;
+    fn search(&self, query: &str, limit: usize, filters: &SearchFilters) -> Result<Vec<SearchHit>>;
 }
 
 /// Trait for semantic/vector search
 pub trait SemanticSearchStorage: Send + Sync {
     /// Search by embedding similarity
-    fn search_semantic(&self, embedding: &[f32], limit: usize) -> Result<Vec<SemanticSearchResult>>;
+    fn search_semantic(
+        &self,
+        embedding: &[f32],
+        limit: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<SemanticSearchResult>>;
 
     /// Hybrid search combining FTS and vector similarity
     fn search_hybrid(
@@ -50,6 +56,7 @@ pub trait SemanticSearchStorage: Send + Sync {
         query: &str,
         embedding: &[f32],
         limit: usize,
+        filters: &SearchFilters,
     ) -> Result<Vec<SemanticSearchResult>>;
 }
 
@@ -65,11 +72,103 @@ pub trait AttachmentStorage: Send + Sync {
     fn get_pending_attachments(&self) -> Result<Vec<Attachment>>;
 }
 
+/// Restrictions applied inside search queries, before the result limit
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    /// Only match conversations from this provider
+    pub provider: Option<String>,
+    /// Only match conversations from this account (parquet files carry no
+    /// account column, so DuckDB queries ignore this)
+    pub account_id: Option<String>,
+    /// Only match conversations created at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only match conversations created at or before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Only match messages with one of these roles (empty matches all roles)
+    pub roles: Vec<Role>,
+    /// Leave out archived conversations
+    pub exclude_archived: bool,
+    /// Also match text extracted from attachments (SQLite full-text search only)
+    pub include_attachments: bool,
+}
+
 /// Full-text search result
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub conversation_id: String,
+    /// Role of the matching message
+    pub role: Role,
+    pub snippet: String,
+}
+
+/// A matching message, with the details of its conversation
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub conversation_id: String,
+    pub title: String,
+    pub provider_id: String,
+    pub updated_at: DateTime<Utc>,
+    /// Matched text; FTS highlights terms with `<mark>` tags
     pub snippet: String,
+    /// Backend relevance: FTS5 rank (lower is better) or semantic similarity
+    pub rank: f64,
+    /// Filename of the attachment the match is in, for attachment hits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<String>,
+}
+
+/// Search hits grouped under their conversation
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationHits {
+    pub conversation_id: String,
+    pub title: String,
+    pub provider_id: String,
+    pub updated_at: DateTime<Utc>,
+    /// Rank of the best hit in this conversation
+    pub rank: f64,
+    /// Up to `per_conversation` snippets, best first
+    pub snippets: Vec<String>,
+    /// Number of hits in this conversation, including dropped snippets
+    pub total_hits: usize,
+}
+
+impl ConversationHits {
+    /// Group best-first `hits` by conversation.
+    ///
+    /// Conversations keep the order of their best hit, and each keeps at most
+    /// `per_conversation` snippets.
+    pub fn group(hits: Vec<SearchHit>, per_conversation: usize) -> Vec<ConversationHits> {
+        let mut groups: Vec<ConversationHits> = Vec::new();
+
+        for hit in hits {
+            match groups
+                .iter_mut()
+                .find(|g| g.conversation_id == hit.conversation_id)
+            {
+                Some(group) => {
+                    group.total_hits += 1;
+                    if group.snippets.len() < per_conversation {
+                        group.snippets.push(hit.snippet);
+                    }
+                }
+                None => groups.push(ConversationHits {
+                    conversation_id: hit.conversation_id,
+                    title: hit.title,
+                    provider_id: hit.provider_id,
+                    updated_at: hit.updated_at,
+                    rank: hit.rank,
+                    snippets: if per_conversation > 0 {
+                        vec![hit.snippet]
+                    } else {
+                        vec![]
+                    },
+                    total_hits: 1,
+                }),
+            }
+        }
+
+        groups
+    }
 }
 
 /// Semantic search result with similarity score
@@ -81,20 +180,54 @@ pub struct SemanticSearchResult {
     pub score: f32,
 }
 
+/// ZSTD level conversation files are written at unless configured
+pub const DEFAULT_ZSTD_LEVEL: i32 = 1;
+
+/// Highest ZSTD level; higher levels trade write time for smaller files
+pub const MAX_ZSTD_LEVEL: i32 = 22;
+
+/// Codec conversation files are compressed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    /// ZSTD at a level from 1 to [`MAX_ZSTD_LEVEL`]
+    Zstd {
+        level: i32,
+    },
+}
+
+impl Default for ParquetCompression {
+    fn default() -> Self {
+        Self::Zstd {
+            level: DEFAULT_ZSTD_LEVEL,
+        }
+    }
+}
+
 /// Configuration for Parquet storage
 #[derive(Debug, Clone)]
 pub struct ParquetStorageConfig {
     /// Base directory for parquet files
     pub base_dir: std::path::PathBuf,
+    /// Codec for conversation files; reading handles any codec
+    pub compression: ParquetCompression,
 }
 
 impl ParquetStorageConfig {
     pub fn new(base_dir: impl AsRef<Path>) -> Self {
         Self {
             base_dir: base_dir.as_ref().to_path_buf(),
+            compression: ParquetCompression::default(),
         }
     }
 
+    /// Write conversation files with `compression`
+    pub fn with_compression(mut self, compression: ParquetCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Path for a conversation's parquet file
     pub fn conversation_path(&self, provider: &str, conversation_id: &str) -> std::path::PathBuf {
         self.base_dir
@@ -118,6 +251,11 @@ impl ParquetStorageConfig {
             .join(format!("{}.parquet", provider))
     }
 
+    /// Path for the metadata describing which embedder built the index
+    pub fn embeddings_metadata_path(&self) -> std::path::PathBuf {
+        self.base_dir.join("embeddings").join("metadata.json")
+    }
+
     /// Directory containing per-conversation embeddings for a provider
     pub fn embeddings_dir(&self, provider: &str) -> std::path::PathBuf {
         self.base_dir.join("embeddings").join(provider)
@@ -190,6 +328,7 @@ mod tests {
     fn test_search_result_debug() {
         let result = SearchResult {
             conversation_id: "conv-123".to_string(),
+            role: Role::Assistant,
             snippet: "Hello world".to_string(),
         };
         // Ensure Debug is implemented
@@ -207,4 +346,48 @@ mod tests {
         let cloned = result.clone();
         assert_eq!(cloned.score, 0.95);
     }
+
+    fn hit(conversation_id: &str, snippet: &str, rank: f64) -> SearchHit {
+        SearchHit {
+            conversation_id: conversation_id.to_string(),
+            title: format!("Title {}", conversation_id),
+            provider_id: "claude".to_string(),
+            updated_at: Utc::now(),
+            snippet: snippet.to_string(),
+            rank,
+            attachment: None,
+        }
+    }
+
+    #[test]
+    fn test_group_hits_per_conversation() {
+        let hits = vec![
+            hit("conv-a", "a1", -9.0),
+            hit("conv-b", "b1", -8.0),
+            hit("conv-a", "a2", -7.0),
+            hit("conv-a", "a3", -6.0),
+            hit("conv-a", "a4", -5.0),
+            hit("conv-c", "c1", -4.0),
+            hit("conv-b", "b2", -3.0),
+        ];
+
+        let groups = ConversationHits::group(hits, 2);
+
+        let ids: Vec<_> = groups.iter().map(|g| g.conversation_id.as_str()).collect();
+        assert_eq!(ids, ["conv-a", "conv-b", "conv-c"]);
+
+        assert_eq!(groups[0].snippets, ["a1", "a2"]);
+        assert_eq!(groups[0].total_hits, 4);
+        assert_eq!(groups[0].rank, -9.0);
+        assert_eq!(groups[0].title, "Title conv-a");
+
+        assert_eq!(groups[1].snippets, ["b1", "b2"]);
+        assert_eq!(groups[1].total_hits, 2);
+        assert_eq!(groups[2].snippets, ["c1"]);
+    }
+
+    #[test]
+    fn test_group_hits_empty() {
+        assert!(ConversationHits::group(vec![], 3).is_empty());
+    }
 }
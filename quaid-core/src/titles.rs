@@ -0,0 +1,434 @@
+//! Offline conversation titles
+//!
+//! [`heuristic_title`] takes the first words of the first user message.
+//! [`smart_title`] picks the most salient phrases of the first user/assistant
+//! exchange, scored by TF-IDF against the whole archive ([`CorpusStats`]).
+//! Phrases are capitalized word runs ("Rust Analyzer"), bigrams repeated in
+//! the exchange, and single terms; no part-of-speech tagging is involved, and
+//! ties are broken alphabetically so titles are deterministic.
+
+use crate::providers::{Message, Role};
+use crate::storage::{extract_text_content, Store};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Longest title produced, in characters
+pub const MAX_TITLE_CHARS: usize = 60;
+
+/// Words taken by [`heuristic_title`]
+const HEURISTIC_WORDS: usize = 8;
+
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be",
+    "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does",
+    "doing", "for", "from", "get", "had", "has", "have", "hello", "help", "here", "hi", "how", "i",
+    "if", "in", "into", "is", "it", "its", "just", "let", "like", "me", "more", "most", "my",
+    "need", "no", "not", "of", "on", "one", "or", "other", "our", "out", "please", "so", "some",
+    "sure", "than", "thanks", "that", "the", "their", "them", "then", "there", "these", "they",
+    "this", "those", "to", "up", "use", "using", "very", "want", "was", "we", "well", "were",
+    "what", "when", "where", "which", "while", "who", "why", "will", "with", "would", "you",
+    "your",
+];
+
+fn is_stopword(word: &str) -> bool {
+    STOPWORDS.contains(&word.to_lowercase().as_str())
+}
+
+/// Words worth putting in a title
+fn is_content(word: &str) -> bool {
+    word.chars().count() > 1 && !is_stopword(word)
+}
+
+/// Sentences of `text`; a `.` only ends one when followed by a space
+fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    text.split(['!', '?', '\n']).flat_map(|s| s.split(". "))
+}
+
+/// Words of `text`, keeping letters, digits and inner `'`, `-`, `.` (e.g. "Node.js")
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !(c.is_alphanumeric() || matches!(c, '\'' | '-' | '.')))
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Lowercased content terms, used for document frequencies
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    words(text)
+        .into_iter()
+        .filter(|w| is_content(w))
+        .map(str::to_lowercase)
+}
+
+/// Document frequencies of terms across the archive
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorpusStats {
+    /// Number of conversations the stats were built from
+    pub conversations: usize,
+    /// Conversations each term appears in
+    pub doc_freq: BTreeMap<String, usize>,
+}
+
+impl CorpusStats {
+    /// Count term document frequencies over conversation texts
+    pub fn from_documents<'a>(documents: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut stats = Self::default();
+        for doc in documents {
+            stats.conversations += 1;
+            let unique: HashSet<String> = terms(doc).collect();
+            for term in unique {
+                *stats.doc_freq.entry(term).or_default() += 1;
+            }
+        }
+        stats
+    }
+
+    /// Build stats from every conversation in the store
+    pub fn build(store: &Store) -> crate::storage::Result<Self> {
+        let mut documents = Vec::new();
+        for account in store.list_accounts()? {
+            for conv in store.list_conversations(&account.id)? {
+                let text = store
+                    .get_messages(&conv.id)?
+                    .iter()
+                    .map(|m| extract_text_content(&m.content))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                documents.push(text);
+            }
+        }
+        Ok(Self::from_documents(documents.iter().map(String::as_str)))
+    }
+
+    /// Smoothed inverse document frequency
+    pub fn idf(&self, term: &str) -> f64 {
+        let df = self.doc_freq.get(term).copied().unwrap_or(0);
+        ((self.conversations as f64 + 1.0) / (df as f64 + 1.0)).ln() + 1.0
+    }
+
+    /// Where the cached stats live inside the data directory
+    pub fn cache_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("cache").join("title_corpus.json")
+    }
+
+    /// Read cached stats, if present and readable
+    pub fn load(path: &Path) -> Option<Self> {
+        let json = std::fs::read(path).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(self)?)
+    }
+
+    /// Rebuild the cached stats from the store (the maintenance task)
+    pub fn refresh(store: &Store, data_dir: &Path) -> crate::storage::Result<Self> {
+        let stats = Self::build(store)?;
+        stats.save(&Self::cache_path(data_dir))?;
+        Ok(stats)
+    }
+
+    /// Cached stats, rebuilt when missing or when the archive has changed size
+    pub fn load_or_refresh(store: &Store, data_dir: &Path) -> crate::storage::Result<Self> {
+        let current = store.stats()?.conversations;
+        match Self::load(&Self::cache_path(data_dir)) {
+            Some(stats) if stats.conversations == current => Ok(stats),
+            _ => Self::refresh(store, data_dir),
+        }
+    }
+}
+
+/// Title from the first words of the first user message
+pub fn heuristic_title(messages: &[Message]) -> Option<String> {
+    let first = messages.iter().find(|m| m.role == Role::User)?;
+    let text = extract_text_content(&first.content);
+    let title = text
+        .split_whitespace()
+        .take(HEURISTIC_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then(|| cap_length(&title))
+}
+
+/// Title from the most salient phrases of the first user/assistant exchange.
+///
+/// Falls back to [`heuristic_title`] when the exchange has no content words.
+pub fn smart_title(messages: &[Message], corpus: &CorpusStats) -> Option<String> {
+    let user = messages.iter().position(|m| m.role == Role::User)?;
+    let mut exchange = extract_text_content(&messages[user].content);
+    if let Some(reply) = messages[user..].iter().find(|m| m.role == Role::Assistant) {
+        exchange.push('\n');
+        exchange.push_str(&extract_text_content(&reply.content));
+    }
+
+    let mut tf: HashMap<String, usize> = HashMap::new();
+    for term in terms(&exchange) {
+        *tf.entry(term).or_default() += 1;
+    }
+    let tfidf = |term: &str| tf.get(term).copied().unwrap_or(0) as f64 * corpus.idf(term);
+
+    let mut candidates: HashMap<Vec<String>, Vec<&str>> = HashMap::new();
+    for sentence in sentences(&exchange) {
+        let words: Vec<&str> = words(sentence);
+
+        // Runs of capitalized words; the first word of a sentence is capitalized
+        // anyway, so it never starts a run
+        let mut run: Vec<&str> = Vec::new();
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 && word.chars().next().is_some_and(char::is_uppercase) && is_content(word) {
+                run.push(word);
+            } else if !run.is_empty() {
+                add_candidate(&mut candidates, &run);
+                run.clear();
+            }
+        }
+        if !run.is_empty() {
+            add_candidate(&mut candidates, &run);
+        }
+
+        for pair in words.windows(2) {
+            if pair.iter().all(|w| is_content(w)) {
+                add_candidate(&mut candidates, pair);
+            }
+        }
+        for word in words.iter().filter(|w| is_content(w)) {
+            add_candidate(&mut candidates, &[word]);
+        }
+    }
+
+    // Bigrams only count when repeated; other phrases score the sum of their terms
+    let bigram_counts = count_bigrams(&exchange);
+    let mut scored: Vec<(f64, Vec<String>, Vec<&str>)> = candidates
+        .into_iter()
+        .filter(|(key, surface)| {
+            key.len() != 2
+                || surface
+                    .iter()
+                    .all(|w| w.chars().next().is_some_and(char::is_uppercase))
+                || bigram_counts.get(key).copied().unwrap_or(0) >= 2
+        })
+        .map(|(key, surface)| {
+            let score = key.iter().map(|t| tfidf(t)).sum::<f64>();
+            (score, key, surface)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut parts: Vec<String> = Vec::new();
+    for (_, key, surface) in scored {
+        if key.iter().any(|t| used.contains(t)) {
+            continue;
+        }
+        let phrase = capitalize(&surface.join(" "));
+        let joined_len =
+            parts.iter().map(|p| p.chars().count() + 2).sum::<usize>() + phrase.chars().count();
+        if !parts.is_empty() && joined_len > MAX_TITLE_CHARS {
+            break;
+        }
+        used.extend(key);
+        parts.push(phrase);
+        if parts.len() == 3 {
+            break;
+        }
+    }
+
+    if parts.is_empty() {
+        return heuristic_title(messages);
+    }
+    Some(cap_length(&parts.join(", ")))
+}
+
+fn add_candidate<'a>(candidates: &mut HashMap<Vec<String>, Vec<&'a str>>, words: &[&'a str]) {
+    let key: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    candidates.entry(key).or_insert_with(|| words.to_vec());
+}
+
+fn count_bigrams(text: &str) -> HashMap<Vec<String>, usize> {
+    let mut counts = HashMap::new();
+    for sentence in sentences(text) {
+        let words: Vec<String> = words(sentence).iter().map(|w| w.to_lowercase()).collect();
+        for pair in words.windows(2) {
+            *counts.entry(pair.to_vec()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+fn capitalize(phrase: &str) -> String {
+    let mut chars = phrase.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Cut to [`MAX_TITLE_CHARS`] at a word boundary
+fn cap_length(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return title.to_string();
+    }
+    let mut out = String::new();
+    for word in title.split_whitespace() {
+        let extra = if out.is_empty() { 0 } else { 1 };
+        if out.chars().count() + extra + word.chars().count() > MAX_TITLE_CHARS {
+            break;
+        }
+        if extra == 1 {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    if out.is_empty() {
+        out = title.chars().take(MAX_TITLE_CHARS).collect();
+    }
+    out.trim_end_matches([',', ' ']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, Conversation, MessageContent, ProviderId};
+    use tempfile::TempDir;
+
+    fn message(role: Role, text: &str) -> Message {
+        Message {
+            id: format!("{:?}-{}", role, text.len()),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        }
+    }
+
+    fn background() -> CorpusStats {
+        CorpusStats::from_documents([
+            "How do I write a function in Python? A function is defined with def.",
+            "Can you help me plan a trip to Lisbon? Lisbon is great in spring.",
+            "What is a good way to learn a new language? Practice every day.",
+            "Explain how a function call works. The stack keeps each call frame.",
+        ])
+    }
+
+    fn exchange() -> Vec<Message> {
+        vec![
+            message(
+                Role::User,
+                "How do I configure Rust Analyzer in Neovim? The borrow checker errors \
+                 don't show up and the borrow checker is the main thing I need.",
+            ),
+            message(
+                Role::Assistant,
+                "Install Rust Analyzer, then enable it in your Neovim LSP config. Borrow \
+                 checker diagnostics are reported by the language server.",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_heuristic_title() {
+        let messages = exchange();
+        assert_eq!(
+            heuristic_title(&messages).unwrap(),
+            "How do I configure Rust Analyzer in Neovim?"
+        );
+        assert_eq!(heuristic_title(&[]), None);
+    }
+
+    #[test]
+    fn test_smart_title_picks_salient_phrases() {
+        let title = smart_title(&exchange(), &background()).unwrap();
+        assert_eq!(title, "Borrow checker, Rust Analyzer, Neovim LSP");
+        assert!(title.chars().count() <= MAX_TITLE_CHARS);
+
+        // Deterministic across runs
+        assert_eq!(smart_title(&exchange(), &background()).unwrap(), title);
+    }
+
+    #[test]
+    fn test_smart_title_falls_back_to_heuristic() {
+        let messages = vec![message(Role::User, "hi, can you help me?")];
+        assert_eq!(
+            smart_title(&messages, &background()).unwrap(),
+            "hi, can you help me?"
+        );
+    }
+
+    #[test]
+    fn test_cap_length_breaks_on_words() {
+        let long = "word ".repeat(30);
+        let capped = cap_length(&long);
+        assert!(capped.chars().count() <= MAX_TITLE_CHARS);
+        assert!(capped.ends_with("word"));
+    }
+
+    #[test]
+    fn test_corpus_cache_refresh() {
+        let dir = TempDir::new().unwrap();
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+
+        let add = |id: &str, text: &str| {
+            store
+                .save_conversation(
+                    "user-1",
+                    &Conversation {
+                        id: id.to_string(),
+                        provider_id: "claude".to_string(),
+                        title: String::new(),
+                        created_at: chrono::Utc::now(),
+                        updated_at: chrono::Utc::now(),
+                        model: None,
+                        project_id: None,
+                        project_name: None,
+                        is_archived: false,
+                    },
+                )
+                .unwrap();
+            let mut msg = message(Role::User, text);
+            msg.id = format!("{}-msg", id);
+            msg.conversation_id = id.to_string();
+            store.save_message(&msg).unwrap();
+        };
+
+        add("conv-1", "Kubernetes deployment questions");
+        let stats = CorpusStats::load_or_refresh(&store, dir.path()).unwrap();
+        assert_eq!(stats.conversations, 1);
+        assert_eq!(stats.doc_freq.get("kubernetes"), Some(&1));
+        assert!(CorpusStats::cache_path(dir.path()).exists());
+
+        // Unchanged archive: served from the cache
+        assert_eq!(
+            CorpusStats::load_or_refresh(&store, dir.path()).unwrap(),
+            stats
+        );
+
+        // A new conversation makes the cache stale
+        add("conv-2", "More Kubernetes questions");
+        let stats = CorpusStats::load_or_refresh(&store, dir.path()).unwrap();
+        assert_eq!(stats.conversations, 2);
+        assert_eq!(stats.doc_freq.get("kubernetes"), Some(&2));
+        assert_eq!(
+            CorpusStats::load(&CorpusStats::cache_path(dir.path())).unwrap(),
+            stats
+        );
+    }
+}
@@ -0,0 +1,128 @@
+// quaid web viewer: conversation list with infinite scroll, search, and
+// code highlighting on server-rendered conversation pages.
+(function () {
+  "use strict";
+
+  var PAGE_SIZE = 50;
+
+  function el(tag, className, text) {
+    var node = document.createElement(tag);
+    if (className) node.className = className;
+    if (text !== undefined) node.textContent = text;
+    return node;
+  }
+
+  function day(iso) {
+    return iso ? iso.slice(0, 10) : "";
+  }
+
+  function item(conv, snippet) {
+    var li = el("li");
+    var link = el("a", null, conv.title || "(untitled)");
+    link.href = "/c/" + encodeURIComponent(conv.id);
+    li.appendChild(link);
+    li.appendChild(el("span", "meta", conv.provider_id + " · " + day(conv.updated_at || conv.created_at)));
+    if (snippet) li.appendChild(el("span", "snippet", snippet));
+    return li;
+  }
+
+  function getJson(url) {
+    return fetch(url, { headers: { Accept: "application/json" } }).then(function (res) {
+      if (!res.ok) throw new Error(res.status + " " + res.statusText);
+      return res.json();
+    });
+  }
+
+  // Conversation list, paged by keyset cursor as the user scrolls
+  function listConversations(list, status) {
+    var cursor = null;
+    var loading = false;
+    var done = false;
+
+    function loadMore() {
+      if (loading || done) return;
+      loading = true;
+      status.textContent = "Loading…";
+      var url = "/conversations?limit=" + PAGE_SIZE + (cursor ? "&cursor=" + encodeURIComponent(cursor) : "");
+      getJson(url).then(function (page) {
+        page.conversations.forEach(function (conv) { list.appendChild(item(conv)); });
+        cursor = page.next_cursor;
+        done = !cursor;
+        status.textContent = done && !list.children.length ? "No conversations yet. Run `quaid pull`." : "";
+      }).catch(function (err) {
+        status.textContent = "Could not load conversations: " + err.message;
+      }).then(function () {
+        loading = false;
+      });
+    }
+
+    var sentinel = el("div");
+    list.after(sentinel);
+    new IntersectionObserver(function (entries) {
+      if (entries[0].isIntersecting) loadMore();
+    }).observe(sentinel);
+    loadMore();
+  }
+
+  // Hybrid search once `quaid index embed` has built an index, keywords before
+  function search(query, list, status) {
+    status.textContent = "Searching…";
+    getJson("/stats").then(function (stats) {
+      var url = "/search?q=" + encodeURIComponent(query) + "&limit=" + PAGE_SIZE;
+      return getJson(stats.embeddings ? url + "&hybrid=true" : url);
+    }).then(function (res) {
+      res.results.forEach(function (hit) {
+        list.appendChild(item({
+          id: hit.conversation_id,
+          title: hit.title,
+          provider_id: hit.provider_id,
+          updated_at: hit.updated_at
+        }, hit.snippets[0]));
+      });
+      status.textContent = res.results.length ? "" : "No matches.";
+    }).catch(function (err) {
+      status.textContent = "Search failed: " + err.message;
+    });
+  }
+
+  var KEYWORDS = /\b(fn|let|mut|pub|impl|struct|enum|match|if|else|for|while|loop|return|use|mod|async|await|def|class|import|from|function|const|var|new|try|catch|true|false|null|None|self|Self)\b/;
+  var TOKENS = /(\/\/[^\n]*|#[^\n]*|"(?:[^"\\\n]|\\.)*"|'(?:[^'\\\n]|\\.)*'|\b\d+(?:\.\d+)?\b|\b[A-Za-z_]\w*\b)/g;
+
+  // Minimal highlighter: comments, strings, numbers and common keywords
+  function highlight(code) {
+    var source = code.textContent;
+    var out = document.createDocumentFragment();
+    var last = 0;
+    source.replace(TOKENS, function (token, _group, offset) {
+      out.appendChild(document.createTextNode(source.slice(last, offset)));
+      var kind = null;
+      if (token[0] === "/" || token[0] === "#") kind = "tok-com";
+      else if (token[0] === "\"" || token[0] === "'") kind = "tok-str";
+      else if (/^\d/.test(token)) kind = "tok-num";
+      else if (KEYWORDS.test(token)) kind = "tok-kw";
+      out.appendChild(kind ? el("span", kind, token) : document.createTextNode(token));
+      last = offset + token.length;
+      return token;
+    });
+    out.appendChild(document.createTextNode(source.slice(last)));
+    code.textContent = "";
+    code.appendChild(out);
+  }
+
+  document.addEventListener("DOMContentLoaded", function () {
+    document.querySelectorAll("pre code").forEach(highlight);
+
+    var list = document.getElementById("conversations");
+    var status = document.getElementById("status");
+    if (!list) return;
+
+    var query = new URLSearchParams(location.search).get("q");
+    var input = document.querySelector("#search input");
+    if (query) {
+      input.value = query;
+      search(query, list, status);
+    } else {
+      listConversations(list, status);
+    }
+  });
+})();
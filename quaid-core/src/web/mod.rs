@@ -0,0 +1,319 @@
+//! Built-in read-only web viewer
+//!
+//! Static assets are compiled into the binary with `include_str!`, so there is
+//! no build step and nothing to install. Conversation pages are rendered on the
+//! server and work without JavaScript; the script only adds the infinite-scroll
+//! list, search, and code highlighting. This module produces responses; the
+//! HTTP server that mounts them is `quaid serve --ui`.
+
+use crate::providers::{document_language, Conversation, Message, MessageContent};
+use crate::storage::{Result, Store};
+
+const INDEX_HTML: &str = include_str!("assets/index.html");
+const APP_JS: &str = include_str!("assets/app.js");
+const STYLE_CSS: &str = include_str!("assets/style.css");
+
+/// Sent with every viewer response: only same-origin scripts, styles and API
+/// calls, and no inline script, so rendered message content can't run code
+pub const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; script-src 'self'; \
+    style-src 'self'; img-src 'self' data:; connect-src 'self'; base-uri 'none'; \
+    form-action 'self'; frame-ancestors 'none'";
+
+/// A response from the viewer, independent of the HTTP server serving it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Page {
+    fn ok(content_type: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    fn not_found(what: &str) -> Self {
+        Self {
+            status: 404,
+            content_type: "text/html; charset=utf-8",
+            body: layout("Not found", &format!("<p>{} not found.</p>", escape(what))),
+        }
+    }
+
+    /// Headers to send along with the body
+    pub fn headers(&self) -> [(&'static str, &'static str); 4] {
+        [
+            ("Content-Type", self.content_type),
+            ("Content-Security-Policy", CONTENT_SECURITY_POLICY),
+            ("X-Content-Type-Options", "nosniff"),
+            ("Referrer-Policy", "no-referrer"),
+        ]
+    }
+}
+
+/// Respond to a GET for `path` (without the query string)
+pub fn route(store: &Store, path: &str) -> Result<Page> {
+    Ok(match path {
+        "/" | "/index.html" => Page::ok("text/html; charset=utf-8", INDEX_HTML),
+        "/assets/app.js" => Page::ok("text/javascript; charset=utf-8", APP_JS),
+        "/assets/style.css" => Page::ok("text/css; charset=utf-8", STYLE_CSS),
+        _ => match path.strip_prefix("/c/") {
+            Some(id) if !id.is_empty() && !id.contains('/') => conversation_page(store, id)?,
+            _ => Page::not_found("Page"),
+        },
+    })
+}
+
+/// A conversation rendered as a full HTML page
+pub fn conversation_page(store: &Store, id: &str) -> Result<Page> {
+    let Some(conversation) = store.get_conversation(id)? else {
+        return Ok(Page::not_found("Conversation"));
+    };
+    let messages = store.get_messages(id)?;
+    let body = render_conversation(&conversation, &messages);
+    Ok(Page::ok(
+        "text/html; charset=utf-8",
+        layout(&conversation.title, &body),
+    ))
+}
+
+fn layout(title: &str, body: &str) -> String {
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1">
+  <title>{title} · quaid</title>
+  <link rel="stylesheet" href="/assets/style.css">
+  <script src="/assets/app.js" defer></script>
+</head>
+<body>
+  <header>
+    <a class="brand" href="/">quaid</a>
+    <form id="search" action="/" method="get">
+      <input type="search" name="q" placeholder="Search your chats" autocomplete="off">
+    </form>
+  </header>
+  <main>
+{body}
+  </main>
+</body>
+</html>
+"#,
+        title = escape(title),
+        body = body
+    )
+}
+
+fn render_conversation(conversation: &Conversation, messages: &[Message]) -> String {
+    let mut html = format!(
+        "<h1>{}</h1>\n<p class=\"muted\">{} · {}</p>\n",
+        escape(&conversation.title),
+        escape(&conversation.provider_id),
+        conversation.created_at.format("%Y-%m-%d %H:%M"),
+    );
+    for message in messages {
+        let role = message.role.as_str();
+        let label = message
+            .speaker
+            .as_deref()
+            .map_or_else(|| role.to_string(), escape);
+        html.push_str(&format!(
+            "<article class=\"message {role}\">\n<div class=\"role\">{label}</div>\n"
+        ));
+        render_content(&message.content, &mut html);
+        html.push_str("</article>\n");
+    }
+    html
+}
+
+fn render_content(content: &MessageContent, html: &mut String) {
+    match content {
+        MessageContent::Text { text } => render_text(text, html),
+        MessageContent::Code { language, code } => render_code(language, code, html),
+        MessageContent::Image { alt, .. } => html.push_str(&format!(
+            "<p class=\"muted\">[image{}]</p>\n",
+            alt.as_deref()
+                .map(|alt| format!(": {}", escape(alt)))
+                .unwrap_or_default()
+        )),
+        MessageContent::Audio { transcript, .. } => match transcript {
+            Some(transcript) => render_text(transcript, html),
+            None => html.push_str("<p class=\"muted\">[audio]</p>\n"),
+        },
+        MessageContent::Document { title, mime, text } => {
+            html.push_str(&format!("<h3>{}</h3>\n", escape(title)));
+            render_code(document_language(mime), text, html);
+        }
+        MessageContent::Thinking { text } => {
+            html.push_str("<details class=\"thinking\">\n<summary>thinking</summary>\n");
+            render_text(text, html);
+            html.push_str("</details>\n");
+        }
+        MessageContent::Mixed { parts } => {
+            for part in parts {
+                render_content(part, html);
+            }
+        }
+    }
+}
+
+/// Plain text, with Markdown code fences turned into code blocks
+fn render_text(text: &str, html: &mut String) {
+    for (i, section) in text.split("```").enumerate() {
+        if i % 2 == 1 {
+            let (language, code) = section.split_once('\n').unwrap_or(("", section));
+            render_code(language.trim(), code, html);
+        } else if !section.trim().is_empty() {
+            html.push_str(&format!(
+                "<div class=\"text\">{}</div>\n",
+                escape(section.trim())
+            ));
+        }
+    }
+}
+
+fn render_code(language: &str, code: &str, html: &mut String) {
+    let class: String = language
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '+')
+        .collect();
+    html.push_str(&format!(
+        "<pre><code class=\"language-{}\">{}</code></pre>\n",
+        class,
+        escape(code.trim_end())
+    ));
+}
+
+/// Escape text for use in HTML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{Account, ProviderId, Role};
+
+    fn fixture_store() -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        store
+            .save_conversation(
+                "user-1",
+                &Conversation {
+                    id: "conv-1".to_string(),
+                    provider_id: "claude".to_string(),
+                    title: "Tags & <b>bold</b> titles".to_string(),
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                    model: None,
+                    project_id: None,
+                    project_name: None,
+                    is_archived: false,
+                },
+            )
+            .unwrap();
+        let messages = [
+            (
+                Role::User,
+                "Why does <script>alert(1)</script> run?".to_string(),
+            ),
+            (
+                Role::Assistant,
+                "Escape it:\n```rust\nlet s = \"<b>\";\n```\nDone.".to_string(),
+            ),
+        ];
+        for (i, (role, text)) in messages.into_iter().enumerate() {
+            store
+                .save_message(&Message {
+                    id: format!("msg-{}", i),
+                    conversation_id: "conv-1".to_string(),
+                    parent_id: None,
+                    role,
+                    content: MessageContent::Text { text },
+                    created_at: None,
+                    model: None,
+                    speaker: None,
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_routes_serve_embedded_assets() {
+        let store = Store::in_memory().unwrap();
+
+        let index = route(&store, "/").unwrap();
+        assert_eq!(index.status, 200);
+        assert!(index.content_type.starts_with("text/html"));
+        assert!(index.body.contains("/assets/app.js"));
+
+        let script = route(&store, "/assets/app.js").unwrap();
+        assert!(script.content_type.starts_with("text/javascript"));
+        assert_eq!(script.body, APP_JS);
+
+        let style = route(&store, "/assets/style.css").unwrap();
+        assert!(style.content_type.starts_with("text/css"));
+
+        assert_eq!(route(&store, "/assets/missing.js").unwrap().status, 404);
+        assert_eq!(route(&store, "/c/").unwrap().status, 404);
+    }
+
+    #[test]
+    fn test_every_response_carries_csp() {
+        let store = Store::in_memory().unwrap();
+        let page = route(&store, "/nope").unwrap();
+        assert!(page
+            .headers()
+            .contains(&("Content-Security-Policy", CONTENT_SECURITY_POLICY)));
+        assert!(!CONTENT_SECURITY_POLICY.contains("unsafe-inline"));
+    }
+
+    #[test]
+    fn test_conversation_page_escapes_message_content() {
+        let store = fixture_store();
+        let page = route(&store, "/c/conv-1").unwrap();
+        assert_eq!(page.status, 200);
+
+        assert!(page
+            .body
+            .contains("Why does &lt;script&gt;alert(1)&lt;/script&gt; run?"));
+        assert!(!page.body.contains("<script>alert(1)"));
+        assert!(page
+            .body
+            .contains("<title>Tags &amp; &lt;b&gt;bold&lt;/b&gt; titles · quaid</title>"));
+        assert!(page.body.contains("<article class=\"message user\">"));
+        assert!(page.body.contains(
+            "<pre><code class=\"language-rust\">let s = &quot;&lt;b&gt;&quot;;</code></pre>"
+        ));
+
+        assert_eq!(route(&store, "/c/missing").unwrap().status, 404);
+    }
+}
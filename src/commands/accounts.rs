@@ -0,0 +1,130 @@
+use super::pull::DataDirLock;
+use super::status::provider_for;
+use quaid_core::credentials::{keys, CredentialError, CredentialStore, SERVICE};
+use quaid_core::storage::EmbeddingsStore;
+use quaid_core::{ParquetStorageConfig, Store};
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+/// List every account with its stored conversations and whether its
+/// credentials are saved
+pub async fn list(store: &Store, credentials: &Arc<dyn CredentialStore>) -> anyhow::Result<()> {
+    let accounts = store.list_accounts()?;
+    if accounts.is_empty() {
+        println!("No accounts configured. Use `quaid <provider> auth` first.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<9} {:<40} {:>13}  CREDENTIALS",
+        "PROVIDER", "ACCOUNT", "CONVERSATIONS"
+    );
+    for account in &accounts {
+        let saved = if account.is_local() {
+            "-"
+        } else {
+            match provider_for(account, credentials) {
+                Some(client) if client.is_authenticated().await => "present",
+                _ => "missing",
+            }
+        };
+        println!(
+            "{:<9} {:<40} {:>13}  {}",
+            account.provider,
+            account.label(),
+            store.account_stats(&account.id)?.conversations,
+            saved
+        );
+    }
+    Ok(())
+}
+
+/// Delete the credentials `provider` saved for its accounts, or only the one
+/// signed in as `email`
+///
+/// With `purge`, the accounts are deleted too, with their conversations,
+/// Parquet files and embeddings.
+pub fn logout(
+    provider: &str,
+    email: Option<&str>,
+    purge: bool,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let users = keys::for_provider(provider);
+    if users.is_empty() && !purge {
+        anyhow::bail!(
+            "quaid saves no {} credentials; sign out of the {} app instead, or add --purge to delete its accounts",
+            provider,
+            provider
+        );
+    }
+
+    let accounts = super::select_accounts(store, Some(provider), email)?;
+    // Workspace accounts share their sign-in's credentials
+    let emails: BTreeSet<&str> = accounts
+        .iter()
+        .filter(|account| !account.is_local())
+        .map(|account| account.email.as_str())
+        .collect();
+    for email in &emails {
+        let mut removed = false;
+        for user in users {
+            removed |= credentials.delete_for_account(SERVICE, user, email)?;
+        }
+        if removed {
+            println!("Removed {} credentials of {}", provider, email);
+        } else if !users.is_empty() {
+            println!("No {} credentials saved for {}", provider, email);
+        }
+    }
+    if email.is_none() && emails.is_empty() && !users.is_empty() {
+        // Signed in before accounts were kept apart, or never saved an account
+        let mut removed = false;
+        for user in users {
+            match credentials.delete(SERVICE, user) {
+                Ok(()) => removed = true,
+                Err(CredentialError::NotFound) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        println!(
+            "{} {} credentials",
+            if removed { "Removed" } else { "No saved" },
+            provider
+        );
+    }
+
+    if !purge {
+        if !accounts.is_empty() {
+            println!(
+                "Kept {} accounts and their conversations; add --purge to delete them too",
+                accounts.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let _lock = DataDirLock::acquire(data_dir)?;
+    let config = ParquetStorageConfig::new(data_dir);
+    let embeddings = EmbeddingsStore::new(config.clone());
+    for account in &accounts {
+        let conversations = store.delete_account(&account.id)?;
+        for conv in &conversations {
+            let path = config.conversation_path(&conv.provider_id, &conv.id);
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        let ids: HashSet<String> = conversations.iter().map(|c| c.id.clone()).collect();
+        embeddings.remove_conversations(provider, &ids)?;
+        println!(
+            "Deleted {} and its {} conversations",
+            account.label(),
+            conversations.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use super::pull::{cancel_on_signal, run_pipeline, DataDirLock};
+use chrono::{DateTime, Utc};
+use quaid_core::{
+    pipeline::{CancellationToken, PipelineConfig},
+    providers::{
+        aistudio::{parse_prompt, prompt_id},
+        Conversation, Message, ProviderId,
+    },
+    Store,
+};
+use std::path::{Path, PathBuf};
+
+/// Import AI Studio prompt files from `path`, a downloaded prompt or a folder of them
+///
+/// Imported prompts are stored and indexed by a pipeline run with `config`.
+/// With `dry_run`, the prompts are only compared with the stored ones.
+pub async fn import(
+    path: &Path,
+    dry_run: bool,
+    verbose: bool,
+    config: &PipelineConfig,
+    store: &Store,
+) -> anyhow::Result<()> {
+    let files = prompt_files(path)?;
+    println!("Reading {} files from {}...", files.len(), path.display());
+    let mut skipped = 0;
+    let prompts = read_prompts(&files, &mut skipped)?;
+
+    if dry_run {
+        super::print_import_diff(store, &prompts, verbose)?;
+        return Ok(());
+    }
+
+    let _lock = DataDirLock::acquire(&config.data_dir)?;
+    let account = super::local_account(store, ProviderId::aistudio(), "Google AI Studio")?;
+
+    let mut pipeline_data = Vec::new();
+    for (conversation, messages) in prompts {
+        store.save_conversation_with_messages(&account.id, &conversation, &messages)?;
+        pipeline_data.push((account.id.clone(), conversation, messages));
+    }
+
+    println!(
+        "Import complete: {} prompts imported, {} skipped",
+        pipeline_data.len(),
+        skipped
+    );
+
+    if !pipeline_data.is_empty() {
+        let cancel = CancellationToken::new();
+        cancel_on_signal(cancel.clone());
+        run_pipeline(config, pipeline_data, &cancel).await?;
+    }
+
+    Ok(())
+}
+
+/// Parse prompt files, counting the ones that can't be read or parsed in `skipped`
+fn read_prompts(
+    files: &[PathBuf],
+    skipped: &mut usize,
+) -> anyhow::Result<Vec<(Conversation, Vec<Message>)>> {
+    let mut prompts = Vec::new();
+    for file in files {
+        let name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let modified: DateTime<Utc> = std::fs::metadata(file)?.modified()?.into();
+        let json = match std::fs::read_to_string(file) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Skipped {}: {}", file.display(), e);
+                *skipped += 1;
+                continue;
+            }
+        };
+        match parse_prompt(&prompt_id(&name), &json, modified) {
+            Ok(prompt) => prompts.push(prompt),
+            Err(e) => {
+                eprintln!("Skipped {}: {}", file.display(), e);
+                *skipped += 1;
+            }
+        }
+    }
+    Ok(prompts)
+}
+
+/// `path` itself, or the files in it, leaving out hidden ones like `.DS_Store`
+fn prompt_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if entry.file_type()?.is_file() && !hidden {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{local_account, print_import_diff};
+    use quaid_core::providers::MessageContent;
+    use quaid_core::storage::DiffSummary;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/quaid-core/tests/fixtures");
+
+    #[test]
+    fn test_dry_run_diffs_a_folder_imported_twice() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["aistudio-chat.json", "aistudio-freeform.json"] {
+            std::fs::copy(format!("{}/{}", FIXTURES, name), dir.path().join(name)).unwrap();
+        }
+        std::fs::write(dir.path().join(".DS_Store"), b"").unwrap();
+        let mut skipped = 0;
+        let prompts = read_prompts(&prompt_files(dir.path()).unwrap(), &mut skipped).unwrap();
+        assert_eq!((prompts.len(), skipped), (2, 0));
+
+        let store = Store::in_memory().unwrap();
+        let counts = |summary: DiffSummary| (summary.new, summary.updated, summary.identical);
+        let summary = print_import_diff(&store, &prompts, true).unwrap();
+        assert_eq!(counts(summary), (2, 0, 0));
+        assert!(store.get_conversation(&prompts[0].0.id).unwrap().is_none());
+
+        let account = local_account(&store, ProviderId::aistudio(), "AI Studio").unwrap();
+        for (conversation, messages) in &prompts {
+            store
+                .save_conversation_with_messages(&account.id, conversation, messages)
+                .unwrap();
+        }
+        let summary = print_import_diff(&store, &prompts, false).unwrap();
+        assert_eq!(counts(summary), (0, 0, 2));
+
+        let mut edited = prompts[1].1[0].clone();
+        edited.content = MessageContent::Text {
+            text: "Edited locally".to_string(),
+        };
+        store.save_message(&edited).unwrap();
+        let summary = print_import_diff(&store, &prompts, true).unwrap();
+        assert_eq!(counts(summary.clone()), (0, 1, 1));
+        assert_eq!(summary.changes[1].conversation_id, prompts[1].0.id);
+    }
+}
@@ -0,0 +1,236 @@
+use super::pull::{download_attachments, AttachmentOptions, DataDirLock};
+use super::stats::format_bytes;
+use crate::config::ProvidersConfig;
+use quaid_core::pipeline::CancellationToken;
+use quaid_core::providers::{
+    chatgpt::ChatGptProvider, claude::ClaudeProvider, fathom::FathomProvider, Account,
+};
+use quaid_core::storage::{AttachmentFilter, AttachmentState};
+use quaid_core::{CredentialStore, Provider, Store};
+use std::path::Path;
+use std::sync::Arc;
+
+/// List attachments with their download state
+pub fn ls(filter: &AttachmentFilter, store: &Store) -> anyhow::Result<()> {
+    let entries = store.list_attachments(filter)?;
+    if entries.is_empty() {
+        println!("No attachments found. They are saved when you pull ChatGPT, Claude or Fathom.");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        let attachment = &entry.file.attachment;
+        println!(
+            "  {:10} | {:>9} | {:8} | {:24} | {} | {}",
+            entry.state.as_str(),
+            format_bytes(attachment.size_bytes),
+            entry.provider_id.as_deref().unwrap_or("-"),
+            attachment.mime_type,
+            attachment.id,
+            attachment.filename
+        );
+        if let Some(error) = &entry.error {
+            if entry.file.local_path.is_none() {
+                println!("      {}", error);
+            }
+        }
+    }
+
+    let count = |state| entries.iter().filter(|e| e.state == state).count();
+    println!(
+        "\n{} attachments: {} pending, {} failed",
+        entries.len(),
+        count(AttachmentState::Pending),
+        count(AttachmentState::Failed)
+    );
+    Ok(())
+}
+
+/// Download everything pending for the selected accounts, each through its
+/// own account
+#[allow(clippy::too_many_arguments)]
+pub async fn download(
+    provider: Option<&str>,
+    account: Option<&str>,
+    max_size: Option<u64>,
+    concurrency: usize,
+    options: &ProvidersConfig,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let _lock = DataDirLock::acquire(data_dir)?;
+    download_pending(
+        provider,
+        account,
+        max_size,
+        concurrency,
+        options,
+        store,
+        credentials,
+        data_dir,
+    )
+    .await
+}
+
+/// Put attachments a pull gave up on back in the queue and download
+/// everything pending for the selected accounts
+#[allow(clippy::too_many_arguments)]
+pub async fn retry(
+    provider: Option<&str>,
+    account: Option<&str>,
+    max_size: Option<u64>,
+    concurrency: usize,
+    options: &ProvidersConfig,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let _lock = DataDirLock::acquire(data_dir)?;
+    let requeued = store.retry_failed_attachments(provider)?;
+    if requeued > 0 {
+        println!("Retrying {} attachments that failed before", requeued);
+    }
+    download_pending(
+        provider,
+        account,
+        max_size,
+        concurrency,
+        options,
+        store,
+        credentials,
+        data_dir,
+    )
+    .await
+}
+
+/// Open a downloaded attachment with the system's default application
+pub fn open(id: &str, print: bool, store: &Store) -> anyhow::Result<()> {
+    let file = store
+        .get_attachment_file(id)?
+        .ok_or_else(|| anyhow::anyhow!("Attachment not found: {}", id))?;
+    let Some(path) = file.local_path else {
+        anyhow::bail!(
+            "{} isn't downloaded yet. Run `quaid attachments download` first.",
+            file.attachment.filename
+        );
+    };
+    if !Path::new(&path).exists() {
+        anyhow::bail!(
+            "{} is gone. Run `quaid attachments verify`, then `quaid attachments download`.",
+            path
+        );
+    }
+
+    if print {
+        println!("{}", path);
+        return Ok(());
+    }
+    let status = super::open::system_open(&path)
+        .map_err(|e| anyhow::anyhow!("Could not open {}: {}", path, e))?;
+    if !status.success() {
+        anyhow::bail!("Could not open {}", path);
+    }
+    println!("Opened: {}", path);
+    Ok(())
+}
+
+/// Put attachments whose files were deleted back in the download queue
+pub fn verify(store: &Store) -> anyhow::Result<()> {
+    let missing = store.verify_attachment_files()?;
+    if missing.is_empty() {
+        println!("All downloaded attachments are on disk");
+        return Ok(());
+    }
+    for file in &missing {
+        println!(
+            "  Missing: {} ({})",
+            file.local_path.as_deref().unwrap_or_default(),
+            file.attachment.id
+        );
+    }
+    println!(
+        "\n{} attachments are pending again. Run `quaid attachments download` to fetch them.",
+        missing.len()
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn download_pending(
+    provider: Option<&str>,
+    account: Option<&str>,
+    max_size: Option<u64>,
+    concurrency: usize,
+    options: &ProvidersConfig,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let accounts = super::select_accounts(store, provider, account)?;
+    if accounts.is_empty() {
+        anyhow::bail!("No accounts configured. Use `quaid <provider> auth` first.");
+    }
+
+    let attachments = AttachmentOptions {
+        skip: false,
+        max_size,
+    };
+    let cancel = CancellationToken::new();
+    super::pull::cancel_on_signal(cancel.clone());
+    for account in accounts {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let Some(provider) = provider_for(&account, credentials, options, max_size) else {
+            continue;
+        };
+        if !provider.is_authenticated().await {
+            eprintln!(
+                "Warning: not signed in to {} as {}; run `quaid {} auth`",
+                account.provider.0, account.email, account.provider.0
+            );
+            continue;
+        }
+        println!("--- {} ({}) ---", account.provider.0, account.label());
+        download_attachments(
+            provider.as_ref(),
+            &account.id,
+            store,
+            data_dir,
+            &attachments,
+            concurrency,
+            &cancel,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Providers with attachments to download; Granola has none
+///
+/// Fathom checks recordings against `max_size` itself, as they're listed
+/// without a size.
+fn provider_for(
+    account: &Account,
+    credentials: &Arc<dyn CredentialStore>,
+    options: &ProvidersConfig,
+    max_size: Option<u64>,
+) -> Option<Box<dyn Provider>> {
+    let (store, email) = (credentials.clone(), Some(account.email.as_str()));
+    Some(match account.provider.0.as_str() {
+        "chatgpt" => Box::new(ChatGptProvider::with_stored_account(store, account)),
+        "claude" => {
+            let mut provider = ClaudeProvider::with_account_credentials(store, email);
+            if let Some(org_id) = &options.claude.org_id {
+                provider = provider.with_org_id(org_id);
+            }
+            Box::new(provider)
+        }
+        "fathom" => {
+            let provider = FathomProvider::with_account_credentials(store, email);
+            Box::new(provider.with_max_recording_size(max_size))
+        }
+        _ => return None,
+    })
+}
@@ -1,59 +1,207 @@
 use quaid_core::{
     providers::{
-        chatgpt::ChatGptProvider, claude::ClaudeProvider, fathom::FathomProvider,
-        granola::GranolaProvider,
+        chatgpt::ChatGptProvider, claude::ClaudeProvider, copilot::CopilotProvider,
+        deepseek::DeepSeekProvider, fathom::FathomProvider, gemini::GeminiProvider,
+        granola::GranolaProvider, grok::GrokProvider, lechat::LeChatProvider, otter::OtterProvider,
+        poe::PoeProvider, Account,
     },
-    Provider, Store,
+    CredentialStore, Provider, Store,
 };
+use std::io::{IsTerminal, Write};
+use std::sync::Arc;
 
-pub async fn run(provider: &str, store: &Store) -> anyhow::Result<()> {
+/// A credential copied from a signed-in browser, for signing in on machines
+/// that can't open one
+#[derive(Debug, PartialEq, Eq)]
+enum Pasted {
+    Cookie(String),
+    Token(String),
+}
+
+impl Pasted {
+    fn value_mut(&mut self) -> &mut String {
+        match self {
+            Pasted::Cookie(value) | Pasted::Token(value) => value,
+        }
+    }
+}
+
+/// Sign in to `provider`; with `all`, sync every ChatGPT workspace without asking
+///
+/// Given a `cookie` or `token`, or one in the environment, Claude and ChatGPT
+/// are signed in to without a browser.
+pub async fn run(
+    provider: &str,
+    all: bool,
+    cookie: Option<String>,
+    token: Option<String>,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+) -> anyhow::Result<()> {
+    if all && provider != "chatgpt" {
+        anyhow::bail!("--all picks ChatGPT workspaces; {} has none", provider);
+    }
+    let env = |name: &str| std::env::var(name).ok();
+    let pasted = pasted_credential(provider, cookie, token, env, read_stdin_line)?;
     match provider {
         "chatgpt" => {
-            println!("Authenticating with ChatGPT...");
-            println!("A browser window will open. Please log in to your ChatGPT account.");
+            let mut provider = ChatGptProvider::with_account_credentials(credentials.clone(), None);
+            let user = match pasted {
+                Some(Pasted::Token(token)) => provider.authenticate_with_token(&token).await?,
+                Some(Pasted::Cookie(cookie)) => {
+                    provider.authenticate_with_session_cookie(&cookie).await?
+                }
+                None => {
+                    println!("Authenticating with ChatGPT...");
+                    println!("A browser window will open. Please log in to your ChatGPT account.");
+                    provider.authenticate().await?
+                }
+            };
+
+            let available = provider
+                .available_accounts(&user)
+                .await
+                .unwrap_or_else(|e| {
+                    eprintln!(
+                        "Warning: Couldn't list workspaces, syncing the personal account: {}",
+                        e
+                    );
+                    vec![user.clone()]
+                });
+            let accounts = if all || available.len() == 1 || !std::io::stdin().is_terminal() {
+                available
+            } else {
+                choose_accounts(available)?
+            };
 
-            let mut provider = ChatGptProvider::new();
+            // Save the chosen accounts to the store, and the choice with the credentials
+            provider.save_selection(&user.email, &accounts)?;
+            for account in &accounts {
+                store.save_account(account)?;
+            }
+
+            println!("\nAuthenticated as: {} ({})", user.email, user.id);
+            if accounts.len() > 1 || accounts.iter().any(|a| a.workspace_id.is_some()) {
+                for account in &accounts {
+                    println!("  Syncing {}", account_choice(account));
+                }
+            }
+            println!(
+                "Account saved. You can now use `quaid pull chatgpt` to sync your conversations."
+            );
+
+            Ok(())
+        }
+        "claude" => {
+            let mut provider = ClaudeProvider::with_account_credentials(credentials.clone(), None);
+            let account = match pasted {
+                Some(Pasted::Cookie(cookies)) => {
+                    provider.authenticate_with_cookies(&cookies).await?
+                }
+                _ => {
+                    println!("Authenticating with Claude...");
+                    println!("A browser window will open. Please log in to your Claude account.");
+                    provider.authenticate().await?
+                }
+            };
+
+            // Save account to store
+            store.save_account(&account)?;
+
+            println!("\nAuthenticated as: {} ({})", account.email, account.id);
+            println!(
+                "Account saved. You can now use `quaid pull claude` to sync your conversations."
+            );
+
+            Ok(())
+        }
+        "copilot" => {
+            println!("Authenticating with Copilot...");
+            println!("A browser window will open. Please sign in to your Microsoft account.");
+
+            let mut provider = CopilotProvider::with_account_credentials(credentials.clone(), None);
             let account = provider.authenticate().await?;
 
             // Save account to store
             store.save_account(&account)?;
 
             println!("\nAuthenticated as: {} ({})", account.email, account.id);
-            println!("Account saved. You can now use `quaid pull chatgpt` to sync your conversations.");
+            println!("Account saved. You can now use `quaid pull copilot` to sync your chats.");
 
             Ok(())
         }
-        "claude" => {
-            println!("Authenticating with Claude...");
-            println!("A browser window will open. Please log in to your Claude account.");
+        "lechat" => {
+            println!("Authenticating with Le Chat...");
+            println!("A browser window will open. Please sign in to your Mistral account.");
 
-            let mut provider = ClaudeProvider::new();
+            let mut provider = LeChatProvider::with_account_credentials(credentials.clone(), None);
             let account = provider.authenticate().await?;
 
             // Save account to store
             store.save_account(&account)?;
 
             println!("\nAuthenticated as: {} ({})", account.email, account.id);
-            println!("Account saved. You can now use `quaid pull claude` to sync your conversations.");
+            println!("Account saved. You can now use `quaid pull lechat` to sync your chats.");
+
+            Ok(())
+        }
+        "deepseek" => {
+            println!("Authenticating with DeepSeek...");
+            println!("A browser window will open. Please sign in to your DeepSeek account.");
+
+            let mut provider =
+                DeepSeekProvider::with_account_credentials(credentials.clone(), None);
+            let account = provider.authenticate().await?;
+
+            // Save account to store
+            store.save_account(&account)?;
+
+            println!("\nAuthenticated as: {} ({})", account.email, account.id);
+            println!("Account saved. You can now use `quaid pull deepseek` to sync your chats.");
+
+            Ok(())
+        }
+        "poe" => {
+            println!("Authenticating with Poe...");
+            println!("A browser window will open. Please sign in to your Poe account.");
+
+            let mut provider = PoeProvider::with_account_credentials(credentials.clone(), None);
+            let account = provider.authenticate().await?;
+
+            // Save account to store
+            store.save_account(&account)?;
+
+            println!("\nAuthenticated as: {} ({})", account.email, account.id);
+            println!("Account saved. You can now use `quaid pull poe` to sync your chats.");
 
             Ok(())
         }
         "gemini" => {
-            anyhow::bail!("Gemini provider not yet implemented");
+            println!("Authenticating with Gemini...");
+            println!("A browser window will open. Please log in to your Google account.");
+
+            let mut provider = GeminiProvider::with_account_credentials(credentials.clone(), None);
+            let account = provider.authenticate().await?;
+
+            // Save account to store
+            store.save_account(&account)?;
+
+            println!("\nAuthenticated as: {} ({})", account.email, account.id);
+            println!("Account saved. You can now use `quaid pull gemini` to sync your chats.");
+
+            Ok(())
         }
         "fathom" => {
             println!("Authenticating with Fathom...");
 
-            let mut provider = FathomProvider::new();
+            let mut provider = FathomProvider::with_account_credentials(credentials.clone(), None);
             let account = provider.authenticate().await?;
 
             // Save account to store
             store.save_account(&account)?;
 
             println!("\nAuthenticated as: {} ({})", account.email, account.id);
-            println!(
-                "Account saved. You can now use `quaid pull fathom` to sync your meetings."
-            );
+            println!("Account saved. You can now use `quaid pull fathom` to sync your meetings.");
 
             Ok(())
         }
@@ -73,11 +221,223 @@ pub async fn run(provider: &str, store: &Store) -> anyhow::Result<()> {
 
             Ok(())
         }
+        "otter" => {
+            println!("Authenticating with Otter...");
+
+            let mut provider = OtterProvider::with_account_credentials(credentials.clone(), None);
+            let account = provider.authenticate().await?;
+
+            // Save account to store
+            store.save_account(&account)?;
+
+            println!("\nAuthenticated as: {} ({})", account.email, account.id);
+            println!("Account saved. You can now use `quaid pull otter` to sync your meetings.");
+
+            Ok(())
+        }
+        "grok" => {
+            println!("Authenticating with Grok...");
+            println!("A browser window will open. Please log in to your X account.");
+
+            let mut provider = GrokProvider::with_account_credentials(credentials.clone(), None);
+            let account = provider.authenticate().await?;
+
+            // Save account to store
+            store.save_account(&account)?;
+
+            println!("\nAuthenticated as: {} ({})", account.email, account.id);
+            println!(
+                "Account saved. You can now use `quaid pull grok` to sync your conversations."
+            );
+
+            Ok(())
+        }
         _ => {
             anyhow::bail!(
-                "Unknown provider: {}. Supported: chatgpt, claude, fathom, granola",
+                "Unknown provider: {}. Supported: chatgpt, claude, copilot, deepseek, fathom, gemini, granola, grok, lechat, otter, poe",
                 provider
             );
         }
     }
 }
+
+/// The credential given with `--cookie` or `--token`, or else in the
+/// environment; `-` reads it from stdin
+fn pasted_credential(
+    provider: &str,
+    cookie: Option<String>,
+    token: Option<String>,
+    env: impl Fn(&str) -> Option<String>,
+    read_stdin: impl FnOnce() -> std::io::Result<String>,
+) -> anyhow::Result<Option<Pasted>> {
+    let env = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+    let mut pasted = match (provider, cookie, token) {
+        (_, Some(cookie), _) => Pasted::Cookie(cookie),
+        (_, None, Some(token)) => Pasted::Token(token),
+        ("claude", None, None) => match env("QUAID_CLAUDE_COOKIE") {
+            Some(cookie) => Pasted::Cookie(cookie),
+            None => return Ok(None),
+        },
+        ("chatgpt", None, None) => {
+            match (env("QUAID_CHATGPT_TOKEN"), env("QUAID_CHATGPT_COOKIE")) {
+                (Some(token), _) => Pasted::Token(token),
+                (None, Some(cookie)) => Pasted::Cookie(cookie),
+                (None, None) => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+    match (provider, &pasted) {
+        ("chatgpt", _) | ("claude", Pasted::Cookie(_)) => {}
+        ("claude", Pasted::Token(_)) => {
+            anyhow::bail!("Claude signs in with --cookie, the Cookie header of a claude.ai request")
+        }
+        _ => anyhow::bail!(
+            "--cookie and --token sign in to Claude and ChatGPT; run `quaid {} auth` without them",
+            provider
+        ),
+    }
+
+    let value = pasted.value_mut();
+    if value == "-" {
+        *value = read_stdin()?;
+    }
+    *value = value.trim().to_string();
+    if value.is_empty() {
+        anyhow::bail!("The pasted credential is empty");
+    }
+    Ok(Some(pasted))
+}
+
+fn read_stdin_line() -> std::io::Result<String> {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line)
+}
+
+/// Ask which of one sign-in's accounts to sync
+fn choose_accounts(available: Vec<Account>) -> anyhow::Result<Vec<Account>> {
+    println!("\nThis sign-in can sync:");
+    for (i, account) in available.iter().enumerate() {
+        println!("  {}. {}", i + 1, account_choice(account));
+    }
+    print!("Accounts to sync (e.g. 1,3; Enter for all): ");
+    std::io::stdout().flush()?;
+
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let picked = parse_choice(&line, available.len())?;
+    Ok(available
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picked.contains(i))
+        .map(|(_, account)| account)
+        .collect())
+}
+
+fn account_choice(account: &Account) -> String {
+    match account.workspace_id {
+        Some(_) => account.label(),
+        None => format!("{} (personal)", account.email),
+    }
+}
+
+/// Indexes picked by a comma-separated list of 1-based numbers; all of them when blank
+fn parse_choice(input: &str, count: usize) -> anyhow::Result<Vec<usize>> {
+    let input = input.trim();
+    if input.is_empty() || input.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+    let mut picked = Vec::new();
+    for part in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.parse::<usize>() {
+            Ok(n) if (1..=count).contains(&n) => {
+                if !picked.contains(&(n - 1)) {
+                    picked.push(n - 1);
+                }
+            }
+            _ => anyhow::bail!("Pick accounts by number, 1 to {}: {}", count, part),
+        }
+    }
+    if picked.is_empty() {
+        anyhow::bail!("No accounts picked");
+    }
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(parse_choice("\n", 3).unwrap(), vec![0, 1, 2]);
+        assert_eq!(parse_choice("all", 2).unwrap(), vec![0, 1]);
+        assert_eq!(parse_choice(" 3, 1 ,3\n", 3).unwrap(), vec![2, 0]);
+        assert!(parse_choice("0", 3).is_err());
+        assert!(parse_choice("4", 3).is_err());
+        assert!(parse_choice("two", 3).is_err());
+        assert!(parse_choice(",", 3).is_err());
+    }
+
+    #[test]
+    fn test_pasted_credential() {
+        let no_env = |_: &str| None;
+        let no_stdin = || -> std::io::Result<String> { panic!("stdin read") };
+        let pasted = |provider, cookie: Option<&str>, token: Option<&str>| {
+            pasted_credential(
+                provider,
+                cookie.map(String::from),
+                token.map(String::from),
+                no_env,
+                no_stdin,
+            )
+        };
+
+        assert_eq!(pasted("claude", None, None).unwrap(), None);
+        assert_eq!(
+            pasted("claude", Some(" sessionKey=abc\n"), None).unwrap(),
+            Some(Pasted::Cookie("sessionKey=abc".to_string()))
+        );
+        assert_eq!(
+            pasted("chatgpt", None, Some("eyJ")).unwrap(),
+            Some(Pasted::Token("eyJ".to_string()))
+        );
+        assert!(pasted("claude", None, Some("eyJ")).is_err());
+        assert!(pasted("fathom", Some("a=b"), None).is_err());
+        assert!(pasted("chatgpt", None, Some("  ")).is_err());
+    }
+
+    #[test]
+    fn test_pasted_credential_from_env_or_stdin() {
+        let env = |name: &str| match name {
+            "QUAID_CLAUDE_COOKIE" => Some("sessionKey=env".to_string()),
+            "QUAID_CHATGPT_TOKEN" => Some(String::new()),
+            "QUAID_CHATGPT_COOKIE" => Some("session".to_string()),
+            _ => None,
+        };
+        let no_stdin = || -> std::io::Result<String> { panic!("stdin read") };
+
+        assert_eq!(
+            pasted_credential("claude", None, None, env, no_stdin).unwrap(),
+            Some(Pasted::Cookie("sessionKey=env".to_string()))
+        );
+        // An empty variable counts as unset
+        assert_eq!(
+            pasted_credential("chatgpt", None, None, env, no_stdin).unwrap(),
+            Some(Pasted::Cookie("session".to_string()))
+        );
+        // Flags win over the environment
+        assert_eq!(
+            pasted_credential("chatgpt", None, Some("-".to_string()), env, || {
+                Ok("from-stdin\n".to_string())
+            })
+            .unwrap(),
+            Some(Pasted::Token("from-stdin".to_string()))
+        );
+        assert_eq!(
+            pasted_credential("granola", None, None, env, no_stdin).unwrap(),
+            None
+        );
+    }
+}
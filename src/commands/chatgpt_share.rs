@@ -0,0 +1,55 @@
+use super::pull::{cancel_on_signal, run_pipeline, DataDirLock};
+use quaid_core::{
+    pipeline::{CancellationToken, PipelineConfig},
+    providers::{
+        chatgpt::share::{fetch_share, share_id},
+        ProviderId,
+    },
+    Store,
+};
+
+/// Import the conversation behind a ChatGPT share link
+///
+/// Shares of conversations already in the archive, pulled or imported
+/// before, are left alone. Others are kept under a local ChatGPT account,
+/// noted as coming from a share, and indexed by a pipeline run with `config`.
+/// With `dry_run`, the share is only compared with the archive.
+pub async fn import(
+    url: &str,
+    dry_run: bool,
+    verbose: bool,
+    config: &PipelineConfig,
+    store: &Store,
+) -> anyhow::Result<()> {
+    let id = share_id(url).ok_or_else(|| anyhow::anyhow!("Not a ChatGPT share link: {}", url))?;
+
+    println!("Fetching share {}...", id);
+    let (conversation, messages) = fetch_share(&id).await?;
+
+    if let Some(existing) = store.get_conversation(&conversation.id)? {
+        println!(
+            "\"{}\" is already in the archive as {}; nothing to import",
+            existing.title, existing.id
+        );
+        return Ok(());
+    }
+    if dry_run {
+        super::print_import_diff(store, &[(conversation, messages)], verbose)?;
+        return Ok(());
+    }
+
+    let _lock = DataDirLock::acquire(&config.data_dir)?;
+    let account = super::local_account(store, ProviderId::chatgpt(), "ChatGPT shares")?;
+    store.save_conversation_with_messages(&account.id, &conversation, &messages)?;
+    store.set_conversation_source(&conversation.id, "share")?;
+    println!(
+        "Imported \"{}\" ({} messages) as {}",
+        conversation.title,
+        messages.len(),
+        conversation.id
+    );
+
+    let cancel = CancellationToken::new();
+    cancel_on_signal(cancel.clone());
+    run_pipeline(config, vec![(account.id, conversation, messages)], &cancel).await
+}
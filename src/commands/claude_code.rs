@@ -0,0 +1,121 @@
+use super::pull::{cancel_on_signal, run_pipeline, DataDirLock};
+use chrono::{DateTime, Utc};
+use quaid_core::{
+    pipeline::{CancellationToken, PipelineConfig},
+    providers::{claude::code::parse_session_file, ProviderId},
+    Store,
+};
+use std::path::{Path, PathBuf};
+
+/// Import Claude Code sessions from `path`, `~/.claude/projects` by default
+///
+/// With `new_only`, files whose modification time is the one recorded by the
+/// last import are skipped; sessions are only ever appended to. Imported
+/// sessions are stored and indexed by a pipeline run with `config`. With
+/// `dry_run`, the sessions are only compared with the stored ones.
+#[allow(clippy::too_many_arguments)]
+pub async fn import(
+    path: Option<&Path>,
+    new_only: bool,
+    include_thinking: bool,
+    dry_run: bool,
+    verbose: bool,
+    config: &PipelineConfig,
+    store: &Store,
+) -> anyhow::Result<()> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("No home directory; pass --path"))?
+            .join(".claude/projects"),
+    };
+    if !path.exists() {
+        anyhow::bail!("No Claude Code sessions at {}", path.display());
+    }
+
+    let provider = ProviderId::claude_code();
+    let mut files = Vec::new();
+    session_files(&path, &mut files)?;
+    println!("Found {} session files in {}", files.len(), path.display());
+
+    if dry_run {
+        let mut sessions = Vec::new();
+        for file in files {
+            let key = file.to_string_lossy().to_string();
+            let modified: DateTime<Utc> = std::fs::metadata(&file)?.modified()?.into();
+            if new_only && store.imported_file_mtime(&provider.0, &key)? == Some(modified) {
+                continue;
+            }
+            match std::fs::read_to_string(&file) {
+                Ok(jsonl) => sessions.extend(parse_session_file(&jsonl, include_thinking)),
+                Err(e) => eprintln!("Error reading {}: {}", file.display(), e),
+            }
+        }
+        super::print_import_diff(store, &sessions, verbose)?;
+        return Ok(());
+    }
+
+    let _lock = DataDirLock::acquire(&config.data_dir)?;
+    let account = super::local_account(store, provider.clone(), "Claude Code")?;
+
+    let mut pipeline_data = Vec::new();
+    let mut unchanged = 0;
+    let mut failed = 0;
+    for file in files {
+        let key = file.to_string_lossy().to_string();
+        let modified: DateTime<Utc> = std::fs::metadata(&file)?.modified()?.into();
+        if new_only && store.imported_file_mtime(&provider.0, &key)? == Some(modified) {
+            unchanged += 1;
+            continue;
+        }
+
+        match std::fs::read_to_string(&file) {
+            Ok(jsonl) => {
+                for (conversation, messages) in parse_session_file(&jsonl, include_thinking) {
+                    store.save_conversation_with_messages(&account.id, &conversation, &messages)?;
+                    pipeline_data.push((account.id.clone(), conversation, messages));
+                }
+                store.record_imported_file(&provider.0, &key, modified)?;
+            }
+            Err(e) => {
+                eprintln!("Error importing {}: {}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Import complete: {} sessions imported, {} files unchanged, {} failed",
+        pipeline_data.len(),
+        unchanged,
+        failed
+    );
+
+    if !pipeline_data.is_empty() {
+        let cancel = CancellationToken::new();
+        cancel_on_signal(cancel.clone());
+        run_pipeline(config, pipeline_data, &cancel).await?;
+    }
+
+    Ok(())
+}
+
+/// `.jsonl` files under `dir`, sorted, or `dir` itself if it's a file
+fn session_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    if !dir.is_dir() {
+        files.push(dir.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            session_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "jsonl") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
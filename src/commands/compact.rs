@@ -1,8 +1,12 @@
 use quaid_core::storage::ParquetStorageConfig;
-use quaid_core::EmbeddingsCompactor;
+use quaid_core::titles::CorpusStats;
+use quaid_core::{EmbeddingsCompactor, Store};
 use std::path::Path;
 
-pub fn run(data_dir: &Path) -> anyhow::Result<()> {
+pub fn run(store: &Store, data_dir: &Path) -> anyhow::Result<()> {
+    // Term statistics used by `rename --auto --smart`
+    CorpusStats::refresh(store, data_dir)?;
+
     let config = ParquetStorageConfig::new(data_dir);
     let compactor = EmbeddingsCompactor::new(config.clone());
 
@@ -20,10 +24,7 @@ pub fn run(data_dir: &Path) -> anyhow::Result<()> {
     if needs_compaction.is_empty() {
         println!("All embeddings are already consolidated:");
         for status in &statuses {
-            println!(
-                "  ✓ {} ({} rows)",
-                status.provider, status.total_rows
-            );
+            println!("  ✓ {} ({} rows)", status.provider, status.total_rows);
         }
         return Ok(());
     }
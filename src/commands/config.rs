@@ -0,0 +1,29 @@
+use crate::config::{Config, TEMPLATE};
+use std::path::Path;
+
+/// Write the commented template to `path`, keeping an existing file unless `force`
+pub fn init(path: &Path, force: bool) -> anyhow::Result<()> {
+    if path.exists() && !force {
+        anyhow::bail!(
+            "{} already exists. Use --force to replace it.",
+            path.display()
+        );
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, TEMPLATE)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// Print the config in effect: the file at `path` merged over the defaults
+pub fn show(config: &Config, path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        println!("# From {} and built-in defaults", path.display());
+    } else {
+        println!("# Built-in defaults ({} not found)", path.display());
+    }
+    print!("{}", config.effective().to_toml()?);
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use super::pull::DataDirLock;
+use quaid_core::storage::parquet::ParquetStore;
+use quaid_core::storage::{Duplicates, EmbeddingsStore, ParquetCompression};
+use quaid_core::{ParquetStorageConfig, Store};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Merge duplicate accounts and conversations, or list them with `dry_run`
+///
+/// Merged conversations' parquet files are written with `compression`.
+pub fn run(
+    store: &Store,
+    data_dir: &Path,
+    dry_run: bool,
+    compression: ParquetCompression,
+) -> anyhow::Result<()> {
+    let report = if dry_run {
+        store.dedupe(true)?
+    } else {
+        let _lock = DataDirLock::acquire(data_dir)?;
+        let report = store.dedupe(false)?;
+        clean_up(store, data_dir, compression, &report.conversations)?;
+        report
+    };
+    print_sets("Accounts", &report.accounts);
+    print_sets("Conversations", &report.conversations);
+
+    if report.accounts.is_empty() && report.conversations.is_empty() {
+        println!("No duplicates found");
+        return Ok(());
+    }
+
+    let accounts: usize = report.accounts.iter().map(|s| s.duplicates.len()).sum();
+    let conversations: usize = report
+        .conversations
+        .iter()
+        .map(|s| s.duplicates.len())
+        .sum();
+    if dry_run {
+        println!(
+            "\nWould merge {} accounts and {} conversations. Run without --dry-run to merge them.",
+            accounts, conversations
+        );
+    } else {
+        println!(
+            "\nMerged {} accounts and {} conversations: moved {} messages, dropped {} already kept",
+            accounts, conversations, report.messages.moved, report.messages.dropped
+        );
+    }
+    Ok(())
+}
+
+/// Remove the parquet files and embeddings of the duplicates in `sets` and
+/// rewrite each kept conversation's parquet file with the messages it gained
+fn clean_up(
+    store: &Store,
+    data_dir: &Path,
+    compression: ParquetCompression,
+    sets: &[Duplicates],
+) -> anyhow::Result<()> {
+    let config = ParquetStorageConfig::new(data_dir);
+    let parquet = ParquetStore::new(config.clone().with_compression(compression));
+    let embeddings = EmbeddingsStore::new(config.clone());
+    for set in sets {
+        let Some(conv) = store.get_conversation(&set.keep)? else {
+            continue;
+        };
+        // Duplicates share the kept conversation's provider
+        for id in &set.duplicates {
+            let path = config.conversation_path(&conv.provider_id, id);
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        let ids: HashSet<String> = set.duplicates.iter().cloned().collect();
+        embeddings.remove_conversations(&conv.provider_id, &ids)?;
+
+        let account_id = store.conversation_account_id(&conv.id)?.unwrap_or_default();
+        parquet.write_conversation(&account_id, &conv, &store.get_messages(&conv.id)?)?;
+    }
+    Ok(())
+}
+
+fn print_sets(heading: &str, sets: &[Duplicates]) {
+    if sets.is_empty() {
+        return;
+    }
+    println!("{}:", heading);
+    for set in sets {
+        println!(
+            "  {} ({}): merging {}",
+            set.label,
+            set.keep,
+            set.duplicates.join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quaid_core::embeddings::Chunk;
+    use quaid_core::providers::{Account, Conversation, Message, MessageContent, ProviderId, Role};
+
+    fn conversation(id: &str) -> Conversation {
+        let at = chrono::DateTime::from_timestamp(1_714_554_000, 0).unwrap();
+        Conversation {
+            id: id.to_string(),
+            provider_id: "claude".to_string(),
+            title: "Trip to Lisbon".to_string(),
+            created_at: at,
+            updated_at: at,
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    fn message(id: &str, conversation_id: &str, text: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_cleans_up_parquet_and_embeddings() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let parquet = ParquetStore::new(config.clone());
+        let embeddings = EmbeddingsStore::new(config.clone());
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+
+        let old = [message("old-1", "conv-old", "Only the old copy has this")];
+        let new = [message("new-1", "conv-new", "Flights in May?")];
+        for (conv, messages) in [
+            (conversation("conv-old"), &old[..]),
+            (conversation("conv-new"), &new[..]),
+        ] {
+            store
+                .save_conversation_with_messages("user-1", &conv, messages)
+                .unwrap();
+            parquet
+                .write_conversation("user-1", &conv, messages)
+                .unwrap();
+        }
+        let chunk = Chunk {
+            text: "Only the old copy has this".to_string(),
+            message_id: "old-1".to_string(),
+            chunk_index: 0,
+            total_chunks: 1,
+        };
+        let embedding = vec![0.0; quaid_core::storage::embeddings::EMBEDDING_DIM as usize];
+        embeddings
+            .write_embeddings("conv-old", "claude", &[chunk], &[embedding])
+            .unwrap();
+
+        run(&store, dir.path(), false, ParquetCompression::default()).unwrap();
+
+        assert!(!config.conversation_path("claude", "conv-old").exists());
+        assert!(embeddings
+            .indexed_conversation_ids("claude")
+            .unwrap()
+            .is_empty());
+        let (_, messages) = parquet
+            .read_conversation("claude", "conv-new")
+            .unwrap()
+            .unwrap();
+        let mut ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["new-1", "old-1"]);
+    }
+}
@@ -0,0 +1,74 @@
+use super::status::provider_for;
+use quaid_core::providers::granola::GranolaProvider;
+use quaid_core::{CredentialStore, Store};
+use std::sync::Arc;
+
+/// Check that each account's credentials are saved and still accepted
+///
+/// Granola accounts also need the desktop app's `supabase.json`. Fails when
+/// any check does, naming what to run or fix.
+pub async fn run(store: &Store, credentials: &Arc<dyn CredentialStore>) -> anyhow::Result<()> {
+    let accounts: Vec<_> = store
+        .list_accounts()?
+        .into_iter()
+        .filter(|account| !account.is_local())
+        .collect();
+    if accounts.is_empty() {
+        println!("No accounts configured. Use `quaid <provider> auth` first.");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for account in &accounts {
+        let provider = &account.provider.0;
+        println!("{} {}", provider, account.label());
+
+        if provider == "granola" {
+            let granola = GranolaProvider::new();
+            let path = granola.credentials_path();
+            let found = if path.exists() {
+                Ok(())
+            } else {
+                Err("not found; sign in to the Granola desktop app".to_string())
+            };
+            failed += report(&format!("supabase.json at {}", path.display()), found);
+        }
+
+        let Some(client) = provider_for(account, credentials) else {
+            failed += report("credentials present", Err("unknown provider".to_string()));
+            continue;
+        };
+        if !client.is_authenticated().await {
+            let missing = format!("missing; run `quaid {} auth`", provider);
+            failed += report("credentials present", Err(missing));
+            continue;
+        }
+        report("credentials present", Ok(()));
+
+        let valid = client
+            .validate_credentials()
+            .await
+            .map_err(|e| e.to_string());
+        failed += report("credentials valid", valid);
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} checks failed", failed);
+    }
+    println!("All checks passed");
+    Ok(())
+}
+
+/// Print one check's outcome; 1 if it failed
+fn report(check: &str, result: Result<(), String>) -> usize {
+    match result {
+        Ok(()) => {
+            println!("  ok    {}", check);
+            0
+        }
+        Err(e) => {
+            println!("  FAIL  {}: {}", check, e);
+            1
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use quaid_core::embeddings::remote::{API_KEY_ENV, API_KEY_SERVICE, API_KEY_USER};
+use quaid_core::CredentialStore;
+use std::io::IsTerminal;
+use std::sync::Arc;
+
+/// Save the API key remote embedders send, typed at a hidden prompt or piped in
+pub fn auth(credentials: &Arc<dyn CredentialStore>) -> anyhow::Result<()> {
+    let key = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password("Embeddings API key: ")?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        line
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        anyhow::bail!("The API key is empty");
+    }
+    credentials.set(API_KEY_SERVICE, API_KEY_USER, key)?;
+    println!("API key saved. {} still wins when it is set.", API_KEY_ENV);
+    Ok(())
+}
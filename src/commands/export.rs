@@ -1,14 +1,52 @@
-use quaid_core::Store;
+use super::parse_date;
+use quaid_core::providers::{Account, Conversation, Message, MessageContent, Role};
+use quaid_core::sample::{ChatExample, ChatMessage};
+use quaid_core::storage::diff::content_hash;
+use quaid_core::storage::duckdb::DuckDbQuery;
+use quaid_core::storage::{content_type_name, extract_text_content, SearchFilters};
+use quaid_core::{ParquetStorageConfig, Store};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
+/// Longest slug used in `--split` file names, in characters
+const MAX_SLUG_LEN: usize = 80;
+
+/// Written into an `--incremental` export directory to tell what changed
+/// since the last run
+const MANIFEST_FILE: &str = ".quaid-export.json";
+
+/// Folder of an Obsidian export that downloaded attachments are copied into
+const OBSIDIAN_ATTACHMENTS_DIR: &str = "attachments";
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     path: &Path,
     format: &str,
+    split: bool,
+    incremental: bool,
     provider: Option<&str>,
+    project: Option<&str>,
+    since: Option<&str>,
+    until: Option<&str>,
+    openai_messages: bool,
+    include_system: bool,
     store: &Store,
+    data_dir: &Path,
 ) -> anyhow::Result<()> {
+    if include_system && !openai_messages {
+        anyhow::bail!("--include-system only applies to --openai-messages");
+    }
+    if openai_messages && (format != "jsonl" || split || incremental) {
+        anyhow::bail!("--openai-messages writes a single jsonl file; use --format jsonl without --split or --incremental");
+    }
+
+    let since = since.map(|s| parse_date(s, false)).transpose()?;
+    let until = until.map(|s| parse_date(s, true)).transpose()?;
+    let created = created_between(data_dir, provider, since, until)?;
+
     let accounts = store.list_accounts()?;
 
     if accounts.is_empty() {
@@ -27,6 +65,12 @@ pub fn run(
 
         let conversations = store.list_conversations(&account.id)?;
         for conv in conversations {
+            if created.as_ref().is_some_and(|ids| !ids.contains(&conv.id))
+                || project.is_some_and(|project| !super::in_project(&conv, project))
+            {
+                continue;
+            }
+
             let messages = store.get_messages(&conv.id)?;
             all_conversations.push((account.clone(), conv, messages));
         }
@@ -42,20 +86,79 @@ pub fn run(
         format
     );
 
+    if incremental {
+        let summary = export_incremental(path, format, &mut all_conversations, store)?;
+        println!(
+            "Wrote {} files, {} unchanged, {} removed in: {}",
+            summary.written,
+            summary.unchanged,
+            summary.removed,
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if split {
+        let written = export_split(path, format, &mut all_conversations)?;
+        println!("Exported {} files to: {}", written, path.display());
+        return Ok(());
+    }
+
     match format {
+        "jsonl" if openai_messages => {
+            let written = export_openai_jsonl(path, &all_conversations, include_system)?;
+            println!("Wrote {} conversations as chat examples", written);
+        }
         "jsonl" => export_jsonl(path, &all_conversations)?,
         "markdown" | "md" => export_markdown(path, &all_conversations)?,
         "json" => export_json(path, &all_conversations)?,
-        _ => anyhow::bail!("Unknown format: {}. Supported: jsonl, markdown, json", format),
+        "csv" => export_csv(path, &all_conversations)?,
+        "obsidian" => export_obsidian(path, &mut all_conversations, store)?,
+        "sqlite" => {
+            quaid_core::storage::dump::write_sqlite_dump(path, &all_conversations)?;
+        }
+        _ => anyhow::bail!(
+            "Unknown format: {}. Supported: jsonl, markdown, json, csv, sqlite, obsidian",
+            format
+        ),
     }
 
     println!("Exported to: {}", path.display());
     Ok(())
 }
 
+/// Ids of the conversations created between `since` and `until`, found by
+/// DuckDB in the parquet files; `None` when neither is given
+fn created_between(
+    data_dir: &Path,
+    provider: Option<&str>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> anyhow::Result<Option<HashSet<String>>> {
+    if since.is_none() && until.is_none() {
+        return Ok(None);
+    }
+    let duckdb = DuckDbQuery::new(ParquetStorageConfig::new(data_dir))
+        .map_err(|e| anyhow::anyhow!("Failed to create query interface: {}", e))?;
+    let filters = SearchFilters {
+        since,
+        until,
+        ..Default::default()
+    };
+    let conversations = match provider {
+        Some(provider) => duckdb.list_conversations_by_provider(provider, &filters)?,
+        None => duckdb.list_all_conversations(&filters)?,
+    };
+    Ok(Some(conversations.into_iter().map(|c| c.id).collect()))
+}
+
 fn export_jsonl(
     path: &Path,
-    conversations: &[(quaid_core::providers::Account, quaid_core::providers::Conversation, Vec<quaid_core::providers::Message>)],
+    conversations: &[(
+        quaid_core::providers::Account,
+        quaid_core::providers::Conversation,
+        Vec<quaid_core::providers::Message>,
+    )],
 ) -> anyhow::Result<()> {
     let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
@@ -84,9 +187,82 @@ fn export_jsonl(
     Ok(())
 }
 
+/// Write conversations as OpenAI chat-format JSONL for fine-tuning
+///
+/// System and tool messages are left out unless `include_system`, as are
+/// messages with nothing to say once flattened, and conversations left with
+/// no messages. Returns the number of conversations written.
+fn export_openai_jsonl(
+    path: &Path,
+    conversations: &[(Account, Conversation, Vec<Message>)],
+    include_system: bool,
+) -> anyhow::Result<usize> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    let mut written = 0;
+
+    for (_, _, messages) in conversations {
+        let Some(example) = chat_example(messages, include_system) else {
+            continue;
+        };
+        // serde_json escapes newlines, so each example stays on one line
+        serde_json::to_writer(&mut writer, &example)?;
+        writeln!(writer)?;
+        written += 1;
+    }
+
+    writer.flush()?;
+    Ok(written)
+}
+
+fn chat_example(messages: &[Message], include_system: bool) -> Option<ChatExample> {
+    let messages: Vec<ChatMessage> = messages
+        .iter()
+        .filter(|m| include_system || matches!(m.role, Role::User | Role::Assistant))
+        .filter_map(|m| {
+            let content = plain_text(&m.content);
+            (!content.trim().is_empty()).then_some(ChatMessage {
+                role: m.role.as_str(),
+                content,
+            })
+        })
+        .collect();
+    (!messages.is_empty()).then_some(ChatExample { messages })
+}
+
+/// Message content as a plain string: code fenced, images and audio without
+/// a transcript as placeholders, and reasoning left out
+fn plain_text(content: &MessageContent) -> String {
+    match content {
+        MessageContent::Text { text } => text.clone(),
+        MessageContent::Code { language, code } => format!("```{}\n{}\n```", language, code),
+        MessageContent::Image { alt, .. } => match alt {
+            Some(alt) => format!("[image: {}]", alt),
+            None => "[image]".to_string(),
+        },
+        MessageContent::Audio { transcript, .. } => {
+            transcript.clone().unwrap_or_else(|| "[audio]".to_string())
+        }
+        MessageContent::Document { title, mime, text } => {
+            document_markdown(title, mime, text).trim_end().to_string()
+        }
+        MessageContent::Thinking { .. } => String::new(),
+        MessageContent::Mixed { parts } => parts
+            .iter()
+            .map(plain_text)
+            .filter(|part| !part.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    }
+}
+
 fn export_markdown(
     path: &Path,
-    conversations: &[(quaid_core::providers::Account, quaid_core::providers::Conversation, Vec<quaid_core::providers::Message>)],
+    conversations: &[(
+        quaid_core::providers::Account,
+        quaid_core::providers::Conversation,
+        Vec<quaid_core::providers::Message>,
+    )],
 ) -> anyhow::Result<()> {
     // Create directory if exporting multiple files
     if conversations.len() > 1 {
@@ -126,74 +302,558 @@ fn export_single_markdown(
 
     // Messages
     for msg in messages {
-        let role = match msg.role {
-            quaid_core::providers::Role::User => "You",
-            quaid_core::providers::Role::Assistant => "Assistant",
-            quaid_core::providers::Role::System => "System",
-            quaid_core::providers::Role::Tool => "Tool",
-        };
+        content.push_str(&message_markdown(msg));
+    }
+
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// One message as a `## Role` section, or `## Speaker` for transcripts, as
+/// written by the markdown export
+pub fn message_markdown(msg: &quaid_core::providers::Message) -> String {
+    let mut content = String::new();
+    let role = match msg.role {
+        quaid_core::providers::Role::User => "You",
+        quaid_core::providers::Role::Assistant => "Assistant",
+        quaid_core::providers::Role::System => "System",
+        quaid_core::providers::Role::Tool => "Tool",
+    };
 
-        content.push_str(&format!("## {}\n\n", role));
+    let heading = msg.speaker.as_deref().unwrap_or(role);
+    content.push_str(&format!("## {}\n\n", heading));
 
-        match &msg.content {
-            quaid_core::providers::MessageContent::Text { text } => {
-                content.push_str(text);
-                content.push_str("\n\n");
+    match &msg.content {
+        quaid_core::providers::MessageContent::Text { text } => {
+            content.push_str(text);
+            content.push_str("\n\n");
+        }
+        quaid_core::providers::MessageContent::Code { language, code } => {
+            content.push_str(&format!("```{}\n{}\n```\n\n", language, code));
+        }
+        quaid_core::providers::MessageContent::Image { url, alt } => {
+            let alt_text = alt.as_deref().unwrap_or("image");
+            content.push_str(&format!("![{}]({})\n\n", alt_text, url));
+        }
+        quaid_core::providers::MessageContent::Audio { transcript, .. } => {
+            if let Some(t) = transcript {
+                content.push_str(&format!("*[Audio transcript]* {}\n\n", t));
+            } else {
+                content.push_str("*[Audio]*\n\n");
             }
-            quaid_core::providers::MessageContent::Code { language, code } => {
-                content.push_str(&format!("```{}\n{}\n```\n\n", language, code));
+        }
+        quaid_core::providers::MessageContent::Document { title, mime, text } => {
+            content.push_str(&document_markdown(title, mime, text));
+            content.push('\n');
+        }
+        quaid_core::providers::MessageContent::Thinking { text } => {
+            content.push_str(&thinking_markdown(text));
+            content.push('\n');
+        }
+        quaid_core::providers::MessageContent::Mixed { parts } => {
+            for part in parts {
+                match part {
+                    quaid_core::providers::MessageContent::Text { text } => {
+                        content.push_str(text);
+                        content.push('\n');
+                    }
+                    quaid_core::providers::MessageContent::Image { url, alt } => {
+                        let alt_text = alt.as_deref().unwrap_or("image");
+                        content.push_str(&format!("![{}]({})\n", alt_text, url));
+                    }
+                    quaid_core::providers::MessageContent::Document { title, mime, text } => {
+                        content.push('\n');
+                        content.push_str(&document_markdown(title, mime, text));
+                    }
+                    quaid_core::providers::MessageContent::Thinking { text } => {
+                        content.push_str(&thinking_markdown(text));
+                        content.push('\n');
+                    }
+                    _ => {}
+                }
             }
-            quaid_core::providers::MessageContent::Image { url, alt } => {
-                let alt_text = alt.as_deref().unwrap_or("image");
-                content.push_str(&format!("![{}]({})\n\n", alt_text, url));
+            content.push('\n');
+        }
+    }
+    content
+}
+
+/// A document as a fenced block under its title
+///
+/// The fence is longer than any run of backticks in the text, so documents
+/// that contain fences of their own stay in one block.
+fn document_markdown(title: &str, mime: &str, text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!(
+        "### {}\n\n{}{}\n{}\n{}\n",
+        title,
+        fence,
+        quaid_core::providers::document_language(mime),
+        text.trim_end(),
+        fence
+    )
+}
+
+/// The model's reasoning as a blockquote, so it reads apart from the answer
+fn thinking_markdown(text: &str) -> String {
+    let mut quoted = String::from("> **Thinking**\n>\n");
+    for line in text.trim_end().lines() {
+        if line.is_empty() {
+            quoted.push_str(">\n");
+        } else {
+            quoted.push_str(&format!("> {}\n", line));
+        }
+    }
+    quoted
+}
+
+/// Write one file per conversation under `dir/{provider}/{date}-{slug}.{ext}`
+///
+/// Conversations are written oldest first, so names that collide get the
+/// same numeric suffix on every export.
+fn export_split(
+    dir: &Path,
+    format: &str,
+    conversations: &mut [(Account, Conversation, Vec<Message>)],
+) -> anyhow::Result<usize> {
+    let extension = split_extension(format)?;
+    sort_oldest_first(conversations);
+
+    let mut taken = HashSet::new();
+    for (account, conv, messages) in conversations.iter() {
+        let file_path = split_path(dir, account, conv, extension, &mut taken);
+        write_split_file(&file_path, extension, account, conv, messages)?;
+    }
+    Ok(taken.len())
+}
+
+/// What an incremental export did
+#[derive(Debug, Default, PartialEq, Eq)]
+struct IncrementalSummary {
+    written: usize,
+    unchanged: usize,
+    removed: usize,
+}
+
+/// Per-conversation files from the last incremental export into a directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// File extension the export was written with
+    extension: String,
+    conversations: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    /// [`content_hash`] of the conversation when it was written
+    hash: String,
+    /// Relative to the export directory
+    path: PathBuf,
+}
+
+/// Like [`export_split`], but only rewrite conversations whose content hash
+/// changed since the last run, and remove files of deleted conversations
+///
+/// Unchanged conversations keep their file names. Conversations left out by
+/// `--provider` or a date filter keep their files and manifest entries.
+fn export_incremental(
+    dir: &Path,
+    format: &str,
+    conversations: &mut [(Account, Conversation, Vec<Message>)],
+    store: &Store,
+) -> anyhow::Result<IncrementalSummary> {
+    let extension = split_extension(format)?;
+    sort_oldest_first(conversations);
+
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let mut previous: Manifest = match std::fs::read_to_string(&manifest_path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| {
+            anyhow::anyhow!(
+                "Can't read {}: {}. Delete it to export everything again.",
+                manifest_path.display(),
+                e
+            )
+        })?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Manifest::default(),
+        Err(e) => return Err(e.into()),
+    };
+
+    // Files that may have to go, unless a conversation is written to one
+    let mut stale: Vec<PathBuf> = Vec::new();
+    if previous.extension != extension {
+        stale.extend(previous.conversations.values().map(|e| e.path.clone()));
+        previous.conversations.clear();
+    }
+
+    let mut manifest = Manifest {
+        extension: extension.to_string(),
+        conversations: BTreeMap::new(),
+    };
+    let mut taken = HashSet::new();
+    let exported: HashSet<&str> = conversations
+        .iter()
+        .map(|(_, c, _)| c.id.as_str())
+        .collect();
+    for (id, entry) in &previous.conversations {
+        if exported.contains(id.as_str()) {
+            continue;
+        }
+        if store.get_conversation(id)?.is_some() {
+            taken.insert(dir.join(&entry.path));
+            manifest.conversations.insert(id.clone(), entry.clone());
+        } else {
+            stale.push(entry.path.clone());
+        }
+    }
+
+    // Unchanged files are claimed first so changed conversations can't take their names
+    let mut summary = IncrementalSummary::default();
+    let mut changed = Vec::new();
+    for (account, conv, messages) in conversations.iter() {
+        let hash = content_hash(conv, messages);
+        match previous.conversations.get(&conv.id) {
+            Some(entry) if entry.hash == hash && dir.join(&entry.path).exists() => {
+                taken.insert(dir.join(&entry.path));
+                manifest
+                    .conversations
+                    .insert(conv.id.clone(), entry.clone());
+                summary.unchanged += 1;
             }
-            quaid_core::providers::MessageContent::Audio { transcript, .. } => {
-                if let Some(t) = transcript {
-                    content.push_str(&format!("*[Audio transcript]* {}\n\n", t));
-                } else {
-                    content.push_str("*[Audio]*\n\n");
-                }
+            entry => {
+                stale.extend(entry.map(|e| e.path.clone()));
+                changed.push((account, conv, messages, hash));
             }
-            quaid_core::providers::MessageContent::Mixed { parts } => {
-                for part in parts {
-                    match part {
-                        quaid_core::providers::MessageContent::Text { text } => {
-                            content.push_str(text);
-                            content.push_str("\n");
-                        }
-                        quaid_core::providers::MessageContent::Image { url, alt } => {
-                            let alt_text = alt.as_deref().unwrap_or("image");
-                            content.push_str(&format!("![{}]({})\n", alt_text, url));
+        }
+    }
+
+    for (account, conv, messages, hash) in changed {
+        let file_path = split_path(dir, account, conv, extension, &mut taken);
+        write_split_file(&file_path, extension, account, conv, messages)?;
+        let path = file_path
+            .strip_prefix(dir)
+            .unwrap_or(&file_path)
+            .to_path_buf();
+        manifest
+            .conversations
+            .insert(conv.id.clone(), ManifestEntry { hash, path });
+        summary.written += 1;
+    }
+
+    for path in stale {
+        // Never follow a hand-edited manifest out of the export directory
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            continue;
+        }
+        let path = dir.join(path);
+        if !taken.contains(&path) && path.exists() {
+            std::fs::remove_file(&path)?;
+            summary.removed += 1;
+        }
+    }
+
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(summary)
+}
+
+fn split_extension(format: &str) -> anyhow::Result<&'static str> {
+    match format {
+        "markdown" | "md" => Ok("md"),
+        "json" => Ok("json"),
+        _ => anyhow::bail!("--split supports markdown and json, not {}", format),
+    }
+}
+
+fn sort_oldest_first(conversations: &mut [(Account, Conversation, Vec<Message>)]) {
+    conversations.sort_by(|(_, a, _), (_, b, _)| {
+        a.created_at
+            .cmp(&b.created_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// `dir/{provider}/{date}-{slug}.{ext}`, unless a path in `taken` has it
+fn split_path(
+    dir: &Path,
+    account: &Account,
+    conv: &Conversation,
+    extension: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> PathBuf {
+    let provider_dir = dir.join(sanitize_filename(&account.provider.0));
+    let stem = format!(
+        "{}-{}",
+        conv.created_at.format("%Y-%m-%d"),
+        slugify(&conv.title)
+    );
+    unique_path(&provider_dir, &stem, extension, taken)
+}
+
+fn write_split_file(
+    path: &Path,
+    extension: &str,
+    account: &Account,
+    conv: &Conversation,
+    messages: &[Message],
+) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if extension == "md" {
+        export_single_markdown(path, conv, messages)
+    } else {
+        let json = conversation_json(account, conv, messages);
+        std::fs::write(path, serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+}
+
+/// `dir/stem.ext`, or `dir/stem-2.ext` and so on if an earlier conversation took it
+fn unique_path(dir: &Path, stem: &str, extension: &str, taken: &mut HashSet<PathBuf>) -> PathBuf {
+    let mut path = dir.join(format!("{}.{}", stem, extension));
+    let mut n = 2;
+    while !taken.insert(path.clone()) {
+        path = dir.join(format!("{}-{}.{}", stem, n, extension));
+        n += 1;
+    }
+    path
+}
+
+/// Write an Obsidian vault folder: one note per conversation under
+/// `dir/{provider}/{date}-{slug}.md`, with downloaded attachments copied
+/// into `dir/attachments/` and embedded where they were sent
+///
+/// Notes carry provider, model, date and project tags in their front matter.
+/// When a message was answered more than once, the branches link to each
+/// other with block references.
+fn export_obsidian(
+    dir: &Path,
+    conversations: &mut [(Account, Conversation, Vec<Message>)],
+    store: &Store,
+) -> anyhow::Result<()> {
+    sort_oldest_first(conversations);
+    let attachments_dir = dir.join(OBSIDIAN_ATTACHMENTS_DIR);
+
+    let mut taken = HashSet::new();
+    // Attachments sharing one downloaded file are copied once
+    let mut copies: HashMap<String, String> = HashMap::new();
+    for (account, conv, messages) in conversations.iter() {
+        let mut embeds: HashMap<String, Vec<String>> = HashMap::new();
+        for file in store.conversation_attachment_files(&conv.id)? {
+            let source = file.local_path.filter(|p| Path::new(p).exists());
+            let embed = match source {
+                Some(source) => {
+                    let name = match copies.get(&source) {
+                        Some(name) => name.clone(),
+                        None => {
+                            let target = attachment_path(
+                                &attachments_dir,
+                                &file.attachment.filename,
+                                &mut taken,
+                            );
+                            std::fs::create_dir_all(&attachments_dir)?;
+                            std::fs::copy(&source, &target)?;
+                            let name = target
+                                .file_name()
+                                .map(|n| n.to_string_lossy().into_owned())
+                                .unwrap_or_default();
+                            copies.insert(source, name.clone());
+                            name
                         }
-                        _ => {}
-                    }
+                    };
+                    format!("![[{}]]", name)
                 }
+                None => format!("*Attachment not downloaded: {}*", file.attachment.filename),
+            };
+            embeds
+                .entry(file.attachment.message_id)
+                .or_default()
+                .push(embed);
+        }
+
+        let file_path = split_path(dir, account, conv, "md", &mut taken);
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file_path, obsidian_note(account, conv, messages, &embeds))?;
+    }
+    Ok(())
+}
+
+fn obsidian_note(
+    account: &Account,
+    conv: &Conversation,
+    messages: &[Message],
+    embeds: &HashMap<String, Vec<String>>,
+) -> String {
+    let mut content = String::new();
+
+    content.push_str("---\n");
+    content.push_str(&format!("title: {}\n", yaml_string(&conv.title)));
+    content.push_str(&format!("aliases:\n  - {}\n", yaml_string(&conv.title)));
+    content.push_str(&format!("provider: {}\n", yaml_string(&account.provider.0)));
+    if let Some(model) = &conv.model {
+        content.push_str(&format!("model: {}\n", yaml_string(model)));
+    }
+    content.push_str(&format!("date: {}\n", conv.created_at.format("%Y-%m-%d")));
+    content.push_str(&format!("created: {}\n", conv.created_at.to_rfc3339()));
+    content.push_str(&format!("updated: {}\n", conv.updated_at.to_rfc3339()));
+    content.push_str("tags:\n");
+    content.push_str(&format!("  - provider/{}\n", slugify(&account.provider.0)));
+    if let Some(model) = &conv.model {
+        content.push_str(&format!("  - model/{}\n", slugify(model)));
+    }
+    if let Some(project) = &conv.project_name {
+        content.push_str(&format!("  - project/{}\n", slugify(project)));
+    }
+    content.push_str("---\n\n");
+
+    content.push_str(&format!("# {}\n\n", conv.title));
+
+    // Replies to each message; more than one means the conversation branched there
+    let ids: HashSet<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+    let mut replies: HashMap<&str, Vec<&str>> = HashMap::new();
+    for msg in messages {
+        if let Some(parent) = msg.parent_id.as_deref().filter(|p| ids.contains(p)) {
+            replies.entry(parent).or_default().push(msg.id.as_str());
+        }
+    }
+    let branches = |id: &str| replies.get(id).filter(|r| r.len() > 1);
+
+    for msg in messages {
+        let section = message_markdown(msg);
+        let (heading, body) = section.split_once("\n\n").unwrap_or((&section, ""));
+        content.push_str(heading);
+        content.push_str("\n\n");
+
+        // A branch carries its block id on the line linking back to where it forked
+        let mut has_block = false;
+        if let Some(parent) = msg.parent_id.as_deref() {
+            if let Some(siblings) = branches(parent) {
+                let n = siblings.iter().position(|s| *s == msg.id).unwrap_or(0) + 1;
+                content.push_str(&format!(
+                    "*Branch {} of {}, replying to [[#^{}|this message]]* ^{}\n\n",
+                    n,
+                    siblings.len(),
+                    block_id(parent),
+                    block_id(&msg.id)
+                ));
+                has_block = true;
+            }
+        }
+
+        content.push_str(body);
+        if let Some(lines) = embeds.get(&msg.id) {
+            for line in lines {
+                content.push_str(line);
                 content.push('\n');
             }
+            content.push('\n');
+        }
+
+        if let Some(children) = branches(&msg.id) {
+            let links: Vec<String> = children
+                .iter()
+                .enumerate()
+                .map(|(i, child)| format!("[[#^{}|{}]]", block_id(child), i + 1))
+                .collect();
+            content.push_str(&format!("*Branches:* {}\n\n", links.join(" · ")));
+            if !has_block {
+                content.push_str(&format!("^{}\n\n", block_id(&msg.id)));
+            }
         }
     }
+    content
+}
 
-    std::fs::write(path, content)?;
-    Ok(())
+/// A message id as an Obsidian block id, which only allows letters, digits and dashes
+fn block_id(message_id: &str) -> String {
+    let id: String = message_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("msg-{}", id)
+}
+
+/// A double-quoted YAML scalar
+fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `dir/filename`, or `dir/stem-2.ext` and so on if another attachment took it
+///
+/// Characters that break a `![[...]]` embed are replaced.
+fn attachment_path(dir: &Path, filename: &str, taken: &mut HashSet<PathBuf>) -> PathBuf {
+    let name = sanitize_filename(filename).replace(['[', ']', '#', '^', '|'], "_");
+    let name = Path::new(&name);
+    let stem = name
+        .file_stem()
+        .map_or("attachment".into(), |s| s.to_string_lossy().into_owned());
+    let extension = name
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut path = dir.join(format!("{}{}", stem, extension));
+    let mut n = 2;
+    while !taken.insert(path.clone()) {
+        path = dir.join(format!("{}-{}{}", stem, n, extension));
+        n += 1;
+    }
+    path
+}
+
+/// Lowercase a title to letters and digits joined by single dashes
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.extend(word.chars().flat_map(char::to_lowercase));
+    }
+
+    if slug.chars().count() > MAX_SLUG_LEN {
+        slug = slug.chars().take(MAX_SLUG_LEN).collect();
+        slug.truncate(slug.trim_end_matches('-').len());
+    }
+    if slug.is_empty() {
+        slug.push_str("untitled");
+    }
+    slug
+}
+
+fn conversation_json(
+    account: &quaid_core::providers::Account,
+    conv: &quaid_core::providers::Conversation,
+    messages: &[quaid_core::providers::Message],
+) -> serde_json::Value {
+    serde_json::json!({
+        "account": {
+            "id": account.id,
+            "provider": account.provider.0,
+            "email": account.email,
+        },
+        "conversation": conv,
+        "messages": messages,
+    })
 }
 
 fn export_json(
     path: &Path,
-    conversations: &[(quaid_core::providers::Account, quaid_core::providers::Conversation, Vec<quaid_core::providers::Message>)],
+    conversations: &[(
+        quaid_core::providers::Account,
+        quaid_core::providers::Conversation,
+        Vec<quaid_core::providers::Message>,
+    )],
 ) -> anyhow::Result<()> {
     let data: Vec<_> = conversations
         .iter()
-        .map(|(account, conv, messages)| {
-            serde_json::json!({
-                "account": {
-                    "id": account.id,
-                    "provider": account.provider.0,
-                    "email": account.email,
-                },
-                "conversation": conv,
-                "messages": messages,
-            })
-        })
+        .map(|(account, conv, messages)| conversation_json(account, conv, messages))
         .collect();
 
     let json = serde_json::to_string_pretty(&data)?;
@@ -202,6 +862,63 @@ fn export_json(
     Ok(())
 }
 
+/// One row per message, with the text extracted the same way it is indexed for search
+fn export_csv(
+    path: &Path,
+    conversations: &[(
+        quaid_core::providers::Account,
+        quaid_core::providers::Conversation,
+        Vec<quaid_core::providers::Message>,
+    )],
+) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    write_csv(BufWriter::new(file), conversations)
+}
+
+fn write_csv<W: Write>(
+    writer: W,
+    conversations: &[(
+        quaid_core::providers::Account,
+        quaid_core::providers::Conversation,
+        Vec<quaid_core::providers::Message>,
+    )],
+) -> anyhow::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record([
+        "conversation_id",
+        "provider",
+        "conversation_title",
+        "role",
+        "speaker",
+        "created_at",
+        "content_type",
+        "model",
+        "text",
+    ])?;
+
+    for (account, conv, messages) in conversations {
+        for msg in messages {
+            let role = format!("{:?}", msg.role).to_lowercase();
+            let created_at = msg.created_at.map(|dt| dt.to_rfc3339()).unwrap_or_default();
+            let model = msg.model.as_deref().or(conv.model.as_deref()).unwrap_or("");
+            csv.write_record([
+                conv.id.as_str(),
+                account.provider.0.as_str(),
+                conv.title.as_str(),
+                role.as_str(),
+                msg.speaker.as_deref().unwrap_or(""),
+                created_at.as_str(),
+                content_type_name(&msg.content),
+                model,
+                extract_text_content(&msg.content).as_str(),
+            ])?;
+        }
+    }
+
+    csv.flush()?;
+    Ok(())
+}
+
 fn sanitize_filename(name: &str) -> String {
     name.chars()
         .map(|c| match c {
@@ -211,3 +928,544 @@ fn sanitize_filename(name: &str) -> String {
         .take(100)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use quaid_core::providers::ProviderId;
+
+    #[test]
+    fn test_csv_escapes_commas_quotes_and_newlines() {
+        let account = Account {
+            id: "user-1".to_string(),
+            provider: ProviderId::chatgpt(),
+            email: "me@example.com".to_string(),
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        };
+        let conv = Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "chatgpt".to_string(),
+            title: "Lists, \"quotes\" and more".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            model: Some("gpt-4".to_string()),
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        let message = Message {
+            id: "msg-1".to_string(),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: "first, second\nsaid \"hi\"".to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &[(account, conv, vec![message])]).unwrap();
+
+        let mut reader = csv::Reader::from_reader(out.as_slice());
+        let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(&rows[0][2], "Lists, \"quotes\" and more");
+        assert_eq!(&rows[0][3], "user");
+        assert_eq!(&rows[0][4], "");
+        assert_eq!(&rows[0][6], "text");
+        assert_eq!(&rows[0][7], "gpt-4");
+        assert_eq!(&rows[0][8], "first, second\nsaid \"hi\"");
+    }
+
+    #[test]
+    fn test_since_and_until_pick_conversations_by_creation_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let parquet =
+            quaid_core::storage::parquet::ParquetStore::new(ParquetStorageConfig::new(dir.path()));
+        for (id, provider, day) in [
+            ("march", "chatgpt", 3),
+            ("april", "chatgpt", 4),
+            ("april-claude", "claude", 4),
+            ("may", "chatgpt", 5),
+        ] {
+            let created_at = Utc.with_ymd_and_hms(2024, day, 15, 12, 0, 0).unwrap();
+            let conv = Conversation {
+                id: id.to_string(),
+                provider_id: provider.to_string(),
+                title: id.to_string(),
+                created_at,
+                updated_at: created_at,
+                model: None,
+                project_id: None,
+                project_name: None,
+                is_archived: false,
+            };
+            let message = Message {
+                id: format!("{}-1", id),
+                conversation_id: id.to_string(),
+                parent_id: None,
+                role: Role::User,
+                content: MessageContent::Text {
+                    text: "notes".to_string(),
+                },
+                created_at: Some(created_at),
+                model: None,
+                speaker: None,
+            };
+            parquet
+                .write_conversation("user-1", &conv, &[message])
+                .unwrap();
+        }
+
+        let since = parse_date("2024-04-01", false).ok();
+        let until = parse_date("2024-04-30", true).ok();
+        assert!(created_between(dir.path(), None, None, None)
+            .unwrap()
+            .is_none());
+        let ids = created_between(dir.path(), None, since, until)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ids,
+            HashSet::from(["april".to_string(), "april-claude".to_string()])
+        );
+        let ids = created_between(dir.path(), Some("chatgpt"), since, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(ids, HashSet::from(["april".to_string(), "may".to_string()]));
+    }
+
+    #[test]
+    fn test_documents_render_as_fenced_blocks_under_their_title() {
+        let message = Message {
+            id: "msg-1".to_string(),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role: Role::Assistant,
+            content: MessageContent::Mixed {
+                parts: vec![
+                    MessageContent::Text {
+                        text: "Here's the README:".to_string(),
+                    },
+                    MessageContent::Document {
+                        title: "README".to_string(),
+                        mime: "text/markdown".to_string(),
+                        text: "Run:\n\n```sh\nmake\n```\n".to_string(),
+                    },
+                ],
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+
+        assert_eq!(
+            message_markdown(&message),
+            "## Assistant\n\nHere's the README:\n\n### README\n\n````markdown\nRun:\n\n```sh\nmake\n```\n````\n\n"
+        );
+    }
+
+    #[test]
+    fn test_thinking_renders_as_a_blockquote_before_the_answer() {
+        let message = Message {
+            id: "msg-1".to_string(),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role: Role::Assistant,
+            content: MessageContent::Mixed {
+                parts: vec![
+                    MessageContent::Thinking {
+                        text: "They want a number.\n\nIt's 4.".to_string(),
+                    },
+                    MessageContent::Text {
+                        text: "4".to_string(),
+                    },
+                ],
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+
+        assert_eq!(
+            message_markdown(&message),
+            "## Assistant\n\n> **Thinking**\n>\n> They want a number.\n>\n> It's 4.\n\n4\n\n"
+        );
+    }
+
+    #[test]
+    fn test_transcript_lines_are_headed_by_their_speaker() {
+        let message = Message {
+            id: "meeting-1-0".to_string(),
+            conversation_id: "meeting-1".to_string(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: "Let's start with the roadmap".to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: Some("Alice".to_string()),
+        };
+
+        assert_eq!(
+            message_markdown(&message),
+            "## Alice\n\nLet's start with the roadmap\n\n"
+        );
+    }
+
+    #[test]
+    fn test_openai_jsonl_flattens_messages_one_conversation_per_line() {
+        let (account, conv, mut messages) = conversation("conv-1", 1, "Sort this:\n\n[3, 1, 2]");
+        let reply = |role: Role, content: MessageContent| Message {
+            id: format!("msg-{}", role.as_str()),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role,
+            content,
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+        let more = vec![
+            reply(
+                Role::System,
+                MessageContent::Text {
+                    text: "Be brief.".to_string(),
+                },
+            ),
+            reply(
+                Role::Assistant,
+                MessageContent::Mixed {
+                    parts: vec![
+                        MessageContent::Thinking {
+                            text: "Easy.".to_string(),
+                        },
+                        MessageContent::Code {
+                            language: "python".to_string(),
+                            code: "sorted([3, 1, 2])".to_string(),
+                        },
+                        MessageContent::Image {
+                            url: "file-1".to_string(),
+                            alt: None,
+                        },
+                    ],
+                },
+            ),
+            reply(
+                Role::Tool,
+                MessageContent::Code {
+                    language: "output".to_string(),
+                    code: "[1, 2, 3]".to_string(),
+                },
+            ),
+        ];
+        messages.extend(more);
+        // Nothing to say, so no example
+        let blank = conversation("conv-2", 2, "  ");
+        let conversations = vec![(account, conv, messages), blank];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("train.jsonl");
+        assert_eq!(
+            export_openai_jsonl(&path, &conversations, false).unwrap(),
+            1
+        );
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), 1);
+        let example: serde_json::Value = serde_json::from_str(written.trim_end()).unwrap();
+        assert_eq!(
+            example,
+            serde_json::json!({"messages": [
+                {"role": "user", "content": "Sort this:\n\n[3, 1, 2]"},
+                {"role": "assistant", "content": "```python\nsorted([3, 1, 2])\n```\n\n[image]"},
+            ]})
+        );
+
+        // System and tool messages only on request
+        export_openai_jsonl(&path, &conversations, true).unwrap();
+        let example: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim_end()).unwrap();
+        let roles: Vec<_> = example["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["user", "system", "assistant", "tool"]);
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Deploying to k8s: a/b testing"),
+            "deploying-to-k8s-a-b-testing"
+        );
+        assert_eq!(slugify("  Lots   of\tspace\n"), "lots-of-space");
+        assert_eq!(slugify("Café über"), "café-über");
+        assert_eq!(slugify(""), "untitled");
+        assert_eq!(slugify("../../"), "untitled");
+
+        let long = slugify(&"word ".repeat(40));
+        assert!(long.chars().count() <= MAX_SLUG_LEN);
+        assert!(!long.ends_with('-'));
+    }
+
+    fn conversation(id: &str, day: u32, text: &str) -> (Account, Conversation, Vec<Message>) {
+        let account = Account {
+            id: "user-1".to_string(),
+            provider: ProviderId::claude(),
+            email: "me@example.com".to_string(),
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        };
+        let created_at = chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 5, day, 9, 0, 0).unwrap();
+        let conv = Conversation {
+            id: id.to_string(),
+            provider_id: "claude".to_string(),
+            title: format!("Conversation {}", id),
+            created_at,
+            updated_at: created_at,
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        let message = Message {
+            id: format!("{}-msg", id),
+            conversation_id: id.to_string(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+        (account, conv, vec![message])
+    }
+
+    fn read_manifest(dir: &Path) -> Manifest {
+        serde_json::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_incremental_export_rewrites_only_changed_conversations() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::in_memory().unwrap();
+        let mut conversations = vec![
+            conversation("one", 1, "Original text"),
+            conversation("two", 2, "Original text"),
+            // Never saved, so it looks deleted once it's left out
+            conversation("three", 3, "Original text"),
+        ];
+        store.save_account(&conversations[0].0).unwrap();
+        for (_, conv, messages) in &conversations[..2] {
+            store
+                .save_conversation_with_messages("user-1", conv, messages)
+                .unwrap();
+        }
+
+        let first = export_incremental(dir.path(), "markdown", &mut conversations, &store).unwrap();
+        assert_eq!(
+            first,
+            IncrementalSummary {
+                written: 3,
+                unchanged: 0,
+                removed: 0
+            }
+        );
+
+        // Mark every file, so rewritten ones can be told apart
+        let paths: BTreeMap<String, PathBuf> = read_manifest(dir.path())
+            .conversations
+            .into_iter()
+            .map(|(id, entry)| (id, dir.path().join(entry.path)))
+            .collect();
+        for path in paths.values() {
+            let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+            file.write_all(b"<!-- kept -->").unwrap();
+        }
+
+        conversations[1].2[0].content = MessageContent::Text {
+            text: "Edited text".to_string(),
+        };
+        let second =
+            export_incremental(dir.path(), "markdown", &mut conversations, &store).unwrap();
+        assert_eq!(
+            second,
+            IncrementalSummary {
+                written: 1,
+                unchanged: 2,
+                removed: 0
+            }
+        );
+        let two = std::fs::read_to_string(&paths["two"]).unwrap();
+        assert!(two.contains("Edited text") && !two.contains("kept"));
+        for id in ["one", "three"] {
+            assert!(std::fs::read_to_string(&paths[id])
+                .unwrap()
+                .contains("kept"));
+        }
+        let (_, conv, messages) = &conversations[1];
+        assert_eq!(
+            read_manifest(dir.path()).conversations["two"].hash,
+            content_hash(conv, messages)
+        );
+
+        conversations.pop();
+        let third = export_incremental(dir.path(), "markdown", &mut conversations, &store).unwrap();
+        assert_eq!(
+            third,
+            IncrementalSummary {
+                written: 0,
+                unchanged: 2,
+                removed: 1
+            }
+        );
+        assert!(!paths["three"].exists());
+        assert!(!read_manifest(dir.path())
+            .conversations
+            .contains_key("three"));
+    }
+
+    #[test]
+    fn test_obsidian_note_tags_branches_and_attachments() {
+        let dir = tempfile::tempdir().unwrap();
+        let downloads = tempfile::tempdir().unwrap();
+        let store = Store::in_memory().unwrap();
+        let (account, mut conv, _) = conversation("conv-1", 1, "");
+        conv.title = "Rust \"lifetimes\"".to_string();
+        conv.model = Some("claude-3-opus".to_string());
+        conv.project_name = Some("Side Project".to_string());
+        let message = |id: &str, parent: Option<&str>, role: Role, text: &str| Message {
+            id: id.to_string(),
+            conversation_id: conv.id.clone(),
+            parent_id: parent.map(str::to_string),
+            role,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+        let messages = vec![
+            message("m1", None, Role::User, "Why won't this borrow?"),
+            message("m2", Some("m1"), Role::Assistant, "First answer"),
+            message("m3", Some("m1"), Role::Assistant, "Second answer"),
+            message("m4", Some("m3"), Role::User, "Thanks"),
+        ];
+        store.save_account(&account).unwrap();
+        store
+            .save_conversation_with_messages(&account.id, &conv, &messages)
+            .unwrap();
+
+        let source = downloads.path().join("spec.md");
+        std::fs::write(&source, "# Spec").unwrap();
+        for (id, filename) in [
+            ("att-1", "spec [v2].md"),
+            ("att-2", "spec copy.md"),
+            ("att-3", "gone.png"),
+        ] {
+            store
+                .save_attachment(&quaid_core::providers::Attachment {
+                    id: id.to_string(),
+                    message_id: "m1".to_string(),
+                    filename: filename.to_string(),
+                    mime_type: "text/markdown".to_string(),
+                    size_bytes: 6,
+                    download_url: id.to_string(),
+                    width: None,
+                    height: None,
+                    sha256: None,
+                })
+                .unwrap();
+        }
+        // The second attachment is a duplicate sharing the first one's file
+        for id in ["att-1", "att-2"] {
+            store
+                .mark_attachment_downloaded(id, &source.to_string_lossy())
+                .unwrap();
+        }
+
+        let mut conversations = vec![(account, conv, messages)];
+        export_obsidian(dir.path(), &mut conversations, &store).unwrap();
+
+        let note = std::fs::read_to_string(dir.path().join("claude/2024-05-01-rust-lifetimes.md"))
+            .unwrap();
+        assert!(
+            note.starts_with("---\ntitle: \"Rust \\\"lifetimes\\\"\"\n"),
+            "{}",
+            note
+        );
+        for tag in [
+            "provider/claude",
+            "model/claude-3-opus",
+            "project/side-project",
+        ] {
+            assert!(note.contains(&format!("  - {}\n", tag)), "{}", note);
+        }
+        assert!(note.contains("date: 2024-05-01\n"));
+
+        // Both answers to m1 link back to it, and m1 links to both
+        assert!(note.contains("*Branches:* [[#^msg-m2|1]] · [[#^msg-m3|2]]\n\n^msg-m1\n"));
+        assert!(note.contains("*Branch 2 of 2, replying to [[#^msg-m1|this message]]* ^msg-m3\n"));
+        assert!(!note.contains("^msg-m4"));
+
+        assert!(
+            note.contains("![[spec _v2_.md]]\n![[spec _v2_.md]]\n"),
+            "{}",
+            note
+        );
+        assert!(note.contains("*Attachment not downloaded: gone.png*"));
+        let copies: Vec<_> = std::fs::read_dir(dir.path().join(OBSIDIAN_ATTACHMENTS_DIR))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(copies, vec!["spec _v2_.md"]);
+    }
+
+    #[test]
+    fn test_attachment_path_keeps_names_embeddable() {
+        let dir = Path::new("/vault/attachments");
+        let mut taken = HashSet::new();
+        assert_eq!(
+            attachment_path(dir, "notes#1|draft.txt", &mut taken),
+            dir.join("notes_1_draft.txt")
+        );
+        assert_eq!(
+            attachment_path(dir, "notes#1|draft.txt", &mut taken),
+            dir.join("notes_1_draft-2.txt")
+        );
+        assert_eq!(
+            attachment_path(dir, "..", &mut taken),
+            dir.join("attachment")
+        );
+        assert_eq!(
+            attachment_path(dir, "Makefile", &mut taken),
+            dir.join("Makefile")
+        );
+    }
+
+    #[test]
+    fn test_unique_path_adds_numeric_suffix() {
+        let dir = Path::new("/export/claude");
+        let mut taken = HashSet::new();
+        let paths: Vec<_> = (0..3)
+            .map(|_| unique_path(dir, "2024-05-01-untitled", "md", &mut taken))
+            .collect();
+        assert_eq!(paths[0], dir.join("2024-05-01-untitled.md"));
+        assert_eq!(paths[1], dir.join("2024-05-01-untitled-2.md"));
+        assert_eq!(paths[2], dir.join("2024-05-01-untitled-3.md"));
+    }
+}
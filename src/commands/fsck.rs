@@ -0,0 +1,35 @@
+use quaid_core::Store;
+
+pub fn run(store: &Store, sample: usize, repair: bool, vacuum: bool) -> anyhow::Result<()> {
+    let check = store.check_fts(sample)?;
+    println!("Search index: checked {} entries", check.checked);
+
+    if check.is_ok() {
+        println!("  OK");
+    } else {
+        println!(
+            "  {} entries don't match their message:",
+            check.mismatched.len()
+        );
+        for id in check.mismatched.iter().take(10) {
+            println!("    {}", id);
+        }
+        if check.mismatched.len() > 10 {
+            println!("    ... and {} more", check.mismatched.len() - 10);
+        }
+
+        if repair {
+            let indexed = store.rebuild_fts()?;
+            println!("  Rebuilt the search index ({} messages)", indexed);
+        } else {
+            println!("  Run `quaid fsck --repair` to rebuild the search index.");
+        }
+    }
+
+    if vacuum {
+        store.vacuum()?;
+        println!("Vacuumed the database");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use quaid_core::grep::{self, Matcher};
+use quaid_core::storage::extract_text_content;
+use quaid_core::storage::parquet::ParquetStore;
+use quaid_core::storage::{ParquetStorageConfig, ScannedMessage};
+use quaid_core::Store;
+use std::io::IsTerminal;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// Options for `quaid grep`, as given on the command line
+pub struct GrepArgs<'a> {
+    pub pattern: &'a str,
+    pub regex: bool,
+    pub ignore_case: bool,
+    pub provider: Option<&'a str>,
+    pub context: usize,
+    pub limit: usize,
+    pub count_only: bool,
+    /// `parquet` or `sqlite`
+    pub engine: &'a str,
+}
+
+pub fn run(args: GrepArgs<'_>, store: &Store, data_dir: &Path) -> anyhow::Result<()> {
+    let matcher = Matcher::new(args.pattern, args.regex, args.ignore_case)
+        .map_err(|e| anyhow::anyhow!("Invalid pattern: {}", e))?;
+    let highlight = std::io::stdout()
+        .is_terminal()
+        .then_some(("\x1b[1;33m", "\x1b[0m"));
+
+    let mut report = Report::new(&matcher, &args, highlight);
+    match args.engine {
+        "parquet" => {
+            let parquet = ParquetStore::new(ParquetStorageConfig::new(data_dir));
+            let providers = match args.provider {
+                Some(provider) => vec![provider.to_string()],
+                None => parquet.providers()?,
+            };
+            if providers.is_empty() {
+                anyhow::bail!(
+                    "No Parquet files under {}. Try `--engine sqlite`.",
+                    data_dir.display()
+                );
+            }
+            for provider in providers {
+                if parquet
+                    .scan_messages(&provider, |m| report.message(m))?
+                    .is_break()
+                {
+                    break;
+                }
+            }
+        }
+        "sqlite" => {
+            // The report knows whether it stopped early
+            let _ = store.scan_messages(args.provider, |m| report.message(m))?;
+        }
+        other => anyhow::bail!("Unknown engine: {}. Supported: parquet, sqlite", other),
+    }
+    report.finish();
+    Ok(())
+}
+
+/// Prints matches as messages stream past, keeping only per-conversation counts
+struct Report<'a> {
+    matcher: &'a Matcher,
+    context: usize,
+    limit: usize,
+    count_only: bool,
+    highlight: Option<(&'a str, &'a str)>,
+    /// Conversation being scanned: id, provider, title and its matching lines
+    current: Option<(String, String, String, usize)>,
+    matches: usize,
+    conversations: usize,
+    limited: bool,
+}
+
+impl<'a> Report<'a> {
+    fn new(
+        matcher: &'a Matcher,
+        args: &GrepArgs<'_>,
+        highlight: Option<(&'a str, &'a str)>,
+    ) -> Self {
+        Self {
+            matcher,
+            context: args.context,
+            limit: args.limit,
+            count_only: args.count_only,
+            highlight,
+            current: None,
+            matches: 0,
+            conversations: 0,
+            limited: false,
+        }
+    }
+
+    fn message(&mut self, message: ScannedMessage) -> ControlFlow<()> {
+        if self
+            .current
+            .as_ref()
+            .is_none_or(|(id, ..)| *id != message.conversation_id)
+        {
+            self.end_conversation();
+            self.current = Some((
+                message.conversation_id.clone(),
+                message.provider_id.clone(),
+                message.conversation_title.clone(),
+                0,
+            ));
+        }
+
+        let text = extract_text_content(&message.content());
+        // --count-only counts everything; otherwise stop at --limit
+        let budget = if self.count_only {
+            usize::MAX
+        } else {
+            self.limit - self.matches
+        };
+        let hunks = grep::hunks(self.matcher, &text, self.context, budget);
+        let found: usize = hunks.iter().map(|h| h.matches()).sum();
+        if found == 0 {
+            return ControlFlow::Continue(());
+        }
+        if let Some((_, _, _, count)) = &mut self.current {
+            *count += found;
+        }
+        self.matches += found;
+
+        if !self.count_only {
+            let (conversation_id, provider_id, title, _) =
+                self.current.as_ref().expect("set above");
+            println!(
+                "[{}] {} ({}) {} [{}]",
+                provider_id,
+                title,
+                conversation_id,
+                message.message_id,
+                message.role.as_str()
+            );
+            for line in grep::render_hunks(&hunks, self.matcher, self.highlight).lines() {
+                println!("  {}", line);
+            }
+            println!();
+            if self.matches >= self.limit {
+                self.limited = true;
+                return ControlFlow::Break(());
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn end_conversation(&mut self) {
+        if let Some((id, provider_id, title, count)) = self.current.take() {
+            if count > 0 {
+                self.conversations += 1;
+                if self.count_only {
+                    println!("{:>6}  [{}] {} ({})", count, provider_id, title, id);
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) {
+        self.end_conversation();
+        if self.matches == 0 {
+            println!("No matches.");
+        } else if self.limited {
+            println!(
+                "Stopped after {} matching lines (raise --limit for more)",
+                self.matches
+            );
+        } else {
+            println!(
+                "{} matching lines in {} conversations",
+                self.matches, self.conversations
+            );
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use quaid_core::providers::Message;
+use quaid_core::storage::extract_text_content;
+use quaid_core::storage::revisions::diff_versions;
+use quaid_core::storage::RevisionDiff;
+use quaid_core::Store;
+
+/// Characters of a message shown in a diff line
+const SNIPPET_CHARS: usize = 60;
+
+/// List the revisions `quaid pull --keep-history` kept of a conversation, or
+/// diff two of them
+///
+/// Revisions are numbered from 1, oldest first; the stored version comes
+/// last. With a single revision in `diff`, it's compared with the stored one.
+pub fn run(id: &str, diff: &[usize], store: &Store) -> anyhow::Result<()> {
+    let conv = store
+        .get_conversation(id)?
+        .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", id))?;
+    let revisions = store.revisions(id)?;
+    let replaced: Vec<_> = revisions
+        .iter()
+        .map(|revision| revision.replaced_at.format("%Y-%m-%d %H:%M").to_string())
+        .collect();
+    let mut versions: Vec<(String, Vec<Message>)> = revisions
+        .into_iter()
+        .map(|revision| (revision.title, revision.messages))
+        .collect();
+    versions.push((conv.title.clone(), store.get_messages(id)?));
+
+    if diff.is_empty() {
+        println!("{} ({})", conv.title, conv.id);
+        if replaced.is_empty() {
+            println!("  No revisions kept. Pull with --keep-history to keep them.");
+            return Ok(());
+        }
+        for (number, (title, messages)) in versions.iter().enumerate() {
+            let when = replaced.get(number).map_or("current", String::as_str);
+            println!(
+                "  {:>3} | {:16} | {:>5} msgs | {}",
+                number + 1,
+                when,
+                messages.len(),
+                title
+            );
+        }
+        return Ok(());
+    }
+
+    let version = |number: usize| {
+        number
+            .checked_sub(1)
+            .and_then(|index| versions.get(index))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No revision {} of {}; it has 1 to {}",
+                    number,
+                    id,
+                    versions.len()
+                )
+            })
+    };
+    let (old_title, old_messages) = version(diff[0])?;
+    let (new_title, new_messages) = version(diff.get(1).copied().unwrap_or(versions.len()))?;
+    let changes = diff_versions(old_title, old_messages, new_title, new_messages);
+    print!("{}", render_diff(&changes, old_messages, new_messages));
+    Ok(())
+}
+
+/// One line per change: `~` edited, `+` added and `-` removed messages
+fn render_diff(diff: &RevisionDiff, old: &[Message], new: &[Message]) -> String {
+    if diff.is_empty() {
+        return "No changes\n".to_string();
+    }
+
+    let line = |mark: char, id: &str, messages: &[Message]| {
+        let text = messages
+            .iter()
+            .find(|m| m.id == id)
+            .map(|m| {
+                let text = extract_text_content(&m.content).replace('\n', " ");
+                format!("[{}] {}", m.role.as_str(), snippet(&text))
+            })
+            .unwrap_or_default();
+        format!("{} {} {}\n", mark, id, text)
+    };
+
+    let mut out = String::new();
+    if let Some((old_title, new_title)) = &diff.title {
+        out.push_str(&format!("Title: \"{}\" -> \"{}\"\n", old_title, new_title));
+    }
+    for id in &diff.edited {
+        out.push_str(&line('~', id, new));
+    }
+    for id in &diff.added {
+        out.push_str(&line('+', id, new));
+    }
+    for id in &diff.removed {
+        out.push_str(&line('-', id, old));
+    }
+    out
+}
+
+fn snippet(text: &str) -> String {
+    if text.chars().count() <= SNIPPET_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(SNIPPET_CHARS - 3).collect();
+    format!("{}...", cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use quaid_core::providers::{Account, Conversation, MessageContent, ProviderId, Role};
+
+    fn message(id: &str, role: Role, text: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            conversation_id: "conv-1".to_string(),
+            parent_id: None,
+            role,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        }
+    }
+
+    fn pull(store: &Store, title: &str, messages: &[Message]) {
+        let conv = Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "claude".to_string(),
+            title: title.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        store.record_revision(&conv, messages, 10).unwrap();
+        store
+            .save_conversation_with_messages("user-1", &conv, messages)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_diff_of_two_pulls() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "user@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+
+        pull(
+            &store,
+            "Trip",
+            &[
+                message("q", Role::User, "Where should I go?"),
+                message("a", Role::Assistant, "Lisbon"),
+            ],
+        );
+        pull(
+            &store,
+            "Trip to Portugal",
+            &[
+                message("q", Role::User, "Where should I go?"),
+                message("a", Role::Assistant, "Porto, then\nLisbon"),
+                message("q2", Role::User, "For how long?"),
+            ],
+        );
+
+        let revision = &store.revisions("conv-1").unwrap()[0];
+        let current = store.get_messages("conv-1").unwrap();
+        let diff = diff_versions(
+            &revision.title,
+            &revision.messages,
+            "Trip to Portugal",
+            &current,
+        );
+        assert_eq!(
+            render_diff(&diff, &revision.messages, &current),
+            "Title: \"Trip\" -> \"Trip to Portugal\"\n\
+             ~ a [assistant] Porto, then Lisbon\n\
+             + q2 [user] For how long?\n"
+        );
+        assert_eq!(
+            render_diff(&RevisionDiff::default(), &[], &[]),
+            "No changes\n"
+        );
+    }
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("short"), "short");
+        let long = "é".repeat(SNIPPET_CHARS + 1);
+        assert_eq!(snippet(&long).chars().count(), SNIPPET_CHARS);
+    }
+}
@@ -0,0 +1,108 @@
+use quaid_core::embeddings::{ChunkerConfig, EmbeddingBackend};
+use quaid_core::extract::{self, Extraction, SkipReason};
+use quaid_core::operations::{Phase, ProgressEvent, SyncProgress};
+use quaid_core::pipeline::EmbeddingBackfill;
+use quaid_core::storage::{EmbeddingsStore, ParquetStorageConfig};
+use quaid_core::{CredentialStore, Store};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Prints backfill progress on a single line
+struct ProgressLine;
+
+impl SyncProgress for ProgressLine {
+    fn report(&self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::Phase {
+                phase: Phase::Listing,
+            } => println!("Looking for conversations without embeddings..."),
+            ProgressEvent::Progress { done, total } => {
+                print!("\r[{}/{}] Embedding conversations...", done, total);
+                let _ = std::io::stdout().flush();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Build embeddings for every stored conversation that has none yet
+pub fn embed(
+    backend: &EmbeddingBackend,
+    chunker: ChunkerConfig,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let embedder = backend.load(data_dir, credentials)?;
+    let chunker = backend.chunker(chunker, data_dir)?;
+    let metadata = backend.metadata(embedder.as_ref());
+    let config = ParquetStorageConfig::new(data_dir);
+    let embeddings_store = EmbeddingsStore::with_dimension(config, metadata.dimension);
+    embeddings_store.ensure_metadata(&metadata)?;
+
+    let result = EmbeddingBackfill::new(embeddings_store, embedder)
+        .with_chunker(chunker)
+        .run(store, &ProgressLine)?;
+
+    println!(
+        "\nEmbedded {} conversations ({} chunks), {} already indexed",
+        result.conversations_embedded, result.chunks_embedded, result.conversations_skipped
+    );
+    if result.conversations_embedded > 0 {
+        println!("Run `quaid compact` to consolidate the new embeddings.");
+    }
+    Ok(())
+}
+
+/// Index the text of every downloaded attachment that hasn't been tried yet
+pub fn attachments(store: &Store) -> anyhow::Result<()> {
+    let files = store.attachments_without_text()?;
+    if files.is_empty() {
+        println!("No new attachments to index.");
+        return Ok(());
+    }
+
+    let total = files.len();
+    let mut indexed = 0;
+    let mut skipped: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for (i, file) in files.into_iter().enumerate() {
+        print!("\r[{}/{}] Extracting attachment text...", i + 1, total);
+        let _ = std::io::stdout().flush();
+
+        let attachment = &file.attachment;
+        let path = file.local_path.as_deref().unwrap_or_default();
+        let extraction =
+            match extract::extract(Path::new(path), &attachment.mime_type, &attachment.filename) {
+                Ok(extraction) => extraction,
+                Err(e) => {
+                    // Left unmarked, so the next run tries again
+                    eprintln!("\nWarning: {} ({}): {}", attachment.filename, path, e);
+                    continue;
+                }
+            };
+        match extraction {
+            Extraction::Text(text) => {
+                store.save_attachment_text(&attachment.id, Some(&text))?;
+                indexed += 1;
+            }
+            Extraction::Skipped(reason) => {
+                store.save_attachment_text(&attachment.id, None)?;
+                *skipped.entry(reason.as_str()).or_default() += 1;
+            }
+        }
+    }
+
+    println!("\nIndexed text from {} of {} attachments", indexed, total);
+    for (reason, count) in &skipped {
+        println!("  {} skipped: {}", count, reason);
+    }
+    if skipped.contains_key(SkipReason::TooLarge.as_str()) {
+        println!(
+            "  (files over {} MB aren't read)",
+            extract::MAX_EXTRACT_BYTES / (1024 * 1024)
+        );
+    }
+    Ok(())
+}
@@ -1,50 +1,116 @@
+use quaid_core::analysis::conversation_summary;
+use quaid_core::storage::ConversationPage;
 use quaid_core::Store;
 
-pub fn run(provider: Option<&str>, _archived: bool, store: &Store) -> anyhow::Result<()> {
-    let accounts = store.list_accounts()?;
-
-    if accounts.is_empty() {
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    provider: Option<&str>,
+    account: Option<&str>,
+    project: Option<&str>,
+    _archived: bool,
+    deleted_remotely: bool,
+    verbose: bool,
+    limit: usize,
+    offset: usize,
+    store: &Store,
+) -> anyhow::Result<()> {
+    if store.list_accounts()?.is_empty() {
         println!("No accounts configured. Use `quaid auth <provider>` first.");
         return Ok(());
     }
 
-    for account in accounts {
-        // Filter by provider if specified
-        if let Some(p) = provider {
-            if account.provider.0 != p {
-                continue;
-            }
-        }
-
+    for account in super::select_accounts(store, provider, account)? {
         println!("\n{} ({})", account.provider, account.email);
         println!("{}", "-".repeat(60));
 
-        let conversations = store.list_conversations(&account.id)?;
+        let page = if project.is_some() || deleted_remotely {
+            // Projects are matched by id or name, so these are filtered here
+            let deleted = store.remotely_deleted(&account.id)?;
+            let mut conversations = store.list_conversations(&account.id)?;
+            conversations.retain(|c| {
+                project.is_none_or(|project| super::in_project(c, project))
+                    && (!deleted_remotely || deleted.contains_key(&c.id))
+            });
+            ConversationPage {
+                total: conversations.len(),
+                conversations: conversations.into_iter().skip(offset).take(limit).collect(),
+                offset,
+            }
+        } else {
+            store.get_conversations_page(&account.id, offset, limit)?
+        };
 
-        if conversations.is_empty() {
-            println!("  No conversations yet. Use `quaid pull {}` to sync.", account.provider);
+        if page.total == 0 && deleted_remotely {
+            println!("  No conversations deleted upstream");
+            continue;
+        }
+        if page.total == 0 {
+            println!(
+                "  No conversations yet. Use `quaid pull {}` to sync.",
+                account.provider
+            );
+            continue;
+        }
+        if page.conversations.is_empty() {
+            println!("  No conversations past {} (of {})", offset, page.total);
             continue;
         }
 
-        for conv in conversations.iter().take(20) {
+        let counts = store.message_counts(&account.id)?;
+        for conv in &page.conversations {
             let date = conv.updated_at.format("%Y-%m-%d %H:%M");
             let model = conv.model.as_deref().unwrap_or("unknown");
+            if verbose {
+                let summary = conversation_summary(conv, &store.get_messages(&conv.id)?);
+                println!(
+                    "  {} | {:40} | {:20} | {}",
+                    date,
+                    truncate(&conv.title, 40),
+                    summary.compact(),
+                    model
+                );
+                continue;
+            }
+            let messages = counts.get(&conv.id).copied().unwrap_or_default();
             println!(
-                "  {} | {:40} | {}",
+                "  {} | {:40} | {:>5} msgs | {}",
                 date,
                 truncate(&conv.title, 40),
+                messages,
                 model
             );
         }
 
-        if conversations.len() > 20 {
-            println!("  ... and {} more", conversations.len() - 20);
+        let shown = page.offset + page.conversations.len();
+        if page.offset > 0 || shown < page.total {
+            println!("  showing {}-{} of {}", page.offset + 1, shown, page.total);
         }
     }
 
     Ok(())
 }
 
+/// List the projects seen during sync
+pub fn projects(provider: Option<&str>, store: &Store) -> anyhow::Result<()> {
+    let projects = store.list_projects(provider)?;
+    if projects.is_empty() {
+        println!(
+            "No projects yet. Projects are saved when you pull ChatGPT, Claude, Granola or Poe."
+        );
+        return Ok(());
+    }
+
+    for project in projects {
+        println!(
+            "  {:8} | {:40} | {}",
+            project.provider_id,
+            truncate(&project.name, 40),
+            project.id
+        );
+    }
+    Ok(())
+}
+
 fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -0,0 +1,555 @@
+//! `quaid mcp serve`: the local store as Model Context Protocol tools
+//!
+//! Speaks JSON-RPC over stdin/stdout for MCP clients such as Claude Desktop,
+//! which start the server themselves. Stdout carries protocol messages only;
+//! warnings go to stderr. Every tool is read-only, and answers with markdown
+//! for the model plus the same data as JSON in `structuredContent`.
+
+use super::export::message_markdown;
+use super::search::{self, FilterArgs};
+use quaid_core::mcp::{
+    self, Request, Response, RpcError, Tool, ToolCall, ToolResult, INTERNAL_ERROR, METHOD_NOT_FOUND,
+};
+use quaid_core::providers::Conversation;
+use quaid_core::storage::{ConversationHits, ParquetStorageConfig};
+use quaid_core::{CredentialStore, Store};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Longest text returned by one `get_conversation` call, in bytes
+pub const MAX_RESPONSE_BYTES: usize = 20_000;
+
+/// Conversations returned by `search_conversations` unless `limit` says otherwise
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// Conversations returned by `list_recent` unless `limit` says otherwise
+const DEFAULT_RECENT_LIMIT: usize = 20;
+
+/// Largest `limit` a client may ask for
+const MAX_LIMIT: usize = 100;
+
+/// Serve requests from stdin until it is closed
+///
+/// Semantic search calls the embeddings `endpoint` from the config, if any.
+pub fn run(
+    store: Store,
+    endpoint: Option<String>,
+    credentials: Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let server = Server::new(store, endpoint, credentials, data_dir);
+    eprintln!("quaid MCP server ready on stdio");
+    serve(&server, std::io::stdin().lock(), std::io::stdout().lock())
+}
+
+fn serve(server: &Server, input: impl BufRead, mut output: impl Write) -> anyhow::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_line(&line) {
+            serde_json::to_writer(&mut output, &response)?;
+            output.write_all(b"\n")?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+pub struct Server {
+    store: Store,
+    data_dir: PathBuf,
+    /// Where the index's embedder is reached, and its API key read from
+    endpoint: Option<String>,
+    credentials: Arc<dyn CredentialStore>,
+}
+
+impl Server {
+    pub fn new(
+        store: Store,
+        endpoint: Option<String>,
+        credentials: Arc<dyn CredentialStore>,
+        data_dir: &Path,
+    ) -> Self {
+        Self {
+            store,
+            data_dir: data_dir.to_path_buf(),
+            endpoint,
+            credentials,
+        }
+    }
+
+    /// Response to one line from the client; `None` for notifications
+    pub fn handle_line(&self, line: &str) -> Option<Response> {
+        match Request::parse(line) {
+            Ok(request) => self.handle(request),
+            Err(error) => Some(Response::error(Value::Null, error)),
+        }
+    }
+
+    fn handle(&self, request: Request) -> Option<Response> {
+        let result = match request.method.as_str() {
+            "initialize" => Ok(json!({
+                "protocolVersion": mcp::negotiate_version(
+                    request.params["protocolVersion"].as_str()
+                ),
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "quaid", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call_tool(request.params),
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        };
+
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => Response::success(id, result),
+            Err(error) => Response::error(id, error),
+        })
+    }
+
+    fn call_tool(&self, params: Value) -> Result<Value, RpcError> {
+        let call: ToolCall = arguments(params)?;
+        let result = match call.name.as_str() {
+            "search_conversations" => self.search_conversations(arguments(call.arguments)?),
+            "get_conversation" => self.get_conversation(arguments(call.arguments)?)?,
+            "list_recent" => self.list_recent(arguments(call.arguments)?),
+            name => return Err(RpcError::invalid_params(format!("Unknown tool: {}", name))),
+        };
+        serde_json::to_value(result).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+    }
+
+    /// Hybrid search when there is an embeddings index, keyword search otherwise
+    ///
+    /// `semantic` forces one or the other.
+    fn search_conversations(&self, args: SearchArgs) -> ToolResult {
+        let limit = args
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_LIMIT);
+        let semantic = args.semantic.unwrap_or_else(|| self.has_embeddings());
+        let groups = search::build_filters(
+            FilterArgs {
+                provider: args.provider.as_deref(),
+                account: None,
+                since: None,
+                until: None,
+                roles: &[],
+                exclude_archived: false,
+                attachments: false,
+            },
+            &self.store,
+        )
+        .and_then(|filters| {
+            if semantic {
+                search::run_semantic_search(
+                    &args.query,
+                    limit,
+                    true,
+                    false,
+                    &filters,
+                    &self.store,
+                    self.endpoint.as_deref(),
+                    &self.credentials,
+                    &self.data_dir,
+                )
+            } else {
+                search::run_fts_search(&args.query, limit, &filters, &self.store)
+            }
+        });
+        match groups {
+            Ok(groups) => ToolResult::structured(
+                render_hits(&groups),
+                json!({
+                    "mode": if semantic { "hybrid" } else { "keyword" },
+                    "results": groups,
+                }),
+            ),
+            Err(e) => ToolResult::error(format!("Search failed: {}", e)),
+        }
+    }
+
+    fn has_embeddings(&self) -> bool {
+        ParquetStorageConfig::new(&self.data_dir)
+            .list_embedding_providers()
+            .is_ok_and(|providers| !providers.is_empty())
+    }
+
+    /// A bad cursor is a malformed call; a missing conversation is a tool error
+    fn get_conversation(&self, args: GetArgs) -> Result<ToolResult, RpcError> {
+        let conversation = match self.store.get_conversation(&args.id) {
+            Ok(Some(conversation)) => conversation,
+            Ok(None) => {
+                return Ok(ToolResult::error(format!(
+                    "Conversation not found: {}",
+                    args.id
+                )))
+            }
+            Err(e) => return Ok(ToolResult::error(e.to_string())),
+        };
+        let messages = match self.store.get_messages(&args.id) {
+            Ok(messages) => messages,
+            Err(e) => return Ok(ToolResult::error(e.to_string())),
+        };
+
+        let mut markdown = format!(
+            "# {}\n\n{} | {} | {}\n\n",
+            conversation.title,
+            conversation.provider_id,
+            conversation.created_at.format("%Y-%m-%d %H:%M"),
+            conversation.model.as_deref().unwrap_or("unknown model")
+        );
+        for message in &messages {
+            markdown.push_str(&message_markdown(message));
+        }
+
+        let page = mcp::page(&markdown, args.cursor.as_deref(), MAX_RESPONSE_BYTES)?;
+        let mut text = page.text.to_string();
+        if let Some(cursor) = &page.next_cursor {
+            text.push_str(&format!(
+                "\n\n[Truncated. Call get_conversation with id \"{}\" and cursor \"{}\" for the rest.]",
+                conversation.id, cursor
+            ));
+        }
+        let structured = json!({
+            "conversation": conversation,
+            "markdown": page.text,
+            "next_cursor": page.next_cursor,
+        });
+        Ok(ToolResult::structured(text, structured))
+    }
+
+    fn list_recent(&self, args: RecentArgs) -> ToolResult {
+        let limit = args
+            .limit
+            .unwrap_or(DEFAULT_RECENT_LIMIT)
+            .clamp(1, MAX_LIMIT);
+        let mut conversations =
+            match super::recent_conversations(&self.store, args.provider.as_deref()) {
+                Ok(conversations) => conversations,
+                Err(e) => return ToolResult::error(e.to_string()),
+            };
+        conversations.truncate(limit);
+        let text = if conversations.is_empty() {
+            "No conversations.".to_string()
+        } else {
+            recent_lines(&conversations)
+        };
+        ToolResult::structured(text, json!({ "conversations": conversations }))
+    }
+}
+
+fn recent_lines(conversations: &[Conversation]) -> String {
+    conversations
+        .iter()
+        .map(|c| {
+            format!(
+                "- {} [{}] updated {}\n  id: {}",
+                c.title,
+                c.provider_id,
+                c.updated_at.format("%Y-%m-%d %H:%M"),
+                c.id
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    query: String,
+    limit: Option<usize>,
+    provider: Option<String>,
+    /// Unset picks hybrid search when there is an embeddings index
+    #[serde(alias = "hybrid")]
+    semantic: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct GetArgs {
+    id: String,
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RecentArgs {
+    limit: Option<usize>,
+    provider: Option<String>,
+}
+
+fn arguments<T: DeserializeOwned>(value: Value) -> Result<T, RpcError> {
+    // Clients may leave out `arguments` entirely for tools without required ones
+    let value = if value.is_null() { json!({}) } else { value };
+    serde_json::from_value(value).map_err(|e| RpcError::invalid_params(e.to_string()))
+}
+
+fn tools() -> Vec<Tool> {
+    let provider = json!({
+        "type": "string",
+        "enum": ["aistudio", "chatgpt", "claude", "claude-code", "copilot", "deepseek", "fathom", "gemini", "granola", "grok", "lechat", "otter", "poe"],
+        "description": "Only conversations from this provider",
+    });
+    vec![
+        Tool {
+            name: "search_conversations",
+            description: "Search the user's synced AI chat and meeting history. Returns \
+                          matching conversations with snippets and their ids.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Words to search for" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_LIMIT },
+                    "provider": provider,
+                    "semantic": {
+                        "type": "boolean",
+                        "description": "Combine keyword and semantic search (needs `quaid index embed`), \
+                                        or only match keywords when false. Defaults to combined \
+                                        when an index exists.",
+                    },
+                },
+                "required": ["query"],
+            }),
+        },
+        Tool {
+            name: "get_conversation",
+            description: "Read a conversation as markdown. Long conversations come in \
+                          pieces; pass the returned cursor to get the next one.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Conversation id" },
+                    "cursor": { "type": "string", "description": "Cursor from a truncated response" },
+                },
+                "required": ["id"],
+            }),
+        },
+        Tool {
+            name: "list_recent",
+            description: "List the most recently updated conversations with their ids.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_LIMIT },
+                    "provider": provider,
+                },
+            }),
+        },
+    ]
+}
+
+/// Search results as a markdown list, with FTS highlights in bold
+fn render_hits(groups: &[ConversationHits]) -> String {
+    if groups.is_empty() {
+        return "No results found.".to_string();
+    }
+    let mut text = String::new();
+    for group in groups {
+        text.push_str(&format!(
+            "- {} [{}] updated {}\n  id: {}\n",
+            group.title,
+            group.provider_id,
+            group.updated_at.format("%Y-%m-%d"),
+            group.conversation_id
+        ));
+        for snippet in &group.snippets {
+            let snippet = snippet
+                .replace("<mark>", "**")
+                .replace("</mark>", "**")
+                .replace('\n', " ");
+            text.push_str(&format!("  > {}\n", snippet));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use quaid_core::mcp::INVALID_PARAMS;
+    use quaid_core::providers::{Account, Conversation, Message, MessageContent, ProviderId, Role};
+    use quaid_core::MockStore;
+
+    fn server(dir: &Path) -> Server {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "a@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        let conversation = Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "claude".to_string(),
+            title: "Ingress".to_string(),
+            created_at: Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        let messages: Vec<Message> = (0..200)
+            .map(|i| Message {
+                id: format!("msg-{}", i),
+                conversation_id: "conv-1".to_string(),
+                parent_id: None,
+                role: if i % 2 == 0 {
+                    Role::User
+                } else {
+                    Role::Assistant
+                },
+                content: MessageContent::Text {
+                    text: format!(
+                        "Step {} of configuring the kubernetes ingress controller",
+                        i
+                    ),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            })
+            .collect();
+        store
+            .save_conversation_with_messages("user-1", &conversation, &messages)
+            .unwrap();
+        Server::new(store, None, Arc::new(MockStore::new()), dir)
+    }
+
+    fn call(server: &Server, name: &str, arguments: Value) -> ToolResult {
+        let line = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments },
+        });
+        let response = server.handle_line(&line.to_string()).unwrap();
+        serde_json::from_value(response.result.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_notifications_get_no_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = server(dir.path());
+        assert!(server
+            .handle_line(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+            .is_none());
+
+        let response = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"resources/list"}"#)
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_long_conversation_continues_from_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = server(dir.path());
+
+        let mut cursor = Value::Null;
+        let mut pages = 0;
+        let mut steps = 0;
+        loop {
+            let result = call(
+                &server,
+                "get_conversation",
+                json!({"id": "conv-1", "cursor": cursor}),
+            );
+            assert!(!result.is_error);
+            let text = &result.content[0].text;
+            pages += 1;
+            steps += text.matches("Step ").count();
+            let Some((_, rest)) = text.split_once("cursor \"") else {
+                break;
+            };
+            cursor = json!(rest.split('"').next().unwrap());
+        }
+        assert!(pages > 1);
+        assert_eq!(steps, 200);
+    }
+
+    #[test]
+    fn test_search_falls_back_to_keywords_without_an_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = server(dir.path());
+
+        let result = call(
+            &server,
+            "search_conversations",
+            json!({"query": "ingress", "limit": 5}),
+        );
+        assert!(!result.is_error);
+        assert!(result.content[0].text.contains("id: conv-1"));
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["mode"], "keyword");
+        assert_eq!(structured["results"][0]["conversation_id"], "conv-1");
+        assert_eq!(structured["results"][0]["title"], "Ingress");
+
+        let semantic = call(
+            &server,
+            "search_conversations",
+            json!({"query": "ingress", "semantic": true}),
+        );
+        assert!(semantic.is_error);
+        assert!(semantic.content[0].text.contains("quaid index embed"));
+    }
+
+    #[test]
+    fn test_results_are_structured() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = server(dir.path());
+
+        let recent = call(&server, "list_recent", json!({"limit": 1}));
+        let structured = recent.structured_content.unwrap();
+        assert_eq!(structured["conversations"][0]["id"], "conv-1");
+        assert_eq!(structured["conversations"][0]["provider_id"], "claude");
+
+        let first = call(&server, "get_conversation", json!({"id": "conv-1"}));
+        let structured = first.structured_content.unwrap();
+        assert_eq!(structured["conversation"]["title"], "Ingress");
+        assert!(structured["markdown"]
+            .as_str()
+            .unwrap()
+            .starts_with("# Ingress"));
+        let cursor = structured["next_cursor"].as_str().unwrap();
+        assert!(first.content[0].text.contains(cursor));
+
+        let response = server
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#,
+            )
+            .unwrap();
+        assert_eq!(response.result.unwrap()["protocolVersion"], "2024-11-05");
+    }
+
+    #[test]
+    fn test_tool_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = server(dir.path());
+
+        let missing = call(&server, "get_conversation", json!({"id": "conv-9"}));
+        assert!(missing.is_error);
+
+        let line = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": { "name": "get_conversation", "arguments": { "id": "conv-1", "cursor": "x" } },
+        });
+        let response = server.handle_line(&line.to_string()).unwrap();
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+    }
+}
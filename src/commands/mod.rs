@@ -1,7 +1,265 @@
+pub mod accounts;
+pub mod aistudio;
+pub mod attachments;
 pub mod auth;
+pub mod chatgpt_share;
+pub mod claude_code;
 pub mod compact;
+pub mod config;
+pub mod dedupe;
+pub mod doctor;
+pub mod embeddings;
 pub mod export;
+pub mod fsck;
+pub mod grep;
+pub mod history;
+pub mod index;
 pub mod list;
+pub mod mcp;
+pub mod open;
 pub mod pull;
+pub mod reindex;
+pub mod rename;
+pub mod sample;
 pub mod search;
+pub mod serve;
+pub mod show;
 pub mod stats;
+pub mod status;
+pub mod sync;
+pub mod sync_debug;
+pub mod verify;
+pub mod watch;
+
+use crate::config::EmbedderChoice;
+use chrono::{DateTime, Utc};
+use quaid_core::dates::parse_date_span;
+use quaid_core::embeddings::EmbeddingBackend;
+use quaid_core::providers::{Account, Conversation, Message, ProviderId};
+use quaid_core::storage::{ChangeKind, DiffSummary, EmbeddingsStore, ParquetStorageConfig};
+use quaid_core::Store;
+use std::path::Path;
+
+/// Accounts picked by `--provider` and `--account <email>`; all of them without either
+pub fn select_accounts(
+    store: &Store,
+    provider: Option<&str>,
+    email: Option<&str>,
+) -> anyhow::Result<Vec<Account>> {
+    let accounts: Vec<_> = store
+        .list_accounts()?
+        .into_iter()
+        .filter(|a| provider.is_none_or(|p| a.provider.0 == p))
+        .filter(|a| email.is_none_or(|e| a.email == e))
+        .collect();
+    if let (Some(email), true) = (email, accounts.is_empty()) {
+        anyhow::bail!(
+            "No {}account signed in as {}. Use `quaid <provider> auth` to add it.",
+            provider.map(|p| format!("{} ", p)).unwrap_or_default(),
+            email
+        );
+    }
+    Ok(accounts)
+}
+
+/// The account conversations imported from local files are kept under,
+/// created on first use; the files don't say whose they are
+pub fn local_account(store: &Store, provider: ProviderId, name: &str) -> anyhow::Result<Account> {
+    if let Some(account) = store.get_account(&provider, "local")? {
+        return Ok(account);
+    }
+    let account = Account {
+        id: format!("{}-local", provider),
+        provider,
+        email: "local".to_string(),
+        name: Some(name.to_string()),
+        avatar_url: None,
+        workspace_id: None,
+    };
+    store.save_account(&account)?;
+    Ok(account)
+}
+
+/// Whether `conv` is in the project with id or name `project`, ignoring case in names
+pub fn in_project(conv: &Conversation, project: &str) -> bool {
+    conv.project_id.as_deref() == Some(project)
+        || conv
+            .project_name
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case(project))
+}
+
+/// Print what importing `incoming` would change, writing nothing
+///
+/// `verbose` also lists every conversation and how it differs from the
+/// stored copy.
+pub fn print_import_diff(
+    store: &Store,
+    incoming: &[(Conversation, Vec<Message>)],
+    verbose: bool,
+) -> anyhow::Result<DiffSummary> {
+    let summary = store.diff_conversations(
+        incoming
+            .iter()
+            .map(|(conv, messages)| (conv, &messages[..])),
+    )?;
+
+    println!("{:<10} {:>8}", "Change", "Count");
+    println!("{:<10} {:>8}", "new", summary.new);
+    println!("{:<10} {:>8}", "updated", summary.updated);
+    println!("{:<10} {:>8}", "identical", summary.identical);
+
+    if verbose {
+        println!();
+        for change in &summary.changes {
+            let kind = match change.kind {
+                ChangeKind::New => "new".to_string(),
+                ChangeKind::Updated { message_delta } => format!("updated {:+}", message_delta),
+                ChangeKind::Identical => "identical".to_string(),
+            };
+            println!(
+                "  {:<13} {}  {}",
+                kind, change.conversation_id, change.title
+            );
+        }
+    }
+    println!("\nDry run: nothing was written. Run without --dry-run to import.");
+    Ok(summary)
+}
+
+/// Conversations of every account, or of `provider`'s, most recently updated first
+pub fn recent_conversations(
+    store: &Store,
+    provider: Option<&str>,
+) -> anyhow::Result<Vec<Conversation>> {
+    let mut conversations = Vec::new();
+    for account in select_accounts(store, provider, None)? {
+        conversations.extend(store.list_conversations(&account.id)?);
+    }
+    conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then(a.id.cmp(&b.id)));
+    Ok(conversations)
+}
+
+/// Pick the embedder from `--embedder`/`--embedding-model`.
+///
+/// Without flags, keep using whichever embedder built the existing index.
+/// Either way it is reached at the chosen endpoint, if any.
+pub fn embedding_backend(
+    choice: &EmbedderChoice,
+    data_dir: &Path,
+) -> anyhow::Result<EmbeddingBackend> {
+    let endpoint = choice.endpoint.as_deref();
+    if let Some(name) = &choice.embedder {
+        let backend = EmbeddingBackend::parse(name, choice.model.as_deref())?;
+        return Ok(backend.with_endpoint(endpoint));
+    }
+    if choice.model.is_some() {
+        anyhow::bail!("--embedding-model needs --embedder");
+    }
+
+    let store = EmbeddingsStore::new(ParquetStorageConfig::new(data_dir));
+    let backend = match store.read_metadata()? {
+        Some(metadata) => EmbeddingBackend::from_metadata(&metadata)?,
+        None => EmbeddingBackend::default(),
+    };
+    Ok(backend.with_endpoint(endpoint))
+}
+
+/// Parse a `--since`/`--until` value, see [`quaid_core::dates`].
+///
+/// An upper bound takes the end of the span the value names, so
+/// `--until 2024-06-01` covers the whole day and `--until 2024-06` the month.
+fn parse_date(value: &str, upper: bool) -> anyhow::Result<DateTime<Utc>> {
+    let span = parse_date_span(value, Utc::now(), &Utc)?;
+    Ok(if upper { span.end } else { span.start })
+}
+
+/// Parse a `--since` value, the start of the span it names
+pub fn parse_since(value: &str) -> anyhow::Result<DateTime<Utc>> {
+    parse_date(value, false)
+}
+
+/// Parse an `--until` value, the end of the span it names
+pub fn parse_until(value: &str) -> anyhow::Result<DateTime<Utc>> {
+    parse_date(value, true)
+}
+
+/// Parse a size like `500MB`, `1.5G` or `4096`; units are powers of 1024
+pub fn parse_size(value: &str) -> anyhow::Result<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size: {}. Use e.g. 500MB or 2GB", value))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => anyhow::bail!("Invalid size: {}. Use e.g. 500MB or 2GB", value),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quaid_core::storage::embeddings::EmbeddingsMetadata;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("200k").unwrap(), 200 * 1024);
+        assert_eq!(parse_size("500MB").unwrap(), 500 * 1024 * 1024);
+        assert_eq!(parse_size("1.5 GiB").unwrap(), 3 * 512 * 1024 * 1024);
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("5 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let since = parse_date("2024-06-01", false).unwrap();
+        assert_eq!(since.to_rfc3339(), "2024-06-01T00:00:00+00:00");
+
+        let until = parse_date("2024-06-01", true).unwrap();
+        assert!(until > parse_date("2024-06-01T23:59:59.999Z", false).unwrap());
+        assert!(until < parse_date("2024-06-02", false).unwrap());
+
+        assert!(parse_date("7d", false).unwrap() < Utc::now());
+        let err = parse_date("June 1st", false).unwrap_err().to_string();
+        assert!(err.contains("last <weekday>"), "{}", err);
+    }
+
+    #[test]
+    fn test_ollama_endpoint_comes_from_the_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let endpoint = |backend: EmbeddingBackend| match backend {
+            EmbeddingBackend::Ollama(config) => config.endpoint,
+            other => panic!("unexpected backend {:?}", other),
+        };
+        let config = crate::config::Config::parse(
+            "[embeddings]\nembedder = \"ollama\"\nendpoint = \"http://gpu-box:11434\"\n",
+        )
+        .unwrap();
+        let backend = embedding_backend(&config.embedder(None, None, None), dir.path()).unwrap();
+        assert_eq!(endpoint(backend), "http://gpu-box:11434");
+
+        // An index Ollama built is reached at the configured endpoint too
+        EmbeddingsStore::new(ParquetStorageConfig::new(dir.path()))
+            .ensure_metadata(&EmbeddingsMetadata {
+                backend: "ollama".to_string(),
+                model: "nomic-embed-text".to_string(),
+                dimension: 768,
+            })
+            .unwrap();
+        let config =
+            crate::config::Config::parse("[embeddings]\nendpoint = \"http://gpu-box:11434\"\n")
+                .unwrap();
+        let backend = embedding_backend(&config.embedder(None, None, None), dir.path()).unwrap();
+        assert_eq!(endpoint(backend), "http://gpu-box:11434");
+    }
+}
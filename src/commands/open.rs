@@ -0,0 +1,48 @@
+use quaid_core::Store;
+use std::process::{Command, ExitStatus};
+
+pub fn run(id: &str, print: bool, store: &Store) -> anyhow::Result<()> {
+    let conv = store
+        .get_conversation(id)?
+        .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", id))?;
+
+    let url = store.conversation_web_url(&conv)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} conversations have no web page to open",
+            conv.provider_id
+        )
+    })?;
+
+    if print {
+        println!("{}", url);
+        return Ok(());
+    }
+
+    launch(&url)?;
+    println!("Opened: {}", url);
+    Ok(())
+}
+
+/// Open `url` in the default browser
+fn launch(url: &str) -> anyhow::Result<()> {
+    let status = system_open(url)
+        .map_err(|e| anyhow::anyhow!("Could not launch a browser: {}. URL: {}", e, url))?;
+    if !status.success() {
+        anyhow::bail!("Could not launch a browser. URL: {}", url);
+    }
+    Ok(())
+}
+
+/// Hand a URL or file to the desktop's default application for it
+pub(super) fn system_open(target: &str) -> std::io::Result<ExitStatus> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(target).status()
+}
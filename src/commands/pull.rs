@@ -1,29 +1,289 @@
+use crate::config::{ProvidersConfig, PROVIDERS};
 use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
 use quaid_core::{
-    pipeline::{Pipeline, PipelineConfig},
+    embeddings::{ChunkerConfig, EmbeddingBackend},
+    operations::{OperationHandle, OperationSummary, Phase, ProgressEvent, SyncProgress},
+    pipeline::{fetch_all, CancellationToken, FetchConfig, Pipeline, PipelineConfig},
     providers::{
-        chatgpt::ChatGptProvider, claude::ClaudeProvider, fathom::FathomProvider,
-        granola::GranolaProvider, Conversation, Message,
+        chatgpt::ChatGptProvider,
+        claude::{project_docs_conversation, project_docs_id, ClaudeProvider},
+        copilot::CopilotProvider,
+        deepseek::DeepSeekProvider,
+        download::{DownloadError, Downloader},
+        fathom::FathomProvider,
+        gemini::GeminiProvider,
+        granola::GranolaProvider,
+        grok::GrokProvider,
+        lechat::LeChatProvider,
+        otter::OtterProvider,
+        poe::PoeProvider,
+        Account, Attachment, Conversation, Message, Project, ProviderError,
     },
-    storage::ParquetStorageConfig,
-    EmbeddingsCompactor, Provider, Store,
+    storage::{
+        AttachmentDownload, ParquetCompression, ParquetStorageConfig, SyncCursor, SyncDecision,
+    },
+    CredentialStore, EmbeddingsCompactor, Provider, Store,
 };
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Skipped conversations printed by `--explain-skips --verbose`
+const EXPLAINED_SKIPS: usize = 20;
+
+/// How `quaid pull` selects and reports conversations
+#[derive(Debug, Clone)]
+pub struct PullOptions {
+    /// Only pull new or updated conversations
+    pub new_only: bool,
+    /// Conversations and attachments to fetch at once (capped per provider)
+    pub concurrency: usize,
+    /// Write why each skipped conversation was skipped to a report
+    pub explain_skips: bool,
+    /// Also print the first skipped conversations
+    pub verbose: bool,
+    /// Log a timestamped line per conversation instead of redrawing progress
+    pub log_progress: bool,
+    /// Index one conversation at a time and compact once at the end
+    pub low_memory: bool,
+    /// Providers pulled when none is named; every account when empty
+    pub providers: Vec<String>,
+    /// Per-provider options from the config file
+    pub provider_options: ProvidersConfig,
+    /// How messages are split before embedding
+    pub chunker: ChunkerConfig,
+    /// Codec conversation files are written with
+    pub compression: ParquetCompression,
+    /// Which attachments to download
+    pub attachments: AttachmentOptions,
+    /// Only Fathom meetings created on or after this
+    pub since: Option<DateTime<Utc>>,
+    /// Only Fathom meetings created on or before this
+    pub until: Option<DateTime<Utc>>,
+    /// Revisions kept of each conversation a pull changes; none when unset
+    pub keep_history: Option<usize>,
+    /// Operation the pull reports its progress to, when started by `quaid serve`
+    pub progress: Option<PullProgress>,
+}
+
+/// Reports a pull's phases and counts to an operation
+#[derive(Clone)]
+pub struct PullProgress {
+    handle: OperationHandle,
+    counts: Arc<Mutex<PullCounts>>,
+}
+
+#[derive(Default)]
+struct PullCounts {
+    phase: Option<Phase>,
+    listed: usize,
+    skipped: usize,
+    synced: usize,
+}
+
+impl PullProgress {
+    pub fn new(handle: OperationHandle) -> Self {
+        Self {
+            handle,
+            counts: Arc::default(),
+        }
+    }
+
+    /// Enter `phase`, unless the pull is already in it
+    fn phase(&self, phase: Phase) {
+        let mut counts = self.counts.lock().unwrap();
+        if counts.phase != Some(phase) {
+            counts.phase = Some(phase);
+            self.handle.report(ProgressEvent::Phase { phase });
+        }
+    }
+
+    fn fetched(&self, done: usize, total: usize) {
+        self.phase(Phase::Fetching);
+        self.handle.report(ProgressEvent::Progress { done, total });
+    }
+
+    fn indexing(&self, conversations: usize) {
+        self.counts.lock().unwrap().synced += conversations;
+        self.phase(Phase::Processing);
+    }
+
+    fn pulled(&self, pulled: &Pulled) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.listed += pulled.listed;
+        counts.skipped += pulled.skips.len();
+    }
+
+    /// Cancelled when the operation is; the pull stops when it is
+    pub fn cancel_token(&self) -> &CancellationToken {
+        self.handle.cancel_token()
+    }
+
+    /// Report how the pull ended; no more events follow
+    pub fn finish(&self, result: &anyhow::Result<()>) {
+        let event = match result {
+            _ if self.cancel_token().is_cancelled() => ProgressEvent::Cancelled,
+            Ok(()) => {
+                let counts = self.counts.lock().unwrap();
+                ProgressEvent::Finished {
+                    summary: OperationSummary {
+                        synced: counts.synced,
+                        skipped: counts.skipped,
+                        failed: counts.listed.saturating_sub(counts.synced + counts.skipped),
+                    },
+                }
+            }
+            Err(e) => ProgressEvent::Failed {
+                error: e.to_string(),
+            },
+        };
+        self.handle.report(event);
+    }
+}
+
+/// For work that reports through [`SyncProgress`] itself, like
+/// [`fetch_conversations`](quaid_core::operations::fetch_conversations)
+///
+/// A `Finished` summary adds to the pull's counts rather than ending it;
+/// [`PullProgress::finish`] does that.
+impl SyncProgress for PullProgress {
+    fn report(&self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::Phase { phase } => self.phase(phase),
+            ProgressEvent::Progress { done, total } => self.fetched(done, total),
+            ProgressEvent::Finished { summary } => {
+                let mut counts = self.counts.lock().unwrap();
+                counts.listed += summary.synced + summary.skipped + summary.failed;
+                counts.skipped += summary.skipped;
+                counts.synced += summary.synced;
+            }
+            ProgressEvent::Failed { .. } | ProgressEvent::Cancelled => {}
+        }
+    }
+}
+
+impl std::fmt::Debug for PullProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PullProgress")
+            .field("operation", &self.handle.id())
+            .finish()
+    }
+}
+
+/// Which pending attachments a pull downloads
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentOptions {
+    /// Leave them all pending for a later pull
+    pub skip: bool,
+    /// Leave files larger than this many bytes pending
+    pub max_size: Option<u64>,
+}
+
+/// How a pull of every account went, for `quaid watch` to pace the next one
+#[derive(Debug, Default)]
+pub struct PullSummary {
+    /// Accounts whose pull failed
+    pub failed: usize,
+    /// Longest a provider asked to wait after rate limiting a pull
+    pub rate_limited: Option<Duration>,
+}
+
+impl PullSummary {
+    fn record_failure(&mut self, error: &anyhow::Error) {
+        self.failed += 1;
+        if let Some(wait) = rate_limit_wait(error) {
+            self.rate_limited = Some(self.rate_limited.map_or(wait, |w| w.max(wait)));
+        }
+    }
+}
+
+/// How long a provider asked to wait, if `error` is a rate limit
+pub fn rate_limit_wait(error: &anyhow::Error) -> Option<Duration> {
+    match error.downcast_ref::<ProviderError>() {
+        Some(ProviderError::RateLimited(secs)) => Some(Duration::from_secs(*secs)),
+        _ => None,
+    }
+}
+
+/// What one account's pull saw
+struct Pulled {
+    /// Conversations the provider listed
+    listed: usize,
+    /// Ids of every conversation the account has upstream, when the listing
+    /// wasn't narrowed down; stored ones missing from it were deleted there
+    remote_ids: Option<HashSet<String>>,
+    /// Conversations left as stored, and why
+    skips: Vec<SyncDecision>,
+}
 
 pub async fn run(
     provider: Option<&str>,
-    new_only: bool,
+    account: Option<&str>,
+    options: PullOptions,
+    embedding_backend: EmbeddingBackend,
     store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
     data_dir: &Path,
 ) -> anyhow::Result<()> {
+    let _lock = DataDirLock::acquire(data_dir)?;
+    let config = pipeline_config(data_dir, &options, embedding_backend, credentials);
+    let cancel = CancellationToken::new();
+    cancel_on_signal(cancel.clone());
+
+    pull_selected(
+        provider,
+        account,
+        &options,
+        store,
+        credentials,
+        &config,
+        &cancel,
+    )
+    .await?;
+    Ok(())
+}
+
+pub fn pipeline_config(
+    data_dir: &Path,
+    options: &PullOptions,
+    embedding_backend: EmbeddingBackend,
+    credentials: &Arc<dyn CredentialStore>,
+) -> PipelineConfig {
+    let config = PipelineConfig::new(data_dir)
+        .with_fetch_workers(options.concurrency)
+        .with_media_workers(options.concurrency)
+        .with_embedding_backend(embedding_backend)
+        .with_credentials(credentials.clone())
+        .with_chunker(options.chunker.clone())
+        .with_compression(options.compression);
+    if options.low_memory {
+        config.with_low_memory()
+    } else {
+        config
+    }
+}
+
+/// Pull the accounts picked by `provider` and `account`, or every account
+///
+/// Without `provider`, a failed account is reported and counted in the
+/// summary instead of stopping the others.
+pub async fn pull_selected(
+    provider: Option<&str>,
+    account: Option<&str>,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<PullSummary> {
+    let accounts = super::select_accounts(store, provider, account)?;
+    let mut summary = PullSummary::default();
     if let Some(provider) = provider {
         // Pull from specific provider
-        let accounts: Vec<_> = store
-            .list_accounts()?
-            .into_iter()
-            .filter(|a| a.provider.0 == provider)
-            .collect();
-
         if accounts.is_empty() {
             anyhow::bail!(
                 "No {} account configured. Use `quaid {} auth` first.",
@@ -33,140 +293,1322 @@ pub async fn run(
         }
 
         for account in accounts {
-            pull_provider(provider, &account.id, new_only, store, data_dir).await?;
+            if cancel.is_cancelled() {
+                break;
+            }
+            pull_provider(&account, options, store, credentials, config, cancel).await?;
         }
     } else {
-        // Pull from all configured providers
-        pull_all(new_only, store, data_dir).await?;
+        // Pull from all configured providers, or the ones the config file names
+        let accounts: Vec<_> = accounts
+            .into_iter()
+            .filter(|a| options.providers.is_empty() || options.providers.contains(&a.provider.0))
+            .collect();
+        summary = pull_all(&accounts, options, store, credentials, config, cancel).await?;
     }
 
-    Ok(())
+    // Low-memory pulls skip compacting after each account
+    if config.low_memory {
+        compact_embeddings(&config.data_dir);
+    }
+
+    Ok(summary)
+}
+
+/// Exclusive lock on the data directory, released when dropped
+///
+/// Two pulls writing the same Parquet files and embeddings would corrupt
+/// them, so only one `pull` or `sync` may run per data directory.
+pub struct DataDirLock {
+    _file: File,
+}
+
+impl DataDirLock {
+    pub fn acquire(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join("quaid.lock");
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let pid = std::fs::read_to_string(&path).unwrap_or_default();
+                anyhow::bail!(
+                    "Another quaid pull or sync is using {} (pid {})",
+                    data_dir.display(),
+                    pid.trim()
+                );
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Cancel the pull on the first Ctrl-C or SIGTERM, and quit outright on the second
+pub fn cancel_on_signal(cancel: CancellationToken) {
+    tokio::spawn(async move {
+        if shutdown_signal().await.is_err() {
+            return;
+        }
+        eprintln!("\nCancelling, finishing what is already in progress (Ctrl-C again to quit)...");
+        cancel.cancel();
+
+        if shutdown_signal().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
 }
 
-/// Check if we should skip this conversation based on updated_at
-fn should_skip(
-    conv_id: &str,
-    remote_updated_at: DateTime<Utc>,
+#[cfg(unix)]
+async fn shutdown_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+/// Current time for `quaid watch` log lines
+pub fn log_time() -> String {
+    Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn parquet_path(config: &PipelineConfig, conv: &Conversation) -> PathBuf {
+    ParquetStorageConfig::new(&config.data_dir).conversation_path(&conv.provider_id, &conv.id)
+}
+
+/// Decide whether to fetch a listed conversation based on updated_at
+fn sync_decision(
+    conv: &Conversation,
     new_only: bool,
     store: &Store,
-) -> bool {
-    if !new_only {
-        return false;
+    config: &PipelineConfig,
+) -> anyhow::Result<SyncDecision> {
+    let indexed = parquet_path(config, conv).exists();
+    Ok(store.sync_decision(conv, new_only, indexed)?)
+}
+
+/// Split a listing into conversations to fetch and the skipped ones
+fn select_for_sync(
+    conversations: Vec<Conversation>,
+    new_only: bool,
+    store: &Store,
+    config: &PipelineConfig,
+) -> anyhow::Result<(Vec<Conversation>, Vec<SyncDecision>)> {
+    let mut to_fetch = Vec::new();
+    let mut skips = Vec::new();
+    for conv in conversations {
+        let decision = sync_decision(&conv, new_only, store, config)?;
+        if decision.skips() {
+            skips.push(decision);
+        } else {
+            to_fetch.push(conv);
+        }
     }
+    Ok((to_fetch, skips))
+}
+
+/// Ids of a provider's listing
+fn listing_ids(conversations: &[Conversation]) -> HashSet<String> {
+    conversations.iter().map(|c| c.id.clone()).collect()
+}
+
+/// Skip decision for a fetched conversation already stored, unchanged, in SQLite and Parquet
+///
+/// Only the messages are left alone: the conversation row is still updated,
+/// so a newer `updated_at`, archive flag, project or model is kept, and
+/// `--new-only` doesn't fetch it again next time.
+fn unchanged_skip(
+    store: &Store,
+    config: &PipelineConfig,
+    account_id: &str,
+    conv: &Conversation,
+    messages: &[Message],
+) -> anyhow::Result<Option<SyncDecision>> {
+    if !parquet_path(config, conv).exists() {
+        return Ok(None);
+    }
+    let Some(skip) = store.identical_decision(conv, messages).ok().flatten() else {
+        return Ok(None);
+    };
+    store.save_conversation(account_id, conv)?;
+    Ok(Some(skip))
+}
+
+/// Save a fetched conversation, first keeping the stored version if asked to
+fn save_pulled(
+    store: &Store,
+    options: &PullOptions,
+    account_id: &str,
+    conv: &Conversation,
+    messages: &[Message],
+) -> anyhow::Result<()> {
+    if let Some(keep) = options.keep_history {
+        store.record_revision(conv, messages, keep)?;
+    }
+    store.save_conversation_with_messages(account_id, conv, messages)?;
+    Ok(())
+}
+
+/// Write an account's skip decisions to a report, printing the first few if asked
+fn explain_skips(
+    provider: &str,
+    account_id: &str,
+    skips: &[SyncDecision],
+    verbose: bool,
+    config: &PipelineConfig,
+) -> anyhow::Result<()> {
+    let reports_dir = config.data_dir.join("reports");
+    std::fs::create_dir_all(&reports_dir)?;
+    let path = reports_dir.join(format!(
+        "pull-{}-{}-{}.json",
+        provider,
+        account_id,
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    std::fs::write(&path, serde_json::to_string_pretty(skips)?)?;
+    println!("Skip report ({} skipped): {}", skips.len(), path.display());
+
+    if verbose {
+        let format_time = |at: Option<DateTime<Utc>>| {
+            at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        for skip in skips.iter().take(EXPLAINED_SKIPS) {
+            println!(
+                "  {:<11} local {}  remote {}  {}",
+                skip.rule.as_str(),
+                format_time(skip.local_updated_at),
+                format_time(Some(skip.remote_updated_at)),
+                truncate(&skip.title, 40)
+            );
+        }
+        if skips.len() > EXPLAINED_SKIPS {
+            println!("  ... and {} more", skips.len() - EXPLAINED_SKIPS);
+        }
+    }
+    Ok(())
+}
 
-    if let Ok(Some(local_updated_at)) = store.get_conversation_updated_at(conv_id) {
-        // Skip if remote hasn't been updated since our last sync
-        remote_updated_at <= local_updated_at
+/// Redraw the progress line, or log one line per conversation
+fn print_progress(options: &PullOptions, verb: &str, done: usize, total: usize, title: &str) {
+    if let Some(progress) = &options.progress {
+        progress.fetched(done, total);
+    }
+    if options.log_progress {
+        println!("{} [{}/{}] {}: {}", log_time(), done, total, verb, title);
     } else {
-        // New conversation, don't skip
-        false
+        print!(
+            "\r[{}/{}] {}: {}...",
+            done,
+            total,
+            verb,
+            truncate(title, 40)
+        );
+        let _ = std::io::stdout().flush();
     }
 }
 
 /// Pull from all configured providers
-async fn pull_all(new_only: bool, store: &Store, data_dir: &Path) -> anyhow::Result<()> {
-    let accounts = store.list_accounts()?;
+async fn pull_all(
+    accounts: &[Account],
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<PullSummary> {
+    let mut summary = PullSummary::default();
     if accounts.is_empty() {
         println!("No accounts configured. Use `quaid <provider> auth` first.");
-        println!("Providers: chatgpt, claude, fathom, granola");
-        return Ok(());
+        println!("Providers: {}", PROVIDERS.join(", "));
+        return Ok(summary);
     }
 
     println!("Pulling from {} providers...\n", accounts.len());
 
-    for account in &accounts {
-        println!("\n--- {} ({}) ---", account.provider.0, account.email);
-        if let Err(e) =
-            pull_provider(&account.provider.0, &account.id, new_only, store, data_dir).await
-        {
+    for account in accounts {
+        if cancel.is_cancelled() {
+            println!("\nPull cancelled. Run `quaid pull` again to pick up the rest.");
+            return Ok(summary);
+        }
+        println!("\n--- {} ({}) ---", account.provider.0, account.label());
+        let pulled = pull_provider(account, options, store, credentials, config, cancel).await;
+        if let Err(e) = pulled {
             eprintln!("Error: {}", e);
+            summary.record_failure(&e);
+        }
+    }
+
+    println!("\nPull complete. Run `quaid stats` to see totals.");
+    Ok(summary)
+}
+
+/// Pull one account, recording the outcome in its sync state
+async fn pull_provider(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let (provider, account_id) = (account.provider.0.as_str(), account.id.as_str());
+    if let Some(progress) = &options.progress {
+        progress.phase(Phase::Listing);
+    }
+    let pulled = match provider {
+        // Shares saved with `quaid chatgpt import-share`, nothing to pull
+        "chatgpt" if account.is_local() => Ok(None),
+        "chatgpt" => pull_chatgpt(account, options, store, credentials, config, cancel).await,
+        "claude" => pull_claude(account, options, store, credentials, config, cancel).await,
+        "copilot" => pull_copilot(account, options, store, credentials, config, cancel).await,
+        "deepseek" => pull_deepseek(account, options, store, credentials, config, cancel).await,
+        "fathom" => pull_fathom(account, options, store, credentials, config, cancel).await,
+        "granola" => pull_granola(account, options, store, config, cancel).await,
+        "gemini" => pull_gemini(account, options, store, credentials, config, cancel).await,
+        "grok" => pull_grok(account, options, store, credentials, config, cancel).await,
+        "lechat" => pull_lechat(account, options, store, credentials, config, cancel).await,
+        "otter" => pull_otter(account, options, store, credentials, config, cancel).await,
+        "poe" => pull_poe(account, options, store, credentials, config, cancel).await,
+        // Imported from files with `quaid aistudio import` and
+        // `quaid claude import-code`, nothing to pull
+        "aistudio" | "claude-code" => Ok(None),
+        _ => {
+            println!("Unknown provider: {}", provider);
+            Ok(None)
+        }
+    };
+
+    match pulled {
+        Ok(Some(pulled)) => {
+            if let Some(progress) = &options.progress {
+                progress.pulled(&pulled);
+            }
+            // A cancelled pull may have missed conversations, so it doesn't count
+            if !cancel.is_cancelled() {
+                store.record_pull(provider, account_id, !options.new_only, pulled.listed)?;
+                if let (false, Some(remote_ids)) = (options.new_only, &pulled.remote_ids) {
+                    let changes = store.record_remote_listing(provider, account_id, remote_ids)?;
+                    if !changes.deleted.is_empty() {
+                        println!(
+                            "{} conversations were deleted upstream and are kept here \
+                             (see `quaid list --deleted-remotely`)",
+                            changes.deleted.len()
+                        );
+                    }
+                }
+            }
+            if options.explain_skips {
+                explain_skips(provider, account_id, &pulled.skips, options.verbose, config)?;
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            store.record_pull_error(provider, account_id, &e.to_string())?;
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+async fn pull_chatgpt(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching conversations from ChatGPT...");
+
+    let account_id = account.id.as_str();
+    let include_archived = options
+        .provider_options
+        .chatgpt
+        .include_archived
+        .unwrap_or(false);
+    let provider = ChatGptProvider::with_stored_account(credentials.clone(), account)
+        .with_include_archived(include_archived);
+
+    // Check if we need to authenticate
+    if !provider.is_authenticated().await {
+        println!("Not authenticated. Please run `quaid auth chatgpt` first.");
+        return Ok(None);
+    }
+    if !provider.is_selected(account) {
+        println!("Not chosen at sign-in, skipping. Run `quaid chatgpt auth` to sync it again.");
+        return Ok(None);
+    }
+
+    let projects = sync_projects(&provider, store).await?;
+
+    // Fetch all conversations, including ones only listed in their project
+    let mut in_projects = Vec::new();
+    // Archived conversations, unless they're pulled, and those of projects
+    // that failed to list would look deleted upstream
+    let mut complete = include_archived;
+    for project in &projects {
+        match provider.project_conversations(&project.id).await {
+            Ok(listed) => in_projects.extend(listed.into_iter().map(|conv| Conversation {
+                project_name: Some(project.name.clone()),
+                ..conv
+            })),
+            Err(e) => {
+                eprintln!("Couldn't list conversations in {}: {}", project.name, e);
+                complete = false;
+            }
+        }
+    }
+    let conversations = merge_project_conversations(provider.conversations().await?, in_projects);
+    let listed = conversations.len();
+    let remote_ids = complete.then(|| listing_ids(&conversations));
+    println!("Found {} conversations", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation_with_attachments(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
+
+    let mut synced = 0;
+    let mut failed = 0;
+
+    // Collect synced conversations for pipeline processing
+    let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
+
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages, attachments)) => {
+                // Keep the project the conversation was listed under
+                let full_conv = Conversation {
+                    project_id: full_conv.project_id.or_else(|| conv.project_id.clone()),
+                    project_name: full_conv.project_name.or_else(|| conv.project_name.clone()),
+                    ..full_conv
+                };
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
+                }
+
+                let messages: Vec<Message> = messages
+                    .into_iter()
+                    .map(|msg| Message {
+                        conversation_id: conv.id.clone(),
+                        ..msg
+                    })
+                    .collect();
+
+                // Save conversation and messages to SQLite
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
+
+                // Save attachments for later download
+                for attachment in attachments {
+                    store.save_attachment(&attachment)?;
+                }
+
+                // Collect for pipeline
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
+
+                synced += 1;
+            }
+            Err(e) => {
+                eprintln!("\nError syncing {}: {}", conv.id, e);
+                failed += 1;
+            }
         }
     }
 
-    println!("\nPull complete. Run `quaid stats` to see totals.");
-    Ok(())
-}
+    if !skips.is_empty() {
+        println!(
+            "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
+            synced,
+            skips.len(),
+            failed
+        );
+    } else {
+        println!("\n\nSync complete: {} synced, {} failed", synced, failed);
+    }
+
+    // Download pending attachments
+    download_attachments(
+        &provider,
+        account_id,
+        store,
+        &config.data_dir,
+        &options.attachments,
+        config.media_workers,
+        cancel,
+    )
+    .await?;
+
+    // Run pipeline for Parquet storage and embeddings
+    if !pipeline_data.is_empty() {
+        index_pulled(options, config, pipeline_data, cancel).await?;
+    }
+
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
+}
+
+async fn pull_claude(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching conversations from Claude...");
+
+    let account_id = account.id.as_str();
+    let mut provider =
+        ClaudeProvider::with_account_credentials(credentials.clone(), Some(&account.email))
+            .with_include_thinking(
+                options
+                    .provider_options
+                    .claude
+                    .include_thinking
+                    .unwrap_or(false),
+            );
+    if let Some(org_id) = &options.provider_options.claude.org_id {
+        provider = provider.with_org_id(org_id);
+    }
+
+    // Check if we need to authenticate
+    if !provider.is_authenticated().await {
+        println!("Not authenticated. Please run `quaid auth claude` first.");
+        return Ok(None);
+    }
+
+    let projects = sync_projects(&provider, store).await?;
+
+    // Fetch all conversations
+    let conversations = provider.conversations().await?;
+    let listed = conversations.len();
+    // Project documents are kept for as long as their project is
+    let mut remote_ids = listing_ids(&conversations);
+    remote_ids.extend(projects.iter().map(|p| project_docs_id(&p.id)));
+    let remote_ids = Some(remote_ids);
+    println!("Found {} conversations", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation_with_attachments(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
+
+    let mut synced = 0;
+    let mut failed = 0;
+
+    // Collect synced conversations for pipeline processing
+    let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
+
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages, attachments)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
+                }
+
+                // Save conversation and messages to SQLite
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
+
+                // Save attachments for later download
+                for attachment in attachments {
+                    store.save_attachment(&attachment)?;
+                }
+
+                // Collect for pipeline
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
+
+                synced += 1;
+            }
+            Err(e) => {
+                eprintln!("\nError syncing {}: {}", conv.id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if options.provider_options.claude.include_project_docs == Some(true) {
+        for project in &projects {
+            let docs = match provider.project_docs(&project.id).await {
+                Ok(docs) => docs,
+                Err(e) => {
+                    eprintln!("\nError fetching documents of {}: {}", project.name, e);
+                    failed += 1;
+                    continue;
+                }
+            };
+            let Some((conv, messages)) = project_docs_conversation(project, &docs) else {
+                continue;
+            };
+            if let Some(skip) = unchanged_skip(store, config, account_id, &conv, &messages)? {
+                skips.push(skip);
+                continue;
+            }
+            save_pulled(store, options, account_id, &conv, &messages)?;
+            pipeline_data.push((account_id.to_string(), conv, messages));
+            synced += 1;
+        }
+    }
+
+    if !skips.is_empty() {
+        println!(
+            "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
+            synced,
+            skips.len(),
+            failed
+        );
+    } else {
+        println!("\n\nSync complete: {} synced, {} failed", synced, failed);
+    }
+
+    // Download pending attachments
+    download_attachments(
+        &provider,
+        account_id,
+        store,
+        &config.data_dir,
+        &options.attachments,
+        config.media_workers,
+        cancel,
+    )
+    .await?;
+
+    // Run pipeline for Parquet storage and embeddings
+    if !pipeline_data.is_empty() {
+        index_pulled(options, config, pipeline_data, cancel).await?;
+    }
+
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
+}
+
+async fn pull_fathom(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching meetings from Fathom (with transcripts)...");
+
+    let account_id = account.id.as_str();
+
+    // --new-only picks up an interrupted listing where it stopped, and
+    // otherwise only lists meetings newer than the last one synced
+    let state = store.sync_cursor("fathom", account_id)?;
+    let newest_stored = if options.new_only {
+        store.newest_conversation_created_at(account_id)?
+    } else {
+        None
+    };
+    let created_after = fathom_created_after(
+        options.since,
+        options.new_only,
+        state.as_ref(),
+        newest_stored,
+    );
+    let (mut cursor, last_synced_at) = match (state, options.new_only) {
+        (Some(state), true) => (state.cursor, state.last_synced_at),
+        _ => (None, None),
+    };
+    if cursor.is_some() {
+        println!("Resuming where the last pull stopped");
+    }
+
+    let provider =
+        FathomProvider::with_account_credentials(credentials.clone(), Some(&account.email))
+            .with_teams(options.provider_options.fathom.teams.clone())
+            .with_created_after(created_after)
+            .with_created_before(options.until)
+            .with_include_recordings(
+                options.provider_options.fathom.include_recordings == Some(true),
+            )
+            .with_max_recording_size(options.attachments.max_size);
+
+    if !provider.is_authenticated().await {
+        println!("Not authenticated. Please run `quaid auth fathom` first.");
+        return Ok(None);
+    }
+
+    let mut listed = 0;
+    let mut synced = 0;
+    let mut skips = Vec::new();
+    let mut newest = last_synced_at;
+    let mut remote_ids = HashSet::new();
+
+    // Collect synced conversations for pipeline processing
+    let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
+
+    // Meetings are saved a page at a time, and the cursor only moves past a
+    // page once all of it is saved
+    loop {
+        let Some(page) = cancel
+            .run_until_cancelled(provider.meetings_page(cursor.as_deref()))
+            .await
+        else {
+            println!("\nCancelled; `--new-only` resumes from here next time");
+            break;
+        };
+        let page = page?;
+        let seen = listed;
+        listed += page.items.len();
+
+        for (i, meeting) in page.items.iter().enumerate() {
+            newest = newest.max(Some(meeting.created_at));
+            let (conv, messages, attachments) = provider.meeting_to_data(meeting);
+            remote_ids.insert(conv.id.clone());
+
+            // Check if we should skip this conversation
+            let decision = sync_decision(&conv, options.new_only, store, config)?;
+            if decision.skips() {
+                skips.push(decision);
+                continue;
+            }
+            if let Some(skip) = unchanged_skip(store, config, account_id, &conv, &messages)? {
+                store.set_conversation_web_url(&conv.id, &meeting.url)?;
+                skips.push(skip);
+                continue;
+            }
+
+            let title = meeting.display_title();
+            print_progress(options, "Syncing", seen + i + 1, listed, &title);
+
+            save_pulled(store, options, account_id, &conv, &messages)?;
+            store.set_conversation_web_url(&conv.id, &meeting.url)?;
+
+            // Save the recording, if included, for later download
+            for attachment in attachments {
+                store.save_attachment(&attachment)?;
+            }
+
+            // Collect for pipeline
+            pipeline_data.push((account_id.to_string(), conv, messages));
+            synced += 1;
+        }
+
+        match page.next_cursor.filter(|next| !next.is_empty()) {
+            Some(next) => {
+                store.save_sync_cursor("fathom", account_id, &next)?;
+                cursor = Some(next);
+            }
+            None => {
+                store.finish_sync_cursor("fathom", account_id, newest)?;
+                break;
+            }
+        }
+    }
+
+    if !skips.is_empty() {
+        println!(
+            "\n\nSync complete: {} synced, {} skipped (unchanged)",
+            synced,
+            skips.len()
+        );
+    } else {
+        println!("\n\nSync complete: {} meetings synced", synced);
+    }
+
+    // Download recordings; not every plan allows it, so failures only skip
+    download_attachments(
+        &provider,
+        account_id,
+        store,
+        &config.data_dir,
+        &options.attachments,
+        config.media_workers,
+        cancel,
+    )
+    .await?;
+
+    // Run pipeline for Parquet storage and embeddings
+    if !pipeline_data.is_empty() {
+        index_pulled(options, config, pipeline_data, cancel).await?;
+    }
+
+    // Meetings outside the dates or teams asked for weren't listed
+    let filtered = options.since.is_some()
+        || options.until.is_some()
+        || !options.provider_options.fathom.teams.is_empty();
+    Ok(Some(Pulled {
+        listed,
+        remote_ids: (!filtered).then_some(remote_ids),
+        skips,
+    }))
+}
+
+/// Pull the one Fathom meeting a webhook reported
+///
+/// Fathom can't fetch a meeting by id, so each authenticated account lists
+/// the minutes around its creation time until the meeting turns up.
+pub async fn pull_fathom_meeting(
+    meeting_id: &str,
+    created_at: Option<DateTime<Utc>>,
+    options: PullOptions,
+    embedding_backend: EmbeddingBackend,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let _lock = DataDirLock::acquire(data_dir)?;
+    let config = pipeline_config(data_dir, &options, embedding_backend, credentials);
+    let cancel = CancellationToken::new();
+    let window = chrono::Duration::minutes(1);
+
+    for account in super::select_accounts(store, Some("fathom"), None)? {
+        let account_id = account.id.as_str();
+        let provider =
+            FathomProvider::with_account_credentials(credentials.clone(), Some(&account.email))
+                .with_teams(options.provider_options.fathom.teams.clone())
+                .with_created_after(created_at.map(|at| at - window))
+                .with_created_before(created_at.map(|at| at + window))
+                .with_include_recordings(
+                    options.provider_options.fathom.include_recordings == Some(true),
+                )
+                .with_max_recording_size(options.attachments.max_size);
+        if !provider.is_authenticated().await {
+            continue;
+        }
+
+        let mut cursor: Option<String> = None;
+        let meeting = loop {
+            let page = provider.meetings_page(cursor.as_deref()).await?;
+            if let Some(meeting) = page.items.into_iter().find(|m| m.id() == meeting_id) {
+                break Some(meeting);
+            }
+            match page.next_cursor.filter(|next| !next.is_empty()) {
+                Some(next) => cursor = Some(next),
+                None => break None,
+            }
+        };
+        let Some(meeting) = meeting else {
+            continue;
+        };
+
+        let (conv, messages, attachments) = provider.meeting_to_data(&meeting);
+        if unchanged_skip(store, &config, account_id, &conv, &messages)?.is_some() {
+            store.set_conversation_web_url(&conv.id, &meeting.url)?;
+            return Ok(());
+        }
+        save_pulled(store, &options, account_id, &conv, &messages)?;
+        store.set_conversation_web_url(&conv.id, &meeting.url)?;
+        for attachment in attachments {
+            store.save_attachment(&attachment)?;
+        }
+
+        download_attachments(
+            &provider,
+            account_id,
+            store,
+            data_dir,
+            &options.attachments,
+            config.media_workers,
+            &cancel,
+        )
+        .await?;
+        let pipeline_data = vec![(account_id.to_string(), conv, messages)];
+        return index_pulled(&options, &config, pipeline_data, &cancel).await;
+    }
+
+    anyhow::bail!("No authenticated Fathom account has meeting {}", meeting_id)
+}
+
+/// Only Fathom meetings created after this are listed
+///
+/// `--since` sets a floor. With `--new-only` so does the newest meeting
+/// synced: the one the last finished listing recorded, or else the newest
+/// stored. A listing being resumed keeps the floor it started with, which its
+/// cursor was issued for.
+fn fathom_created_after(
+    since: Option<DateTime<Utc>>,
+    new_only: bool,
+    state: Option<&SyncCursor>,
+    newest_stored: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    if !new_only {
+        return since;
+    }
+    let synced = match state {
+        Some(state) if state.cursor.is_some() => state.last_synced_at,
+        Some(state) => state.last_synced_at.or(newest_stored),
+        None => newest_stored,
+    };
+    since.max(synced)
+}
+
+/// How far before the newest stored update a `--new-only` Granola listing
+/// starts, for documents saved around the same time as it
+const GRANOLA_UPDATE_OVERLAP: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Only Granola documents updated after this are listed
+///
+/// With `--new-only`, shortly before the newest update stored; documents that
+/// failed to sync before then are picked up by the next full pull.
+fn granola_updated_after(
+    new_only: bool,
+    newest_stored: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    newest_stored
+        .filter(|_| new_only)
+        .map(|newest| newest - GRANOLA_UPDATE_OVERLAP)
+}
+
+/// Stored conversations to archive because a full listing no longer has
+/// them, and archived ones to restore because it has them again
+///
+/// Nothing is deleted, so a document removed by mistake keeps its history.
+fn reconcile_archived(
+    stored: &[Conversation],
+    listed: &[Conversation],
+) -> (Vec<String>, Vec<String>) {
+    let listed: HashSet<&str> = listed.iter().map(|c| c.id.as_str()).collect();
+    let (present, gone): (Vec<_>, Vec<_>) =
+        stored.iter().partition(|c| listed.contains(c.id.as_str()));
+    let archive = gone
+        .into_iter()
+        .filter(|c| !c.is_archived)
+        .map(|c| c.id.clone())
+        .collect();
+    let restore = present
+        .into_iter()
+        .filter(|c| c.is_archived)
+        .map(|c| c.id.clone())
+        .collect();
+    (archive, restore)
+}
+
+async fn pull_granola(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching meeting notes from Granola...");
+
+    // The desktop app holds a single session, whichever account it is
+    let account_id = account.id.as_str();
+    let newest_stored = if options.new_only {
+        store.newest_conversation_updated_at(account_id)?
+    } else {
+        None
+    };
+    let provider = GranolaProvider::new()
+        .with_updated_after(granola_updated_after(options.new_only, newest_stored));
+
+    if !provider.is_authenticated().await {
+        println!("Not authenticated. Please run `quaid auth granola` first.");
+        println!("(Make sure you're logged into the Granola desktop app)");
+        return Ok(None);
+    }
+
+    let conversations = provider.conversations().await?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} documents", listed);
+
+    // Workspaces and folders, which documents are filed under
+    sync_projects(&provider, store).await?;
+
+    // Only a full listing shows which documents were deleted
+    if !options.new_only {
+        let stored = store.list_conversations(account_id)?;
+        let (deleted, restored) = reconcile_archived(&stored, &conversations);
+        for id in &deleted {
+            store.set_conversation_archived(id, true)?;
+        }
+        for id in &restored {
+            store.set_conversation_archived(id, false)?;
+        }
+        if !deleted.is_empty() {
+            println!("Archived {} documents no longer in Granola", deleted.len());
+        }
+    }
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
+
+    let mut synced = 0;
+    let mut failed = 0;
+
+    // Collect synced conversations for pipeline processing
+    let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
+
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
+                }
+
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
+
+                // Collect for pipeline
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
+                synced += 1;
+            }
+            Err(e) => {
+                eprintln!("\nError syncing {}: {}", conv.id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if !skips.is_empty() {
+        println!(
+            "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
+            synced,
+            skips.len(),
+            failed
+        );
+    } else {
+        println!("\n\nSync complete: {} synced, {} failed", synced, failed);
+    }
+
+    // Run pipeline for Parquet storage and embeddings
+    if !pipeline_data.is_empty() {
+        index_pulled(options, config, pipeline_data, cancel).await?;
+    }
+
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
+}
+
+async fn pull_gemini(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching chats from Gemini...");
+
+    let account_id = account.id.as_str();
+    let provider =
+        GeminiProvider::with_account_credentials(credentials.clone(), Some(&account.email));
+
+    if !provider.is_authenticated().await {
+        println!("Not authenticated. Please run `quaid auth gemini` first.");
+        return Ok(None);
+    }
+
+    let conversations = provider.conversations().await.map_err(|e| match e {
+        ProviderError::TokenExpired => {
+            anyhow::anyhow!("Gemini session expired. Please run `quaid auth gemini` again.")
+        }
+        e => e.into(),
+    })?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} chats", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
+
+    let mut synced = 0;
+    let mut failed = 0;
+
+    // Collect synced conversations for pipeline processing
+    let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
+
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
+                }
+
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
+
+                // Collect for pipeline
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
+                synced += 1;
+            }
+            Err(e) => {
+                eprintln!("\nError syncing {}: {}", conv.id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if !skips.is_empty() {
+        println!(
+            "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
+            synced,
+            skips.len(),
+            failed
+        );
+    } else {
+        println!("\n\nSync complete: {} synced, {} failed", synced, failed);
+    }
+
+    // Run pipeline for Parquet storage and embeddings
+    if !pipeline_data.is_empty() {
+        index_pulled(options, config, pipeline_data, cancel).await?;
+    }
+
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
+}
+
+async fn pull_grok(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching conversations from Grok...");
+
+    let account_id = account.id.as_str();
+    let provider =
+        GrokProvider::with_account_credentials(credentials.clone(), Some(&account.email));
+
+    if !provider.is_authenticated().await {
+        println!("Not authenticated. Please run `quaid auth grok` first.");
+        return Ok(None);
+    }
+
+    let conversations = provider.conversations().await.map_err(|e| match e {
+        ProviderError::TokenExpired => {
+            anyhow::anyhow!("X session expired. Please run `quaid auth grok` again.")
+        }
+        e => e.into(),
+    })?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} conversations", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation_with_attachments(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
+
+    let mut synced = 0;
+    let mut failed = 0;
+
+    // Collect synced conversations for pipeline processing
+    let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
+
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages, attachments)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
+                }
+
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
+
+                // Save attachments for later download
+                for attachment in attachments {
+                    store.save_attachment(&attachment)?;
+                }
+
+                // Collect for pipeline
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
+                synced += 1;
+            }
+            Err(e) => {
+                eprintln!("\nError syncing {}: {}", conv.id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if !skips.is_empty() {
+        println!(
+            "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
+            synced,
+            skips.len(),
+            failed
+        );
+    } else {
+        println!("\n\nSync complete: {} synced, {} failed", synced, failed);
+    }
+
+    // Download pending attachments
+    download_attachments(
+        &provider,
+        account_id,
+        store,
+        &config.data_dir,
+        &options.attachments,
+        config.media_workers,
+        cancel,
+    )
+    .await?;
 
-async fn pull_provider(
-    provider: &str,
-    account_id: &str,
-    new_only: bool,
-    store: &Store,
-    data_dir: &Path,
-) -> anyhow::Result<()> {
-    match provider {
-        "chatgpt" => pull_chatgpt(account_id, new_only, store, data_dir).await,
-        "claude" => pull_claude(account_id, new_only, store, data_dir).await,
-        "fathom" => pull_fathom(account_id, new_only, store, data_dir).await,
-        "granola" => pull_granola(account_id, new_only, store, data_dir).await,
-        "gemini" => {
-            println!("Gemini provider not yet implemented");
-            Ok(())
-        }
-        _ => {
-            println!("Unknown provider: {}", provider);
-            Ok(())
-        }
+    // Run pipeline for Parquet storage and embeddings
+    if !pipeline_data.is_empty() {
+        index_pulled(options, config, pipeline_data, cancel).await?;
     }
+
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
 }
 
-async fn pull_chatgpt(
-    account_id: &str,
-    new_only: bool,
+async fn pull_copilot(
+    account: &Account,
+    options: &PullOptions,
     store: &Store,
-    data_dir: &Path,
-) -> anyhow::Result<()> {
-    println!("Fetching conversations from ChatGPT...");
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching chats from Copilot...");
 
-    let provider = ChatGptProvider::new();
+    let account_id = account.id.as_str();
+    let provider =
+        CopilotProvider::with_account_credentials(credentials.clone(), Some(&account.email));
 
-    // Check if we need to authenticate
     if !provider.is_authenticated().await {
-        println!("Not authenticated. Please run `quaid auth chatgpt` first.");
-        return Ok(());
+        println!("Not authenticated. Please run `quaid auth copilot` first.");
+        return Ok(None);
     }
 
-    // Fetch all conversations
-    let conversations = provider.conversations().await?;
-    println!("Found {} conversations", conversations.len());
+    let conversations = provider.conversations().await.map_err(|e| match e {
+        ProviderError::TokenExpired => {
+            anyhow::anyhow!("Copilot session expired. Please run `quaid auth copilot` again.")
+        }
+        e => e.into(),
+    })?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} chats", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation_with_attachments(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
 
     let mut synced = 0;
-    let mut skipped = 0;
     let mut failed = 0;
 
     // Collect synced conversations for pipeline processing
     let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
 
-    for (i, conv) in conversations.iter().enumerate() {
-        // Check if we should skip this conversation
-        if should_skip(&conv.id, conv.updated_at, new_only, store) {
-            skipped += 1;
-            continue;
-        }
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages, attachments)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
+                }
 
-        print!(
-            "\r[{}/{}] Syncing: {}...",
-            i + 1,
-            conversations.len(),
-            truncate(&conv.title, 40)
-        );
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
 
-        match provider.conversation(&conv.id).await {
-            Ok((full_conv, messages)) => {
-                // Save conversation to SQLite
-                store.save_conversation(account_id, &full_conv)?;
-
-                // Save messages to SQLite
-                let mut saved_messages = Vec::new();
-                for mut msg in messages {
-                    msg.conversation_id = conv.id.clone();
-                    store.save_message(&msg)?;
-                    saved_messages.push(msg);
+                // Save attachments for later download
+                for attachment in attachments {
+                    store.save_attachment(&attachment)?;
                 }
 
                 // Collect for pipeline
-                pipeline_data.push((account_id.to_string(), full_conv, saved_messages));
-
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
                 synced += 1;
             }
             Err(e) => {
@@ -174,111 +1616,109 @@ async fn pull_chatgpt(
                 failed += 1;
             }
         }
-
-        // Rate limiting - be nice to the API
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
 
-    if skipped > 0 {
+    if !skips.is_empty() {
         println!(
             "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
-            synced, skipped, failed
+            synced,
+            skips.len(),
+            failed
         );
     } else {
         println!("\n\nSync complete: {} synced, {} failed", synced, failed);
     }
 
     // Download pending attachments
-    let pending = store.get_pending_attachments()?;
-    if !pending.is_empty() {
-        println!("\nDownloading {} attachments...", pending.len());
-
-        let attachments_dir = data_dir.join("attachments").join(account_id);
-        std::fs::create_dir_all(&attachments_dir)?;
-
-        for attachment in pending {
-            let path = attachments_dir.join(&attachment.filename);
-            match provider.download_attachment(&attachment, &path).await {
-                Ok(_) => {
-                    store.mark_attachment_downloaded(&attachment.id, path.to_str().unwrap_or(""))?;
-                    println!("  Downloaded: {}", attachment.filename);
-                }
-                Err(e) => {
-                    eprintln!("  Failed to download {}: {}", attachment.filename, e);
-                }
-            }
-        }
-    }
+    download_attachments(
+        &provider,
+        account_id,
+        store,
+        &config.data_dir,
+        &options.attachments,
+        config.media_workers,
+        cancel,
+    )
+    .await?;
 
     // Run pipeline for Parquet storage and embeddings
     if !pipeline_data.is_empty() {
-        run_pipeline(data_dir, pipeline_data)?;
+        index_pulled(options, config, pipeline_data, cancel).await?;
     }
 
-    Ok(())
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
 }
 
-async fn pull_claude(
-    account_id: &str,
-    new_only: bool,
+/// Save `provider`'s projects so their conversations get names
+///
+/// A failed project listing is reported but doesn't stop the pull.
+async fn pull_lechat(
+    account: &Account,
+    options: &PullOptions,
     store: &Store,
-    data_dir: &Path,
-) -> anyhow::Result<()> {
-    println!("Fetching conversations from Claude...");
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching chats from Le Chat...");
 
-    let provider = ClaudeProvider::new();
+    let account_id = account.id.as_str();
+    let provider =
+        LeChatProvider::with_account_credentials(credentials.clone(), Some(&account.email));
 
-    // Check if we need to authenticate
     if !provider.is_authenticated().await {
-        println!("Not authenticated. Please run `quaid auth claude` first.");
-        return Ok(());
+        println!("Not authenticated. Please run `quaid auth lechat` first.");
+        return Ok(None);
     }
 
-    // Fetch all conversations
-    let conversations = provider.conversations().await?;
-    println!("Found {} conversations", conversations.len());
+    let conversations = provider.conversations().await.map_err(|e| match e {
+        ProviderError::TokenExpired => {
+            anyhow::anyhow!("Le Chat session expired. Please run `quaid auth lechat` again.")
+        }
+        e => e.into(),
+    })?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} chats", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
 
     let mut synced = 0;
-    let mut skipped = 0;
     let mut failed = 0;
 
     // Collect synced conversations for pipeline processing
     let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
 
-    for (i, conv) in conversations.iter().enumerate() {
-        // Check if we should skip this conversation
-        if should_skip(&conv.id, conv.updated_at, new_only, store) {
-            skipped += 1;
-            continue;
-        }
-
-        print!(
-            "\r[{}/{}] Syncing: {}...",
-            i + 1,
-            conversations.len(),
-            truncate(&conv.title, 40)
-        );
-
-        match provider.conversation_with_attachments(&conv.id).await {
-            Ok((full_conv, messages, attachments)) => {
-                // Save conversation to SQLite
-                store.save_conversation(account_id, &full_conv)?;
-
-                // Save messages to SQLite
-                let mut saved_messages = Vec::new();
-                for msg in messages {
-                    store.save_message(&msg)?;
-                    saved_messages.push(msg);
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
                 }
 
-                // Save attachments for later download
-                for attachment in attachments {
-                    store.save_attachment(&attachment)?;
-                }
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
 
                 // Collect for pipeline
-                pipeline_data.push((account_id.to_string(), full_conv, saved_messages));
-
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
                 synced += 1;
             }
             Err(e) => {
@@ -286,171 +1726,310 @@ async fn pull_claude(
                 failed += 1;
             }
         }
-
-        // Rate limiting - be nice to the API
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
 
-    if skipped > 0 {
+    if !skips.is_empty() {
         println!(
             "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
-            synced, skipped, failed
+            synced,
+            skips.len(),
+            failed
         );
     } else {
         println!("\n\nSync complete: {} synced, {} failed", synced, failed);
     }
 
-    // Download pending attachments
-    let pending = store.get_pending_attachments()?;
-    if !pending.is_empty() {
-        println!("\nDownloading {} attachments...", pending.len());
-
-        let attachments_dir = data_dir.join("attachments").join(account_id);
-        std::fs::create_dir_all(&attachments_dir)?;
-
-        for attachment in pending {
-            let path = attachments_dir.join(&attachment.filename);
-            match provider.download_attachment(&attachment, &path).await {
-                Ok(_) => {
-                    store.mark_attachment_downloaded(&attachment.id, path.to_str().unwrap_or(""))?;
-                    println!("  Downloaded: {}", attachment.filename);
-                }
-                Err(e) => {
-                    eprintln!("  Failed to download {}: {}", attachment.filename, e);
+    // Run pipeline for Parquet storage and embeddings
+    if !pipeline_data.is_empty() {
+        index_pulled(options, config, pipeline_data, cancel).await?;
+    }
+
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
+}
+
+async fn pull_deepseek(
+    account: &Account,
+    options: &PullOptions,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching chats from DeepSeek...");
+
+    let account_id = account.id.as_str();
+    let provider =
+        DeepSeekProvider::with_account_credentials(credentials.clone(), Some(&account.email))
+            .with_include_thinking(
+                options
+                    .provider_options
+                    .deepseek
+                    .include_thinking
+                    .unwrap_or(false),
+            );
+
+    if !provider.is_authenticated().await {
+        println!("Not authenticated. Please run `quaid auth deepseek` first.");
+        return Ok(None);
+    }
+
+    let conversations = provider.conversations().await.map_err(|e| match e {
+        ProviderError::TokenExpired => {
+            anyhow::anyhow!("DeepSeek session expired. Please run `quaid auth deepseek` again.")
+        }
+        e => e.into(),
+    })?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} chats", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
+
+    let mut synced = 0;
+    let mut failed = 0;
+
+    // Collect synced conversations for pipeline processing
+    let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
+
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
                 }
+
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
+
+                // Collect for pipeline
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
+                synced += 1;
+            }
+            Err(e) => {
+                eprintln!("\nError syncing {}: {}", conv.id, e);
+                failed += 1;
             }
         }
     }
 
+    if !skips.is_empty() {
+        println!(
+            "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
+            synced,
+            skips.len(),
+            failed
+        );
+    } else {
+        println!("\n\nSync complete: {} synced, {} failed", synced, failed);
+    }
+
     // Run pipeline for Parquet storage and embeddings
     if !pipeline_data.is_empty() {
-        run_pipeline(data_dir, pipeline_data)?;
+        index_pulled(options, config, pipeline_data, cancel).await?;
     }
 
-    Ok(())
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
 }
 
-async fn pull_fathom(
-    account_id: &str,
-    new_only: bool,
+async fn pull_otter(
+    account: &Account,
+    options: &PullOptions,
     store: &Store,
-    data_dir: &Path,
-) -> anyhow::Result<()> {
-    println!("Fetching meetings from Fathom (with transcripts)...");
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching meetings from Otter...");
 
-    let provider = FathomProvider::new();
+    let account_id = account.id.as_str();
+    let provider =
+        OtterProvider::with_account_credentials(credentials.clone(), Some(&account.email));
 
     if !provider.is_authenticated().await {
-        println!("Not authenticated. Please run `quaid auth fathom` first.");
-        return Ok(());
+        println!("Not authenticated. Please run `quaid auth otter` first.");
+        return Ok(None);
     }
 
-    // Fetch all meetings with transcripts in one batch (more efficient)
-    let meetings = provider.fetch_all_meetings_with_transcripts().await?;
-    println!("Found {} meetings", meetings.len());
+    let conversations = provider.conversations().await.map_err(|e| match e {
+        ProviderError::TokenExpired => {
+            anyhow::anyhow!("Otter session expired. Please run `quaid auth otter` again.")
+        }
+        e => e.into(),
+    })?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} meetings", listed);
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation_with_attachments(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
 
     let mut synced = 0;
-    let mut skipped = 0;
+    let mut failed = 0;
 
     // Collect synced conversations for pipeline processing
     let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
 
-    for (i, meeting) in meetings.iter().enumerate() {
-        let (conv, messages) = provider.meeting_to_data(meeting);
+    for (conv, result) in results {
+        match result {
+            Ok((full_conv, messages, attachments)) => {
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
+                }
 
-        // Check if we should skip this conversation
-        if should_skip(&conv.id, conv.updated_at, new_only, store) {
-            skipped += 1;
-            continue;
-        }
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
 
-        print!(
-            "\r[{}/{}] Syncing: {}...",
-            i + 1,
-            meetings.len(),
-            truncate(&meeting.display_title(), 40)
-        );
+                // Save attachments for later download
+                for attachment in attachments {
+                    store.save_attachment(&attachment)?;
+                }
 
-        store.save_conversation(account_id, &conv)?;
-        let mut saved_messages = Vec::new();
-        for msg in messages {
-            store.save_message(&msg)?;
-            saved_messages.push(msg);
+                // Collect for pipeline
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
+                synced += 1;
+            }
+            Err(e) => {
+                eprintln!("\nError syncing {}: {}", conv.id, e);
+                failed += 1;
+            }
         }
-
-        // Collect for pipeline
-        pipeline_data.push((account_id.to_string(), conv, saved_messages));
-        synced += 1;
     }
 
-    if skipped > 0 {
+    if !skips.is_empty() {
         println!(
-            "\n\nSync complete: {} synced, {} skipped (unchanged)",
-            synced, skipped
+            "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
+            synced,
+            skips.len(),
+            failed
         );
     } else {
-        println!("\n\nSync complete: {} meetings synced", synced);
+        println!("\n\nSync complete: {} synced, {} failed", synced, failed);
     }
 
+    // Download pending attachments
+    download_attachments(
+        &provider,
+        account_id,
+        store,
+        &config.data_dir,
+        &options.attachments,
+        config.media_workers,
+        cancel,
+    )
+    .await?;
+
     // Run pipeline for Parquet storage and embeddings
     if !pipeline_data.is_empty() {
-        run_pipeline(data_dir, pipeline_data)?;
+        index_pulled(options, config, pipeline_data, cancel).await?;
     }
 
-    Ok(())
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
 }
 
-async fn pull_granola(
-    account_id: &str,
-    new_only: bool,
+async fn pull_poe(
+    account: &Account,
+    options: &PullOptions,
     store: &Store,
-    data_dir: &Path,
-) -> anyhow::Result<()> {
-    println!("Fetching meeting notes from Granola...");
+    credentials: &Arc<dyn CredentialStore>,
+    config: &PipelineConfig,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Option<Pulled>> {
+    println!("Fetching chats from Poe...");
 
-    let provider = GranolaProvider::new();
+    let account_id = account.id.as_str();
+    let provider = PoeProvider::with_account_credentials(credentials.clone(), Some(&account.email));
 
     if !provider.is_authenticated().await {
-        println!("Not authenticated. Please run `quaid auth granola` first.");
-        println!("(Make sure you're logged into the Granola desktop app)");
-        return Ok(());
+        println!("Not authenticated. Please run `quaid auth poe` first.");
+        return Ok(None);
     }
 
-    let conversations = provider.conversations().await?;
-    println!("Found {} documents", conversations.len());
+    let conversations = provider.conversations().await.map_err(|e| match e {
+        ProviderError::TokenExpired => {
+            anyhow::anyhow!("Poe session expired. Please run `quaid auth poe` again.")
+        }
+        e => e.into(),
+    })?;
+    let listed = conversations.len();
+    let remote_ids = Some(listing_ids(&conversations));
+    println!("Found {} chats", listed);
+
+    // One project per bot, taken from the chats just listed
+    sync_projects(&provider, store).await?;
+
+    let (to_fetch, mut skips) = select_for_sync(conversations, options.new_only, store, config)?;
+    let fetch_config =
+        FetchConfig::for_provider(&provider.id(), config.fetch_workers).with_cancel(cancel.clone());
+    let results = fetch_all(
+        to_fetch,
+        &fetch_config,
+        |id| {
+            let provider = &provider;
+            async move { provider.conversation(&id).await }
+        },
+        |done, total, conv| print_progress(options, "Synced", done, total, &conv.title),
+    )
+    .await;
 
     let mut synced = 0;
-    let mut skipped = 0;
     let mut failed = 0;
 
     // Collect synced conversations for pipeline processing
     let mut pipeline_data: Vec<(String, Conversation, Vec<Message>)> = Vec::new();
 
-    for (i, conv) in conversations.iter().enumerate() {
-        // Check if we should skip this conversation
-        if should_skip(&conv.id, conv.updated_at, new_only, store) {
-            skipped += 1;
-            continue;
-        }
-
-        print!(
-            "\r[{}/{}] Syncing: {}...",
-            i + 1,
-            conversations.len(),
-            truncate(&conv.title, 40)
-        );
-
-        match provider.conversation(&conv.id).await {
+    for (conv, result) in results {
+        match result {
             Ok((full_conv, messages)) => {
-                store.save_conversation(account_id, &full_conv)?;
-                let mut saved_messages = Vec::new();
-                for msg in messages {
-                    store.save_message(&msg)?;
-                    saved_messages.push(msg);
+                if let Some(skip) =
+                    unchanged_skip(store, config, account_id, &full_conv, &messages)?
+                {
+                    skips.push(skip);
+                    continue;
                 }
 
+                save_pulled(store, options, account_id, &full_conv, &messages)?;
+
                 // Collect for pipeline
-                pipeline_data.push((account_id.to_string(), full_conv, saved_messages));
+                pipeline_data.push((account_id.to_string(), full_conv, messages));
                 synced += 1;
             }
             Err(e) => {
@@ -458,14 +2037,14 @@ async fn pull_granola(
                 failed += 1;
             }
         }
-
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
 
-    if skipped > 0 {
+    if !skips.is_empty() {
         println!(
             "\n\nSync complete: {} synced, {} skipped (unchanged), {} failed",
-            synced, skipped, failed
+            synced,
+            skips.len(),
+            failed
         );
     } else {
         println!("\n\nSync complete: {} synced, {} failed", synced, failed);
@@ -473,12 +2052,201 @@ async fn pull_granola(
 
     // Run pipeline for Parquet storage and embeddings
     if !pipeline_data.is_empty() {
-        run_pipeline(data_dir, pipeline_data)?;
+        index_pulled(options, config, pipeline_data, cancel).await?;
+    }
+
+    Ok(Some(Pulled {
+        listed,
+        remote_ids,
+        skips,
+    }))
+}
+
+async fn sync_projects(provider: &dyn Provider, store: &Store) -> anyhow::Result<Vec<Project>> {
+    match provider.projects().await {
+        Ok(projects) => {
+            for project in &projects {
+                store.save_project(project)?;
+            }
+            if !projects.is_empty() {
+                println!("Found {} projects", projects.len());
+            }
+            Ok(projects)
+        }
+        Err(e) => {
+            eprintln!("Couldn't list projects: {}", e);
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// The main listing plus the conversations listed under projects, each once
+///
+/// A conversation listed in both keeps the project's copy, which names its
+/// project, with the archived flag and latest update time of either.
+fn merge_project_conversations(
+    listed: Vec<Conversation>,
+    in_projects: Vec<Conversation>,
+) -> Vec<Conversation> {
+    let mut by_id: HashMap<String, Conversation> =
+        in_projects.into_iter().map(|c| (c.id.clone(), c)).collect();
+    let mut merged = Vec::with_capacity(listed.len() + by_id.len());
+    for conv in listed {
+        match by_id.remove(&conv.id) {
+            Some(project_copy) => merged.push(Conversation {
+                updated_at: project_copy.updated_at.max(conv.updated_at),
+                is_archived: project_copy.is_archived || conv.is_archived,
+                ..project_copy
+            }),
+            None => merged.push(conv),
+        }
+    }
+    let mut only_in_projects: Vec<_> = by_id.into_values().collect();
+    only_in_projects.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then(a.id.cmp(&b.id)));
+    merged.extend(only_in_projects);
+    merged
+}
+
+/// Download the account's pending attachments, skipping content already on disk
+///
+/// Up to `workers` files download at once, capped at what the provider
+/// tolerates; results are written to the store from this task only. Files
+/// that can't be downloaded at all (gone, or like Fathom recordings on lower
+/// plans, not offered) are marked failed with the reason, for
+/// `quaid attachments retry`. Others stay pending for the next pull, which
+/// resumes partial downloads; every failed attempt is counted.
+pub async fn download_attachments(
+    provider: &dyn Provider,
+    account_id: &str,
+    store: &Store,
+    data_dir: &Path,
+    options: &AttachmentOptions,
+    workers: usize,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let mut pending = store.get_pending_attachments_for_account(account_id)?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    if options.skip {
+        println!(
+            "\nLeaving {} attachments pending (--skip-attachments)",
+            pending.len()
+        );
+        return Ok(());
+    }
+
+    let mut downloader = Downloader::new();
+    let mut too_large = 0;
+    if let Some(max_size) = options.max_size {
+        downloader = downloader.with_max_size(max_size);
+        let before = pending.len();
+        pending.retain(|a| a.size_bytes <= max_size);
+        too_large = before - pending.len();
+    }
+    let workers = workers.min(provider.id().max_concurrent_requests()).max(1);
+    println!("\nDownloading {} attachments...", pending.len());
+
+    let attachments_dir = data_dir.join("attachments").join(account_id);
+    std::fs::create_dir_all(&attachments_dir)?;
+
+    let mut duplicates = 0;
+    for round in download_rounds(pending) {
+        // Same provider file already downloaded for another message
+        let mut queued = Vec::with_capacity(round.len());
+        for attachment in round {
+            match store.find_downloaded_copy(&attachment)? {
+                Some(copy) => {
+                    store.mark_attachment_duplicate(&attachment.id, &copy)?;
+                    duplicates += 1;
+                }
+                None => queued.push(attachment),
+            }
+        }
+
+        let downloader = &downloader;
+        let attachments_dir = &attachments_dir;
+        let mut downloads = stream::iter(queued)
+            .map(|attachment| async move {
+                let path = attachments_dir.join(&attachment.filename);
+                let result = downloader.download(provider, &attachment, &path).await;
+                (attachment, path, result)
+            })
+            .buffer_unordered(workers);
+
+        loop {
+            // Dropped downloads leave their partial file to resume from
+            let next = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => break,
+                next = downloads.next() => next,
+            };
+            let Some((attachment, path, result)) = next else {
+                break;
+            };
+            match result {
+                Ok(_) => match store.record_attachment_download(&attachment.id, &path)? {
+                    AttachmentDownload::Stored { .. } => {
+                        println!("  Downloaded: {}", attachment.filename);
+                    }
+                    AttachmentDownload::Duplicate { .. } => duplicates += 1,
+                },
+                Err(DownloadError::TooLarge(_)) => too_large += 1,
+                Err(e) if e.is_permanent() => {
+                    store.mark_attachment_failed(&attachment.id, &e.to_string())?;
+                    eprintln!("  Failed {}: {}", attachment.filename, e);
+                }
+                Err(e) => {
+                    store.record_attachment_error(&attachment.id, &e.to_string())?;
+                    eprintln!("  Skipped {} (will retry): {}", attachment.filename, e);
+                }
+            }
+        }
+        if cancel.is_cancelled() {
+            break;
+        }
     }
 
+    if duplicates > 0 {
+        println!("  {} duplicates share a file already on disk", duplicates);
+    }
+    if too_large > 0 {
+        println!(
+            "  {} attachments over --max-attachment-size left pending",
+            too_large
+        );
+    }
     Ok(())
 }
 
+/// Split `pending` into batches that can download side by side
+///
+/// Within a batch no two attachments share a filename, which would have them
+/// write the same partial file, or a provider file, which the later batch
+/// then finds on disk instead of fetching again. Order is kept otherwise.
+fn download_rounds(pending: Vec<Attachment>) -> Vec<Vec<Attachment>> {
+    let mut rounds: Vec<Vec<Attachment>> = Vec::new();
+    let mut claimed: Vec<HashSet<String>> = Vec::new();
+    for attachment in pending {
+        let keys = [
+            format!("name:{}", attachment.filename),
+            format!("url:{}", attachment.download_url),
+        ];
+        // After the last round holding either key, so dependants stay in order
+        let round = claimed
+            .iter()
+            .rposition(|taken| keys.iter().any(|key| taken.contains(key)))
+            .map_or(0, |last| last + 1);
+        if round == rounds.len() {
+            rounds.push(Vec::new());
+            claimed.push(HashSet::new());
+        }
+        claimed[round].extend(keys);
+        rounds[round].push(attachment);
+    }
+    rounds
+}
+
 fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -487,23 +2255,45 @@ fn truncate(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Index what a pull fetched, telling its operation first
+async fn index_pulled(
+    options: &PullOptions,
+    config: &PipelineConfig,
+    conversations: Vec<(String, Conversation, Vec<Message>)>,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    if let Some(progress) = &options.progress {
+        progress.indexing(conversations.len());
+    }
+    run_pipeline(config, conversations, cancel).await
+}
+
 /// Run the pipeline for Parquet storage and embeddings
-fn run_pipeline(
-    data_dir: &Path,
+pub async fn run_pipeline(
+    config: &PipelineConfig,
     conversations: Vec<(String, Conversation, Vec<Message>)>,
+    cancel: &CancellationToken,
 ) -> anyhow::Result<()> {
     let count = conversations.len();
     println!("\nIndexing {} conversations...", count);
 
-    let config = PipelineConfig::new(data_dir);
-    let pipeline = Pipeline::new(config);
+    let pipeline = Pipeline::new(config.clone());
 
-    match pipeline.run(conversations) {
+    match pipeline.run(conversations, cancel).await {
         Ok(result) => {
-            println!(
-                "Indexed: {} conversations, {} messages, {} embeddings",
-                result.conversations_synced, result.messages_processed, result.embeddings_generated
-            );
+            if result.cancelled {
+                println!(
+                    "Cancelled: indexed {} of {} conversations. Run `quaid pull --new-only` to finish.",
+                    result.conversations_synced, count
+                );
+            } else {
+                println!(
+                    "Indexed: {} conversations, {} messages, {} embeddings",
+                    result.conversations_synced,
+                    result.messages_processed,
+                    result.embeddings_generated
+                );
+            }
             if !result.errors.is_empty() {
                 eprintln!("Pipeline errors: {}", result.errors.len());
                 for err in result.errors.iter().take(3) {
@@ -512,8 +2302,8 @@ fn run_pipeline(
             }
 
             // Auto-compact embeddings for faster semantic search
-            if result.embeddings_generated > 0 {
-                compact_embeddings(data_dir);
+            if result.embeddings_generated > 0 && !config.low_memory {
+                compact_embeddings(&config.data_dir);
             }
         }
         Err(e) => {
@@ -542,3 +2332,242 @@ fn compact_embeddings(data_dir: &Path) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn attachment(id: &str, filename: &str, download_url: &str) -> Attachment {
+        Attachment {
+            id: id.to_string(),
+            message_id: "msg-1".to_string(),
+            filename: filename.to_string(),
+            mime_type: "image/png".to_string(),
+            size_bytes: 1,
+            download_url: download_url.to_string(),
+            width: None,
+            height: None,
+            sha256: None,
+        }
+    }
+
+    #[test]
+    fn test_fathom_created_after() {
+        let at = |day| Utc.with_ymd_and_hms(2025, 1, day, 0, 0, 0).single();
+        let state = |cursor: Option<&str>, last_synced_at| SyncCursor {
+            provider: "fathom".to_string(),
+            account_id: "fathom-abc".to_string(),
+            cursor: cursor.map(str::to_string),
+            last_synced_at,
+        };
+
+        // A full pull lists everything, or everything since --since
+        assert_eq!(fathom_created_after(None, false, None, at(9)), None);
+        assert_eq!(fathom_created_after(at(5), false, None, at(9)), at(5));
+
+        // --new-only starts after the newest meeting synced
+        let finished = state(None, at(7));
+        assert_eq!(
+            fathom_created_after(None, true, Some(&finished), at(9)),
+            at(7)
+        );
+        assert_eq!(fathom_created_after(None, true, None, at(9)), at(9));
+        assert_eq!(fathom_created_after(None, true, None, None), None);
+        // ...unless --since is later
+        assert_eq!(
+            fathom_created_after(at(8), true, Some(&finished), None),
+            at(8)
+        );
+
+        // A resumed listing keeps the floor its cursor was issued for
+        let first_pull = state(Some("eyJwYWdlIjoyfQ"), None);
+        assert_eq!(
+            fathom_created_after(None, true, Some(&first_pull), at(9)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_granola_updated_after() {
+        let newest = Utc.with_ymd_and_hms(2025, 6, 12, 16, 5, 0).single();
+        assert_eq!(granola_updated_after(false, newest), None);
+        assert_eq!(granola_updated_after(true, None), None);
+        assert_eq!(
+            granola_updated_after(true, newest),
+            Utc.with_ymd_and_hms(2025, 6, 12, 16, 0, 0).single()
+        );
+    }
+
+    #[test]
+    fn test_reconcile_archived() {
+        let doc = |id: &str, is_archived| Conversation {
+            id: id.to_string(),
+            provider_id: "granola".to_string(),
+            title: id.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived,
+        };
+        let stored = [
+            doc("kept", false),
+            doc("deleted", false),
+            doc("deleted-before", true),
+            doc("back", true),
+        ];
+        let listed = [doc("kept", false), doc("back", false), doc("new", false)];
+
+        let (archive, restore) = reconcile_archived(&stored, &listed);
+        assert_eq!(archive, ["deleted"]);
+        assert_eq!(restore, ["back"]);
+    }
+
+    #[test]
+    fn test_download_rounds_keep_shared_files_apart() {
+        let pending = vec![
+            attachment("a", "chart.png", "file-1"),
+            attachment("b", "photo.png", "file-2"),
+            attachment("c", "chart.png", "file-3"),
+            attachment("d", "logo.png", "file-2"),
+            attachment("e", "notes.png", "file-4"),
+            attachment("f", "chart.png", "file-5"),
+        ];
+        let rounds: Vec<Vec<String>> = download_rounds(pending)
+            .into_iter()
+            .map(|round| round.into_iter().map(|a| a.id).collect())
+            .collect();
+        assert_eq!(rounds, vec![vec!["a", "b", "e"], vec!["c", "d"], vec!["f"]]);
+        assert!(download_rounds(Vec::new()).is_empty());
+    }
+
+    fn conversation(id: &str, day: u32, project: Option<&str>) -> Conversation {
+        let at = Utc.with_ymd_and_hms(2024, 6, day, 12, 0, 0).unwrap();
+        Conversation {
+            id: id.to_string(),
+            provider_id: "chatgpt".to_string(),
+            title: id.to_string(),
+            created_at: at,
+            updated_at: at,
+            model: None,
+            project_id: project.map(|p| format!("g-p-{}", p)),
+            project_name: project.map(str::to_string),
+            is_archived: false,
+        }
+    }
+
+    #[test]
+    fn test_project_conversations_are_merged_once() {
+        let mut archived = conversation("both-archived", 1, None);
+        archived.is_archived = true;
+        let listed = vec![
+            conversation("plain", 5, None),
+            conversation("both", 9, None),
+            archived,
+        ];
+        let in_projects = vec![
+            conversation("both", 7, Some("Thesis")),
+            conversation("project-old", 2, Some("Thesis")),
+            conversation("both-archived", 1, Some("Taxes")),
+            conversation("project-new", 8, Some("Taxes")),
+        ];
+
+        let merged = merge_project_conversations(listed, in_projects);
+        let summary: Vec<_> = merged
+            .iter()
+            .map(|c| (c.id.as_str(), c.project_name.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("plain", None),
+                ("both", Some("Thesis")),
+                ("both-archived", Some("Taxes")),
+                ("project-new", Some("Taxes")),
+                ("project-old", Some("Thesis")),
+            ]
+        );
+        assert_eq!(
+            merged[1].updated_at,
+            conversation("both", 9, None).updated_at
+        );
+        assert!(merged[2].is_archived);
+    }
+
+    #[test]
+    fn test_unchanged_pull_still_updates_metadata() {
+        use chrono::TimeZone;
+        use quaid_core::providers::{Account, MessageContent, ProviderId, Role};
+        use quaid_core::storage::SkipRule;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = PipelineConfig::new(dir.path());
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::chatgpt(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        let conv = Conversation {
+            id: "conv-1".to_string(),
+            provider_id: "chatgpt".to_string(),
+            title: "Notes".to_string(),
+            created_at: chrono::Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap(),
+            updated_at: chrono::Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        let messages = vec![Message {
+            id: "msg-1".to_string(),
+            conversation_id: conv.id.clone(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: "Hello".to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        }];
+        store.save_conversation("user-1", &conv).unwrap();
+        store.save_message(&messages[0]).unwrap();
+
+        // Not written to Parquet yet, so it is saved in full
+        let moved = Conversation {
+            updated_at: chrono::Utc.with_ymd_and_hms(2024, 6, 9, 12, 0, 0).unwrap(),
+            model: Some("gpt-4o".to_string()),
+            project_id: Some("g-p-thesis".to_string()),
+            project_name: Some("Thesis".to_string()),
+            is_archived: true,
+            ..conv.clone()
+        };
+        let skip = unchanged_skip(&store, &config, "user-1", &moved, &messages).unwrap();
+        assert!(skip.is_none());
+
+        let path = parquet_path(&config, &conv);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"").unwrap();
+        let skip = unchanged_skip(&store, &config, "user-1", &moved, &messages).unwrap();
+        assert_eq!(skip.unwrap().rule, SkipRule::Identical);
+
+        let stored = store.get_conversation("conv-1").unwrap().unwrap();
+        assert_eq!(stored.updated_at, moved.updated_at);
+        assert!(stored.is_archived);
+        assert_eq!(stored.project_name.as_deref(), Some("Thesis"));
+        assert_eq!(stored.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(store.get_messages("conv-1").unwrap().len(), 1);
+
+        // --new-only now sees it as up to date
+        let decision = sync_decision(&moved, true, &store, &config).unwrap();
+        assert_eq!(decision.rule, SkipRule::NotUpdated);
+    }
+}
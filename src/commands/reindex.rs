@@ -0,0 +1,20 @@
+use quaid_core::Store;
+use std::time::Instant;
+
+/// Rebuild the full-text search index from the messages in SQLite
+pub fn run(store: &Store) -> anyhow::Result<()> {
+    let total = store.stats()?.messages;
+    println!("Rebuilding the search index from {} messages...", total);
+
+    let started = Instant::now();
+    let indexed = store.rebuild_fts()?;
+    println!(
+        "Indexed {} messages in {:.1}s",
+        indexed,
+        started.elapsed().as_secs_f64()
+    );
+    if indexed < total {
+        println!("{} messages have no text to index", total - indexed);
+    }
+    Ok(())
+}
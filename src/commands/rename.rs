@@ -0,0 +1,41 @@
+use quaid_core::titles::{heuristic_title, smart_title, CorpusStats};
+use quaid_core::Store;
+use std::path::Path;
+
+pub fn run(
+    id: &str,
+    title: Option<&str>,
+    auto: bool,
+    smart: bool,
+    store: &Store,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let conv = store
+        .get_conversation(id)?
+        .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", id))?;
+
+    let new_title = match title {
+        Some(title) => title.trim().to_string(),
+        None if auto => {
+            let messages = store.get_messages(id)?;
+            let generated = if smart {
+                let corpus = CorpusStats::load_or_refresh(store, data_dir)?;
+                smart_title(&messages, &corpus)
+            } else {
+                heuristic_title(&messages)
+            };
+            generated.ok_or_else(|| {
+                anyhow::anyhow!("Conversation {} has no user message to title it from", id)
+            })?
+        }
+        None => anyhow::bail!("Give a new title or use --auto"),
+    };
+
+    if new_title.is_empty() {
+        anyhow::bail!("Title can't be empty");
+    }
+
+    store.rename_conversation(id, &new_title)?;
+    println!("Renamed: {}\n     -> {}", conv.title, new_title);
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use quaid_core::sample::{self, SampleOptions};
+use quaid_core::Store;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+pub fn run(
+    store: &Store,
+    options: &SampleOptions,
+    format: &str,
+    output: &Path,
+) -> anyhow::Result<()> {
+    if format != "openai-jsonl" {
+        anyhow::bail!("Unknown format: {}. Supported: openai-jsonl", format);
+    }
+
+    let ids = sample::select(store, options)?;
+    if ids.is_empty() {
+        println!("No conversations to sample.");
+        return Ok(());
+    }
+
+    let file = BufWriter::new(File::create(output)?);
+    let report = sample::write_openai_jsonl(store, &ids, options, file)?;
+
+    println!(
+        "Wrote {} conversations to {}",
+        report.written,
+        output.display()
+    );
+    if report.skipped > 0 {
+        println!("Skipped {} without an assistant reply", report.skipped);
+    }
+    println!("Estimated tokens: ~{}", report.estimated_tokens);
+    println!("Seed: {} (pass --seed to reproduce)", options.seed);
+    Ok(())
+}
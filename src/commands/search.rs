@@ -1,128 +1,363 @@
-use quaid_core::embeddings::{EmbeddingModel, Embedder};
+use super::parse_date;
+use crate::config::EmbedderChoice;
+use quaid_core::embeddings::rerank::{self, CrossEncoder, RERANK_CANDIDATE_FACTOR};
+use quaid_core::providers::Role;
 use quaid_core::storage::duckdb::DuckDbQuery;
-use quaid_core::storage::ParquetStorageConfig;
-use quaid_core::Store;
+use quaid_core::storage::{
+    ConversationHits, ParquetStorageConfig, SearchFilters, SearchHit, SemanticSearchResult,
+};
+use quaid_core::{CredentialStore, Store};
+use std::io::IsTerminal;
 use std::path::Path;
+use std::sync::Arc;
 
+/// Filter flags as given on the command line
+pub struct FilterArgs<'a> {
+    pub provider: Option<&'a str>,
+    pub account: Option<&'a str>,
+    pub since: Option<&'a str>,
+    pub until: Option<&'a str>,
+    pub roles: &'a [String],
+    pub exclude_archived: bool,
+    pub attachments: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     query: &str,
     limit: usize,
     semantic: bool,
     hybrid: bool,
+    rerank: bool,
+    regex: bool,
+    json: bool,
+    filter_args: FilterArgs<'_>,
     store: &Store,
+    endpoint: Option<&str>,
+    credentials: &Arc<dyn CredentialStore>,
     data_dir: &Path,
 ) -> anyhow::Result<()> {
-    if semantic || hybrid {
-        run_semantic_search(query, limit, hybrid, store, data_dir)
-    } else {
-        run_fts_search(query, limit, store)
+    if rerank && !(semantic || hybrid) {
+        anyhow::bail!("--rerank needs --semantic or --hybrid");
     }
-}
+    if regex && (semantic || hybrid) {
+        anyhow::bail!("--regex can't be combined with --semantic or --hybrid");
+    }
+    if filter_args.attachments && (semantic || hybrid || regex) {
+        anyhow::bail!("--attachments only works with full-text search");
+    }
+    let filters = build_filters(filter_args, store)?;
 
-/// Full-text search using SQLite FTS
-fn run_fts_search(query: &str, limit: usize, store: &Store) -> anyhow::Result<()> {
-    println!("Searching for: {}\n", query);
+    if !json {
+        let mode = if hybrid {
+            " (hybrid)"
+        } else if semantic {
+            " (semantic)"
+        } else if regex {
+            " (regex)"
+        } else {
+            ""
+        };
+        println!("Searching{} for: {}\n", mode, query);
+    }
 
-    let results = store.search(query, limit)?;
+    let groups = if semantic || hybrid {
+        run_semantic_search(
+            query,
+            limit,
+            hybrid,
+            rerank,
+            &filters,
+            store,
+            endpoint,
+            credentials,
+            data_dir,
+        )?
+    } else if regex {
+        run_regex_search(query, limit, &filters, store, data_dir)?
+    } else {
+        run_fts_search(query, limit, &filters, store)?
+    };
 
-    if results.is_empty() {
-        println!("No results found.");
+    if json {
+        println!("{}", serde_json::to_string_pretty(&groups)?);
         return Ok(());
     }
 
-    println!("Found {} results:\n", results.len());
-
-    for (conv_id, snippet) in results {
-        if let Ok(Some(conv)) = store.get_conversation(&conv_id) {
-            println!("📝 {}", conv.title);
-            println!("   {}", snippet);
-            println!("   ID: {}", conv.id);
-            println!();
+    if groups.is_empty() {
+        println!("No results found.");
+        if semantic || hybrid {
+            println!("\nTip: Run `quaid index embed` to index conversations pulled before embeddings existed.");
         }
+        return Ok(());
     }
 
+    print_results(&groups, std::io::stdout().is_terminal());
     Ok(())
 }
 
+pub fn build_filters(args: FilterArgs<'_>, store: &Store) -> anyhow::Result<SearchFilters> {
+    let account_id = match args.account {
+        Some(email) => {
+            let accounts = super::select_accounts(store, args.provider, Some(email))?;
+            if accounts.len() > 1 {
+                anyhow::bail!(
+                    "{} is signed in to several providers; pick one with --provider",
+                    email
+                );
+            }
+            accounts.into_iter().next().map(|a| a.id)
+        }
+        None => None,
+    };
+
+    let roles = args
+        .roles
+        .iter()
+        .map(|r| {
+            Role::parse(&r.to_lowercase()).ok_or_else(|| {
+                anyhow::anyhow!("Unknown role: {}. Use user, assistant, system or tool", r)
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(SearchFilters {
+        provider: args.provider.map(str::to_string),
+        account_id,
+        since: args.since.map(|s| parse_date(s, false)).transpose()?,
+        until: args.until.map(|s| parse_date(s, true)).transpose()?,
+        roles,
+        exclude_archived: args.exclude_archived,
+        include_attachments: args.attachments,
+    })
+}
+
+/// Snippets shown for each matching conversation
+const SNIPPETS_PER_CONVERSATION: usize = 3;
+
+/// Full-text search using SQLite FTS
+pub fn run_fts_search(
+    query: &str,
+    limit: usize,
+    filters: &SearchFilters,
+    store: &Store,
+) -> anyhow::Result<Vec<ConversationHits>> {
+    let hits = store.search(query, limit * SNIPPETS_PER_CONVERSATION, filters)?;
+    let mut groups = ConversationHits::group(hits, SNIPPETS_PER_CONVERSATION);
+    groups.truncate(limit);
+    Ok(groups)
+}
+
 /// Semantic or hybrid search using embeddings
-fn run_semantic_search(
+///
+/// The query is embedded by the embedder that built the index, reached at
+/// `endpoint` when one is configured.
+#[allow(clippy::too_many_arguments)]
+pub fn run_semantic_search(
     query: &str,
     limit: usize,
     hybrid: bool,
+    rerank: bool,
+    filters: &SearchFilters,
     store: &Store,
+    endpoint: Option<&str>,
+    credentials: &Arc<dyn CredentialStore>,
     data_dir: &Path,
-) -> anyhow::Result<()> {
-    let mode = if hybrid { "hybrid" } else { "semantic" };
-    println!("Searching ({}) for: {}\n", mode, query);
+) -> anyhow::Result<Vec<ConversationHits>> {
+    let config = ParquetStorageConfig::new(data_dir);
+    if config.list_embedding_providers()?.is_empty() {
+        anyhow::bail!(
+            "No embeddings index found. Run `quaid index embed` to build it from your stored conversations."
+        );
+    }
 
-    // Load the embedding model
-    let models_dir = data_dir.join("models");
-    let embedder = match EmbeddingModel::load_or_download(&models_dir) {
-        Ok(model) => model,
-        Err(e) => {
-            eprintln!("Failed to load embedding model: {}", e);
-            eprintln!("Run `quaid pull` first to download the model.");
-            return Ok(());
-        }
+    // Queries must be embedded by the same model that built the index
+    let choice = EmbedderChoice {
+        endpoint: endpoint.map(str::to_string),
+        ..Default::default()
     };
+    let backend = super::embedding_backend(&choice, data_dir)?;
+    let embedder = backend.load(data_dir, credentials).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to load {} embedder: {}. Run `quaid pull` first to download the model.",
+            backend.name(),
+            e
+        )
+    })?;
 
     // Generate query embedding
-    let query_embedding = match embedder.embed(query) {
-        Ok(emb) => emb,
-        Err(e) => {
-            eprintln!("Failed to generate query embedding: {}", e);
-            return Ok(());
-        }
-    };
+    let query_embedding = embedder
+        .embed(query)
+        .map_err(|e| anyhow::anyhow!("Failed to generate query embedding: {}", e))?;
 
     // Create DuckDB query interface
-    let config = ParquetStorageConfig::new(data_dir);
-    let duckdb = match DuckDbQuery::new(config) {
-        Ok(db) => db,
-        Err(e) => {
-            eprintln!("Failed to create query interface: {}", e);
-            return Ok(());
-        }
-    };
+    let duckdb = DuckDbQuery::new(config)
+        .map_err(|e| anyhow::anyhow!("Failed to create query interface: {}", e))?;
 
-    // Perform search
-    let results = if hybrid {
-        duckdb.search_hybrid(query, &query_embedding, limit)?
+    // Perform search, widening the candidate pool when reranking
+    let mut fetch = limit * SNIPPETS_PER_CONVERSATION;
+    if rerank {
+        fetch *= RERANK_CANDIDATE_FACTOR;
+    }
+    let mut results = if hybrid {
+        duckdb.search_hybrid(query, &query_embedding, fetch, filters)?
     } else {
-        duckdb.search_semantic(&query_embedding, limit)?
+        duckdb.search_semantic(&query_embedding, fetch, filters)?
     };
+    if rerank {
+        results = rerank_results(query, results, data_dir);
+    }
 
-    if results.is_empty() {
-        println!("No results found.");
-        println!("\nTip: Run `quaid pull` to index your conversations first.");
-        return Ok(());
+    let hits = results
+        .into_iter()
+        .filter_map(|result| {
+            stored_hit(
+                &result.conversation_id,
+                truncate(&result.chunk_text, 160),
+                result.score as f64,
+                filters,
+                store,
+            )
+        })
+        .collect();
+
+    let mut groups = ConversationHits::group(hits, SNIPPETS_PER_CONVERSATION);
+    groups.truncate(limit);
+    Ok(groups)
+}
+
+/// Regex search over the parquet files with DuckDB
+///
+/// Scans every message rather than using an index, so it is much slower than
+/// full-text search.
+pub fn run_regex_search(
+    pattern: &str,
+    limit: usize,
+    filters: &SearchFilters,
+    store: &Store,
+    data_dir: &Path,
+) -> anyhow::Result<Vec<ConversationHits>> {
+    let duckdb = DuckDbQuery::new(ParquetStorageConfig::new(data_dir))
+        .map_err(|e| anyhow::anyhow!("Failed to create query interface: {}", e))?;
+    let results =
+        duckdb.search_messages_regex(pattern, limit * SNIPPETS_PER_CONVERSATION, filters)?;
+
+    let hits = results
+        .into_iter()
+        .filter_map(|result| {
+            stored_hit(&result.conversation_id, result.snippet, 0.0, filters, store)
+        })
+        .collect();
+
+    let mut groups = ConversationHits::group(hits, SNIPPETS_PER_CONVERSATION);
+    groups.truncate(limit);
+    Ok(groups)
+}
+
+/// A hit on a conversation found in the parquet files, if it's in the database
+///
+/// Conversations missing from SQLite can't be opened, so they are dropped.
+/// Parquet files carry no account, so --account is applied here.
+fn stored_hit(
+    conversation_id: &str,
+    snippet: String,
+    rank: f64,
+    filters: &SearchFilters,
+    store: &Store,
+) -> Option<SearchHit> {
+    if let Some(account_id) = &filters.account_id {
+        let owner = store.conversation_account_id(conversation_id).ok()?;
+        if owner.as_ref() != Some(account_id) {
+            return None;
+        }
     }
+    let conv = store.get_conversation(conversation_id).ok()??;
+    Some(SearchHit {
+        conversation_id: conv.id,
+        title: conv.title,
+        provider_id: conv.provider_id,
+        updated_at: conv.updated_at,
+        snippet,
+        rank,
+        attachment: None,
+    })
+}
 
-    println!("Found {} results:\n", results.len());
+/// Reorder candidates with the cross-encoder, keeping vector order if it can't run
+fn rerank_results(
+    query: &str,
+    results: Vec<SemanticSearchResult>,
+    data_dir: &Path,
+) -> Vec<SemanticSearchResult> {
+    let reranker = match CrossEncoder::load_or_download(data_dir.join("models")) {
+        Ok(reranker) => reranker,
+        Err(e) => {
+            eprintln!("Reranker unavailable ({}), using vector order", e);
+            return results;
+        }
+    };
+    let fallback = results.clone();
+    rerank::rerank(query, results, &reranker).unwrap_or_else(|e| {
+        eprintln!("Reranking failed ({}), using vector order", e);
+        fallback
+    })
+}
 
-    for result in results {
-        // Get conversation details
-        if let Ok(Some(conv)) = store.get_conversation(&result.conversation_id) {
-            println!("📝 {} (score: {:.3})", conv.title, result.score);
-            println!("   {}", truncate(&result.chunk_text, 80));
-            println!("   ID: {}", conv.id);
-            println!();
-        } else {
-            // Conversation not in SQLite, show basic info
-            println!("📝 (score: {:.3})", result.score);
-            println!("   {}", truncate(&result.chunk_text, 80));
-            println!("   ID: {}", result.conversation_id);
-            println!();
+fn print_results(groups: &[ConversationHits], highlight: bool) {
+    println!("Found {} conversations:\n", groups.len());
+
+    for group in groups {
+        println!(
+            "[{}] {}  ({})",
+            group.provider_id,
+            group.title,
+            group.updated_at.format("%Y-%m-%d")
+        );
+        for snippet in &group.snippets {
+            println!("   {}", render_snippet(snippet, highlight));
         }
+        let more = group.total_hits - group.snippets.len();
+        if more > 0 {
+            println!("   (+{} more matches)", more);
+        }
+        println!("   ID: {}", group.conversation_id);
+        println!();
     }
+}
 
-    Ok(())
+/// Turn FTS `<mark>` tags into terminal highlighting, or drop them
+fn render_snippet(snippet: &str, highlight: bool) -> String {
+    let (open, close) = if highlight {
+        ("\x1b[1;33m", "\x1b[0m")
+    } else {
+        ("", "")
+    };
+    snippet
+        .replace("<mark>", open)
+        .replace("</mark>", close)
+        .replace('\n', " ")
 }
 
 fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
+    if s.chars().count() <= max_len {
         s.to_string()
     } else {
-        format!("{}...", &s[..max_len - 3])
+        let cut: String = s.chars().take(max_len - 3).collect();
+        format!("{}...", cut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_snippet() {
+        let snippet = "about <mark>kubernetes</mark>\nclusters";
+        assert_eq!(render_snippet(snippet, false), "about kubernetes clusters");
+        assert_eq!(
+            render_snippet(snippet, true),
+            "about \x1b[1;33mkubernetes\x1b[0m clusters"
+        );
     }
 }
@@ -0,0 +1,1541 @@
+//! JSON API over the local store, for building UIs on top of quaid
+//!
+//! With `--ui`, the built-in viewer from [`quaid_core::web`] is served at `/`
+//! as well. Pulls can be started over `POST /operations/pull` and followed as
+//! server-sent events, and providers with webhooks can post to
+//! `/ingest/{provider}` to sync one conversation. Both run in the background,
+//! each on a thread and store connection of its own.
+//!
+//! Binds to localhost unless told otherwise. With a token, every request
+//! needs `Authorization: Bearer <token>`; without one anything that can reach
+//! the port can read the archive. Webhook deliveries are the exception:
+//! providers can't send the token, so they are checked by their signature.
+
+use super::parse_date;
+use super::pull::{self, DataDirLock, PullOptions, PullProgress};
+use super::search::{self, FilterArgs};
+use crate::config::EmbedderChoice;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path as UrlPath, Query, Request, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::future::LocalBoxFuture;
+use futures::stream::{self, StreamExt};
+use quaid_core::analysis::conversation_summary;
+use quaid_core::operations::{OperationError, OperationKind, Operations};
+use quaid_core::providers::ingest::{
+    self, IngestQueue, JobStatus, SignedDelivery, WebhookEvent, WebhookRegistry,
+};
+use quaid_core::storage::{ConversationCursor, ConversationHits, ParquetStorageConfig};
+use quaid_core::{web, CredentialStore, Store};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::io::ReaderStream;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Conversations per page unless `?limit=` says otherwise
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a client may ask for
+const MAX_PAGE_SIZE: usize = 500;
+
+/// Conversations returned by `/search` unless `?limit=` says otherwise
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+struct AppState {
+    store: Mutex<Store>,
+    /// Where pulls and webhook syncs read provider credentials
+    credentials: Arc<dyn CredentialStore>,
+    data_dir: PathBuf,
+    operations: Arc<Operations>,
+    /// Held by the pull or webhook sync running in the background; the
+    /// data-directory lock only lets one run at a time, so the rest wait
+    pulling: tokio::sync::Mutex<()>,
+    pull: PullOptions,
+    embedder: EmbedderChoice,
+    puller: Puller,
+    webhooks: WebhookRegistry,
+    webhook_secret: Option<String>,
+    ingest: IngestQueue,
+}
+
+type SharedState = Arc<AppState>;
+
+/// Error response with a JSON body
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+impl From<OperationError> for ApiError {
+    fn from(e: OperationError) -> Self {
+        let status = match e {
+            OperationError::AlreadyRunning(..) | OperationError::Ended(_) => StatusCode::CONFLICT,
+            OperationError::NotFound(_) => StatusCode::NOT_FOUND,
+        };
+        Self(status, e.to_string())
+    }
+}
+
+impl From<quaid_core::storage::StorageError> for ApiError {
+    fn from(e: quaid_core::storage::StorageError) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+type ApiResult = Result<Json<Value>, ApiError>;
+
+/// Where to listen and who may call
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    pub host: IpAddr,
+    pub port: u16,
+    /// Bearer token required on every request, if set
+    pub token: Option<String>,
+    /// Browser origins allowed to call the API, e.g. `http://localhost:5173`
+    pub cors_origins: Vec<String>,
+    /// Secret webhook deliveries are signed with; they're refused without one
+    pub webhook_secret: Option<String>,
+    /// Serve the web viewer at `/` too
+    pub ui: bool,
+    /// How pulls started with `POST /operations/pull` run
+    pub pull: PullOptions,
+    /// Embedder, model and endpoint for those pulls; by default the index's own
+    pub embedder: EmbedderChoice,
+}
+
+/// Serve the API until Ctrl-C
+pub async fn run(
+    options: ServeOptions,
+    store: Store,
+    credentials: Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let app = router(store, credentials, data_dir, &options)?;
+    let listener = tokio::net::TcpListener::bind((options.host, options.port)).await?;
+    println!(
+        "Serving API on http://{} (Ctrl-C to stop)",
+        listener.local_addr()?
+    );
+    match (&options.token, options.host.is_loopback()) {
+        (Some(_), _) => println!("Requests need the bearer token from the config."),
+        (None, true) => println!("There is no token; don't expose this port publicly."),
+        (None, false) => eprintln!(
+            "Warning: listening on {} without a token; anyone who can reach it can read your archive.",
+            options.host
+        ),
+    }
+    if options.ui {
+        println!("Web viewer on http://{}/", listener.local_addr()?);
+        if options.token.is_some() {
+            println!("Browsers don't send the token on their own; put the viewer behind a proxy that adds it.");
+        }
+    }
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+fn router(
+    store: Store,
+    credentials: Arc<dyn CredentialStore>,
+    data_dir: &Path,
+    options: &ServeOptions,
+) -> anyhow::Result<Router> {
+    router_with_puller(store, credentials, data_dir, options, pull_account)
+}
+
+fn router_with_puller(
+    store: Store,
+    credentials: Arc<dyn CredentialStore>,
+    data_dir: &Path,
+    options: &ServeOptions,
+    puller: Puller,
+) -> anyhow::Result<Router> {
+    if let Some(secret) = &options.webhook_secret {
+        if !ingest::is_valid_secret(secret) {
+            anyhow::bail!("serve.webhook_secret must be a base64 secret, like Fathom's whsec_...");
+        }
+    }
+    let state = Arc::new(AppState {
+        store: Mutex::new(store),
+        credentials,
+        data_dir: data_dir.to_path_buf(),
+        operations: Operations::new(),
+        pulling: tokio::sync::Mutex::new(()),
+        pull: options.pull.clone(),
+        embedder: options.embedder.clone(),
+        puller,
+        webhooks: WebhookRegistry::with_defaults(),
+        webhook_secret: options.webhook_secret.clone(),
+        ingest: IngestQueue::new(),
+    });
+    let mut app: Router<SharedState> = Router::new()
+        .route("/conversations", get(list_conversations))
+        .route("/conversations/{id}", get(get_conversation))
+        .route("/search", get(search))
+        .route("/stats", get(stats))
+        .route("/attachments/{id}", get(get_attachment))
+        .route("/operations", get(list_operations))
+        .route("/operations/pull", post(start_pull))
+        .route("/operations/{id}/cancel", post(cancel_operation))
+        .route("/operations/{id}/events", get(operation_events))
+        .route("/ingest/jobs/{id}", get(ingest_job));
+    if options.ui {
+        app = app
+            .route("/", get(web_page))
+            .route("/index.html", get(web_page))
+            .route("/assets/{file}", get(web_page))
+            .route("/c/{id}", get(web_page));
+    }
+
+    // Covers every route above, and only those
+    if let Some(token) = &options.token {
+        let expected = Arc::new(format!("Bearer {}", token));
+        app = app.route_layer(middleware::from_fn_with_state(expected, require_token));
+    }
+    // Providers can't send the token; deliveries are checked by signature
+    let mut app = app
+        .route("/ingest/{provider}", post(ingest_webhook))
+        .with_state(state);
+
+    // Added last so preflight requests are answered before the token check
+    if !options.cors_origins.is_empty() {
+        let origins = options
+            .cors_origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin)
+                    .map_err(|_| anyhow::anyhow!("Invalid CORS origin: {}", origin))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        app = app.layer(
+            CorsLayer::new()
+                .allow_origin(AllowOrigin::list(origins))
+                .allow_methods([Method::GET, Method::POST])
+                .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]),
+        );
+    }
+    Ok(app)
+}
+
+/// Reject requests without the configured `Authorization` header
+async fn require_token(
+    State(expected): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .map(|value| value.as_bytes())
+        .unwrap_or_default();
+    if !constant_time_eq(given, expected.as_bytes()) {
+        return ApiError(
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong bearer token".to_string(),
+        )
+        .into_response();
+    }
+    next.run(request).await
+}
+
+/// Compare without stopping at the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Run `f` against the store on the blocking pool
+async fn with_store<T, F>(state: SharedState, f: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Store, &Path) -> Result<T, ApiError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let store = state
+            .store
+            .lock()
+            .map_err(|_| anyhow::anyhow!("store lock poisoned"))?;
+        f(&store, &state.data_dir)
+    })
+    .await
+    .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+}
+
+/// Run `job` in the background with a store connection of its own
+///
+/// The store can't be shared across an await, so the job gets a thread and a
+/// single-threaded runtime to itself rather than a task on the server's.
+fn spawn_job<F, Fut>(state: &SharedState, job: F)
+where
+    F: FnOnce(anyhow::Result<Store>, PathBuf) -> Fut + Send + 'static,
+    Fut: Future<Output = ()>,
+{
+    let data_dir = state.data_dir.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("building a runtime for a background job");
+        let store = Store::open(&data_dir.join("quaid.db")).map_err(anyhow::Error::from);
+        runtime.block_on(job(store, data_dir));
+    });
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    provider: Option<String>,
+    /// Created on or after, as for `--since`
+    since: Option<String>,
+    /// Created on or before, as for `--until`
+    until: Option<String>,
+    /// `next_cursor` from the previous page
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+fn bad_date(e: anyhow::Error) -> ApiError {
+    ApiError(StatusCode::BAD_REQUEST, e.to_string())
+}
+
+/// `GET /conversations`: most recently updated first, a page at a time
+///
+/// Pass the `next_cursor` of a page as `?cursor=` to get the next one; the
+/// last page has none.
+async fn list_conversations(
+    State(state): State<SharedState>,
+    Query(params): Query<ListParams>,
+) -> ApiResult {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let since = params
+        .since
+        .as_deref()
+        .map(|s| parse_date(s, false))
+        .transpose()
+        .map_err(bad_date)?;
+    let until = params
+        .until
+        .as_deref()
+        .map(|s| parse_date(s, true))
+        .transpose()
+        .map_err(bad_date)?;
+    let after = params
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            ConversationCursor::decode(cursor)
+                .ok_or_else(|| ApiError(StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))
+        })
+        .transpose()?;
+    with_store(state, move |store, _| {
+        // One more than asked for, to know whether another page follows
+        let mut page = store.get_conversations_after(
+            params.provider.as_deref(),
+            (since, until),
+            after.as_ref(),
+            limit + 1,
+        )?;
+        let next_cursor = (page.len() > limit).then(|| {
+            page.truncate(limit);
+            ConversationCursor::after(&page[limit - 1]).encode()
+        });
+        Ok(Json(json!({
+            "limit": limit,
+            "conversations": page,
+            "next_cursor": next_cursor,
+        })))
+    })
+    .await
+}
+
+/// `GET /conversations/{id}`: the conversation, its messages and their summary
+async fn get_conversation(
+    State(state): State<SharedState>,
+    UrlPath(id): UrlPath<String>,
+) -> ApiResult {
+    with_store(state, move |store, _| {
+        let conversation = store.get_conversation(&id)?.ok_or_else(|| {
+            ApiError(
+                StatusCode::NOT_FOUND,
+                format!("Conversation not found: {}", id),
+            )
+        })?;
+        let messages = store.get_messages(&id)?;
+        let summary = conversation_summary(&conversation, &messages);
+        Ok(Json(json!({
+            "conversation": conversation,
+            "messages": messages,
+            "summary": summary,
+        })))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    q: String,
+    #[serde(default)]
+    semantic: bool,
+    /// Full-text and semantic matches together
+    #[serde(default)]
+    hybrid: bool,
+    provider: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+}
+
+/// `GET /search?q=`: full-text search, or semantic with `semantic=true`
+/// and both with `hybrid=true`
+async fn search(State(state): State<SharedState>, Query(params): Query<SearchParams>) -> ApiResult {
+    if params.q.trim().is_empty() {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "q must not be empty".to_string(),
+        ));
+    }
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_PAGE_SIZE);
+    let endpoint = state.embedder.endpoint.clone();
+    let credentials = state.credentials.clone();
+    with_store(state, move |store, data_dir| {
+        let filters = search::build_filters(
+            FilterArgs {
+                provider: params.provider.as_deref(),
+                account: None,
+                since: params.since.as_deref(),
+                until: params.until.as_deref(),
+                roles: &[],
+                exclude_archived: false,
+                attachments: false,
+            },
+            store,
+        )
+        .map_err(bad_date)?;
+        let groups: Vec<ConversationHits> = if params.semantic || params.hybrid {
+            search::run_semantic_search(
+                &params.q,
+                limit,
+                params.hybrid,
+                false,
+                &filters,
+                store,
+                endpoint.as_deref(),
+                &credentials,
+                data_dir,
+            )?
+        } else {
+            search::run_fts_search(&params.q, limit, &filters, store)?
+        };
+        Ok(Json(json!({ "results": groups })))
+    })
+    .await
+}
+
+/// `GET /attachments/{id}`: the downloaded file, streamed from disk
+async fn get_attachment(
+    State(state): State<SharedState>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Response, ApiError> {
+    let lookup_id = id.clone();
+    let file = with_store(state, move |store, _| {
+        Ok(store.get_attachment_file(&lookup_id)?)
+    })
+    .await?
+    .ok_or_else(|| {
+        ApiError(
+            StatusCode::NOT_FOUND,
+            format!("Attachment not found: {}", id),
+        )
+    })?;
+    let not_downloaded = || {
+        ApiError(
+            StatusCode::NOT_FOUND,
+            format!("Attachment {} hasn't been downloaded", id),
+        )
+    };
+    let path = file.local_path.ok_or_else(not_downloaded)?;
+    let handle = tokio::fs::File::open(&path)
+        .await
+        .map_err(|_| not_downloaded())?;
+
+    let attachment = file.attachment;
+    // Header values must be printable ASCII
+    let filename: String = attachment
+        .filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c == ' ' || c.is_ascii_graphic() => c,
+            _ => '_',
+        })
+        .collect();
+    let disposition = format!("inline; filename=\"{}\"", filename);
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.mime_type),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(ReaderStream::new(handle)),
+    )
+        .into_response())
+}
+
+/// `GET /`, `/assets/*` and `/c/{id}`: the web viewer, with `--ui`
+async fn web_page(State(state): State<SharedState>, uri: Uri) -> Result<Response, ApiError> {
+    let path = uri.path().to_string();
+    let page = with_store(state, move |store, _| Ok(web::route(store, &path)?)).await?;
+    let status = StatusCode::from_u16(page.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let headers = page.headers();
+    let mut response = (status, page.body).into_response();
+    for (name, value) in headers {
+        response.headers_mut().insert(
+            HeaderName::from_bytes(name.as_bytes()).expect("valid header name"),
+            HeaderValue::from_static(value),
+        );
+    }
+    Ok(response)
+}
+
+/// `GET /operations`: every operation since the server started, oldest first
+async fn list_operations(State(state): State<SharedState>) -> ApiResult {
+    Ok(Json(json!({ "operations": state.operations.list() })))
+}
+
+#[derive(Deserialize)]
+struct PullRequest {
+    /// Pull only this provider's accounts; every account without it
+    provider: Option<String>,
+    /// Pull only the accounts signed in with this email
+    account: Option<String>,
+}
+
+/// One account's share of a `POST /operations/pull`, and its operation
+struct AccountPull {
+    provider: String,
+    email: String,
+    progress: PullProgress,
+}
+
+/// Runs an [`AccountPull`]: [`pull_account`], or a fake provider in tests
+type Puller =
+    for<'a> fn(&'a AppState, &'a Store, AccountPull) -> LocalBoxFuture<'a, anyhow::Result<()>>;
+
+/// `POST /operations/pull`: start pulling in the background
+///
+/// Each account gets an operation of its own, and the response lists their
+/// ids right away. It's a `409` if any of them is still being pulled; the
+/// accounts are pulled one after another, after any pull already running.
+async fn start_pull(
+    State(state): State<SharedState>,
+    Json(request): Json<PullRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let provider = request.provider.filter(|p| !p.is_empty());
+    let account = request.account.filter(|a| !a.is_empty());
+    let providers = state.pull.providers.clone();
+    let mut targets = with_store(state.clone(), move |store, _| {
+        let accounts = super::select_accounts(store, provider.as_deref(), account.as_deref())
+            .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+        Ok(accounts
+            .into_iter()
+            // Every account, or the ones the config file names, as for `quaid pull`
+            .filter(|a| {
+                provider.is_some() || providers.is_empty() || providers.contains(&a.provider.0)
+            })
+            .map(|a| (a.provider.0, a.email))
+            .collect::<Vec<_>>())
+    })
+    .await?;
+    // ChatGPT workspaces share an email, and are pulled together
+    targets.sort();
+    targets.dedup();
+    if targets.is_empty() {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "No account to pull".to_string(),
+        ));
+    }
+
+    let names: Vec<String> = targets
+        .iter()
+        .map(|(provider, email)| format!("{}:{}", provider, email))
+        .collect();
+    let handles = state.operations.start_all(OperationKind::Pull, &names)?;
+    let operations: Vec<Value> = handles
+        .iter()
+        .zip(&names)
+        .map(|(handle, target)| json!({ "id": handle.id(), "target": target }))
+        .collect();
+    let pulls: Vec<AccountPull> = targets
+        .into_iter()
+        .zip(handles)
+        .map(|((provider, email), handle)| AccountPull {
+            provider,
+            email,
+            progress: PullProgress::new(handle),
+        })
+        .collect();
+
+    let job_state = state.clone();
+    spawn_job(&state, move |store, _| async move {
+        let _queued = job_state.pulling.lock().await;
+        for pull in pulls {
+            let progress = pull.progress.clone();
+            let result = match &store {
+                // Cancelled while it waited
+                _ if progress.cancel_token().is_cancelled() => Ok(()),
+                Ok(store) => (job_state.puller)(&job_state, store, pull).await,
+                Err(e) => Err(anyhow::anyhow!("Couldn't open the store: {}", e)),
+            };
+            progress.finish(&result);
+        }
+    });
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({ "operations": operations })),
+    ))
+}
+
+/// Pull one account with [`pull::pull_selected`], stopping when its
+/// operation is cancelled
+fn pull_account<'a>(
+    state: &'a AppState,
+    store: &'a Store,
+    pull: AccountPull,
+) -> LocalBoxFuture<'a, anyhow::Result<()>> {
+    Box::pin(async move {
+        // Against a `quaid pull` or `sync` running outside the server
+        let _lock = DataDirLock::acquire(&state.data_dir)?;
+        let backend = super::embedding_backend(&state.embedder, &state.data_dir)?;
+        let cancel = pull.progress.cancel_token().clone();
+        let options = PullOptions {
+            progress: Some(pull.progress),
+            ..state.pull.clone()
+        };
+        let config = pull::pipeline_config(&state.data_dir, &options, backend, &state.credentials);
+        pull::pull_selected(
+            Some(&pull.provider),
+            Some(&pull.email),
+            &options,
+            store,
+            &state.credentials,
+            &config,
+            &cancel,
+        )
+        .await?;
+        Ok(())
+    })
+}
+
+/// `POST /operations/{id}/cancel`: stop a running operation
+///
+/// Answers `202 Accepted` at once; the operation's events end with
+/// `cancelled` once what was in flight has finished. It's a `409` if the
+/// operation has already ended.
+async fn cancel_operation(
+    State(state): State<SharedState>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    state.operations.cancel(id)?;
+    Ok((StatusCode::ACCEPTED, Json(json!({ "id": id }))))
+}
+
+/// `POST /ingest/{provider}`: a webhook delivery, synced in the background
+///
+/// The delivery must be signed with the configured webhook secret, as the
+/// Standard Webhooks spec describes. Deliveries for a conversation whose
+/// sync hasn't finished yet share its job.
+async fn ingest_webhook(
+    State(state): State<SharedState>,
+    UrlPath(provider): UrlPath<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    if !state.webhooks.supports(&provider) {
+        return Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("No webhook support for {}", provider),
+        ));
+    }
+    let Some(secret) = &state.webhook_secret else {
+        return Err(ApiError(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No webhook secret is configured".to_string(),
+        ));
+    };
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    };
+    let delivery = SignedDelivery {
+        id: header(ingest::ID_HEADER),
+        timestamp: header(ingest::TIMESTAMP_HEADER),
+        signature: header(ingest::SIGNATURE_HEADER),
+    };
+    if !ingest::verify_signature(secret, delivery, &body, chrono::Utc::now()) {
+        return Err(ApiError(
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong webhook signature".to_string(),
+        ));
+    }
+    let event = state
+        .webhooks
+        .parse(&provider, &body)
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let (id, created) = state.ingest.enqueue(event.clone());
+    if created {
+        let job_state = state.clone();
+        spawn_job(&state, move |store, data_dir| async move {
+            let _queued = job_state.pulling.lock().await;
+            job_state.ingest.set_status(id, JobStatus::Running);
+            let result = async {
+                let store = store?;
+                sync_webhook_event(&job_state, &event, &store, &data_dir).await
+            }
+            .await;
+            let status = match result {
+                Ok(()) => JobStatus::Done,
+                Err(e) => JobStatus::Failed(e.to_string()),
+            };
+            job_state.ingest.set_status(id, status);
+        });
+    }
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({ "job": id, "created": created })),
+    ))
+}
+
+/// Pull the one conversation a webhook reported
+async fn sync_webhook_event(
+    state: &AppState,
+    event: &WebhookEvent,
+    store: &Store,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let backend = super::embedding_backend(&state.embedder, data_dir)?;
+    match event.provider.0.as_str() {
+        "fathom" => {
+            pull::pull_fathom_meeting(
+                &event.conversation_id,
+                event.created_at,
+                state.pull.clone(),
+                backend,
+                store,
+                &state.credentials,
+                data_dir,
+            )
+            .await
+        }
+        other => anyhow::bail!("Webhook syncs aren't supported for {}", other),
+    }
+}
+
+/// `GET /ingest/jobs/{id}`: where a webhook-triggered sync is at
+async fn ingest_job(State(state): State<SharedState>, UrlPath(id): UrlPath<u64>) -> ApiResult {
+    let job = state
+        .ingest
+        .get(id)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "Job not found".to_string()))?;
+    let error = match &job.status {
+        JobStatus::Failed(e) => Some(e.clone()),
+        _ => None,
+    };
+    Ok(Json(json!({
+        "id": job.id,
+        "provider": job.event.provider.0,
+        "conversation_id": job.event.conversation_id,
+        "status": job.status.as_str(),
+        "error": error,
+    })))
+}
+
+/// `GET /operations/{id}/events`: the operation's progress as server-sent events
+///
+/// Starts with every event so far and ends after the last one, so a client
+/// connecting late or reconnecting sees the whole operation.
+async fn operation_events(
+    State(state): State<SharedState>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<Response, ApiError> {
+    let (history, receiver) = state.operations.subscribe(id)?;
+    let ended = history.last().is_some_and(|event| event.is_terminal());
+    let live = stream::unfold((receiver, ended), |(mut receiver, ended)| async move {
+        if ended {
+            return None;
+        }
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let ended = event.is_terminal();
+                    return Some((event, (receiver, ended)));
+                }
+                // The snapshot at GET /operations still has the counts
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = stream::iter(history)
+        .chain(live)
+        .map(|event| Ok::<_, Infallible>(event.to_sse()));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(events),
+    )
+        .into_response())
+}
+
+/// `GET /stats`: totals, a per-provider breakdown and whether semantic
+/// search has an embeddings index to use
+async fn stats(State(state): State<SharedState>) -> ApiResult {
+    with_store(state, |store, data_dir| {
+        let embeddings = ParquetStorageConfig::new(data_dir)
+            .list_embedding_providers()
+            .is_ok_and(|providers| !providers.is_empty());
+        Ok(Json(json!({
+            "totals": store.stats()?,
+            "providers": store.stats_by_provider()?,
+            "embeddings": embeddings,
+        })))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use chrono::{TimeZone, Utc};
+    use quaid_core::operations::{fetch_conversations, ProgressEvent, SyncProgress};
+    use quaid_core::pipeline::CancellationToken;
+    use quaid_core::providers::{
+        self, Account, Attachment, Conversation, Message, MessageContent, Provider, ProviderError,
+        ProviderId, Role,
+    };
+    use tower::ServiceExt;
+
+    fn test_store() -> Store {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "a@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+        for day in 1..=3 {
+            let id = format!("conv-{}", day);
+            let conversation = Conversation {
+                id: id.clone(),
+                provider_id: "claude".to_string(),
+                title: format!("Kubernetes notes {}", day),
+                created_at: Utc.with_ymd_and_hms(2024, 1, day, 9, 0, 0).unwrap(),
+                updated_at: Utc.with_ymd_and_hms(2024, 1, day, 9, 0, 0).unwrap(),
+                model: None,
+                project_id: None,
+                project_name: None,
+                is_archived: false,
+            };
+            let message = Message {
+                id: format!("msg-{}", day),
+                conversation_id: id,
+                parent_id: None,
+                role: Role::User,
+                content: MessageContent::Text {
+                    text: "How do I configure a kubernetes ingress?".to_string(),
+                },
+                created_at: None,
+                model: None,
+                speaker: None,
+            };
+            store
+                .save_conversation_with_messages("user-1", &conversation, &[message])
+                .unwrap();
+        }
+        store
+    }
+
+    /// Percent-encode a query value; cursors hold `:` and `+`
+    fn urlencode(value: &str) -> String {
+        value
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    (b as char).to_string()
+                }
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    }
+
+    fn credentials() -> Arc<dyn CredentialStore> {
+        Arc::new(quaid_core::MockStore::new())
+    }
+
+    fn local() -> ServeOptions {
+        crate::config::Config::default().serve(None, Some(0))
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_conversations_are_paginated() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = router(test_store(), credentials(), dir.path(), &local()).unwrap();
+
+        let ids = |page: &Value| -> Vec<String> {
+            page["conversations"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|c| c["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let (status, first) = get_json(app.clone(), "/conversations?limit=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&first), ["conv-3", "conv-2"]);
+        let cursor = first["next_cursor"].as_str().unwrap();
+        let (_, last) = get_json(
+            app.clone(),
+            &format!("/conversations?limit=2&cursor={}", urlencode(cursor)),
+        )
+        .await;
+        assert_eq!(ids(&last), ["conv-1"]);
+        assert!(last["next_cursor"].is_null());
+
+        let (_, other) = get_json(app.clone(), "/conversations?provider=chatgpt").await;
+        assert!(ids(&other).is_empty());
+
+        let (status, _) = get_json(app.clone(), "/conversations?cursor=nope").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (_, dated) = get_json(
+            app.clone(),
+            "/conversations?since=2024-01-02&until=2024-01-02",
+        )
+        .await;
+        assert_eq!(ids(&dated), ["conv-2"]);
+
+        let (status, _) = get_json(app.clone(), "/conversations?since=someday").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, detail) = get_json(app.clone(), "/conversations/conv-1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(detail["conversation"]["title"], "Kubernetes notes 1");
+        assert_eq!(detail["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(detail["summary"]["user_messages"], 1);
+        assert_eq!(detail["summary"]["user_words"], 7);
+
+        let (status, missing) = get_json(app, "/conversations/conv-9").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(missing["error"].as_str().unwrap().contains("conv-9"));
+    }
+
+    #[tokio::test]
+    async fn test_search_and_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = router(test_store(), credentials(), dir.path(), &local()).unwrap();
+
+        let (status, found) = get_json(app.clone(), "/search?q=ingress").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(found["results"].as_array().unwrap().len(), 3);
+
+        let (status, _) = get_json(app.clone(), "/search?q=").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        // Hybrid search needs an index
+        let (status, body) = get_json(app.clone(), "/search?q=ingress&hybrid=true").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .contains("quaid index embed"));
+
+        let (status, stats) = get_json(app, "/stats").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["totals"]["conversations"], 3);
+        assert_eq!(stats["providers"][0]["provider"], "claude");
+        assert_eq!(stats["embeddings"], false);
+    }
+
+    #[tokio::test]
+    async fn test_attachments_are_streamed_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = test_store();
+        for id in ["att-1", "att-2"] {
+            store
+                .save_attachment(&quaid_core::providers::Attachment {
+                    id: id.to_string(),
+                    message_id: "msg-1".to_string(),
+                    filename: "diagram é.png".to_string(),
+                    mime_type: "image/png".to_string(),
+                    size_bytes: 4,
+                    download_url: format!("file-{}", id),
+                    width: None,
+                    height: None,
+                    sha256: None,
+                })
+                .unwrap();
+        }
+        let path = dir.path().join("diagram.png");
+        std::fs::write(&path, b"\x89PNG").unwrap();
+        store.record_attachment_download("att-1", &path).unwrap();
+        let app = router(store, credentials(), dir.path(), &local()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get("/attachments/att-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "inline; filename=\"diagram _.png\""
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"\x89PNG");
+
+        let (status, pending) = get_json(app.clone(), "/attachments/att-2").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(pending["error"].as_str().unwrap().contains("downloaded"));
+        let (status, _) = get_json(app, "/attachments/att-9").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_ui_serves_the_viewer() {
+        let dir = tempfile::tempdir().unwrap();
+        let get =
+            |app: Router, uri: &str| app.oneshot(Request::get(uri).body(Body::empty()).unwrap());
+
+        let app = router(test_store(), credentials(), dir.path(), &local()).unwrap();
+        let response = get(app, "/").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let options = ServeOptions {
+            ui: true,
+            ..local()
+        };
+        let app = router(test_store(), credentials(), dir.path(), &options).unwrap();
+        let response = get(app.clone(), "/").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["content-security-policy"],
+            web::CONTENT_SECURITY_POLICY
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("/assets/app.js"));
+
+        let response = get(app.clone(), "/assets/app.js").await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/javascript; charset=utf-8"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let script = String::from_utf8_lossy(&body);
+        assert!(script.contains("\"/conversations?limit=\""));
+        assert!(script.contains("\"/search?q=\" + "));
+
+        let response = get(app.clone(), "/c/conv-2").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("Kubernetes notes 2"));
+
+        let response = get(app, "/c/conv-9").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Lists a few conversations; `slow` isn't fetched until the pull is cancelled
+    struct FakeProvider {
+        ids: Vec<&'static str>,
+        cancel: CancellationToken,
+    }
+
+    fn fake_conversation(id: &str) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            provider_id: "claude".to_string(),
+            title: id.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    #[async_trait]
+    impl Provider for FakeProvider {
+        fn id(&self) -> ProviderId {
+            ProviderId::claude()
+        }
+
+        async fn is_authenticated(&self) -> bool {
+            true
+        }
+
+        async fn authenticate(&mut self) -> providers::Result<Account> {
+            self.account().await
+        }
+
+        async fn account(&self) -> providers::Result<Account> {
+            Err(ProviderError::AuthRequired)
+        }
+
+        async fn conversations(&self) -> providers::Result<Vec<Conversation>> {
+            Ok(self.ids.iter().map(|id| fake_conversation(id)).collect())
+        }
+
+        async fn conversation(&self, id: &str) -> providers::Result<(Conversation, Vec<Message>)> {
+            match id {
+                "slow" => {
+                    self.cancel.cancelled().await;
+                    Err(ProviderError::Api("cancelled".to_string()))
+                }
+                "broken" => Err(ProviderError::Api("500: boom".to_string())),
+                _ => Ok((fake_conversation(id), vec![])),
+            }
+        }
+
+        async fn project_conversations(&self, _: &str) -> providers::Result<Vec<Conversation>> {
+            Ok(vec![])
+        }
+
+        async fn download_attachment(&self, _: &Attachment, _: &Path) -> providers::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Pulls `slow@example.com` with a conversation that waits to be cancelled
+    fn fake_pull<'a>(
+        _: &'a AppState,
+        _: &'a Store,
+        pull: AccountPull,
+    ) -> LocalBoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut ids = vec!["a", "skip-me", "broken", "b"];
+            if pull.email == "slow@example.com" {
+                ids.push("slow");
+            }
+            let provider = FakeProvider {
+                ids,
+                cancel: pull.progress.cancel_token().clone(),
+            };
+            let (_, summary) =
+                fetch_conversations(&provider, |c| c.id == "skip-me", &pull.progress).await?;
+            pull.progress.report(ProgressEvent::Finished { summary });
+            Ok(())
+        })
+    }
+
+    fn fake_app(dir: &Path, emails: &[&str]) -> Router {
+        // Background jobs open the store in the data directory themselves
+        let store = Store::open(&dir.join("quaid.db")).unwrap();
+        for (i, email) in emails.iter().enumerate() {
+            store
+                .save_account(&Account {
+                    id: format!("user-{}", i),
+                    provider: ProviderId::claude(),
+                    email: email.to_string(),
+                    name: None,
+                    avatar_url: None,
+                    workspace_id: None,
+                })
+                .unwrap();
+        }
+        router_with_puller(store, credentials(), dir, &local(), fake_pull).unwrap()
+    }
+
+    fn post_json(uri: &str, body: &str) -> Request<Body> {
+        Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn send_json(app: Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    /// Event names of an operation, read until its stream ends
+    async fn event_names(app: Router, id: u64) -> (Vec<String>, String) {
+        let response = app
+            .oneshot(
+                Request::get(format!("/operations/{}/events", id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let events = String::from_utf8(body.to_vec()).unwrap();
+        let names = events
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: "))
+            .map(str::to_string)
+            .collect();
+        (names, events)
+    }
+
+    #[tokio::test]
+    async fn test_pull_operation_streams_its_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = fake_app(dir.path(), &["a@example.com", "b@example.com"]);
+
+        let (status, _) = send_json(
+            app.clone(),
+            post_json("/operations/pull", r#"{"provider":"chatgpt"}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // One operation per account
+        let (status, started) = send_json(app.clone(), post_json("/operations/pull", "{}")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let operations = started["operations"].as_array().unwrap();
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0]["target"], "claude:a@example.com");
+        assert_eq!(operations[1]["target"], "claude:b@example.com");
+
+        for operation in operations {
+            let id = operation["id"].as_u64().unwrap();
+            // The stream ends with the operation
+            let (names, events) = event_names(app.clone(), id).await;
+            assert_eq!(
+                names,
+                ["phase", "phase", "progress", "progress", "progress", "progress", "finished"]
+            );
+            assert!(events.contains(r#""phase":"listing""#));
+            assert!(events.contains(r#""phase":"fetching""#));
+            assert!(events.contains(r#""summary":{"synced":2,"skipped":1,"failed":1}"#));
+        }
+
+        let (_, listed) = get_json(app.clone(), "/operations").await;
+        assert_eq!(listed["operations"][0]["kind"], "pull");
+        assert_eq!(listed["operations"][0]["status"], "completed");
+        assert_eq!(listed["operations"][1]["done"], 4);
+
+        let (status, _) = get_json(app, "/operations/99/events").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_pull_operation_can_be_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = fake_app(dir.path(), &["slow@example.com", "b@example.com"]);
+        let pull = |account: &str| {
+            post_json(
+                "/operations/pull",
+                &format!(r#"{{"provider":"claude","account":"{}"}}"#, account),
+            )
+        };
+
+        let (status, started) = send_json(app.clone(), pull("slow@example.com")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let slow = started["operations"][0]["id"].as_u64().unwrap();
+        // One pull per account at a time; the others wait their turn
+        let (status, _) = send_json(app.clone(), pull("slow@example.com")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, started) = send_json(app.clone(), pull("b@example.com")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let queued = started["operations"][0]["id"].as_u64().unwrap();
+
+        let cancel = |id: u64| post_json(&format!("/operations/{}/cancel", id), "");
+        let (status, _) = send_json(app.clone(), cancel(slow)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let (names, _) = event_names(app.clone(), slow).await;
+        assert_eq!(names.last().map(String::as_str), Some("cancelled"));
+
+        // The queued pull runs once the cancelled one has stopped
+        let (names, _) = event_names(app.clone(), queued).await;
+        assert_eq!(names.last().map(String::as_str), Some("finished"));
+
+        let (_, listed) = get_json(app.clone(), "/operations").await;
+        assert_eq!(listed["operations"][0]["status"], "cancelled");
+        assert_eq!(listed["operations"][1]["status"], "completed");
+        let (status, _) = send_json(app.clone(), cancel(slow)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send_json(app, cancel(99)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    const WEBHOOK_SECRET: &str = "whsec_aDAwaw==";
+
+    /// Standard Webhooks headers for `body`, sent now
+    fn signed(secret: &str, body: &str) -> Vec<(&'static str, String)> {
+        signed_at(secret, body, Utc::now().timestamp())
+    }
+
+    fn signed_at(secret: &str, body: &str, timestamp: i64) -> Vec<(&'static str, String)> {
+        let signature = ingest::sign(secret, "msg_1", timestamp, body.as_bytes()).unwrap();
+        vec![
+            (ingest::ID_HEADER, "msg_1".to_string()),
+            (ingest::TIMESTAMP_HEADER, timestamp.to_string()),
+            (ingest::SIGNATURE_HEADER, signature),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_fathom_webhook_queues_a_sync() {
+        const PAYLOAD: &str = r#"{
+            "url": "https://fathom.video/calls/meeting-42",
+            "title": "Customer call",
+            "created_at": "2025-01-15T10:00:00Z",
+            "transcript": []
+        }"#;
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::open(&dir.path().join("quaid.db")).unwrap();
+        let options = ServeOptions {
+            webhook_secret: Some(WEBHOOK_SECRET.to_string()),
+            ..local()
+        };
+        let app = router(store, credentials(), dir.path(), &options).unwrap();
+        let signature = signed(WEBHOOK_SECRET, PAYLOAD);
+        let deliver = |provider: &str, signature: &[(&'static str, String)]| {
+            let mut request = Request::post(format!("/ingest/{}", provider))
+                .header(header::CONTENT_TYPE, "application/json");
+            for (name, value) in signature {
+                request = request.header(*name, value);
+            }
+            request.body(Body::from(PAYLOAD)).unwrap()
+        };
+
+        for bad in [
+            signed("whsec_d3Jvbmc=", PAYLOAD),
+            vec![],
+            // Replayed from long ago
+            signed_at(WEBHOOK_SECRET, PAYLOAD, 1614265330),
+        ] {
+            let response = app.clone().oneshot(deliver("fathom", &bad)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = app
+            .clone()
+            .oneshot(deliver("otter", &signature))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(deliver("fathom", &signature))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let accepted: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(accepted["created"], true);
+        let job = accepted["job"].as_u64().unwrap();
+
+        // No Fathom account is signed in, so the sync fails once it runs
+        let mut polled = Value::Null;
+        for _ in 0..100 {
+            let (status, body) = get_json(app.clone(), &format!("/ingest/jobs/{}", job)).await;
+            assert_eq!(status, StatusCode::OK);
+            polled = body;
+            if !matches!(polled["status"].as_str(), Some("pending" | "running")) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(polled["provider"], "fathom");
+        assert_eq!(polled["conversation_id"], "meeting-42");
+        assert_eq!(polled["status"], "failed");
+        assert!(polled["error"]
+            .as_str()
+            .unwrap()
+            .contains("No authenticated Fathom account"));
+
+        let (status, _) = get_json(app.clone(), "/ingest/jobs/99").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Without a secret, deliveries are refused
+        let store = Store::open(&dir.path().join("quaid.db")).unwrap();
+        let app = router(store, credentials(), dir.path(), &local()).unwrap();
+        let response = app.oneshot(deliver("fathom", &signature)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let bad = ServeOptions {
+            webhook_secret: Some("not base64!".to_string()),
+            ..local()
+        };
+        assert!(router(test_store(), credentials(), dir.path(), &bad).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bearer_token_and_cors() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ServeOptions {
+            token: Some("s3cret".to_string()),
+            cors_origins: vec!["http://localhost:5173".to_string()],
+            ..local()
+        };
+        let app = router(test_store(), credentials(), dir.path(), &options).unwrap();
+
+        let (status, _) = get_json(app.clone(), "/stats").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let authorized = Request::get("/stats")
+            .header(header::AUTHORIZATION, "Bearer s3cret")
+            .header(header::ORIGIN, "http://localhost:5173")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(authorized).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:5173"
+        );
+
+        // Browsers send preflights without credentials
+        let preflight = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/stats")
+            .header(header::ORIGIN, "http://localhost:5173")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(preflight).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_HEADERS));
+
+        let bad = ServeOptions {
+            cors_origins: vec!["http://bad\norigin".to_string()],
+            ..local()
+        };
+        assert!(router(test_store(), credentials(), dir.path(), &bad).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_guards_every_route_but_webhooks() {
+        const PAYLOAD: &str = r#"{
+            "url": "https://fathom.video/calls/meeting-42",
+            "title": "Customer call",
+            "created_at": "2025-01-15T10:00:00Z",
+            "transcript": []
+        }"#;
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::open(&dir.path().join("quaid.db")).unwrap();
+        let options = ServeOptions {
+            token: Some("s3cret".to_string()),
+            webhook_secret: Some(WEBHOOK_SECRET.to_string()),
+            ui: true,
+            ..local()
+        };
+        let app = router(store, credentials(), dir.path(), &options).unwrap();
+        let routes = [
+            (Method::GET, "/"),
+            (Method::GET, "/index.html"),
+            (Method::GET, "/assets/app.js"),
+            (Method::GET, "/c/conv-1"),
+            (Method::GET, "/operations"),
+            (Method::POST, "/operations/pull"),
+            (Method::POST, "/operations/1/cancel"),
+            (Method::GET, "/operations/1/events"),
+            (Method::GET, "/ingest/jobs/1"),
+        ];
+
+        for (method, uri) in routes {
+            let request = |token: Option<&str>| {
+                let mut request = Request::builder().method(method.clone()).uri(uri);
+                if let Some(token) = token {
+                    request = request.header(header::AUTHORIZATION, token);
+                }
+                request.body(Body::empty()).unwrap()
+            };
+            for (token, refused) in [
+                (None, true),
+                (Some("Bearer wrong"), true),
+                (Some("Bearer s3cret"), false),
+            ] {
+                let response = app.clone().oneshot(request(token)).await.unwrap();
+                let refused_by_token = response.status() == StatusCode::UNAUTHORIZED;
+                assert_eq!(
+                    refused_by_token, refused,
+                    "{} {} with {:?}",
+                    method, uri, token
+                );
+            }
+        }
+
+        // Providers can't send the token, so only the signature counts
+        let deliver = |signature: Vec<(&'static str, String)>| {
+            let mut request = Request::post("/ingest/fathom");
+            for (name, value) in signature {
+                request = request.header(name, value);
+            }
+            request.body(Body::from(PAYLOAD)).unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(deliver(signed(WEBHOOK_SECRET, PAYLOAD)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let response = app.oneshot(deliver(vec![])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,40 @@
+use quaid_core::analysis::conversation_summary;
+use quaid_core::storage::extract_text_content;
+use quaid_core::Store;
+
+/// Print a conversation's messages followed by a summary line, or all of it as JSON
+pub fn run(id: &str, format: &str, store: &Store) -> anyhow::Result<()> {
+    let conv = store
+        .get_conversation(id)?
+        .ok_or_else(|| anyhow::anyhow!("Conversation not found: {}", id))?;
+    let messages = store.get_messages(id)?;
+    let summary = conversation_summary(&conv, &messages);
+
+    match format {
+        "text" => {
+            println!("{} ({})", conv.title, conv.id);
+            println!(
+                "{} | {} | {}",
+                conv.provider_id,
+                conv.created_at.format("%Y-%m-%d %H:%M"),
+                conv.model.as_deref().unwrap_or("unknown")
+            );
+            for msg in &messages {
+                println!("\n[{}]", msg.role.as_str());
+                println!("{}", extract_text_content(&msg.content));
+            }
+            println!("\n{}", "-".repeat(60));
+            println!("{}", summary.line());
+        }
+        "json" => {
+            let json = serde_json::json!({
+                "conversation": conv,
+                "messages": messages,
+                "summary": summary,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        _ => anyhow::bail!("Unknown format: {}. Supported: text, json", format),
+    }
+    Ok(())
+}
@@ -1,15 +1,34 @@
-use quaid_core::Store;
+use quaid_core::storage::parquet::ParquetStore;
+use quaid_core::{ParquetStorageConfig, Store};
+use std::path::Path;
+
+pub fn run(by_provider: bool, store: &Store, data_dir: &Path) -> anyhow::Result<()> {
+    if by_provider {
+        return run_by_provider(store, data_dir);
+    }
 
-pub fn run(store: &Store) -> anyhow::Result<()> {
     let stats = store.stats()?;
 
     println!("Quaid Statistics");
     println!("================");
     println!();
     println!("Accounts:      {}", stats.accounts);
-    println!("Conversations: {}", stats.conversations);
+    if stats.conversations_deleted_remotely > 0 {
+        println!(
+            "Conversations: {} ({} deleted remotely)",
+            stats.conversations, stats.conversations_deleted_remotely
+        );
+    } else {
+        println!("Conversations: {}", stats.conversations);
+    }
     println!("Messages:      {}", stats.messages);
-    println!("Attachments:   {}", stats.attachments);
+    println!(
+        "Attachments:   {} ({} files on disk, {} pending, {} failed)",
+        stats.attachments,
+        stats.attachment_files,
+        stats.attachments_pending,
+        stats.attachments_failed
+    );
 
     // Show per-account breakdown
     let accounts = store.list_accounts()?;
@@ -28,7 +47,7 @@ pub fn run(store: &Store) -> anyhow::Result<()> {
             println!(
                 "  {} ({}): {} conversations, {} messages",
                 account.provider,
-                account.email,
+                account.label(),
                 convs.len(),
                 msg_count
             );
@@ -37,3 +56,68 @@ pub fn run(store: &Store) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn run_by_provider(store: &Store, data_dir: &Path) -> anyhow::Result<()> {
+    let stats = store.stats_by_provider()?;
+    if stats.is_empty() {
+        println!("No conversations yet.");
+        return Ok(());
+    }
+
+    let parquet = ParquetStore::new(ParquetStorageConfig::new(data_dir));
+
+    println!(
+        "{:<10} {:>8} {:>9} {:>10} {:>10} {:>11} {:>9}",
+        "Provider", "Convs", "Messages", "Earliest", "Latest", "Attachments", "On disk"
+    );
+    println!("{}", "-".repeat(73));
+
+    for p in stats {
+        let date = |d: Option<chrono::DateTime<chrono::Utc>>| {
+            d.map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let on_disk = parquet.disk_usage(&p.provider)?;
+
+        println!(
+            "{:<10} {:>8} {:>9} {:>10} {:>10} {:>11} {:>9}",
+            p.provider,
+            p.conversations,
+            p.messages,
+            date(p.earliest),
+            date(p.latest),
+            format_bytes(p.attachment_bytes),
+            format_bytes(on_disk)
+        );
+    }
+
+    Ok(())
+}
+
+pub(super) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+    }
+}
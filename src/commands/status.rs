@@ -0,0 +1,128 @@
+use quaid_core::providers::{
+    chatgpt::ChatGptProvider, claude::ClaudeProvider, copilot::CopilotProvider,
+    deepseek::DeepSeekProvider, fathom::FathomProvider, gemini::GeminiProvider,
+    granola::GranolaProvider, grok::GrokProvider, lechat::LeChatProvider, otter::OtterProvider,
+    poe::PoeProvider, Account,
+};
+use quaid_core::storage::{AccountStats, SyncState};
+use quaid_core::{CredentialStore, Provider, Store};
+use serde_json::json;
+use std::sync::Arc;
+
+struct AccountStatus {
+    provider: String,
+    email: String,
+    /// Email, with the workspace's name for a workspace account
+    label: String,
+    workspace_id: Option<String>,
+    account_id: String,
+    authenticated: bool,
+    stats: AccountStats,
+    sync: Option<SyncState>,
+}
+
+/// Show each account's last sync, stored totals and credentials, without pulling
+pub async fn run(
+    json: bool,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+) -> anyhow::Result<()> {
+    let mut statuses = Vec::new();
+    for account in store.list_accounts()? {
+        let authenticated = match provider_for(&account, credentials) {
+            Some(client) => client.is_authenticated().await,
+            None => false,
+        };
+        let provider = account.provider.0.clone();
+        statuses.push(AccountStatus {
+            stats: store.account_stats(&account.id)?,
+            sync: store.sync_state(&provider, &account.id)?,
+            provider,
+            label: account.label(),
+            email: account.email,
+            workspace_id: account.workspace_id,
+            account_id: account.id,
+            authenticated,
+        });
+    }
+
+    if json {
+        let rows: Vec<_> = statuses
+            .iter()
+            .map(|status| {
+                json!({
+                    "provider": status.provider,
+                    "email": status.email,
+                    "account_id": status.account_id,
+                    "workspace_id": status.workspace_id,
+                    "authenticated": status.authenticated,
+                    "conversations": status.stats.conversations,
+                    "messages": status.stats.messages,
+                    "pending_attachments": status.stats.pending_attachments,
+                    "sync": status.sync,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    if statuses.is_empty() {
+        println!("No accounts configured. Use `quaid <provider> auth` first.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<9} {:<28} {:<17} {:>13} {:>8}  AUTH",
+        "PROVIDER", "EMAIL", "LAST SYNC", "CONVERSATIONS", "PENDING"
+    );
+    for status in &statuses {
+        let auth = if status.authenticated {
+            "ok"
+        } else {
+            "missing"
+        };
+        let last_sync = status
+            .sync
+            .as_ref()
+            .and_then(|sync| sync.last_pull_at)
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        println!(
+            "{:<9} {:<28} {:<17} {:>13} {:>8}  {}",
+            status.provider,
+            status.label,
+            last_sync,
+            status.stats.conversations,
+            status.stats.pending_attachments,
+            auth
+        );
+        if let Some(error) = status.sync.as_ref().and_then(|s| s.last_error.as_ref()) {
+            println!("          last pull failed: {}", error);
+        }
+    }
+    Ok(())
+}
+
+/// A client for `account`'s provider, loaded with its saved credentials
+pub fn provider_for(
+    account: &Account,
+    credentials: &Arc<dyn CredentialStore>,
+) -> Option<Box<dyn Provider>> {
+    let email = Some(account.email.as_str());
+    let store = credentials.clone();
+    Some(match account.provider.0.as_str() {
+        "chatgpt" => Box::new(ChatGptProvider::with_stored_account(store, account)),
+        "claude" => Box::new(ClaudeProvider::with_account_credentials(store, email)),
+        "copilot" => Box::new(CopilotProvider::with_account_credentials(store, email)),
+        "deepseek" => Box::new(DeepSeekProvider::with_account_credentials(store, email)),
+        "fathom" => Box::new(FathomProvider::with_account_credentials(store, email)),
+        "gemini" => Box::new(GeminiProvider::with_account_credentials(store, email)),
+        "granola" => Box::new(GranolaProvider::new()),
+        "grok" => Box::new(GrokProvider::with_account_credentials(store, email)),
+        "lechat" => Box::new(LeChatProvider::with_account_credentials(store, email)),
+        "otter" => Box::new(OtterProvider::with_account_credentials(store, email)),
+        "poe" => Box::new(PoeProvider::with_account_credentials(store, email)),
+        _ => return None,
+    })
+}
@@ -0,0 +1,52 @@
+use super::pull::{self, DataDirLock, PullOptions};
+use quaid_core::embeddings::EmbeddingBackend;
+use quaid_core::pipeline::CancellationToken;
+use quaid_core::{CredentialStore, Store};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Pull every configured account, once or every `interval` until cancelled
+///
+/// Watch mode is `quaid watch`; see [`super::watch::run`].
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    account: Option<&str>,
+    options: PullOptions,
+    watch: bool,
+    interval: Duration,
+    embedding_backend: EmbeddingBackend,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    if watch {
+        return super::watch::run(
+            account,
+            options,
+            interval,
+            false,
+            embedding_backend,
+            store,
+            credentials,
+            data_dir,
+        )
+        .await;
+    }
+
+    let _lock = DataDirLock::acquire(data_dir)?;
+    let config = pull::pipeline_config(data_dir, &options, embedding_backend, credentials);
+    let cancel = CancellationToken::new();
+    pull::cancel_on_signal(cancel.clone());
+    pull::pull_selected(
+        None,
+        account,
+        &options,
+        store,
+        credentials,
+        &config,
+        &cancel,
+    )
+    .await?;
+    Ok(())
+}
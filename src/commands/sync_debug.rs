@@ -0,0 +1,58 @@
+use quaid_core::storage::{diff::content_hash, ParquetStorageConfig};
+use quaid_core::Store;
+use std::path::Path;
+
+/// Show what the store knows about one conversation's sync
+pub fn run(conversation_id: &str, store: &Store, data_dir: &Path) -> anyhow::Result<()> {
+    let Some(conv) = store.get_conversation(conversation_id)? else {
+        anyhow::bail!("Conversation not found: {}", conversation_id);
+    };
+    let messages = store.get_messages(&conv.id)?;
+    let parquet =
+        ParquetStorageConfig::new(data_dir).conversation_path(&conv.provider_id, &conv.id);
+    let indexed = parquet.exists();
+    let account_id = store.conversation_account_id(&conv.id)?;
+    let sync = match &account_id {
+        Some(account_id) => store.sync_state(&conv.provider_id, account_id)?,
+        None => None,
+    };
+
+    println!("{} ({})", conv.title, conv.id);
+    println!("  Provider:      {}", conv.provider_id);
+    println!("  Account:       {}", account_id.as_deref().unwrap_or("-"));
+    println!("  Created:       {}", conv.created_at.to_rfc3339());
+    println!("  Updated:       {}", conv.updated_at.to_rfc3339());
+    println!("  Messages:      {}", messages.len());
+    println!("  Content hash:  {}", content_hash(&conv, &messages));
+    println!(
+        "  Parquet:       {} ({})",
+        if indexed { "written" } else { "missing" },
+        parquet.display()
+    );
+
+    let decision = store.sync_decision(&conv, true, indexed)?;
+    if decision.skips() {
+        println!(
+            "  --new-only:    skipped unless the provider reports an update after {}",
+            conv.updated_at.to_rfc3339()
+        );
+    } else {
+        println!("  --new-only:    fetched ({})", decision.rule.as_str());
+    }
+
+    match sync {
+        Some(sync) => {
+            let last_pull = sync
+                .last_pull_at
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_else(|| "never".to_string());
+            println!("  Last pull:     {}", last_pull);
+            println!(
+                "  Last error:    {}",
+                sync.last_error.as_deref().unwrap_or("-")
+            );
+        }
+        None => println!("  Last pull:     never"),
+    }
+    Ok(())
+}
@@ -0,0 +1,346 @@
+use quaid_core::providers::Conversation;
+use quaid_core::storage::parquet::ParquetStore;
+use quaid_core::storage::{AttachmentFile, EmbeddingsStore, ParquetCompression};
+use quaid_core::{ParquetStorageConfig, Store};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::path::Path;
+
+/// What `quaid verify` found out of step between the stores
+#[derive(Debug, Default)]
+struct Report {
+    /// Conversations in the database without a parquet file, with their account
+    missing_parquet: Vec<(String, Conversation)>,
+    /// Parquet files of conversations the database doesn't have, as (provider, id)
+    orphaned_parquet: Vec<(String, String)>,
+    /// Attachments marked downloaded whose file is gone
+    missing_attachments: Vec<AttachmentFile>,
+    /// Search index rows whose message is gone
+    orphaned_fts: usize,
+    /// Embedded conversations the database doesn't have, by provider
+    orphaned_embeddings: BTreeMap<String, HashSet<String>>,
+}
+
+impl Report {
+    fn is_ok(&self) -> bool {
+        self.missing_parquet.is_empty()
+            && self.orphaned_parquet.is_empty()
+            && self.missing_attachments.is_empty()
+            && self.orphaned_fts == 0
+            && self.orphaned_embeddings.is_empty()
+    }
+}
+
+/// With `fix`, missing parquet files are written with `compression`
+pub fn run(
+    store: &Store,
+    data_dir: &Path,
+    fix: bool,
+    compression: ParquetCompression,
+) -> anyhow::Result<()> {
+    let report = check(store, data_dir)?;
+
+    println!("Parquet files:");
+    if report.missing_parquet.is_empty() && report.orphaned_parquet.is_empty() {
+        println!("  OK");
+    }
+    if !report.missing_parquet.is_empty() {
+        println!(
+            "  {} conversations have no parquet file:",
+            report.missing_parquet.len()
+        );
+        print_examples(report.missing_parquet.iter().map(|(_, conv)| &conv.id));
+    }
+    if !report.orphaned_parquet.is_empty() {
+        println!(
+            "  {} parquet files have no conversation in the database:",
+            report.orphaned_parquet.len()
+        );
+        print_examples(
+            report
+                .orphaned_parquet
+                .iter()
+                .map(|(provider, id)| format!("{}/{}", provider, id)),
+        );
+    }
+
+    println!("Attachments:");
+    if report.missing_attachments.is_empty() {
+        println!("  OK");
+    } else {
+        println!(
+            "  {} downloaded attachments are missing their file:",
+            report.missing_attachments.len()
+        );
+        print_examples(
+            report
+                .missing_attachments
+                .iter()
+                .filter_map(|file| file.local_path.as_deref()),
+        );
+    }
+
+    println!("Search index:");
+    if report.orphaned_fts == 0 {
+        println!("  OK");
+    } else {
+        println!(
+            "  {} entries point at messages that are gone",
+            report.orphaned_fts
+        );
+    }
+
+    println!("Embeddings:");
+    if report.orphaned_embeddings.is_empty() {
+        println!("  OK");
+    }
+    for (provider, ids) in &report.orphaned_embeddings {
+        println!(
+            "  {} {} conversations have embeddings but no conversation in the database:",
+            ids.len(),
+            provider
+        );
+        let mut ids: Vec<_> = ids.iter().collect();
+        ids.sort();
+        print_examples(ids);
+    }
+
+    if report.is_ok() {
+        return Ok(());
+    }
+    if fix {
+        println!();
+        repair(store, data_dir, compression, &report)?;
+    } else {
+        println!();
+        println!("Run `quaid verify --fix` to reconcile the stores.");
+    }
+
+    Ok(())
+}
+
+fn print_examples<T: Display>(items: impl IntoIterator<Item = T>) {
+    let items: Vec<T> = items.into_iter().collect();
+    for item in items.iter().take(10) {
+        println!("    {}", item);
+    }
+    if items.len() > 10 {
+        println!("    ... and {} more", items.len() - 10);
+    }
+}
+
+/// Cross-reference the database with the parquet files and embeddings under `data_dir`
+fn check(store: &Store, data_dir: &Path) -> anyhow::Result<Report> {
+    let config = ParquetStorageConfig::new(data_dir);
+    let parquet = ParquetStore::new(config.clone());
+    let embeddings = EmbeddingsStore::new(config.clone());
+
+    let mut report = Report::default();
+
+    // Conversation ids in the database, by provider
+    let mut known: HashMap<String, HashSet<String>> = HashMap::new();
+    for account in store.list_accounts()? {
+        for conv in store.list_conversations(&account.id)? {
+            known
+                .entry(conv.provider_id.clone())
+                .or_default()
+                .insert(conv.id.clone());
+            if !config
+                .conversation_path(&conv.provider_id, &conv.id)
+                .exists()
+            {
+                report.missing_parquet.push((account.id.clone(), conv));
+            }
+        }
+    }
+    let no_ids = HashSet::new();
+
+    for provider in parquet.providers()? {
+        let ids = known.get(&provider).unwrap_or(&no_ids);
+        let mut orphaned: Vec<String> = parquet
+            .list_conversation_ids(&provider)?
+            .into_iter()
+            .filter(|id| !ids.contains(id))
+            .collect();
+        orphaned.sort();
+        report
+            .orphaned_parquet
+            .extend(orphaned.into_iter().map(|id| (provider.clone(), id)));
+    }
+
+    report.missing_attachments = store.missing_attachment_files()?;
+    report.orphaned_fts = store.orphaned_fts_rows()?;
+
+    for provider in config.list_embedding_providers()? {
+        let ids = known.get(&provider).unwrap_or(&no_ids);
+        let orphaned: HashSet<String> = embeddings
+            .indexed_conversation_ids(&provider)?
+            .into_iter()
+            .filter(|id| !ids.contains(id))
+            .collect();
+        if !orphaned.is_empty() {
+            report.orphaned_embeddings.insert(provider, orphaned);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Bring the parquet files, attachments, search index and embeddings back in
+/// line with the database, which is taken as the source of truth
+fn repair(
+    store: &Store,
+    data_dir: &Path,
+    compression: ParquetCompression,
+    report: &Report,
+) -> anyhow::Result<()> {
+    let config = ParquetStorageConfig::new(data_dir);
+    let parquet = ParquetStore::new(config.clone().with_compression(compression));
+    let embeddings = EmbeddingsStore::new(config.clone());
+
+    for (account_id, conv) in &report.missing_parquet {
+        parquet.write_conversation(account_id, conv, &store.get_messages(&conv.id)?)?;
+    }
+    if !report.missing_parquet.is_empty() {
+        println!(
+            "Wrote {} missing parquet files",
+            report.missing_parquet.len()
+        );
+    }
+
+    for (provider, id) in &report.orphaned_parquet {
+        std::fs::remove_file(config.conversation_path(provider, id))?;
+    }
+    if !report.orphaned_parquet.is_empty() {
+        println!(
+            "Removed {} orphaned parquet files",
+            report.orphaned_parquet.len()
+        );
+    }
+
+    if !report.missing_attachments.is_empty() {
+        let cleared = store.verify_attachment_files()?;
+        println!("Marked {} attachments for download again", cleared.len());
+    }
+
+    if report.orphaned_fts > 0 {
+        let indexed = store.rebuild_fts()?;
+        println!("Rebuilt the search index ({} messages)", indexed);
+    }
+
+    for (provider, ids) in &report.orphaned_embeddings {
+        let removed = embeddings.remove_conversations(provider, ids)?;
+        println!(
+            "Removed the embeddings of {} {} conversations",
+            removed, provider
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quaid_core::embeddings::Chunk;
+    use quaid_core::providers::{Account, Message, MessageContent, ProviderId, Role};
+
+    fn conversation(id: &str) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            provider_id: "claude".to_string(),
+            title: id.to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        }
+    }
+
+    fn message(id: &str, conversation_id: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: "notes".to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn test_fix_reconciles_the_stores() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ParquetStorageConfig::new(dir.path());
+        let parquet = ParquetStore::new(config.clone());
+        let store = Store::in_memory().unwrap();
+        store
+            .save_account(&Account {
+                id: "user-1".to_string(),
+                provider: ProviderId::claude(),
+                email: "me@example.com".to_string(),
+                name: None,
+                avatar_url: None,
+                workspace_id: None,
+            })
+            .unwrap();
+
+        // In sync, in the database only, and in parquet only
+        store
+            .save_conversation_with_messages(
+                "user-1",
+                &conversation("conv-1"),
+                &[message("msg-1", "conv-1")],
+            )
+            .unwrap();
+        parquet
+            .write_conversation("user-1", &conversation("conv-1"), &[])
+            .unwrap();
+        store
+            .save_conversation_with_messages(
+                "user-1",
+                &conversation("conv-2"),
+                &[message("msg-2", "conv-2")],
+            )
+            .unwrap();
+        parquet
+            .write_conversation("user-1", &conversation("gone"), &[])
+            .unwrap();
+
+        let chunk = Chunk {
+            text: "notes".to_string(),
+            message_id: "msg-x".to_string(),
+            chunk_index: 0,
+            total_chunks: 1,
+        };
+        let embedding = vec![0.0; quaid_core::storage::embeddings::EMBEDDING_DIM as usize];
+        EmbeddingsStore::new(config.clone())
+            .write_embeddings("gone", "claude", &[chunk], &[embedding])
+            .unwrap();
+
+        let report = check(&store, dir.path()).unwrap();
+        assert_eq!(report.missing_parquet.len(), 1);
+        assert_eq!(report.missing_parquet[0].1.id, "conv-2");
+        assert_eq!(
+            report.orphaned_parquet,
+            vec![("claude".to_string(), "gone".to_string())]
+        );
+        assert_eq!(
+            report.orphaned_embeddings["claude"],
+            HashSet::from(["gone".to_string()])
+        );
+        assert!(report.missing_attachments.is_empty());
+        assert_eq!(report.orphaned_fts, 0);
+
+        repair(&store, dir.path(), ParquetCompression::default(), &report).unwrap();
+        assert!(check(&store, dir.path()).unwrap().is_ok());
+        assert!(config.conversation_path("claude", "conv-2").exists());
+        assert!(!config.conversation_path("claude", "gone").exists());
+    }
+}
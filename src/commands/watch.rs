@@ -0,0 +1,207 @@
+use super::pull::{self, log_time, rate_limit_wait, DataDirLock, PullOptions};
+use quaid_core::embeddings::EmbeddingBackend;
+use quaid_core::pipeline::CancellationToken;
+use quaid_core::{CredentialStore, Store};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Longest back-off after repeated rate limits, in intervals
+const MAX_BACKOFF: u32 = 8;
+
+/// Pull every configured account every `interval` until cancelled, or once
+///
+/// A failed pull is logged and retried on the next run; each account's
+/// failure is already kept from stopping the others. After a rate-limited
+/// run the interval doubles, up to eight times, and never undercuts the wait
+/// the provider asked for.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    account: Option<&str>,
+    options: PullOptions,
+    interval: Duration,
+    once: bool,
+    embedding_backend: EmbeddingBackend,
+    store: &Store,
+    credentials: &Arc<dyn CredentialStore>,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let _lock = DataDirLock::acquire(data_dir)?;
+    let config = pull::pipeline_config(data_dir, &options, embedding_backend, credentials);
+    let cancel = CancellationToken::new();
+    pull::cancel_on_signal(cancel.clone());
+
+    if !once {
+        println!(
+            "{} watching, pulling every {}",
+            log_time(),
+            format_interval(interval)
+        );
+    }
+    let mut backoff = 1;
+    loop {
+        println!("{} sync started", log_time());
+        let pulled = pull::pull_selected(
+            None,
+            account,
+            &options,
+            store,
+            credentials,
+            &config,
+            &cancel,
+        )
+        .await;
+        let rate_limited = match pulled {
+            Ok(summary) if summary.failed > 0 => {
+                println!(
+                    "{} sync finished, {} account(s) failed",
+                    log_time(),
+                    summary.failed
+                );
+                summary.rate_limited
+            }
+            Ok(summary) => {
+                println!("{} sync finished", log_time());
+                summary.rate_limited
+            }
+            Err(e) => {
+                eprintln!("{} sync failed: {}", log_time(), e);
+                rate_limit_wait(&e)
+            }
+        };
+        if once || cancel.is_cancelled() {
+            break;
+        }
+
+        let wait = match rate_limited {
+            Some(retry_after) => {
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                let wait = backed_off(interval, backoff, retry_after);
+                println!(
+                    "{} rate limited, backing off to {}",
+                    log_time(),
+                    format_interval(wait)
+                );
+                wait
+            }
+            None => {
+                backoff = 1;
+                let seed = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map(|d| d.subsec_nanos())
+                    .unwrap_or_default();
+                jittered(interval, seed)
+            }
+        };
+        println!("{} next sync in {}", log_time(), format_interval(wait));
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = cancel.cancelled() => break,
+        }
+    }
+    if !once {
+        println!("{} stopped", log_time());
+    }
+    Ok(())
+}
+
+/// Parse an interval like `90s`, `30m`, `2h`, `1d` or `1h30m`
+pub fn parse_interval(value: &str) -> anyhow::Result<Duration> {
+    let invalid = || anyhow::anyhow!("Invalid interval: {}. Use e.g. 30m, 2h or 1h30m", value);
+
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let n: u64 = number.parse().map_err(|_| invalid())?;
+        total += n * unit;
+        number.clear();
+    }
+    if !number.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// `backoff` intervals, or as long as the provider asked if that's longer
+fn backed_off(interval: Duration, backoff: u32, retry_after: Duration) -> Duration {
+    (interval * backoff).max(retry_after)
+}
+
+/// `interval` moved up to a tenth either way, so machines started together drift apart
+fn jittered(interval: Duration, seed: u32) -> Duration {
+    let spread = interval.as_secs_f64() / 10.0;
+    let offset = spread * ((seed % 2001) as f64 / 1000.0 - 1.0);
+    Duration::from_secs_f64((interval.as_secs_f64() + offset).max(1.0))
+}
+
+fn format_interval(interval: Duration) -> String {
+    let secs = interval.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, 0) => format!("{}m", m),
+        (0, m, s) => format!("{}m{}s", m, s),
+        (h, 0, _) => format!("{}h", h),
+        (h, m, _) => format!("{}h{}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_interval("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_interval("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86400));
+        for bad in ["", "30", "m", "0m", "2w", "-5m"] {
+            assert!(parse_interval(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_jitter_stays_within_a_tenth() {
+        let interval = Duration::from_secs(1800);
+        assert_eq!(jittered(interval, 1000), interval);
+        assert_eq!(jittered(interval, 0), Duration::from_secs(1620));
+        assert_eq!(jittered(interval, 2000), Duration::from_secs(1980));
+        assert_eq!(format_interval(Duration::from_secs(1620)), "27m");
+        assert_eq!(format_interval(Duration::from_secs(5430)), "1h30m");
+    }
+
+    #[test]
+    fn test_backoff_respects_the_provider() {
+        let interval = Duration::from_secs(600);
+        assert_eq!(
+            backed_off(interval, 2, Duration::from_secs(60)),
+            Duration::from_secs(1200)
+        );
+        assert_eq!(
+            backed_off(interval, MAX_BACKOFF, Duration::from_secs(3)),
+            Duration::from_secs(4800)
+        );
+        assert_eq!(
+            backed_off(interval, 2, Duration::from_secs(7200)),
+            Duration::from_secs(7200)
+        );
+    }
+
+    #[test]
+    fn test_rate_limits_are_recognized() {
+        let limited = anyhow::Error::from(quaid_core::providers::ProviderError::RateLimited(90));
+        assert_eq!(rate_limit_wait(&limited), Some(Duration::from_secs(90)));
+        assert_eq!(rate_limit_wait(&anyhow::anyhow!("boom")), None);
+    }
+}
@@ -0,0 +1,671 @@
+//! `config.toml`: defaults for CLI flags and per-provider options
+//!
+//! Read from the data directory, or from `--config`. A flag given on the
+//! command line wins over the config file, which wins over the built-in
+//! defaults below.
+
+use crate::commands::pull::PullOptions;
+use crate::commands::serve::ServeOptions;
+use quaid_core::embeddings::{ChunkStrategy, ChunkerConfig};
+use quaid_core::storage::{ParquetCompression, DEFAULT_ZSTD_LEVEL, MAX_ZSTD_LEVEL};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+
+/// File name looked up in the data directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Conversations fetched at once when neither flag nor config says otherwise
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Export format when neither flag nor config says otherwise
+pub const DEFAULT_EXPORT_FORMAT: &str = "jsonl";
+
+/// `quaid serve` port when neither flag nor config says otherwise
+pub const DEFAULT_SERVE_PORT: u16 = 8484;
+
+/// Tokens shared by consecutive chunks when only `max_tokens` is set
+pub const DEFAULT_OVERLAP_TOKENS: usize = 32;
+
+/// Values `storage.compression` can take
+const COMPRESSIONS: [&str; 3] = ["zstd", "snappy", "none"];
+
+/// Shown by `quaid config show` in place of the API token
+const REDACTED: &str = "********";
+
+/// Providers quaid can pull from, and so can appear in `pull.providers`
+pub const PROVIDERS: [&str; 11] = [
+    "chatgpt", "claude", "copilot", "deepseek", "fathom", "gemini", "granola", "grok", "lechat",
+    "otter", "poe",
+];
+
+/// Commented template written by `quaid config init`
+pub const TEMPLATE: &str = r#"# quaid configuration
+#
+# Flags on the command line override these values. Uncomment a line to use it.
+
+[pull]
+# Providers pulled by `quaid pull` and `quaid sync` (default: every signed-in account)
+# providers = ["chatgpt", "claude"]
+# Conversations and attachments to fetch at once, capped per provider
+# concurrency = 4
+
+[embeddings]
+# Embedder for the semantic index: local, openai (or remote) or ollama
+# (default: whichever built the existing index); `backend` works too
+# embedder = "local"
+# model = "text-embedding-3-small"
+# OpenAI-compatible API or Ollama server to call instead of the default one.
+# The API key comes from QUAID_EMBEDDINGS_API_KEY or `quaid embeddings auth`
+# endpoint = "http://localhost:11434"
+
+[chunker]
+# Longest chunk embedded at once, in characters
+# max_chunk_chars = 1024
+# Characters shared by consecutive chunks
+# overlap = 128
+# Set to measure chunks in tokens of the local model's tokenizer instead
+# max_tokens = 256
+# overlap_tokens = 32
+# Also embed the model's thinking, when pulled, so it shows up in semantic search
+# embed_thinking = false
+
+[export]
+# Default `quaid export --format`: jsonl, markdown, json, csv or sqlite
+# format = "jsonl"
+
+[storage]
+# Codec for conversation files: zstd, snappy or none
+# compression = "zstd"
+# ZSTD level, from 1 (fastest) to 22 (smallest files)
+# zstd_level = 1
+
+[serve]
+# Address `quaid serve` listens on; use a token for anything but localhost
+# host = "127.0.0.1"
+# port = 8484
+# Required on every request as `Authorization: Bearer <token>`
+# token = "change-me"
+# Browser origins allowed to call the API, for a local web UI
+# cors_origins = ["http://localhost:5173"]
+# Secret webhook deliveries to `POST /ingest/<provider>` are signed with,
+# as Fathom shows it when you add the webhook; they're refused without it
+# webhook_secret = "whsec_..."
+
+[providers.chatgpt]
+# Also pull conversations archived in the web app
+# include_archived = false
+
+[providers.claude]
+# Organization to pull instead of the one picked at sign-in
+# org_id = "00000000-0000-0000-0000-000000000000"
+# Also pull project knowledge documents, one conversation per project
+# include_project_docs = false
+# Also keep the model's extended thinking alongside its answers
+# include_thinking = false
+
+[providers.deepseek]
+# Also keep DeepThink (R1) reasoning alongside its answers
+# include_thinking = false
+
+[providers.fathom]
+# Only pull meetings recorded by these teams
+# teams = ["Sales"]
+# Also download meeting recordings, on plans that allow it
+# include_recordings = false
+"#;
+
+/// Everything `config.toml` can set; unset values fall back to the defaults
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub pull: PullConfig,
+    pub embeddings: EmbeddingsConfig,
+    pub chunker: ChunkerSection,
+    pub export: ExportConfig,
+    pub storage: StorageConfig,
+    pub serve: ServeConfig,
+    pub providers: ProvidersConfig,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PullConfig {
+    /// Providers pulled when none is named; every account when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingsConfig {
+    #[serde(alias = "backend")]
+    pub embedder: Option<String>,
+    pub model: Option<String>,
+    pub endpoint: Option<String>,
+}
+
+/// Embedder for the semantic index, as the flags and config name it
+///
+/// Whatever is left unset follows the embedder that built the existing index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmbedderChoice {
+    pub embedder: Option<String>,
+    pub model: Option<String>,
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChunkerSection {
+    pub max_chunk_chars: Option<usize>,
+    pub overlap: Option<usize>,
+    pub max_tokens: Option<usize>,
+    pub overlap_tokens: Option<usize>,
+    pub embed_thinking: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub compression: Option<String>,
+    pub zstd_level: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServeConfig {
+    pub host: Option<IpAddr>,
+    pub port: Option<u16>,
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cors_origins: Vec<String>,
+    pub webhook_secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvidersConfig {
+    pub chatgpt: ChatGptConfig,
+    pub claude: ClaudeConfig,
+    pub deepseek: DeepSeekConfig,
+    pub fathom: FathomConfig,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChatGptConfig {
+    pub include_archived: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaudeConfig {
+    pub org_id: Option<String>,
+    pub include_project_docs: Option<bool>,
+    pub include_thinking: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeepSeekConfig {
+    pub include_thinking: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FathomConfig {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub teams: Vec<String>,
+    pub include_recordings: Option<bool>,
+}
+
+impl Config {
+    /// Load `path`, or `config.toml` in the data directory without one.
+    ///
+    /// A missing default file means the built-in defaults; a missing
+    /// `--config` file is an error.
+    pub fn load(path: Option<&Path>, data_dir: &Path) -> anyhow::Result<Self> {
+        let Some(path) = path else {
+            let default_path = data_dir.join(CONFIG_FILE);
+            if !default_path.exists() {
+                return Ok(Self::default());
+            }
+            return Self::read(&default_path);
+        };
+        Self::read(path)
+    }
+
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Couldn't read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        if let Some(unknown) = config
+            .pull
+            .providers
+            .iter()
+            .find(|p| !PROVIDERS.contains(&p.as_str()))
+        {
+            anyhow::bail!(
+                "Unknown provider in pull.providers: {}. Supported: {}",
+                unknown,
+                PROVIDERS.join(", ")
+            );
+        }
+        let storage = &config.storage;
+        if let Some(compression) = &storage.compression {
+            if !COMPRESSIONS.contains(&compression.as_str()) {
+                anyhow::bail!(
+                    "Unknown storage.compression: {}. Supported: {}",
+                    compression,
+                    COMPRESSIONS.join(", ")
+                );
+            }
+        }
+        if let Some(level) = storage.zstd_level {
+            if storage.compression.as_deref().is_some_and(|c| c != "zstd") {
+                anyhow::bail!("storage.zstd_level only applies to compression = \"zstd\"");
+            }
+            if !(1..=MAX_ZSTD_LEVEL).contains(&level) {
+                anyhow::bail!(
+                    "storage.zstd_level must be between 1 and {}, got {}",
+                    MAX_ZSTD_LEVEL,
+                    level
+                );
+            }
+        }
+        Ok(config)
+    }
+
+    /// `--concurrency`, else the config, else [`DEFAULT_CONCURRENCY`]
+    pub fn concurrency(&self, flag: Option<usize>) -> usize {
+        flag.or(self.pull.concurrency)
+            .unwrap_or(DEFAULT_CONCURRENCY)
+    }
+
+    /// `--format` for export, else the config, else [`DEFAULT_EXPORT_FORMAT`]
+    pub fn export_format(&self, flag: Option<String>) -> String {
+        flag.or_else(|| self.export.format.clone())
+            .unwrap_or_else(|| DEFAULT_EXPORT_FORMAT.to_string())
+    }
+
+    /// Options for pulls nothing but the config decides on, like the ones
+    /// `quaid serve` starts: new or updated conversations only
+    pub fn pull_options(&self) -> PullOptions {
+        PullOptions {
+            new_only: true,
+            concurrency: self.concurrency(None),
+            explain_skips: false,
+            verbose: false,
+            log_progress: true,
+            low_memory: false,
+            providers: self.pull.providers.clone(),
+            provider_options: self.providers.clone(),
+            chunker: self.chunker(),
+            compression: self.compression(),
+            attachments: Default::default(),
+            since: None,
+            until: None,
+            keep_history: None,
+            progress: None,
+        }
+    }
+
+    /// `quaid serve` options: `--host`/`--port`, else the config, else localhost
+    pub fn serve(&self, host: Option<IpAddr>, port: Option<u16>) -> ServeOptions {
+        ServeOptions {
+            host: host
+                .or(self.serve.host)
+                .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            port: port.or(self.serve.port).unwrap_or(DEFAULT_SERVE_PORT),
+            token: self.serve.token.clone().filter(|t| !t.is_empty()),
+            cors_origins: self.serve.cors_origins.clone(),
+            webhook_secret: self.serve.webhook_secret.clone().filter(|s| !s.is_empty()),
+            ui: false,
+            pull: self.pull_options(),
+            embedder: self.embedder(None, None, None),
+        }
+    }
+
+    /// Embedder, model and endpoint from `--embedder`, `--embedding-model`
+    /// and `--embeddings-endpoint`, else the config
+    ///
+    /// The configured model and endpoint only apply to the configured
+    /// embedder, so `--embedder ollama` doesn't pick up an OpenAI model name.
+    pub fn embedder(
+        &self,
+        embedder: Option<String>,
+        model: Option<String>,
+        endpoint: Option<String>,
+    ) -> EmbedderChoice {
+        let configured = &self.embeddings;
+        match embedder {
+            Some(name) if configured.embedder.as_deref() != Some(name.as_str()) => EmbedderChoice {
+                embedder: Some(name),
+                model,
+                endpoint,
+            },
+            embedder => EmbedderChoice {
+                embedder: embedder.or_else(|| configured.embedder.clone()),
+                model: model.or_else(|| configured.model.clone()),
+                endpoint: endpoint.or_else(|| configured.endpoint.clone()),
+            },
+        }
+    }
+
+    /// Chunker settings, starting from the built-in ones
+    pub fn chunker(&self) -> ChunkerConfig {
+        let mut chunker = ChunkerConfig::default();
+        if let Some(max_chunk_chars) = self.chunker.max_chunk_chars {
+            chunker.max_chunk_chars = max_chunk_chars;
+        }
+        if let Some(overlap) = self.chunker.overlap {
+            chunker.overlap_chars = overlap;
+        }
+        if let Some(max_tokens) = self.chunker.max_tokens {
+            chunker.strategy = ChunkStrategy::Tokens {
+                max_tokens,
+                overlap_tokens: self
+                    .chunker
+                    .overlap_tokens
+                    .unwrap_or(DEFAULT_OVERLAP_TOKENS),
+            };
+        }
+        if let Some(embed_thinking) = self.chunker.embed_thinking {
+            chunker.embed_thinking = embed_thinking;
+        }
+        chunker
+    }
+
+    /// Codec for conversation files, ZSTD at [`DEFAULT_ZSTD_LEVEL`] by default
+    pub fn compression(&self) -> ParquetCompression {
+        match self.storage.compression.as_deref() {
+            Some("none") => ParquetCompression::Uncompressed,
+            Some("snappy") => ParquetCompression::Snappy,
+            _ => ParquetCompression::Zstd {
+                level: self.storage.zstd_level.unwrap_or(DEFAULT_ZSTD_LEVEL),
+            },
+        }
+    }
+
+    /// This config with every unset value filled in with its default
+    ///
+    /// What `quaid config show` prints. The embedder stays unset when neither
+    /// is configured, since it then follows the existing index. The API token
+    /// and webhook secret are masked.
+    pub fn effective(&self) -> Self {
+        let chunker = self.chunker();
+        let mut effective = self.clone();
+        effective.pull.concurrency = Some(self.concurrency(None));
+        effective.chunker = match chunker.strategy {
+            ChunkStrategy::Chars => ChunkerSection {
+                max_chunk_chars: Some(chunker.max_chunk_chars),
+                overlap: Some(chunker.overlap_chars),
+                embed_thinking: Some(chunker.embed_thinking),
+                ..Default::default()
+            },
+            ChunkStrategy::Tokens {
+                max_tokens,
+                overlap_tokens,
+            } => ChunkerSection {
+                max_tokens: Some(max_tokens),
+                overlap_tokens: Some(overlap_tokens),
+                embed_thinking: Some(chunker.embed_thinking),
+                ..Default::default()
+            },
+        };
+        effective.export.format = Some(self.export_format(None));
+        effective.storage = match self.compression() {
+            ParquetCompression::Uncompressed => StorageConfig {
+                compression: Some("none".to_string()),
+                zstd_level: None,
+            },
+            ParquetCompression::Snappy => StorageConfig {
+                compression: Some("snappy".to_string()),
+                zstd_level: None,
+            },
+            ParquetCompression::Zstd { level } => StorageConfig {
+                compression: Some("zstd".to_string()),
+                zstd_level: Some(level),
+            },
+        };
+        let serve = self.serve(None, None);
+        effective.serve.host = Some(serve.host);
+        effective.serve.port = Some(serve.port);
+        effective.serve.token = serve.token.map(|_| REDACTED.to_string());
+        effective.serve.webhook_secret = serve.webhook_secret.map(|_| REDACTED.to_string());
+        effective.providers.chatgpt.include_archived =
+            Some(self.providers.chatgpt.include_archived.unwrap_or(false));
+        effective.providers.claude.include_project_docs =
+            Some(self.providers.claude.include_project_docs.unwrap_or(false));
+        effective.providers.claude.include_thinking =
+            Some(self.providers.claude.include_thinking.unwrap_or(false));
+        effective.providers.deepseek.include_thinking =
+            Some(self.providers.deepseek.include_thinking.unwrap_or(false));
+        effective.providers.fathom.include_recordings =
+            Some(self.providers.fathom.include_recordings.unwrap_or(false));
+        effective
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_parses_to_defaults() {
+        assert_eq!(Config::parse(TEMPLATE).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_flags_override_config_override_defaults() {
+        let config = Config::parse(
+            r#"
+            [pull]
+            concurrency = 8
+
+            [export]
+            format = "markdown"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.concurrency(Some(2)), 2);
+        assert_eq!(config.concurrency(None), 8);
+        assert_eq!(Config::default().concurrency(None), DEFAULT_CONCURRENCY);
+
+        assert_eq!(config.export_format(Some("csv".to_string())), "csv");
+        assert_eq!(config.export_format(None), "markdown");
+        assert_eq!(Config::default().export_format(None), "jsonl");
+    }
+
+    #[test]
+    fn test_embedder_precedence() {
+        let config = Config::parse(
+            r#"
+            [embeddings]
+            backend = "openai"
+            model = "text-embedding-3-large"
+            endpoint = "http://gateway:4000/v1"
+            "#,
+        )
+        .unwrap();
+        let some = |s: &str| Some(s.to_string());
+        let choice =
+            |embedder: &str, model: Option<String>, endpoint: Option<String>| EmbedderChoice {
+                embedder: some(embedder),
+                model,
+                endpoint,
+            };
+
+        assert_eq!(
+            config.embedder(None, None, None),
+            choice(
+                "openai",
+                some("text-embedding-3-large"),
+                some("http://gateway:4000/v1")
+            )
+        );
+        assert_eq!(
+            config.embedder(
+                None,
+                some("text-embedding-3-small"),
+                some("http://other/v1")
+            ),
+            choice(
+                "openai",
+                some("text-embedding-3-small"),
+                some("http://other/v1")
+            )
+        );
+        // A different embedder on the command line drops the configured model
+        // and endpoint
+        assert_eq!(
+            config.embedder(some("ollama"), None, None),
+            choice("ollama", None, None)
+        );
+        assert_eq!(
+            Config::default().embedder(None, None, None),
+            EmbedderChoice::default()
+        );
+    }
+
+    #[test]
+    fn test_chunker_and_provider_options() {
+        let config = Config::parse(
+            r#"
+            [chunker]
+            max_chunk_chars = 2000
+
+            [providers.chatgpt]
+            include_archived = true
+
+            [providers.fathom]
+            teams = ["Sales", "Support"]
+            include_recordings = true
+            "#,
+        )
+        .unwrap();
+
+        let chunker = config.chunker();
+        assert_eq!(chunker.max_chunk_chars, 2000);
+        assert_eq!(
+            chunker.overlap_chars,
+            ChunkerConfig::default().overlap_chars
+        );
+        assert_eq!(config.providers.chatgpt.include_archived, Some(true));
+        assert_eq!(config.providers.fathom.teams, vec!["Sales", "Support"]);
+        assert_eq!(config.providers.fathom.include_recordings, Some(true));
+        assert_eq!(chunker.strategy, ChunkStrategy::Chars);
+        assert!(!chunker.embed_thinking);
+        assert!(
+            Config::parse("[chunker]\nembed_thinking = true\n")
+                .unwrap()
+                .chunker()
+                .embed_thinking
+        );
+
+        let tokens = Config::parse("[chunker]\nmax_tokens = 200\n").unwrap();
+        assert_eq!(
+            tokens.chunker().strategy,
+            ChunkStrategy::Tokens {
+                max_tokens: 200,
+                overlap_tokens: DEFAULT_OVERLAP_TOKENS
+            }
+        );
+        assert_eq!(tokens.effective().chunker.max_tokens, Some(200));
+        assert_eq!(tokens.effective().chunker.max_chunk_chars, None);
+    }
+
+    #[test]
+    fn test_serve_options() {
+        let config = Config::parse(
+            r#"
+            [serve]
+            port = 9000
+            token = "s3cret"
+            cors_origins = ["http://localhost:5173"]
+            webhook_secret = "h00k"
+            "#,
+        )
+        .unwrap();
+
+        let options = config.serve(None, Some(9001));
+        assert!(options.host.is_loopback());
+        assert_eq!(options.port, 9001);
+        assert_eq!(options.token.as_deref(), Some("s3cret"));
+        assert_eq!(config.serve(None, None).port, 9000);
+        assert_eq!(Config::default().serve(None, None).port, DEFAULT_SERVE_PORT);
+
+        assert_eq!(options.webhook_secret.as_deref(), Some("h00k"));
+
+        let shown = config.effective().to_toml().unwrap();
+        assert!(!shown.contains("s3cret"));
+        assert!(!shown.contains("h00k"));
+    }
+
+    #[test]
+    fn test_storage_compression() {
+        assert_eq!(
+            Config::default().compression(),
+            ParquetCompression::Zstd {
+                level: DEFAULT_ZSTD_LEVEL
+            }
+        );
+        assert_eq!(
+            Config::parse("[storage]\nzstd_level = 9\n")
+                .unwrap()
+                .compression(),
+            ParquetCompression::Zstd { level: 9 }
+        );
+        let snappy = Config::parse("[storage]\ncompression = \"snappy\"\n").unwrap();
+        assert_eq!(snappy.compression(), ParquetCompression::Snappy);
+        assert_eq!(snappy.effective().storage.zstd_level, None);
+
+        assert!(Config::parse("[storage]\ncompression = \"lz4\"\n").is_err());
+        assert!(Config::parse("[storage]\nzstd_level = 23\n").is_err());
+        assert!(Config::parse("[storage]\ncompression = \"none\"\nzstd_level = 3\n").is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_settings() {
+        assert!(Config::parse("[pull]\nprovider = [\"claude\"]").is_err());
+        assert!(Config::parse("[pull]\nproviders = [\"bard\"]").is_err());
+    }
+
+    #[test]
+    fn test_effective_config_round_trips() {
+        let config = Config::parse("[pull]\nproviders = [\"claude\"]").unwrap();
+        let effective = config.effective();
+        assert_eq!(effective.pull.concurrency, Some(DEFAULT_CONCURRENCY));
+        assert_eq!(effective.export.format.as_deref(), Some("jsonl"));
+
+        let shown = effective.to_toml().unwrap();
+        assert_eq!(Config::parse(&shown).unwrap(), effective);
+    }
+
+    #[test]
+    fn test_load_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Config::load(None, dir.path()).unwrap(), Config::default());
+        assert!(Config::load(Some(&dir.path().join("nope.toml")), dir.path()).is_err());
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "[pull]\nconcurrency = 2").unwrap();
+        assert_eq!(Config::load(None, dir.path()).unwrap().concurrency(None), 2);
+    }
+}
@@ -1,7 +1,17 @@
 mod commands;
+mod config;
 
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use config::Config;
+use quaid_core::embeddings::EmbeddingBackend;
+use quaid_core::pipeline::PipelineConfig;
+use quaid_core::sample::{SampleOptions, SampleStrategy};
+use quaid_core::storage::AttachmentFilter;
+use quaid_core::{CredentialStore, FileStore, KeyringStore};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[command(name = "quaid")]
@@ -12,6 +22,15 @@ struct Cli {
     #[arg(long, global = true)]
     data_dir: Option<PathBuf>,
 
+    /// Config file to use instead of config.toml in the data directory
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Where provider credentials are kept: keyring, or file for an encrypted
+    /// file in the data directory unlocked with QUAID_PASSPHRASE (or a prompt)
+    #[arg(long, global = true, default_value = "keyring")]
+    credential_store: String,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -21,13 +40,13 @@ enum Commands {
     /// ChatGPT provider commands
     Chatgpt {
         #[command(subcommand)]
-        action: ProviderAction,
+        action: ChatGptAction,
     },
 
     /// Claude provider commands
     Claude {
         #[command(subcommand)]
-        action: ProviderAction,
+        action: ClaudeAction,
     },
 
     /// Fathom.video provider commands
@@ -42,11 +61,58 @@ enum Commands {
         action: ProviderAction,
     },
 
+    /// Copilot provider commands
+    Copilot {
+        #[command(subcommand)]
+        action: ProviderAction,
+    },
+
+    /// Gemini provider commands
+    Gemini {
+        #[command(subcommand)]
+        action: ProviderAction,
+    },
+
+    /// Grok provider commands
+    Grok {
+        #[command(subcommand)]
+        action: ProviderAction,
+    },
+
+    /// Mistral Le Chat provider commands
+    Lechat {
+        #[command(subcommand)]
+        action: ProviderAction,
+    },
+
+    /// DeepSeek provider commands
+    Deepseek {
+        #[command(subcommand)]
+        action: ProviderAction,
+    },
+
+    /// Poe provider commands
+    Poe {
+        #[command(subcommand)]
+        action: ProviderAction,
+    },
+
+    /// Otter.ai provider commands
+    Otter {
+        #[command(subcommand)]
+        action: ProviderAction,
+    },
+
+    /// Google AI Studio commands
+    Aistudio {
+        #[command(subcommand)]
+        action: AiStudioAction,
+    },
+
     /// Pull from all configured providers (or specify one with quaid <provider> pull)
     Pull {
-        /// Only pull new or updated conversations
-        #[arg(long)]
-        new_only: bool,
+        #[command(flatten)]
+        args: PullArgs,
     },
 
     /// List local conversations
@@ -55,9 +121,60 @@ enum Commands {
         #[arg(long)]
         provider: Option<String>,
 
+        /// Only the account signed in with this email
+        #[arg(long)]
+        account: Option<String>,
+
+        /// Only conversations in this project, by id or name (see `quaid projects`)
+        #[arg(long)]
+        project: Option<String>,
+
         /// Show archived conversations
         #[arg(long)]
         archived: bool,
+
+        /// Only conversations a full pull found deleted on the provider's site
+        #[arg(long)]
+        deleted_remotely: bool,
+
+        /// Add message counts per role and the time each conversation spans
+        #[arg(long, short)]
+        verbose: bool,
+
+        /// Conversations to show per account
+        #[arg(long, default_value = "20")]
+        limit: usize,
+
+        /// Skip this many of each account's most recent conversations
+        #[arg(long, default_value = "0")]
+        offset: usize,
+    },
+
+    /// Show a conversation's messages and a summary of who said how much
+    Show {
+        /// Conversation ID
+        id: String,
+
+        /// Output format (text, json)
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// List the earlier versions `pull --keep-history` kept of a conversation
+    History {
+        /// Conversation ID
+        id: String,
+
+        /// Compare two revisions by number, or one with the current version
+        #[arg(long, num_args = 1..=2, value_name = "REVISION")]
+        diff: Vec<usize>,
+    },
+
+    /// List ChatGPT and Claude projects
+    Projects {
+        /// Filter by provider
+        #[arg(long)]
+        provider: Option<String>,
     },
 
     /// Search conversations
@@ -76,6 +193,85 @@ enum Commands {
         /// Use hybrid search (FTS + semantic)
         #[arg(long)]
         hybrid: bool,
+
+        /// Reorder semantic results with a cross-encoder (downloaded on first use)
+        #[arg(long)]
+        rerank: bool,
+
+        /// Treat the query as a regex matched against stored messages; `(?i)`
+        /// ignores case. Scans every parquet file, so slower than full-text search
+        #[arg(long)]
+        regex: bool,
+
+        /// Only search conversations from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only search conversations from the account signed in with this email
+        #[arg(long)]
+        account: Option<String>,
+
+        /// Only search conversations created on or after this date
+        /// (2024-06-01, 2024-06, RFC3339, 7d, 2mo, yesterday, last monday...)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only search conversations created on or before this date, inclusive
+        /// (2024-06-01, 2024-06, RFC3339, 7d, 2mo, yesterday, last monday...)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only match messages with these roles (user, assistant, system, tool)
+        #[arg(long, value_delimiter = ',')]
+        role: Vec<String>,
+
+        /// Leave out archived conversations
+        #[arg(long)]
+        exclude_archived: bool,
+
+        /// Also search text extracted from attachments (see `quaid index attachments`)
+        #[arg(long)]
+        attachments: bool,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Find messages containing a literal string or regex, without the search index
+    ///
+    /// For code and error messages that full-text search tokenizes away.
+    Grep {
+        /// Text to look for (a regex with --regex)
+        pattern: String,
+
+        /// Treat the pattern as a regular expression
+        #[arg(long)]
+        regex: bool,
+
+        /// Match case-insensitively
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+
+        /// Only search conversations from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Lines of context to show around each match
+        #[arg(short = 'C', long, default_value_t = 0)]
+        context: usize,
+
+        /// Stop after this many matching lines
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+
+        /// Only print the number of matching lines per conversation
+        #[arg(long)]
+        count_only: bool,
+
+        /// Where to read messages from (parquet, sqlite)
+        #[arg(long, default_value = "parquet")]
+        engine: String,
     },
 
     /// Export conversations
@@ -83,36 +279,696 @@ enum Commands {
         /// Output path
         path: PathBuf,
 
-        /// Export format (jsonl, markdown, json)
-        #[arg(long, default_value = "jsonl")]
-        format: String,
+        /// Export format (jsonl, markdown, json, csv, sqlite, obsidian) [default: jsonl]
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Write one file per conversation into the output directory (markdown or json)
+        #[arg(long)]
+        split: bool,
+
+        /// Like --split, but only rewrite files of conversations changed since the
+        /// last incremental export, and remove those of deleted conversations
+        #[arg(long)]
+        incremental: bool,
+
+        /// Filter by provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only export conversations in this project, by id or name (see `quaid projects`)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only export conversations created on or after this date
+        /// (2024-06-01, 2024-06, RFC3339, 7d, 2mo, yesterday, last monday...)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only export conversations created on or before this date, inclusive
+        /// (2024-06-01, 2024-06, RFC3339, 7d, 2mo, yesterday, last monday...)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// With --format jsonl, write OpenAI chat-format examples for fine-tuning:
+        /// one {"messages": [...]} line per conversation
+        #[arg(long)]
+        openai_messages: bool,
+
+        /// Keep system and tool messages in --openai-messages examples
+        #[arg(long)]
+        include_system: bool,
+    },
+
+    /// Check the database for inconsistencies
+    Fsck {
+        /// Search index entries to check
+        #[arg(long, default_value_t = 1000)]
+        sample: usize,
+
+        /// Rebuild the search index if it's inconsistent
+        #[arg(long)]
+        repair: bool,
+
+        /// Compact the database file afterwards
+        #[arg(long)]
+        vacuum: bool,
+    },
+
+    /// Cross-check the database against the parquet files, attachments,
+    /// search index and embeddings
+    Verify {
+        /// Reconcile what's out of step, taking the database as the source of truth
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Rebuild the full-text search index from the stored messages
+    ///
+    /// For when search misses messages, e.g. after an interrupted sync or a
+    /// bulk import straight into the database.
+    Reindex,
+
+    /// Merge accounts signed in twice and conversations pulled twice
+    ///
+    /// Accounts match on provider, workspace and email, ignoring case;
+    /// conversations on provider, title and creation time.
+    Dedupe {
+        /// Only list what would be merged
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Sample conversations into a fine-tuning dataset
+    Sample {
+        /// Number of conversations to sample
+        #[arg(long)]
+        n: usize,
 
         /// Filter by provider
         #[arg(long)]
         provider: Option<String>,
+
+        /// Sampling strategy (random, stratified-by-month, longest)
+        #[arg(long, default_value = "random")]
+        strategy: String,
+
+        /// Seed for the random strategies; printed so a sample can be reproduced
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Output format (openai-jsonl)
+        #[arg(long, default_value = "openai-jsonl")]
+        format: String,
+
+        /// Output path
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// Keep tool messages instead of dropping them
+        #[arg(long)]
+        include_tools: bool,
+
+        /// Mask email addresses and secret-looking tokens
+        #[arg(long)]
+        redact: bool,
+    },
+
+    /// Rename a conversation
+    Rename {
+        /// Conversation ID
+        id: String,
+
+        /// New title
+        #[arg(required_unless_present = "auto")]
+        title: Option<String>,
+
+        /// Generate the title from the first user message
+        #[arg(long, conflicts_with = "title")]
+        auto: bool,
+
+        /// With --auto, build the title from the most distinctive phrases of the first exchange
+        #[arg(long, requires = "auto")]
+        smart: bool,
+    },
+
+    /// Open a conversation in its provider's web app
+    Open {
+        /// Conversation ID
+        id: String,
+
+        /// Print the URL instead of launching a browser
+        #[arg(long)]
+        print: bool,
     },
 
     /// Show statistics
-    Stats,
+    Stats {
+        /// Break down counts, dates and disk usage per provider
+        #[arg(long)]
+        by_provider: bool,
+    },
+
+    /// Show when each account was last pulled and whether it's still signed in
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that each account's credentials are saved and still accepted
+    Doctor,
+
+    /// Manage signed-in accounts
+    Accounts {
+        #[command(subcommand)]
+        action: AccountsAction,
+    },
+
+    /// Pull new or updated conversations from every account, once or on a schedule
+    Sync {
+        /// Keep running, pulling every --interval until Ctrl-C or SIGTERM
+        #[arg(long, conflicts_with = "once")]
+        watch: bool,
+
+        /// Pull once and exit (the default)
+        #[arg(long)]
+        once: bool,
+
+        /// Time between pulls in watch mode, e.g. 30m, 2h or 1h30m (jittered by up to 10%)
+        #[arg(long, default_value = "30m")]
+        interval: String,
+
+        #[command(flatten)]
+        args: SyncArgs,
+    },
+
+    /// Keep pulling new or updated conversations from every account on a schedule
+    ///
+    /// Logs each run, keeps going when an account fails, backs off when a
+    /// provider rate limits, and stops cleanly on Ctrl-C or SIGTERM.
+    Watch {
+        /// Time between pulls, e.g. 30m, 2h or 1h30m (jittered by up to 10%)
+        #[arg(long, default_value = "30m")]
+        interval: String,
+
+        /// Run a single pull and exit
+        #[arg(long)]
+        once: bool,
+
+        #[command(flatten)]
+        args: SyncArgs,
+    },
+
+    /// Serve a JSON API over the local store, on localhost by default
+    ///
+    /// Endpoints: GET /conversations, /conversations/{id}, /search?q=,
+    /// /attachments/{id} and /stats; POST /operations/pull starts a pull,
+    /// followed with GET /operations and /operations/{id}/events and stopped
+    /// with POST /operations/{id}/cancel; POST /ingest/fathom takes signed
+    /// webhooks, tracked at /ingest/jobs/{id}. Set `serve.token` in
+    /// config.toml to require a bearer token, `serve.webhook_secret` to
+    /// accept webhooks, and `serve.cors_origins` for a browser UI.
+    Serve {
+        /// Address to listen on [default: 127.0.0.1]
+        #[arg(long)]
+        host: Option<std::net::IpAddr>,
+
+        /// Port to listen on [default: 8484]
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Also serve the built-in web viewer at /
+        #[arg(long)]
+        ui: bool,
+    },
+
+    /// Model Context Protocol server for Claude Desktop and other MCP clients
+    Mcp {
+        #[command(subcommand)]
+        action: McpAction,
+    },
+
+    /// Show why a conversation is or isn't re-fetched by `pull --new-only`
+    SyncDebug {
+        /// Conversation ID
+        conversation_id: String,
+    },
 
     /// Compact embeddings for faster semantic search
     Compact,
+
+    /// Manage the search index
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+
+    /// Manage the remote embeddings API
+    Embeddings {
+        #[command(subcommand)]
+        action: EmbeddingsAction,
+    },
+
+    /// Manage downloaded attachments
+    Attachments {
+        #[command(subcommand)]
+        action: AttachmentsAction,
+    },
+
+    /// Manage config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+/// Actions on the config file
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Write a commented config.toml template
+    Init {
+        /// Replace an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the effective config: the file merged over the built-in defaults
+    Show,
+}
+
+/// MCP server actions
+#[derive(Subcommand)]
+enum McpAction {
+    /// Serve the tools search_conversations, get_conversation and list_recent
+    /// over stdio
+    Serve,
+}
+
+/// Actions on the search index
+#[derive(Subcommand)]
+enum IndexAction {
+    /// Build embeddings for conversations that don't have any yet
+    Embed {
+        #[command(flatten)]
+        embedder: EmbedderArgs,
+    },
+
+    /// Extract text from downloaded attachments (PDFs, text and source files)
+    /// so `quaid search --attachments` can find it
+    Attachments,
+}
+
+/// Actions on the remote embeddings API
+#[derive(Subcommand)]
+enum EmbeddingsAction {
+    /// Save the API key `--embedder openai` sends, typed at a prompt or piped
+    /// in; QUAID_EMBEDDINGS_API_KEY overrides it
+    Auth,
+}
+
+/// Actions on attachments
+#[derive(Subcommand)]
+enum AttachmentsAction {
+    /// List attachments with their size, type and download state
+    Ls {
+        /// Only attachments still to download
+        #[arg(long)]
+        pending: bool,
+
+        /// Only this provider's attachments
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only this MIME type, e.g. application/pdf, or a whole type like image
+        #[arg(long)]
+        mime: Option<String>,
+    },
+
+    /// Download pending attachments, each through the account that owns it
+    Download {
+        #[command(flatten)]
+        download: AttachmentDownloadArgs,
+    },
+
+    /// Download pending attachments again, including ones a pull gave up on
+    Retry {
+        #[command(flatten)]
+        download: AttachmentDownloadArgs,
+    },
+
+    /// Open a downloaded attachment with the system's default application
+    Open {
+        /// Attachment ID, as shown by `quaid attachments ls`
+        id: String,
+
+        /// Print the file's path instead of opening it
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Check downloaded files still exist; missing ones go back to pending
+    Verify,
+}
+
+/// Which attachments `attachments download` and `retry` fetch
+#[derive(Args)]
+struct AttachmentDownloadArgs {
+    /// Only this provider's attachments
+    #[arg(long)]
+    provider: Option<String>,
+
+    /// Only the account signed in with this email
+    #[arg(long)]
+    account: Option<String>,
+
+    /// Leave attachments larger than this pending, e.g. 50MB or 2GB
+    #[arg(long, value_parser = commands::parse_size)]
+    max_attachment_size: Option<u64>,
+
+    /// Attachments to download at once (capped per provider) [default: 4]
+    #[arg(long)]
+    concurrency: Option<usize>,
 }
 
 /// Actions available for each provider
 #[derive(Subcommand)]
 enum ProviderAction {
     /// Authenticate with this provider
-    Auth,
+    Auth {
+        /// Sync the personal account and every ChatGPT workspace without asking
+        #[arg(long)]
+        all: bool,
+
+        /// Sign in without a browser: Claude's Cookie header, or ChatGPT's
+        /// __Secure-next-auth.session-token cookie; `-` reads it from stdin.
+        /// Also read from QUAID_CLAUDE_COOKIE or QUAID_CHATGPT_COOKIE
+        #[arg(long, conflicts_with = "token")]
+        cookie: Option<String>,
+
+        /// Sign in to ChatGPT without a browser with an access token; `-`
+        /// reads it from stdin. Also read from QUAID_CHATGPT_TOKEN
+        #[arg(long)]
+        token: Option<String>,
+    },
 
     /// Pull conversations from this provider
     Pull {
-        /// Only pull new or updated conversations
+        #[command(flatten)]
+        args: PullArgs,
+    },
+
+    /// Delete the credentials saved for this provider
+    Logout {
+        /// Only sign out the account signed in with this email
+        #[arg(long)]
+        account: Option<String>,
+
+        /// Also delete the accounts and every conversation pulled for them
+        #[arg(long)]
+        purge: bool,
+    },
+}
+
+/// Actions on the accounts of every provider
+#[derive(Subcommand)]
+enum AccountsAction {
+    /// List accounts with their conversations and whether credentials are saved
+    Ls,
+}
+
+/// ChatGPT's provider actions, plus importing share links
+#[derive(Subcommand)]
+enum ChatGptAction {
+    #[command(flatten)]
+    Provider(ProviderAction),
+
+    /// Import a conversation from its public share link, no sign-in needed
+    ImportShare {
+        /// Share link (https://chatgpt.com/share/...) or share id
+        url: String,
+
+        #[command(flatten)]
+        preview: ImportPreviewArgs,
+
+        #[command(flatten)]
+        embedder: EmbedderArgs,
+    },
+}
+
+/// Claude's provider actions, plus importing Claude Code sessions
+#[derive(Subcommand)]
+enum ClaudeAction {
+    #[command(flatten)]
+    Provider(ProviderAction),
+
+    /// Import Claude Code sessions from their JSONL files
+    ImportCode {
+        /// Folder of session files [default: ~/.claude/projects]
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        /// Skip session files that haven't changed since the last import
         #[arg(long)]
         new_only: bool,
+
+        #[command(flatten)]
+        preview: ImportPreviewArgs,
+
+        #[command(flatten)]
+        embedder: EmbedderArgs,
+    },
+}
+
+/// Actions for Google AI Studio, whose prompts are imported from files
+#[derive(Subcommand)]
+enum AiStudioAction {
+    /// Import prompts saved to Drive, from a downloaded prompt file or folder
+    Import {
+        /// A prompt file, or a folder of them like a downloaded "Google AI Studio" folder
+        path: PathBuf,
+
+        #[command(flatten)]
+        preview: ImportPreviewArgs,
+
+        #[command(flatten)]
+        embedder: EmbedderArgs,
     },
 }
 
+/// `--dry-run` for commands importing from files or links
+#[derive(Args)]
+struct ImportPreviewArgs {
+    /// Only show what the import would change, writing nothing
+    #[arg(long)]
+    dry_run: bool,
+
+    /// With --dry-run, also list every conversation and its change
+    #[arg(long, short)]
+    verbose: bool,
+}
+
+/// Options shared by `quaid pull` and `quaid <provider> pull`
+#[derive(Args)]
+struct PullArgs {
+    /// Only pull new or updated conversations
+    #[arg(long)]
+    new_only: bool,
+
+    /// Conversations and attachments to fetch at once (capped per provider) [default: 4]
+    #[arg(long)]
+    concurrency: Option<usize>,
+
+    /// Only pull the account signed in with this email
+    #[arg(long)]
+    account: Option<String>,
+
+    /// Record why each skipped conversation was skipped in a pull report
+    #[arg(long)]
+    explain_skips: bool,
+
+    /// With --explain-skips, also print the first skipped conversations
+    #[arg(long, short)]
+    verbose: bool,
+
+    /// Keep memory use low, at the cost of throughput
+    ///
+    /// Fetches, embeds and writes one conversation at a time with a
+    /// single-threaded model, and compacts embeddings once at the end instead
+    /// of after every account. Indexing is several times slower; meant for
+    /// small VPSes and single-board computers.
+    #[arg(long)]
+    low_memory: bool,
+
+    /// Don't download attachments; they stay pending for a later pull
+    #[arg(long)]
+    skip_attachments: bool,
+
+    /// Leave attachments larger than this pending, e.g. 50MB or 2GB
+    #[arg(long, value_parser = commands::parse_size)]
+    max_attachment_size: Option<u64>,
+
+    /// Also pull Claude project knowledge documents, one conversation per project
+    #[arg(long)]
+    include_project_docs: bool,
+
+    /// Also keep Claude's extended thinking and DeepSeek's R1 reasoning alongside their answers
+    #[arg(long)]
+    include_thinking: bool,
+
+    /// Also download Fathom meeting recordings, on plans that allow it
+    #[arg(long)]
+    include_recordings: bool,
+
+    /// Only pull Fathom meetings created on or after this date
+    /// (2024-06-01, 2024-06, RFC3339, 7d, 2mo, yesterday, last monday...)
+    #[arg(long, value_parser = commands::parse_since)]
+    since: Option<DateTime<Utc>>,
+
+    /// Only pull Fathom meetings created on or before this date, inclusive
+    #[arg(long, value_parser = commands::parse_until)]
+    until: Option<DateTime<Utc>>,
+
+    /// Only pull Fathom meetings recorded by this team (repeatable)
+    #[arg(long = "team")]
+    teams: Vec<String>,
+
+    /// Keep the stored version of each conversation a pull changes, up to
+    /// this many per conversation [default: 10]
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "10")]
+    keep_history: Option<usize>,
+
+    #[command(flatten)]
+    embedder: EmbedderArgs,
+}
+
+impl PullArgs {
+    fn options(&self, config: &Config) -> commands::pull::PullOptions {
+        let mut provider_options = config.providers.clone();
+        if self.include_project_docs {
+            provider_options.claude.include_project_docs = Some(true);
+        }
+        if self.include_thinking {
+            provider_options.claude.include_thinking = Some(true);
+            provider_options.deepseek.include_thinking = Some(true);
+        }
+        if self.include_recordings {
+            provider_options.fathom.include_recordings = Some(true);
+        }
+        if !self.teams.is_empty() {
+            provider_options.fathom.teams = self.teams.clone();
+        }
+        commands::pull::PullOptions {
+            new_only: self.new_only,
+            concurrency: config.concurrency(self.concurrency),
+            explain_skips: self.explain_skips,
+            verbose: self.verbose,
+            log_progress: false,
+            low_memory: self.low_memory,
+            providers: config.pull.providers.clone(),
+            provider_options,
+            chunker: config.chunker(),
+            compression: config.compression(),
+            attachments: commands::pull::AttachmentOptions {
+                skip: self.skip_attachments,
+                max_size: self.max_attachment_size,
+            },
+            since: self.since,
+            until: self.until,
+            keep_history: self.keep_history,
+            progress: None,
+        }
+    }
+}
+
+/// Options shared by `quaid sync` and `quaid watch`
+#[derive(Args)]
+struct SyncArgs {
+    /// Look at every conversation, not only new or updated ones
+    #[arg(long)]
+    full: bool,
+
+    /// Conversations and attachments to fetch at once (capped per provider) [default: 4]
+    #[arg(long)]
+    concurrency: Option<usize>,
+
+    /// Only sync the account signed in with this email
+    #[arg(long)]
+    account: Option<String>,
+
+    /// Keep memory use low, at the cost of throughput
+    ///
+    /// Fetches, embeds and writes one conversation at a time with a
+    /// single-threaded model, and compacts embeddings once at the end instead
+    /// of after every account. Indexing is several times slower; meant for
+    /// small VPSes and single-board computers.
+    #[arg(long)]
+    low_memory: bool,
+
+    #[command(flatten)]
+    embedder: EmbedderArgs,
+}
+
+impl SyncArgs {
+    fn options(&self, config: &Config, log_progress: bool) -> commands::pull::PullOptions {
+        commands::pull::PullOptions {
+            new_only: !self.full,
+            concurrency: config.concurrency(self.concurrency),
+            log_progress,
+            low_memory: self.low_memory,
+            ..config.pull_options()
+        }
+    }
+}
+
+/// Which embedder builds the semantic index
+#[derive(Args)]
+struct EmbedderArgs {
+    /// Embedder to use (local, openai, ollama); defaults to the one that built the index
+    #[arg(long)]
+    embedder: Option<String>,
+
+    /// Model for the embedder, e.g. text-embedding-3-large or mxbai-embed-large
+    #[arg(long)]
+    embedding_model: Option<String>,
+
+    /// OpenAI-compatible API or Ollama server to call, e.g. http://localhost:11434
+    #[arg(long, value_name = "URL")]
+    embeddings_endpoint: Option<String>,
+}
+
+impl EmbedderArgs {
+    fn backend(
+        &self,
+        config: &Config,
+        data_dir: &std::path::Path,
+    ) -> anyhow::Result<EmbeddingBackend> {
+        let choice = config.embedder(
+            self.embedder.clone(),
+            self.embedding_model.clone(),
+            self.embeddings_endpoint.clone(),
+        );
+        commands::embedding_backend(&choice, data_dir)
+    }
+
+    /// Pipeline an import stores and indexes what it read with
+    fn pipeline(
+        &self,
+        config: &Config,
+        credentials: &Arc<dyn CredentialStore>,
+        data_dir: &std::path::Path,
+    ) -> anyhow::Result<PipelineConfig> {
+        Ok(PipelineConfig::new(data_dir)
+            .with_embedding_backend(self.backend(config, data_dir)?)
+            .with_credentials(credentials.clone())
+            .with_chunker(config.chunker())
+            .with_compression(config.compression()))
+    }
+}
+
+/// Encrypted credentials file in the data directory, for `--credential-store file`
+const CREDENTIALS_FILE: &str = "credentials.enc";
+
 fn get_data_dir(cli_path: Option<PathBuf>) -> PathBuf {
     cli_path.unwrap_or_else(|| {
         dirs::data_dir()
@@ -121,77 +977,744 @@ fn get_data_dir(cli_path: Option<PathBuf>) -> PathBuf {
     })
 }
 
+/// The credential store `--credential-store` names
+fn credential_store(kind: &str, data_dir: &Path) -> anyhow::Result<Arc<dyn CredentialStore>> {
+    match kind {
+        "keyring" => Ok(Arc::new(KeyringStore::new())),
+        "file" => {
+            let passphrase = match std::env::var("QUAID_PASSPHRASE") {
+                Ok(passphrase) => passphrase,
+                Err(_) if std::io::stdin().is_terminal() => {
+                    rpassword::prompt_password(format!("Passphrase for {}: ", CREDENTIALS_FILE))?
+                }
+                Err(_) => anyhow::bail!(
+                    "Set QUAID_PASSPHRASE to unlock {} without a terminal",
+                    CREDENTIALS_FILE
+                ),
+            };
+            let store = FileStore::open(data_dir.join(CREDENTIALS_FILE), &passphrase)?;
+            Ok(Arc::new(store))
+        }
+        other => anyhow::bail!(
+            "Unknown credential store: {}. Supported: keyring, file",
+            other
+        ),
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let data_dir = get_data_dir(cli.data_dir);
+    let data_dir = get_data_dir(cli.data_dir.clone());
 
     // Ensure data directory exists
     std::fs::create_dir_all(&data_dir)?;
+    let credentials = credential_store(&cli.credential_store, &data_dir)?;
+
+    let config_path = cli
+        .config
+        .clone()
+        .unwrap_or_else(|| data_dir.join(config::CONFIG_FILE));
+    let config = match &cli.command {
+        // Writing a fresh template shouldn't fail on a broken old one
+        Commands::Config {
+            action: ConfigAction::Init { .. },
+        } => Config::default(),
+        _ => Config::load(cli.config.as_deref(), &data_dir)?,
+    };
 
     let db_path = data_dir.join("quaid.db");
     let store = quaid_core::Store::open(&db_path)?;
 
     match cli.command {
         Commands::Chatgpt { action } => match action {
-            ProviderAction::Auth => {
-                commands::auth::run("chatgpt", &store).await?;
+            ChatGptAction::Provider(ProviderAction::Auth { all, cookie, token }) => {
+                commands::auth::run("chatgpt", all, cookie, token, &store, &credentials).await?;
+            }
+            ChatGptAction::Provider(ProviderAction::Logout { account, purge }) => {
+                commands::accounts::logout(
+                    "chatgpt",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ChatGptAction::Provider(ProviderAction::Pull { args }) => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("chatgpt"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
             }
-            ProviderAction::Pull { new_only } => {
-                commands::pull::run(Some("chatgpt"), new_only, &store, &data_dir).await?;
+            ChatGptAction::ImportShare {
+                url,
+                preview,
+                embedder,
+            } => {
+                let pipeline = embedder.pipeline(&config, &credentials, &data_dir)?;
+                commands::chatgpt_share::import(
+                    &url,
+                    preview.dry_run,
+                    preview.verbose,
+                    &pipeline,
+                    &store,
+                )
+                .await?;
             }
         },
         Commands::Claude { action } => match action {
-            ProviderAction::Auth => {
-                commands::auth::run("claude", &store).await?;
+            ClaudeAction::Provider(ProviderAction::Auth { all, cookie, token }) => {
+                commands::auth::run("claude", all, cookie, token, &store, &credentials).await?;
             }
-            ProviderAction::Pull { new_only } => {
-                commands::pull::run(Some("claude"), new_only, &store, &data_dir).await?;
+            ClaudeAction::Provider(ProviderAction::Logout { account, purge }) => {
+                commands::accounts::logout(
+                    "claude",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ClaudeAction::Provider(ProviderAction::Pull { args }) => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("claude"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+            ClaudeAction::ImportCode {
+                path,
+                new_only,
+                preview,
+                embedder,
+            } => {
+                let pipeline = embedder.pipeline(&config, &credentials, &data_dir)?;
+                let include_thinking = config.providers.claude.include_thinking.unwrap_or(false);
+                commands::claude_code::import(
+                    path.as_deref(),
+                    new_only,
+                    include_thinking,
+                    preview.dry_run,
+                    preview.verbose,
+                    &pipeline,
+                    &store,
+                )
+                .await?;
             }
         },
         Commands::Fathom { action } => match action {
-            ProviderAction::Auth => {
-                commands::auth::run("fathom", &store).await?;
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("fathom", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "fathom",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
             }
-            ProviderAction::Pull { new_only } => {
-                commands::pull::run(Some("fathom"), new_only, &store, &data_dir).await?;
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("fathom"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
             }
         },
         Commands::Granola { action } => match action {
-            ProviderAction::Auth => {
-                commands::auth::run("granola", &store).await?;
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("granola", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "granola",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("granola"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+        },
+        Commands::Copilot { action } => match action {
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("copilot", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "copilot",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("copilot"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+        },
+        Commands::Gemini { action } => match action {
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("gemini", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "gemini",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("gemini"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+        },
+        Commands::Grok { action } => match action {
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("grok", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "grok",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("grok"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+        },
+        Commands::Lechat { action } => match action {
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("lechat", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "lechat",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("lechat"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+        },
+        Commands::Deepseek { action } => match action {
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("deepseek", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "deepseek",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("deepseek"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+        },
+        Commands::Poe { action } => match action {
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("poe", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "poe",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
             }
-            ProviderAction::Pull { new_only } => {
-                commands::pull::run(Some("granola"), new_only, &store, &data_dir).await?;
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("poe"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
             }
         },
-        Commands::Pull { new_only } => {
-            commands::pull::run(None, new_only, &store, &data_dir).await?;
+        Commands::Otter { action } => match action {
+            ProviderAction::Auth { all, cookie, token } => {
+                commands::auth::run("otter", all, cookie, token, &store, &credentials).await?;
+            }
+            ProviderAction::Logout { account, purge } => {
+                commands::accounts::logout(
+                    "otter",
+                    account.as_deref(),
+                    purge,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )?;
+            }
+            ProviderAction::Pull { args } => {
+                let backend = args.embedder.backend(&config, &data_dir)?;
+                commands::pull::run(
+                    Some("otter"),
+                    args.account.as_deref(),
+                    args.options(&config),
+                    backend,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+        },
+        Commands::Aistudio { action } => match action {
+            AiStudioAction::Import {
+                path,
+                preview,
+                embedder,
+            } => {
+                let pipeline = embedder.pipeline(&config, &credentials, &data_dir)?;
+                commands::aistudio::import(
+                    &path,
+                    preview.dry_run,
+                    preview.verbose,
+                    &pipeline,
+                    &store,
+                )
+                .await?;
+            }
+        },
+        Commands::Pull { args } => {
+            let backend = args.embedder.backend(&config, &data_dir)?;
+            commands::pull::run(
+                None,
+                args.account.as_deref(),
+                args.options(&config),
+                backend,
+                &store,
+                &credentials,
+                &data_dir,
+            )
+            .await?;
         }
-        Commands::List { provider, archived } => {
-            commands::list::run(provider.as_deref(), archived, &store)?;
+        Commands::List {
+            provider,
+            account,
+            project,
+            archived,
+            deleted_remotely,
+            verbose,
+            limit,
+            offset,
+        } => {
+            commands::list::run(
+                provider.as_deref(),
+                account.as_deref(),
+                project.as_deref(),
+                archived,
+                deleted_remotely,
+                verbose,
+                limit,
+                offset,
+                &store,
+            )?;
+        }
+        Commands::Show { id, format } => {
+            commands::show::run(&id, &format, &store)?;
+        }
+        Commands::History { id, diff } => {
+            commands::history::run(&id, &diff, &store)?;
+        }
+        Commands::Projects { provider } => {
+            commands::list::projects(provider.as_deref(), &store)?;
         }
         Commands::Search {
             query,
             limit,
             semantic,
             hybrid,
+            rerank,
+            regex,
+            provider,
+            account,
+            since,
+            until,
+            role,
+            exclude_archived,
+            attachments,
+            json,
         } => {
-            commands::search::run(&query, limit, semantic, hybrid, &store, &data_dir)?;
+            let filters = commands::search::FilterArgs {
+                provider: provider.as_deref(),
+                account: account.as_deref(),
+                since: since.as_deref(),
+                until: until.as_deref(),
+                roles: &role,
+                exclude_archived,
+                attachments,
+            };
+            tokio::task::block_in_place(|| {
+                commands::search::run(
+                    &query,
+                    limit,
+                    semantic,
+                    hybrid,
+                    rerank,
+                    regex,
+                    json,
+                    filters,
+                    &store,
+                    config.embeddings.endpoint.as_deref(),
+                    &credentials,
+                    &data_dir,
+                )
+            })?;
+        }
+        Commands::Grep {
+            pattern,
+            regex,
+            ignore_case,
+            provider,
+            context,
+            limit,
+            count_only,
+            engine,
+        } => {
+            let args = commands::grep::GrepArgs {
+                pattern: &pattern,
+                regex,
+                ignore_case,
+                provider: provider.as_deref(),
+                context,
+                limit,
+                count_only,
+                engine: &engine,
+            };
+            commands::grep::run(args, &store, &data_dir)?;
         }
         Commands::Export {
             path,
             format,
+            split,
+            incremental,
+            provider,
+            project,
+            since,
+            until,
+            openai_messages,
+            include_system,
+        } => {
+            let format = config.export_format(format);
+            commands::export::run(
+                &path,
+                &format,
+                split,
+                incremental,
+                provider.as_deref(),
+                project.as_deref(),
+                since.as_deref(),
+                until.as_deref(),
+                openai_messages,
+                include_system,
+                &store,
+                &data_dir,
+            )?;
+        }
+        Commands::Fsck {
+            sample,
+            repair,
+            vacuum,
+        } => {
+            commands::fsck::run(&store, sample, repair, vacuum)?;
+        }
+        Commands::Verify { fix } => {
+            commands::verify::run(&store, &data_dir, fix, config.compression())?;
+        }
+        Commands::Reindex => {
+            commands::reindex::run(&store)?;
+        }
+        Commands::Dedupe { dry_run } => {
+            commands::dedupe::run(&store, &data_dir, dry_run, config.compression())?;
+        }
+        Commands::Sample {
+            n,
             provider,
+            strategy,
+            seed,
+            format,
+            output,
+            include_tools,
+            redact,
+        } => {
+            let strategy = SampleStrategy::parse(&strategy).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown strategy: {}. Supported: random, stratified-by-month, longest",
+                    strategy
+                )
+            })?;
+            let options = SampleOptions {
+                n,
+                provider,
+                strategy,
+                seed: seed.unwrap_or_else(|| {
+                    use std::hash::{BuildHasher, Hasher};
+                    std::collections::hash_map::RandomState::new()
+                        .build_hasher()
+                        .finish()
+                }),
+                include_tools,
+                redact,
+            };
+            commands::sample::run(&store, &options, &format, &output)?;
+        }
+        Commands::Rename {
+            id,
+            title,
+            auto,
+            smart,
         } => {
-            commands::export::run(&path, &format, provider.as_deref(), &store)?;
+            commands::rename::run(&id, title.as_deref(), auto, smart, &store, &data_dir)?;
+        }
+        Commands::Open { id, print } => {
+            commands::open::run(&id, print, &store)?;
         }
-        Commands::Stats => {
-            commands::stats::run(&store)?;
+        Commands::Stats { by_provider } => {
+            commands::stats::run(by_provider, &store, &data_dir)?;
+        }
+        Commands::Status { json } => {
+            commands::status::run(json, &store, &credentials).await?;
+        }
+        Commands::Accounts { action } => match action {
+            AccountsAction::Ls => commands::accounts::list(&store, &credentials).await?,
+        },
+        Commands::Doctor => {
+            commands::doctor::run(&store, &credentials).await?;
+        }
+        Commands::Sync {
+            watch,
+            once: _,
+            interval,
+            args,
+        } => {
+            let interval = commands::watch::parse_interval(&interval)?;
+            let backend = args.embedder.backend(&config, &data_dir)?;
+            commands::sync::run(
+                args.account.as_deref(),
+                args.options(&config, watch),
+                watch,
+                interval,
+                backend,
+                &store,
+                &credentials,
+                &data_dir,
+            )
+            .await?;
+        }
+        Commands::Watch {
+            interval,
+            once,
+            args,
+        } => {
+            let interval = commands::watch::parse_interval(&interval)?;
+            let backend = args.embedder.backend(&config, &data_dir)?;
+            commands::watch::run(
+                args.account.as_deref(),
+                args.options(&config, true),
+                interval,
+                once,
+                backend,
+                &store,
+                &credentials,
+                &data_dir,
+            )
+            .await?;
+        }
+        Commands::Serve { host, port, ui } => {
+            let options = commands::serve::ServeOptions {
+                ui,
+                ..config.serve(host, port)
+            };
+            commands::serve::run(options, store, credentials, &data_dir).await?;
+        }
+        Commands::Mcp { action } => match action {
+            McpAction::Serve => {
+                let endpoint = config.embeddings.endpoint.clone();
+                tokio::task::block_in_place(|| {
+                    commands::mcp::run(store, endpoint, credentials, &data_dir)
+                })?;
+            }
+        },
+        Commands::SyncDebug { conversation_id } => {
+            commands::sync_debug::run(&conversation_id, &store, &data_dir)?;
         }
         Commands::Compact => {
-            commands::compact::run(&data_dir)?;
+            commands::compact::run(&store, &data_dir)?;
         }
+        Commands::Index { action } => match action {
+            IndexAction::Embed { embedder } => {
+                let backend = embedder.backend(&config, &data_dir)?;
+                let chunker = config.chunker();
+                tokio::task::block_in_place(|| {
+                    commands::index::embed(&backend, chunker, &store, &credentials, &data_dir)
+                })?;
+            }
+            IndexAction::Attachments => {
+                commands::index::attachments(&store)?;
+            }
+        },
+        Commands::Embeddings { action } => match action {
+            EmbeddingsAction::Auth => commands::embeddings::auth(&credentials)?,
+        },
+        Commands::Attachments { action } => match action {
+            AttachmentsAction::Ls {
+                pending,
+                provider,
+                mime,
+            } => {
+                let filter = AttachmentFilter {
+                    pending,
+                    provider,
+                    mime,
+                };
+                commands::attachments::ls(&filter, &store)?;
+            }
+            AttachmentsAction::Download { download } => {
+                commands::attachments::download(
+                    download.provider.as_deref(),
+                    download.account.as_deref(),
+                    download.max_attachment_size,
+                    config.concurrency(download.concurrency),
+                    &config.providers,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+            AttachmentsAction::Retry { download } => {
+                commands::attachments::retry(
+                    download.provider.as_deref(),
+                    download.account.as_deref(),
+                    download.max_attachment_size,
+                    config.concurrency(download.concurrency),
+                    &config.providers,
+                    &store,
+                    &credentials,
+                    &data_dir,
+                )
+                .await?;
+            }
+            AttachmentsAction::Open { id, print } => {
+                commands::attachments::open(&id, print, &store)?;
+            }
+            AttachmentsAction::Verify => commands::attachments::verify(&store)?,
+        },
+        Commands::Config { action } => match action {
+            ConfigAction::Init { force } => commands::config::init(&config_path, force)?,
+            ConfigAction::Show => commands::config::show(&config, &config_path)?,
+        },
     }
 
     Ok(())
@@ -0,0 +1,190 @@
+//! Drives `quaid mcp serve` through piped stdin/stdout, as an MCP client would
+
+use chrono::{TimeZone, Utc};
+use quaid_core::providers::{Account, Conversation, Message, MessageContent, ProviderId, Role};
+use quaid_core::Store;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+fn seed(data_dir: &Path) {
+    let store = Store::open(&data_dir.join("quaid.db")).unwrap();
+    store
+        .save_account(&Account {
+            id: "user-1".to_string(),
+            provider: ProviderId::claude(),
+            email: "a@example.com".to_string(),
+            name: None,
+            avatar_url: None,
+            workspace_id: None,
+        })
+        .unwrap();
+    for (day, title, text) in [
+        (
+            1,
+            "Ingress setup",
+            "How do I configure a kubernetes ingress?",
+        ),
+        (2, "Sourdough", "How long should the dough rest?"),
+    ] {
+        let id = format!("conv-{}", day);
+        let conversation = Conversation {
+            id: id.clone(),
+            provider_id: "claude".to_string(),
+            title: title.to_string(),
+            created_at: Utc.with_ymd_and_hms(2024, 1, day, 9, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 1, day, 9, 0, 0).unwrap(),
+            model: None,
+            project_id: None,
+            project_name: None,
+            is_archived: false,
+        };
+        let message = Message {
+            id: format!("msg-{}", day),
+            conversation_id: id,
+            parent_id: None,
+            role: Role::User,
+            content: MessageContent::Text {
+                text: text.to_string(),
+            },
+            created_at: None,
+            model: None,
+            speaker: None,
+        };
+        store
+            .save_conversation_with_messages("user-1", &conversation, &[message])
+            .unwrap();
+    }
+}
+
+/// Send `requests` one per line, close stdin and collect the responses
+fn exchange(data_dir: &Path, requests: &[Value]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_quaid"))
+        .args(["--data-dir", data_dir.to_str().unwrap(), "mcp", "serve"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        for request in requests {
+            writeln!(stdin, "{}", request).unwrap();
+        }
+    }
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn tool_call(id: i64, name: &str, arguments: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": { "name": name, "arguments": arguments },
+    })
+}
+
+fn text(response: &Value) -> &str {
+    response["result"]["content"][0]["text"].as_str().unwrap()
+}
+
+#[test]
+fn test_handshake_and_tools() {
+    let dir = tempfile::tempdir().unwrap();
+    seed(dir.path());
+
+    let responses = exchange(
+        dir.path(),
+        &[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0"},
+            }}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            tool_call(3, "search_conversations", json!({"query": "ingress"})),
+            tool_call(4, "list_recent", json!({"limit": 1})),
+            tool_call(5, "get_conversation", json!({"id": "conv-2"})),
+        ],
+    );
+
+    // The notification gets no response
+    assert_eq!(responses.len(), 5);
+    let ids: Vec<_> = responses
+        .iter()
+        .map(|r| r["id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+    assert_eq!(responses[0]["result"]["serverInfo"]["name"], "quaid");
+
+    let tools: Vec<_> = responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        tools,
+        vec!["search_conversations", "get_conversation", "list_recent"]
+    );
+
+    let found = text(&responses[2]);
+    assert!(found.contains("Ingress setup"));
+    assert!(found.contains("id: conv-1"));
+    assert!(!found.contains("Sourdough"));
+
+    let recent = text(&responses[3]);
+    assert!(recent.contains("Sourdough"));
+    assert!(!recent.contains("Ingress setup"));
+
+    let conversation = text(&responses[4]);
+    assert!(conversation.starts_with("# Sourdough"));
+    assert!(conversation.contains("## You\n\nHow long should the dough rest?"));
+}
+
+#[test]
+fn test_malformed_input_gets_errors_and_the_server_keeps_going() {
+    let dir = tempfile::tempdir().unwrap();
+    seed(dir.path());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_quaid"))
+        .args(["--data-dir", dir.path().to_str().unwrap(), "mcp", "serve"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        writeln!(stdin, "not json").unwrap();
+        writeln!(stdin, "{}", tool_call(2, "delete_everything", json!({}))).unwrap();
+        writeln!(
+            stdin,
+            "{}",
+            json!({"jsonrpc": "2.0", "id": 3, "method": "ping"})
+        )
+        .unwrap();
+    }
+    let output = child.wait_with_output().unwrap();
+    let responses: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0]["id"], Value::Null);
+    assert_eq!(responses[0]["error"]["code"], -32700);
+    assert_eq!(responses[1]["error"]["code"], -32602);
+    assert_eq!(responses[2]["result"], json!({}));
+}